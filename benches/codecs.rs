@@ -142,6 +142,36 @@ zenbench::main!(|suite| {
         });
     });
 
+    // ── Farbfeld endian swap: scalar vs SIMD ─────────────────────────
+    // Without the `simd` feature both entries run the scalar loop.
+    suite.compare("farbfeld_swap_1mpx", |g| {
+        g.throughput(Throughput::Bytes(W as u64 * H as u64 * 8));
+
+        let ff = zenbitmaps::encode_farbfeld(
+            &make_rgba16(),
+            W,
+            H,
+            zenbitmaps::PixelLayout::Rgba16,
+            Unstoppable,
+        )
+        .unwrap();
+        #[cfg(target_arch = "x86_64")]
+        {
+            let ff = ff.clone();
+            g.bench("scalar", move |b| {
+                b.iter(|| {
+                    let _ = archmage::X64V3Token::dangerously_disable_token_process_wide(true);
+                    let out = black_box(zenbitmaps::decode_farbfeld(&ff, Unstoppable).unwrap());
+                    let _ = archmage::X64V3Token::dangerously_disable_token_process_wide(false);
+                    out
+                })
+            });
+        }
+        g.bench("dispatched", move |b| {
+            b.iter(|| black_box(zenbitmaps::decode_farbfeld(&ff, Unstoppable).unwrap()))
+        });
+    });
+
     // ── Encode comparison ────────────────────────────────────────────
     suite.compare("encode_1mpx", |g| {
         g.throughput(Throughput::Bytes(W as u64 * H as u64 * 3));
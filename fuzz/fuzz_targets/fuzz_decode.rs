@@ -4,8 +4,33 @@ use libfuzzer_sys::fuzz_target;
 fuzz_target!(|data: &[u8]| {
     // Try auto-detect decode (PNM, BMP, farbfeld) — must never panic
     let _ = zenbitmaps::decode(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_region(data, 1, 1, 2, 2, enough::Unstoppable);
 
     // Try each format explicitly — must never panic
     let _ = zenbitmaps::decode_bmp(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_dib(data, enough::Unstoppable);
+    let permissive = zenbitmaps::BmpDecodeOptions::new()
+        .with_permissiveness(zenbitmaps::BmpPermissiveness::Permissive);
+    let _ = zenbitmaps::decode_bmp_with_report(data, &permissive, enough::Unstoppable);
+    let preview = permissive.with_downscale(zenbitmaps::BmpDownscale::Quarter);
+    let _ = zenbitmaps::decode_bmp_with_report(data, &preview, enough::Unstoppable);
+    if let Ok(frames) = zenbitmaps::decode_bmp_array(data, enough::Unstoppable) {
+        frames.for_each(drop);
+    }
     let _ = zenbitmaps::decode_farbfeld(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_ico(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_pcx(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_xpm(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_dds(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_xwd(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_tiff(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_dpx_linear(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_pgx(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_npy(data, enough::Unstoppable);
+    let _ = zenbitmaps::decode_exr(data, enough::Unstoppable);
+    if let Ok(frames) = zenbitmaps::decode_y4m_frames(data, enough::Unstoppable) {
+        for frame in frames.flatten() {
+            let _ = frame.to_rgb(zenbitmaps::YuvMatrix::Bt709, enough::Unstoppable);
+        }
+    }
 });
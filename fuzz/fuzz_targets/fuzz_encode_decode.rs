@@ -0,0 +1,63 @@
+#![no_main]
+//! Structured encode-then-decode: arbitrary pixels, layout, target format,
+//! per-format options and limits go through `encode`, and whatever encodes
+//! must decode back to the same dimensions unless a limit stops it.
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use zenbitmaps::*;
+
+#[derive(Debug, Arbitrary)]
+struct Input<'a> {
+    width: u8,
+    height: u8,
+    layout: PixelLayout,
+    format: EncodeFormat,
+    options: EncodeOptions<'a>,
+    limits: Limits,
+    permissiveness: BmpPermissiveness,
+    pixels: &'a [u8],
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let (width, height) = (u32::from(input.width), u32::from(input.height));
+    // Encoders write empty images, but every format's reader rejects them.
+    if width == 0 || height == 0 {
+        return;
+    }
+    let len = width as usize * height as usize * input.layout.bytes_per_pixel();
+    let mut pixels: Vec<u8> = input.pixels.iter().copied().cycle().take(len).collect();
+    pixels.resize(len, 0);
+
+    let Ok(file) = encode(
+        &pixels,
+        width,
+        height,
+        input.layout,
+        input.format,
+        &input.options,
+        enough::Unstoppable,
+    ) else {
+        return;
+    };
+    if input.format != EncodeFormat::Bmp {
+        let size = estimate_encoded_size(width, height, input.layout, input.format)
+            .expect("layout the encoder took must have a size");
+        assert_eq!(file.len(), size, "{:?} {:?}", input.format, input.layout);
+    }
+
+    let decoded = DecodeRequest::new(&file)
+        .with_limits(&input.limits)
+        .with_permissiveness(input.permissiveness)
+        .decode();
+    match decoded {
+        Ok(decoded) => assert_eq!((decoded.width, decoded.height), (width, height)),
+        Err(e) => assert_eq!(
+            e.error().kind(),
+            ErrorKind::LimitExceeded,
+            "{:?} {:?} failed to decode: {e}",
+            input.format,
+            input.layout
+        ),
+    }
+});
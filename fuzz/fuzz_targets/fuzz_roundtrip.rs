@@ -49,7 +49,11 @@ fuzz_target!(|data: &[u8]| {
         };
 
         let Ok(reencoded) = reencoded else { return };
-        let Ok(decoded2) = decode_bmp(&reencoded, enough::Unstoppable) else {
+        // Our encoder writes real alpha into the 32-bit reserved byte, so the
+        // re-decode must trust it (an all-zero alpha source would otherwise
+        // come back opaque under the default `BmpAlphaPolicy::Auto`).
+        let trust = BmpDecodeOptions::new().with_alpha_policy(BmpAlphaPolicy::Trust);
+        let Ok(decoded2) = decode_bmp_with_options(&reencoded, &trust, enough::Unstoppable) else {
             panic!("re-encoded BMP data failed to decode");
         };
 
@@ -61,4 +65,35 @@ fuzz_target!(|data: &[u8]| {
         assert_eq!(decoded.width, decoded2.width);
         assert_eq!(decoded.height, decoded2.height);
     }
+
+    // Self-generated RLE8/RLE4: the input bytes as palette indices, so the
+    // RLE decode paths see every run/literal mix the encoder can produce.
+    if let Some((&w, indices)) = data.split_first() {
+        let width = u32::from(w % 32) + 1;
+        let height = (indices.len() as u32 / width).min(64);
+        let palette: Vec<[u8; 3]> = (0..=255u8).map(|i| [i, !i, i.rotate_left(3)]).collect();
+        let n = (width * height) as usize;
+        for depth in [8u16, 4] {
+            if n == 0 {
+                break;
+            }
+            let mask = ((1u16 << depth) - 1) as u8;
+            let idx: Vec<u8> = indices[..n].iter().map(|&i| i & mask).collect();
+            let palette = &palette[..1 << depth];
+            let bmp = if depth == 8 {
+                encode_bmp_rle8(&idx, palette, width, height, enough::Unstoppable)
+            } else {
+                encode_bmp_rle4(&idx, palette, width, height, enough::Unstoppable)
+            }
+            .expect("valid indices must encode");
+            let decoded =
+                decode_bmp(&bmp, enough::Unstoppable).expect("self-encoded RLE BMP must decode");
+            let expected: Vec<u8> = idx.iter().flat_map(|&i| palette[usize::from(i)]).collect();
+            assert_eq!(
+                decoded.pixels(),
+                &expected[..],
+                "RLE{depth} roundtrip pixel mismatch"
+            );
+        }
+    }
 });
@@ -0,0 +1,111 @@
+/* C interface to zenbitmaps, built with the `capi` feature:
+ *
+ *     cargo rustc --release --lib --features capi --crate-type cdylib
+ *
+ * Every function takes caller-owned buffers and returns ZENBITMAPS_OK or a
+ * negative ZENBITMAPS_ERROR_* code. When output does not fit, the size
+ * needed is reported and ZENBITMAPS_ERROR_BUFFER_TOO_SMALL returned, so a
+ * first call with a zero capacity sizes the buffer. Keep in sync with
+ * src/capi.rs. */
+
+#ifndef ZENBITMAPS_H
+#define ZENBITMAPS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define ZENBITMAPS_OK 0
+#define ZENBITMAPS_ERROR_MALFORMED (-1)
+#define ZENBITMAPS_ERROR_UNSUPPORTED (-2)
+#define ZENBITMAPS_ERROR_LIMIT_EXCEEDED (-3)
+#define ZENBITMAPS_ERROR_CANCELLED (-4)
+#define ZENBITMAPS_ERROR_IO (-5)
+#define ZENBITMAPS_ERROR_INVALID_ARGUMENT (-6)
+#define ZENBITMAPS_ERROR_BUFFER_TOO_SMALL (-7)
+
+#define ZENBITMAPS_LAYOUT_GRAY8 0u
+#define ZENBITMAPS_LAYOUT_GRAY16 1u
+#define ZENBITMAPS_LAYOUT_RGB8 2u
+#define ZENBITMAPS_LAYOUT_RGBA8 3u
+#define ZENBITMAPS_LAYOUT_BGR8 4u
+#define ZENBITMAPS_LAYOUT_BGRA8 5u
+#define ZENBITMAPS_LAYOUT_BGRX8 6u
+#define ZENBITMAPS_LAYOUT_RGBX8 7u
+#define ZENBITMAPS_LAYOUT_GRAYF32 8u
+#define ZENBITMAPS_LAYOUT_RGBF32 9u
+#define ZENBITMAPS_LAYOUT_RGBAF32 10u
+#define ZENBITMAPS_LAYOUT_RGBA16 11u
+#define ZENBITMAPS_LAYOUT_RGBA16BE 12u
+#define ZENBITMAPS_LAYOUT_RGB16 13u
+#define ZENBITMAPS_LAYOUT_GRAYA16 14u
+#define ZENBITMAPS_LAYOUT_RGB_PLANAR8 15u
+#define ZENBITMAPS_LAYOUT_RGBA_PLANAR8 16u
+#define ZENBITMAPS_LAYOUT_RGB_PLANAR_F32 17u
+
+#define ZENBITMAPS_FORMAT_PGM 0u
+#define ZENBITMAPS_FORMAT_PPM 1u
+#define ZENBITMAPS_FORMAT_PAM 2u
+#define ZENBITMAPS_FORMAT_PFM 3u
+#define ZENBITMAPS_FORMAT_FARBFELD 4u
+#define ZENBITMAPS_FORMAT_BMP 5u /* needs the `bmp` feature */
+
+typedef struct ZenbitmapsImageInfo {
+    uint32_t width;
+    uint32_t height;
+    uint32_t layout; /* ZENBITMAPS_LAYOUT_* */
+    size_t size;     /* bytes of packed pixels */
+} ZenbitmapsImageInfo;
+
+/* 0 leaves a limit unset; the built-in 120 MP / 1 GiB ceilings still apply. */
+typedef struct ZenbitmapsLimits {
+    uint64_t max_width;
+    uint64_t max_height;
+    uint64_t max_pixels;
+    uint64_t max_memory_bytes;
+    uint64_t max_file_size;
+} ZenbitmapsLimits;
+
+/* Decode any detected format into packed rows. `info` is filled on success
+ * and on ZENBITMAPS_ERROR_BUFFER_TOO_SMALL. */
+int32_t zenbitmaps_decode(const uint8_t *data, size_t data_len, uint8_t *out,
+                          size_t out_cap, ZenbitmapsImageInfo *info);
+int32_t zenbitmaps_decode_with_limits(const uint8_t *data, size_t data_len,
+                                      const ZenbitmapsLimits *limits,
+                                      uint8_t *out, size_t out_cap,
+                                      ZenbitmapsImageInfo *info);
+
+int32_t zenbitmaps_encoded_size(uint32_t width, uint32_t height,
+                                uint32_t layout, uint32_t format,
+                                size_t *size);
+
+/* Encode packed rows with the format's default options. `*out_len` gets the
+ * file size, or the size needed on ZENBITMAPS_ERROR_BUFFER_TOO_SMALL. */
+int32_t zenbitmaps_encode(const uint8_t *pixels, size_t pixels_len,
+                          uint32_t width, uint32_t height, uint32_t layout,
+                          uint32_t format, uint8_t *out, size_t out_cap,
+                          size_t *out_len);
+
+#define ZENBITMAPS_ENCODE_AS(name)                                             \
+    int32_t name(const uint8_t *pixels, size_t pixels_len, uint32_t width,     \
+                 uint32_t height, uint32_t layout, uint8_t *out,               \
+                 size_t out_cap, size_t *out_len)
+ZENBITMAPS_ENCODE_AS(zenbitmaps_encode_ppm);
+ZENBITMAPS_ENCODE_AS(zenbitmaps_encode_pgm);
+ZENBITMAPS_ENCODE_AS(zenbitmaps_encode_pam);
+ZENBITMAPS_ENCODE_AS(zenbitmaps_encode_pfm);
+ZENBITMAPS_ENCODE_AS(zenbitmaps_encode_farbfeld);
+ZENBITMAPS_ENCODE_AS(zenbitmaps_encode_bmp);
+#undef ZENBITMAPS_ENCODE_AS
+
+/* Static, NUL-terminated description of a return code. */
+const char *zenbitmaps_error_message(int32_t code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ZENBITMAPS_H */
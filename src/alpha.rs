@@ -0,0 +1,286 @@
+//! Alpha premultiplication and flattening (internal).
+//!
+//! Use top-level [`crate::premultiply_alpha`], [`crate::unpremultiply_alpha`]
+//! and [`crate::flatten_alpha`], or [`AlphaMode`] and the background options
+//! of the encoders.
+//!
+//! Integer samples are rounded to nearest. Unpremultiplying a pixel with
+//! zero alpha gives black, since its color is gone; colors brighter than
+//! their alpha (invalid premultiplied data) clamp to the maximum. Float
+//! samples are multiplied and divided as they are, without clamping.
+//!
+//! Flattening blends each sample with the background in the buffer's own
+//! encoding (sRGB for integers, linear for floats); the 8-bit background is
+//! scaled to the sample range (`× 257`, `/ 255`) without a transfer curve.
+
+use alloc::borrow::Cow;
+use enough::Stop;
+use whereat::at;
+
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+
+/// Whether color samples are stored as is or already multiplied by alpha.
+///
+/// Set on `FarbfeldEncodeOptions`, `BmpEncodeOptions` and
+/// `TiffEncodeOptions` to describe the input pixels. Farbfeld and BMP store
+/// straight alpha, so premultiplied input is converted before writing; TIFF
+/// records it as associated alpha instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum AlphaMode {
+    /// Color is independent of alpha (unassociated alpha).
+    #[default]
+    Straight,
+    /// Color is already multiplied by alpha (associated alpha).
+    Premultiplied,
+}
+
+#[derive(Clone, Copy)]
+enum Sample {
+    U8,
+    U16Ne,
+    U16Be,
+    F32,
+}
+
+impl Sample {
+    fn of(layout: PixelLayout) -> crate::Result<Self> {
+        Ok(match layout {
+            PixelLayout::Rgba8 | PixelLayout::Bgra8 | PixelLayout::RgbaPlanar8 => Self::U8,
+            PixelLayout::GrayA16 | PixelLayout::Rgba16 => Self::U16Ne,
+            PixelLayout::Rgba16Be => Self::U16Be,
+            PixelLayout::RgbaF32 => Self::F32,
+            other => {
+                return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                    "{other:?} has no alpha channel (supported: Rgba8, Bgra8, GrayA16, \
+                     Rgba16, Rgba16Be, RgbaF32, RgbaPlanar8)"
+                ))));
+            }
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16Ne | Self::U16Be => 2,
+            Self::F32 => 4,
+        }
+    }
+
+    fn get(self, b: &[u8]) -> u32 {
+        match self {
+            Self::U8 => u32::from(b[0]),
+            Self::U16Ne => u32::from(u16::from_ne_bytes([b[0], b[1]])),
+            Self::U16Be => u32::from(u16::from_be_bytes([b[0], b[1]])),
+            Self::F32 => u32::from_ne_bytes([b[0], b[1], b[2], b[3]]),
+        }
+    }
+
+    fn set(self, b: &mut [u8], v: u32) {
+        match self {
+            Self::U8 => b[0] = v as u8,
+            Self::U16Ne => b.copy_from_slice(&(v as u16).to_ne_bytes()),
+            Self::U16Be => b.copy_from_slice(&(v as u16).to_be_bytes()),
+            Self::F32 => b.copy_from_slice(&v.to_ne_bytes()),
+        }
+    }
+
+    fn max(self) -> u32 {
+        match self {
+            Self::U8 => 255,
+            Self::U16Ne | Self::U16Be => 65535,
+            Self::F32 => 1f32.to_bits(),
+        }
+    }
+
+    /// Color sample `c` (raw bits) multiplied or divided by alpha `a`.
+    fn apply(self, c: u32, a: u32, premultiply: bool) -> u32 {
+        if let Self::F32 = self {
+            let (c, a) = (f32::from_bits(c), f32::from_bits(a));
+            let v = match premultiply {
+                true => c * a,
+                false if a > 0.0 => c / a,
+                false => 0.0,
+            };
+            return v.to_bits();
+        }
+        let max = self.max();
+        match premultiply {
+            true => (c * a + max / 2) / max,
+            false if a == 0 => 0,
+            false => ((u64::from(c) * u64::from(max) + u64::from(a / 2)) / u64::from(a))
+                .min(u64::from(max)) as u32,
+        }
+    }
+
+    /// Color sample `c` over background sample `bg` with alpha `a`.
+    fn over(self, c: u32, bg: u32, a: u32) -> u32 {
+        if let Self::F32 = self {
+            let (c, bg, a) = (f32::from_bits(c), f32::from_bits(bg), f32::from_bits(a));
+            let a = if a.is_nan() { 0.0 } else { a.clamp(0.0, 1.0) };
+            return (c * a + bg * (1.0 - a)).to_bits();
+        }
+        let max = self.max();
+        let a = a.min(max);
+        ((u64::from(c) * u64::from(a) + u64::from(bg) * u64::from(max - a) + u64::from(max / 2))
+            / u64::from(max)) as u32
+    }
+
+    /// An 8-bit background sample in this sample type.
+    fn scale_u8(self, v: u8) -> u32 {
+        match self {
+            Self::U8 => u32::from(v),
+            Self::U16Ne | Self::U16Be => u32::from(v) * 257,
+            Self::F32 => (f32::from(v) / 255.0).to_bits(),
+        }
+    }
+}
+
+/// Run `f` on every pixel of `pixels`, with its samples as raw `u32`s in
+/// channel order (alpha last), and store what it leaves.
+fn each_pixel(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+    mut f: impl FnMut(&mut [u32]),
+) -> crate::Result<()> {
+    let sample = Sample::of(layout)?;
+    let (w, h) = (width as usize, height as usize);
+    let count = w
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let expected = count
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+    let size = sample.size();
+    let channels = layout.channels();
+    // Byte offset of channel `c` of pixel `i`.
+    let offset = |i: usize, c: usize| match layout.is_planar() {
+        true => (c * count + i) * size,
+        false => (i * channels + c) * size,
+    };
+    let mut px = [0u32; 4];
+    let px = &mut px[..channels];
+    for y in 0..h {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for i in y * w..(y + 1) * w {
+            for (c, v) in px.iter_mut().enumerate() {
+                let o = offset(i, c);
+                *v = sample.get(&pixels[o..o + size]);
+            }
+            f(px);
+            for (c, &v) in px.iter().enumerate() {
+                let o = offset(i, c);
+                sample.set(&mut pixels[o..o + size], v);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Multiply (`premultiply`) or divide the color samples of `pixels` by
+/// alpha, in place.
+pub(crate) fn apply(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    premultiply: bool,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    let sample = Sample::of(layout)?;
+    each_pixel(pixels, width, height, layout, stop, |px| {
+        let (color, alpha) = px.split_at_mut(px.len() - 1);
+        for c in color {
+            *c = sample.apply(*c, alpha[0], premultiply);
+        }
+    })
+}
+
+/// Blend straight-alpha `pixels` over an opaque sRGB `background`, in
+/// place, leaving alpha at its maximum.
+pub(crate) fn flatten(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    background: [u8; 3],
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    let sample = Sample::of(layout)?;
+    let [r, g, b] = background;
+    let bg = match layout {
+        PixelLayout::Bgra8 => [b, g, r],
+        // BT.709 luma, as `convert` uses
+        PixelLayout::GrayA16 => {
+            let y = (2126 * u32::from(r) + 7152 * u32::from(g) + 722 * u32::from(b) + 5000) / 10000;
+            [y as u8; 3]
+        }
+        _ => background,
+    }
+    .map(|v| sample.scale_u8(v));
+    let max = sample.max();
+    each_pixel(pixels, width, height, layout, stop, |px| {
+        let (color, alpha) = px.split_at_mut(px.len() - 1);
+        for (c, &bg) in color.iter_mut().zip(&bg) {
+            *c = sample.over(*c, bg, alpha[0]);
+        }
+        alpha[0] = max;
+    })
+}
+
+/// `pixels` with straight alpha: borrowed unless `mode` is
+/// [`AlphaMode::Premultiplied`] and `layout` has alpha.
+pub(crate) fn straight<'p>(
+    pixels: &'p [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    mode: AlphaMode,
+    stop: &dyn Stop,
+) -> crate::Result<Cow<'p, [u8]>> {
+    if mode == AlphaMode::Straight || !layout.has_alpha() {
+        return Ok(Cow::Borrowed(pixels));
+    }
+    let mut owned = pixels[..image_len(pixels, width, height, layout)].to_vec();
+    apply(&mut owned, width, height, layout, false, stop)?;
+    Ok(Cow::Owned(owned))
+}
+
+/// `pixels` flattened onto `background`: borrowed when there is no
+/// background or `layout` has no alpha.
+pub(crate) fn flattened<'p>(
+    pixels: &'p [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    background: Option<[u8; 3]>,
+    stop: &dyn Stop,
+) -> crate::Result<Cow<'p, [u8]>> {
+    let Some(background) = background.filter(|_| layout.has_alpha()) else {
+        return Ok(Cow::Borrowed(pixels));
+    };
+    let mut owned = pixels[..image_len(pixels, width, height, layout)].to_vec();
+    flatten(&mut owned, width, height, layout, background, stop)?;
+    Ok(Cow::Owned(owned))
+}
+
+/// Bytes of a `width × height` image in `pixels`, capped at its length.
+fn image_len(pixels: &[u8], width: u32, height: u32, layout: PixelLayout) -> usize {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(layout.bytes_per_pixel()))
+        .map_or(pixels.len(), |n| n.min(pixels.len()))
+}
@@ -0,0 +1,31 @@
+//! Many-input decoding behind [`crate::decode_batch`].
+
+use alloc::vec::Vec;
+use enough::Stop;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use whereat::at;
+
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::Limits;
+use crate::request::{self, Settings};
+
+/// Decode each of `inputs` with `limits`, in order, on the rayon pool with
+/// the `rayon` feature. Inputs not yet started when `stop` fires fail with
+/// [`BitmapError::Cancelled`] without being looked at.
+pub(crate) fn decode_batch<'a>(
+    inputs: &[&'a [u8]],
+    limits: &Limits,
+    stop: &dyn Stop,
+) -> Vec<crate::Result<DecodeOutput<'a>>> {
+    let decode_one = |data: &&'a [u8]| {
+        stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        let settings = Settings::default();
+        request::dispatch(data, Some(limits), &settings, &limits.deadline(stop))
+    };
+    #[cfg(feature = "rayon")]
+    return inputs.par_iter().map(decode_one).collect();
+    #[cfg(not(feature = "rayon"))]
+    return inputs.iter().map(decode_one).collect();
+}
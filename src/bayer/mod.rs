@@ -0,0 +1,129 @@
+//! Bayer color filter array (CFA) mosaics to RGB (internal).
+//!
+//! Use top-level [`crate::decode_bayer`].
+//!
+//! A mosaic has one sample per pixel, filtered red, green or blue by a
+//! repeating 2×2 tile. Demosaicing is bilinear: each missing channel is the
+//! mean of the samples of that color in the pixel's 3×3 neighborhood (two
+//! or four of them), clipped at the image edges. The result is smooth and
+//! exactly reproducible rather than sharp; it is meant as a reference
+//! input, not a camera-quality raw developer.
+
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Color order of the 2×2 filter tile, read left to right, top row first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BayerPattern {
+    /// Red, green / green, blue.
+    Rggb,
+    /// Blue, green / green, red.
+    Bggr,
+    /// Green, red / blue, green.
+    Grbg,
+    /// Green, blue / red, green.
+    Gbrg,
+}
+
+impl BayerPattern {
+    /// Channel (0 red, 1 green, 2 blue) filtered at `(x, y)`.
+    fn channel_at(self, x: usize, y: usize) -> usize {
+        let tile = match self {
+            Self::Rggb => [0, 1, 1, 2],
+            Self::Bggr => [2, 1, 1, 0],
+            Self::Grbg => [1, 0, 2, 1],
+            Self::Gbrg => [1, 2, 0, 1],
+        };
+        tile[(y & 1) * 2 + (x & 1)]
+    }
+}
+
+/// Demosaic the first `width × height` samples to [`PixelLayout::Rgb16`].
+///
+/// Depths up to 8 bits take one byte per sample; deeper ones a
+/// little-endian `u16` with the value in the low `bit_depth` bits.
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    pattern: BayerPattern,
+    bit_depth: u8,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    if !(1..=16).contains(&bit_depth) {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "Bayer bit depth {bit_depth} is not supported; use 1 to 16"
+        ))));
+    }
+    if width < 2 || height < 2 {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "Bayer mosaic {width}×{height} is smaller than one 2×2 filter tile"
+        ))));
+    }
+    limits::check_dimensions(width, height, limits)?;
+    let (w, h) = (width as usize, height as usize);
+    let too_large = || at!(BitmapError::DimensionsTooLarge { width, height });
+    let sample_bytes = if bit_depth > 8 { 2 } else { 1 };
+    let frame_size = w
+        .checked_mul(h)
+        .and_then(|n| n.checked_mul(sample_bytes))
+        .ok_or_else(too_large)?;
+    let frame = data
+        .get(..frame_size)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let out_bytes = (w * h).checked_mul(6).ok_or_else(too_large)?;
+    limits::check_output_size(out_bytes, limits)?;
+
+    let max = (1u32 << bit_depth) - 1;
+    let samples: Vec<u16> = frame
+        .chunks_exact(sample_bytes)
+        .map(|s| {
+            let v = if sample_bytes == 2 {
+                u32::from(u16::from_le_bytes([s[0], s[1]]))
+            } else {
+                u32::from(s[0])
+            };
+            (v & max) as u16
+        })
+        .collect();
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    // Mean of `count` samples summing to `sum`, rescaled to 0..=65535.
+    let scale = |sum: u32, count: u32| {
+        let den = u64::from(count) * u64::from(max);
+        ((u64::from(sum) * 65535 + den / 2) / den) as u16
+    };
+    for (y, out_row) in out.chunks_exact_mut(w * 6).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in out_row.chunks_exact_mut(6).enumerate() {
+            let own = pattern.channel_at(x, y);
+            let mut sum = [0u32; 3];
+            let mut count = [0u32; 3];
+            for ny in y.saturating_sub(1)..(y + 2).min(h) {
+                for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                    let c = pattern.channel_at(nx, ny);
+                    sum[c] += u32::from(samples[ny * w + nx]);
+                    count[c] += 1;
+                }
+            }
+            sum[own] = u32::from(samples[y * w + x]);
+            count[own] = 1;
+            for (c, d) in px.chunks_exact_mut(2).enumerate() {
+                d.copy_from_slice(&scale(sum[c], count[c]).to_ne_bytes());
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, PixelLayout::Rgb16))
+}
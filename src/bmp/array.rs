@@ -0,0 +1,221 @@
+//! OS/2 bitmap arrays (`BA` magic) (internal).
+//!
+//! Use top-level [`crate::probe_bmp_array`] and [`crate::decode_bmp_array`];
+//! the plain BMP entry points decode the largest bitmap in an array.
+//!
+//! An array is a chain of 14-byte `BITMAPARRAYFILEHEADER`s, each immediately
+//! followed by an embedded bitmap file header (`BM`, or `IC`/`CI`/`PT`/`CP`
+//! for icons and pointers). The embedded headers' pixel offsets are relative
+//! to the start of the whole file, so each bitmap is reassembled into a
+//! standalone `BM` buffer before it is handed to the BMP decoder.
+
+use super::{BmpDecodeOptions, parse_dib_info};
+use crate::alloc_util::AllocPref;
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::Limits;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+/// One image in an OS/2 bitmap array.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct BmpArrayEntry {
+    /// Display width the image targets (`cxDisplay`); 0 = device independent.
+    pub display_width: u16,
+
+    /// Display height the image targets (`cyDisplay`); 0 = device independent.
+    pub display_height: u16,
+
+    /// Width in pixels from the embedded info header (0 if unreadable).
+    pub width: u32,
+
+    /// Height in pixels from the embedded info header (0 if unreadable).
+    pub height: u32,
+
+    /// Bits per pixel from the embedded info header (0 if unreadable).
+    pub bit_count: u16,
+
+    /// Byte offset of the embedded file header within the array.
+    pub offset: u32,
+
+    /// The entry is a plain bitmap (`BM`). Icon and pointer entries (`IC`,
+    /// `CI`, `PT`, `CP`) are listed but not decoded.
+    pub is_bitmap: bool,
+}
+
+/// A decoded image from [`crate::decode_bmp_array`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct BmpArrayFrame<'a> {
+    /// The directory entry this image came from.
+    pub entry: BmpArrayEntry,
+
+    /// Decoded pixels.
+    pub image: DecodeOutput<'a>,
+}
+
+/// Iterator over the images of an OS/2 bitmap array, decoding lazily.
+///
+/// Returned by [`crate::decode_bmp_array`]. Yields one item per entry in file
+/// order; icon and pointer entries yield
+/// [`BitmapError::UnsupportedVariant`](crate::BitmapError::UnsupportedVariant)
+/// and can be skipped.
+pub struct BmpArrayFrames<'a, S> {
+    data: &'a [u8],
+    entries: alloc::vec::IntoIter<BmpArrayEntry>,
+    limits: Option<&'a Limits>,
+    stop: S,
+}
+
+impl<'a, S: Stop> Iterator for BmpArrayFrames<'a, S> {
+    type Item = crate::Result<BmpArrayFrame<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        let decoded = standalone(self.data, &entry, self.limits).and_then(|bmp| {
+            super::decode_to_vec(
+                &bmp,
+                self.limits,
+                &BmpDecodeOptions::new(),
+                AllocPref::CodecDefault,
+                &self.stop,
+            )
+        });
+        Some(decoded.map(|decoded| BmpArrayFrame {
+            entry,
+            image: decoded.into_output(),
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+pub(crate) fn frames<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&'a Limits>,
+    stop: S,
+) -> crate::Result<BmpArrayFrames<'a, S>> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    Ok(BmpArrayFrames {
+        data,
+        entries: probe(data)?.into_iter(),
+        limits,
+        stop,
+    })
+}
+
+const ARRAY_HEADER_LEN: usize = 14;
+
+/// Walk the array header chain.
+pub(crate) fn probe(data: &[u8]) -> crate::Result<Vec<BmpArrayEntry>> {
+    let mut entries = Vec::new();
+    let mut pos = 0usize;
+    loop {
+        // The array header and the magic of the file header after it. The
+        // offsets come from the file, so the end is checked for overflow
+        // (32-bit targets).
+        let header = pos
+            .checked_add(ARRAY_HEADER_LEN + 2)
+            .and_then(|end| data.get(pos..end))
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        let u16_at = |off: usize| u16::from_le_bytes([header[off], header[off + 1]]);
+        if &header[..2] != b"BA" {
+            return Err(at!(BitmapError::invalid_header(alloc::format!(
+                "OS/2 bitmap array header at offset {pos} lacks 'BA' magic"
+            ))));
+        }
+        let next = u32::from_le_bytes([header[6], header[7], header[8], header[9]]) as usize;
+        let offset = pos + ARRAY_HEADER_LEN;
+        let info = data
+            .get(offset..)
+            .and_then(|file| file.get(14..))
+            .and_then(|dib| parse_dib_info(dib, false).ok());
+        entries.push(BmpArrayEntry {
+            display_width: u16_at(10),
+            display_height: u16_at(12),
+            width: info.map_or(0, |i| i.width),
+            height: info.map_or(0, |i| i.height.unsigned_abs()),
+            bit_count: info.map_or(0, |i| i.bpp),
+            offset: offset as u32,
+            is_bitmap: &header[ARRAY_HEADER_LEN..] == b"BM",
+        });
+        if next == 0 {
+            return Ok(entries);
+        }
+        // Strictly increasing offsets bound the walk by the input length.
+        if next <= pos {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "OS/2 bitmap array link at offset {pos} points backwards to {next}"
+            ))));
+        }
+        pos = next;
+    }
+}
+
+/// Reassemble entry `entry` into a standalone `BM` file: its file and info
+/// headers and palette, followed by its pixel data, with `bfOffBits`
+/// rewritten to match. The copy is offered to `limits`' `on_alloc` hook.
+pub(crate) fn standalone(
+    data: &[u8],
+    entry: &BmpArrayEntry,
+    limits: Option<&Limits>,
+) -> crate::Result<Vec<u8>> {
+    if !entry.is_bitmap {
+        return Err(at!(BitmapError::UnsupportedVariant(
+            "OS/2 icon/pointer entries in bitmap arrays are not supported".into()
+        )));
+    }
+    // The entry's file header onwards; `offset` comes from the file.
+    let file = data
+        .get(entry.offset as usize..)
+        .filter(|file| file.len() >= 14)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let file_header = &file[..14];
+    let bits = u32::from_le_bytes([
+        file_header[10],
+        file_header[11],
+        file_header[12],
+        file_header[13],
+    ]) as usize;
+    let info = parse_dib_info(&file[14..], false)?;
+    // A truncated palette is zero-filled by the decoder, as for plain files.
+    let header_end = info.pixel_offset().saturating_add(14).min(file.len());
+    let headers = &file[..header_end];
+    let pixels = data
+        .get(bits..)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+
+    let len = headers.len() + pixels.len();
+    crate::limits::check_alloc(len, limits)?;
+    let mut out = Vec::with_capacity(len);
+    out.extend_from_slice(headers);
+    out.extend_from_slice(pixels);
+    let file_size = u32::try_from(out.len()).unwrap_or(u32::MAX);
+    out[2..6].copy_from_slice(&file_size.to_le_bytes());
+    out[10..14].copy_from_slice(&(headers.len() as u32).to_le_bytes());
+    Ok(out)
+}
+
+/// Standalone `BM` data for decoding: `data` itself, or for a bitmap array
+/// its largest bitmap (ties broken by bit depth), copied under `limits`.
+pub(crate) fn resolve<'a>(data: &'a [u8], limits: Option<&Limits>) -> crate::Result<Cow<'a, [u8]>> {
+    if !data.starts_with(b"BA") {
+        return Ok(Cow::Borrowed(data));
+    }
+    let entries = probe(data)?;
+    let best = entries
+        .iter()
+        .filter(|e| e.is_bitmap)
+        .max_by_key(|e| (u64::from(e.width) * u64::from(e.height), e.bit_count))
+        .ok_or_else(|| {
+            at!(BitmapError::UnsupportedVariant(
+                "OS/2 bitmap array contains no plain bitmaps".into()
+            ))
+        })?;
+    Ok(Cow::Owned(standalone(data, best, limits)?))
+}
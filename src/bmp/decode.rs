@@ -4,14 +4,19 @@
 //! Adapted: ZReader → &[u8] cursor, DecoderOptions → Option<&Limits>,
 //! BmpDecoderErrors → BitmapError, log removed, stop.check() added.
 
+use alloc::borrow::Cow;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::ops::Range;
 
 use enough::Stop;
 
-use super::utils::{expand_bits_to_byte, shift_signed};
+use super::BmpPaletteUsage;
+use super::utils::{expand_bits_to_byte, scale_mask_to_u16, shift_signed};
 use crate::alloc_util::{self, AllocPref};
-use crate::error::BitmapError;
+use crate::decode::{DecodeReport, DecodeWarning};
+use crate::error::{BitmapError, ResultExt};
+use crate::limits::Limits;
 use crate::pixel::PixelLayout;
 use whereat::at;
 
@@ -19,6 +24,7 @@ use whereat::at;
 
 /// Controls how strictly the BMP decoder validates input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum BmpPermissiveness {
     /// Reject files that violate the BMP spec even in non-critical ways.
     /// Validates: planes == 1, file size matches, palette count, DPI
@@ -38,15 +44,154 @@ pub enum BmpPermissiveness {
     Permissive,
 }
 
+/// How to interpret the fourth byte of 32-bit `BI_RGB` pixels.
+///
+/// The BMP spec calls this byte "reserved", but many writers store real
+/// alpha in it while others leave it zero. Trusting it blindly turns the
+/// latter into fully transparent images. Files with an explicit alpha
+/// bitfield mask are unaffected — their alpha is always honored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BmpAlphaPolicy {
+    /// Ignore the reserved byte; output alpha is always 255.
+    Opaque,
+
+    /// Use the reserved byte as alpha verbatim.
+    Trust,
+
+    /// Use the reserved byte as alpha, unless it is zero for every pixel —
+    /// then treat the image as opaque. Default.
+    #[default]
+    Auto,
+}
+
+/// Power-of-two downscale applied while decoding.
+///
+/// Nearest-neighbour: the output keeps every `factor`-th row and column,
+/// starting with the first, so a `w`×`h` image becomes
+/// `w.div_ceil(factor)`×`h.div_ceil(factor)`. Skipped rows of uncompressed
+/// data are never read and only the reduced image is allocated; RLE streams
+/// still have to be run in full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum BmpDownscale {
+    /// Full resolution. Default.
+    #[default]
+    None,
+    /// Every second row and column.
+    Half,
+    /// Every fourth row and column.
+    Quarter,
+    /// Every eighth row and column.
+    Eighth,
+}
+
+impl BmpDownscale {
+    /// Step between kept rows and columns (1, 2, 4 or 8).
+    pub fn factor(self) -> u32 {
+        match self {
+            Self::None => 1,
+            Self::Half => 2,
+            Self::Quarter => 4,
+            Self::Eighth => 8,
+        }
+    }
+}
+
+/// Options for BMP decoding.
+///
+/// ```
+/// use zenbitmaps::{BmpAlphaPolicy, BmpDecodeOptions, BmpPermissiveness};
+///
+/// let options = BmpDecodeOptions::new()
+///     .with_permissiveness(BmpPermissiveness::Permissive)
+///     .with_alpha_policy(BmpAlphaPolicy::Trust);
+/// assert_eq!(options.alpha_policy(), BmpAlphaPolicy::Trust);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BmpDecodeOptions {
+    permissiveness: BmpPermissiveness,
+    alpha_policy: BmpAlphaPolicy,
+    keep_padding: bool,
+    downscale: BmpDownscale,
+}
+
+impl BmpDecodeOptions {
+    /// Default options: [`BmpPermissiveness::Standard`], [`BmpAlphaPolicy::Auto`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the validation strictness.
+    pub fn with_permissiveness(mut self, permissiveness: BmpPermissiveness) -> Self {
+        self.permissiveness = permissiveness;
+        self
+    }
+
+    /// Set how the reserved byte of 32-bit `BI_RGB` pixels is interpreted.
+    pub fn with_alpha_policy(mut self, alpha_policy: BmpAlphaPolicy) -> Self {
+        self.alpha_policy = alpha_policy;
+        self
+    }
+
+    /// Report 32-bit pixels without alpha as [`PixelLayout::Rgbx8`] instead
+    /// of `Rgba8` with synthesized alpha, so the fourth byte can be treated
+    /// as padding. Applies when the file has no alpha mask and the
+    /// [`BmpAlphaPolicy`] resolves to opaque; the padding byte is 255.
+    /// Default `false`.
+    pub fn with_keep_padding(mut self, keep_padding: bool) -> Self {
+        self.keep_padding = keep_padding;
+        self
+    }
+
+    /// Decode a reduced preview instead of the full image (see
+    /// [`BmpDownscale`]). Dimension and size limits apply to the reduced
+    /// output. With [`BmpAlphaPolicy::Auto`], the "all zero" test looks at
+    /// the kept pixels only. Default [`BmpDownscale::None`].
+    pub fn with_downscale(mut self, downscale: BmpDownscale) -> Self {
+        self.downscale = downscale;
+        self
+    }
+
+    /// Validation strictness.
+    pub fn permissiveness(&self) -> BmpPermissiveness {
+        self.permissiveness
+    }
+
+    /// Interpretation of the reserved byte of 32-bit `BI_RGB` pixels.
+    pub fn alpha_policy(&self) -> BmpAlphaPolicy {
+        self.alpha_policy
+    }
+
+    /// Whether alpha-less 32-bit pixels are reported as `Rgbx8`/`Bgrx8`.
+    pub fn keep_padding(&self) -> bool {
+        self.keep_padding
+    }
+
+    /// Downscale applied while decoding.
+    pub fn downscale(&self) -> BmpDownscale {
+        self.downscale
+    }
+}
+
 // ── Compression enum ────────────────────────────────────────────────
 
+/// Declared BMP compression (`biCompression`), as reported by
+/// [`crate::probe_bmp`].
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
-enum BmpCompression {
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum BmpCompression {
+    /// `BI_RGB`: uncompressed.
     Rgb,
+    /// `BI_RLE8`.
     Rle8,
+    /// `BI_RLE4`.
     Rle4,
+    /// `BI_BITFIELDS` or `BI_ALPHABITFIELDS`: uncompressed with channel masks.
     Bitfields,
-    /// Unknown compression type (only used in Permissive mode).
+    /// Any other value (e.g. 4 = embedded JPEG, 5 = embedded PNG). Only
+    /// decoded in Permissive mode, as a zero-filled image.
     Unknown(u32),
 }
 
@@ -63,6 +208,43 @@ impl BmpCompression {
     }
 }
 
+/// DIB info header variant, identified by its size (`biSize`).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum BmpHeaderVariant {
+    /// 12-byte `BITMAPCOREHEADER` (OS/2 1.x, Windows 2.x).
+    Core,
+    /// 16- or 64-byte OS/2 2.x `OS22XBITMAPHEADER`.
+    Os2V2,
+    /// 40-byte `BITMAPINFOHEADER` (often called V3).
+    Info,
+    /// 52-byte `BITMAPV2INFOHEADER` (RGB masks).
+    V2,
+    /// 56-byte `BITMAPV3INFOHEADER` (RGBA masks).
+    V3,
+    /// 108-byte `BITMAPV4HEADER` (masks, color space).
+    V4,
+    /// 124-byte `BITMAPV5HEADER` (adds rendering intent and ICC profile).
+    V5,
+}
+
+impl BmpHeaderVariant {
+    fn from_size(size: u32) -> Option<Self> {
+        match size {
+            12 => Some(Self::Core),
+            16 | 64 => Some(Self::Os2V2),
+            40 => Some(Self::Info),
+            52 => Some(Self::V2),
+            56 => Some(Self::V3),
+            108 => Some(Self::V4),
+            124 => Some(Self::V5),
+            _ => None,
+        }
+    }
+}
+
 // ── Pixel format enum ───────────────────────────────────────────────
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -72,17 +254,36 @@ enum BmpPixelFormat {
     Pal8,
     Gray8,
     Rgb,
+    /// BITFIELDS with any channel mask wider than 8 bits (e.g. 2-10-10-10),
+    /// decoded to native-endian RGBA16 to keep the extra precision.
+    Rgba16,
 }
 
 impl BmpPixelFormat {
     fn num_components(self) -> usize {
         match self {
             Self::None => 0,
-            Self::Rgba => 4,
+            Self::Rgba | Self::Rgba16 => 4,
             Self::Pal8 | Self::Rgb => 3,
             Self::Gray8 => 1,
         }
     }
+
+    /// Output bytes per pixel (2 bytes per channel for `Rgba16`).
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba16 => 8,
+            other => other.num_components(),
+        }
+    }
+
+    /// Output bytes per channel sample.
+    fn bytes_per_sample(self) -> usize {
+        match self {
+            Self::Rgba16 => 2,
+            _ => 1,
+        }
+    }
 }
 
 // ── Palette entry ───────────────────────────────────────────────────
@@ -123,7 +324,7 @@ impl<'a> Cursor<'a> {
                 self.pos = self.data.len();
                 return Ok(());
             }
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof().at_offset(pos)));
         }
         self.pos = pos;
         Ok(())
@@ -133,13 +334,13 @@ impl<'a> Cursor<'a> {
         let new_pos = self
             .pos
             .checked_add(n)
-            .ok_or_else(|| at!(BitmapError::UnexpectedEof))?;
+            .ok_or_else(|| at!(BitmapError::unexpected_eof().at_offset(self.pos)))?;
         if new_pos > self.data.len() {
             if self.permissive {
                 self.pos = self.data.len();
                 return Ok(());
             }
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof().at_offset(self.pos)));
         }
         self.pos = new_pos;
         Ok(())
@@ -161,13 +362,13 @@ impl<'a> Cursor<'a> {
             self.pos += 1;
             Ok(b)
         } else {
-            Err(at!(BitmapError::UnexpectedEof))
+            Err(at!(BitmapError::unexpected_eof().at_offset(self.pos)))
         }
     }
 
     fn get_u16_le_err(&mut self) -> crate::Result<u16> {
         if self.pos + 2 > self.data.len() {
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof().at_offset(self.pos)));
         }
         let val = u16::from_le_bytes([self.data[self.pos], self.data[self.pos + 1]]);
         self.pos += 2;
@@ -185,7 +386,7 @@ impl<'a> Cursor<'a> {
 
     fn get_u32_le_err(&mut self) -> crate::Result<u32> {
         if self.pos + 4 > self.data.len() {
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof().at_offset(self.pos)));
         }
         let val = u32::from_le_bytes([
             self.data[self.pos],
@@ -210,7 +411,7 @@ impl<'a> Cursor<'a> {
                 self.pos = self.data.len();
                 return Ok(buf);
             }
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof().at_offset(self.pos)));
         }
         let mut buf = [0u8; N];
         buf.copy_from_slice(&self.data[self.pos..self.pos + N]);
@@ -232,7 +433,7 @@ impl<'a> Cursor<'a> {
                 self.pos = self.data.len();
                 return Ok(());
             }
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof().at_offset(self.pos)));
         }
         buf.copy_from_slice(&self.data[self.pos..self.pos + n]);
         self.pos += n;
@@ -247,13 +448,20 @@ pub(crate) struct BmpHeader {
     pub height: u32,
     pub layout: PixelLayout,
     /// Bits per pixel as declared in the BMP header.
-    #[cfg_attr(not(feature = "zencodec"), allow(dead_code))]
     pub bpp: u16,
     pub x_pels_per_meter: u32,
     pub y_pels_per_meter: u32,
     /// Color table entries (BGRA order, up to 256 entries).
     /// Only present for indexed-color BMPs (1/2/4/8-bit).
     pub color_table: Option<alloc::vec::Vec<[u8; 4]>>,
+    pub header_variant: BmpHeaderVariant,
+    pub compression: BmpCompression,
+    /// Effective R, G, B, A masks for direct-color (16/24/32-bit) files;
+    /// `None` for 64-bit, whose channels are fixed.
+    pub masks: Option<[u32; 4]>,
+    /// Whether the file holds every row of uncompressed pixel data (always
+    /// `true` for RLE).
+    pub rows_complete: bool,
 }
 
 // ── Public header parsing (for probe) ───────────────────────────────
@@ -265,15 +473,19 @@ pub(crate) struct BmpHeader {
 /// `max_pixels` is the effective pixel-count ceiling enforced on the declared
 /// dimensions (pass `u64::MAX` for metadata-only probing that must not reject
 /// on size; the decode path passes the caller's resolved [`crate::Limits`]).
-pub(crate) fn parse_bmp_header(data: &[u8], max_pixels: u64) -> crate::Result<BmpHeader> {
+/// `limits` sees the allocations made on the way (`None` when probing).
+pub(crate) fn parse_bmp_header(
+    data: &[u8],
+    limits: Option<&Limits>,
+    max_pixels: u64,
+) -> crate::Result<BmpHeader> {
+    let data = &*super::array::resolve(data, limits)?;
     // Header probing uses Permissive to avoid rejecting files before
-    // the caller has chosen a permissiveness level. No output buffer is
-    // allocated here, so the alloc preference is irrelevant — pass the default.
+    // the caller has chosen a permissiveness level.
     let mut dec = BmpDecoderState::new(
         data,
-        BmpPermissiveness::Permissive,
+        &BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Permissive),
         max_pixels,
-        AllocPref::CodecDefault,
     );
     dec.decode_headers()?;
 
@@ -281,6 +493,7 @@ pub(crate) fn parse_bmp_header(data: &[u8], max_pixels: u64) -> crate::Result<Bm
         BmpPixelFormat::Rgba => PixelLayout::Rgba8,
         BmpPixelFormat::Rgb | BmpPixelFormat::Pal8 => PixelLayout::Rgb8,
         BmpPixelFormat::Gray8 => PixelLayout::Gray8,
+        BmpPixelFormat::Rgba16 => PixelLayout::Rgba16,
         BmpPixelFormat::None => {
             return Err(at!(BitmapError::UnsupportedVariant(
                 "unsupported BMP pixel format".into(),
@@ -290,6 +503,7 @@ pub(crate) fn parse_bmp_header(data: &[u8], max_pixels: u64) -> crate::Result<Bm
 
     // Extract color table for indexed formats
     let color_table = if dec.pix_fmt == BmpPixelFormat::Pal8 && dec.palette_numbers > 0 {
+        crate::limits::check_alloc(dec.palette_numbers * 4, limits)?;
         let mut table = alloc::vec::Vec::with_capacity(dec.palette_numbers);
         for i in 0..dec.palette_numbers {
             let e = &dec.palette[i];
@@ -308,83 +522,287 @@ pub(crate) fn parse_bmp_header(data: &[u8], max_pixels: u64) -> crate::Result<Bm
         x_pels_per_meter: dec.x_pels_per_meter,
         y_pels_per_meter: dec.y_pels_per_meter,
         color_table,
+        // `decode_headers` rejects every other size.
+        header_variant: BmpHeaderVariant::from_size(dec.ihsize).unwrap_or(BmpHeaderVariant::Info),
+        compression: dec.comp,
+        masks: dec.effective_masks(),
+        rows_complete: dec.report.valid_rows == Some(0..dec.height as u32),
     })
 }
 
 // ── Full decode ─────────────────────────────────────────────────────
 
+/// Channel order and sample type of a full decode.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OutputKind {
+    /// RGB/RGBA order, 8- or 16-bit samples.
+    Rgb,
+    /// Native BGR/BGRA order.
+    Native,
+    /// Linear-light f32 (sRGB transfer removed, alpha straight).
+    LinearF32,
+}
+
 /// Decode BMP pixel data (RGB/RGBA output).
 ///
-/// `max_pixels` is the effective pixel-count ceiling (already resolved against
-/// the caller's [`crate::Limits`], defaulting to
-/// [`crate::limits::DEFAULT_MAX_PIXELS`]); pass `u64::MAX` to opt out.
+/// `data` has been through `array::resolve`: a bitmap array's entry arrives
+/// as an owned standalone copy, which counts against the memory limit.
+/// `limits` bounds the declared pixel count and every buffer the decode
+/// allocates (output plus scratch); `None` applies the crate defaults.
 pub(crate) fn decode_bmp_pixels(
-    data: &[u8],
-    permissiveness: BmpPermissiveness,
-    max_pixels: u64,
+    data: Cow<'_, [u8]>,
+    options: &BmpDecodeOptions,
+    limits: Option<&Limits>,
     alloc_pref: AllocPref,
     stop: &dyn Stop,
-) -> crate::Result<(Vec<u8>, PixelLayout)> {
-    let mut dec = BmpDecoderState::new(data, permissiveness, max_pixels, alloc_pref);
-    dec.decode_headers()?;
-
-    // Output buffer sized from the (untrusted) header dimensions → default
-    // fallible.
-    let output_size = dec.output_buf_size()?;
-    let mut buf = alloc_util::alloc_zeroed(alloc_pref, true, output_size)?;
-
-    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
-    dec.decode_into::<false>(&mut buf, stop)?;
+) -> crate::Result<(Vec<u8>, PixelLayout, DecodeReport)> {
+    decode_pixels(
+        data,
+        options,
+        limits,
+        alloc_pref,
+        stop,
+        OutputKind::Rgb,
+        None,
+        None,
+    )
+}
 
-    let layout = match dec.pix_fmt {
-        BmpPixelFormat::Rgba => PixelLayout::Rgba8,
-        BmpPixelFormat::Rgb | BmpPixelFormat::Pal8 => PixelLayout::Rgb8,
-        BmpPixelFormat::Gray8 => PixelLayout::Gray8,
-        BmpPixelFormat::None => {
-            return Err(at!(BitmapError::UnsupportedVariant(
-                "unsupported BMP pixel format".into(),
-            )));
-        }
-    };
+/// [`decode_bmp_pixels`], also counting the pixels that reference each
+/// palette index. `usage` is left `None` for files without a palette.
+pub(crate) fn decode_bmp_pixels_counting(
+    data: Cow<'_, [u8]>,
+    options: &BmpDecodeOptions,
+    limits: Option<&Limits>,
+    usage: &mut Option<BmpPaletteUsage>,
+    stop: &dyn Stop,
+) -> crate::Result<(Vec<u8>, PixelLayout, DecodeReport)> {
+    decode_pixels(
+        data,
+        options,
+        limits,
+        AllocPref::CodecDefault,
+        stop,
+        OutputKind::Rgb,
+        None,
+        Some(usage),
+    )
+}
 
-    Ok((buf, layout))
+/// Decode only output rows `rows` (top-down) of a BMP, RGB/RGBA order.
+///
+/// `limits` applies to the window rather than the declared image: the
+/// caller checks the window's dimensions, and the buffers allocated here
+/// count against the memory limit as usual.
+pub(crate) fn decode_bmp_pixels_rows(
+    data: Cow<'_, [u8]>,
+    options: &BmpDecodeOptions,
+    limits: Option<&Limits>,
+    rows: Range<usize>,
+    stop: &dyn Stop,
+) -> crate::Result<(Vec<u8>, PixelLayout, DecodeReport)> {
+    decode_pixels(
+        data,
+        options,
+        limits,
+        AllocPref::CodecDefault,
+        stop,
+        OutputKind::Rgb,
+        Some(rows),
+        None,
+    )
 }
 
 /// Decode BMP pixel data in native byte order (BGR/BGRA).
 ///
-/// `max_pixels` is the effective pixel-count ceiling (already resolved against
-/// the caller's [`crate::Limits`], defaulting to
-/// [`crate::limits::DEFAULT_MAX_PIXELS`]); pass `u64::MAX` to opt out.
+/// `limits` is applied as in [`decode_bmp_pixels`].
 pub(crate) fn decode_bmp_pixels_native(
-    data: &[u8],
-    permissiveness: BmpPermissiveness,
-    max_pixels: u64,
+    data: Cow<'_, [u8]>,
+    options: &BmpDecodeOptions,
+    limits: Option<&Limits>,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> crate::Result<(Vec<u8>, PixelLayout, DecodeReport)> {
+    decode_pixels(
+        data,
+        options,
+        limits,
+        alloc_pref,
+        stop,
+        OutputKind::Native,
+        None,
+        None,
+    )
+}
+
+/// Decode BMP pixel data to linear-light f32 (`GrayF32`, `RgbF32` or
+/// `RgbaF32`).
+///
+/// The output buffer is allocated once at f32 size and each row is
+/// converted as it is decoded, so no 8-bit copy of the image exists.
+/// `limits` is applied as in [`decode_bmp_pixels`].
+pub(crate) fn decode_bmp_pixels_linear(
+    data: Cow<'_, [u8]>,
+    options: &BmpDecodeOptions,
+    limits: Option<&Limits>,
     alloc_pref: AllocPref,
     stop: &dyn Stop,
-) -> crate::Result<(Vec<u8>, PixelLayout)> {
-    let mut dec = BmpDecoderState::new(data, permissiveness, max_pixels, alloc_pref);
+) -> crate::Result<(Vec<u8>, PixelLayout, DecodeReport)> {
+    decode_pixels(
+        data,
+        options,
+        limits,
+        alloc_pref,
+        stop,
+        OutputKind::LinearF32,
+        None,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn decode_pixels(
+    data: Cow<'_, [u8]>,
+    options: &BmpDecodeOptions,
+    limits: Option<&Limits>,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+    kind: OutputKind,
+    rows: Option<Range<usize>>,
+    usage: Option<&mut Option<BmpPaletteUsage>>,
+) -> crate::Result<(Vec<u8>, PixelLayout, DecodeReport)> {
+    // A row window or downscaled decode is capped on its own output size,
+    // not the declared image's.
+    let max_pixels = if rows.is_some() || options.downscale != BmpDownscale::None {
+        u64::MAX
+    } else {
+        super::effective_max_pixels(limits)
+    };
+    let mut dec = BmpDecoderState::new(&data, options, max_pixels);
+    dec.limits = limits;
+    if let Cow::Owned(copy) = &data {
+        // A bitmap array entry rebuilt as a standalone BMP, already offered
+        // to `on_alloc` by `array::resolve`.
+        dec.working_bytes = copy.len();
+        crate::limits::check_working_memory(dec.working_bytes, limits)?;
+    }
     dec.decode_headers()?;
+    if let Some(rows) = rows {
+        dec.select_rows(rows)?;
+    }
+    if usage.is_some() && dec.pix_fmt == BmpPixelFormat::Pal8 {
+        dec.palette_counts = Some(vec![0; 256]);
+    }
 
     // Output buffer sized from the (untrusted) header dimensions → default
     // fallible.
     let output_size = dec.output_buf_size()?;
-    let mut buf = alloc_util::alloc_zeroed(alloc_pref, true, output_size)?;
+    let alloc_size = match kind {
+        // Every integer sample becomes a 4-byte f32.
+        OutputKind::LinearF32 => output_size
+            .checked_mul(4 / dec.pix_fmt.bytes_per_sample())
+            .ok_or_else(|| {
+                at!(BitmapError::DimensionsTooLarge {
+                    width: dec.width as u32,
+                    height: dec.height as u32,
+                })
+            })?,
+        OutputKind::Rgb | OutputKind::Native => output_size,
+    };
+    dec.reserve(alloc_size)?;
+    let mut buf = alloc_util::alloc_zeroed(alloc_pref, true, alloc_size)?;
 
     stop.check().map_err(|r| at!(BitmapError::from(r)))?;
-    dec.decode_into::<true>(&mut buf, stop)?;
+    let decoded = match kind {
+        OutputKind::LinearF32 if dec.reads_rows_apart() => dec.decode_linear_rows(&mut buf, stop),
+        OutputKind::LinearF32 => {
+            dec.linear = true;
+            dec.decode_into::<false>(&mut buf, stop)
+        }
+        OutputKind::Native if dec.subsamples_rows() => {
+            dec.decode_subsampled::<true>(&mut buf, stop)
+        }
+        OutputKind::Native => dec.decode_into::<true>(&mut buf, stop),
+        OutputKind::Rgb if dec.subsamples_rows() => dec.decode_subsampled::<false>(&mut buf, stop),
+        OutputKind::Rgb => dec.decode_into::<false>(&mut buf, stop),
+    };
+    let recoverable = dec.permissiveness != BmpPermissiveness::Permissive;
+    decoded
+        .in_stage(dec.stage)
+        .at_offset(dec.bytes.pos)
+        .permissive_may_recover(recoverable)?;
+    if let (Some(usage), Some(mut counts)) = (usage, dec.palette_counts.take()) {
+        // Indices past the declared palette (accepted only when permissive)
+        // are reported apart from the entries.
+        let out_of_range = counts[dec.palette_numbers.min(counts.len())..].iter().sum();
+        counts.truncate(dec.palette_numbers);
+        *usage = Some(BmpPaletteUsage {
+            counts,
+            out_of_range,
+        });
+    }
+    if let Some(valid) = &mut dec.report.valid_rows {
+        // Report rows of the reduced output.
+        let step = options.downscale.factor();
+        *valid = valid.start.div_ceil(step)..valid.end.div_ceil(step);
+    }
 
-    let layout = match dec.pix_fmt {
-        BmpPixelFormat::Rgba => PixelLayout::Bgra8,
-        BmpPixelFormat::Rgb | BmpPixelFormat::Pal8 => PixelLayout::Bgr8,
-        BmpPixelFormat::Gray8 => PixelLayout::Gray8,
-        BmpPixelFormat::None => {
+    let padded = dec.keep_padding && dec.alpha_synthesized;
+    let layout = match (dec.pix_fmt, kind) {
+        (BmpPixelFormat::None, _) => {
             return Err(at!(BitmapError::UnsupportedVariant(
                 "unsupported BMP pixel format".into(),
             )));
         }
+        (BmpPixelFormat::Gray8, OutputKind::LinearF32) => PixelLayout::GrayF32,
+        (BmpPixelFormat::Rgb | BmpPixelFormat::Pal8, OutputKind::LinearF32) => PixelLayout::RgbF32,
+        (BmpPixelFormat::Rgba | BmpPixelFormat::Rgba16, OutputKind::LinearF32) => {
+            PixelLayout::RgbaF32
+        }
+        (BmpPixelFormat::Rgba, OutputKind::Rgb) if padded => PixelLayout::Rgbx8,
+        (BmpPixelFormat::Rgba, OutputKind::Rgb) => PixelLayout::Rgba8,
+        (BmpPixelFormat::Rgba, _) if padded => PixelLayout::Bgrx8,
+        (BmpPixelFormat::Rgba, _) => PixelLayout::Bgra8,
+        (BmpPixelFormat::Rgb | BmpPixelFormat::Pal8, OutputKind::Rgb) => PixelLayout::Rgb8,
+        (BmpPixelFormat::Rgb | BmpPixelFormat::Pal8, _) => PixelLayout::Bgr8,
+        (BmpPixelFormat::Gray8, _) => PixelLayout::Gray8,
+        // No 16-bit BGRA layout exists; wide bitfields stay RGBA16.
+        (BmpPixelFormat::Rgba16, _) => PixelLayout::Rgba16,
     };
 
-    Ok((buf, layout))
+    Ok((buf, layout, dec.report))
+}
+
+/// An 8-bit sample as linear-light f32: sRGB-decoded, or scaled linearly
+/// for `alpha`.
+fn u8_to_linear(v: u8, alpha: bool) -> f32 {
+    if alpha {
+        f32::from(v) / 255.0
+    } else {
+        linear_srgb::default::srgb_u8_to_linear(v)
+    }
+}
+
+/// [`u8_to_linear`] for a 16-bit sample.
+fn u16_to_linear(v: u16, alpha: bool) -> f32 {
+    if alpha {
+        f32::from(v) / 65535.0
+    } else {
+        linear_srgb::default::srgb_u16_to_linear(v)
+    }
+}
+
+/// Store the 8-bit `color` samples of one pixel in `px`: as they are, or
+/// as native-endian linear-light f32 when `linear` (the fourth sample is
+/// alpha).
+fn store_color(px: &mut [u8], color: &[u8], linear: bool) {
+    if !linear {
+        px.copy_from_slice(color);
+        return;
+    }
+    for (c, (dst, &v)) in px.chunks_exact_mut(4).zip(color).enumerate() {
+        dst.copy_from_slice(&u8_to_linear(v, c == 3).to_ne_bytes());
+    }
 }
 
 // ── Internal decoder state ──────────────────────────────────────────
@@ -406,6 +824,11 @@ struct BmpDecoderState<'a> {
     palette_numbers: usize,
     image_in_bgra: bool,
     permissiveness: BmpPermissiveness,
+    alpha_policy: BmpAlphaPolicy,
+    keep_padding: bool,
+    /// The fourth byte of 32-bit output was filled with 255 rather than
+    /// taken from the file.
+    alpha_synthesized: bool,
     /// Horizontal pixels per meter from the DIB header (0 if not present).
     x_pels_per_meter: u32,
     /// Vertical pixels per meter from the DIB header (0 if not present).
@@ -418,9 +841,29 @@ struct BmpDecoderState<'a> {
     /// resource error rather than masked by a downstream truncation error.
     /// `u64::MAX` opts out (header-probe path uses this).
     max_pixels: u64,
-    /// Allocation-fallibility preference for the RLE-decompressed output buffer
-    /// (the only buffer this state allocates from untrusted-derived sizes).
-    alloc_pref: AllocPref,
+    /// Problems patched over so far (Standard/Permissive only; Strict
+    /// rejects instead).
+    report: DecodeReport,
+    /// Caller limits whose `max_memory_bytes` bounds `working_bytes`. `None`
+    /// applies [`crate::limits::DEFAULT_MAX_MEMORY_BYTES`].
+    limits: Option<&'a Limits>,
+    /// Bytes of output and scratch buffers allocated so far.
+    working_bytes: usize,
+    /// Output rows an RLE decode keeps (see `select_rows`); `None` keeps
+    /// all of them.
+    rle_rows: Option<Range<usize>>,
+    /// Downscale step (see [`BmpDownscale`]); 1 decodes every pixel.
+    step: usize,
+    /// Pixels decoded per palette index, when requested (see
+    /// `decode_bmp_pixels_counting`). RLE pixels the stream never writes
+    /// are not counted.
+    palette_counts: Option<Vec<u64>>,
+    /// The part of the file being read, named in decode errors.
+    stage: &'static str,
+    /// RLE and zero-filled output is written as linear-light f32 (see
+    /// `decode_bmp_pixels_linear`); uncompressed data goes through
+    /// `decode_linear_rows` instead.
+    linear: bool,
 }
 
 impl<'a> BmpDecoderState<'a> {
@@ -429,12 +872,8 @@ impl<'a> BmpDecoderState<'a> {
     /// via the `Limits` API. This limit is independent of system memory.
     const MAX_OUTPUT_BYTES: usize = 1024 * 1024 * 1024;
 
-    fn new(
-        data: &'a [u8],
-        permissiveness: BmpPermissiveness,
-        max_pixels: u64,
-        alloc_pref: AllocPref,
-    ) -> Self {
+    fn new(data: &'a [u8], options: &BmpDecodeOptions, max_pixels: u64) -> Self {
+        let permissiveness = options.permissiveness;
         let mut cursor = Cursor::new(data);
         cursor.permissive = permissiveness == BmpPermissiveness::Permissive;
         Self {
@@ -454,15 +893,106 @@ impl<'a> BmpDecoderState<'a> {
             palette_numbers: 0,
             image_in_bgra: false,
             permissiveness,
+            alpha_policy: options.alpha_policy,
+            keep_padding: options.keep_padding,
+            alpha_synthesized: false,
             x_pels_per_meter: 0,
             y_pels_per_meter: 0,
             max_pixels,
-            alloc_pref,
+            report: DecodeReport::default(),
+            limits: None,
+            working_bytes: 0,
+            rle_rows: None,
+            step: options.downscale.factor() as usize,
+            palette_counts: None,
+            stage: "BMP file header",
+            linear: false,
         }
     }
 
-    #[allow(unused_assignments)]
+    fn warn(&mut self, warning: DecodeWarning) {
+        self.report.warn(warning);
+    }
+
+    /// Account for a `len`-byte allocation against the memory limit, and
+    /// offer it to [`Limits::on_alloc`], before making it. Buffers are never
+    /// released from the tally, so this bounds the peak from above.
+    fn reserve(&mut self, len: usize) -> crate::Result<()> {
+        self.working_bytes = self.working_bytes.saturating_add(len);
+        crate::limits::check_working_memory(self.working_bytes, self.limits)?;
+        crate::limits::check_alloc(len, self.limits)
+    }
+
+    /// Restrict the decode to output rows `rows` (top-down, within the
+    /// image). Uncompressed data is entered at the first stored row needed
+    /// and read for `rows.len()` rows; RLE streams cannot be entered midway,
+    /// so they are run from the start with writes outside the window
+    /// dropped.
+    fn select_rows(&mut self, rows: Range<usize>) -> crate::Result<()> {
+        if matches!(self.comp, BmpCompression::Rle4 | BmpCompression::Rle8) {
+            self.rle_rows = Some(rows);
+            return Ok(());
+        }
+        let stride = self.stored_stride()?;
+        // Stored rows run bottom-up unless the height was negative.
+        let first = if self.flip_vertically {
+            self.height - rows.end
+        } else {
+            rows.start
+        };
+        let start = first
+            .checked_mul(stride)
+            .and_then(|off| off.checked_add(self.bytes.pos))
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        self.bytes.set_position(start)?;
+        self.height = rows.len();
+        Ok(())
+    }
+
+    /// Bytes per stored row of uncompressed data, padding included.
+    fn stored_stride(&self) -> crate::Result<usize> {
+        self.width_times(usize::from(self.depth))?
+            .checked_add(31)
+            .map(|bits| bits / 32 * 4)
+            .ok_or_else(|| {
+                at!(BitmapError::DimensionsTooLarge {
+                    width: self.width as u32,
+                    height: self.height as u32,
+                })
+            })
+    }
+
+    /// A downscaled decode of uncompressed data, which reads only the kept
+    /// rows (see `decode_subsampled`). RLE and unknown compression decode
+    /// through `decode_into`, dropping skipped pixels in `put_rle_index`.
+    fn subsamples_rows(&self) -> bool {
+        self.step > 1 && self.reads_rows_apart()
+    }
+
+    /// Uncompressed data, whose stored rows can be located and decoded one
+    /// at a time.
+    fn reads_rows_apart(&self) -> bool {
+        matches!(self.comp, BmpCompression::Rgb | BmpCompression::Bitfields)
+    }
+
+    /// Zeroed scratch buffer of `len` bytes, counted against the memory
+    /// limit.
+    fn scratch(&mut self, len: usize) -> crate::Result<Vec<u8>> {
+        self.reserve(len)?;
+        Ok(vec![0u8; len])
+    }
+
     fn decode_headers(&mut self) -> crate::Result<()> {
+        let parsed = self.parse_headers();
+        let recoverable = self.permissiveness != BmpPermissiveness::Permissive;
+        parsed
+            .in_stage(self.stage)
+            .at_offset(self.bytes.pos)
+            .permissive_may_recover(recoverable)
+    }
+
+    #[allow(unused_assignments)]
+    fn parse_headers(&mut self) -> crate::Result<()> {
         if self.decoded_headers {
             return Ok(());
         }
@@ -482,18 +1012,23 @@ impl<'a> BmpDecoderState<'a> {
 
         // Strict: validate file size field matches actual data length
         if is_strict && file_size_field != 0 && file_size_field as usize != data_len {
-            return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+            return Err(at!(BitmapError::invalid_header(alloc::format!(
                 "BMP file size field ({file_size_field}) doesn't match actual size ({data_len})"
             ))));
         }
+        if file_size_field != 0 && file_size_field as usize != data_len {
+            self.warn(DecodeWarning::FileSizeMismatch {
+                declared: file_size_field,
+                actual: data_len,
+            });
+        }
 
         let hsize = self.bytes.get_u32_le_err()?;
+        self.stage = "BMP info header";
         let ihsize = self.bytes.get_u32_le_err()?;
 
         if ihsize.saturating_add(14) > hsize {
-            return Err(at!(BitmapError::InvalidHeader(
-                "invalid BMP header size".into()
-            )));
+            return Err(at!(BitmapError::invalid_header("invalid BMP header size")));
         }
 
         let (width, height, planes, bpp, compression);
@@ -542,12 +1077,12 @@ impl<'a> BmpDecoderState<'a> {
                         let x_signed = x_pixels as i32;
                         let y_signed = y_pixels as i32;
                         if x_signed < 0 || x_pixels > MAX_RESOLUTION {
-                            return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+                            return Err(at!(BitmapError::invalid_header(alloc::format!(
                                 "BMP horizontal resolution out of range ({x_signed})"
                             ))));
                         }
                         if y_signed < 0 || y_pixels > MAX_RESOLUTION {
-                            return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+                            return Err(at!(BitmapError::invalid_header(alloc::format!(
                                 "BMP vertical resolution out of range ({y_signed})"
                             ))));
                         }
@@ -557,7 +1092,7 @@ impl<'a> BmpDecoderState<'a> {
                             let row_bytes = (width as usize * bpp as usize).div_ceil(32) * 4;
                             let expected_size = row_bytes * (height as i32).unsigned_abs() as usize;
                             if image_size_field as usize != expected_size {
-                                return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+                                return Err(at!(BitmapError::invalid_header(alloc::format!(
                                     "BMP image data size field ({image_size_field}) doesn't match expected ({expected_size})"
                                 ))));
                             }
@@ -568,9 +1103,11 @@ impl<'a> BmpDecoderState<'a> {
                     // (BITMAPV2INFOHEADER+), or external (after 40-byte header)
                     // when compression is BI_BITFIELDS.
                     if ihsize >= 52 || compression == BmpCompression::Bitfields {
+                        self.stage = "BMP bitfield masks";
                         self.rgb_bitfields[0] = self.bytes.get_u32_le_err()?;
                         self.rgb_bitfields[1] = self.bytes.get_u32_le_err()?;
                         self.rgb_bitfields[2] = self.bytes.get_u32_le_err()?;
+                        self.stage = "BMP info header";
                     }
 
                     let mut _colorspace_type: u32 = 0;
@@ -596,7 +1133,7 @@ impl<'a> BmpDecoderState<'a> {
                 }
             }
             _ => {
-                return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+                return Err(at!(BitmapError::invalid_header(alloc::format!(
                     "unknown BMP info header size: {ihsize}"
                 ))));
             }
@@ -604,20 +1141,23 @@ impl<'a> BmpDecoderState<'a> {
 
         // Planes validation (Standard and Strict reject planes != 1)
         if !is_permissive && planes != 1 {
-            return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+            return Err(at!(BitmapError::invalid_header(alloc::format!(
                 "BMP planes field is {planes}, expected 1"
             ))));
         }
+        if planes != 1 {
+            self.warn(DecodeWarning::InvalidPlanes(planes));
+        }
 
         self.flip_vertically = (height as i32) > 0;
         self.height = (height as i32).unsigned_abs() as usize;
         self.width = width as usize;
 
         if self.width == 0 {
-            return Err(at!(BitmapError::InvalidHeader("BMP width is zero".into())));
+            return Err(at!(BitmapError::invalid_header("BMP width is zero")));
         }
         if self.height == 0 {
-            return Err(at!(BitmapError::InvalidHeader("BMP height is zero".into())));
+            return Err(at!(BitmapError::invalid_header("BMP height is zero")));
         }
 
         // Enforce the effective pixel-count ceiling on the *declared*
@@ -638,18 +1178,29 @@ impl<'a> BmpDecoderState<'a> {
             && !self.flip_vertically
             && matches!(compression, BmpCompression::Rle4 | BmpCompression::Rle8)
         {
-            return Err(at!(BitmapError::InvalidData(
-                "RLE compression with top-down row order is forbidden by BMP spec".into(),
+            return Err(at!(BitmapError::invalid_data(
+                "RLE compression with top-down row order is forbidden by BMP spec",
             )));
         }
+        if !self.flip_vertically
+            && matches!(compression, BmpCompression::Rle4 | BmpCompression::Rle8)
+        {
+            self.warn(DecodeWarning::RleTopDown);
+        }
 
         if bpp == 0 {
-            return Err(at!(BitmapError::InvalidHeader(
-                "BMP bit depth is zero".into()
-            )));
+            return Err(at!(BitmapError::invalid_header("BMP bit depth is zero")));
         }
 
+        // Any BITFIELDS channel wider than 8 bits (e.g. 2-10-10-10) decodes
+        // to RGBA16 rather than being crushed to 8 bits by `shift_signed`.
+        let wide_bitfields = compression == BmpCompression::Bitfields
+            && self.rgb_bitfields.iter().any(|m| m.count_ones() > 8);
+
         match bpp {
+            // 64-bit BGRA in s2.13 fixed point (the WIC extension).
+            64 if compression == BmpCompression::Rgb => self.pix_fmt = BmpPixelFormat::Rgba16,
+            32 | 16 if wide_bitfields => self.pix_fmt = BmpPixelFormat::Rgba16,
             32 => self.pix_fmt = BmpPixelFormat::Rgba,
             24 => self.pix_fmt = BmpPixelFormat::Rgb,
             16 => {
@@ -696,9 +1247,10 @@ impl<'a> BmpDecoderState<'a> {
             )));
         }
 
-        let p = self.hsize.wrapping_sub(self.ihsize).wrapping_sub(14);
+        let p = hsize.wrapping_sub(ihsize).wrapping_sub(14);
 
         if self.pix_fmt == BmpPixelFormat::Pal8 {
+            self.stage = "BMP palette";
             let max_colors = 1u32 << bpp;
             let mut colors = max_colors;
 
@@ -707,12 +1259,17 @@ impl<'a> BmpDecoderState<'a> {
                 let t = self.bytes.get_u32_le_err()? as i32;
                 if t < 0 || t > (1 << bpp) {
                     if !is_permissive {
-                        return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+                        return Err(at!(BitmapError::invalid_header(alloc::format!(
                             "BMP palette count ({t}) exceeds max for {bpp}-bit depth ({})",
                             1u32 << bpp
-                        ))));
+                        ))
+                        .at_offset(46)));
                     }
                     // Permissive: clamp to max
+                    self.warn(DecodeWarning::PaletteCountClamped {
+                        declared: t,
+                        max: max_colors,
+                    });
                 } else if t != 0 {
                     colors = t as u32;
                 }
@@ -726,8 +1283,8 @@ impl<'a> BmpDecoderState<'a> {
             // OS/2: 3 bytes per entry
             if ihsize == 12 {
                 if p < colors * 3 {
-                    return Err(at!(BitmapError::InvalidData(
-                        "invalid BMP palette entries".into(),
+                    return Err(at!(BitmapError::invalid_data(
+                        "invalid BMP palette entries",
                     )));
                 }
                 for i in 0..colors.min(256) as usize {
@@ -753,13 +1310,21 @@ impl<'a> BmpDecoderState<'a> {
             self.palette_numbers = colors as usize;
         }
 
+        self.stage = "BMP pixel data";
         self.comp = compression;
         self.depth = bpp;
         self.ihsize = ihsize;
         self.hsize = hsize;
         // Pixel data starts at the data offset (hsize), or after the palette
         // if the data offset is too small (malformed BMP with wrong bfOffBits).
+        self.report.valid_rows = Some(0..self.height as u32);
         let pixel_data_start = (hsize as usize).max(self.bytes.pos);
+        if pixel_data_start != hsize as usize {
+            self.warn(DecodeWarning::PixelOffsetAdjusted {
+                declared: hsize,
+                used: pixel_data_start,
+            });
+        }
         self.bytes.set_position(pixel_data_start)?;
 
         // For non-RLE (uncompressed) formats, validate that the available
@@ -786,7 +1351,7 @@ impl<'a> BmpDecoderState<'a> {
             if bytes_per_row > 0 && available_bytes < bytes_per_row {
                 // Not enough data for even a single scanline
                 if !is_permissive {
-                    return Err(at!(BitmapError::InvalidData(alloc::format!(
+                    return Err(at!(BitmapError::invalid_data(alloc::format!(
                         "BMP pixel data too short: {available_bytes} bytes available, \
                          need at least {bytes_per_row} for one row of {}×{} @ {bpp}bpp",
                         self.width,
@@ -795,6 +1360,28 @@ impl<'a> BmpDecoderState<'a> {
                 }
             }
 
+            // The last row's padding is commonly omitted, so it doesn't count.
+            let stride = bytes_per_row.div_ceil(4) * 4;
+            let needed = stride
+                .saturating_mul(self.height - 1)
+                .saturating_add(bytes_per_row);
+            if available_bytes < needed {
+                let complete = match available_bytes.checked_sub(bytes_per_row) {
+                    Some(rest) => (rest / stride + 1).min(self.height),
+                    None => 0,
+                };
+                // Stored rows run bottom-up unless the height was negative.
+                self.report.valid_rows = Some(if self.flip_vertically {
+                    (self.height - complete) as u32..self.height as u32
+                } else {
+                    0..complete as u32
+                });
+                self.warn(DecodeWarning::TruncatedPixelData {
+                    expected: needed,
+                    available: available_bytes,
+                });
+            }
+
             // Cap: output size must not exceed 1024× the available input data.
             // Uncompressed BMP expands at most ~4× (1bpp → 3 bytes RGB) per
             // input byte; 1024× is extremely generous and only catches
@@ -802,10 +1389,10 @@ impl<'a> BmpDecoderState<'a> {
             let output_size = self
                 .width
                 .saturating_mul(self.height)
-                .saturating_mul(self.pix_fmt.num_components());
+                .saturating_mul(self.pix_fmt.bytes_per_pixel());
             let max_reasonable = available_bytes.saturating_mul(1024);
             if output_size > max_reasonable && available_bytes < 1024 * 1024 {
-                return Err(at!(BitmapError::InvalidData(alloc::format!(
+                return Err(at!(BitmapError::invalid_data(alloc::format!(
                     "BMP claims {}×{} @ {bpp}bpp ({output_size} output bytes) \
                      but only {available_bytes} bytes of pixel data available",
                     self.width,
@@ -819,10 +1406,27 @@ impl<'a> BmpDecoderState<'a> {
         Ok(())
     }
 
+    /// Channel masks the pixel decode applies to direct-color data: the
+    /// declared BITFIELDS masks, or the implicit `BI_RGB` layout (5-5-5 for
+    /// 16-bit, 8-8-8 for 24/32-bit, where the fourth byte of 32-bit pixels
+    /// is governed by [`BmpAlphaPolicy`] rather than a mask).
+    fn effective_masks(&self) -> Option<[u32; 4]> {
+        match self.depth {
+            16 | 32 if self.comp == BmpCompression::Bitfields && self.rgb_bitfields != [0; 4] => {
+                Some(self.rgb_bitfields)
+            }
+            16 => Some([0x7C00, 0x03E0, 0x001F, 0]),
+            24 | 32 => Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0]),
+            _ => None,
+        }
+    }
+
     fn output_buf_size(&self) -> crate::Result<usize> {
+        let rows = self.rle_rows.as_ref().map_or(self.height, |r| r.len());
         self.width
-            .checked_mul(self.height)
-            .and_then(|wh| wh.checked_mul(self.pix_fmt.num_components()))
+            .div_ceil(self.step)
+            .checked_mul(rows.div_ceil(self.step))
+            .and_then(|wh| wh.checked_mul(self.pix_fmt.bytes_per_pixel()))
             .filter(|&size| size <= Self::MAX_OUTPUT_BYTES)
             .ok_or_else(|| {
                 at!(BitmapError::DimensionsTooLarge {
@@ -850,26 +1454,34 @@ impl<'a> BmpDecoderState<'a> {
         stop: &dyn Stop,
     ) -> crate::Result<()> {
         let output_size = self.output_buf_size()?;
-        let buf = &mut buf[0..output_size];
+        // Linear output holds an f32 per sample.
+        let buf = if self.linear {
+            buf
+        } else {
+            &mut buf[0..output_size]
+        };
 
         // Unknown compression (Permissive only): zero-fill output
-        if let BmpCompression::Unknown(_) = self.comp {
+        if let BmpCompression::Unknown(c) = self.comp {
+            self.warn(DecodeWarning::UnknownCompression(c));
+            self.report.valid_rows = Some(0..0);
             buf.fill(0);
             return Ok(());
         }
 
         if self.comp == BmpCompression::Rle4 || self.comp == BmpCompression::Rle8 {
-            let scanline_data = self.decode_rle(stop)?;
-            if self.pix_fmt == BmpPixelFormat::Pal8 {
-                self.expand_palette(&scanline_data, buf, false)?;
-                self.flip_vertically = true;
-            }
+            self.decode_rle(buf, stop)?;
+            // Runs are written in final row order (see `put_rle_index`).
+            self.flip_vertically = false;
         } else {
             match self.depth {
-                8 | 16 | 24 | 32 => {
+                8 | 16 | 24 | 32 | 64 => {
                     if self.pix_fmt == BmpPixelFormat::Pal8 {
                         self.expand_palette_from_remaining_bytes(buf, true)?;
                         self.flip_vertically ^= true;
+                    } else if self.pix_fmt == BmpPixelFormat::Rgba16 {
+                        self.decode_wide_bitfields(buf, stop)?;
+                        self.flip_vertically ^= true;
                     } else if self.depth == 32 || self.depth == 16 {
                         let pad_size = self.width_times(self.pix_fmt.num_components())?;
 
@@ -882,6 +1494,9 @@ impl<'a> BmpDecoderState<'a> {
                         if (self.rgb_bitfields == [0; 4] || self.comp != BmpCompression::Bitfields)
                             && self.depth == 32
                         {
+                            // 32-bit BI_RGB: the 4th byte is "reserved" and
+                            // may or may not be alpha — see `BmpAlphaPolicy`.
+                            let mut any_alpha = 0u8;
                             for (row_idx, out) in buf.rchunks_exact_mut(pad_size).enumerate() {
                                 if row_idx % 16 == 0 {
                                     stop.check().map_err(|r| at!(BitmapError::from(r)))?;
@@ -891,9 +1506,21 @@ impl<'a> BmpDecoderState<'a> {
                                     if !PRESERVE_BGRA {
                                         pixels.swap(0, 2);
                                     }
+                                    any_alpha |= pixels[3];
                                     a.copy_from_slice(&pixels);
                                 }
                             }
+                            let force_opaque = match self.alpha_policy {
+                                BmpAlphaPolicy::Opaque => true,
+                                BmpAlphaPolicy::Trust => false,
+                                BmpAlphaPolicy::Auto => any_alpha == 0,
+                            };
+                            if force_opaque {
+                                for px in buf.chunks_exact_mut(4) {
+                                    px[3] = 255;
+                                }
+                                self.alpha_synthesized = true;
+                            }
                             self.image_in_bgra = true;
                         } else {
                             let [mr, mg, mb, ma] = self.rgb_bitfields;
@@ -931,6 +1558,7 @@ impl<'a> BmpDecoderState<'a> {
                             };
 
                             if self.depth == 32 {
+                                self.alpha_synthesized = ma == 0;
                                 for (row_idx, out) in buf.rchunks_exact_mut(pad_size).enumerate() {
                                     if row_idx % 16 == 0 {
                                         stop.check().map_err(|r| at!(BitmapError::from(r)))?;
@@ -1030,9 +1658,10 @@ impl<'a> BmpDecoderState<'a> {
                             })
                         })?;
                     let in_width_bytes = self.width_times(usize::from(self.depth))?.div_ceil(8);
-                    let mut in_width_buf = vec![0u8; in_width_bytes];
+                    let row_padding = self.stored_stride()? - in_width_bytes;
+                    let mut in_width_buf = self.scratch(in_width_bytes)?;
                     let scanline_size = width_bytes * 3;
-                    let mut scanline_bytes = vec![0u8; scanline_size];
+                    let mut scanline_bytes = self.scratch(scanline_size)?;
 
                     let row_out_size = (3 + usize::from(self.is_alpha)) * self.width;
                     for (row_idx, out_bytes) in buf.rchunks_exact_mut(row_out_size).enumerate() {
@@ -1040,6 +1669,7 @@ impl<'a> BmpDecoderState<'a> {
                             stop.check().map_err(|r| at!(BitmapError::from(r)))?;
                         }
                         self.bytes.read_exact_bytes(&mut in_width_buf)?;
+                        let _ = self.bytes.skip(row_padding);
                         expand_bits_to_byte(
                             self.depth as usize,
                             true,
@@ -1060,8 +1690,8 @@ impl<'a> BmpDecoderState<'a> {
 
         // Flip if needed
         if self.flip_vertically {
-            let length = self.width_times(self.pix_fmt.num_components())?;
-            let mut scanline = vec![0u8; length];
+            let length = self.width_times(self.pix_fmt.bytes_per_pixel())?;
+            let mut scanline = self.scratch(length)?;
             let mid = buf.len() / 2;
             let (in_img_top, in_img_bottom) = buf.split_at_mut(mid);
 
@@ -1077,7 +1707,7 @@ impl<'a> BmpDecoderState<'a> {
 
         // Convert to BGR(A) if requested and not already done
         if PRESERVE_BGRA && !self.image_in_bgra {
-            match self.pix_fmt.num_components() {
+            match self.pix_fmt.bytes_per_pixel() {
                 3 => {
                     for pix in buf.chunks_exact_mut(3) {
                         pix.swap(0, 2);
@@ -1095,10 +1725,207 @@ impl<'a> BmpDecoderState<'a> {
         Ok(())
     }
 
-    fn expand_palette(&self, in_bytes: &[u8], buf: &mut [u8], unpad: bool) -> crate::Result<()> {
+    /// Downscaled decode of uncompressed data: every `step`-th pixel of
+    /// each kept row (see `decode_row_by_row`). `buf` holds the reduced
+    /// image, top-down.
+    fn decode_subsampled<const PRESERVE_BGRA: bool>(
+        &mut self,
+        buf: &mut [u8],
+        stop: &dyn Stop,
+    ) -> crate::Result<()> {
+        let (bpp, step) = (self.pix_fmt.bytes_per_pixel(), self.step);
+        let out_row = self.width.div_ceil(step) * bpp;
+        let transparent =
+            self.decode_row_by_row::<PRESERVE_BGRA>(buf, out_row, stop, |row, out| {
+                for (dst, src) in out.chunks_exact_mut(bpp).zip(row.chunks(bpp * step)) {
+                    dst.copy_from_slice(&src[..bpp]);
+                }
+            })?;
+        if transparent {
+            for px in buf.chunks_exact_mut(4) {
+                px[3] = 255;
+            }
+        }
+        Ok(())
+    }
+
+    /// Linear-light f32 decode of uncompressed data (every `step`-th pixel
+    /// when downscaling): each row is widened as soon as it is decoded, so
+    /// the integer image never exists in full. `buf` holds the output,
+    /// top-down.
+    fn decode_linear_rows(&mut self, buf: &mut [u8], stop: &dyn Stop) -> crate::Result<()> {
+        let (bpp, step) = (self.pix_fmt.bytes_per_pixel(), self.step);
+        let channels = self.pix_fmt.num_components();
+        let wide = self.pix_fmt == BmpPixelFormat::Rgba16;
+        let out_row = self.width.div_ceil(step) * channels * 4;
+        let transparent = self.decode_row_by_row::<false>(buf, out_row, stop, |row, out| {
+            for (dst, src) in out
+                .chunks_exact_mut(channels * 4)
+                .zip(row.chunks(bpp * step))
+            {
+                for (c, sample) in dst.chunks_exact_mut(4).enumerate() {
+                    let v = if wide {
+                        u16_to_linear(u16::from_ne_bytes([src[c * 2], src[c * 2 + 1]]), c == 3)
+                    } else {
+                        u8_to_linear(src[c], c == 3)
+                    };
+                    sample.copy_from_slice(&v.to_ne_bytes());
+                }
+            }
+        })?;
+        if transparent {
+            for px in buf.chunks_exact_mut(16) {
+                px[12..].copy_from_slice(&1.0f32.to_ne_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode uncompressed data one output row at a time: each kept row is
+    /// located by its stored offset, decoded on its own through
+    /// `decode_into` into a full-width scratch row, and handed to `emit`
+    /// with its `out_row`-byte slice of `buf` (top-down).
+    ///
+    /// `BmpAlphaPolicy::Auto` has to see every kept pixel before deciding,
+    /// so rows are read verbatim; returns `true` when it found them all
+    /// transparent and the caller must make `buf` opaque.
+    fn decode_row_by_row<const PRESERVE_BGRA: bool>(
+        &mut self,
+        buf: &mut [u8],
+        out_row: usize,
+        stop: &dyn Stop,
+        mut emit: impl FnMut(&[u8], &mut [u8]),
+    ) -> crate::Result<bool> {
+        let step = core::mem::replace(&mut self.step, 1);
+        let (height, bottom_up) = (self.height, self.flip_vertically);
+        let stride = self.stored_stride()?;
+        let data_start = self.bytes.pos;
+        let bpp = self.pix_fmt.bytes_per_pixel();
+        let mut row = self.scratch(self.width_times(bpp)?)?;
+
+        let reserved_alpha = self.depth == 32
+            && self.pix_fmt == BmpPixelFormat::Rgba
+            && (self.rgb_bitfields == [0; 4] || self.comp != BmpCompression::Bitfields);
+        let auto_alpha = reserved_alpha && self.alpha_policy == BmpAlphaPolicy::Auto;
+        if auto_alpha {
+            self.alpha_policy = BmpAlphaPolicy::Trust;
+        }
+        let mut any_alpha = false;
+
+        // Each row's scratch buffers are freed before the next row.
+        let working = self.working_bytes;
+        for (out_y, out) in buf.chunks_exact_mut(out_row).enumerate() {
+            if out_y % 16 == 0 {
+                stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+            }
+            let y = out_y * step;
+            let stored = if bottom_up { height - 1 - y } else { y };
+            // Rows past the end read as they would in a full decode.
+            self.bytes.pos = stored
+                .saturating_mul(stride)
+                .saturating_add(data_start)
+                .min(self.bytes.data.len());
+            self.height = 1;
+            self.flip_vertically = bottom_up;
+            self.decode_into::<PRESERVE_BGRA>(&mut row, stop)?;
+            self.working_bytes = working;
+            if auto_alpha {
+                any_alpha |= row.chunks(bpp * step).any(|px| px[3] != 0);
+            }
+            emit(&row, out);
+        }
+        self.height = height;
+        self.flip_vertically = false;
+
+        if auto_alpha {
+            self.alpha_policy = BmpAlphaPolicy::Auto;
+            if !any_alpha {
+                self.alpha_synthesized = true;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Decode 16/32-bit BITFIELDS pixels with a channel mask wider than 8
+    /// bits, or 64-bit pixels, into native-endian RGBA16 rows (bottom-up,
+    /// like the other uncompressed readers). Missing alpha mask → opaque.
+    ///
+    /// 64-bit pixels are B, G, R, A as little-endian s2.13 fixed point with
+    /// linear-light color and straight alpha; values are clamped to
+    /// 0.0–1.0 and the color channels sRGB-encoded.
+    fn decode_wide_bitfields(&mut self, buf: &mut [u8], stop: &dyn Stop) -> crate::Result<()> {
+        let [mr, mg, mb, ma] = self.rgb_bitfields;
+        let out_row_bytes = self.width_times(8)?;
+        let in_pixel_bytes = usize::from(self.depth / 8);
+        let in_data_bytes = self.width_times(in_pixel_bytes)?;
+        let in_row_bytes = in_data_bytes
+            .checked_add(3)
+            .map(|v| v & !3usize)
+            .ok_or_else(|| {
+                at!(BitmapError::DimensionsTooLarge {
+                    width: self.width as u32,
+                    height: self.height as u32,
+                })
+            })?;
+
+        for (row_idx, out) in buf.rchunks_exact_mut(out_row_bytes).enumerate() {
+            if row_idx % 16 == 0 {
+                stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+            }
+            for px in out.chunks_exact_mut(8) {
+                if in_pixel_bytes == 8 {
+                    let v = self.bytes.read_fixed_bytes_or_zero::<8>();
+                    let fixed = |i: usize| {
+                        u32::from(i16::from_le_bytes([v[i * 2], v[i * 2 + 1]]).clamp(0, 8192) as u16)
+                    };
+                    let color = |i: usize| {
+                        linear_srgb::default::linear_to_srgb_u16(fixed(i) as f32 / 8192.0)
+                    };
+                    let alpha = ((fixed(3) * 65535 + 4096) / 8192) as u16;
+                    let rgba = [color(2), color(1), color(0), alpha];
+                    for (dst, val) in px.chunks_exact_mut(2).zip(rgba) {
+                        dst.copy_from_slice(&val.to_ne_bytes());
+                    }
+                    continue;
+                }
+                let v = if in_pixel_bytes == 4 {
+                    self.bytes.get_u32_le()
+                } else {
+                    u32::from(u16::from_le_bytes(
+                        self.bytes.read_fixed_bytes_or_zero::<2>(),
+                    ))
+                };
+                let a = if ma == 0 {
+                    u16::MAX
+                } else {
+                    scale_mask_to_u16(v, ma)
+                };
+                let rgba = [
+                    scale_mask_to_u16(v, mr),
+                    scale_mask_to_u16(v, mg),
+                    scale_mask_to_u16(v, mb),
+                    a,
+                ];
+                for (dst, val) in px.chunks_exact_mut(2).zip(rgba) {
+                    dst.copy_from_slice(&val.to_ne_bytes());
+                }
+            }
+            let _ = self.bytes.skip(in_row_bytes - in_data_bytes);
+        }
+        Ok(())
+    }
+
+    fn expand_palette(
+        &mut self,
+        in_bytes: &[u8],
+        buf: &mut [u8],
+        unpad: bool,
+    ) -> crate::Result<()> {
         let palette = &self.palette;
         let pad = usize::from(unpad) * (((-(self.width as i32)) as u32) & 3) as usize;
         let validate = self.permissiveness != BmpPermissiveness::Permissive;
+        let mut out_of_range = false;
 
         if self.is_alpha {
             for (out_stride, in_stride) in buf
@@ -1108,11 +1935,17 @@ impl<'a> BmpDecoderState<'a> {
             {
                 for (pal_byte, chunks) in in_stride.iter().zip(out_stride.chunks_exact_mut(4)) {
                     let idx = usize::from(*pal_byte);
-                    if validate && idx >= self.palette_numbers {
-                        return Err(at!(BitmapError::InvalidData(alloc::format!(
-                            "palette index {idx} out of range (palette has {} entries)",
-                            self.palette_numbers
-                        ))));
+                    if idx >= self.palette_numbers {
+                        if validate {
+                            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                                "palette index {idx} out of range (palette has {} entries)",
+                                self.palette_numbers
+                            ))));
+                        }
+                        out_of_range = true;
+                    }
+                    if let Some(counts) = &mut self.palette_counts {
+                        counts[idx] += 1;
                     }
                     let entry = palette[idx];
                     chunks[0] = entry.red;
@@ -1129,11 +1962,17 @@ impl<'a> BmpDecoderState<'a> {
             {
                 for (pal_byte, chunks) in in_stride.iter().zip(out_stride.chunks_exact_mut(3)) {
                     let idx = usize::from(*pal_byte);
-                    if validate && idx >= self.palette_numbers {
-                        return Err(at!(BitmapError::InvalidData(alloc::format!(
-                            "palette index {idx} out of range (palette has {} entries)",
-                            self.palette_numbers
-                        ))));
+                    if idx >= self.palette_numbers {
+                        if validate {
+                            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                                "palette index {idx} out of range (palette has {} entries)",
+                                self.palette_numbers
+                            ))));
+                        }
+                        out_of_range = true;
+                    }
+                    if let Some(counts) = &mut self.palette_counts {
+                        counts[idx] += 1;
                     }
                     let entry = palette[idx];
                     chunks[0] = entry.red;
@@ -1142,6 +1981,9 @@ impl<'a> BmpDecoderState<'a> {
                 }
             }
         }
+        if out_of_range {
+            self.warn(DecodeWarning::PaletteIndexOutOfRange);
+        }
         Ok(())
     }
 
@@ -1152,17 +1994,24 @@ impl<'a> BmpDecoderState<'a> {
     ) -> crate::Result<()> {
         let pad = usize::from(unpad) * (((-(self.width as i32)) as u32) & 3) as usize;
         let validate = self.permissiveness != BmpPermissiveness::Permissive;
+        let mut out_of_range = false;
 
         if self.is_alpha {
             for out_stride in buf.rchunks_exact_mut(self.width * 4).take(self.height) {
                 for chunks in out_stride.chunks_exact_mut(4) {
                     let byte = self.bytes.read_u8();
                     let idx = usize::from(byte);
-                    if validate && idx >= self.palette_numbers {
-                        return Err(at!(BitmapError::InvalidData(alloc::format!(
-                            "palette index {idx} out of range (palette has {} entries)",
-                            self.palette_numbers
-                        ))));
+                    if idx >= self.palette_numbers {
+                        if validate {
+                            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                                "palette index {idx} out of range (palette has {} entries)",
+                                self.palette_numbers
+                            ))));
+                        }
+                        out_of_range = true;
+                    }
+                    if let Some(counts) = &mut self.palette_counts {
+                        counts[idx] += 1;
                     }
                     let entry = self.palette[idx];
                     chunks[0] = entry.red;
@@ -1177,11 +2026,17 @@ impl<'a> BmpDecoderState<'a> {
                 for chunks in out_stride.chunks_exact_mut(3) {
                     let byte = self.bytes.read_u8();
                     let idx = usize::from(byte);
-                    if validate && idx >= self.palette_numbers {
-                        return Err(at!(BitmapError::InvalidData(alloc::format!(
-                            "palette index {idx} out of range (palette has {} entries)",
-                            self.palette_numbers
-                        ))));
+                    if idx >= self.palette_numbers {
+                        if validate {
+                            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                                "palette index {idx} out of range (palette has {} entries)",
+                                self.palette_numbers
+                            ))));
+                        }
+                        out_of_range = true;
+                    }
+                    if let Some(counts) = &mut self.palette_counts {
+                        counts[idx] += 1;
                     }
                     let entry = self.palette[idx];
                     chunks[0] = entry.red;
@@ -1191,10 +2046,14 @@ impl<'a> BmpDecoderState<'a> {
                 self.bytes.skip(pad)?;
             }
         }
+        if out_of_range {
+            self.warn(DecodeWarning::PaletteIndexOutOfRange);
+        }
         Ok(())
     }
 
-    fn decode_rle(&mut self, stop: &dyn Stop) -> crate::Result<Vec<u8>> {
+    fn decode_rle(&mut self, out: &mut [u8], stop: &dyn Stop) -> crate::Result<()> {
+        self.stage = "BMP RLE data";
         let depth = if self.depth < 8 { 8 } else { self.depth };
 
         let pixel_bits = self
@@ -1208,14 +2067,17 @@ impl<'a> BmpDecoderState<'a> {
                 })
             })?;
 
-        let alloc_size = pixel_bits.checked_add(7).ok_or_else(|| {
+        // Size of the decoded index stream. Runs are expanded straight into
+        // `out`, so this is bookkeeping only — nothing of this size is
+        // allocated beyond the output buffer itself.
+        let decoded_size = pixel_bits.checked_add(7).ok_or_else(|| {
             at!(BitmapError::DimensionsTooLarge {
                 width: self.width as u32,
                 height: self.height as u32,
             })
         })? >> 3;
 
-        if alloc_size > Self::MAX_OUTPUT_BYTES {
+        if decoded_size > Self::MAX_OUTPUT_BYTES {
             return Err(at!(BitmapError::DimensionsTooLarge {
                 width: self.width as u32,
                 height: self.height as u32,
@@ -1225,7 +2087,7 @@ impl<'a> BmpDecoderState<'a> {
         // Decompression-bomb guard. RLE4/RLE8 expand by at most ~127 output
         // bytes per input byte (a 2-byte encoded run yields ≤255 indices). A
         // declared output far larger than the whole file could possibly encode
-        // is a bomb or a truncated header — reject it instead of allocating and
+        // is a bomb or a truncated header — reject it instead of
         // post-processing a near-output-cap buffer (palette expansion, flip,
         // format conversion all run per output pixel) for a tiny input. A
         // 158-byte file declaring ~2.7e8 pixels otherwise cost ~18 s of work
@@ -1238,15 +2100,12 @@ impl<'a> BmpDecoderState<'a> {
             .len()
             .saturating_mul(MAX_RLE_RATIO)
             .max(64 * 1024);
-        if alloc_size > ratio_cap {
-            return Err(at!(BitmapError::InvalidData(
-                "RLE output far exceeds the compressed size (decompression bomb)".into(),
+        if decoded_size > ratio_cap {
+            return Err(at!(BitmapError::invalid_data(
+                "RLE output far exceeds the compressed size (decompression bomb)",
             )));
         }
 
-        // RLE-decompressed output sized from the header-declared `alloc_size`
-        // (already bomb-ratio-guarded above) → default fallible.
-        let mut pixels = alloc_util::alloc_zeroed(self.alloc_pref, true, alloc_size)?;
         let mut line = (self.height - 1) as i32;
         let mut pos = 0usize;
 
@@ -1259,18 +2118,90 @@ impl<'a> BmpDecoderState<'a> {
 
         stop.check().map_err(|r| at!(BitmapError::from(r)))?;
 
+        // Pixels the stream never touches (delta / end-of-line skips) take
+        // palette entry 0.
+        if self.pix_fmt == BmpPixelFormat::Pal8 {
+            let fill = self.palette_rgba(0)?;
+            let bpp = 3 + usize::from(self.is_alpha);
+            let px_bytes = if self.linear { bpp * 4 } else { bpp };
+            for px in out.chunks_exact_mut(px_bytes) {
+                store_color(px, &fill[..bpp], self.linear);
+            }
+        }
+
         if self.depth == 4 {
-            self.decode_rle4(&mut pixels, &mut line, &mut pos)?;
+            self.decode_rle4(out, &mut line, &mut pos)?;
         } else {
-            self.decode_rle8plus(&mut pixels, &mut line, &mut pos, stop)?;
+            self.decode_rle8plus(out, decoded_size, &mut line, &mut pos, stop)?;
+        }
+
+        Ok(())
+    }
+
+    /// The RLE stream ran out while on `line`: only the lines below it (which
+    /// are the output rows after it) were fully decoded.
+    fn truncated_rle(&mut self, line: i32) {
+        self.warn(DecodeWarning::RleTruncated);
+        let first = u32::try_from(line.saturating_add(1)).unwrap_or(0);
+        self.report.valid_rows = Some(first..self.height as u32);
+    }
+
+    /// Palette entry `idx` as RGBA, validated against the declared palette
+    /// size unless permissive.
+    fn palette_rgba(&mut self, idx: u8) -> crate::Result<[u8; 4]> {
+        let idx = usize::from(idx);
+        if idx >= self.palette_numbers {
+            if self.permissiveness != BmpPermissiveness::Permissive {
+                return Err(at!(BitmapError::invalid_data(alloc::format!(
+                    "palette index {idx} out of range (palette has {} entries)",
+                    self.palette_numbers
+                ))));
+            }
+            self.warn(DecodeWarning::PaletteIndexOutOfRange);
         }
+        let entry = self.palette[idx];
+        Ok([entry.red, entry.green, entry.blue, entry.alpha])
+    }
 
-        Ok(pixels)
+    /// Expand one RLE palette index into `out` at flat pixel index `flat`
+    /// (`line * width + pos`; RLE lines count up from the bottom, which is
+    /// also the output row order after the usual bottom-up flip).
+    /// Out-of-bounds writes are dropped, as are pixels a downscale skips.
+    /// Non-paletted RLE output is discarded.
+    fn put_rle_index(&mut self, out: &mut [u8], flat: usize, idx: u8) -> crate::Result<()> {
+        if self.pix_fmt != BmpPixelFormat::Pal8 {
+            return Ok(());
+        }
+        let skipped = self.rle_rows.as_ref().map_or(0, |r| r.start * self.width);
+        let Some(mut flat) = flat.checked_sub(skipped) else {
+            return Ok(());
+        };
+        if self.step > 1 {
+            let (row, col) = (flat / self.width, flat % self.width);
+            if row % self.step != 0 || col % self.step != 0 {
+                return Ok(());
+            }
+            flat = row / self.step * self.width.div_ceil(self.step) + col / self.step;
+        }
+        let bpp = 3 + usize::from(self.is_alpha);
+        let px_bytes = if self.linear { bpp * 4 } else { bpp };
+        let Some(px) = flat
+            .checked_mul(px_bytes)
+            .and_then(|o| out.get_mut(o..o.checked_add(px_bytes)?))
+        else {
+            return Ok(());
+        };
+        let rgba = self.palette_rgba(idx)?;
+        if let Some(counts) = &mut self.palette_counts {
+            counts[usize::from(idx)] += 1;
+        }
+        store_color(px, &rgba[..bpp], self.linear);
+        Ok(())
     }
 
     fn decode_rle4(
         &mut self,
-        pixels: &mut [u8],
+        out: &mut [u8],
         line: &mut i32,
         pos: &mut usize,
     ) -> crate::Result<()> {
@@ -1294,9 +2225,10 @@ impl<'a> BmpDecoderState<'a> {
                     *line -= 1;
                     if *line < 0 {
                         if self.permissiveness == BmpPermissiveness::Permissive {
+                            self.warn(DecodeWarning::RleOverflow);
                             return Ok(());
                         }
-                        return Err(at!(BitmapError::InvalidData("RLE4 line underflow".into())));
+                        return Err(at!(BitmapError::invalid_data("RLE4 line underflow")));
                     }
                     *pos = 0;
                     continue;
@@ -1309,9 +2241,10 @@ impl<'a> BmpDecoderState<'a> {
                     *line -= i32::from(stream_byte);
                     if *line < 0 {
                         if self.permissiveness == BmpPermissiveness::Permissive {
+                            self.warn(DecodeWarning::RleOverflow);
                             return Ok(());
                         }
-                        return Err(at!(BitmapError::InvalidData("RLE4 line underflow".into())));
+                        return Err(at!(BitmapError::invalid_data("RLE4 line underflow")));
                     }
                 } else {
                     let odd_pixel = usize::from(stream_byte & 1);
@@ -1324,9 +2257,7 @@ impl<'a> BmpDecoderState<'a> {
                         }
                         let row_start = *line as usize * self.width;
                         stream_byte = self.bytes.read_u8();
-                        if row_start + *pos < pixels.len() {
-                            pixels[row_start + *pos] = stream_byte >> 4;
-                        }
+                        self.put_rle_index(out, row_start + *pos, stream_byte >> 4)?;
                         *pos += 1;
 
                         if i + 1 == rle_code && odd_pixel > 0 {
@@ -1335,9 +2266,7 @@ impl<'a> BmpDecoderState<'a> {
                         if *pos >= self.width {
                             break;
                         }
-                        if row_start + *pos < pixels.len() {
-                            pixels[row_start + *pos] = stream_byte & 0x0F;
-                        }
+                        self.put_rle_index(out, row_start + *pos, stream_byte & 0x0F)?;
                         *pos += 1;
                     }
                     let _ = self.bytes.skip(usize::from(extra_byte > 0));
@@ -1347,10 +2276,11 @@ impl<'a> BmpDecoderState<'a> {
                     if self.permissiveness == BmpPermissiveness::Permissive {
                         // Consume stream byte, skip this run
                         let _ = self.bytes.read_u8();
+                        self.warn(DecodeWarning::RleRunSkipped);
                         continue;
                     }
-                    return Err(at!(BitmapError::InvalidData(
-                        "RLE4 frame pointer out of bounds".into(),
+                    return Err(at!(BitmapError::invalid_data(
+                        "RLE4 frame pointer out of bounds",
                     )));
                 }
                 stream_byte = self.bytes.read_u8();
@@ -1360,29 +2290,37 @@ impl<'a> BmpDecoderState<'a> {
                     if *pos >= self.width {
                         break;
                     }
-                    let idx = row_start + *pos;
-                    if idx < pixels.len() {
-                        if (i & 1) == 0 {
-                            pixels[idx] = stream_byte >> 4;
-                        } else {
-                            pixels[idx] = stream_byte & 0x0F;
-                        }
-                    }
+                    let idx = if (i & 1) == 0 {
+                        stream_byte >> 4
+                    } else {
+                        stream_byte & 0x0F
+                    };
+                    self.put_rle_index(out, row_start + *pos, idx)?;
                     *pos += 1;
                 }
             }
         }
+        if self.bytes.eof() {
+            self.truncated_rle(*line);
+        } else if *pos > self.width {
+            self.warn(DecodeWarning::RleRunSkipped);
+        }
         Ok(())
     }
 
+    /// RLE8 (and the non-standard 16/32-bit RLE variants, whose pixels are
+    /// consumed but not output). `decoded_len` is the size of the decoded
+    /// stream in bytes and bounds run positions.
     fn decode_rle8plus(
         &mut self,
-        pixels: &mut [u8],
+        out: &mut [u8],
+        decoded_len: usize,
         line: &mut i32,
         pos: &mut usize,
         stop: &dyn Stop,
     ) -> crate::Result<()> {
         let mut check_counter = 0u32;
+        let byte_depth = usize::from(self.depth >> 3);
 
         while !self.bytes.eof() {
             check_counter += 1;
@@ -1397,13 +2335,15 @@ impl<'a> BmpDecoderState<'a> {
                     // End of line
                     *line -= 1;
                     if *line < 0 {
-                        if self.bytes.get_u16_be() == 1
-                            || self.permissiveness == BmpPermissiveness::Permissive
-                        {
+                        if self.bytes.get_u16_be() == 1 {
+                            return Ok(());
+                        }
+                        if self.permissiveness == BmpPermissiveness::Permissive {
+                            self.warn(DecodeWarning::RleOverflow);
                             return Ok(());
                         }
-                        return Err(at!(BitmapError::InvalidData(
-                            "RLE line beyond picture bounds".into(),
+                        return Err(at!(BitmapError::invalid_data(
+                            "RLE line beyond picture bounds",
                         )));
                     }
                     *pos = 0;
@@ -1420,21 +2360,21 @@ impl<'a> BmpDecoderState<'a> {
                         Some(v) => v,
                         None => {
                             if self.permissiveness == BmpPermissiveness::Permissive {
+                                self.warn(DecodeWarning::RleRunSkipped);
                                 return Ok(());
                             }
-                            return Err(at!(BitmapError::InvalidData(
-                                "RLE delta column overflow".into(),
+                            return Err(at!(BitmapError::invalid_data(
+                                "RLE delta column overflow",
                             )));
                         }
                     };
                     *line -= i32::from(dy);
                     if *line < 0 {
                         if self.permissiveness == BmpPermissiveness::Permissive {
+                            self.warn(DecodeWarning::RleOverflow);
                             return Ok(());
                         }
-                        return Err(at!(BitmapError::InvalidData(
-                            "RLE delta line underflow".into()
-                        )));
+                        return Err(at!(BitmapError::invalid_data("RLE delta line underflow")));
                     }
                     continue;
                 }
@@ -1442,135 +2382,72 @@ impl<'a> BmpDecoderState<'a> {
                 // Absolute mode
                 let row_start = *line as usize * self.width;
                 let output_slice_start = row_start + *pos;
+                let size = usize::from(p2) * byte_depth;
 
-                if output_slice_start + usize::from(p2) * usize::from(self.depth >> 3)
-                    > pixels.len()
-                {
+                if output_slice_start + size > decoded_len {
                     // Skip invalid data
-                    let _ = self.bytes.skip(2 * usize::from(self.depth >> 3));
+                    let _ = self.bytes.skip(2 * byte_depth);
+                    self.warn(DecodeWarning::RleRunSkipped);
                     continue;
                 }
 
                 match self.depth {
                     8 | 24 => {
-                        let size = usize::from(p2) * usize::from(self.depth >> 3);
-                        if output_slice_start + size <= pixels.len() {
-                            self.bytes.read_exact_bytes(
-                                &mut pixels[output_slice_start..output_slice_start + size],
-                            )?;
+                        // ≤ 255 pixels × 3 bytes per absolute run.
+                        let mut run = [0u8; 255 * 3];
+                        let run = &mut run[..size];
+                        self.bytes.read_exact_bytes(run)?;
+                        if self.depth == 8 {
+                            for (i, &idx) in run.iter().enumerate() {
+                                self.put_rle_index(out, output_slice_start + i, idx)?;
+                            }
                         }
                         *pos += size;
                         if self.depth == 8 && (p2 & 1) == 1 {
                             let _ = self.bytes.skip(1);
                         }
                     }
-                    16 => {
-                        for chunk in pixels[output_slice_start..]
-                            .chunks_exact_mut(2)
-                            .take(usize::from(p2))
-                        {
-                            chunk[0] = self.bytes.read_u8();
-                            chunk[1] = self.bytes.read_u8();
+                    16 | 32 => {
+                        for _ in 0..size {
+                            let _ = self.bytes.read_u8();
                         }
-                        *pos += 2 * usize::from(p2);
-                    }
-                    32 => {
-                        for chunk in pixels[output_slice_start..]
-                            .chunks_exact_mut(4)
-                            .take(usize::from(p2))
-                        {
-                            chunk[0] = self.bytes.read_u8();
-                            chunk[1] = self.bytes.read_u8();
-                            chunk[2] = self.bytes.read_u8();
-                            chunk[3] = self.bytes.read_u8();
-                        }
-                        *pos += 4 * usize::from(p2);
+                        *pos += size;
                     }
                     _ => {}
                 }
             } else {
                 // Run of pixels
                 let row_start = *line as usize * self.width;
-                let byte_depth = usize::from(self.depth >> 3);
 
-                if *pos + (usize::from(p1) * byte_depth) > pixels.len().saturating_sub(row_start) {
+                if *pos + (usize::from(p1) * byte_depth) > decoded_len.saturating_sub(row_start) {
                     if self.permissiveness == BmpPermissiveness::Permissive {
                         // Clamp: skip this run, consume the pixel data from stream
-                        match self.depth {
-                            8 => {
-                                let _ = self.bytes.read_u8();
-                            }
-                            16 => {
-                                let _ = self.bytes.read_u8();
-                                let _ = self.bytes.read_u8();
-                            }
-                            24 => {
-                                for _ in 0..3 {
-                                    let _ = self.bytes.read_u8();
-                                }
-                            }
-                            32 => {
-                                for _ in 0..4 {
-                                    let _ = self.bytes.read_u8();
-                                }
-                            }
-                            _ => {}
+                        for _ in 0..byte_depth {
+                            let _ = self.bytes.read_u8();
                         }
+                        self.warn(DecodeWarning::RleRunSkipped);
                         continue;
                     }
-                    return Err(at!(BitmapError::InvalidData("RLE position overrun".into())));
+                    return Err(at!(BitmapError::invalid_data("RLE position overrun")));
                 }
 
                 let output_start = row_start + *pos;
-                let mut pix = [0u8; 4];
 
-                match self.depth {
-                    8 => {
-                        pix[0] = self.bytes.read_u8();
-                        let end = (output_start + usize::from(p1)).min(pixels.len());
-                        pixels[output_start..end].fill(pix[0]);
-                        *pos += usize::from(p1);
-                    }
-                    16 => {
-                        pix[0] = self.bytes.read_u8();
-                        pix[1] = self.bytes.read_u8();
-                        for chunk in pixels[output_start..]
-                            .chunks_exact_mut(2)
-                            .take(usize::from(p1))
-                        {
-                            chunk[0..2].copy_from_slice(&pix[..2]);
-                        }
-                        *pos += 2 * usize::from(p1);
-                    }
-                    24 => {
-                        pix[0] = self.bytes.read_u8();
-                        pix[1] = self.bytes.read_u8();
-                        pix[2] = self.bytes.read_u8();
-                        for chunk in pixels[output_start..]
-                            .chunks_exact_mut(3)
-                            .take(usize::from(p1))
-                        {
-                            chunk[0..3].copy_from_slice(&pix[..3]);
-                        }
-                        *pos += 3 * usize::from(p1);
+                if self.depth == 8 {
+                    let idx = self.bytes.read_u8();
+                    let end = (output_start + usize::from(p1)).min(decoded_len);
+                    for flat in output_start..end {
+                        self.put_rle_index(out, flat, idx)?;
                     }
-                    32 => {
-                        pix[0] = self.bytes.read_u8();
-                        pix[1] = self.bytes.read_u8();
-                        pix[2] = self.bytes.read_u8();
-                        pix[3] = self.bytes.read_u8();
-                        for chunk in pixels[output_start..]
-                            .chunks_exact_mut(4)
-                            .take(usize::from(p1))
-                        {
-                            chunk[0..4].copy_from_slice(&pix[..4]);
-                        }
-                        *pos += 4 * usize::from(p1);
+                } else {
+                    for _ in 0..byte_depth {
+                        let _ = self.bytes.read_u8();
                     }
-                    _ => {}
                 }
+                *pos += usize::from(p1) * byte_depth;
             }
         }
+        self.truncated_rle(*line);
         Ok(())
     }
 }
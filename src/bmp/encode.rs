@@ -1,20 +1,456 @@
-//! BMP encoder: uncompressed 24-bit and 32-bit BMP.
+//! BMP encoder: uncompressed 16-bit (RGB555 / RGB565), 24-bit, 32-bit and
+//! 64-bit BMP, and uncompressed or RLE8/RLE4-compressed paletted BMP.
 
+use super::decode::{BmpCompression, BmpHeaderVariant};
+use crate::alpha::{self, AlphaMode};
 use crate::error::BitmapError;
 use crate::pixel::PixelLayout;
 use alloc::vec::Vec;
 use enough::Stop;
 use whereat::at;
 
+/// Color space type (`bV4CSType`) written to V4/V5 headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum BmpColorSpace {
+    /// `LCS_CALIBRATED_RGB` with all-zero endpoints and gamma — in effect
+    /// untagged; readers fall back to their own assumption.
+    #[default]
+    CalibratedRgb,
+    /// `LCS_sRGB`: pixels are sRGB.
+    Srgb,
+    /// `LCS_WINDOWS_COLOR_SPACE`: the system default space (sRGB on
+    /// Windows).
+    WindowsColorSpace,
+}
+
+impl BmpColorSpace {
+    fn cs_type(self) -> u32 {
+        match self {
+            Self::CalibratedRgb => 0,
+            Self::Srgb => 0x7352_4742,              // 'sRGB'
+            Self::WindowsColorSpace => 0x5769_6E20, // 'Win '
+        }
+    }
+}
+
+/// Options for BMP encoding with [`crate::encode_bmp_with`].
+///
+/// Bit depth, compression, header version, row order, resolution, palette
+/// and ICC profile in one place; the flat `encode_bmp*` functions are
+/// shorthands for particular settings.
+///
+/// ```
+/// use zenbitmaps::{BmpCompression, BmpEncodeOptions};
+///
+/// let options = BmpEncodeOptions::new()
+///     .with_bit_depth(16)
+///     .with_compression(BmpCompression::Bitfields)
+///     .with_top_down(true);
+/// assert_eq!(options.bit_depth(), Some(16));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BmpEncodeOptions<'a> {
+    bit_depth: Option<u16>,
+    compression: BmpCompression,
+    palette: Option<&'a [[u8; 3]]>,
+    alpha: bool,
+    top_down: bool,
+    header: BmpHeaderVariant,
+    color_space: BmpColorSpace,
+    pixels_per_meter: (u32, u32),
+    icc_profile: Option<&'a [u8]>,
+    alpha_mode: AlphaMode,
+    background: Option<[u8; 3]>,
+}
+
+impl Default for BmpEncodeOptions<'_> {
+    fn default() -> Self {
+        Self {
+            bit_depth: None,
+            compression: BmpCompression::Rgb,
+            palette: None,
+            alpha: false,
+            top_down: false,
+            header: BmpHeaderVariant::Info,
+            color_space: BmpColorSpace::CalibratedRgb,
+            pixels_per_meter: (2835, 2835), // 72 DPI
+            icc_profile: None,
+            alpha_mode: AlphaMode::Straight,
+            background: None,
+        }
+    }
+}
+
+/// Bit depths are drawn from those the encoder writes so most inputs get
+/// past validation; palettes are left to the quantizer.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for BmpEncodeOptions<'a> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        const DEPTHS: [Option<u16>; 9] = [
+            None,
+            Some(1),
+            Some(2),
+            Some(4),
+            Some(8),
+            Some(16),
+            Some(24),
+            Some(32),
+            Some(64),
+        ];
+        Ok(Self {
+            bit_depth: *u.choose(&DEPTHS)?,
+            compression: u.arbitrary()?,
+            palette: None,
+            alpha: u.arbitrary()?,
+            top_down: u.arbitrary()?,
+            header: u.arbitrary()?,
+            color_space: u.arbitrary()?,
+            pixels_per_meter: u.arbitrary()?,
+            icc_profile: u.arbitrary()?,
+            alpha_mode: u.arbitrary()?,
+            background: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> BmpEncodeOptions<'a> {
+    /// Default options: 24-bit (8-bit for `Gray8` input), bottom-up,
+    /// `BITMAPINFOHEADER`, 72 DPI.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the bits per pixel: 1, 2, 4 or 8 (paletted), 16, 24, 32 or 64.
+    ///
+    /// Without it the depth follows the other options: 8 with a palette or
+    /// RLE8, 4 with RLE4, 64 for `Rgba16` input, 32 with
+    /// [`with_alpha`](Self::with_alpha), 8 (grayscale, no color table) for
+    /// `Gray8` input, else 24.
+    ///
+    /// Paletted depths use [`with_palette`](Self::with_palette), mapping
+    /// each pixel to its nearest entry, or else a palette quantized from
+    /// the image (exact when it has few enough colors). 16-bit output is
+    /// RGB555, or RGB565 with [`BmpCompression::Bitfields`]. 32-bit output
+    /// stores the layout's alpha (255 without one). 64-bit output takes
+    /// `Rgba16` only and stores the de facto (WIC) format: s2.13 fixed-point
+    /// channels, color in linear light. That keeps 13 bits per channel in
+    /// linear light, so 16-bit sRGB values round-trip closely but not
+    /// exactly — finest in the highlights, coarsest in deep shadows.
+    pub fn with_bit_depth(mut self, bit_depth: u16) -> Self {
+        self.bit_depth = Some(bit_depth);
+        self
+    }
+
+    /// Set the compression: [`BmpCompression::Rgb`] (default),
+    /// [`Bitfields`](BmpCompression::Bitfields) for 16/32-bit output,
+    /// [`Rle8`](BmpCompression::Rle8) for 8-bit or
+    /// [`Rle4`](BmpCompression::Rle4) for 4-bit output. RLE bitmaps are
+    /// always bottom-up.
+    pub fn with_compression(mut self, compression: BmpCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Use `palette` (1 to `1 << bit_depth` RGB entries) as the color table
+    /// of paletted output, verbatim and in order.
+    pub fn with_palette(mut self, palette: &'a [[u8; 3]]) -> Self {
+        self.palette = Some(palette);
+        self
+    }
+
+    /// Without an explicit bit depth, write 32-bit pixels with alpha, as
+    /// [`crate::encode_bmp_rgba`] does. Default `false`.
+    pub fn with_alpha(mut self, alpha: bool) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Store rows top-down, signalled by a negative height in the header,
+    /// so top-down buffers are written in memory order. Default `false`
+    /// (bottom-up, the conventional order).
+    pub fn with_top_down(mut self, top_down: bool) -> Self {
+        self.top_down = top_down;
+        self
+    }
+
+    /// Select the info header: [`BmpHeaderVariant::Info`] (the 40-byte
+    /// `BITMAPINFOHEADER`, default), [`BmpHeaderVariant::V4`],
+    /// [`BmpHeaderVariant::V5`] or [`BmpHeaderVariant::Core`]. With a V4/V5
+    /// header, 32-bit output is written as `BI_BITFIELDS` with an explicit
+    /// alpha mask, so alpha is defined by the spec rather than left in the
+    /// reserved byte. Other variants are rejected at encode time.
+    ///
+    /// `Core` writes the 12-byte OS/2 1.x `BITMAPCOREHEADER`, with 3-byte
+    /// color table entries. It can only hold what OS/2 1.x could: 1/4/8-bit
+    /// paletted or 24-bit pixels, uncompressed and bottom-up, at most 65535
+    /// pixels on a side, with no resolution, color space or ICC profile
+    /// (resolution is dropped; the others are rejected). 8-bit grayscale
+    /// gets an explicit gray palette.
+    pub fn with_header(mut self, header: BmpHeaderVariant) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Tag the pixels with a color space. Anything but the default
+    /// [`BmpColorSpace::CalibratedRgb`] needs at least a V4 header, which is
+    /// used if [`with_header`](Self::with_header) asked for less. Ignored
+    /// when an ICC profile is embedded.
+    pub fn with_color_space(mut self, color_space: BmpColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Set the resolution written to `biXPelsPerMeter` /
+    /// `biYPelsPerMeter`. Default 2835 × 2835 (72 DPI).
+    pub fn with_pixels_per_meter(mut self, x: u32, y: u32) -> Self {
+        self.pixels_per_meter = (x, y);
+        self
+    }
+
+    /// Set the resolution in dots per inch, rounded to whole pixels per
+    /// meter (so it reads back to within 0.013 DPI). Negative or non-finite
+    /// values write 0, meaning "unspecified".
+    pub fn with_dpi(self, x: f32, y: f32) -> Self {
+        let to_ppm = |dpi: f32| {
+            let ppm = dpi / 0.0254;
+            if ppm.is_finite() && ppm > 0.0 {
+                // `as` saturates at u32::MAX.
+                (ppm + 0.5) as u32
+            } else {
+                0
+            }
+        };
+        self.with_pixels_per_meter(to_ppm(x), to_ppm(y))
+    }
+
+    /// Embed an ICC profile. The file gets a `BITMAPV5HEADER` with color
+    /// space `PROFILE_EMBEDDED` whatever [`with_header`](Self::with_header)
+    /// selects, and the profile bytes are stored after the pixel data.
+    pub fn with_icc_profile(mut self, profile: &'a [u8]) -> Self {
+        self.icc_profile = Some(profile);
+        self
+    }
+
+    /// Whether the input's color is premultiplied by alpha. BMP stores
+    /// straight alpha, so [`AlphaMode::Premultiplied`] input is
+    /// unpremultiplied before writing. Default [`AlphaMode::Straight`].
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Blend input with alpha over this opaque sRGB color when the output
+    /// has no alpha (24-bit, 16-bit and paletted), instead of dropping the
+    /// alpha as is. 32- and 64-bit output keeps alpha and ignores it.
+    /// Default none.
+    pub fn with_background(mut self, background: [u8; 3]) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Requested bits per pixel (`None`: chosen from the other options).
+    pub fn bit_depth(&self) -> Option<u16> {
+        self.bit_depth
+    }
+
+    /// Compression.
+    pub fn compression(&self) -> BmpCompression {
+        self.compression
+    }
+
+    /// Color table for paletted output, if supplied.
+    pub fn palette(&self) -> Option<&'a [[u8; 3]]> {
+        self.palette
+    }
+
+    /// Whether 32-bit pixels with alpha are written.
+    pub fn alpha(&self) -> bool {
+        self.alpha
+    }
+
+    /// Whether rows are stored top-down.
+    pub fn top_down(&self) -> bool {
+        self.top_down
+    }
+
+    /// Info header variant requested.
+    pub fn header(&self) -> BmpHeaderVariant {
+        self.header
+    }
+
+    /// Color space tag.
+    pub fn color_space(&self) -> BmpColorSpace {
+        self.color_space
+    }
+
+    /// Horizontal and vertical resolution in pixels per meter.
+    pub fn pixels_per_meter(&self) -> (u32, u32) {
+        self.pixels_per_meter
+    }
+
+    /// ICC profile to embed, if any.
+    pub fn icc_profile(&self) -> Option<&'a [u8]> {
+        self.icc_profile
+    }
+
+    /// Alpha state of the input.
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    /// Background color for output without alpha, if set.
+    pub fn background(&self) -> Option<[u8; 3]> {
+        self.background
+    }
+
+    /// Info header variant actually written: an embedded profile needs V5,
+    /// a color space tag V4.
+    fn written_header(&self) -> BmpHeaderVariant {
+        if self.icc_profile.is_some() {
+            BmpHeaderVariant::V5
+        } else if self.color_space != BmpColorSpace::CalibratedRgb
+            && self.header == BmpHeaderVariant::Info
+        {
+            BmpHeaderVariant::V4
+        } else {
+            self.header
+        }
+    }
+
+    /// Bits per pixel written for `layout`, checked against the compression,
+    /// row order and palette.
+    fn resolve_depth(&self, layout: PixelLayout) -> crate::Result<u16> {
+        if !matches!(
+            self.header,
+            BmpHeaderVariant::Core
+                | BmpHeaderVariant::Info
+                | BmpHeaderVariant::V4
+                | BmpHeaderVariant::V5
+        ) {
+            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "BMP encode: cannot write a {:?} header",
+                self.header
+            ))));
+        }
+        if self.header == BmpHeaderVariant::Core
+            && (self.icc_profile.is_some() || self.color_space != BmpColorSpace::CalibratedRgb)
+        {
+            return Err(at!(BitmapError::UnsupportedVariant(
+                "BMP encode: a Core header has no color space or ICC profile".into()
+            )));
+        }
+        let depth = self.bit_depth.unwrap_or(match self.compression {
+            BmpCompression::Rle8 => 8,
+            BmpCompression::Rle4 => 4,
+            _ if self.palette.is_some() => 8,
+            _ if layout == PixelLayout::Rgba16 => 64,
+            _ if self.alpha => 32,
+            _ if layout == PixelLayout::Gray8 => 8,
+            _ => 24,
+        });
+        let supported = match (depth, self.compression) {
+            _ if self.header == BmpHeaderVariant::Core => {
+                matches!(depth, 1 | 4 | 8 | 24)
+                    && self.compression == BmpCompression::Rgb
+                    && !self.top_down
+                    && (depth != 24 || self.palette.is_none())
+            }
+            (1 | 2 | 4 | 8, BmpCompression::Rgb) => true,
+            (8, BmpCompression::Rle8) | (4, BmpCompression::Rle4) => !self.top_down,
+            (16 | 32, BmpCompression::Rgb | BmpCompression::Bitfields) => self.palette.is_none(),
+            (24 | 64, BmpCompression::Rgb) => self.palette.is_none(),
+            _ => false,
+        };
+        if !supported {
+            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "BMP encode: cannot write {depth}-bit {:?}{}{}{}",
+                self.compression,
+                if self.header == BmpHeaderVariant::Core {
+                    " with a Core header"
+                } else {
+                    ""
+                },
+                if self.top_down { " top-down" } else { "" },
+                if self.palette.is_some() {
+                    " with a palette"
+                } else {
+                    ""
+                },
+            ))));
+        }
+        Ok(depth)
+    }
+
+    /// Source rows in the order they are stored.
+    fn stored_rows(&self, height: usize) -> impl Iterator<Item = usize> {
+        let top_down = self.top_down;
+        (0..height).map(move |i| if top_down { i } else { height - 1 - i })
+    }
+}
+
+/// Receives encoded bytes as they are produced: called with the output
+/// buffer every 16 rows and once at the end, it may consume (and clear) it.
+pub(crate) type Drain<'d> = dyn FnMut(&mut Vec<u8>) -> crate::Result<()> + 'd;
+
 /// Encode pixels to BMP format.
 pub(crate) fn encode_bmp(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
-    alpha: bool,
+    options: &BmpEncodeOptions<'_>,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(whole_file_capacity(pixels, width, height, layout, options));
+    encode_bmp_into(
+        pixels,
+        width,
+        height,
+        layout,
+        options,
+        &mut out,
+        &mut |_| Ok(()),
+        stop,
+    )?;
+    Ok(out)
+}
+
+/// Bytes to reserve for encoding the whole file into one buffer: its
+/// [`encoded_size`] (an upper bound for paletted output), or 0 when
+/// `pixels` is too short for the image, so that a bad call fails before
+/// anything large is allocated.
+pub(crate) fn whole_file_capacity(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+) -> usize {
+    let needed = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(layout.bytes_per_pixel()));
+    if needed.is_none_or(|needed| pixels.len() < needed) {
+        return 0;
+    }
+    encoded_size(width, height, layout, options).unwrap_or(0)
+}
+
+/// Encode pixels to BMP format, appending to `out`. Direct-color rows are
+/// handed to `drain` as they are written; paletted output is assembled
+/// whole first, since its palette and RLE size depend on every row.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encode_bmp_into(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut Drain<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
     let w = width as usize;
     let h = height as usize;
     let expected = w
@@ -27,20 +463,272 @@ pub(crate) fn encode_bmp(
             actual: pixels.len(),
         }));
     }
+    if options.alpha_mode == AlphaMode::Premultiplied && layout.has_alpha() {
+        let straight = alpha::straight(pixels, width, height, layout, options.alpha_mode, stop)?;
+        let options = options.with_alpha_mode(AlphaMode::Straight);
+        return encode_bmp_into(&straight, width, height, layout, &options, out, drain, stop);
+    }
 
+    let depth = options.resolve_depth(layout)?;
+    if options.background.is_some() && layout.has_alpha() && !matches!(depth, 32 | 64) {
+        let flat = alpha::flattened(pixels, width, height, layout, options.background, stop)?;
+        let options = BmpEncodeOptions {
+            background: None,
+            ..*options
+        };
+        return encode_bmp_into(&flat, width, height, layout, &options, out, drain, stop);
+    }
     stop.check().map_err(|r| at!(BitmapError::from(r)))?;
 
-    if layout == PixelLayout::Gray8 && !alpha {
-        return encode_8bit_gray(pixels, width, height, w, h, stop);
+    match depth {
+        8 if layout == PixelLayout::Gray8
+            && options.palette.is_none()
+            && options.compression == BmpCompression::Rgb
+            && options.header != BmpHeaderVariant::Core =>
+        {
+            encode_8bit_gray(pixels, width, height, w, h, options, out, drain, stop)?
+        }
+        1 | 2 | 4 | 8 => {
+            let colors = (0..w * h)
+                .map(|i| get_rgb(pixels, i, layout).map(|(r, g, b)| [r, g, b]))
+                .collect::<crate::Result<Vec<_>>>()?;
+            let (palette, indices) = match options.palette {
+                Some(palette) => (palette.to_vec(), map_to_palette(&colors, palette, stop)?),
+                None => super::quantize::quantize(&colors, 1 << depth, stop)?,
+            };
+            write_indexed(out, &indices, &palette, width, height, depth, options, stop)?
+        }
+        16 => encode_16bit(
+            pixels, width, height, w, h, layout, options, out, drain, stop,
+        )?,
+        64 => encode_64bit(
+            pixels, width, height, w, h, layout, options, out, drain, stop,
+        )?,
+        32 => encode_32bit(
+            pixels, width, height, w, h, layout, options, out, drain, stop,
+        )?,
+        _ => encode_24bit(
+            pixels, width, height, w, h, layout, options, out, drain, stop,
+        )?,
+    }
+    // Accounted for in the header (size, profile offset).
+    if let Some(profile) = options.icc_profile {
+        out.extend_from_slice(profile);
     }
+    drain(out)
+}
 
-    if alpha {
-        encode_32bit(pixels, width, height, w, h, layout, stop)
+/// Size in bytes of the file [`encode_bmp`] writes: exact, except that
+/// RLE output is bounded by its worst case (two bytes per pixel plus the
+/// end-of-line escapes) and a quantized palette by its largest size.
+pub(crate) fn encoded_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+) -> crate::Result<usize> {
+    let depth = options.resolve_depth(layout)?;
+    let supported = match depth {
+        64 => layout == PixelLayout::Rgba16,
+        _ => matches!(
+            layout,
+            PixelLayout::Gray8
+                | PixelLayout::Rgb8
+                | PixelLayout::Rgba8
+                | PixelLayout::Bgr8
+                | PixelLayout::Bgra8
+                | PixelLayout::Bgrx8
+                | PixelLayout::Rgbx8
+        ),
+    };
+    if !supported {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "BMP encode: cannot write {layout:?} as {depth}-bit"
+        ))));
+    }
+
+    let too_large = || at!(BitmapError::DimensionsTooLarge { width, height });
+    let (w, h) = (width as usize, height as usize);
+    let header = options.written_header();
+    let info_size = match header {
+        BmpHeaderVariant::Core => 12,
+        BmpHeaderVariant::V4 => 108,
+        BmpHeaderVariant::V5 => 124,
+        _ => 40,
+    };
+    let has_masks = match depth {
+        16 => options.compression == BmpCompression::Bitfields,
+        32 => {
+            matches!(header, BmpHeaderVariant::V4 | BmpHeaderVariant::V5)
+                || options.compression == BmpCompression::Bitfields
+        }
+        _ => false,
+    };
+    let trailing_masks = if info_size == 40 && has_masks { 12 } else { 0 };
+    let gray = depth == 8
+        && layout == PixelLayout::Gray8
+        && options.palette.is_none()
+        && options.compression == BmpCompression::Rgb
+        && header != BmpHeaderVariant::Core;
+    let palette_len = match options.palette {
+        _ if depth > 8 || gray => 0,
+        Some(palette) => palette.len(),
+        None => (1usize << depth).min(w.saturating_mul(h)),
+    };
+    let entry_size = if info_size == 12 { 3 } else { 4 };
+
+    let pixel_data = if matches!(
+        options.compression,
+        BmpCompression::Rle8 | BmpCompression::Rle4
+    ) {
+        w.checked_mul(2)
+            .and_then(|row| row.checked_add(2))
+            .and_then(|row| row.checked_mul(h))
     } else {
-        encode_24bit(pixels, width, height, w, h, layout, stop)
+        w.checked_mul(usize::from(depth))
+            .and_then(|bits| bits.checked_add(31))
+            .map(|bits| bits / 32 * 4)
+            .and_then(|stride| stride.checked_mul(h))
+    }
+    .ok_or_else(too_large)?;
+    let size = pixel_data
+        .checked_add(14 + info_size + trailing_masks + palette_len * entry_size)
+        .and_then(|size| size.checked_add(options.icc_profile.map_or(0, <[u8]>::len)))
+        .ok_or_else(too_large)?;
+    // The same checks the encoder applies to the header fields.
+    if u32::try_from(size).is_err()
+        || (info_size == 12 && (width > u32::from(u16::MAX) || height > u32::from(u16::MAX)))
+    {
+        return Err(too_large());
     }
+    Ok(size)
 }
 
+/// Row-at-a-time BMP encoder.
+///
+/// Accepts rows top to bottom in any batch size, then writes the file on
+/// [`finish()`](Self::finish) with the settings of a [`BmpEncodeOptions`].
+/// The height need not be known up front. BMP stores rows bottom-up by
+/// default, so the last row pushed is the first one written: rows are
+/// buffered until `finish()`, which also lets paletted output quantize the
+/// whole image.
+///
+/// ```
+/// use zenbitmaps::{BmpEncodeOptions, BmpRowEncoder, PixelLayout};
+/// use enough::Unstoppable;
+///
+/// let options = BmpEncodeOptions::new();
+/// let mut encoder = BmpRowEncoder::new(2, PixelLayout::Rgb8, &options)?;
+/// encoder.push_rows(&[255, 0, 0, 0, 255, 0])?;
+/// encoder.push_rows(&[0, 0, 255, 9, 9, 9])?;
+/// assert_eq!(encoder.rows(), 2);
+/// let bmp = encoder.finish(Unstoppable)?;
+/// assert_eq!(&bmp[..2], b"BM");
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BmpRowEncoder<'a> {
+    width: u32,
+    layout: PixelLayout,
+    options: BmpEncodeOptions<'a>,
+    pixels: Vec<u8>,
+    rows: u32,
+}
+
+impl<'a> BmpRowEncoder<'a> {
+    /// Start an image `width` pixels wide whose rows are `layout` pixels.
+    ///
+    /// Fails up front, like [`encode_bmp_with()`](crate::encode_bmp_with),
+    /// if `options` cannot be written for `layout`.
+    pub fn new(
+        width: u32,
+        layout: PixelLayout,
+        options: &BmpEncodeOptions<'a>,
+    ) -> crate::Result<Self> {
+        if width == 0 {
+            return Err(at!(BitmapError::invalid_data("BMP width is zero")));
+        }
+        if i32::try_from(width).is_err() {
+            return Err(at!(BitmapError::DimensionsTooLarge { width, height: 0 }));
+        }
+        options.resolve_depth(layout)?;
+        Ok(Self {
+            width,
+            layout,
+            options: *options,
+            pixels: Vec::new(),
+            rows: 0,
+        })
+    }
+
+    /// Append one or more rows, top to bottom. `rows` must hold a whole
+    /// number of rows, each `width * layout.bytes_per_pixel()` bytes.
+    pub fn push_rows(&mut self, rows: &[u8]) -> crate::Result<()> {
+        let row_bytes = self.width as usize * self.layout.bytes_per_pixel();
+        if !rows.len().is_multiple_of(row_bytes) {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "BMP push_rows: {} bytes is not a multiple of the {row_bytes}-byte row",
+                rows.len()
+            ))));
+        }
+        let total = u32::try_from(rows.len() / row_bytes)
+            .ok()
+            .and_then(|n| self.rows.checked_add(n))
+            .filter(|&total| i32::try_from(total).is_ok())
+            .ok_or_else(|| {
+                at!(BitmapError::DimensionsTooLarge {
+                    width: self.width,
+                    height: u32::MAX,
+                })
+            })?;
+        self.pixels.extend_from_slice(rows);
+        self.rows = total;
+        Ok(())
+    }
+
+    /// Image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Layout of the pushed rows.
+    pub fn layout(&self) -> PixelLayout {
+        self.layout
+    }
+
+    /// Rows pushed so far.
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Embed `profile` in the finished file, as
+    /// [`BmpEncodeOptions::with_icc_profile`] does. Lets a caller whose
+    /// profile lives shorter than the encoder add it last.
+    #[cfg(feature = "zencodec")]
+    pub(crate) fn with_icc_profile(mut self, profile: &'a [u8]) -> Self {
+        self.options = self.options.with_icc_profile(profile);
+        self
+    }
+
+    /// Write the BMP file, `rows()` pixels tall.
+    pub fn finish(self, stop: impl Stop) -> crate::Result<Vec<u8>> {
+        if self.rows == 0 {
+            return Err(at!(BitmapError::invalid_data(
+                "BMP finish: no rows were pushed"
+            )));
+        }
+        encode_bmp(
+            &self.pixels,
+            self.width,
+            self.rows,
+            self.layout,
+            &self.options,
+            &stop,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn encode_24bit(
     pixels: &[u8],
     width: u32,
@@ -48,8 +736,11 @@ fn encode_24bit(
     w: usize,
     h: usize,
     layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut Drain<'_>,
     stop: &dyn Stop,
-) -> crate::Result<Vec<u8>> {
+) -> crate::Result<()> {
     let row_stride = w
         .checked_mul(3)
         .and_then(|r| r.checked_add(3))
@@ -58,18 +749,14 @@ fn encode_24bit(
     let pixel_data_size = row_stride
         .checked_mul(h)
         .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
-    let file_size = pixel_data_size
-        .checked_add(54)
-        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
-
-    let mut out = Vec::with_capacity(file_size);
-    write_bmp_header(&mut out, file_size, pixel_data_size, width, height, 24);
+    write_bmp_header(out, pixel_data_size, width, height, 24, None, &[], options)?;
 
     let pad_bytes = row_stride - w * 3;
     let is_bgr_native = matches!(layout, PixelLayout::Bgr8);
     let src_bpp = layout.bytes_per_pixel();
-    for row in (0..h).rev() {
+    for row in options.stored_rows(h) {
         if row % 16 == 0 {
+            drain(out)?;
             stop.check().map_err(|r| at!(BitmapError::from(r)))?;
         }
         if is_bgr_native {
@@ -87,9 +774,10 @@ fn encode_24bit(
         out.extend(core::iter::repeat_n(0u8, pad_bytes));
     }
 
-    Ok(out)
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn encode_32bit(
     pixels: &[u8],
     width: u32,
@@ -97,26 +785,45 @@ fn encode_32bit(
     w: usize,
     h: usize,
     layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut Drain<'_>,
     stop: &dyn Stop,
-) -> crate::Result<Vec<u8>> {
+) -> crate::Result<()> {
     let row_stride = w
         .checked_mul(4)
         .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
     let pixel_data_size = row_stride
         .checked_mul(h)
         .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
-    let file_size = pixel_data_size
-        .checked_add(54)
-        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
-
-    let mut out = Vec::with_capacity(file_size);
-    write_bmp_header(&mut out, file_size, pixel_data_size, width, height, 32);
+    // Alpha is only declared where the header can carry its mask; a
+    // BITMAPINFOHEADER with BI_BITFIELDS gets the RGB masks alone.
+    let alpha_masks = match options.written_header() {
+        BmpHeaderVariant::V4 | BmpHeaderVariant::V5 => {
+            Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000])
+        }
+        _ if options.compression == BmpCompression::Bitfields => {
+            Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0])
+        }
+        _ => None,
+    };
+    write_bmp_header(
+        out,
+        pixel_data_size,
+        width,
+        height,
+        32,
+        alpha_masks,
+        &[],
+        options,
+    )?;
 
     // Only Bgra8 can use the direct copy fast path. Bgrx8 must go through
     // get_rgba() which forces the padding byte to 255 (opaque).
     let is_bgra_native = matches!(layout, PixelLayout::Bgra8);
-    for row in (0..h).rev() {
+    for row in options.stored_rows(h) {
         if row % 16 == 0 {
+            drain(out)?;
             stop.check().map_err(|r| at!(BitmapError::from(r)))?;
         }
         if is_bgra_native {
@@ -134,57 +841,138 @@ fn encode_32bit(
         }
     }
 
-    Ok(out)
+    Ok(())
 }
 
-fn encode_8bit_gray(
+/// 64-bit pixels from `Rgba16`: B, G, R, A as little-endian s2.13 fixed
+/// point, color in linear light (decoded from sRGB), alpha straight.
+#[allow(clippy::too_many_arguments)]
+fn encode_64bit(
     pixels: &[u8],
     width: u32,
     height: u32,
     w: usize,
     h: usize,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut Drain<'_>,
     stop: &dyn Stop,
-) -> crate::Result<Vec<u8>> {
-    // Row stride for 8bpp must be a multiple of 4 bytes
+) -> crate::Result<()> {
+    if layout != PixelLayout::Rgba16 {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "BMP encode: 64-bit output needs Rgba16 input, got {layout:?}"
+        ))));
+    }
+    let pixel_data_size = w
+        .checked_mul(8)
+        .and_then(|row| row.checked_mul(h))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    write_bmp_header(out, pixel_data_size, width, height, 64, None, &[], options)?;
+
+    // 1.0 is 8192 (13 fraction bits).
+    let color = |v: u16| (linear_srgb::default::srgb_u16_to_linear(v) * 8192.0 + 0.5) as u16;
+    let alpha = |v: u16| ((u32::from(v) * 8192 + 32767) / 65535) as u16;
+    for row in options.stored_rows(h) {
+        if row % 16 == 0 {
+            drain(out)?;
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for px in pixels[row * w * 8..(row + 1) * w * 8].chunks_exact(8) {
+            let [r, g, b, a] =
+                core::array::from_fn(|i| u16::from_ne_bytes([px[i * 2], px[i * 2 + 1]]));
+            for v in [color(b), color(g), color(r), alpha(a)] {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 16-bit pixels: RGB555 under `BI_RGB`, or RGB565 under `BI_BITFIELDS`
+/// with explicit masks. Channels are rounded to the nearest 5- or 6-bit
+/// value; alpha is dropped.
+#[allow(clippy::too_many_arguments)]
+fn encode_16bit(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    w: usize,
+    h: usize,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut Drain<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
     let row_stride = w
-        .checked_add(3)
+        .checked_mul(2)
+        .and_then(|r| r.checked_add(3))
         .map(|r| r & !3)
         .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
     let pixel_data_size = row_stride
         .checked_mul(h)
         .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
-    // No palette: data_offset = 14 (file header) + 40 (DIB header) = 54
-    // The decoder recognizes 8bpp with no palette space as Gray8.
-    let data_offset: usize = 54;
-    let file_size = pixel_data_size
-        .checked_add(data_offset)
-        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let (masks, green_bits) = if options.compression == BmpCompression::Bitfields {
+        (Some([0xF800, 0x07E0, 0x001F, 0]), 6)
+    } else {
+        (None, 5)
+    };
 
-    let mut out = Vec::with_capacity(file_size);
+    write_bmp_header(out, pixel_data_size, width, height, 16, masks, &[], options)?;
 
-    // File header (14 bytes)
-    out.extend_from_slice(b"BM");
-    out.extend_from_slice(&(file_size as u32).to_le_bytes());
-    out.extend_from_slice(&[0u8; 4]); // reserved
-    out.extend_from_slice(&(data_offset as u32).to_le_bytes());
+    // Round an 8-bit channel to `bits` bits.
+    let reduce = |v: u8, bits: u32| {
+        let max = (1u32 << bits) - 1;
+        (u32::from(v) * max + 127) / 255
+    };
+    let pad_bytes = row_stride - w * 2;
+    for row in options.stored_rows(h) {
+        if row % 16 == 0 {
+            drain(out)?;
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for col in 0..w {
+            let (r, g, b) = get_rgb(pixels, row * w + col, layout)?;
+            let v = reduce(r, 5) << (5 + green_bits) | reduce(g, green_bits) << 5 | reduce(b, 5);
+            out.extend_from_slice(&(v as u16).to_le_bytes());
+        }
+        out.extend(core::iter::repeat_n(0u8, pad_bytes));
+    }
 
-    // DIB header (BITMAPINFOHEADER, 40 bytes)
-    out.extend_from_slice(&40u32.to_le_bytes());
-    out.extend_from_slice(&(width as i32).to_le_bytes());
-    out.extend_from_slice(&(height as i32).to_le_bytes()); // positive = bottom-up
-    out.extend_from_slice(&1u16.to_le_bytes()); // planes
-    out.extend_from_slice(&8u16.to_le_bytes()); // bits per pixel
-    out.extend_from_slice(&0u32.to_le_bytes()); // compression
-    out.extend_from_slice(&(pixel_data_size as u32).to_le_bytes());
-    out.extend_from_slice(&2835u32.to_le_bytes()); // h resolution (72 DPI)
-    out.extend_from_slice(&2835u32.to_le_bytes()); // v resolution
-    out.extend_from_slice(&0u32.to_le_bytes()); // colors used
-    out.extend_from_slice(&0u32.to_le_bytes()); // important colors
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_8bit_gray(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    w: usize,
+    h: usize,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut Drain<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    // Row stride for 8bpp must be a multiple of 4 bytes
+    let row_stride = w
+        .checked_add(3)
+        .map(|r| r & !3)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let pixel_data_size = row_stride
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    // No palette: the data follows the headers directly. The decoder
+    // recognizes 8bpp with no palette space as Gray8.
+    write_bmp_header(out, pixel_data_size, width, height, 8, None, &[], options)?;
 
-    // Pixel data: 1 byte per pixel, bottom-up, padded rows
+    // Pixel data: 1 byte per pixel, padded rows
     let pad_bytes = row_stride - w;
-    for row in (0..h).rev() {
+    for row in options.stored_rows(h) {
         if row % 16 == 0 {
+            drain(out)?;
             stop.check().map_err(|r| at!(BitmapError::from(r)))?;
         }
         let row_start = row * w;
@@ -192,35 +980,371 @@ fn encode_8bit_gray(
         out.extend(core::iter::repeat_n(0u8, pad_bytes));
     }
 
+    Ok(())
+}
+
+/// `BI_RGB` (uncompressed) compression value.
+const BI_RGB: u32 = 0;
+/// `BI_RLE8` compression value.
+const BI_RLE8: u32 = 1;
+/// `BI_RLE4` compression value.
+const BI_RLE4: u32 = 2;
+/// `BI_BITFIELDS` compression value.
+const BI_BITFIELDS: u32 = 3;
+
+/// `PROFILE_EMBEDDED` color space type (`'MBED'`).
+const PROFILE_EMBEDDED: u32 = 0x4D42_4544;
+/// `LCS_GM_IMAGES` rendering intent (perceptual).
+const LCS_GM_IMAGES: u32 = 4;
+
+/// Encode palette indices as a paletted BMP of `depth` bits per pixel:
+/// uncompressed (1, 2, 4 or 8 bits; lower depths pack pixels MSB-first) or,
+/// per `options`, RLE8 (`depth` 8) / RLE4 (`depth` 4).
+///
+/// For RLE, repeated indices become encoded runs and everything else
+/// absolute runs. Each row but the last ends with an end-of-line escape and
+/// the bitmap with end-of-bitmap; delta escapes are never written, so every
+/// pixel is stored explicitly.
+pub(crate) fn encode_indexed(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    depth: u16,
+    options: &BmpEncodeOptions<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    write_indexed(
+        &mut out, indices, palette, width, height, depth, options, stop,
+    )?;
     Ok(out)
 }
 
+/// [`encode_indexed`], appending to `out`.
+#[allow(clippy::too_many_arguments)]
+fn write_indexed(
+    out: &mut Vec<u8>,
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    depth: u16,
+    options: &BmpEncodeOptions<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    let (w, h) = check_indices(indices, palette, 1 << depth, width, height)?;
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+
+    let mut data = Vec::new();
+    if matches!(
+        options.compression,
+        BmpCompression::Rle8 | BmpCompression::Rle4
+    ) {
+        for row in (0..h).rev() {
+            if row % 16 == 0 {
+                stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+            }
+            rle_row(&mut data, &indices[row * w..(row + 1) * w], depth == 4);
+            // End of line, or end of bitmap after the last stored row.
+            data.extend_from_slice(if row == 0 { &[0, 1] } else { &[0, 0] });
+        }
+    } else {
+        let row_stride = w
+            .checked_mul(usize::from(depth))
+            .and_then(|bits| bits.checked_add(31))
+            .map(|bits| bits / 32 * 4)
+            .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+        let pixel_data_size = row_stride
+            .checked_mul(h)
+            .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+        data.reserve(pixel_data_size);
+        let per_byte = usize::from(8 / depth);
+        for row in options.stored_rows(h) {
+            if row % 16 == 0 {
+                stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+            }
+            let start = data.len();
+            for chunk in indices[row * w..(row + 1) * w].chunks(per_byte) {
+                let byte = chunk.iter().enumerate().fold(0u8, |acc, (i, &v)| {
+                    acc | v << (8 - depth as usize * (i + 1))
+                });
+                data.push(byte);
+            }
+            data.resize(start + row_stride, 0);
+        }
+    }
+
+    write_bmp_header(
+        out,
+        data.len(),
+        width,
+        height,
+        depth,
+        None,
+        palette,
+        options,
+    )?;
+    out.extend_from_slice(&data);
+    Ok(())
+}
+
+/// Index of the nearest `palette` entry for each color.
+fn map_to_palette(
+    colors: &[[u8; 3]],
+    palette: &[[u8; 3]],
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    let mut indices = Vec::with_capacity(colors.len());
+    for (i, &px) in colors.iter().enumerate() {
+        if i % (1 << 16) == 0 {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        indices.push(super::quantize::nearest(palette, px));
+    }
+    Ok(indices)
+}
+
+/// Append one RLE-encoded row (without the end-of-line escape). With
+/// `nibbles`, absolute runs pack two 4-bit indices per byte and encoded
+/// runs repeat a single index (the high and low nibble are equal).
+fn rle_row(out: &mut Vec<u8>, row: &[u8], nibbles: bool) {
+    let run_at = |i: usize| {
+        row[i..]
+            .iter()
+            .take(255)
+            .take_while(|&&v| v == row[i])
+            .count()
+    };
+    let mut i = 0;
+    while i < row.len() {
+        let run = run_at(i);
+        if run >= 2 {
+            let v = if nibbles {
+                row[i] << 4 | row[i]
+            } else {
+                row[i]
+            };
+            out.extend_from_slice(&[run as u8, v]);
+            i += run;
+            continue;
+        }
+        // Literal pixels up to the next run worth encoding.
+        let mut end = i + 1;
+        while end < row.len() && end - i < 255 && run_at(end) < 3 {
+            end += 1;
+        }
+        let literal = &row[i..end];
+        if literal.len() < 3 {
+            // Absolute runs of 1 and 2 would read as escapes.
+            for &v in literal {
+                out.extend_from_slice(&[1, if nibbles { v << 4 } else { v }]);
+            }
+        } else {
+            out.extend_from_slice(&[0, literal.len() as u8]);
+            let start = out.len();
+            if nibbles {
+                out.extend(
+                    literal
+                        .chunks(2)
+                        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)),
+                );
+            } else {
+                out.extend_from_slice(literal);
+            }
+            // Absolute runs end on a 16-bit boundary.
+            if (out.len() - start) % 2 == 1 {
+                out.push(0);
+            }
+        }
+        i = end;
+    }
+}
+
+/// Validate paletted input: a palette of 1 to `max_colors` entries and
+/// `width * height` indices that all fall inside it. Returns the dimensions
+/// as `usize`.
+fn check_indices(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    max_colors: usize,
+    width: u32,
+    height: u32,
+) -> crate::Result<(usize, usize)> {
+    if palette.is_empty() || palette.len() > max_colors {
+        return Err(at!(BitmapError::invalid_data(alloc::format!(
+            "BMP palette must have 1 to {max_colors} entries, got {}",
+            palette.len()
+        ))));
+    }
+    let (w, h) = (width as usize, height as usize);
+    let expected = w
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if indices.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: indices.len(),
+        }));
+    }
+    if let Some(&idx) = indices[..expected]
+        .iter()
+        .find(|&&i| usize::from(i) >= palette.len())
+    {
+        return Err(at!(BitmapError::invalid_data(alloc::format!(
+            "palette index {idx} out of range (palette has {} entries)",
+            palette.len()
+        ))));
+    }
+    Ok((w, h))
+}
+
+/// File header, info header and color table for pixel data of
+/// `pixel_data_size` bytes, which follows directly.
+///
+/// With `masks`, the header declares `BI_BITFIELDS`. A V4/V5 header
+/// carries all four masks itself; a `BITMAPINFOHEADER` is followed by the
+/// red, green and blue masks (alpha cannot be declared there). A non-empty
+/// `palette` is written as the color table (BGR0) and counted as the colors
+/// used; RLE compression comes from `options`.
+#[allow(clippy::too_many_arguments)]
 fn write_bmp_header(
     out: &mut Vec<u8>,
-    file_size: usize,
     pixel_data_size: usize,
     width: u32,
     height: u32,
     bpp: u16,
-) {
+    masks: Option<[u32; 4]>,
+    palette: &[[u8; 3]],
+    options: &BmpEncodeOptions<'_>,
+) -> crate::Result<()> {
+    let info_size: usize = match options.written_header() {
+        BmpHeaderVariant::Core => 12,
+        BmpHeaderVariant::V4 => 108,
+        BmpHeaderVariant::V5 => 124,
+        _ => 40,
+    };
+    let trailing_masks = if info_size == 40 && masks.is_some() {
+        12
+    } else {
+        0
+    };
+    let entry_size = if info_size == 12 { 3 } else { 4 };
+    let data_offset = 14 + info_size + trailing_masks + palette.len() * entry_size;
+    let profile = options.icc_profile.unwrap_or_default();
+    let file_size = pixel_data_size
+        .checked_add(data_offset)
+        .and_then(|size| size.checked_add(profile.len()))
+        .filter(|&size| u32::try_from(size).is_ok())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if info_size == 12 && (width > u32::from(u16::MAX) || height > u32::from(u16::MAX)) {
+        return Err(at!(BitmapError::DimensionsTooLarge { width, height }));
+    }
+    // Room for the headers and one 16-row band: a drain empties `out` every
+    // band, so reserving the whole file would defeat streaming. Whole-file
+    // callers reserve `encoded_size` up front instead.
+    let band = pixel_data_size / height.max(1) as usize * 16;
+    out.reserve(data_offset + pixel_data_size.min(band));
+
+    // Negative height = top-down
+    let height = if options.top_down {
+        (height as i32).wrapping_neg()
+    } else {
+        height as i32
+    };
+    let compression = match options.compression {
+        _ if masks.is_some() => BI_BITFIELDS,
+        BmpCompression::Rle8 => BI_RLE8,
+        BmpCompression::Rle4 => BI_RLE4,
+        _ => BI_RGB,
+    };
+
     // File header (14 bytes)
     out.extend_from_slice(b"BM");
     out.extend_from_slice(&(file_size as u32).to_le_bytes());
     out.extend_from_slice(&[0u8; 4]); // reserved
-    out.extend_from_slice(&54u32.to_le_bytes()); // data offset
+    out.extend_from_slice(&(data_offset as u32).to_le_bytes());
 
-    // DIB header (BITMAPINFOHEADER, 40 bytes)
-    out.extend_from_slice(&40u32.to_le_bytes());
+    if info_size == 12 {
+        // BITMAPCOREHEADER: 16-bit dimensions (always bottom-up), then a
+        // BGR color table.
+        out.extend_from_slice(&12u32.to_le_bytes());
+        out.extend_from_slice(&(width as u16).to_le_bytes());
+        out.extend_from_slice(&(height as u16).to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes()); // planes
+        out.extend_from_slice(&bpp.to_le_bytes());
+        for &[r, g, b] in palette {
+            out.extend_from_slice(&[b, g, r]);
+        }
+        return Ok(());
+    }
+
+    // DIB header (BITMAPINFOHEADER part, 40 bytes)
+    out.extend_from_slice(&(info_size as u32).to_le_bytes());
     out.extend_from_slice(&(width as i32).to_le_bytes());
-    out.extend_from_slice(&(height as i32).to_le_bytes()); // positive = bottom-up
+    out.extend_from_slice(&height.to_le_bytes());
     out.extend_from_slice(&1u16.to_le_bytes()); // planes
     out.extend_from_slice(&bpp.to_le_bytes());
-    out.extend_from_slice(&0u32.to_le_bytes()); // compression
+    out.extend_from_slice(&compression.to_le_bytes());
     out.extend_from_slice(&(pixel_data_size as u32).to_le_bytes());
-    out.extend_from_slice(&2835u32.to_le_bytes()); // h resolution (72 DPI)
-    out.extend_from_slice(&2835u32.to_le_bytes()); // v resolution
-    out.extend_from_slice(&0u32.to_le_bytes()); // colors used
+    out.extend_from_slice(&options.pixels_per_meter.0.to_le_bytes()); // h resolution
+    out.extend_from_slice(&options.pixels_per_meter.1.to_le_bytes()); // v resolution
+    out.extend_from_slice(&(palette.len() as u32).to_le_bytes()); // colors used
     out.extend_from_slice(&0u32.to_le_bytes()); // important colors
+
+    let masks = masks.unwrap_or_default();
+    if info_size == 40 {
+        if trailing_masks > 0 {
+            for mask in &masks[..3] {
+                out.extend_from_slice(&mask.to_le_bytes());
+            }
+        }
+    } else {
+        write_v4_v5_fields(out, masks, data_offset, pixel_data_size, info_size, options);
+    }
+
+    // Color table (BGR0)
+    for &[r, g, b] in palette {
+        out.extend_from_slice(&[b, g, r, 0]);
+    }
+    Ok(())
+}
+
+/// The V4 fields after the `BITMAPINFOHEADER` part, plus the V5 ones when
+/// `info_size` is 124.
+fn write_v4_v5_fields(
+    out: &mut Vec<u8>,
+    masks: [u32; 4],
+    data_offset: usize,
+    pixel_data_size: usize,
+    info_size: usize,
+    options: &BmpEncodeOptions<'_>,
+) {
+    // V4 fields: RGBA masks, color space type, then endpoints and gamma
+    // (all zero: no calibration).
+    for mask in masks {
+        out.extend_from_slice(&mask.to_le_bytes());
+    }
+    let cs_type = if options.icc_profile.is_some() {
+        PROFILE_EMBEDDED
+    } else {
+        options.color_space.cs_type()
+    };
+    out.extend_from_slice(&cs_type.to_le_bytes());
+    out.extend_from_slice(&[0u8; 36 + 12]);
+    if info_size == 124 {
+        // V5: intent, profile offset (from the start of the info header;
+        // the profile follows the pixel data) and size, reserved.
+        let (intent, profile_offset) = match options.icc_profile {
+            Some(_) => (LCS_GM_IMAGES, data_offset - 14 + pixel_data_size),
+            None => (0, 0),
+        };
+        out.extend_from_slice(&intent.to_le_bytes());
+        out.extend_from_slice(&(profile_offset as u32).to_le_bytes());
+        let profile_size = options.icc_profile.map_or(0, <[u8]>::len);
+        out.extend_from_slice(&(profile_size as u32).to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+    }
 }
 
 fn get_rgb(pixels: &[u8], idx: usize, layout: PixelLayout) -> crate::Result<(u8, u8, u8)> {
@@ -233,7 +1357,7 @@ fn get_rgb(pixels: &[u8], idx: usize, layout: PixelLayout) -> crate::Result<(u8,
             let off = idx * 3;
             (pixels[off + 2], pixels[off + 1], pixels[off])
         }
-        PixelLayout::Rgba8 => {
+        PixelLayout::Rgba8 | PixelLayout::Rgbx8 => {
             let off = idx * 4;
             (pixels[off], pixels[off + 1], pixels[off + 2])
         }
@@ -278,6 +1402,10 @@ fn get_rgba(pixels: &[u8], idx: usize, layout: PixelLayout) -> crate::Result<(u8
             let off = idx * 4;
             (pixels[off + 2], pixels[off + 1], pixels[off], 255)
         }
+        PixelLayout::Rgbx8 => {
+            let off = idx * 4;
+            (pixels[off], pixels[off + 1], pixels[off + 2], 255)
+        }
         PixelLayout::Rgb8 => {
             let off = idx * 3;
             (pixels[off], pixels[off + 1], pixels[off + 2], 255)
@@ -2,17 +2,25 @@
 //!
 //! Use top-level [`crate::decode_bmp`], [`crate::encode_bmp`], etc.
 
+pub(crate) mod array;
 pub(crate) mod decode;
 mod encode;
-mod utils;
+pub(crate) mod quantize;
+pub(crate) mod utils;
 
 use crate::alloc_util::AllocPref;
-use crate::decode::DecodeOutput;
+use crate::decode::{DecodeOutput, DecodeReport};
 use crate::error::BitmapError;
 use crate::limits::Limits;
 use crate::pixel::PixelLayout;
+use crate::region::Region;
 use alloc::vec::Vec;
-pub use decode::BmpPermissiveness;
+pub use array::{BmpArrayEntry, BmpArrayFrame, BmpArrayFrames};
+pub use decode::{
+    BmpAlphaPolicy, BmpCompression, BmpDecodeOptions, BmpDownscale, BmpHeaderVariant,
+    BmpPermissiveness,
+};
+pub use encode::{BmpColorSpace, BmpEncodeOptions, BmpRowEncoder};
 use enough::Stop;
 
 /// Metadata extracted from a BMP file header.
@@ -21,6 +29,7 @@ use enough::Stop;
 /// information that is not part of the pixel decode output.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BmpMetadata {
     /// Image width in pixels.
     pub width: u32,
@@ -47,6 +56,55 @@ pub struct BmpMetadata {
     /// Only present for indexed-color BMPs (1, 2, 4, or 8 bits per pixel).
     /// Each entry is `[B, G, R, A]` where A is typically 0 or 255.
     pub color_table: Option<Vec<[u8; 4]>>,
+
+    /// DIB info header variant (core, OS/2 2.x, V3 … V5).
+    pub header_variant: BmpHeaderVariant,
+
+    /// Compression as declared in the header.
+    pub compression: BmpCompression,
+
+    /// Bits per pixel as declared in the header.
+    pub bit_count: u16,
+
+    /// Effective R, G, B, A channel masks for direct-color (16/24/32-bit)
+    /// files: the declared BITFIELDS masks, or the implicit `BI_RGB` layout
+    /// (5-5-5 for 16-bit, 8-8-8 otherwise, alpha mask 0 — the fourth byte of
+    /// 32-bit `BI_RGB` pixels is governed by [`BmpAlphaPolicy`]). `None` for
+    /// indexed and 64-bit files.
+    pub masks: Option<[u32; 4]>,
+}
+
+/// How often each palette entry is referenced by a paletted BMP.
+///
+/// Returned by [`crate::decode_bmp_with_palette_usage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BmpPaletteUsage {
+    /// Pixel count per palette index, one slot per declared palette entry.
+    /// Pixels an RLE stream skips (delta / end-of-line escapes) take entry 0
+    /// in the output but are not counted.
+    pub counts: Vec<u64>,
+
+    /// Pixels whose index lies past the declared palette. Only
+    /// [`BmpPermissiveness::Permissive`] decodes them (as black); other
+    /// levels reject the file.
+    pub out_of_range: u64,
+}
+
+impl BmpPaletteUsage {
+    /// Indices referenced by at least one pixel, ascending.
+    pub fn used(&self) -> impl Iterator<Item = u8> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &n)| n > 0)
+            .map(|(i, _)| i as u8)
+    }
+
+    /// Number of distinct indices referenced.
+    pub fn used_count(&self) -> usize {
+        self.counts.iter().filter(|&&n| n > 0).count()
+    }
 }
 
 /// Decode BMP data (output in RGB/RGBA byte order).
@@ -59,76 +117,411 @@ pub(crate) fn decode<'a>(
 }
 
 /// Decode BMP data with a specific permissiveness level.
-///
-/// Allocations use each site's default fallibility; for the zencodec path that
-/// honors [`AllocPreference`](zencodec::AllocPreference), call
-/// [`decode_with_permissiveness_and_alloc_pref`].
 pub(crate) fn decode_with_permissiveness<'a>(
     data: &'a [u8],
     limits: Option<&Limits>,
     permissiveness: BmpPermissiveness,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
-    decode_with_permissiveness_and_alloc_pref(
+    decode_with_options(
         data,
         limits,
-        permissiveness,
-        AllocPref::CodecDefault,
+        &BmpDecodeOptions::new().with_permissiveness(permissiveness),
         stop,
     )
 }
 
-/// Decode BMP data with a specific permissiveness level, honoring an explicit
-/// [`AllocPref`] at the output-buffer (and RLE-output) allocations.
-pub(crate) fn decode_with_permissiveness_and_alloc_pref<'a>(
+/// Decode BMP data with explicit [`BmpDecodeOptions`].
+///
+/// Allocations use each site's default fallibility; for the zencodec path that
+/// honors [`AllocPreference`](zencodec::AllocPreference), call
+/// [`decode_with_options_and_alloc_pref`].
+pub(crate) fn decode_with_options<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    decode_with_options_and_alloc_pref(data, limits, options, AllocPref::CodecDefault, stop)
+}
+
+/// Decode BMP data with explicit [`BmpDecodeOptions`], honoring an explicit
+/// [`AllocPref`] at the output-buffer allocation.
+pub(crate) fn decode_with_options_and_alloc_pref<'a>(
     data: &'a [u8],
     limits: Option<&Limits>,
-    permissiveness: BmpPermissiveness,
+    options: &BmpDecodeOptions,
     alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    let decoded = decode_to_vec(data, limits, options, alloc_pref, stop)?;
+    Ok(decoded.into_output())
+}
+
+/// Decode BMP data, also returning the problems patched over on the way.
+pub(crate) fn decode_with_report<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<(DecodeOutput<'a>, DecodeReport)> {
+    let mut decoded = decode_to_vec(data, limits, options, AllocPref::CodecDefault, stop)?;
+    let report = core::mem::take(&mut decoded.report);
+    Ok((decoded.into_output(), report))
+}
+
+/// Owned result of [`decode_to_vec`].
+pub(crate) struct DecodedBmp {
+    pub(crate) pixels: Vec<u8>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) layout: PixelLayout,
+    pub(crate) report: DecodeReport,
+}
+
+impl DecodedBmp {
+    fn into_output<'a>(self) -> DecodeOutput<'a> {
+        DecodeOutput::owned(self.pixels, self.width, self.height, self.layout)
+    }
+}
+
+/// Limit checks + pixel decode, returning the owned buffer with its
+/// dimensions, layout and decode report. Shared by the BMP entry points and
+/// containers (ICO/CUR) that post-process the pixels.
+pub(crate) fn decode_to_vec(
+    data: &[u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> crate::Result<DecodedBmp> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    // A bitmap array is reduced to its largest bitmap once, for both the
+    // header and the pixels.
+    let data = array::resolve(data, limits)?;
     // Resolve the pixel-count ceiling up front and parse the header *with* it,
     // so an over-cap header is rejected with a `LimitExceeded("pixel count …")`
     // resource error before the header parser's byte-availability heuristic can
     // mask it as `InvalidData`.
+    // A downscaled decode is limited on its reduced output instead.
+    let step = options.downscale().factor();
+    let max_pixels = match step {
+        1 => effective_max_pixels(limits),
+        _ => u64::MAX,
+    };
+    let header = decode::parse_bmp_header(&data, limits, max_pixels)?;
+    let (width, height) = (header.width.div_ceil(step), header.height.div_ceil(step));
+    check_limits(limits, width, height, &header.layout)?;
+    stop.check()
+        .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+    let (pixels, layout, report) =
+        decode::decode_bmp_pixels(data, options, limits, alloc_pref, stop)?;
+    Ok(DecodedBmp {
+        pixels,
+        width,
+        height,
+        layout,
+        report,
+    })
+}
+
+/// Decode a headerless DIB (`BITMAPINFOHEADER` or later, then optional
+/// masks/palette, then pixels) such as clipboard `CF_DIB` / `CF_DIBV5` data.
+pub(crate) fn decode_dib<'a>(
+    dib: &[u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    crate::limits::check_file_size(dib.len(), limits)?;
+    let info = parse_dib_info(dib, true)?;
+    let data = wrap_dib(dib, &info, info.height, limits)?;
+    let decoded = decode_to_vec(&data, limits, options, AllocPref::CodecDefault, stop)?;
+    Ok(decoded.into_output())
+}
+
+/// Geometry of a headerless DIB: info header, then optional bitfield masks
+/// and palette, then the pixel array.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DibInfo {
+    /// Info header size (`biSize`): 12 (OS/2 core) or 16..=124.
+    pub header_size: u32,
+    pub width: u32,
+    /// Signed height as stored (negative = top-down).
+    pub height: i32,
+    #[cfg_attr(not(feature = "ico"), allow(dead_code))]
+    pub bpp: u16,
+    /// Raw `biCompression` value (0 for OS/2 core headers).
+    #[cfg_attr(not(feature = "ico"), allow(dead_code))]
+    pub compression: u32,
+    /// Bytes between the info header and the pixel array (masks + palette).
+    pub table_bytes: usize,
+}
+
+impl DibInfo {
+    /// Offset of the pixel array from the start of the DIB.
+    pub fn pixel_offset(&self) -> usize {
+        (self.header_size as usize).saturating_add(self.table_bytes)
+    }
+}
+
+/// Parse the geometry of a headerless DIB (no `BITMAPFILEHEADER`).
+///
+/// `infer_paletteless` enables the size heuristic for palette-less 8-bit
+/// grayscale DIBs; containers whose stored height is not the pixel-array
+/// height (ICO) must pass `false`.
+pub(crate) fn parse_dib_info(dib: &[u8], infer_paletteless: bool) -> crate::Result<DibInfo> {
+    let u16_at = |off: usize| -> crate::Result<u16> {
+        dib.get(off..off + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))
+    };
+    let u32_at = |off: usize| -> crate::Result<u32> {
+        dib.get(off..off + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))
+    };
+
+    let header_size = u32_at(0)?;
+    let (width, height, bpp, compression, colors_used) = match header_size {
+        12 => (
+            u32::from(u16_at(4)?),
+            i32::from(u16_at(6)? as i16),
+            u16_at(10)?,
+            0,
+            0,
+        ),
+        16 | 40 | 52 | 56 | 64 | 108 | 124 => {
+            let compression = if header_size >= 20 { u32_at(16)? } else { 0 };
+            let colors_used = if header_size >= 36 { u32_at(32)? } else { 0 };
+            (
+                u32_at(4)?,
+                u32_at(8)? as i32,
+                u16_at(14)?,
+                compression,
+                colors_used,
+            )
+        }
+        _ => {
+            return Err(whereat::at!(BitmapError::invalid_header(alloc::format!(
+                "unknown DIB header size: {header_size}"
+            ))));
+        }
+    };
+
+    // BITMAPINFOHEADER stores BI_BITFIELDS / BI_ALPHABITFIELDS masks after
+    // the header; V2 headers embed the color masks and V3+ the alpha mask
+    // too.
+    let mask_bytes = match (header_size, compression) {
+        (40, 3) => 12,
+        (40, 6) => 16,
+        (52, 6) => 4,
+        _ => 0,
+    };
+    let palette_bytes = if bpp <= 8 {
+        let max_colors = 1usize << bpp;
+        let colors = match colors_used as usize {
+            0 => max_colors,
+            n => n.min(max_colors),
+        };
+        let full = colors * if header_size == 12 { 3 } else { 4 };
+        // Without `bfOffBits` a palette-less 8-bit grayscale DIB (as written
+        // by our encoder) is only recognizable by size: if an uncompressed
+        // pixel array fits but a default-sized palette does not, there is
+        // no palette.
+        let pixel_bytes = (width as usize)
+            .saturating_mul(usize::from(bpp))
+            .div_ceil(32)
+            .saturating_mul(4)
+            .saturating_mul(height.unsigned_abs() as usize);
+        let available = dib.len().saturating_sub(header_size as usize + mask_bytes);
+        if infer_paletteless
+            && bpp == 8
+            && compression == 0
+            && colors_used == 0
+            && available >= pixel_bytes
+            && available < pixel_bytes.saturating_add(full)
+        {
+            0
+        } else {
+            full
+        }
+    } else {
+        // Deeper images may still carry a `biClrUsed`-sized color table (a
+        // hint for palette devices) ahead of the pixels.
+        (colors_used as usize).saturating_mul(4)
+    };
+
+    Ok(DibInfo {
+        header_size,
+        width,
+        height,
+        bpp,
+        compression,
+        table_bytes: mask_bytes.saturating_add(palette_bytes),
+    })
+}
+
+/// Prepend a synthetic `BITMAPFILEHEADER` to a headerless DIB so the regular
+/// BMP decoder can read it, overriding the stored height with `height` (ICO
+/// entries store a doubled height). The copy counts against `limits`'
+/// memory cap and is offered to its `on_alloc` hook.
+pub(crate) fn wrap_dib(
+    dib: &[u8],
+    info: &DibInfo,
+    height: i32,
+    limits: Option<&Limits>,
+) -> crate::Result<Vec<u8>> {
+    let total = dib.len().checked_add(14).ok_or_else(|| {
+        whereat::at!(BitmapError::LimitExceeded(
+            "DIB size overflows usize".into()
+        ))
+    })?;
+    let file_size = u32::try_from(total).unwrap_or(u32::MAX);
+    let data_offset = u32::try_from(info.pixel_offset().saturating_add(14)).map_err(|_| {
+        whereat::at!(BitmapError::invalid_header(
+            "DIB palette offset out of range"
+        ))
+    })?;
+
+    crate::limits::check_working_memory(total, limits)?;
+    crate::limits::check_alloc(total, limits)?;
+    let mut out = Vec::with_capacity(total);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&file_size.to_le_bytes());
+    out.extend_from_slice(&[0u8; 4]);
+    out.extend_from_slice(&data_offset.to_le_bytes());
+    out.extend_from_slice(dib);
+    if info.header_size == 12 {
+        out[14 + 6..14 + 8].copy_from_slice(&(height as i16).to_le_bytes());
+    } else {
+        out[14 + 8..14 + 12].copy_from_slice(&height.to_le_bytes());
+    }
+    Ok(out)
+}
+
+/// Decode BMP data in native byte order (BGR/BGRA — no channel swizzle).
+pub(crate) fn decode_native<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    decode_native_with_alloc_pref(data, limits, options, AllocPref::CodecDefault, stop)
+}
+
+/// Decode BMP data in native byte order, honoring an explicit [`AllocPref`]
+/// at the output-buffer allocation.
+pub(crate) fn decode_native_with_alloc_pref<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    let data = array::resolve(data, limits)?;
     let max_pixels = effective_max_pixels(limits);
-    let header = decode::parse_bmp_header(data, max_pixels)?;
+    let header = decode::parse_bmp_header(&data, limits, max_pixels)?;
     check_limits(limits, header.width, header.height, &header.layout)?;
     stop.check()
         .map_err(|r| whereat::at!(BitmapError::from(r)))?;
-    let (pixels, layout) =
-        decode::decode_bmp_pixels(data, permissiveness, max_pixels, alloc_pref, stop)?;
+    let (pixels, native_layout, _) =
+        decode::decode_bmp_pixels_native(data, options, limits, alloc_pref, stop)?;
     Ok(DecodeOutput::owned(
         pixels,
         header.width,
         header.height,
+        native_layout,
+    ))
+}
+
+/// Decode BMP data (RGB/RGBA order), also counting palette index usage.
+/// The usage is `None` for files without a palette.
+pub(crate) fn decode_with_palette_usage<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<(DecodeOutput<'a>, Option<BmpPaletteUsage>)> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    let data = array::resolve(data, limits)?;
+    // Counts cover every pixel, so there is no reduced image to count.
+    if options.downscale() != BmpDownscale::None {
+        return Err(whereat::at!(BitmapError::InvalidArgument(
+            "palette usage cannot be counted on a downscaled decode".into()
+        )));
+    }
+    let max_pixels = effective_max_pixels(limits);
+    let header = decode::parse_bmp_header(&data, limits, max_pixels)?;
+    check_limits(limits, header.width, header.height, &header.layout)?;
+    stop.check()
+        .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+    let mut usage = None;
+    let (pixels, layout, _) =
+        decode::decode_bmp_pixels_counting(data, options, limits, &mut usage, stop)?;
+    let output = DecodeOutput::owned(pixels, header.width, header.height, layout);
+    Ok((output, usage))
+}
+
+/// Decode the `region` window of a BMP with `options`. Only the window's
+/// rows are decoded (see `BmpDecoderState::select_rows`); columns are
+/// cropped afterwards.
+pub(crate) fn decode_region<'a>(
+    data: &[u8],
+    region: Region,
+    limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    let data = array::resolve(data, limits)?;
+    let header = decode::parse_bmp_header(&data, limits, u64::MAX)?;
+    region.check(header.width, header.height)?;
+    check_limits(limits, region.width, region.height, &header.layout)?;
+    stop.check()
+        .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+    let (pixels, layout, _) =
+        decode::decode_bmp_pixels_rows(data, options, limits, region.rows(), stop)?;
+    let pixels = region.crop_columns(pixels, header.width, layout.bytes_per_pixel());
+    Ok(DecodeOutput::owned(
+        pixels,
+        region.width,
+        region.height,
         layout,
     ))
 }
 
-/// Decode BMP data in native byte order (BGR/BGRA — no channel swizzle).
-pub(crate) fn decode_native<'a>(
-    data: &'a [u8],
+/// Whether `data` holds the pixel data of every row. A full decode
+/// zero-fills missing rows (with a warning, in `Standard` mode), but
+/// [`decode_region`] rejects a window that runs past the end.
+pub(crate) fn rows_complete(data: &[u8]) -> bool {
+    decode::parse_bmp_header(data, None, u64::MAX).is_ok_and(|header| header.rows_complete)
+}
+
+/// Decode BMP data to linear-light f32 (sRGB transfer removed), honoring
+/// an explicit [`AllocPref`] at the output-buffer allocation.
+pub(crate) fn decode_linear<'a>(
+    data: &[u8],
     limits: Option<&Limits>,
+    options: &BmpDecodeOptions,
+    alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    let data = array::resolve(data, limits)?;
     let max_pixels = effective_max_pixels(limits);
-    let header = decode::parse_bmp_header(data, max_pixels)?;
+    let header = decode::parse_bmp_header(&data, limits, max_pixels)?;
     check_limits(limits, header.width, header.height, &header.layout)?;
     stop.check()
         .map_err(|r| whereat::at!(BitmapError::from(r)))?;
-    let (pixels, native_layout) = decode::decode_bmp_pixels_native(
-        data,
-        BmpPermissiveness::Standard,
-        max_pixels,
-        AllocPref::CodecDefault,
-        stop,
-    )?;
+    let (pixels, layout, _) =
+        decode::decode_bmp_pixels_linear(data, options, limits, alloc_pref, stop)?;
     Ok(DecodeOutput::owned(
         pixels,
         header.width,
         header.height,
-        native_layout,
+        layout,
     ))
 }
 
@@ -166,7 +559,7 @@ fn check_limits(
 pub(crate) fn probe(data: &[u8]) -> crate::Result<BmpMetadata> {
     // Metadata probe reads dimensions only; it must not reject on the
     // pixel-count cap, so opt out with `u64::MAX`.
-    let header = decode::parse_bmp_header(data, u64::MAX)?;
+    let header = decode::parse_bmp_header(data, None, u64::MAX)?;
 
     /// Convert pixels-per-meter to DPI. Returns None if the value is 0.
     fn pels_to_dpi(pels: u32) -> Option<f32> {
@@ -184,6 +577,10 @@ pub(crate) fn probe(data: &[u8]) -> crate::Result<BmpMetadata> {
         dpi_x: pels_to_dpi(header.x_pels_per_meter),
         dpi_y: pels_to_dpi(header.y_pels_per_meter),
         color_table: header.color_table,
+        header_variant: header.header_variant,
+        compression: header.compression,
+        bit_count: header.bpp,
+        masks: header.masks,
     })
 }
 
@@ -193,10 +590,62 @@ pub(crate) fn encode(
     width: u32,
     height: u32,
     layout: PixelLayout,
-    alpha: bool,
+    options: &BmpEncodeOptions<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    encode::encode_bmp(pixels, width, height, layout, options, stop)
+}
+
+/// Encode pixels to BMP, appending to `out` and handing bytes to `drain`
+/// as they are written.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encode_into(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut Vec<u8>,
+    drain: &mut encode::Drain<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    encode::encode_bmp_into(pixels, width, height, layout, options, out, drain, stop)
+}
+
+/// Bytes to reserve for encoding the whole file into one buffer.
+pub(crate) fn whole_file_capacity(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+) -> usize {
+    encode::whole_file_capacity(pixels, width, height, layout, options)
+}
+
+/// Size of the file [`encode`] writes (an upper bound for RLE and
+/// quantized output).
+pub(crate) fn encoded_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+) -> crate::Result<usize> {
+    encode::encoded_size(width, height, layout, options)
+}
+
+/// Encode palette indices as a `depth`-bit paletted BMP; compression,
+/// header and row order come from `options`.
+pub(crate) fn encode_indexed(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    depth: u16,
+    options: &BmpEncodeOptions<'_>,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
-    encode::encode_bmp(pixels, width, height, layout, alpha, stop)
+    encode::encode_indexed(indices, palette, width, height, depth, options, stop)
 }
 
 #[cfg(test)]
@@ -353,9 +802,17 @@ mod tests {
     #[test]
     fn roundtrip_encode_preserves_dpi() {
         // Encode a 1x1 BMP and verify the DPI in the output.
-        // The encoder hardcodes 2835 (72 DPI).
+        // The encoder defaults to 2835 (72 DPI).
         let pixels = [0xFF, 0x00, 0x00]; // one RGB pixel
-        let encoded = encode(&pixels, 1, 1, PixelLayout::Rgb8, false, &Unstoppable).unwrap();
+        let encoded = encode(
+            &pixels,
+            1,
+            1,
+            PixelLayout::Rgb8,
+            &BmpEncodeOptions::new(),
+            &Unstoppable,
+        )
+        .unwrap();
         let meta = probe(&encoded).unwrap();
         let dpi_x = meta.dpi_x.unwrap();
         assert!(
@@ -363,4 +820,31 @@ mod tests {
             "expected ~72 DPI in encoder output, got {dpi_x}"
         );
     }
+
+    #[test]
+    fn sub_byte_rows_skip_padding() {
+        // 3 pixels at 1, 2 and 4 bits fit in one byte; each stored row is
+        // padded to 4, and the padding must not be read as the next row.
+        let palette = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        for depth in [1u16, 2, 4] {
+            let max = (1usize << depth).min(palette.len()) as u8;
+            let indices: Vec<u8> = (0..9u8).map(|i| (i * 5 + 1) % max).collect();
+            let bmp = encode_indexed(
+                &indices,
+                &palette[..max as usize],
+                3,
+                3,
+                depth,
+                &BmpEncodeOptions::new(),
+                &Unstoppable,
+            )
+            .unwrap();
+            let decoded = decode(&bmp, None, &Unstoppable).unwrap();
+            let expected: Vec<u8> = indices
+                .iter()
+                .flat_map(|&i| palette[usize::from(i)])
+                .collect();
+            assert_eq!(decoded.pixels(), &expected[..], "{depth}-bit");
+        }
+    }
 }
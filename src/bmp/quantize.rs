@@ -0,0 +1,131 @@
+//! Small median-cut quantizer for the low-depth paletted encoders and
+//! sixel output.
+//!
+//! Built for reference output, not quality: images with few enough colors
+//! get an exact palette; anything else is split by weighted median cut and
+//! each pixel mapped to the nearest entry (squared RGB distance). No
+//! dithering.
+
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::error::BitmapError;
+
+/// Reduce `colors` (one RGB triple per pixel) to a palette of at most
+/// `max_colors` entries, returning the palette and one index per pixel.
+pub(crate) fn quantize(
+    colors: &[[u8; 3]],
+    max_colors: usize,
+    stop: &dyn Stop,
+) -> crate::Result<(Vec<[u8; 3]>, Vec<u8>)> {
+    // Distinct colors with their pixel counts, sorted.
+    let mut packed: Vec<u32> = colors
+        .iter()
+        .map(|&[r, g, b]| u32::from_be_bytes([0, r, g, b]))
+        .collect();
+    packed.sort_unstable();
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+    let mut histogram: Vec<([u8; 3], u64)> = Vec::new();
+    for p in packed {
+        let [_, r, g, b] = p.to_be_bytes();
+        match histogram.last_mut() {
+            Some((c, n)) if *c == [r, g, b] => *n += 1,
+            _ => histogram.push(([r, g, b], 1)),
+        }
+    }
+
+    let palette: Vec<[u8; 3]> = if histogram.len() <= max_colors {
+        histogram.iter().map(|&(c, _)| c).collect()
+    } else {
+        median_cut(histogram, max_colors)
+    };
+
+    let mut indices = Vec::with_capacity(colors.len());
+    for (i, px) in colors.iter().enumerate() {
+        if i % (1 << 16) == 0 {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        indices.push(nearest(&palette, *px));
+    }
+    Ok((palette, indices))
+}
+
+/// Split the histogram into `boxes` boxes, each time halving (at the
+/// weighted median of its widest channel) the box with the widest range;
+/// each box contributes its pixel-weighted mean color.
+fn median_cut(histogram: Vec<([u8; 3], u64)>, boxes: usize) -> Vec<[u8; 3]> {
+    let widest = |b: &[([u8; 3], u64)]| {
+        (0..3)
+            .map(|ch| {
+                let (lo, hi) = b.iter().fold((255u8, 0u8), |(lo, hi), (c, _)| {
+                    (lo.min(c[ch]), hi.max(c[ch]))
+                });
+                (hi - lo, ch)
+            })
+            .max()
+            .unwrap_or((0, 0))
+    };
+
+    let mut parts = alloc::vec![histogram];
+    while parts.len() < boxes {
+        let Some((i, ch)) = parts
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (range, ch) = widest(b);
+                (range, i, ch)
+            })
+            .max()
+            .map(|(_, i, ch)| (i, ch))
+        else {
+            break;
+        };
+        let mut part = parts.swap_remove(i);
+        part.sort_unstable_by_key(|(c, _)| c[ch]);
+        let half = part.iter().map(|(_, n)| n).sum::<u64>() / 2;
+        let mut seen = 0;
+        let split = part
+            .iter()
+            .position(|(_, n)| {
+                seen += n;
+                seen > half
+            })
+            .unwrap_or(0)
+            .clamp(1, part.len() - 1);
+        let upper = part.split_off(split);
+        parts.push(part);
+        parts.push(upper);
+    }
+
+    parts
+        .iter()
+        .map(|part| {
+            let total: u64 = part.iter().map(|(_, n)| n).sum();
+            let mut mean = [0u8; 3];
+            for (ch, m) in mean.iter_mut().enumerate() {
+                let sum: u64 = part.iter().map(|(c, n)| u64::from(c[ch]) * n).sum();
+                *m = ((sum + total / 2) / total) as u8;
+            }
+            mean
+        })
+        .collect()
+}
+
+/// Index of the palette entry closest to `px`.
+pub(crate) fn nearest(palette: &[[u8; 3]], px: [u8; 3]) -> u8 {
+    let dist = |c: &[u8; 3]| {
+        (0..3)
+            .map(|ch| {
+                let d = i32::from(c[ch]) - i32::from(px[ch]);
+                (d * d) as u32
+            })
+            .sum::<u32>()
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| dist(c))
+        .map_or(0, |(i, _)| i as u8)
+}
@@ -112,3 +112,29 @@ pub(crate) fn shift_signed(mut v: u32, shift: i32, mut bits: u32) -> u32 {
     v >>= 8 - bits;
     (v.wrapping_mul(MUL_TABLE[bits as usize])) >> SHIFT_TABLE[bits as usize]
 }
+
+/// Extract a bitfield channel and scale it to 8 bits.
+///
+/// The per-pixel form of the BMP bitfield path: derives the shift and bit
+/// count from `mask` and applies [`shift_signed`]. Masks wider than 8 bits
+/// keep their top 8 bits; an empty mask yields 0.
+#[cfg(any(feature = "dds", feature = "xwd"))]
+pub(crate) fn scale_mask_to_u8(v: u32, mask: u32) -> u8 {
+    let shift = 32u32.wrapping_sub(mask.leading_zeros()).wrapping_sub(8) as i32;
+    shift_signed(v & mask, shift, mask.count_ones()) as u8
+}
+
+/// Extract a bitfield channel and scale it to the full 16-bit range.
+///
+/// Used for masks wider than 8 bits, where [`shift_signed`] would discard
+/// precision. Rounds to nearest; an empty mask yields 0.
+pub(crate) fn scale_mask_to_u16(v: u32, mask: u32) -> u16 {
+    let bits = mask.count_ones();
+    if bits == 0 {
+        return 0;
+    }
+    let max = (1u64 << bits) - 1;
+    // Non-contiguous masks can exceed `max` after the shift; clamp them.
+    let raw = u64::from((v & mask) >> mask.trailing_zeros()).min(max);
+    ((raw * 65535 + max / 2) / max) as u16
+}
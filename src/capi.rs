@@ -0,0 +1,429 @@
+//! C ABI for the reference decoders and encoders (`capi` feature).
+//!
+//! Every function takes caller-owned buffers and returns [`ZENBITMAPS_OK`]
+//! or a negative `ZENBITMAPS_ERROR_*` code; nothing allocated here outlives
+//! the call. Output that does not fit fails with
+//! [`ZENBITMAPS_ERROR_BUFFER_TOO_SMALL`] after reporting the size needed, so
+//! callers can pass a zero capacity first to size their buffer. Layouts and
+//! formats cross the boundary as the `ZENBITMAPS_LAYOUT_*` and
+//! `ZENBITMAPS_FORMAT_*` codes. The matching header is
+//! `include/zenbitmaps.h`.
+//!
+//! Build a shared or static library with
+//! `cargo rustc --release --lib --features capi --crate-type cdylib`
+//! (or `staticlib`).
+
+use core::ffi::c_char;
+
+use enough::Unstoppable;
+
+use crate::error::{BitmapError, ErrorKind};
+use crate::limits::Limits;
+use crate::pixel::{EncodeFormat, PixelLayout};
+
+/// Success.
+pub const ZENBITMAPS_OK: i32 = 0;
+/// The input is corrupt or truncated.
+pub const ZENBITMAPS_ERROR_MALFORMED: i32 = -1;
+/// The format, variant or layout is not supported.
+pub const ZENBITMAPS_ERROR_UNSUPPORTED: i32 = -2;
+/// The image exceeds a resource limit.
+pub const ZENBITMAPS_ERROR_LIMIT_EXCEEDED: i32 = -3;
+/// The operation was cancelled.
+pub const ZENBITMAPS_ERROR_CANCELLED: i32 = -4;
+/// Reading or writing failed.
+pub const ZENBITMAPS_ERROR_IO: i32 = -5;
+/// A null pointer, unknown code, or arguments that do not fit together.
+pub const ZENBITMAPS_ERROR_INVALID_ARGUMENT: i32 = -6;
+/// The output buffer is too small; the size needed was reported.
+pub const ZENBITMAPS_ERROR_BUFFER_TOO_SMALL: i32 = -7;
+
+// Indices into `PixelLayout::ALL`.
+pub const ZENBITMAPS_LAYOUT_GRAY8: u32 = 0;
+pub const ZENBITMAPS_LAYOUT_GRAY16: u32 = 1;
+pub const ZENBITMAPS_LAYOUT_RGB8: u32 = 2;
+pub const ZENBITMAPS_LAYOUT_RGBA8: u32 = 3;
+pub const ZENBITMAPS_LAYOUT_BGR8: u32 = 4;
+pub const ZENBITMAPS_LAYOUT_BGRA8: u32 = 5;
+pub const ZENBITMAPS_LAYOUT_BGRX8: u32 = 6;
+pub const ZENBITMAPS_LAYOUT_RGBX8: u32 = 7;
+pub const ZENBITMAPS_LAYOUT_GRAYF32: u32 = 8;
+pub const ZENBITMAPS_LAYOUT_RGBF32: u32 = 9;
+pub const ZENBITMAPS_LAYOUT_RGBAF32: u32 = 10;
+pub const ZENBITMAPS_LAYOUT_RGBA16: u32 = 11;
+pub const ZENBITMAPS_LAYOUT_RGBA16BE: u32 = 12;
+pub const ZENBITMAPS_LAYOUT_RGB16: u32 = 13;
+pub const ZENBITMAPS_LAYOUT_GRAYA16: u32 = 14;
+pub const ZENBITMAPS_LAYOUT_RGB_PLANAR8: u32 = 15;
+pub const ZENBITMAPS_LAYOUT_RGBA_PLANAR8: u32 = 16;
+pub const ZENBITMAPS_LAYOUT_RGB_PLANAR_F32: u32 = 17;
+
+pub const ZENBITMAPS_FORMAT_PGM: u32 = 0;
+pub const ZENBITMAPS_FORMAT_PPM: u32 = 1;
+pub const ZENBITMAPS_FORMAT_PAM: u32 = 2;
+pub const ZENBITMAPS_FORMAT_PFM: u32 = 3;
+pub const ZENBITMAPS_FORMAT_FARBFELD: u32 = 4;
+/// Needs the `bmp` feature; otherwise [`ZENBITMAPS_ERROR_UNSUPPORTED`].
+pub const ZENBITMAPS_FORMAT_BMP: u32 = 5;
+
+/// A decoded image's dimensions and layout, and the bytes its packed
+/// pixels take (`size`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZenbitmapsImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// A `ZENBITMAPS_LAYOUT_*` code.
+    pub layout: u32,
+    pub size: usize,
+}
+
+/// Decode limits; `0` leaves a field unset, so the built-in 120 MP and
+/// 1 GiB ceilings still apply.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZenbitmapsLimits {
+    pub max_width: u64,
+    pub max_height: u64,
+    pub max_pixels: u64,
+    pub max_memory_bytes: u64,
+    pub max_file_size: u64,
+}
+
+impl ZenbitmapsLimits {
+    fn to_limits(self) -> Limits {
+        let set = |v: u64| (v != 0).then_some(v);
+        Limits {
+            max_width: set(self.max_width),
+            max_height: set(self.max_height),
+            max_pixels: set(self.max_pixels),
+            max_memory_bytes: set(self.max_memory_bytes),
+            max_file_size: set(self.max_file_size),
+            ..Limits::default()
+        }
+    }
+}
+
+fn error_code(error: &BitmapError) -> i32 {
+    match error.kind() {
+        ErrorKind::Malformed => ZENBITMAPS_ERROR_MALFORMED,
+        ErrorKind::Unsupported => ZENBITMAPS_ERROR_UNSUPPORTED,
+        ErrorKind::LimitExceeded => ZENBITMAPS_ERROR_LIMIT_EXCEEDED,
+        ErrorKind::Cancelled => ZENBITMAPS_ERROR_CANCELLED,
+        ErrorKind::Io => ZENBITMAPS_ERROR_IO,
+        ErrorKind::InvalidArgument => ZENBITMAPS_ERROR_INVALID_ARGUMENT,
+    }
+}
+
+fn layout_code(layout: PixelLayout) -> u32 {
+    PixelLayout::ALL
+        .iter()
+        .position(|&l| l == layout)
+        .unwrap_or(0) as u32
+}
+
+fn layout_from_code(code: u32) -> Option<PixelLayout> {
+    PixelLayout::ALL.get(code as usize).copied()
+}
+
+fn format_from_code(code: u32) -> Result<EncodeFormat, i32> {
+    match code {
+        ZENBITMAPS_FORMAT_PGM => Ok(EncodeFormat::Pgm),
+        ZENBITMAPS_FORMAT_PPM => Ok(EncodeFormat::Ppm),
+        ZENBITMAPS_FORMAT_PAM => Ok(EncodeFormat::Pam),
+        ZENBITMAPS_FORMAT_PFM => Ok(EncodeFormat::Pfm),
+        ZENBITMAPS_FORMAT_FARBFELD => Ok(EncodeFormat::Farbfeld),
+        #[cfg(feature = "bmp")]
+        ZENBITMAPS_FORMAT_BMP => Ok(EncodeFormat::Bmp),
+        #[cfg(not(feature = "bmp"))]
+        ZENBITMAPS_FORMAT_BMP => Err(ZENBITMAPS_ERROR_UNSUPPORTED),
+        _ => Err(ZENBITMAPS_ERROR_INVALID_ARGUMENT),
+    }
+}
+
+/// `len` bytes at `ptr`, which may be null when `len` is 0.
+///
+/// # Safety
+///
+/// A non-null `ptr` must be valid for reads of `len` bytes for `'a`.
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        return Some(&[]);
+    }
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: non-null, and valid for `len` bytes per the caller.
+    Some(unsafe { core::slice::from_raw_parts(ptr, len) })
+}
+
+/// Copy `bytes` to `out` if they fit in `out_cap`.
+///
+/// # Safety
+///
+/// A non-null `out` must be valid for writes of `out_cap` bytes.
+unsafe fn copy_out(bytes: &[u8], out: *mut u8, out_cap: usize) -> i32 {
+    if bytes.len() > out_cap {
+        return ZENBITMAPS_ERROR_BUFFER_TOO_SMALL;
+    }
+    if bytes.is_empty() {
+        return ZENBITMAPS_OK;
+    }
+    if out.is_null() {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    }
+    // SAFETY: non-null, and valid for `out_cap >= bytes.len()` bytes per
+    // the caller.
+    unsafe { core::slice::from_raw_parts_mut(out, bytes.len()) }.copy_from_slice(bytes);
+    ZENBITMAPS_OK
+}
+
+/// Shared body of the decode entry points.
+///
+/// # Safety
+///
+/// As [`zenbitmaps_decode`].
+unsafe fn decode(
+    data: *const u8,
+    data_len: usize,
+    limits: Option<&Limits>,
+    out: *mut u8,
+    out_cap: usize,
+    info: *mut ZenbitmapsImageInfo,
+) -> i32 {
+    // SAFETY: forwarded from the caller.
+    let Some(data) = (unsafe { input(data, data_len) }) else {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    };
+    let decoded = match limits {
+        Some(limits) => crate::decode_with_limits(data, limits, Unstoppable),
+        None => crate::decode(data, Unstoppable),
+    };
+    let decoded = match decoded {
+        Ok(decoded) => decoded,
+        Err(e) => return error_code(e.error()),
+    };
+    let pixels = decoded.packed_pixels();
+    if !info.is_null() {
+        // SAFETY: non-null, and valid for writes per the caller.
+        unsafe {
+            info.write(ZenbitmapsImageInfo {
+                width: decoded.width,
+                height: decoded.height,
+                layout: layout_code(decoded.layout),
+                size: pixels.len(),
+            });
+        }
+    }
+    // SAFETY: forwarded from the caller.
+    unsafe { copy_out(&pixels, out, out_cap) }
+}
+
+/// Decode `data` (format auto-detected, as `zenbitmaps::decode`) into
+/// `out` as packed rows, and describe the image in `*info`.
+///
+/// `*info` is filled whenever the decode succeeds, including when the
+/// pixels do not fit in `out_cap` bytes and
+/// [`ZENBITMAPS_ERROR_BUFFER_TOO_SMALL`] is returned; `out` may then be
+/// null. `info` may be null if the caller knows the size.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `data_len` bytes and `out` for writes
+/// of `out_cap` bytes (either may be null when its length is 0); `info`
+/// must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zenbitmaps_decode(
+    data: *const u8,
+    data_len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    info: *mut ZenbitmapsImageInfo,
+) -> i32 {
+    // SAFETY: forwarded from the caller.
+    unsafe { decode(data, data_len, None, out, out_cap, info) }
+}
+
+/// [`zenbitmaps_decode`] with resource limits; a null `limits` applies
+/// only the built-in ceilings.
+///
+/// # Safety
+///
+/// As [`zenbitmaps_decode`]; `limits` must be null or valid for reads.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zenbitmaps_decode_with_limits(
+    data: *const u8,
+    data_len: usize,
+    limits: *const ZenbitmapsLimits,
+    out: *mut u8,
+    out_cap: usize,
+    info: *mut ZenbitmapsImageInfo,
+) -> i32 {
+    // SAFETY: null or valid for reads per the caller.
+    let limits = unsafe { limits.as_ref() }.map(|l| l.to_limits());
+    // SAFETY: forwarded from the caller.
+    unsafe { decode(data, data_len, limits.as_ref(), out, out_cap, info) }
+}
+
+/// Store in `*size` the exact size of the file [`zenbitmaps_encode`] writes
+/// for a `width` × `height` image of `layout` pixels as `format` (an upper
+/// bound for BMP).
+///
+/// # Safety
+///
+/// `size` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zenbitmaps_encoded_size(
+    width: u32,
+    height: u32,
+    layout: u32,
+    format: u32,
+    size: *mut usize,
+) -> i32 {
+    let Some(layout) = layout_from_code(layout) else {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    };
+    let format = match format_from_code(format) {
+        Ok(format) => format,
+        Err(code) => return code,
+    };
+    if size.is_null() {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    }
+    match crate::estimate_encoded_size(width, height, layout, format) {
+        Ok(len) => {
+            // SAFETY: non-null, and valid for writes per the caller.
+            unsafe { size.write(len) };
+            ZENBITMAPS_OK
+        }
+        Err(e) => error_code(e.error()),
+    }
+}
+
+/// Encode `pixels` (`width` × `height`, packed rows of `layout`) as
+/// `format` into `out`, with each format's default options, and store the
+/// file size in `*out_len`.
+///
+/// When the file does not fit in `out_cap` bytes, `*out_len` gets the size
+/// needed and [`ZENBITMAPS_ERROR_BUFFER_TOO_SMALL`] is returned.
+///
+/// # Safety
+///
+/// `pixels` must be valid for reads of `pixels_len` bytes, `out` for writes
+/// of `out_cap` bytes (either may be null when its length is 0), and
+/// `out_len` for writes.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn zenbitmaps_encode(
+    pixels: *const u8,
+    pixels_len: usize,
+    width: u32,
+    height: u32,
+    layout: u32,
+    format: u32,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> i32 {
+    // SAFETY: forwarded from the caller.
+    let Some(pixels) = (unsafe { input(pixels, pixels_len) }) else {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    };
+    let Some(layout) = layout_from_code(layout) else {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    };
+    let format = match format_from_code(format) {
+        Ok(format) => format,
+        Err(code) => return code,
+    };
+    if out_len.is_null() {
+        return ZENBITMAPS_ERROR_INVALID_ARGUMENT;
+    }
+    let options = crate::EncodeOptions::new();
+    let file = match crate::encode(pixels, width, height, layout, format, &options, Unstoppable) {
+        Ok(file) => file,
+        Err(e) => return error_code(e.error()),
+    };
+    // SAFETY: non-null, and valid for writes per the caller.
+    unsafe { out_len.write(file.len()) };
+    // SAFETY: forwarded from the caller.
+    unsafe { copy_out(&file, out, out_cap) }
+}
+
+macro_rules! encode_as {
+    ($(#[$doc:meta])* $name:ident, $format:expr) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        ///
+        /// As [`zenbitmaps_encode`].
+        #[unsafe(no_mangle)]
+        #[allow(clippy::too_many_arguments)]
+        pub unsafe extern "C" fn $name(
+            pixels: *const u8,
+            pixels_len: usize,
+            width: u32,
+            height: u32,
+            layout: u32,
+            out: *mut u8,
+            out_cap: usize,
+            out_len: *mut usize,
+        ) -> i32 {
+            // SAFETY: forwarded from the caller.
+            unsafe {
+                zenbitmaps_encode(
+                    pixels, pixels_len, width, height, layout, $format, out, out_cap, out_len,
+                )
+            }
+        }
+    };
+}
+
+encode_as!(
+    /// [`zenbitmaps_encode`] as binary PPM (P6).
+    zenbitmaps_encode_ppm,
+    ZENBITMAPS_FORMAT_PPM
+);
+encode_as!(
+    /// [`zenbitmaps_encode`] as binary PGM (P5).
+    zenbitmaps_encode_pgm,
+    ZENBITMAPS_FORMAT_PGM
+);
+encode_as!(
+    /// [`zenbitmaps_encode`] as PAM (P7).
+    zenbitmaps_encode_pam,
+    ZENBITMAPS_FORMAT_PAM
+);
+encode_as!(
+    /// [`zenbitmaps_encode`] as PFM.
+    zenbitmaps_encode_pfm,
+    ZENBITMAPS_FORMAT_PFM
+);
+encode_as!(
+    /// [`zenbitmaps_encode`] as farbfeld.
+    zenbitmaps_encode_farbfeld,
+    ZENBITMAPS_FORMAT_FARBFELD
+);
+encode_as!(
+    /// [`zenbitmaps_encode`] as BMP; [`ZENBITMAPS_ERROR_UNSUPPORTED`]
+    /// without the `bmp` feature.
+    zenbitmaps_encode_bmp,
+    ZENBITMAPS_FORMAT_BMP
+);
+
+/// A static, NUL-terminated description of a `ZENBITMAPS_*` return code.
+#[unsafe(no_mangle)]
+pub extern "C" fn zenbitmaps_error_message(code: i32) -> *const c_char {
+    let message = match code {
+        ZENBITMAPS_OK => c"ok",
+        ZENBITMAPS_ERROR_MALFORMED => c"malformed input",
+        ZENBITMAPS_ERROR_UNSUPPORTED => c"unsupported format, variant or layout",
+        ZENBITMAPS_ERROR_LIMIT_EXCEEDED => c"resource limit exceeded",
+        ZENBITMAPS_ERROR_CANCELLED => c"cancelled",
+        ZENBITMAPS_ERROR_IO => c"I/O error",
+        ZENBITMAPS_ERROR_INVALID_ARGUMENT => c"invalid argument",
+        ZENBITMAPS_ERROR_BUFFER_TOO_SMALL => c"output buffer too small",
+        _ => c"unknown error code",
+    };
+    message.as_ptr()
+}
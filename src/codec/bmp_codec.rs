@@ -8,6 +8,7 @@ use crate::alloc_util::AllocPref;
 // ══════════════════════════════════════════════════════════════════════
 
 static BMP_ENCODE_CAPS: EncodeCapabilities = EncodeCapabilities::new()
+    .with_icc(true)
     .with_lossless(true)
     .with_native_alpha(true)
     .with_stop(true)
@@ -33,6 +34,9 @@ static BMP_DECODE_DESCRIPTORS: &[PixelDescriptor] = &[
     PixelDescriptor::RGBA8_SRGB,
     PixelDescriptor::GRAY8_SRGB,
     PixelDescriptor::BGRA8_SRGB,
+    PixelDescriptor::RGBA16_SRGB,
+    PixelDescriptor::RGBAF32_LINEAR,
+    PixelDescriptor::GRAYF32_LINEAR,
 ];
 
 // ══════════════════════════════════════════════════════════════════════
@@ -98,6 +102,7 @@ impl zencodec::encode::EncoderConfig for BmpEncoderConfig {
             config: self,
             limits: None,
             stop: None,
+            metadata: Metadata::none(),
         }
     }
 }
@@ -105,10 +110,16 @@ impl zencodec::encode::EncoderConfig for BmpEncoderConfig {
 // ── BmpEncodeJob ─────────────────────────────────────────────────
 
 /// Per-operation BMP encode job.
+///
+/// [`with_metadata`](zencodec::encode::EncodeJob::with_metadata) embeds
+/// the ICC profile (in a `BITMAPV5HEADER`) and writes the EXIF
+/// `XResolution`/`YResolution` to the header's resolution fields. BMP has
+/// nowhere to keep the rest.
 pub struct BmpEncodeJob {
     config: BmpEncoderConfig,
     limits: Option<ResourceLimits>,
     stop: Option<zencodec::StopToken>,
+    metadata: Metadata,
 }
 
 impl zencodec::encode::EncodeJob for BmpEncodeJob {
@@ -121,7 +132,8 @@ impl zencodec::encode::EncodeJob for BmpEncodeJob {
         self
     }
 
-    fn with_metadata(self, _meta: Metadata) -> Self {
+    fn with_metadata(mut self, meta: Metadata) -> Self {
+        self.metadata = meta;
         self
     }
 
@@ -135,6 +147,8 @@ impl zencodec::encode::EncodeJob for BmpEncodeJob {
             config: self.config,
             limits: self.limits,
             stop: self.stop,
+            metadata: self.metadata,
+            rows: None,
         })
     }
 
@@ -152,6 +166,10 @@ pub struct BmpEncoder {
     config: BmpEncoderConfig,
     limits: Option<ResourceLimits>,
     stop: Option<zencodec::StopToken>,
+    metadata: Metadata,
+    /// Row-streaming state, started by the first `push_rows()`. The ICC
+    /// profile is borrowed from `metadata`, so it is added at `finish()`.
+    rows: Option<crate::BmpRowEncoder<'static>>,
 }
 
 impl BmpEncoder {
@@ -169,6 +187,82 @@ impl BmpEncoder {
             }
         })
     }
+
+    /// Options for `alpha` output carrying the EXIF resolution from the
+    /// metadata. The caller adds the ICC profile.
+    fn options(&self, alpha: bool) -> crate::BmpEncodeOptions<'static> {
+        let options = crate::BmpEncodeOptions::new().with_alpha(alpha);
+        match self.metadata.exif.as_deref().and_then(exif_dpi) {
+            Some((x, y)) => options.with_dpi(x, y),
+            None => options,
+        }
+    }
+}
+
+/// Resolution in dots per inch from the `XResolution`, `YResolution` and
+/// `ResolutionUnit` tags of a TIFF/EXIF blob's IFD0 (optionally
+/// `Exif\0\0`-prefixed), when both resolutions are present.
+fn exif_dpi(exif: &[u8]) -> Option<(f32, f32)> {
+    let tiff = exif.strip_prefix(b"Exif\0\0").unwrap_or(exif);
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let b = tiff.get(at..at.checked_add(2)?)?;
+        let b = [b[0], b[1]];
+        Some(if big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    };
+    let u32_at = |at: usize| {
+        let b = tiff.get(at..at.checked_add(4)?)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    };
+    let rational_at = |at: usize| {
+        let (num, den) = (u32_at(at)?, u32_at(at.checked_add(4)?)?);
+        (den != 0).then(|| num as f32 / den as f32)
+    };
+    let ifd = u32_at(4)? as usize;
+    let (mut x, mut y) = (None, None);
+    // TIFF's default unit is the inch.
+    let mut unit = 2;
+    for i in 0..usize::from(u16_at(ifd)?) {
+        let entry = ifd.checked_add(2 + 12 * i)?;
+        match (u16_at(entry)?, u16_at(entry + 2)?) {
+            // RATIONAL values don't fit in the entry; it holds their offset.
+            (0x011A, 5) => x = rational_at(u32_at(entry + 8)? as usize),
+            (0x011B, 5) => y = rational_at(u32_at(entry + 8)? as usize),
+            (0x0128, 3) => unit = u16_at(entry + 8)?,
+            _ => {}
+        }
+    }
+    let per_inch = match unit {
+        2 => 1.0,
+        3 => 2.54,
+        _ => return None,
+    };
+    Some((x? * per_inch, y? * per_inch))
+}
+
+/// BMP input layout for `desc`, and whether to keep its alpha.
+fn pixel_slice_to_bmp_layout(desc: PixelDescriptor) -> crate::Result<(crate::PixelLayout, bool)> {
+    match (desc.channel_type(), desc.layout()) {
+        (ChannelType::U8, ChannelLayout::Rgb) => Ok((crate::PixelLayout::Rgb8, false)),
+        (ChannelType::U8, ChannelLayout::Rgba) => Ok((crate::PixelLayout::Rgba8, true)),
+        (ChannelType::U8, ChannelLayout::Bgra) => Ok((crate::PixelLayout::Bgra8, true)),
+        _ => Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "BMP encode: unsupported pixel format: {desc:?}"
+        )))),
+    }
 }
 
 impl zencodec::encode::Encoder for BmpEncoder {
@@ -200,7 +294,6 @@ impl zencodec::encode::Encoder for BmpEncoder {
             Some(s) => s,
             None => &enough::Unstoppable,
         };
-        let desc = pixels.descriptor();
         let w = pixels.width();
         let h = pixels.rows();
 
@@ -209,19 +302,53 @@ impl zencodec::encode::Encoder for BmpEncoder {
         }
 
         let bytes = pixels.contiguous_bytes();
-        let (layout, alpha) = match (desc.channel_type(), desc.layout()) {
-            (ChannelType::U8, ChannelLayout::Rgb) => (crate::PixelLayout::Rgb8, false),
-            (ChannelType::U8, ChannelLayout::Rgba) => (crate::PixelLayout::Rgba8, true),
-            (ChannelType::U8, ChannelLayout::Bgra) => (crate::PixelLayout::Bgra8, true),
-            _ => {
-                return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
-                    "BMP encode: unsupported pixel format: {:?}",
-                    desc
-                ))));
-            }
+        let (layout, alpha) = pixel_slice_to_bmp_layout(pixels.descriptor())?;
+
+        let mut options = self.options(alpha);
+        if let Some(icc) = &self.metadata.icc_profile {
+            options = options.with_icc_profile(icc);
+        }
+        let encoded = crate::bmp::encode(&bytes, w, h, layout, &options, stop)?;
+        Ok(EncodeOutput::new(encoded, ImageFormat::Bmp))
+    }
+
+    fn push_rows(&mut self, rows: PixelSlice<'_>) -> crate::Result<()> {
+        let (layout, alpha) = pixel_slice_to_bmp_layout(rows.descriptor())?;
+        let width = rows.width();
+        let limits = self.effective_limits();
+        let options = self.options(alpha);
+        let encoder = match &mut self.rows {
+            Some(encoder) => encoder,
+            None => self
+                .rows
+                .insert(crate::BmpRowEncoder::new(width, layout, &options)?),
         };
+        if encoder.width() != width || encoder.layout() != layout {
+            return Err(at!(BitmapError::invalid_data(
+                "push_rows: width or pixel format changed",
+            )));
+        }
+        let total = encoder.rows().saturating_add(rows.rows());
+        if let Some(limits) = limits {
+            limits.check(width, total)?;
+        }
+        if let Some(stop) = &self.stop {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        encoder.push_rows(&rows.contiguous_bytes())
+    }
 
-        let encoded = crate::bmp::encode(&bytes, w, h, layout, alpha, stop)?;
+    fn finish(self) -> crate::Result<EncodeOutput> {
+        let mut encoder: crate::BmpRowEncoder<'_> = self
+            .rows
+            .ok_or_else(|| at!(BitmapError::invalid_data("finish() without push_rows()")))?;
+        if let Some(icc) = &self.metadata.icc_profile {
+            encoder = encoder.with_icc_profile(icc);
+        }
+        let encoded = match self.stop {
+            Some(stop) => encoder.finish(stop)?,
+            None => encoder.finish(enough::Unstoppable)?,
+        };
         Ok(EncodeOutput::new(encoded, ImageFormat::Bmp))
     }
 }
@@ -229,9 +356,17 @@ impl zencodec::encode::Encoder for BmpEncoder {
 // ── BmpDecoderConfig ─────────────────────────────────────────────
 
 /// Decoding configuration for BMP format.
+///
+/// Carries the BMP knobs of the native API ([`DecodeRequest`]): how
+/// malformed files are treated and whether pixels keep BMP's BGR byte
+/// order.
+///
+/// [`DecodeRequest`]: crate::DecodeRequest
 #[derive(Clone, Debug)]
 pub struct BmpDecoderConfig {
     limits: Option<Limits>,
+    permissiveness: crate::bmp::BmpPermissiveness,
+    native_order: bool,
 }
 
 impl Default for BmpDecoderConfig {
@@ -243,7 +378,39 @@ impl Default for BmpDecoderConfig {
 impl BmpDecoderConfig {
     /// Create a new BMP decoder config with default settings.
     pub fn new() -> Self {
-        Self { limits: None }
+        Self {
+            limits: None,
+            permissiveness: crate::bmp::BmpPermissiveness::Standard,
+            native_order: false,
+        }
+    }
+
+    /// How strictly to treat malformed files. Default
+    /// [`BmpPermissiveness::Standard`](crate::BmpPermissiveness::Standard).
+    /// A job's [`DecodePolicy`] that sets `strict` or `allow_truncated`
+    /// takes precedence.
+    pub fn with_permissiveness(mut self, permissiveness: crate::BmpPermissiveness) -> Self {
+        self.permissiveness = permissiveness;
+        self
+    }
+
+    /// Keep BMP's native byte order, as [`crate::decode_bmp_native`] does,
+    /// so 32-bit files decode to BGRA8 without a channel swizzle. zencodec
+    /// has no 3-byte BGR format, so 24-bit and paletted files still arrive
+    /// as RGB8. Default `false`.
+    pub fn with_native_order(mut self, native_order: bool) -> Self {
+        self.native_order = native_order;
+        self
+    }
+
+    /// The permissiveness used when the job's policy does not set one.
+    pub fn permissiveness(&self) -> crate::BmpPermissiveness {
+        self.permissiveness
+    }
+
+    /// Whether pixels keep BMP's native byte order.
+    pub fn native_order(&self) -> bool {
+        self.native_order
     }
 }
 
@@ -269,8 +436,7 @@ impl zencodec::decode::DecoderConfig for BmpDecoderConfig {
         compute: &zencodec::estimate::ComputeEnvironment,
     ) -> zencodec::estimate::ResourceEstimate {
         // BMP working set ≈ output buffer + one scanline + ≤256-entry palette
-        // (serial; RLE expands into a second output-sized buffer but the
-        // bomb-ratio guard keeps it bounded).
+        // (serial; RLE runs are expanded straight into the output buffer).
         super::trivial_decode_resources(image, compute)
     }
 
@@ -325,15 +491,15 @@ impl<'a> zencodec::decode::DecodeJob<'a> for BmpDecodeJob {
 
     fn probe(&self, data: &[u8]) -> crate::Result<ImageInfo> {
         // Metadata only — do not reject on the pixel-count cap.
-        let header = crate::bmp::decode::parse_bmp_header(data, u64::MAX)?;
+        let header = crate::bmp::decode::parse_bmp_header(data, None, u64::MAX)?;
         let has_alpha = matches!(
             header.layout,
-            crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8
+            crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8 | crate::PixelLayout::Rgba16
         );
         let channel_count: u8 = match header.layout {
             crate::PixelLayout::Gray8 => 1,
             crate::PixelLayout::Rgb8 => 3,
-            crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8 => 4,
+            crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8 | crate::PixelLayout::Rgba16 => 4,
             _ => 3, // BMP decoded output is at least RGB
         };
         let mut info = ImageInfo::new(header.width, header.height, ImageFormat::Bmp)
@@ -355,12 +521,16 @@ impl<'a> zencodec::decode::DecodeJob<'a> for BmpDecodeJob {
 
     fn output_info(&self, data: &[u8]) -> crate::Result<OutputInfo> {
         // Metadata only — do not reject on the pixel-count cap.
-        let header = crate::bmp::decode::parse_bmp_header(data, u64::MAX)?;
+        let header = crate::bmp::decode::parse_bmp_header(data, None, u64::MAX)?;
         let has_alpha = matches!(
             header.layout,
-            crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8
+            crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8 | crate::PixelLayout::Rgba16
         );
-        let native_format = layout_to_descriptor(header.layout);
+        let layout = match header.layout {
+            crate::PixelLayout::Rgba8 if self.config.native_order => crate::PixelLayout::Bgra8,
+            layout => layout,
+        };
+        let native_format = layout_to_descriptor(layout);
         Ok(
             OutputInfo::full_decode(header.width, header.height, native_format)
                 .with_alpha(has_alpha),
@@ -370,7 +540,7 @@ impl<'a> zencodec::decode::DecodeJob<'a> for BmpDecodeJob {
     fn decoder(
         self,
         data: Cow<'a, [u8]>,
-        _preferred: &[PixelDescriptor],
+        preferred: &[PixelDescriptor],
     ) -> crate::Result<BmpDecoder<'a>> {
         if let Some(max) = self.max_input_bytes
             && data.len() as u64 > max
@@ -380,7 +550,8 @@ impl<'a> zencodec::decode::DecodeJob<'a> for BmpDecodeJob {
                 data.len()
             ))));
         }
-        let permissiveness = policy_to_bmp_permissiveness(self.policy.as_ref());
+        let permissiveness =
+            policy_to_bmp_permissiveness(self.policy.as_ref(), self.config.permissiveness);
         Ok(BmpDecoder {
             config: self.config,
             limits: self.limits,
@@ -388,6 +559,10 @@ impl<'a> zencodec::decode::DecodeJob<'a> for BmpDecodeJob {
             stop: self.stop,
             permissiveness,
             alloc_pref: self.alloc_pref,
+            // A float preference is met by converting while decoding.
+            linear: preferred
+                .first()
+                .is_some_and(|d| d.channel_type() == ChannelType::F32),
         })
     }
 
@@ -397,9 +572,8 @@ impl<'a> zencodec::decode::DecodeJob<'a> for BmpDecodeJob {
         sink: &mut dyn zencodec::decode::DecodeRowSink,
         preferred: &[PixelDescriptor],
     ) -> Result<OutputInfo, Self::Error> {
-        zencodec::helpers::copy_decode_to_sink(self, data, sink, preferred, |e| {
-            at!(BitmapError::InvalidData(e.to_string()))
-        })
+        let decoder = self.decoder(data, preferred)?;
+        decoded_to_sink(&decoder.decode_internal()?, sink)
     }
 
     fn streaming_decoder(
@@ -433,31 +607,52 @@ pub struct BmpDecoder<'a> {
     stop: Option<zencodec::StopToken>,
     permissiveness: crate::bmp::BmpPermissiveness,
     alloc_pref: AllocPref,
+    /// Decode to linear-light f32 (a float descriptor was preferred).
+    linear: bool,
 }
 
 impl BmpDecoder<'_> {
     fn effective_limits(&self) -> Option<&Limits> {
         self.limits.as_ref().or(self.config.limits.as_ref())
     }
-}
-
-impl zencodec::decode::Decode for BmpDecoder<'_> {
-    type Error = At<BitmapError>;
 
-    fn decode(self) -> crate::Result<DecodeOutput> {
+    /// Decode to the core output, borrowed from the input where the format
+    /// allows.
+    fn decode_internal(&self) -> crate::Result<crate::decode::DecodeOutput<'_>> {
         let limits = self.effective_limits();
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
             None => &enough::Unstoppable,
         };
-        let decoded = crate::bmp::decode_with_permissiveness_and_alloc_pref(
-            &self.data,
-            limits,
-            self.permissiveness,
-            self.alloc_pref,
-            stop,
-        )?;
-        decode_output_from_internal(&decoded, ImageFormat::Bmp)
+        let options = crate::bmp::BmpDecodeOptions::new().with_permissiveness(self.permissiveness);
+        if self.linear {
+            crate::bmp::decode_linear(&self.data, limits, &options, self.alloc_pref, stop)
+        } else if self.config.native_order {
+            crate::bmp::decode_native_with_alloc_pref(
+                &self.data,
+                limits,
+                &options,
+                self.alloc_pref,
+                stop,
+            )
+        } else {
+            crate::bmp::decode_with_options_and_alloc_pref(
+                &self.data,
+                limits,
+                &options,
+                self.alloc_pref,
+                stop,
+            )
+        }
+    }
+}
+
+impl zencodec::decode::Decode for BmpDecoder<'_> {
+    type Error = At<BitmapError>;
+
+    fn decode(self) -> crate::Result<DecodeOutput> {
+        let decoded = self.decode_internal()?;
+        decode_output_from_internal(&decoded, ImageFormat::Bmp, self.effective_limits())
     }
 }
 
@@ -465,17 +660,20 @@ impl zencodec::decode::Decode for BmpDecoder<'_> {
 ///
 /// - `strict == Some(true)` → `Strict`
 /// - `allow_truncated == Some(true)` → `Permissive`
-/// - otherwise (or no policy) → `Standard`
-fn policy_to_bmp_permissiveness(policy: Option<&DecodePolicy>) -> crate::bmp::BmpPermissiveness {
+/// - otherwise (or no policy) → `default`, the config's setting
+fn policy_to_bmp_permissiveness(
+    policy: Option<&DecodePolicy>,
+    default: crate::bmp::BmpPermissiveness,
+) -> crate::bmp::BmpPermissiveness {
     use crate::bmp::BmpPermissiveness;
     let Some(p) = policy else {
-        return BmpPermissiveness::Standard;
+        return default;
     };
     if p.resolve_strict(false) {
         BmpPermissiveness::Strict
     } else if p.resolve_truncated(false) {
         BmpPermissiveness::Permissive
     } else {
-        BmpPermissiveness::Standard
+        default
     }
 }
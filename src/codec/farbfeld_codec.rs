@@ -1,5 +1,6 @@
 use super::*;
 use whereat::{At, at};
+use zencodec::ImageSequence;
 
 use crate::alloc_util::AllocPref;
 
@@ -21,7 +22,8 @@ static FF_DECODE_CAPS: DecodeCapabilities = DecodeCapabilities::new()
     .with_stop(true)
     .with_enforces_max_pixels(true)
     .with_enforces_max_memory(true)
-    .with_enforces_max_input_bytes(true);
+    .with_enforces_max_input_bytes(true)
+    .with_animation(true);
 
 static FF_ENCODE_DESCRIPTORS: &[PixelDescriptor] = &[
     PixelDescriptor::RGBA16_SRGB,
@@ -288,7 +290,7 @@ impl<'a> zencodec::decode::DecodeJob<'a> for FarbfeldDecodeJob {
     type Error = At<BitmapError>;
     type Dec = FarbfeldDecoder<'a>;
     type StreamDec = zencodec::Unsupported<At<BitmapError>>;
-    type AnimationFrameDec = zencodec::Unsupported<At<BitmapError>>;
+    type AnimationFrameDec = FarbfeldFrameDecoder;
 
     fn with_stop(mut self, stop: zencodec::StopToken) -> Self {
         self.stop = Some(stop);
@@ -350,9 +352,7 @@ impl<'a> zencodec::decode::DecodeJob<'a> for FarbfeldDecodeJob {
         sink: &mut dyn zencodec::decode::DecodeRowSink,
         preferred: &[PixelDescriptor],
     ) -> Result<OutputInfo, Self::Error> {
-        zencodec::helpers::copy_decode_to_sink(self, data, sink, preferred, |e| {
-            at!(BitmapError::InvalidData(e.to_string()))
-        })
+        self.decoder(data, preferred)?.decode_to_sink(sink)
     }
 
     fn streaming_decoder(
@@ -367,12 +367,36 @@ impl<'a> zencodec::decode::DecodeJob<'a> for FarbfeldDecodeJob {
 
     fn animation_frame_decoder(
         self,
-        _data: Cow<'a, [u8]>,
+        data: Cow<'a, [u8]>,
         _preferred: &[PixelDescriptor],
-    ) -> crate::Result<zencodec::Unsupported<At<BitmapError>>> {
-        Err(at!(BitmapError::from(
-            zencodec::UnsupportedOperation::AnimationDecode,
-        )))
+    ) -> crate::Result<FarbfeldFrameDecoder> {
+        if let Some(max) = self.max_input_bytes
+            && data.len() as u64 > max
+        {
+            return Err(at!(BitmapError::LimitExceeded(alloc::format!(
+                "input size {} exceeds limit {max}",
+                data.len()
+            ))));
+        }
+        let (width, height) = crate::farbfeld::decode::parse_header(&data)?;
+        let frame_count = crate::farbfeld::stream::count_frames(&data)?;
+        let info = self.probe(&data)?.with_sequence(ImageSequence::Animation {
+            frame_count: Some(frame_count),
+            loop_count: None,
+            random_access: true,
+        });
+        Ok(FarbfeldFrameDecoder {
+            limits: self.limits.or(self.config.limits),
+            data: data.into_owned(),
+            offset: 0,
+            index: 0,
+            width,
+            height,
+            info,
+            stop: self.stop,
+            alloc_pref: self.alloc_pref,
+            canvas: None,
+        })
     }
 }
 
@@ -391,19 +415,140 @@ impl FarbfeldDecoder<'_> {
     fn effective_limits(&self) -> Option<&Limits> {
         self.limits.as_ref().or(self.config.limits.as_ref())
     }
+
+    /// Decode straight into the sink's buffer: farbfeld rows convert
+    /// independently, so no intermediate image is needed.
+    fn decode_to_sink(
+        &self,
+        sink: &mut dyn zencodec::decode::DecodeRowSink,
+    ) -> crate::Result<OutputInfo> {
+        let limits = self.effective_limits();
+        let stop: &dyn Stop = match &self.stop {
+            Some(s) => s,
+            None => &enough::Unstoppable,
+        };
+        crate::limits::check_file_size(self.data.len(), limits)?;
+        let (width, height) = crate::farbfeld::decode::parse_header(&self.data)?;
+        crate::limits::check_dimensions(width, height, limits)?;
+        let descriptor = output_descriptor(crate::PixelLayout::Rgba16);
+        let mut dst = sink_buffer(sink, width, height, descriptor)?;
+        let stride = dst.stride();
+        crate::farbfeld::decode_into(
+            &self.data,
+            dst.as_strided_bytes_mut(),
+            stride,
+            crate::PixelLayout::Rgba16,
+            limits,
+            stop,
+        )?;
+        drop(dst);
+        sink.finish().map_err(sink_error)?;
+        Ok(OutputInfo::full_decode(width, height, descriptor))
+    }
+
+    /// Decode to the core output, borrowed from the input where the format
+    /// allows.
+    fn decode_internal(&self) -> crate::Result<crate::decode::DecodeOutput<'_>> {
+        let limits = self.effective_limits();
+        let stop: &dyn Stop = match &self.stop {
+            Some(s) => s,
+            None => &enough::Unstoppable,
+        };
+        crate::farbfeld::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)
+    }
 }
 
 impl zencodec::decode::Decode for FarbfeldDecoder<'_> {
     type Error = At<BitmapError>;
 
     fn decode(self) -> crate::Result<DecodeOutput> {
-        let limits = self.effective_limits();
-        let stop: &dyn Stop = match &self.stop {
-            Some(s) => s,
-            None => &enough::Unstoppable,
+        let decoded = self.decode_internal()?;
+        decode_output_from_internal(&decoded, ImageFormat::Farbfeld, self.effective_limits())
+    }
+}
+
+// ── FarbfeldFrameDecoder ─────────────────────────────────────────────
+
+/// Frame decoder for concatenated farbfeld frames.
+///
+/// Every frame replaces the whole canvas and has no duration; all frames
+/// must match the first frame's dimensions.
+pub struct FarbfeldFrameDecoder {
+    limits: Option<Limits>,
+    data: Vec<u8>,
+    offset: usize,
+    index: u32,
+    width: u32,
+    height: u32,
+    info: ImageInfo,
+    stop: Option<zencodec::StopToken>,
+    alloc_pref: AllocPref,
+    canvas: Option<PixelBuffer>,
+}
+
+impl zencodec::decode::AnimationFrameDecoder for FarbfeldFrameDecoder {
+    type Error = At<BitmapError>;
+
+    fn wrap_sink_error(err: zencodec::decode::SinkError) -> At<BitmapError> {
+        at!(BitmapError::invalid_data(err.to_string()))
+    }
+
+    fn info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    fn frame_count(&self) -> Option<u32> {
+        match self.info.sequence {
+            ImageSequence::Animation { frame_count, .. } => frame_count,
+            _ => None,
+        }
+    }
+
+    fn render_next_frame(
+        &mut self,
+        stop: Option<&dyn Stop>,
+    ) -> crate::Result<Option<zencodec::decode::AnimationFrame<'_>>> {
+        if self.offset == self.data.len() {
+            return Ok(None);
+        }
+        let stop: &dyn Stop = match (stop, &self.stop) {
+            (Some(s), _) => s,
+            (None, Some(s)) => s,
+            (None, None) => &enough::Unstoppable,
         };
-        let decoded =
-            crate::farbfeld::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)?;
-        decode_output_from_internal(&decoded, ImageFormat::Farbfeld)
+        let (decoded, end) = crate::farbfeld::stream::next_frame(
+            &self.data,
+            self.offset,
+            self.limits.as_ref(),
+            self.alloc_pref,
+            stop,
+        )?;
+        if (decoded.width, decoded.height) != (self.width, self.height) {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "farbfeld frame {} is {}x{}, expected {}x{}",
+                self.index,
+                decoded.width,
+                decoded.height,
+                self.width,
+                self.height
+            ))));
+        }
+        self.offset = end;
+        let index = self.index;
+        self.index += 1;
+        let canvas = self.canvas.insert(layout_to_pixel_buffer(&decoded)?);
+        Ok(Some(zencodec::decode::AnimationFrame::new(
+            canvas.as_slice(),
+            0,
+            index,
+        )))
+    }
+
+    fn render_next_frame_to_sink(
+        &mut self,
+        stop: Option<&dyn Stop>,
+        sink: &mut dyn zencodec::decode::DecodeRowSink,
+    ) -> crate::Result<Option<OutputInfo>> {
+        zencodec::helpers::copy_frame_to_sink(self, stop, sink)
     }
 }
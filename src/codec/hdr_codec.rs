@@ -227,11 +227,15 @@ impl zencodec::encode::Encoder for HdrEncoder {
             });
 
         if acc.width != rows.width() || acc.layout != layout {
-            return Err(at!(BitmapError::InvalidData(
-                "push_rows: width or pixel format changed".into(),
+            return Err(at!(BitmapError::invalid_data(
+                "push_rows: width or pixel format changed",
             )));
         }
 
+        if let Some(stop) = &self.stop {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+
         let bytes = rows.contiguous_bytes();
         acc.data.extend_from_slice(&bytes);
         acc.total_rows += rows.rows();
@@ -239,11 +243,9 @@ impl zencodec::encode::Encoder for HdrEncoder {
     }
 
     fn finish(self) -> crate::Result<EncodeOutput> {
-        let acc = self.accumulator.ok_or_else(|| {
-            at!(BitmapError::InvalidData(
-                "finish() without push_rows()".into()
-            ))
-        })?;
+        let acc = self
+            .accumulator
+            .ok_or_else(|| at!(BitmapError::invalid_data("finish() without push_rows()")))?;
 
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
@@ -397,9 +399,8 @@ impl<'a> zencodec::decode::DecodeJob<'a> for HdrDecodeJob {
         sink: &mut dyn zencodec::decode::DecodeRowSink,
         preferred: &[PixelDescriptor],
     ) -> Result<OutputInfo, Self::Error> {
-        zencodec::helpers::copy_decode_to_sink(self, data, sink, preferred, |e| {
-            at!(BitmapError::InvalidData(e.to_string()))
-        })
+        let decoder = self.decoder(data, preferred)?;
+        decoded_to_sink(&decoder.decode_internal()?, sink)
     }
 
     fn streaming_decoder(
@@ -446,7 +447,7 @@ impl<'a> zencodec::decode::DecodeJob<'a> for HdrDecodeJob {
         };
         let decoded =
             crate::hdr::decode_with_alloc_pref(&data, limits.as_ref(), self.alloc_pref, stop)?;
-        let pixels_owned: Vec<u8> = decoded.pixels().to_vec();
+        let pixels_owned: Vec<u8> = decoded.into_vec();
 
         Ok(HdrStreamingDecoder {
             info,
@@ -484,20 +485,25 @@ impl HdrDecoder<'_> {
     fn effective_limits(&self) -> Option<&Limits> {
         self.limits.as_ref().or(self.config.limits.as_ref())
     }
-}
-
-impl zencodec::decode::Decode for HdrDecoder<'_> {
-    type Error = At<BitmapError>;
 
-    fn decode(self) -> crate::Result<DecodeOutput> {
+    /// Decode to the core output, borrowed from the input where the format
+    /// allows.
+    fn decode_internal(&self) -> crate::Result<crate::decode::DecodeOutput<'_>> {
         let limits = self.effective_limits();
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
             None => &enough::Unstoppable,
         };
-        let decoded =
-            crate::hdr::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)?;
-        decode_output_from_internal(&decoded, ImageFormat::Hdr)
+        crate::hdr::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)
+    }
+}
+
+impl zencodec::decode::Decode for HdrDecoder<'_> {
+    type Error = At<BitmapError>;
+
+    fn decode(self) -> crate::Result<DecodeOutput> {
+        let decoded = self.decode_internal()?;
+        decode_output_from_internal(&decoded, ImageFormat::Hdr, self.effective_limits())
     }
 }
 
@@ -532,7 +538,7 @@ impl zencodec::decode::StreamingDecode for HdrStreamingDecoder {
             self.row_bytes,
             PixelDescriptor::RGBF32_LINEAR,
         )
-        .map_err_at(|inner| BitmapError::InvalidData(inner.to_string()))?;
+        .map_err_at(|inner| BitmapError::invalid_data(inner.to_string()))?;
 
         self.current_row += 1;
         Ok(Some((y, slice)))
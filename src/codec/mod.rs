@@ -9,6 +9,7 @@ use alloc::borrow::Cow;
 use alloc::string::ToString as _;
 use alloc::vec::Vec;
 use enough::Stop;
+use whereat::At;
 use zencodec::decode::{DecodeCapabilities, DecodeOutput, DecodePolicy, OutputInfo};
 use zencodec::encode::{EncodeCapabilities, EncodeOutput};
 use zencodec::{ImageFormat, ImageInfo, Metadata, ResourceLimits};
@@ -171,6 +172,9 @@ pub(crate) fn convert_limits(limits: &ResourceLimits) -> Limits {
         max_height: limits.max_height.map(u64::from),
         max_pixels: limits.max_pixels,
         max_memory_bytes: limits.max_memory_bytes,
+        max_file_size: limits.max_input_bytes,
+        max_duration: None,
+        on_alloc: None,
     }
 }
 
@@ -208,13 +212,23 @@ pub(crate) fn layout_to_descriptor(layout: crate::PixelLayout) -> PixelDescripto
         PixelLayout::Rgb8 => PixelDescriptor::RGB8_SRGB,
         PixelLayout::Rgba8 => PixelDescriptor::RGBA8_SRGB,
         PixelLayout::GrayF32 => PixelDescriptor::GRAYF32_LINEAR,
-        PixelLayout::RgbF32 => PixelDescriptor::RGBAF32_LINEAR,
-        PixelLayout::Bgr8 | PixelLayout::Bgrx8 => PixelDescriptor::RGB8_SRGB,
+        PixelLayout::RgbF32 | PixelLayout::RgbaF32 => PixelDescriptor::RGBAF32_LINEAR,
+        PixelLayout::Bgr8 | PixelLayout::Bgrx8 | PixelLayout::Rgbx8 => PixelDescriptor::RGB8_SRGB,
         PixelLayout::Bgra8 => PixelDescriptor::BGRA8_SRGB,
-        PixelLayout::Rgba16 => PixelDescriptor::RGBA16_SRGB,
+        PixelLayout::Rgba16 | PixelLayout::Rgba16Be => PixelDescriptor::RGBA16_SRGB,
+        PixelLayout::Rgb16 => PixelDescriptor::RGB16_SRGB,
+        PixelLayout::GrayA16 => PixelDescriptor::GRAYA16_SRGB,
+        PixelLayout::RgbPlanar8 | PixelLayout::RgbaPlanar8 | PixelLayout::RgbPlanarF32 => {
+            layout_to_descriptor(layout.interleaved())
+        }
     }
 }
 
+/// The native-endian `f32` at byte offset `i` of `c`.
+fn ne_f32(c: &[u8], i: usize) -> f32 {
+    f32::from_ne_bytes([c[i], c[i + 1], c[i + 2], c[i + 3]])
+}
+
 pub(crate) fn layout_to_pixel_buffer(
     decoded: &crate::decode::DecodeOutput<'_>,
 ) -> crate::Result<PixelBuffer> {
@@ -223,9 +237,27 @@ pub(crate) fn layout_to_pixel_buffer(
 
     let w = decoded.width as usize;
     let h = decoded.height as usize;
-    let bytes = decoded.pixels();
+    let bytes = decoded.packed_pixels();
+    let bytes = &*bytes;
 
     match decoded.layout {
+        PixelLayout::RgbPlanar8 | PixelLayout::RgbaPlanar8 | PixelLayout::RgbPlanarF32 => {
+            let (width, height) = (decoded.width, decoded.height);
+            let interleaved = crate::planar::interleave(
+                bytes,
+                width,
+                height,
+                decoded.layout,
+                &enough::Unstoppable,
+            )?;
+            let layout = decoded.layout.interleaved();
+            layout_to_pixel_buffer(&crate::decode::DecodeOutput::owned(
+                interleaved,
+                width,
+                height,
+                layout,
+            ))
+        }
         PixelLayout::Gray8 => {
             let pixels: &[rgb::Gray<u8>] = bytes.as_pixels();
             Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels.to_vec(), w, h)).into())
@@ -248,7 +280,7 @@ pub(crate) fn layout_to_pixel_buffer(
         PixelLayout::GrayF32 => {
             let pixels: Vec<rgb::Gray<f32>> = bytes
                 .chunks_exact(4)
-                .map(|c| rgb::Gray::new(f32::from_ne_bytes([c[0], c[1], c[2], c[3]])))
+                .map(|c| rgb::Gray::new(ne_f32(c, 0)))
                 .collect();
             Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
         }
@@ -256,11 +288,23 @@ pub(crate) fn layout_to_pixel_buffer(
             // RgbF32 → promote to RgbaF32 (PFM has no alpha concept)
             let pixels: Vec<rgb::Rgba<f32>> = bytes
                 .chunks_exact(12)
-                .map(|c| {
-                    let r = f32::from_ne_bytes([c[0], c[1], c[2], c[3]]);
-                    let g = f32::from_ne_bytes([c[4], c[5], c[6], c[7]]);
-                    let b = f32::from_ne_bytes([c[8], c[9], c[10], c[11]]);
-                    rgb::Rgba { r, g, b, a: 1.0 }
+                .map(|c| rgb::Rgba {
+                    r: ne_f32(c, 0),
+                    g: ne_f32(c, 4),
+                    b: ne_f32(c, 8),
+                    a: 1.0,
+                })
+                .collect();
+            Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
+        }
+        PixelLayout::RgbaF32 => {
+            let pixels: Vec<rgb::Rgba<f32>> = bytes
+                .chunks_exact(16)
+                .map(|c| rgb::Rgba {
+                    r: ne_f32(c, 0),
+                    g: ne_f32(c, 4),
+                    b: ne_f32(c, 8),
+                    a: ne_f32(c, 12),
                 })
                 .collect();
             Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
@@ -293,6 +337,18 @@ pub(crate) fn layout_to_pixel_buffer(
                 .collect();
             Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
         }
+        PixelLayout::Rgbx8 => {
+            // RGBX → RGB (strip padding byte)
+            let pixels: Vec<rgb::Rgb<u8>> = bytes
+                .chunks_exact(4)
+                .map(|c| rgb::Rgb {
+                    r: c[0],
+                    g: c[1],
+                    b: c[2],
+                })
+                .collect();
+            Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
+        }
         PixelLayout::Rgba16 => {
             let pixels: Vec<rgb::Rgba<u16>> = bytes
                 .chunks_exact(8)
@@ -305,17 +361,82 @@ pub(crate) fn layout_to_pixel_buffer(
                 .collect();
             Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
         }
+        PixelLayout::Rgb16 => {
+            let pixels: Vec<rgb::Rgb<u16>> = bytes
+                .chunks_exact(6)
+                .map(|c| rgb::Rgb {
+                    r: u16::from_ne_bytes([c[0], c[1]]),
+                    g: u16::from_ne_bytes([c[2], c[3]]),
+                    b: u16::from_ne_bytes([c[4], c[5]]),
+                })
+                .collect();
+            Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
+        }
+        PixelLayout::GrayA16 => {
+            let pixels: Vec<zenpixels::GrayAlpha16> = bytes
+                .chunks_exact(4)
+                .map(|c| {
+                    zenpixels::GrayAlpha16::new(
+                        u16::from_ne_bytes([c[0], c[1]]),
+                        u16::from_ne_bytes([c[2], c[3]]),
+                    )
+                })
+                .collect();
+            Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
+        }
+        PixelLayout::Rgba16Be => {
+            let pixels: Vec<rgb::Rgba<u16>> = bytes
+                .chunks_exact(8)
+                .map(|c| rgb::Rgba {
+                    r: u16::from_be_bytes([c[0], c[1]]),
+                    g: u16::from_be_bytes([c[2], c[3]]),
+                    b: u16::from_be_bytes([c[4], c[5]]),
+                    a: u16::from_be_bytes([c[6], c[7]]),
+                })
+                .collect();
+            Ok(PixelBuffer::from_imgvec(imgref::ImgVec::new(pixels, w, h)).into())
+        }
     }
 }
 
-/// Build a zencodec DecodeOutput from an internal DecodeOutput.
+/// Bytes alive at once while [`layout_to_pixel_buffer`] converts
+/// `decoded`: the decoded buffer (unless borrowed from the input), the
+/// packed copy of padded rows or interleaved copy of planar data, and the
+/// zencodec buffer. Saturates on overflow.
+fn conversion_working_bytes(decoded: &crate::decode::DecodeOutput<'_>) -> usize {
+    let pixels = decoded.width as usize * decoded.height as usize;
+    let held = if decoded.is_borrowed() {
+        0
+    } else {
+        decoded.pixels().len()
+    };
+    let copy = if decoded.layout.is_planar() {
+        pixels.saturating_mul(decoded.layout.interleaved().bytes_per_pixel())
+    } else if decoded.is_contiguous() {
+        0
+    } else {
+        pixels.saturating_mul(decoded.layout.bytes_per_pixel())
+    };
+    let output = pixels.saturating_mul(layout_to_descriptor(decoded.layout).bytes_per_pixel());
+    held.saturating_add(copy).saturating_add(output)
+}
+
+/// Build a zencodec DecodeOutput from an internal DecodeOutput, checking
+/// the conversion's buffers against `limits`' memory cap first.
 pub(crate) fn decode_output_from_internal(
     decoded: &crate::decode::DecodeOutput<'_>,
     format: ImageFormat,
+    limits: Option<&Limits>,
 ) -> crate::Result<DecodeOutput> {
+    crate::limits::check_working_memory(conversion_working_bytes(decoded), limits)?;
     let has_alpha = matches!(
         decoded.layout,
-        crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8 | crate::PixelLayout::Rgba16
+        crate::PixelLayout::Rgba8
+            | crate::PixelLayout::Bgra8
+            | crate::PixelLayout::Rgba16
+            | crate::PixelLayout::Rgba16Be
+            | crate::PixelLayout::GrayA16
+            | crate::PixelLayout::RgbaF32
     );
     let info = ImageInfo::new(decoded.width, decoded.height, format)
         .with_alpha(has_alpha)
@@ -324,6 +445,128 @@ pub(crate) fn decode_output_from_internal(
     Ok(DecodeOutput::new(pixels, info).with_source_encoding_details(BitmapSourceEncoding))
 }
 
+/// The descriptor [`layout_to_pixel_buffer`] gives `layout` pixels, which
+/// [`decoded_to_sink`] hands sinks too.
+pub(crate) fn output_descriptor(layout: crate::PixelLayout) -> PixelDescriptor {
+    use crate::PixelLayout;
+    use zenpixels::Pixel;
+    match layout {
+        PixelLayout::Gray8 => rgb::Gray::<u8>::DESCRIPTOR,
+        PixelLayout::Gray16 => rgb::Gray::<u16>::DESCRIPTOR,
+        PixelLayout::Rgb8 | PixelLayout::Bgr8 | PixelLayout::Bgrx8 | PixelLayout::Rgbx8 => {
+            rgb::Rgb::<u8>::DESCRIPTOR
+        }
+        PixelLayout::Rgba8 => rgb::Rgba::<u8>::DESCRIPTOR,
+        PixelLayout::GrayF32 => rgb::Gray::<f32>::DESCRIPTOR,
+        PixelLayout::RgbF32 | PixelLayout::RgbaF32 => rgb::Rgba::<f32>::DESCRIPTOR,
+        PixelLayout::Bgra8 => rgb::alt::BGRA::<u8>::DESCRIPTOR,
+        PixelLayout::Rgba16 | PixelLayout::Rgba16Be => rgb::Rgba::<u16>::DESCRIPTOR,
+        PixelLayout::Rgb16 => rgb::Rgb::<u16>::DESCRIPTOR,
+        PixelLayout::GrayA16 => zenpixels::GrayAlpha16::DESCRIPTOR,
+        PixelLayout::RgbPlanar8 | PixelLayout::RgbaPlanar8 | PixelLayout::RgbPlanarF32 => {
+            output_descriptor(layout.interleaved())
+        }
+    }
+}
+
+/// Convert one packed row of interleaved `layout` pixels into `dst`, a row
+/// of [`output_descriptor`] pixels, as [`layout_to_pixel_buffer`] does.
+fn write_row(layout: crate::PixelLayout, src: &[u8], dst: &mut [u8]) {
+    use crate::PixelLayout;
+    match layout {
+        PixelLayout::RgbF32 => {
+            for (d, s) in dst.chunks_exact_mut(16).zip(src.chunks_exact(12)) {
+                d[..12].copy_from_slice(s);
+                d[12..].copy_from_slice(&1.0f32.to_ne_bytes());
+            }
+        }
+        PixelLayout::Bgr8 | PixelLayout::Bgrx8 => {
+            for (d, s) in dst
+                .chunks_exact_mut(3)
+                .zip(src.chunks_exact(layout.bytes_per_pixel()))
+            {
+                d.copy_from_slice(&[s[2], s[1], s[0]]);
+            }
+        }
+        PixelLayout::Rgbx8 => {
+            for (d, s) in dst.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+                d.copy_from_slice(&s[..3]);
+            }
+        }
+        PixelLayout::Rgba16Be => {
+            for (d, s) in dst.chunks_exact_mut(2).zip(src.chunks_exact(2)) {
+                d.copy_from_slice(&u16::from_be_bytes([s[0], s[1]]).to_ne_bytes());
+            }
+        }
+        _ => dst.copy_from_slice(src),
+    }
+}
+
+/// A sink's refusal, as a decode error.
+pub(crate) fn sink_error(e: zencodec::decode::SinkError) -> At<BitmapError> {
+    whereat::at!(BitmapError::invalid_data(e.to_string()))
+}
+
+/// The sink's buffer for the whole `width`×`height` image, checked to
+/// match it, after announcing the image with `begin`.
+pub(crate) fn sink_buffer(
+    sink: &mut dyn zencodec::decode::DecodeRowSink,
+    width: u32,
+    height: u32,
+    descriptor: PixelDescriptor,
+) -> crate::Result<zenpixels::PixelSliceMut<'_>> {
+    sink.begin(width, height, descriptor).map_err(sink_error)?;
+    let dst = sink
+        .provide_next_buffer(0, height, width, descriptor)
+        .map_err(sink_error)?;
+    if dst.width() != width
+        || dst.rows() < height
+        || dst.descriptor().bytes_per_pixel() != descriptor.bytes_per_pixel()
+    {
+        return Err(whereat::at!(BitmapError::invalid_data(alloc::format!(
+            "sink buffer is {}x{} {:?}, expected {width}x{height} {descriptor:?}",
+            dst.width(),
+            dst.rows(),
+            dst.descriptor()
+        ))));
+    }
+    Ok(dst)
+}
+
+/// Write `decoded` into `sink` as a single strip, converting each row
+/// straight into the sink's buffer. Unlike
+/// [`zencodec::helpers::copy_decode_to_sink`], no zencodec `PixelData` copy
+/// of the image is built first.
+pub(crate) fn decoded_to_sink(
+    decoded: &crate::decode::DecodeOutput<'_>,
+    sink: &mut dyn zencodec::decode::DecodeRowSink,
+) -> crate::Result<OutputInfo> {
+    if decoded.layout.is_planar() {
+        let (width, height) = (decoded.width, decoded.height);
+        let interleaved = crate::planar::interleave(
+            &decoded.packed_pixels(),
+            width,
+            height,
+            decoded.layout,
+            &enough::Unstoppable,
+        )?;
+        let layout = decoded.layout.interleaved();
+        return decoded_to_sink(
+            &crate::decode::DecodeOutput::owned(interleaved, width, height, layout),
+            sink,
+        );
+    }
+    let (width, height) = (decoded.width, decoded.height);
+    let descriptor = output_descriptor(decoded.layout);
+    let mut dst = sink_buffer(sink, width, height, descriptor)?;
+    for (y, row) in (0..).zip(decoded.rows()) {
+        write_row(decoded.layout, row, dst.row_mut(y));
+    }
+    drop(dst);
+    sink.finish().map_err(sink_error)?;
+    Ok(OutputInfo::full_decode(width, height, descriptor))
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -594,6 +837,53 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// `max_memory_bytes` covers the zencodec output buffer alongside the
+    /// decoded one: a 2×2 PFM decodes to 48 bytes of `RgbF32` and converts
+    /// to 64 bytes of `RgbaF32`.
+    #[test]
+    fn memory_limit_counts_output_conversion() {
+        let floats: Vec<u8> = (0..12).flat_map(|i| (i as f32).to_ne_bytes()).collect();
+        let pfm = crate::encode_pfm(
+            &floats,
+            2,
+            2,
+            crate::PixelLayout::RgbF32,
+            enough::Unstoppable,
+        )
+        .unwrap();
+        let decode = |max_memory| {
+            PnmDecoderConfig::new()
+                .job()
+                .with_limits(ResourceLimits::none().with_max_memory(max_memory))
+                .decoder(Cow::Borrowed(&pfm), &[])
+                .unwrap()
+                .decode()
+        };
+        let err = decode(111).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::LimitExceeded(_)),
+            "{err}"
+        );
+        decode(112).unwrap();
+
+        // 8-bit PNM is borrowed from the input, so only the copy counts.
+        let ppm = crate::encode_ppm(
+            &[7; 12],
+            2,
+            2,
+            crate::PixelLayout::Rgb8,
+            enough::Unstoppable,
+        )
+        .unwrap();
+        PnmDecoderConfig::new()
+            .job()
+            .with_limits(ResourceLimits::none().with_max_memory(12))
+            .decoder(Cow::Borrowed(&ppm), &[])
+            .unwrap()
+            .decode()
+            .unwrap();
+    }
+
     #[test]
     fn decode_rgb_pixel_data() {
         let pixels = vec![
@@ -934,6 +1224,60 @@ mod tests {
         assert!(info.has_alpha, "farbfeld probe must report has_alpha");
     }
 
+    #[test]
+    fn farbfeld_frame_decoder_walks_concatenated_frames() {
+        use zencodec::decode::AnimationFrameDecoder as _;
+
+        let frame = |v: u8| {
+            crate::encode_farbfeld(
+                &[v; 2 * 2 * 4],
+                2,
+                2,
+                crate::PixelLayout::Rgba8,
+                enough::Unstoppable,
+            )
+            .unwrap()
+        };
+        let mut data = frame(10);
+        data.extend(frame(20));
+        data.extend(frame(30));
+
+        let mut dec = FarbfeldDecoderConfig::new()
+            .job()
+            .animation_frame_decoder(Cow::Owned(data.clone()), &[])
+            .unwrap();
+        assert_eq!(dec.frame_count(), Some(3));
+        for (i, v) in [10u16, 20, 30].into_iter().enumerate() {
+            let f = dec.render_next_frame(None).unwrap().unwrap();
+            assert_eq!(f.frame_index(), i as u32);
+            assert_eq!(f.pixels().descriptor(), PixelDescriptor::RGBA16);
+            let sample = u16::from_ne_bytes([f.pixels().row(0)[0], f.pixels().row(0)[1]]);
+            assert_eq!(sample, v * 257);
+        }
+        assert!(dec.render_next_frame(None).unwrap().is_none());
+
+        // A frame with different dimensions can't share the canvas.
+        data.extend(
+            crate::encode_farbfeld(
+                &[0; 4],
+                1,
+                1,
+                crate::PixelLayout::Rgba8,
+                enough::Unstoppable,
+            )
+            .unwrap(),
+        );
+        let mut dec = FarbfeldDecoderConfig::new()
+            .job()
+            .animation_frame_decoder(Cow::Owned(data), &[])
+            .unwrap();
+        assert_eq!(dec.frame_count(), Some(4));
+        for _ in 0..3 {
+            dec.render_next_frame(None).unwrap().unwrap();
+        }
+        assert!(dec.render_next_frame(None).is_err());
+    }
+
     #[cfg(feature = "qoi")]
     #[test]
     fn qoi_encode_decode_rgb8_roundtrip() {
@@ -1234,18 +1578,101 @@ mod tests {
     }
 
     #[test]
-    fn pnm_decode_descriptors_exclude_rgba16() {
-        // PNM decoder downscales non-gray 16-bit to 8-bit, so RGBA16 must
-        // not appear in the supported decode descriptors.
-        let descs = PnmDecoderConfig::supported_descriptors();
-        assert!(
-            !descs.contains(&PixelDescriptor::RGBA16_SRGB),
-            "PNM_DECODE_DESCRIPTORS should not contain RGBA16_SRGB"
+    fn pnm_keeps_16bit_color() {
+        for desc in [
+            PixelDescriptor::GRAY16_SRGB,
+            PixelDescriptor::RGB16_SRGB,
+            PixelDescriptor::RGBA16_SRGB,
+        ] {
+            assert!(PnmDecoderConfig::supported_descriptors().contains(&desc));
+            assert!(PnmEncoderConfig::supported_descriptors().contains(&desc));
+        }
+
+        // 16-bit P6 decodes to RGB16 instead of being downscaled.
+        let mut ppm = b"P6\n2 1\n65535\n".to_vec();
+        let samples = [0x0102u16, 0x0304, 0x0506, 0xfffe, 0x8001, 0x0000];
+        ppm.extend(samples.iter().flat_map(|v| v.to_be_bytes()));
+        let info = PnmDecoderConfig::new().job().output_info(&ppm).unwrap();
+        assert_eq!(info.native_format, PixelDescriptor::RGB16_SRGB);
+        let decoded = decode_bytes(&ppm);
+        let buf = decoded.into_buffer();
+        let img = buf.try_as_imgref::<rgb::Rgb<u16>>().unwrap();
+        assert_eq!(
+            img.buf()[0],
+            rgb::Rgb {
+                r: 0x0102,
+                g: 0x0304,
+                b: 0x0506
+            }
         );
-        // Gray16 IS preserved, so it should be present.
-        assert!(
-            descs.contains(&PixelDescriptor::GRAY16_SRGB),
-            "PNM_DECODE_DESCRIPTORS should contain GRAY16_SRGB"
+        assert_eq!(
+            img.buf()[1],
+            rgb::Rgb {
+                r: 0xfffe,
+                g: 0x8001,
+                b: 0
+            }
+        );
+
+        // RGBA16 input is written as 16-bit PAM and read back exactly.
+        let pixels: Vec<rgb::Rgba<u16>> = (0..6u16)
+            .map(|i| rgb::Rgba {
+                r: i * 10_000,
+                g: 65535 - i,
+                b: i * 257,
+                a: 1000 + i,
+            })
+            .collect();
+        let img = imgref::ImgVec::new(pixels.clone(), 3, 2);
+        let encoded = PnmEncoderConfig::new()
+            .job()
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        assert!(encoded.data().starts_with(b"P7\n"));
+        let decoded = decode_bytes(encoded.data());
+        let buf = decoded.into_buffer();
+        assert_eq!(
+            buf.try_as_imgref::<rgb::Rgba<u16>>().unwrap().buf(),
+            &pixels
+        );
+    }
+
+    #[test]
+    fn pnm_16bit_samples_fill_the_full_range() {
+        // maxval 1023: white is 65535 and 512 rounds to 32800, in the binary
+        // and ASCII paths alike.
+        let mut p6 = b"P6\n2 1\n1023\n".to_vec();
+        let samples = [1023u16, 1023, 1023, 512, 0, 2000];
+        p6.extend(samples.iter().flat_map(|v| v.to_be_bytes()));
+        let p3 = b"P3\n2 1\n1023\n1023 1023 1023 512 0 2000\n";
+        let expected = [
+            rgb::Rgb {
+                r: 65535u16,
+                g: 65535,
+                b: 65535,
+            },
+            rgb::Rgb {
+                r: 32800,
+                g: 0,
+                b: 65535,
+            },
+        ];
+        for data in [&p6[..], &p3[..]] {
+            let buf = decode_bytes(data).into_buffer();
+            assert_eq!(
+                buf.try_as_imgref::<rgb::Rgb<u16>>().unwrap().buf(),
+                &expected
+            );
+        }
+
+        let mut p5 = b"P5\n2 1\n4095\n".to_vec();
+        p5.extend([4095u16, 1].iter().flat_map(|v| v.to_be_bytes()));
+        let buf = decode_bytes(&p5).into_buffer();
+        assert_eq!(
+            buf.try_as_imgref::<rgb::Gray<u16>>().unwrap().buf(),
+            &[rgb::Gray::new(65535), rgb::Gray::new(16)]
         );
     }
 
@@ -1619,6 +2046,64 @@ mod tests {
         assert_eq!(QoiDecoderConfig::formats(), &[ImageFormat::Qoi]);
     }
 
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn bmp_streaming_encode_roundtrip() {
+        use zencodec::decode::{Decode, DecodeJob, DecoderConfig};
+        use zencodec::encode::{EncodeJob, Encoder, EncoderConfig};
+
+        let rows: Vec<rgb::Rgba<u8>> = (0..12u8)
+            .map(|i| rgb::Rgba {
+                r: i,
+                g: i * 2,
+                b: i * 3,
+                a: 255 - i,
+            })
+            .collect();
+        let mut encoder = BmpEncoderConfig::new().job().encoder().unwrap();
+        // Strips of 1, 2 and 1 rows, three pixels wide.
+        for strip in [&rows[..3], &rows[3..9], &rows[9..]] {
+            let img = imgref::ImgVec::new(strip.to_vec(), 3, strip.len() / 3);
+            encoder
+                .push_rows(PixelSlice::from(img.as_ref()).erase())
+                .unwrap();
+        }
+        let output = encoder.finish().unwrap();
+        assert_eq!(output.format(), ImageFormat::Bmp);
+
+        let decoded = BmpDecoderConfig::new()
+            .job()
+            .decoder(Cow::Borrowed(output.data()), &[])
+            .unwrap()
+            .decode()
+            .unwrap();
+        let buf = decoded.into_buffer();
+        let result = buf.try_as_imgref::<rgb::Rgba<u8>>().unwrap();
+        assert_eq!((result.width(), result.height()), (3, 4));
+        assert_eq!(result.buf(), &rows);
+    }
+
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn bmp_streaming_encode_rejects_changes() {
+        use zencodec::encode::{EncodeJob, Encoder, EncoderConfig};
+
+        let mut encoder = BmpEncoderConfig::new().job().encoder().unwrap();
+        let img = imgref::ImgVec::new(vec![rgb::Rgb { r: 1u8, g: 2, b: 3 }; 2], 2, 1);
+        encoder
+            .push_rows(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        let wider = imgref::ImgVec::new(vec![rgb::Rgb { r: 1u8, g: 2, b: 3 }; 3], 3, 1);
+        assert!(
+            encoder
+                .push_rows(PixelSlice::from(wider.as_ref()).erase())
+                .is_err()
+        );
+
+        let empty = BmpEncoderConfig::new().job().encoder().unwrap();
+        assert!(empty.finish().is_err());
+    }
+
     #[cfg(feature = "bmp")]
     #[test]
     fn bmp_capabilities_include_native_gray() {
@@ -1641,6 +2126,31 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn bmp_float_preference_decodes_linear() {
+        let bmp = crate::encode_bmp(
+            &[0, 128, 255],
+            1,
+            1,
+            crate::PixelLayout::Rgb8,
+            enough::Unstoppable,
+        )
+        .unwrap();
+        let core = crate::decode_bmp_linear(&bmp, enough::Unstoppable).unwrap();
+        let decoded = BmpDecoderConfig::new()
+            .job()
+            .decoder(Cow::Borrowed(&bmp), &[PixelDescriptor::RGBAF32_LINEAR])
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(decoded.descriptor().channel_type(), ChannelType::F32);
+        let buf = decoded.into_buffer();
+        let px = buf.try_as_imgref::<rgb::Rgba<f32>>().unwrap().buf()[0];
+        let f = |i: usize| ne_f32(core.pixels(), i);
+        assert_eq!([px.r, px.g, px.b, px.a], [f(0), f(4), f(8), 1.0]);
+    }
+
     // ── HDR zencodec trait tests ───────────────────────────────────────
 
     #[cfg(feature = "hdr")]
@@ -2214,4 +2724,506 @@ mod tests {
             .animation_frame_decoder(Cow::Borrowed(encoded.data()), &[]);
         assert!(result.is_err());
     }
+
+    /// A token that has already fired.
+    fn stopped() -> zencodec::StopToken {
+        struct AlreadyStopped;
+        impl Stop for AlreadyStopped {
+            fn check(&self) -> Result<(), enough::StopReason> {
+                Err(enough::StopReason::Cancelled)
+            }
+        }
+        zencodec::StopToken::new(AlreadyStopped)
+    }
+
+    fn is_cancelled<T>(result: crate::Result<T>) -> bool {
+        matches!(
+            result.map_err(|e| e.decompose().0),
+            Err(BitmapError::Cancelled(_))
+        )
+    }
+
+    /// `with_stop` reaches the core codecs: a fired token cancels encodes,
+    /// row pushes and decodes of every format.
+    #[test]
+    fn with_stop_cancels_every_codec() {
+        let pixels = vec![rgb::Rgb { r: 1u8, g: 2, b: 3 }; 4];
+        let img = imgref::ImgVec::new(pixels, 2, 2);
+        let slice = || PixelSlice::from(img.as_ref()).erase();
+
+        macro_rules! check {
+            ($enc:expr, $dec:expr, push_rows: $push:literal) => {{
+                let encoded = $enc.job().encoder().unwrap().encode(slice()).unwrap();
+                let encoder = $enc.job().with_stop(stopped()).encoder().unwrap();
+                assert!(
+                    is_cancelled(encoder.encode(slice())),
+                    "{:?} encode",
+                    encoded.format()
+                );
+                if $push {
+                    let mut encoder = $enc.job().with_stop(stopped()).encoder().unwrap();
+                    assert!(
+                        is_cancelled(encoder.push_rows(slice())),
+                        "{:?} push_rows",
+                        encoded.format()
+                    );
+                }
+                let decoder = $dec
+                    .job()
+                    .with_stop(stopped())
+                    .decoder(Cow::Borrowed(encoded.data()), &[])
+                    .unwrap();
+                assert!(
+                    is_cancelled(decoder.decode()),
+                    "{:?} decode",
+                    encoded.format()
+                );
+            }};
+        }
+
+        check!(PnmEncoderConfig::new(), PnmDecoderConfig::new(), push_rows: false);
+        check!(FarbfeldEncoderConfig::new(), FarbfeldDecoderConfig::new(), push_rows: false);
+        #[cfg(feature = "bmp")]
+        check!(BmpEncoderConfig::new(), BmpDecoderConfig::new(), push_rows: true);
+        #[cfg(feature = "qoi")]
+        check!(QoiEncoderConfig::new(), QoiDecoderConfig::new(), push_rows: true);
+        #[cfg(feature = "tga")]
+        check!(TgaEncoderConfig::new(), TgaDecoderConfig::new(), push_rows: true);
+        #[cfg(feature = "hdr")]
+        check!(HdrEncoderConfig::new(), HdrDecoderConfig::new(), push_rows: true);
+    }
+
+    /// A little-endian TIFF blob whose IFD0 holds `XResolution` and
+    /// `YResolution` (both `x`/1 and `y`/1) in `unit`.
+    #[cfg(feature = "bmp")]
+    fn exif_resolution(x: u32, y: u32, unit: u16) -> Vec<u8> {
+        let mut tiff = b"II*\0\x08\0\0\0\x03\0".to_vec();
+        // Three 12-byte entries and the next-IFD offset end at 50.
+        for (tag, kind, value) in [
+            (0x011A, 5, 50),
+            (0x011B, 5, 58),
+            (0x0128, 3, u32::from(unit)),
+        ] {
+            tiff.extend_from_slice(&u16::to_le_bytes(tag));
+            tiff.extend_from_slice(&u16::to_le_bytes(kind));
+            tiff.extend_from_slice(&1u32.to_le_bytes());
+            tiff.extend_from_slice(&u32::to_le_bytes(value));
+        }
+        tiff.extend_from_slice(&[0; 4]);
+        for value in [x, 1, y, 1] {
+            tiff.extend_from_slice(&value.to_le_bytes());
+        }
+        tiff
+    }
+
+    /// `with_metadata` puts the ICC profile in a V5 header and the EXIF
+    /// resolution in the BMP header, for whole-image and row encodes.
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn bmp_encode_writes_icc_and_dpi() {
+        assert!(BmpEncoderConfig::capabilities().icc());
+        let pixels = vec![rgb::Rgb { r: 1u8, g: 2, b: 3 }; 4];
+        let img = imgref::ImgVec::new(pixels, 2, 2);
+        let icc: &[u8] = b"not really an ICC profile";
+        let meta = Metadata::none()
+            .with_icc(icc)
+            .with_exif(exif_resolution(300, 150, 2));
+
+        let encoded = BmpEncoderConfig::new()
+            .job()
+            .with_metadata_policy(meta.clone(), zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        let mut encoder = BmpEncoderConfig::new()
+            .job()
+            .with_metadata_policy(meta, zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap();
+        encoder
+            .push_rows(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        let streamed = encoder.finish().unwrap();
+        assert_eq!(streamed.data(), encoded.data());
+
+        let probed = crate::probe_bmp(encoded.data()).unwrap();
+        assert_eq!(probed.header_variant, crate::BmpHeaderVariant::V5);
+        assert!(encoded.data().ends_with(icc));
+        assert!((probed.dpi_x.unwrap() - 300.0).abs() < 0.02);
+        assert!((probed.dpi_y.unwrap() - 150.0).abs() < 0.02);
+        let decoded = crate::decode(encoded.data(), enough::Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), [1, 2, 3].repeat(4));
+
+        // Centimeters, and a blob without resolution tags leaves 72 DPI.
+        let cm = Metadata::none().with_exif(exif_resolution(100, 100, 3));
+        let encoded = BmpEncoderConfig::new()
+            .job()
+            .with_metadata_policy(cm, zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        let probed = crate::probe_bmp(encoded.data()).unwrap();
+        assert!((probed.dpi_x.unwrap() - 254.0).abs() < 0.02);
+        assert_ne!(probed.header_variant, crate::BmpHeaderVariant::V5);
+        let artist = Metadata::none().with_artist("someone");
+        let encoded = BmpEncoderConfig::new()
+            .job()
+            .with_metadata_policy(artist, zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        let probed = crate::probe_bmp(encoded.data()).unwrap();
+        assert!((probed.dpi_x.unwrap() - 72.0).abs() < 0.02);
+    }
+
+    /// `with_metadata` writes EXIF artist and copyright as PNM header
+    /// comments, except in PFM.
+    #[test]
+    fn pnm_encode_writes_comments() {
+        let meta = Metadata::none()
+            .with_artist("A. Person\nwith a newline")
+            .with_copyright("CC0");
+        let pixels = vec![rgb::Rgb { r: 1u8, g: 2, b: 3 }; 4];
+        let img = imgref::ImgVec::new(pixels, 2, 2);
+        let encoded = PnmEncoderConfig::new()
+            .job()
+            .with_metadata_policy(meta.clone(), zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        assert!(
+            encoded.data().starts_with(
+                b"P6\n# Artist: A. Person with a newline\n# Copyright: CC0\n2 2\n255\n"
+            )
+        );
+        let decoded = crate::decode(encoded.data(), enough::Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), [1, 2, 3].repeat(4));
+
+        let pixels = vec![
+            rgb::Rgba {
+                r: 1u8,
+                g: 2,
+                b: 3,
+                a: 4
+            };
+            4
+        ];
+        let img = imgref::ImgVec::new(pixels, 2, 2);
+        let encoded = PnmEncoderConfig::new()
+            .job()
+            .with_metadata_policy(meta.clone(), zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        assert!(encoded.data().starts_with(b"P7\n# Artist: "));
+        let decoded = crate::decode(encoded.data(), enough::Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), [1, 2, 3, 4].repeat(4));
+
+        let pixels = vec![
+            rgb::Rgb {
+                r: 0.5f32,
+                g: 0.25,
+                b: 1.0
+            };
+            4
+        ];
+        let img = imgref::ImgVec::new(pixels, 2, 2);
+        let encoded = PnmEncoderConfig::new()
+            .job()
+            .with_metadata_policy(meta, zencodec::MetadataPolicy::PreserveExact)
+            .encoder()
+            .unwrap()
+            .encode(PixelSlice::from(img.as_ref()).erase())
+            .unwrap();
+        assert!(encoded.data().starts_with(b"PF\n2 2\n"));
+    }
+
+    #[test]
+    fn pnm_encoder_config_format_and_maxval() {
+        let pixels = vec![
+            rgb::Rgb {
+                r: 0u8,
+                g: 128,
+                b: 255
+            };
+            4
+        ];
+        let img = imgref::ImgVec::new(pixels, 2, 2);
+        let encode = |config: PnmEncoderConfig| {
+            config
+                .job()
+                .encoder()
+                .unwrap()
+                .encode(PixelSlice::from(img.as_ref()).erase())
+        };
+
+        let pam = encode(PnmEncoderConfig::new().with_format(crate::EncodeFormat::Pam)).unwrap();
+        assert!(pam.data().starts_with(b"P7\n"));
+        let decoded = crate::decode(pam.data(), enough::Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), [0, 128, 255].repeat(4));
+
+        let config = PnmEncoderConfig::new().with_maxval(65535);
+        assert_eq!(config.maxval(), Some(65535));
+        assert_eq!(config.is_lossless(), Some(true));
+        let ppm16 = encode(config).unwrap();
+        assert!(ppm16.data().starts_with(b"P6\n2 2\n65535\n"));
+        assert_eq!(
+            &ppm16.data()[ppm16.data().len() - 6..],
+            [0, 0, 128, 128, 255, 255]
+        );
+
+        let pam4 = encode(
+            PnmEncoderConfig::new()
+                .with_format(crate::EncodeFormat::Pam)
+                .with_maxval(15),
+        )
+        .unwrap();
+        assert!(pam4.data().windows(9).any(|w| w == b"MAXVAL 15"));
+        assert!(pam4.data().ends_with(&[0, 8, 15]));
+
+        assert!(encode(PnmEncoderConfig::new().with_format(crate::EncodeFormat::Pfm)).is_err());
+        assert!(
+            encode(PnmEncoderConfig::new().with_format(crate::EncodeFormat::Farbfeld)).is_err()
+        );
+        assert!(encode(PnmEncoderConfig::new().with_maxval(0)).is_err());
+    }
+
+    /// A sink that hands out one buffer with padding after every row.
+    struct PaddedSink {
+        buf: Vec<u8>,
+        stride: usize,
+        begun: Option<(u32, u32, PixelDescriptor)>,
+        finished: bool,
+    }
+
+    impl zencodec::decode::DecodeRowSink for PaddedSink {
+        fn begin(
+            &mut self,
+            width: u32,
+            height: u32,
+            descriptor: PixelDescriptor,
+        ) -> Result<(), zencodec::decode::SinkError> {
+            self.begun = Some((width, height, descriptor));
+            Ok(())
+        }
+
+        fn provide_next_buffer(
+            &mut self,
+            y: u32,
+            height: u32,
+            width: u32,
+            descriptor: PixelDescriptor,
+        ) -> Result<zenpixels::PixelSliceMut<'_>, zencodec::decode::SinkError> {
+            assert_eq!(y, 0);
+            let bpp = descriptor.bytes_per_pixel();
+            self.stride = (width as usize + 3) * bpp;
+            self.buf = vec![0xAB; self.stride * height as usize];
+            zenpixels::PixelSliceMut::new(&mut self.buf, width, height, self.stride, descriptor)
+                .map_err(|e| e.to_string().into())
+        }
+
+        fn finish(&mut self) -> Result<(), zencodec::decode::SinkError> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    /// `push_decoder` writes into the sink's (padded) buffer the same
+    /// pixels and descriptor `decode` returns.
+    #[test]
+    fn push_decoder_matches_decode() {
+        fn check<'a, C: DecoderConfig>(config: C, data: &'a [u8])
+        where
+            C::Job<'a>: DecodeJob<'a>,
+        {
+            let decoded = config
+                .clone()
+                .job()
+                .decoder(Cow::Borrowed(data), &[])
+                .unwrap()
+                .decode()
+                .unwrap();
+            let expected = decoded.pixels();
+            let mut sink = PaddedSink {
+                buf: Vec::new(),
+                stride: 0,
+                begun: None,
+                finished: false,
+            };
+            let info = config
+                .job()
+                .push_decoder(Cow::Borrowed(data), &mut sink, &[])
+                .unwrap();
+            let (width, height) = (expected.width(), expected.rows());
+            assert_eq!(sink.begun, Some((width, height, expected.descriptor())));
+            assert_eq!(
+                info,
+                OutputInfo::full_decode(width, height, expected.descriptor())
+            );
+            assert!(sink.finished);
+            let row_bytes = expected.row(0).len();
+            for y in 0..height {
+                let start = y as usize * sink.stride;
+                assert_eq!(
+                    &sink.buf[start..start + row_bytes],
+                    expected.row(y),
+                    "row {y}"
+                );
+            }
+        }
+
+        use crate::PixelLayout;
+        let stop = enough::Unstoppable;
+        let rgb: Vec<u8> = (0..5 * 3 * 3).map(|i| (i * 13) as u8).collect();
+        #[cfg(feature = "qoi")]
+        let rgba: Vec<u8> = (0..5 * 3 * 4).map(|i| (i * 7) as u8).collect();
+        let floats: Vec<u8> = (0..5 * 3 * 3)
+            .flat_map(|i| (i as f32 / 4.0).to_ne_bytes())
+            .collect();
+        let rgba16: Vec<u8> = (0..5 * 3 * 8).map(|i| (i * 5) as u8).collect();
+
+        check(
+            PnmDecoderConfig::new(),
+            &crate::encode_ppm(&rgb, 5, 3, PixelLayout::Rgb8, stop).unwrap(),
+        );
+        check(
+            PnmDecoderConfig::new(),
+            &crate::encode_pfm(&floats, 5, 3, PixelLayout::RgbF32, stop).unwrap(),
+        );
+        check(
+            PnmDecoderConfig::new(),
+            &crate::encode_pam(&rgba16[..30], 5, 3, PixelLayout::Gray16, stop).unwrap(),
+        );
+        check(
+            FarbfeldDecoderConfig::new(),
+            &crate::encode_farbfeld(&rgba16, 5, 3, PixelLayout::Rgba16, stop).unwrap(),
+        );
+        #[cfg(feature = "bmp")]
+        for data in [
+            &include_bytes!("../../tests/bmp-fixtures/pal8.bmp")[..],
+            include_bytes!("../../tests/bmp-fixtures/rgb24.bmp"),
+            include_bytes!("../../tests/bmp-fixtures/rgb16-565.bmp"),
+            include_bytes!("../../tests/bmp-fixtures/rgba32abf.bmp"),
+        ] {
+            check(BmpDecoderConfig::new(), data);
+        }
+        #[cfg(feature = "qoi")]
+        check(
+            QoiDecoderConfig::new(),
+            &crate::encode_qoi(&rgba, 5, 3, PixelLayout::Rgba8, stop).unwrap(),
+        );
+        #[cfg(feature = "tga")]
+        check(
+            TgaDecoderConfig::new(),
+            &crate::encode_tga(&rgb, 5, 3, PixelLayout::Bgr8, stop).unwrap(),
+        );
+        #[cfg(feature = "hdr")]
+        check(
+            HdrDecoderConfig::new(),
+            &crate::encode_hdr(&floats, 5, 3, PixelLayout::RgbF32, stop).unwrap(),
+        );
+    }
+
+    /// `decode_borrowed` returns 8-bit binary PNM pixels in place, matching
+    /// `decode`, and declines everything else.
+    #[test]
+    fn pnm_decode_borrowed_is_zero_copy() {
+        use crate::PixelLayout;
+        let stop = enough::Unstoppable;
+        let rgb: Vec<u8> = (0..4 * 3 * 3).map(|i| (i * 11) as u8).collect();
+        let rgba: Vec<u8> = (0..4 * 3 * 4).map(|i| (i * 5) as u8).collect();
+        for data in [
+            crate::encode_ppm(&rgb, 4, 3, PixelLayout::Rgb8, stop).unwrap(),
+            crate::encode_pgm(&rgb[..12], 4, 3, PixelLayout::Gray8, stop).unwrap(),
+            crate::encode_pam(&rgba, 4, 3, PixelLayout::Rgba8, stop).unwrap(),
+        ] {
+            let decoder = PnmDecoderConfig::new()
+                .job()
+                .decoder(Cow::Borrowed(&data), &[])
+                .unwrap();
+            let borrowed = decoder.decode_borrowed().unwrap().unwrap();
+            let bytes = borrowed.as_contiguous_bytes().unwrap();
+            assert!(data.ends_with(bytes));
+            assert_eq!(bytes.as_ptr(), data[data.len() - bytes.len()..].as_ptr());
+
+            let decoded = PnmDecoderConfig::new()
+                .job()
+                .decoder(Cow::Borrowed(&data), &[])
+                .unwrap()
+                .decode()
+                .unwrap();
+            assert_eq!(borrowed.descriptor(), decoded.pixels().descriptor());
+            assert_eq!(bytes, &*decoded.pixels().contiguous_bytes());
+        }
+
+        let floats: Vec<u8> = (0..4 * 3 * 3)
+            .flat_map(|i| (i as f32).to_ne_bytes())
+            .collect();
+        for data in [
+            crate::encode_pfm(&floats, 4, 3, PixelLayout::RgbF32, stop).unwrap(),
+            crate::encode_pam(&floats[..24], 4, 3, PixelLayout::Gray16, stop).unwrap(),
+            b"P3\n1 1\n255\n1 2 3\n".to_vec(),
+            b"P4\n8 1\n\xAA".to_vec(),
+        ] {
+            let decoder = PnmDecoderConfig::new()
+                .job()
+                .decoder(Cow::Borrowed(&data), &[])
+                .unwrap();
+            assert!(decoder.decode_borrowed().unwrap().is_none());
+        }
+    }
+
+    /// `BmpDecoderConfig` reaches the native API's permissiveness and byte
+    /// order settings; a job policy still overrides the permissiveness.
+    #[cfg(feature = "bmp")]
+    #[test]
+    fn bmp_decoder_config_settings() {
+        let rgb24 = include_bytes!("../../tests/bmp-fixtures/rgb24.bmp");
+        let truncated = &rgb24[..rgb24.len() - 100];
+        let decode = |config: BmpDecoderConfig, policy: Option<DecodePolicy>| {
+            let mut job = config.job();
+            if let Some(policy) = policy {
+                job = job.with_policy(policy);
+            }
+            job.decoder(Cow::Borrowed(truncated), &[]).unwrap().decode()
+        };
+        let permissive =
+            BmpDecoderConfig::new().with_permissiveness(crate::BmpPermissiveness::Permissive);
+        assert_eq!(
+            permissive.permissiveness(),
+            crate::BmpPermissiveness::Permissive
+        );
+        assert!(decode(BmpDecoderConfig::new(), None).is_err());
+        decode(permissive.clone(), None).unwrap();
+        assert!(decode(permissive, Some(DecodePolicy::strict())).is_err());
+
+        let rgba = include_bytes!("../../tests/bmp-fixtures/rgba32abf.bmp");
+        let decode = |config: BmpDecoderConfig| {
+            config
+                .job()
+                .decoder(Cow::Borrowed(&rgba[..]), &[])
+                .unwrap()
+                .decode()
+                .unwrap()
+        };
+        let native = BmpDecoderConfig::new().with_native_order(true);
+        assert!(native.native_order());
+        let info = native.clone().job().output_info(rgba).unwrap();
+        let (bgra, rgba) = (decode(native), decode(BmpDecoderConfig::new()));
+        assert_eq!(bgra.pixels().descriptor().layout(), ChannelLayout::Bgra);
+        assert_eq!(info.native_format.layout(), ChannelLayout::Bgra);
+        assert_eq!(rgba.pixels().descriptor().layout(), ChannelLayout::Rgba);
+        let swizzled: Vec<u8> = rgba
+            .pixels()
+            .contiguous_bytes()
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .collect();
+        assert_eq!(&*bgra.pixels().contiguous_bytes(), &swizzled[..]);
+    }
 }
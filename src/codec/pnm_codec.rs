@@ -29,25 +29,31 @@ static PNM_DECODE_CAPS: DecodeCapabilities = DecodeCapabilities::new()
     .with_enforces_max_memory(true)
     .with_enforces_max_input_bytes(true);
 
-// Note: U16 encode is not implemented — RGBA16_SRGB intentionally absent.
+// Note: U16 input is written as PAM, the only PNM sub-format the core
+// encoder writes at 16 bits.
 static PNM_ENCODE_DESCRIPTORS: &[PixelDescriptor] = &[
     PixelDescriptor::RGB8_SRGB,
     PixelDescriptor::RGBA8_SRGB,
     PixelDescriptor::GRAY8_SRGB,
     PixelDescriptor::BGRA8_SRGB,
+    PixelDescriptor::RGB16_SRGB,
+    PixelDescriptor::RGBA16_SRGB,
+    PixelDescriptor::GRAY16_SRGB,
     PixelDescriptor::RGBF32_LINEAR,
     PixelDescriptor::RGBAF32_LINEAR,
     PixelDescriptor::GRAYF32_LINEAR,
 ];
 
 // Note: RgbF32 is promoted to RgbaF32 in decode, so RGBF32_LINEAR is absent.
-// Note: RGBA16_SRGB is absent because the PNM decoder downscales non-gray
-// 16-bit to 8-bit (only Gray16 is preserved at 16-bit).
+// Note: unlike the flat `decode`, which downscales 16-bit color to 8-bit,
+// the zencodec decoder keeps 16-bit PPM and PAM at 16 bits.
 static PNM_DECODE_DESCRIPTORS: &[PixelDescriptor] = &[
     PixelDescriptor::RGB8_SRGB,
     PixelDescriptor::RGBA8_SRGB,
     PixelDescriptor::GRAY8_SRGB,
     PixelDescriptor::GRAY16_SRGB,
+    PixelDescriptor::RGB16_SRGB,
+    PixelDescriptor::RGBA16_SRGB,
     PixelDescriptor::BGRA8_SRGB,
     PixelDescriptor::RGBAF32_LINEAR,
     PixelDescriptor::GRAYF32_LINEAR,
@@ -62,10 +68,15 @@ static PNM_DECODE_DESCRIPTORS: &[PixelDescriptor] = &[
 /// Encoding configuration for PNM formats.
 ///
 /// Implements [`zencodec::encode::EncoderConfig`] for the PNM family.
-/// Default output: PPM for RGB, PGM for Gray, PAM for RGBA, PFM for float.
+/// Default output: PPM for RGB, PGM for Gray, PAM for RGBA and 16-bit,
+/// PFM for float.
+/// [`with_format`](Self::with_format) and [`with_maxval`](Self::with_maxval)
+/// override the sub-format and sample range.
 #[derive(Clone, Debug)]
 pub struct PnmEncoderConfig {
     limits: ResourceLimits,
+    format: Option<crate::EncodeFormat>,
+    maxval: Option<u16>,
 }
 
 impl Default for PnmEncoderConfig {
@@ -79,8 +90,41 @@ impl PnmEncoderConfig {
     pub fn new() -> Self {
         Self {
             limits: ResourceLimits::none(),
+            format: None,
+            maxval: None,
         }
     }
+
+    /// Write every image as this sub-format ([`Pgm`](crate::EncodeFormat::Pgm),
+    /// [`Ppm`](crate::EncodeFormat::Ppm), [`Pam`](crate::EncodeFormat::Pam) or
+    /// [`Pfm`](crate::EncodeFormat::Pfm)) instead of picking one from the
+    /// pixel descriptor — say, PAM even for RGB. The pixels are converted as
+    /// the flat `encode_*` functions do (PGM takes the luma, PPM drops
+    /// alpha); a format that cannot hold the descriptor at all, such as PFM
+    /// for 8-bit input, fails to encode, as do the non-PNM formats.
+    pub fn with_format(mut self, format: crate::EncodeFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Write integer samples (PGM, PPM, PAM) scaled to `0..=maxval`
+    /// instead of 0–255, with rounding; above 255 each sample takes two
+    /// big-endian bytes, so 65535 stores 8-bit input as exact 16-bit.
+    /// PFM output ignores it. A maxval of 0 fails to encode.
+    pub fn with_maxval(mut self, maxval: u16) -> Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// The forced sub-format, if any.
+    pub fn format(&self) -> Option<crate::EncodeFormat> {
+        self.format
+    }
+
+    /// The output maxval, if set.
+    pub fn maxval(&self) -> Option<u16> {
+        self.maxval
+    }
 }
 
 impl zencodec::encode::EncoderConfig for PnmEncoderConfig {
@@ -100,7 +144,7 @@ impl zencodec::encode::EncoderConfig for PnmEncoderConfig {
     }
 
     fn is_lossless(&self) -> Option<bool> {
-        Some(true)
+        Some(self.maxval.is_none_or(|maxval| maxval >= 255))
     }
 
     fn estimate_encode_resources(
@@ -117,6 +161,7 @@ impl zencodec::encode::EncoderConfig for PnmEncoderConfig {
             config: self,
             limits: None,
             stop: None,
+            metadata: Metadata::none(),
         }
     }
 }
@@ -124,10 +169,16 @@ impl zencodec::encode::EncoderConfig for PnmEncoderConfig {
 // ── PnmEncodeJob ─────────────────────────────────────────────────────
 
 /// Per-operation PNM encode job.
+///
+/// [`with_metadata`](zencodec::encode::EncodeJob::with_metadata) writes
+/// the EXIF `Artist` and `Copyright` tags as `#` comments in PGM, PPM and
+/// PAM headers. PFM headers take no comments, and PNM has no place for
+/// ICC profiles or the rest of the EXIF data.
 pub struct PnmEncodeJob {
     config: PnmEncoderConfig,
     limits: Option<ResourceLimits>,
     stop: Option<zencodec::StopToken>,
+    metadata: Metadata,
 }
 
 impl zencodec::encode::EncodeJob for PnmEncodeJob {
@@ -140,7 +191,8 @@ impl zencodec::encode::EncodeJob for PnmEncodeJob {
         self
     }
 
-    fn with_metadata(self, _meta: Metadata) -> Self {
+    fn with_metadata(mut self, meta: Metadata) -> Self {
+        self.metadata = meta;
         self
     }
 
@@ -154,6 +206,7 @@ impl zencodec::encode::EncodeJob for PnmEncodeJob {
             config: self.config,
             limits: self.limits,
             stop: self.stop,
+            metadata: self.metadata,
         })
     }
 
@@ -171,6 +224,7 @@ pub struct PnmEncoder {
     config: PnmEncoderConfig,
     limits: Option<ResourceLimits>,
     stop: Option<zencodec::StopToken>,
+    metadata: Metadata,
 }
 
 impl PnmEncoder {
@@ -188,6 +242,37 @@ impl PnmEncoder {
             }
         })
     }
+
+    /// `encoded` with a header comment for each of the metadata's EXIF
+    /// `Artist` and `Copyright` tags, placed after the magic number. Line
+    /// breaks in the text become spaces.
+    fn with_comments(&self, encoded: Vec<u8>) -> Vec<u8> {
+        let Some(exif) = self
+            .metadata
+            .exif
+            .as_deref()
+            .and_then(zencodec::exif::Exif::parse)
+        else {
+            return encoded;
+        };
+        // PFM readers take the next token after the magic as the width.
+        if encoded.starts_with(b"Pf") || encoded.starts_with(b"PF") {
+            return encoded;
+        }
+        let mut comments = alloc::string::String::new();
+        for (label, text) in [("Artist", exif.artist()), ("Copyright", exif.copyright())] {
+            if let Some(text) = text {
+                let text = text.replace(['\r', '\n'], " ");
+                comments.push_str(&alloc::format!("# {label}: {text}\n"));
+            }
+        }
+        // Every magic number is two bytes and a newline.
+        let mut out = Vec::with_capacity(encoded.len() + comments.len());
+        out.extend_from_slice(&encoded[..3]);
+        out.extend_from_slice(comments.as_bytes());
+        out.extend_from_slice(&encoded[3..]);
+        out
+    }
 }
 
 impl zencodec::encode::Encoder for PnmEncoder {
@@ -219,67 +304,49 @@ impl zencodec::encode::Encoder for PnmEncoder {
             limits.check(w, h)?;
         }
 
-        match (desc.channel_type(), desc.layout()) {
-            (ChannelType::U8, ChannelLayout::Rgb) => {
-                let bytes = pixels.contiguous_bytes();
-                let encoded = pnm::encode(
-                    &bytes,
-                    w,
-                    h,
-                    crate::PixelLayout::Rgb8,
-                    pnm::PnmFormat::Ppm,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
-            }
-            (ChannelType::U8, ChannelLayout::Rgba) => {
-                let bytes = pixels.contiguous_bytes();
-                let encoded = pnm::encode(
-                    &bytes,
-                    w,
-                    h,
-                    crate::PixelLayout::Rgba8,
-                    pnm::PnmFormat::Pam,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
-            }
-            (ChannelType::U8, ChannelLayout::Gray) => {
-                let bytes = pixels.contiguous_bytes();
-                let encoded = pnm::encode(
-                    &bytes,
-                    w,
-                    h,
-                    crate::PixelLayout::Gray8,
-                    pnm::PnmFormat::Pgm,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
-            }
-            (ChannelType::U8, ChannelLayout::Bgra) => {
-                let bytes = pixels.contiguous_bytes();
-                let encoded = pnm::encode(
-                    &bytes,
-                    w,
-                    h,
-                    crate::PixelLayout::Bgra8,
-                    pnm::PnmFormat::Ppm,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
-            }
-            (ChannelType::F32, ChannelLayout::Rgb) => {
-                let bytes = pixels.contiguous_bytes();
-                let encoded = pnm::encode(
-                    &bytes,
-                    w,
-                    h,
-                    crate::PixelLayout::RgbF32,
-                    pnm::PnmFormat::Pfm,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
-            }
+        // The default sub-format for each descriptor, and the pixels to
+        // hand the core encoder.
+        let (layout, default_format, bytes) = match (desc.channel_type(), desc.layout()) {
+            (ChannelType::U8, ChannelLayout::Rgb) => (
+                crate::PixelLayout::Rgb8,
+                crate::EncodeFormat::Ppm,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::U8, ChannelLayout::Rgba) => (
+                crate::PixelLayout::Rgba8,
+                crate::EncodeFormat::Pam,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::U8, ChannelLayout::Gray) => (
+                crate::PixelLayout::Gray8,
+                crate::EncodeFormat::Pgm,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::U8, ChannelLayout::Bgra) => (
+                crate::PixelLayout::Bgra8,
+                crate::EncodeFormat::Ppm,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::U16, ChannelLayout::Rgb) => (
+                crate::PixelLayout::Rgb16,
+                crate::EncodeFormat::Pam,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::U16, ChannelLayout::Rgba) => (
+                crate::PixelLayout::Rgba16,
+                crate::EncodeFormat::Pam,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::U16, ChannelLayout::Gray) => (
+                crate::PixelLayout::Gray16,
+                crate::EncodeFormat::Pam,
+                pixels.contiguous_bytes(),
+            ),
+            (ChannelType::F32, ChannelLayout::Rgb) => (
+                crate::PixelLayout::RgbF32,
+                crate::EncodeFormat::Pfm,
+                pixels.contiguous_bytes(),
+            ),
             (ChannelType::F32, ChannelLayout::Rgba) => {
                 // PFM has no alpha channel — drop alpha and write PFM color.
                 let bpp = desc.bytes_per_pixel();
@@ -290,33 +357,44 @@ impl zencodec::encode::Encoder for PnmEncoder {
                         rgb_bytes.extend_from_slice(&chunk[..12]);
                     }
                 }
-                let encoded = pnm::encode(
-                    &rgb_bytes,
-                    w,
-                    h,
+                (
                     crate::PixelLayout::RgbF32,
-                    pnm::PnmFormat::Pfm,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
+                    crate::EncodeFormat::Pfm,
+                    Cow::Owned(rgb_bytes),
+                )
             }
-            (ChannelType::F32, ChannelLayout::Gray) => {
-                let bytes = pixels.contiguous_bytes();
-                let encoded = pnm::encode(
-                    &bytes,
-                    w,
-                    h,
-                    crate::PixelLayout::GrayF32,
-                    pnm::PnmFormat::Pfm,
-                    stop,
-                )?;
-                Ok(EncodeOutput::new(encoded, ImageFormat::Pnm))
+            (ChannelType::F32, ChannelLayout::Gray) => (
+                crate::PixelLayout::GrayF32,
+                crate::EncodeFormat::Pfm,
+                pixels.contiguous_bytes(),
+            ),
+            _ => {
+                return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                    "unsupported pixel format: {:?}",
+                    desc
+                ))));
             }
-            _ => Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
-                "unsupported pixel format: {:?}",
-                desc
-            )))),
+        };
+        let format = match self.config.format.unwrap_or(default_format) {
+            crate::EncodeFormat::Pgm => pnm::PnmFormat::Pgm,
+            crate::EncodeFormat::Ppm => pnm::PnmFormat::Ppm,
+            crate::EncodeFormat::Pam => pnm::PnmFormat::Pam,
+            crate::EncodeFormat::Pfm => pnm::PnmFormat::Pfm,
+            other => {
+                return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                    "{other:?} is not a PNM format"
+                ))));
+            }
+        };
+        let mut options = pnm::PnmEncodeOptions::new();
+        if let Some(maxval) = self.config.maxval {
+            options = options.with_maxval(maxval);
         }
+        let encoded = pnm::encode_with(&bytes, w, h, layout, format, &options, stop)?;
+        Ok(EncodeOutput::new(
+            self.with_comments(encoded),
+            ImageFormat::Pnm,
+        ))
     }
 }
 
@@ -429,7 +507,7 @@ impl<'a> zencodec::decode::DecodeJob<'a> for PnmDecodeJob {
             header.layout,
             crate::PixelLayout::Rgba8 | crate::PixelLayout::Bgra8 | crate::PixelLayout::Rgba16
         );
-        let native_format = layout_to_descriptor(header.layout);
+        let native_format = layout_to_descriptor(header.wide_layout());
         Ok(
             OutputInfo::full_decode(header.width, header.height, native_format)
                 .with_alpha(has_alpha),
@@ -464,9 +542,8 @@ impl<'a> zencodec::decode::DecodeJob<'a> for PnmDecodeJob {
         sink: &mut dyn zencodec::decode::DecodeRowSink,
         preferred: &[PixelDescriptor],
     ) -> Result<OutputInfo, Self::Error> {
-        zencodec::helpers::copy_decode_to_sink(self, data, sink, preferred, |e| {
-            at!(BitmapError::InvalidData(e.to_string()))
-        })
+        let decoder = self.decoder(data, preferred)?;
+        decoded_to_sink(&decoder.decode_internal()?, sink)
     }
 
     fn streaming_decoder(
@@ -505,19 +582,62 @@ impl PnmDecoder<'_> {
     fn effective_limits(&self) -> Option<&Limits> {
         self.limits.as_ref().or(self.config.limits.as_ref())
     }
-}
-
-impl zencodec::decode::Decode for PnmDecoder<'_> {
-    type Error = At<BitmapError>;
 
-    fn decode(self) -> crate::Result<DecodeOutput> {
+    /// Decode to the core output, borrowed from the input where the format
+    /// allows.
+    fn decode_internal(&self) -> crate::Result<crate::decode::DecodeOutput<'_>> {
         let limits = self.effective_limits();
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
             None => &enough::Unstoppable,
         };
-        let decoded =
-            crate::pnm::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)?;
-        decode_output_from_internal(&decoded, ImageFormat::Pnm)
+        // Unlike the flat `decode`, keep 16-bit color at 16 bits.
+        crate::pnm::decode_with_depth(&self.data, limits, self.alloc_pref, true, stop)
+    }
+}
+
+impl PnmDecoder<'_> {
+    /// Decode without copying. Binary PGM, PPM and PAM files with 8-bit
+    /// samples (maxval 255) store their pixels as zencodec lays them out, so
+    /// this returns the input bytes after the header as a [`PixelSlice`],
+    /// with the same descriptor [`decode`](zencodec::decode::Decode::decode)
+    /// would give them.
+    ///
+    /// Returns `Ok(None)` for every other variant (PBM, ASCII, 16-bit and
+    /// PFM data) after parsing only the header; `decode` those instead.
+    pub fn decode_borrowed(&self) -> crate::Result<Option<PixelSlice<'_>>> {
+        let header = pnm::decode::parse_header(&self.data)?;
+        let zero_copy = matches!(self.data[1], b'5' | b'6' | b'7')
+            && header.maxval == 255
+            && matches!(
+                header.layout,
+                crate::PixelLayout::Gray8 | crate::PixelLayout::Rgb8 | crate::PixelLayout::Rgba8
+            )
+            && header.depth as usize == header.layout.channels();
+        if !zero_copy {
+            return Ok(None);
+        }
+        let decoded = self.decode_internal()?;
+        let Some(pixels) = decoded.borrowed_pixels() else {
+            return Ok(None);
+        };
+        let slice = PixelSlice::new(
+            pixels,
+            decoded.width,
+            decoded.height,
+            decoded.stride(),
+            output_descriptor(decoded.layout),
+        )
+        .map_err(|e| at!(BitmapError::invalid_data(e.to_string())))?;
+        Ok(Some(slice))
+    }
+}
+
+impl zencodec::decode::Decode for PnmDecoder<'_> {
+    type Error = At<BitmapError>;
+
+    fn decode(self) -> crate::Result<DecodeOutput> {
+        let decoded = self.decode_internal()?;
+        decode_output_from_internal(&decoded, ImageFormat::Pnm, self.effective_limits())
     }
 }
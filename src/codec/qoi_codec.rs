@@ -261,11 +261,15 @@ impl zencodec::encode::Encoder for QoiEncoder {
             });
 
         if acc.width != rows.width() || acc.channels != channels {
-            return Err(at!(BitmapError::InvalidData(
-                "push_rows: width or channel count changed".into(),
+            return Err(at!(BitmapError::invalid_data(
+                "push_rows: width or channel count changed",
             )));
         }
 
+        if let Some(stop) = &self.stop {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+
         let bytes = rows.contiguous_bytes();
         if acc.needs_swizzle {
             // BGRA → RGBA swizzle
@@ -283,25 +287,20 @@ impl zencodec::encode::Encoder for QoiEncoder {
     }
 
     fn finish(self) -> crate::Result<EncodeOutput> {
-        let acc = self.accumulator.ok_or_else(|| {
-            at!(BitmapError::InvalidData(
-                "finish() without push_rows()".into()
-            ))
-        })?;
-
-        let colors = if acc.channels == 4 {
-            crate::qoi::rapid_qoi::Colors::SrgbLinA
+        let acc = self
+            .accumulator
+            .ok_or_else(|| at!(BitmapError::invalid_data("finish() without push_rows()")))?;
+
+        let layout = if acc.channels == 4 {
+            crate::PixelLayout::Rgba8
         } else {
-            crate::qoi::rapid_qoi::Colors::Srgb
+            crate::PixelLayout::Rgb8
         };
-        let qoi = crate::qoi::rapid_qoi::Qoi {
-            width: acc.width,
-            height: acc.total_rows,
-            colors,
+        let stop: &dyn Stop = match &self.stop {
+            Some(s) => s,
+            None => &enough::Unstoppable,
         };
-        let encoded = qoi
-            .encode_alloc(&acc.data)
-            .map_err(|e| at!(BitmapError::InvalidData(e.to_string())))?;
+        let encoded = crate::qoi::encode(&acc.data, acc.width, acc.total_rows, layout, stop)?;
         Ok(EncodeOutput::new(encoded, ImageFormat::Qoi))
     }
 }
@@ -455,9 +454,8 @@ impl<'a> zencodec::decode::DecodeJob<'a> for QoiDecodeJob {
         sink: &mut dyn zencodec::decode::DecodeRowSink,
         preferred: &[PixelDescriptor],
     ) -> Result<OutputInfo, Self::Error> {
-        zencodec::helpers::copy_decode_to_sink(self, data, sink, preferred, |e| {
-            at!(BitmapError::InvalidData(e.to_string()))
-        })
+        let decoder = self.decoder(data, preferred)?;
+        decoded_to_sink(&decoder.decode_internal()?, sink)
     }
 
     fn streaming_decoder(
@@ -533,20 +531,25 @@ impl QoiDecoder<'_> {
     fn effective_limits(&self) -> Option<&Limits> {
         self.limits.as_ref().or(self.config.limits.as_ref())
     }
-}
 
-impl zencodec::decode::Decode for QoiDecoder<'_> {
-    type Error = At<BitmapError>;
-
-    fn decode(self) -> crate::Result<DecodeOutput> {
+    /// Decode to the core output, borrowed from the input where the format
+    /// allows.
+    fn decode_internal(&self) -> crate::Result<crate::decode::DecodeOutput<'_>> {
         let limits = self.effective_limits();
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
             None => &enough::Unstoppable,
         };
-        let decoded =
-            crate::qoi::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)?;
-        decode_output_from_internal(&decoded, ImageFormat::Qoi)
+        crate::qoi::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)
+    }
+}
+
+impl zencodec::decode::Decode for QoiDecoder<'_> {
+    type Error = At<BitmapError>;
+
+    fn decode(self) -> crate::Result<DecodeOutput> {
+        let decoded = self.decode_internal()?;
+        decode_output_from_internal(&decoded, ImageFormat::Qoi, self.effective_limits())
     }
 }
 
@@ -617,19 +620,19 @@ impl zencodec::decode::StreamingDecode for QoiStreamingDecoder<'_> {
         let encoded = self
             .data
             .get(self.byte_offset..)
-            .ok_or_else(|| at!(BitmapError::UnexpectedEof))?;
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
 
         if self.has_alpha {
             let consumed = self
                 .state_rgba
                 .decode_into(encoded, &mut self.row_buf)
-                .map_err(|()| at!(BitmapError::UnexpectedEof))?;
+                .map_err(|()| at!(BitmapError::unexpected_eof()))?;
             self.byte_offset += consumed;
         } else {
             let consumed = self
                 .state_rgb
                 .decode_into(encoded, &mut self.row_buf)
-                .map_err(|()| at!(BitmapError::UnexpectedEof))?;
+                .map_err(|()| at!(BitmapError::unexpected_eof()))?;
             self.byte_offset += consumed;
         }
 
@@ -638,7 +641,7 @@ impl zencodec::decode::StreamingDecode for QoiStreamingDecoder<'_> {
 
         let stride = self.row_buf.len();
         let slice = PixelSlice::new(&self.row_buf, self.width, 1, stride, self.descriptor)
-            .map_err_at(|inner| BitmapError::InvalidData(inner.to_string()))?;
+            .map_err_at(|inner| BitmapError::invalid_data(inner.to_string()))?;
 
         Ok(Some((y, slice)))
     }
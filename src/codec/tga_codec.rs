@@ -243,11 +243,15 @@ impl zencodec::encode::Encoder for TgaEncoder {
             });
 
         if acc.width != rows.width() || acc.layout != layout {
-            return Err(at!(BitmapError::InvalidData(
-                "push_rows: width or pixel format changed".into(),
+            return Err(at!(BitmapError::invalid_data(
+                "push_rows: width or pixel format changed",
             )));
         }
 
+        if let Some(stop) = &self.stop {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+
         let bytes = rows.contiguous_bytes();
         acc.data.extend_from_slice(&bytes);
         acc.total_rows += rows.rows();
@@ -255,11 +259,9 @@ impl zencodec::encode::Encoder for TgaEncoder {
     }
 
     fn finish(self) -> crate::Result<EncodeOutput> {
-        let acc = self.accumulator.ok_or_else(|| {
-            at!(BitmapError::InvalidData(
-                "finish() without push_rows()".into()
-            ))
-        })?;
+        let acc = self
+            .accumulator
+            .ok_or_else(|| at!(BitmapError::invalid_data("finish() without push_rows()")))?;
 
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
@@ -434,9 +436,8 @@ impl<'a> zencodec::decode::DecodeJob<'a> for TgaDecodeJob {
         sink: &mut dyn zencodec::decode::DecodeRowSink,
         preferred: &[PixelDescriptor],
     ) -> Result<OutputInfo, Self::Error> {
-        zencodec::helpers::copy_decode_to_sink(self, data, sink, preferred, |e| {
-            at!(BitmapError::InvalidData(e.to_string()))
-        })
+        let decoder = self.decoder(data, preferred)?;
+        decoded_to_sink(&decoder.decode_internal()?, sink)
     }
 
     fn streaming_decoder(
@@ -478,7 +479,7 @@ impl<'a> zencodec::decode::DecodeJob<'a> for TgaDecodeJob {
             .with_cicp(zencodec::Cicp::SRGB)
             .with_source_encoding_details(BitmapSourceEncoding);
 
-        let pixels_owned = decoded.pixels().to_vec();
+        let pixels_owned = decoded.into_vec();
 
         Ok(TgaStreamingDecoder {
             info,
@@ -517,20 +518,25 @@ impl TgaDecoder<'_> {
     fn effective_limits(&self) -> Option<&Limits> {
         self.limits.as_ref().or(self.config.limits.as_ref())
     }
-}
-
-impl zencodec::decode::Decode for TgaDecoder<'_> {
-    type Error = At<BitmapError>;
 
-    fn decode(self) -> crate::Result<DecodeOutput> {
+    /// Decode to the core output, borrowed from the input where the format
+    /// allows.
+    fn decode_internal(&self) -> crate::Result<crate::decode::DecodeOutput<'_>> {
         let limits = self.effective_limits();
         let stop: &dyn Stop = match &self.stop {
             Some(s) => s,
             None => &enough::Unstoppable,
         };
-        let decoded =
-            crate::tga::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)?;
-        decode_output_from_internal(&decoded, ImageFormat::Tga)
+        crate::tga::decode_with_alloc_pref(&self.data, limits, self.alloc_pref, stop)
+    }
+}
+
+impl zencodec::decode::Decode for TgaDecoder<'_> {
+    type Error = At<BitmapError>;
+
+    fn decode(self) -> crate::Result<DecodeOutput> {
+        let decoded = self.decode_internal()?;
+        decode_output_from_internal(&decoded, ImageFormat::Tga, self.effective_limits())
     }
 }
 
@@ -563,7 +569,7 @@ impl zencodec::decode::StreamingDecode for TgaStreamingDecoder {
         let row_data = &self.decoded_bytes[offset..offset + self.row_bytes];
 
         let slice = PixelSlice::new(row_data, self.width, 1, self.row_bytes, self.descriptor)
-            .map_err_at(|inner| BitmapError::InvalidData(inner.to_string()))?;
+            .map_err_at(|inner| BitmapError::invalid_data(inner.to_string()))?;
 
         self.current_row += 1;
         Ok(Some((y, slice)))
@@ -0,0 +1,347 @@
+//! Conversion between any two pixel layouts (internal).
+//!
+//! Use top-level [`crate::convert`] and [`crate::convert_into`].
+//!
+//! Integer-to-integer conversions stay in integers: 8-bit samples widen by
+//! `× 257` and narrow by rounding, so 8 → 16 → 8 is lossless. As soon as a
+//! float layout is involved every pixel goes through `f32`, where
+//! [`Transfer`] decides whether integer samples are sRGB-encoded (and
+//! floats linear light) or the same values scaled to `0.0..=1.0`. Alpha is
+//! never transfer-encoded. Float samples written to integer layouts are
+//! clamped to `0.0..=1.0`.
+//!
+//! RGB to gray uses BT.709 weights (`0.2126 R + 0.7152 G + 0.0722 B`) on
+//! the samples in whichever form the conversion works on. Dropping alpha
+//! discards it; gaining alpha, or the padding byte of `Rgbx8` / `Bgrx8`,
+//! writes the maximum value. Planar layouts are interleaved first (or
+//! split last).
+
+use alloc::vec::Vec;
+use enough::Stop;
+use linear_srgb::default::{linear_to_srgb, srgb_to_linear};
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+
+/// How float samples relate to integer samples in [`crate::convert`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Transfer {
+    /// Integer samples are sRGB-encoded and float samples are linear light,
+    /// the convention of PFM and [`crate::decode_bmp_linear`].
+    #[default]
+    Srgb,
+    /// Float samples are integer samples divided by their maximum, with no
+    /// transfer curve.
+    Identity,
+}
+
+/// Options for [`crate::convert`] and [`crate::convert_into`].
+///
+/// ```
+/// use zenbitmaps::{ConvertOptions, Transfer};
+///
+/// let options = ConvertOptions::new().with_transfer(Transfer::Identity);
+/// assert_eq!(options.transfer(), Transfer::Identity);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConvertOptions {
+    transfer: Transfer,
+}
+
+impl ConvertOptions {
+    /// Default options: [`Transfer::Srgb`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How float samples relate to integer ones. Only matters when exactly
+    /// one side of the conversion is a float layout.
+    pub fn with_transfer(mut self, transfer: Transfer) -> Self {
+        self.transfer = transfer;
+        self
+    }
+
+    /// The transfer between float and integer samples.
+    pub fn transfer(&self) -> Transfer {
+        self.transfer
+    }
+}
+
+fn is_float(layout: PixelLayout) -> bool {
+    matches!(
+        layout,
+        PixelLayout::GrayF32 | PixelLayout::RgbF32 | PixelLayout::RgbaF32
+    )
+}
+
+fn is_gray(layout: PixelLayout) -> bool {
+    layout.channels() <= 2
+}
+
+/// Largest sample value of an integer layout.
+fn max_value(layout: PixelLayout) -> u16 {
+    match layout {
+        PixelLayout::Gray16
+        | PixelLayout::GrayA16
+        | PixelLayout::Rgb16
+        | PixelLayout::Rgba16
+        | PixelLayout::Rgba16Be => u16::MAX,
+        _ => u8::MAX.into(),
+    }
+}
+
+/// RGBA of one pixel of an interleaved integer layout, widened to 16 bits.
+fn read_u16(layout: PixelLayout, p: &[u8]) -> [u16; 4] {
+    let e = |i: usize| u16::from(p[i]) * 257;
+    let n = |i: usize| u16::from_ne_bytes([p[2 * i], p[2 * i + 1]]);
+    match layout {
+        PixelLayout::Gray8 => [e(0), e(0), e(0), u16::MAX],
+        PixelLayout::Rgb8 | PixelLayout::Rgbx8 => [e(0), e(1), e(2), u16::MAX],
+        PixelLayout::Rgba8 => [e(0), e(1), e(2), e(3)],
+        PixelLayout::Bgr8 | PixelLayout::Bgrx8 => [e(2), e(1), e(0), u16::MAX],
+        PixelLayout::Bgra8 => [e(2), e(1), e(0), e(3)],
+        PixelLayout::Gray16 => [n(0), n(0), n(0), u16::MAX],
+        PixelLayout::GrayA16 => [n(0), n(0), n(0), n(1)],
+        PixelLayout::Rgb16 => [n(0), n(1), n(2), u16::MAX],
+        PixelLayout::Rgba16Be => [0, 1, 2, 3].map(|i| u16::from_be_bytes([p[2 * i], p[2 * i + 1]])),
+        _ => [n(0), n(1), n(2), n(3)],
+    }
+}
+
+/// RGBA of one pixel of an interleaved float layout.
+fn read_f32(layout: PixelLayout, p: &[u8]) -> [f32; 4] {
+    let f = |i: usize| f32::from_ne_bytes([p[4 * i], p[4 * i + 1], p[4 * i + 2], p[4 * i + 3]]);
+    match layout {
+        PixelLayout::GrayF32 => [f(0), f(0), f(0), 1.0],
+        PixelLayout::RgbF32 => [f(0), f(1), f(2), 1.0],
+        _ => [f(0), f(1), f(2), f(3)],
+    }
+}
+
+/// Write RGBA samples already at `layout`'s depth; gray layouts take the
+/// first sample.
+fn write_int(layout: PixelLayout, [r, g, b, a]: [u16; 4], out: &mut [u8]) {
+    let mut put = |i: usize, v: u16| out[2 * i..2 * i + 2].copy_from_slice(&v.to_ne_bytes());
+    match layout {
+        PixelLayout::Gray8 => out[0] = r as u8,
+        PixelLayout::Rgb8 => out.copy_from_slice(&[r as u8, g as u8, b as u8]),
+        PixelLayout::Rgba8 => out.copy_from_slice(&[r as u8, g as u8, b as u8, a as u8]),
+        PixelLayout::Rgbx8 => out.copy_from_slice(&[r as u8, g as u8, b as u8, 255]),
+        PixelLayout::Bgr8 => out.copy_from_slice(&[b as u8, g as u8, r as u8]),
+        PixelLayout::Bgra8 => out.copy_from_slice(&[b as u8, g as u8, r as u8, a as u8]),
+        PixelLayout::Bgrx8 => out.copy_from_slice(&[b as u8, g as u8, r as u8, 255]),
+        PixelLayout::Gray16 => put(0, r),
+        PixelLayout::GrayA16 => {
+            put(0, r);
+            put(1, a);
+        }
+        PixelLayout::Rgba16Be => {
+            for (d, v) in out.chunks_exact_mut(2).zip([r, g, b, a]) {
+                d.copy_from_slice(&v.to_be_bytes());
+            }
+        }
+        _ => {
+            for (i, v) in [r, g, b, a].into_iter().take(layout.channels()).enumerate() {
+                put(i, v);
+            }
+        }
+    }
+}
+
+/// Write the first samples of `px` that fit: one for `GrayF32`, three for
+/// `RgbF32`, four for `RgbaF32`.
+fn write_f32(px: [f32; 4], out: &mut [u8]) {
+    for (d, v) in out.chunks_exact_mut(4).zip(px) {
+        d.copy_from_slice(&v.to_ne_bytes());
+    }
+}
+
+fn luma_u16([r, g, b, _]: [u16; 4]) -> u16 {
+    ((2126 * u32::from(r) + 7152 * u32::from(g) + 722 * u32::from(b) + 5000) / 10000) as u16
+}
+
+fn luma_f32([r, g, b, _]: [f32; 4]) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Convert one interleaved row of pixels.
+fn convert_row(src: &[u8], from: PixelLayout, dst: &mut [u8], to: PixelLayout, transfer: Transfer) {
+    let (src_bpp, dst_bpp) = (from.bytes_per_pixel(), to.bytes_per_pixel());
+    let pixels = src.chunks_exact(src_bpp).zip(dst.chunks_exact_mut(dst_bpp));
+    let dst_max = max_value(to);
+    if !is_float(from) && !is_float(to) {
+        for (p, o) in pixels {
+            let mut q = read_u16(from, p);
+            if is_gray(to) {
+                q[0] = luma_u16(q);
+            }
+            if dst_max != u16::MAX {
+                q = q.map(|v| ((u32::from(v) * 255 + 32767) / 65535) as u16);
+            }
+            write_int(to, q, o);
+        }
+        return;
+    }
+    let decode = |v: u16| {
+        let v = f32::from(v) / 65535.0;
+        match transfer {
+            Transfer::Srgb => srgb_to_linear(v),
+            Transfer::Identity => v,
+        }
+    };
+    let quantize = |v: f32| (v.clamp(0.0, 1.0) * f32::from(dst_max) + 0.5) as u16;
+    let encode = |v: f32| match transfer {
+        Transfer::Srgb => quantize(linear_to_srgb(v)),
+        Transfer::Identity => quantize(v),
+    };
+    for (p, o) in pixels {
+        let mut f = if is_float(from) {
+            read_f32(from, p)
+        } else {
+            let [r, g, b, a] = read_u16(from, p);
+            [decode(r), decode(g), decode(b), f32::from(a) / 65535.0]
+        };
+        if is_gray(to) {
+            f[0] = luma_f32(f);
+        }
+        if is_float(to) {
+            write_f32(f, o);
+        } else {
+            let [r, g, b, a] = f;
+            write_int(to, [encode(r), encode(g), encode(b), quantize(a)], o);
+        }
+    }
+}
+
+/// Bytes of a `width × height` image in `layout`.
+fn image_size(width: u32, height: u32, layout: PixelLayout) -> crate::Result<usize> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(layout.bytes_per_pixel()))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn convert_into(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    from: PixelLayout,
+    to: PixelLayout,
+    options: &ConvertOptions,
+    out: &mut [u8],
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "convert width or height is zero"
+        )));
+    }
+    let src_len = image_size(width, height, from)?;
+    let dst_len = image_size(width, height, to)?;
+    if pixels.len() < src_len {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: src_len,
+            actual: pixels.len(),
+        }));
+    }
+    if out.len() < dst_len {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: dst_len,
+            actual: out.len(),
+        }));
+    }
+    let (pixels, out) = (&pixels[..src_len], &mut out[..dst_len]);
+    if from == to {
+        stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        out.copy_from_slice(pixels);
+        return Ok(());
+    }
+    if from.is_planar() {
+        let interleaved = crate::planar::interleave(pixels, width, height, from, stop)?;
+        return convert_into(
+            &interleaved,
+            width,
+            height,
+            from.interleaved(),
+            to,
+            options,
+            out,
+            stop,
+        );
+    }
+    if to.is_planar() {
+        let mut interleaved = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, dst_len)?;
+        let inner = to.interleaved();
+        convert_into(
+            pixels,
+            width,
+            height,
+            from,
+            inner,
+            options,
+            &mut interleaved,
+            stop,
+        )?;
+        let planes = crate::planar::split(&interleaved, width, height, inner, stop)?;
+        out.copy_from_slice(&planes);
+        return Ok(());
+    }
+
+    let w = width as usize;
+    let rows = pixels
+        .chunks_exact(w * from.bytes_per_pixel())
+        .zip(out.chunks_exact_mut(w * to.bytes_per_pixel()));
+    for (y, (src, dst)) in rows.enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        convert_row(src, from, dst, to, options.transfer);
+    }
+    Ok(())
+}
+
+pub(crate) fn convert(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    from: PixelLayout,
+    to: PixelLayout,
+    options: &ConvertOptions,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    let len = image_size(width, height, to)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, len)?;
+    convert_into(pixels, width, height, from, to, options, &mut out, stop)?;
+    Ok(out)
+}
+
+/// 8-bit sRGB samples → linear `f32`, through `linear-srgb`'s SIMD slice
+/// kernels.
+pub(crate) fn srgb_u8_to_linear(srgb: &[u8], linear: &mut [f32]) -> crate::Result<()> {
+    if linear.len() < srgb.len() {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: srgb.len(),
+            actual: linear.len(),
+        }));
+    }
+    let out = &mut linear[..srgb.len()];
+    linear_srgb::default::srgb_u8_to_linear_slice(srgb, out);
+    Ok(())
+}
+
+/// Linear `f32` samples → 8-bit sRGB, clamped to `0.0..=1.0`.
+pub(crate) fn linear_to_srgb_u8(linear: &[f32], srgb: &mut [u8]) -> crate::Result<()> {
+    if srgb.len() < linear.len() {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: linear.len(),
+            actual: srgb.len(),
+        }));
+    }
+    let out = &mut srgb[..linear.len()];
+    linear_srgb::default::linear_to_srgb_u8_slice(linear, out);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! `data:` URI payload extraction (internal).
+//!
+//! Use top-level [`crate::decode_data_uri`].
+//!
+//! Only base64 payloads are accepted. The media type is not consulted; the
+//! image format comes from the payload's magic bytes, as for
+//! [`crate::decode`]. ASCII whitespace inside the payload (line-wrapped
+//! HTML or JSON) is skipped, padding is optional, and the URL-safe alphabet
+//! (`-`, `_`) is accepted alongside `+` and `/`.
+
+use alloc::vec::Vec;
+use whereat::at;
+
+use crate::error::BitmapError;
+
+/// Value of one base64 character, or `None` if it is not in either alphabet.
+fn sextet(c: u8) -> Option<u32> {
+    let v = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    };
+    Some(u32::from(v))
+}
+
+fn decode_base64(text: &[u8]) -> crate::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut acc, mut bits, mut digits) = (0u32, 0u32, 0usize);
+    let mut padding = false;
+    for (i, &c) in text.iter().enumerate() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        if c == b'=' {
+            padding = true;
+            continue;
+        }
+        let v = match sextet(c) {
+            Some(v) if !padding => v,
+            _ => {
+                return Err(at!(BitmapError::invalid_data(alloc::format!(
+                    "data URI: invalid base64 character {:?} at payload offset {i}",
+                    char::from(c)
+                ))));
+            }
+        };
+        acc = acc << 6 | v;
+        bits += 6;
+        digits += 1;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if digits % 4 == 1 {
+        return Err(at!(BitmapError::invalid_data(
+            "data URI: base64 payload is truncated"
+        )));
+    }
+    Ok(out)
+}
+
+/// The decoded bytes of a `data:[<media type>][;<params>];base64,<payload>`
+/// URI. Surrounding whitespace is ignored.
+pub(crate) fn payload(uri: &str) -> crate::Result<Vec<u8>> {
+    let uri = uri.trim().as_bytes();
+    let rest = match uri.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(b"data:") => &uri[5..],
+        _ => {
+            return Err(at!(BitmapError::invalid_header(
+                "not a data URI (expected a `data:` prefix)"
+            )));
+        }
+    };
+    let comma = rest.iter().position(|&c| c == b',').ok_or_else(|| {
+        at!(BitmapError::invalid_header(
+            "data URI has no `,` before its payload"
+        ))
+    })?;
+    let header = &rest[..comma];
+    let base64 = header
+        .len()
+        .checked_sub(7)
+        .is_some_and(|i| header[i..].eq_ignore_ascii_case(b";base64"));
+    if !base64 {
+        return Err(at!(BitmapError::UnsupportedVariant(
+            "data URI payload is not base64; only `;base64,` URIs are supported".into()
+        )));
+    }
+    decode_base64(&rest[comma + 1..])
+}
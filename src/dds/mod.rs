@@ -0,0 +1,219 @@
+//! Uncompressed DDS (DirectDraw Surface) decoding (internal).
+//!
+//! Use top-level [`crate::decode_dds`].
+//!
+//! A DDS file is the `"DDS "` magic, a 124-byte header whose pixel-format
+//! block describes each pixel with channel bit masks, an optional 20-byte
+//! DX10 header, then the surfaces. Only the top mip level of the first
+//! surface (first cube face, first volume slice, first array element) is
+//! decoded. Channel masks go through the BMP bitfield scaling, so any
+//! mask layout works, not just the named D3D formats.
+
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::bmp::utils::{scale_mask_to_u8, scale_mask_to_u16};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_LEN: usize = 4 + 124;
+const DX10_HEADER_LEN: usize = 20;
+
+// DDS_PIXELFORMAT.dwFlags
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+
+/// Pixel layout of the surface, as bit count and R, G, B, A masks
+/// (luminance uses the R mask).
+struct Format {
+    bits: u32,
+    masks: [u32; 4],
+    luminance: bool,
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// Map a DX10 DXGI format to masks; only the 8-bit RGBA/BGRA family.
+fn dxgi_format(dxgi: u32) -> crate::Result<Format> {
+    let masks = match dxgi {
+        // R8G8B8A8_UNORM, _UNORM_SRGB
+        28 | 29 => [0xFF, 0xFF00, 0xFF_0000, 0xFF00_0000],
+        // B8G8R8A8_UNORM, _UNORM_SRGB
+        87 | 91 => [0xFF_0000, 0xFF00, 0xFF, 0xFF00_0000],
+        // B8G8R8X8_UNORM, _UNORM_SRGB
+        88 | 93 => [0xFF_0000, 0xFF00, 0xFF, 0],
+        _ => {
+            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "DDS DXGI format {dxgi} is not supported"
+            ))));
+        }
+    };
+    Ok(Format {
+        bits: 32,
+        masks,
+        luminance: false,
+    })
+}
+
+fn parse_format(data: &[u8]) -> crate::Result<(Format, usize)> {
+    let flags = read_u32(data, 80);
+    if flags & DDPF_FOURCC != 0 {
+        let fourcc = &data[84..88];
+        if fourcc == b"DX10" {
+            if data.len() < HEADER_LEN + DX10_HEADER_LEN {
+                return Err(at!(BitmapError::unexpected_eof()));
+            }
+            return Ok((
+                dxgi_format(read_u32(data, HEADER_LEN))?,
+                HEADER_LEN + DX10_HEADER_LEN,
+            ));
+        }
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "DDS FourCC '{}' (block-compressed or D3DFMT code) is not supported",
+            fourcc.escape_ascii()
+        ))));
+    }
+    let luminance = flags & DDPF_LUMINANCE != 0;
+    if flags & DDPF_RGB == 0 && !luminance {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "DDS pixel format flags {flags:#x} (alpha-only or YUV) are not supported"
+        ))));
+    }
+    let bits = read_u32(data, 88);
+    if !matches!(bits, 8 | 16 | 24 | 32) {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "DDS bit count {bits} is not supported"
+        ))));
+    }
+    let alpha = if flags & DDPF_ALPHAPIXELS != 0 {
+        read_u32(data, 104)
+    } else {
+        0
+    };
+    let masks = [
+        read_u32(data, 92),
+        read_u32(data, 96),
+        read_u32(data, 100),
+        alpha,
+    ];
+    Ok((
+        Format {
+            bits,
+            masks,
+            luminance,
+        },
+        HEADER_LEN,
+    ))
+}
+
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    if data.len() < 4 || &data[..4] != MAGIC {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    if data.len() < HEADER_LEN {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    if read_u32(data, 4) != 124 || read_u32(data, 76) != 32 {
+        return Err(at!(BitmapError::invalid_header(
+            "DDS header or pixel format size is wrong"
+        )));
+    }
+    let height = read_u32(data, 12);
+    let width = read_u32(data, 16);
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "DDS width or height is zero"
+        )));
+    }
+    let (format, offset) = parse_format(data)?;
+    limits::check_dimensions(width, height, limits)?;
+
+    let [mr, mg, mb, ma] = format.masks;
+    let wide = format.masks.iter().any(|m| m.count_ones() > 8);
+    let (layout, channels) = match (format.luminance, ma != 0, wide) {
+        (true, false, false) => (PixelLayout::Gray8, 1),
+        (true, false, true) => (PixelLayout::Gray16, 1),
+        (_, _, true) => (PixelLayout::Rgba16, 4),
+        (false, false, false) => (PixelLayout::Rgb8, 3),
+        (_, true, false) => (PixelLayout::Rgba8, 4),
+    };
+
+    let (w, h) = (width as usize, height as usize);
+    let src_bpp = format.bits as usize / 8;
+    let pitch = w
+        .checked_mul(src_bpp)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let out_row = w
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let out_bytes = out_row
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(out_bytes, limits)?;
+    let pixels = pitch
+        .checked_mul(h)
+        .and_then(|n| data.get(offset..)?.get(..n))
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    for (y, (src, dst)) in pixels
+        .chunks_exact(pitch)
+        .zip(out.chunks_exact_mut(out_row))
+        .enumerate()
+    {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (px, dst) in src
+            .chunks_exact(src_bpp)
+            .zip(dst.chunks_exact_mut(layout.bytes_per_pixel()))
+        {
+            let mut v = [0u8; 4];
+            v[..src_bpp].copy_from_slice(px);
+            let v = u32::from_le_bytes(v);
+            if wide {
+                let alpha = if ma == 0 {
+                    u16::MAX
+                } else {
+                    scale_mask_to_u16(v, ma)
+                };
+                let rgb = if format.luminance {
+                    [scale_mask_to_u16(v, mr); 3]
+                } else {
+                    [mr, mg, mb].map(|m| scale_mask_to_u16(v, m))
+                };
+                let values = [rgb[0], rgb[1], rgb[2], alpha];
+                for (d, s) in dst.chunks_exact_mut(2).zip(&values[..channels]) {
+                    d.copy_from_slice(&s.to_ne_bytes());
+                }
+            } else {
+                let alpha = if ma == 0 {
+                    255
+                } else {
+                    scale_mask_to_u8(v, ma)
+                };
+                let rgb = if format.luminance {
+                    [scale_mask_to_u8(v, mr); 3]
+                } else {
+                    [mr, mg, mb].map(|m| scale_mask_to_u8(v, m))
+                };
+                dst.copy_from_slice(&[rgb[0], rgb[1], rgb[2], alpha][..channels]);
+            }
+        }
+    }
+
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,214 @@
+//! Plain-text pixel grids for debugging (internal).
+//!
+//! Use top-level [`crate::encode_debug_text`] and
+//! [`crate::decode_debug_text`].
+//!
+//! The text starts with a `# W×H Layout` comment, then has one line per
+//! row. Samples are decimal, right-aligned to a common width, with channels
+//! joined by `,` and pixels separated by two spaces, so two dumps of the
+//! same size line up column for column in a diff. Floats use Rust's
+//! shortest round-trip formatting, so every layout survives a round trip
+//! exactly. Planar layouts are written and read in interleaved order.
+//!
+//! The parser is deliberately loose, to take hand-written fixtures as well:
+//! lines starting with `#` and blank lines are skipped, and spaces, tabs,
+//! `,`, `;` and `|` all separate samples. Only the sample count of each
+//! row matters; it must be a multiple of the layout's channel count and
+//! the same for every row.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use enough::Stop;
+use whereat::at;
+
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Storage of one sample of `layout`.
+#[derive(Clone, Copy)]
+enum Sample {
+    U8,
+    U16Ne,
+    U16Be,
+    F32,
+}
+
+impl Sample {
+    fn of(layout: PixelLayout) -> Self {
+        match layout {
+            PixelLayout::Gray16
+            | PixelLayout::GrayA16
+            | PixelLayout::Rgb16
+            | PixelLayout::Rgba16 => Self::U16Ne,
+            PixelLayout::Rgba16Be => Self::U16Be,
+            PixelLayout::GrayF32 | PixelLayout::RgbF32 | PixelLayout::RgbaF32 => Self::F32,
+            _ => Self::U8,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16Ne | Self::U16Be => 2,
+            Self::F32 => 4,
+        }
+    }
+
+    fn format(self, b: &[u8], out: &mut String) {
+        let _ = match self {
+            Self::U8 => write!(out, "{}", b[0]),
+            Self::U16Ne => write!(out, "{}", u16::from_ne_bytes([b[0], b[1]])),
+            Self::U16Be => write!(out, "{}", u16::from_be_bytes([b[0], b[1]])),
+            Self::F32 => write!(out, "{}", f32::from_ne_bytes([b[0], b[1], b[2], b[3]])),
+        };
+    }
+
+    fn parse(self, token: &str, out: &mut Vec<u8>) -> Option<()> {
+        match self {
+            Self::U8 => out.push(token.parse().ok()?),
+            Self::U16Ne => out.extend_from_slice(&token.parse::<u16>().ok()?.to_ne_bytes()),
+            Self::U16Be => out.extend_from_slice(&token.parse::<u16>().ok()?.to_be_bytes()),
+            Self::F32 => out.extend_from_slice(&token.parse::<f32>().ok()?.to_ne_bytes()),
+        }
+        Some(())
+    }
+}
+
+pub(crate) fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+) -> crate::Result<String> {
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "debug text width or height is zero"
+        )));
+    }
+    let (w, h) = (width as usize, height as usize);
+    let row_bytes = w
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let expected = row_bytes
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+    // Planar images are shown interleaved, one pixel per group.
+    let (pixels, grid) = if layout.is_planar() {
+        let interleaved = crate::planar::interleave(pixels, width, height, layout, stop)?;
+        (Cow::Owned(interleaved), layout.interleaved())
+    } else {
+        (Cow::Borrowed(&pixels[..expected]), layout)
+    };
+    let sample = Sample::of(grid);
+
+    // First pass: the widest sample sets the column width.
+    let mut scratch = String::new();
+    let mut column = 1;
+    for s in pixels.chunks_exact(sample.size()) {
+        scratch.clear();
+        sample.format(s, &mut scratch);
+        column = column.max(scratch.len());
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# {width}×{height} {layout:?}");
+    for (y, row) in pixels.chunks_exact(row_bytes).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in row.chunks_exact(layout.bytes_per_pixel()).enumerate() {
+            if x > 0 {
+                out.push_str("  ");
+            }
+            for (c, s) in px.chunks_exact(sample.size()).enumerate() {
+                if c > 0 {
+                    out.push(',');
+                }
+                scratch.clear();
+                sample.format(s, &mut scratch);
+                out.extend(core::iter::repeat_n(' ', column - scratch.len()));
+                out.push_str(&scratch);
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+pub(crate) fn decode<'a>(
+    text: &str,
+    layout: PixelLayout,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(text.len(), limits)?;
+    let sample = Sample::of(layout.interleaved());
+    let channels = layout.channels();
+    let is_separator = |c: char| c.is_whitespace() || matches!(c, ',' | ';' | '|');
+    let rows = text.lines().enumerate().filter(|(_, line)| {
+        let line = line.trim();
+        !line.is_empty() && !line.starts_with('#')
+    });
+
+    let mut out = Vec::new();
+    let (mut width, mut height) = (0u32, 0u32);
+    for (n, line) in rows {
+        if height.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let start = out.len();
+        for token in line.split(is_separator).filter(|t| !t.is_empty()) {
+            sample.parse(token, &mut out).ok_or_else(|| {
+                at!(BitmapError::invalid_data(alloc::format!(
+                    "debug text line {}: {token:?} is not a {layout:?} sample",
+                    n + 1
+                )))
+            })?;
+        }
+        let count = (out.len() - start) / sample.size();
+        if height == 0 {
+            if count == 0 || !count.is_multiple_of(channels) {
+                return Err(at!(BitmapError::invalid_data(alloc::format!(
+                    "debug text line {}: {count} samples is not a whole number of \
+                     {channels}-channel pixels",
+                    n + 1
+                ))));
+            }
+            width = u32::try_from(count / channels).map_err(|_| {
+                at!(BitmapError::DimensionsTooLarge {
+                    width: u32::MAX,
+                    height: 1
+                })
+            })?;
+        } else if count != width as usize * channels {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "debug text line {}: {count} samples, expected {} like the first row",
+                n + 1,
+                width as usize * channels
+            ))));
+        }
+        height += 1;
+        limits::check_dimensions(width, height, limits)?;
+    }
+    if height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "debug text has no pixel rows"
+        )));
+    }
+    limits::check_output_size(out.len(), limits)?;
+    if layout.is_planar() {
+        out = crate::planar::split(&out, width, height, layout.interleaved(), stop)?;
+    }
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -1,8 +1,7 @@
 use alloc::borrow::Cow;
 use alloc::vec::Vec;
+use core::ops::Range;
 
-#[cfg(feature = "rgb")]
-use rgb::AsPixels as _;
 #[cfg(feature = "rgb")]
 use whereat::at;
 
@@ -15,14 +14,98 @@ pub struct DecodeOutput<'a> {
     pub width: u32,
     pub height: u32,
     pub layout: PixelLayout,
+    stride: usize,
 }
 
 impl<'a> DecodeOutput<'a> {
     /// Access the pixel data.
+    ///
+    /// Rows start [`stride`](Self::stride) bytes apart, so when the output
+    /// is not [contiguous](Self::is_contiguous) this includes the padding
+    /// after each row (the last row may be unpadded). Use
+    /// [`rows`](Self::rows) or [`packed_pixels`](Self::packed_pixels) to
+    /// skip it.
     pub fn pixels(&self) -> &[u8] {
         &self.pixels
     }
 
+    /// Bytes from the start of one row to the start of the next.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Whether rows are packed with no padding between them
+    /// (`stride == width × bytes_per_pixel`).
+    pub fn is_contiguous(&self) -> bool {
+        self.stride == self.row_bytes()
+    }
+
+    /// Pixel bytes with any row padding removed. Borrows when the output is
+    /// already contiguous.
+    pub fn packed_pixels(&self) -> Cow<'_, [u8]> {
+        let len = self.row_bytes() * self.height as usize;
+        if self.is_contiguous() {
+            return Cow::Borrowed(&self.pixels[..len.min(self.pixels.len())]);
+        }
+        let mut packed = Vec::with_capacity(len);
+        for row in self.rows() {
+            packed.extend_from_slice(row);
+        }
+        Cow::Owned(packed)
+    }
+
+    /// Bytes in one row: `width × bytes_per_pixel`.
+    fn row_bytes(&self) -> usize {
+        self.width as usize * self.layout.bytes_per_pixel()
+    }
+
+    /// Pixel bytes of row `y`, or `None` if `y` is not below `height`.
+    ///
+    /// Planar layouts are split the same way, which does not follow their
+    /// planes; interleave them first with [`crate::interleave_planes`].
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        let len = self.row_bytes();
+        let start = (y as usize).checked_mul(self.stride)?;
+        if y >= self.height {
+            return None;
+        }
+        self.pixels.get(start..start + len)
+    }
+
+    /// Iterate over the rows, top to bottom, each `width × bytes_per_pixel`
+    /// bytes with any row padding left out.
+    ///
+    /// ```
+    /// use zenbitmaps::*;
+    /// let pgm = encode_pgm(&[1, 2, 3, 4, 5, 6], 3, 2, PixelLayout::Gray8, Unstoppable)?;
+    /// let decoded = decode(&pgm, Unstoppable)?;
+    /// let sums: Vec<u32> = decoded.rows().map(|r| r.iter().map(|&v| u32::from(v)).sum()).collect();
+    /// assert_eq!(sums, [6, 15]);
+    /// assert_eq!(decoded.row(1), Some(&[4, 5, 6][..]));
+    /// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+    /// ```
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[u8]> + ExactSizeIterator {
+        let len = self.row_bytes();
+        self.pixels
+            .chunks(self.stride.max(1))
+            .take(self.height as usize)
+            .map(move |row| &row[..len])
+    }
+
+    /// Iterate mutably over the rows, top to bottom.
+    ///
+    /// Borrowed pixels are copied into an owned buffer first, so the input
+    /// the image was decoded from is never modified.
+    pub fn rows_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut [u8]> + ExactSizeIterator {
+        let len = self.row_bytes();
+        let height = self.height as usize;
+        self.pixels
+            .to_mut()
+            .chunks_mut(self.stride.max(1))
+            .take(height)
+            .map(move |row| &mut row[..len])
+    }
+
     /// Take ownership of the pixel data (copies if borrowed).
     pub fn into_owned(self) -> DecodeOutput<'static> {
         DecodeOutput {
@@ -30,7 +113,40 @@ impl<'a> DecodeOutput<'a> {
             width: self.width,
             height: self.height,
             layout: self.layout,
+            stride: self.stride,
+        }
+    }
+
+    /// Take the packed pixel buffer, without row padding.
+    ///
+    /// Owned, contiguous pixels are moved out without copying; borrowed or
+    /// padded ones are copied.
+    pub fn into_vec(self) -> Vec<u8> {
+        if !self.is_contiguous() {
+            return self.packed_pixels().into_owned();
         }
+        let len = self.row_bytes() * self.height as usize;
+        let mut pixels = self.pixels.into_owned();
+        pixels.truncate(len);
+        pixels
+    }
+
+    /// Split into the packed pixel buffer, width, height and layout.
+    ///
+    /// Copies only like [`into_vec`](Self::into_vec), so the buffer can be
+    /// handed to other libraries without a `Cow` in their API.
+    ///
+    /// ```
+    /// use zenbitmaps::*;
+    /// let ppm = encode_ppm(&[1, 2, 3, 4, 5, 6], 2, 1, PixelLayout::Rgb8, Unstoppable)?;
+    /// let (pixels, width, height, layout) = decode(&ppm, Unstoppable)?.into_parts();
+    /// assert_eq!(pixels, [1, 2, 3, 4, 5, 6]);
+    /// assert_eq!((width, height, layout), (2, 1, PixelLayout::Rgb8));
+    /// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+    /// ```
+    pub fn into_parts(self) -> (Vec<u8>, u32, u32, PixelLayout) {
+        let (width, height, layout) = (self.width, self.height, self.layout);
+        (self.into_vec(), width, height, layout)
     }
 
     /// Whether the pixel data is borrowed (zero-copy from input).
@@ -38,12 +154,22 @@ impl<'a> DecodeOutput<'a> {
         matches!(self.pixels, Cow::Borrowed(_))
     }
 
+    /// The pixel data, when it is borrowed from the input.
+    #[cfg(feature = "zencodec")]
+    pub(crate) fn borrowed_pixels(&self) -> Option<&'a [u8]> {
+        match self.pixels {
+            Cow::Borrowed(pixels) => Some(pixels),
+            Cow::Owned(_) => None,
+        }
+    }
+
     pub(crate) fn borrowed(data: &'a [u8], width: u32, height: u32, layout: PixelLayout) -> Self {
         Self {
             pixels: Cow::Borrowed(data),
             width,
             height,
             layout,
+            stride: width as usize * layout.bytes_per_pixel(),
         }
     }
 
@@ -53,43 +179,141 @@ impl<'a> DecodeOutput<'a> {
             width,
             height,
             layout,
+            stride: width as usize * layout.bytes_per_pixel(),
         }
     }
 
+    /// Split into one buffer per channel, in the layout's channel order.
+    ///
+    /// See [`crate::split_channels`]; [`crate::merge_channels`] reverses it.
+    pub fn split_channels(&self) -> crate::Result<Vec<Vec<u8>>> {
+        crate::planar::split_channels(
+            &self.packed_pixels(),
+            self.width,
+            self.height,
+            self.layout,
+            &enough::Unstoppable,
+        )
+    }
+
+    /// Reinterpret the buffer as rows `stride` bytes apart. The caller
+    /// guarantees `stride` is at least one row and the buffer holds
+    /// `height` rows (the last may be unpadded).
+    pub(crate) fn with_stride(mut self, stride: usize) -> Self {
+        debug_assert!(stride >= self.row_bytes());
+        debug_assert!(
+            self.height == 0
+                || self.pixels.len() >= stride * (self.height as usize - 1) + self.row_bytes()
+        );
+        self.stride = stride;
+        self
+    }
+
+    /// Convert to `layout`, consuming `self`.
+    ///
+    /// Uses [`crate::convert`] with default [`crate::ConvertOptions`]
+    /// (sRGB-encoded integers, linear floats). When the layout already
+    /// matches, `self` is returned as is, still borrowed if it was.
+    ///
+    /// ```
+    /// use zenbitmaps::*;
+    /// let ppm = encode_ppm(&[255, 128, 0], 1, 1, PixelLayout::Rgb8, Unstoppable)?;
+    /// let rgba16 = decode(&ppm, Unstoppable)?.convert_to(PixelLayout::Rgba16)?;
+    /// assert_eq!(rgba16.layout, PixelLayout::Rgba16);
+    /// assert_eq!(&rgba16.pixels()[6..], &u16::MAX.to_ne_bytes());
+    /// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+    /// ```
+    #[cfg(feature = "convert")]
+    pub fn convert_to(self, layout: PixelLayout) -> crate::Result<DecodeOutput<'a>> {
+        self.convert_to_with(layout, &crate::ConvertOptions::new())
+    }
+
+    /// Convert to `layout` with explicit [`crate::ConvertOptions`],
+    /// consuming `self`.
+    #[cfg(feature = "convert")]
+    pub fn convert_to_with(
+        self,
+        layout: PixelLayout,
+        options: &crate::ConvertOptions,
+    ) -> crate::Result<DecodeOutput<'a>> {
+        if layout == self.layout {
+            return Ok(self);
+        }
+        self.to_layout_with(layout, options)
+    }
+
+    /// Convert a copy to `layout`, leaving `self` untouched.
+    ///
+    /// Like [`convert_to`](Self::convert_to), but always returns owned
+    /// pixels.
+    #[cfg(feature = "convert")]
+    pub fn to_layout(&self, layout: PixelLayout) -> crate::Result<DecodeOutput<'static>> {
+        self.to_layout_with(layout, &crate::ConvertOptions::new())
+    }
+
+    /// Convert a copy to `layout` with explicit [`crate::ConvertOptions`].
+    #[cfg(feature = "convert")]
+    pub fn to_layout_with(
+        &self,
+        layout: PixelLayout,
+        options: &crate::ConvertOptions,
+    ) -> crate::Result<DecodeOutput<'static>> {
+        let pixels = crate::convert::convert(
+            &self.packed_pixels(),
+            self.width,
+            self.height,
+            self.layout,
+            layout,
+            options,
+            &enough::Unstoppable,
+        )?;
+        Ok(DecodeOutput::owned(pixels, self.width, self.height, layout))
+    }
+
     /// Reinterpret pixel data as typed pixel slice.
     ///
-    /// Returns [`crate::BitmapError::LayoutMismatch`] if the pixel layout doesn't match `P`.
+    /// Returns [`crate::BitmapError::LayoutMismatch`] if the pixel layout doesn't match `P`,
+    /// or [`crate::BitmapError::InvalidData`] if a 16-bit `P` is viewed over a
+    /// buffer that isn't 2-byte aligned ([`to_imgvec`](Self::to_imgvec) copies instead)
+    /// or the rows are padded (use `as_imgref` or [`rows`](Self::rows)).
     #[cfg(feature = "rgb")]
-    pub fn as_pixels<P: crate::DecodePixel>(&self) -> crate::Result<&[P]>
-    where
-        [u8]: rgb::AsPixels<P>,
-    {
-        if !self.layout.is_memory_compatible(P::layout()) {
-            return Err(at!(crate::BitmapError::LayoutMismatch {
-                expected: P::layout(),
-                actual: self.layout,
-            }));
+    pub fn as_pixels<P: crate::DecodePixel>(&self) -> crate::Result<&[P]> {
+        self.check_pixel_type::<P>()?;
+        if !self.is_contiguous() {
+            return Err(at!(crate::BitmapError::invalid_data(alloc::format!(
+                "rows are padded to a {}-byte stride; a flat pixel slice would include the padding",
+                self.stride
+            ))));
         }
-        Ok(self.pixels().as_pixels())
+        cast_pixels(self.pixels())
     }
 
     /// Zero-copy view as an [`imgref::ImgRef`] of typed pixels.
     ///
     /// No allocation or copy — the returned `ImgRef` borrows directly from
     /// this `DecodeOutput`'s pixel buffer. Works for both borrowed (PNM) and
-    /// owned (BMP, farbfeld) data.
+    /// owned (BMP, farbfeld) data, and for padded rows, which become the
+    /// `ImgRef`'s stride.
     ///
-    /// Returns [`crate::BitmapError::LayoutMismatch`] if the pixel layout doesn't match `P`.
+    /// Returns [`crate::BitmapError::LayoutMismatch`] if the pixel layout doesn't match `P`,
+    /// or [`crate::BitmapError::InvalidData`] if the stride is not a whole
+    /// number of pixels.
     #[cfg(feature = "imgref")]
-    pub fn as_imgref<P: crate::DecodePixel>(&self) -> crate::Result<imgref::ImgRef<'_, P>>
-    where
-        [u8]: rgb::AsPixels<P>,
-    {
-        let pixels: &[P] = self.as_pixels()?;
-        Ok(imgref::ImgRef::new(
+    pub fn as_imgref<P: crate::DecodePixel>(&self) -> crate::Result<imgref::ImgRef<'_, P>> {
+        self.check_pixel_type::<P>()?;
+        let bpp = self.layout.bytes_per_pixel();
+        if !self.stride.is_multiple_of(bpp) {
+            return Err(at!(crate::BitmapError::invalid_data(alloc::format!(
+                "{}-byte stride is not a whole number of {bpp}-byte pixels",
+                self.stride
+            ))));
+        }
+        let pixels: &[P] = cast_pixels(self.pixels())?;
+        Ok(imgref::ImgRef::new_stride(
             pixels,
             self.width as usize,
             self.height as usize,
+            self.stride / bpp,
         ))
     }
 
@@ -97,15 +321,207 @@ impl<'a> DecodeOutput<'a> {
     ///
     /// Returns [`crate::BitmapError::LayoutMismatch`] if the pixel layout doesn't match `P`.
     #[cfg(feature = "imgref")]
-    pub fn to_imgvec<P: crate::DecodePixel>(&self) -> crate::Result<imgref::ImgVec<P>>
-    where
-        [u8]: rgb::AsPixels<P>,
-    {
-        let pixels: &[P] = self.as_pixels()?;
+    pub fn to_imgvec<P: crate::DecodePixel>(&self) -> crate::Result<imgref::ImgVec<P>> {
+        self.check_pixel_type::<P>()?;
         Ok(imgref::ImgVec::new(
-            pixels.to_vec(),
+            crate::pixel_traits::pixels_to_vec(&self.packed_pixels()),
             self.width as usize,
             self.height as usize,
         ))
     }
+
+    #[cfg(feature = "rgb")]
+    pub(crate) fn check_pixel_type<P: crate::DecodePixel>(&self) -> crate::Result<()> {
+        if !self.layout.is_memory_compatible(P::layout()) {
+            return Err(at!(crate::BitmapError::LayoutMismatch {
+                expected: P::layout(),
+                actual: self.layout,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rgb")]
+fn cast_pixels<P: crate::DecodePixel>(bytes: &[u8]) -> crate::Result<&[P]> {
+    crate::pixel_traits::cast_pixels(bytes).ok_or_else(|| {
+        at!(crate::BitmapError::invalid_data(alloc::format!(
+            "pixel buffer is not aligned for {}",
+            core::any::type_name::<P>()
+        )))
+    })
+}
+
+/// Non-fatal problems the decoder recovered from.
+///
+/// Returned alongside the pixels by [`crate::decode_bmp_with_report`]. An
+/// empty report means the file decoded cleanly; anything else means the
+/// pixels were produced by patching over damage or spec deviations, which
+/// conformance tooling usually wants to tell apart from a clean decode.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodeReport {
+    /// Recovered problems in the order first encountered. Each distinct
+    /// warning is recorded once, however often it occurs.
+    pub warnings: Vec<DecodeWarning>,
+
+    /// Output rows (top-down, as in the decoded buffer) that are backed by
+    /// real pixel data. Rows outside the range were fabricated — zero-filled
+    /// after truncation, or palette entry 0 after a truncated RLE stream — so
+    /// visual-diff tooling can mask them out. `None` when the decoder doesn't
+    /// track it; BMP always sets it, to the full height for complete files.
+    pub valid_rows: Option<Range<u32>>,
+}
+
+impl DecodeReport {
+    /// No warnings were recorded.
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    #[cfg_attr(not(feature = "bmp"), allow(dead_code))]
+    pub(crate) fn warn(&mut self, warning: DecodeWarning) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// A single recovered problem in a [`DecodeReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecodeWarning {
+    /// The header's file-size field disagrees with the input length.
+    FileSizeMismatch { declared: u32, actual: usize },
+    /// The pixel-data offset pointed into the headers or palette; decoding
+    /// started right after the palette instead.
+    PixelOffsetAdjusted { declared: u32, used: usize },
+    /// The planes field was not 1 (accepted in permissive mode).
+    InvalidPlanes(u16),
+    /// The declared palette size exceeds the maximum for the bit depth and
+    /// was clamped to it.
+    PaletteCountClamped { declared: i32, max: u32 },
+    /// A pixel referenced an entry past the declared palette; the stored
+    /// (or zeroed) entry was used.
+    PaletteIndexOutOfRange,
+    /// Unrecognized compression scheme; the output was left zero-filled.
+    UnknownCompression(u32),
+    /// RLE data stored top-down, which the BMP spec forbids.
+    RleTopDown,
+    /// An RLE run or delta extended past the image bounds and was skipped.
+    RleRunSkipped,
+    /// The RLE stream continued past the last row; the rest was ignored.
+    RleOverflow,
+    /// The RLE stream ended without an end-of-bitmap marker; undecoded
+    /// pixels were left at palette entry 0.
+    RleTruncated,
+    /// Uncompressed pixel data was shorter than the image needs; the missing
+    /// bytes were zero-filled.
+    TruncatedPixelData { expected: usize, available: usize },
+}
+
+impl core::fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::FileSizeMismatch { declared, actual } => {
+                write!(f, "file size field {declared} != actual size {actual}")
+            }
+            Self::PixelOffsetAdjusted { declared, used } => {
+                write!(
+                    f,
+                    "pixel data offset {declared} overlaps headers, used {used}"
+                )
+            }
+            Self::InvalidPlanes(planes) => write!(f, "planes field is {planes}, expected 1"),
+            Self::PaletteCountClamped { declared, max } => {
+                write!(f, "palette count {declared} clamped to {max}")
+            }
+            Self::PaletteIndexOutOfRange => f.write_str("palette index out of range"),
+            Self::UnknownCompression(c) => {
+                write!(f, "unknown compression {c}, output zero-filled")
+            }
+            Self::RleTopDown => f.write_str("RLE data with top-down row order"),
+            Self::RleRunSkipped => f.write_str("out-of-bounds RLE run skipped"),
+            Self::RleOverflow => f.write_str("RLE data past the last row ignored"),
+            Self::RleTruncated => f.write_str("RLE stream ended without end-of-bitmap marker"),
+            Self::TruncatedPixelData {
+                expected,
+                available,
+            } => write!(
+                f,
+                "pixel data truncated: {available} of {expected} bytes, rest zero-filled"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2×3 Rgb8 in rows 8 bytes apart; the last row is unpadded.
+    fn padded() -> DecodeOutput<'static> {
+        let mut data = alloc::vec![0xEE; 8 * 2 + 6];
+        for (y, row) in data.chunks_mut(8).enumerate() {
+            for (x, v) in row[..6].iter_mut().enumerate() {
+                *v = (y * 10 + x) as u8;
+            }
+        }
+        DecodeOutput::owned(data, 2, 3, PixelLayout::Rgb8).with_stride(8)
+    }
+
+    #[test]
+    fn padded_rows() {
+        let out = padded();
+        assert_eq!(out.stride(), 8);
+        assert!(!out.is_contiguous());
+        assert_eq!(out.pixels().len(), 22);
+        assert_eq!(out.row(1), Some(&[10, 11, 12, 13, 14, 15][..]));
+        assert_eq!(out.row(3), None);
+        assert_eq!(out.rows().len(), 3);
+        assert_eq!(out.rows().next_back(), Some(&[20, 21, 22, 23, 24, 25][..]));
+        let packed = out.packed_pixels();
+        assert!(matches!(packed, Cow::Owned(_)));
+        assert_eq!(packed.len(), 18);
+        assert!(!packed.contains(&0xEE));
+
+        let mut out = out;
+        for row in out.rows_mut() {
+            row.fill(1);
+        }
+        assert_eq!(&out.pixels()[6..8], &[0xEE, 0xEE]);
+        assert_eq!(out.clone().into_owned().stride(), 8);
+        let (pixels, width, height, layout) = out.into_parts();
+        assert_eq!((width, height, layout), (2, 3, PixelLayout::Rgb8));
+        assert_eq!(pixels, [1; 18]);
+    }
+
+    #[test]
+    fn tight_rows() {
+        let out = DecodeOutput::borrowed(&[1, 2, 3, 4], 2, 2, PixelLayout::Gray8);
+        assert_eq!(out.stride(), 2);
+        assert!(out.is_contiguous());
+        assert!(matches!(out.packed_pixels(), Cow::Borrowed(&[1, 2, 3, 4])));
+    }
+
+    #[cfg(feature = "imgref")]
+    #[test]
+    fn padded_typed_views() {
+        let out = padded();
+        let err = out.as_pixels::<rgb::Rgb<u8>>().unwrap_err();
+        assert!(matches!(err.error(), crate::BitmapError::InvalidData(..)));
+        // 8 bytes is not a whole number of 3-byte pixels.
+        let err = out.as_imgref::<rgb::Rgb<u8>>().unwrap_err();
+        assert!(matches!(err.error(), crate::BitmapError::InvalidData(..)));
+        let img = out.to_imgvec::<rgb::Rgb<u8>>().unwrap();
+        assert_eq!(img.buf()[2], rgb::Rgb::new(10, 11, 12));
+
+        let data: Vec<u8> = (0..16).collect();
+        let out = DecodeOutput::borrowed(&data, 1, 2, PixelLayout::Rgba8).with_stride(8);
+        let img = out.as_imgref::<rgb::Rgba<u8>>().unwrap();
+        assert_eq!(img.stride(), 2);
+        assert_eq!(img[(0usize, 1usize)], rgb::Rgba::new(8, 9, 10, 11));
+    }
 }
@@ -0,0 +1,287 @@
+//! DPX (SMPTE 268M Digital Picture Exchange) decoding (internal).
+//!
+//! Use top-level [`crate::decode_dpx`] and [`crate::decode_dpx_linear`].
+//!
+//! A DPX file is a 768-byte file header, a 640-byte image header describing
+//! up to eight image elements, optional film and television headers, then
+//! the element data. Only the variant film scanners and grading tools write
+//! by default is read: the first element, 10-bit RGB (descriptor 50),
+//! uncompressed, with three samples filled into each 32-bit word (packing
+//! method A, padding in the low bits, or B, padding in the high bits), in
+//! either byte order. Everything else is rejected as
+//! [`UnsupportedVariant`](crate::BitmapError::UnsupportedVariant).
+
+use alloc::format;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// File header plus image header up to the end of the first element.
+const HEADER_LEN: usize = 852;
+/// Offset of the first image element in the image header.
+const ELEMENT: usize = 780;
+/// Unset fields are all ones.
+const UNDEFINED: u32 = u32::MAX;
+
+const DESCRIPTOR_RGB: u8 = 50;
+const TRANSFER_PRINTING_DENSITY: u8 = 1;
+const TRANSFER_LOGARITHMIC: u8 = 3;
+
+/// Cineon defaults for log data that does not record its reference codes.
+const DEFAULT_REF_BLACK: u32 = 95;
+const DEFAULT_REF_WHITE: u32 = 685;
+
+/// The fields of the file and image headers that the decoder uses.
+struct DpxHeader {
+    big_endian: bool,
+    width: u32,
+    height: u32,
+    /// Flip columns (orientation 1 and 3).
+    right_to_left: bool,
+    /// Flip rows (orientation 2 and 3).
+    bottom_to_top: bool,
+    data_offset: usize,
+    /// Bit positions of the three samples in a word, first sample first.
+    shifts: [u32; 3],
+    eol_padding: usize,
+    log: bool,
+    ref_black: u32,
+    ref_white: u32,
+}
+
+impl DpxHeader {
+    fn u32(&self, b: [u8; 4]) -> u32 {
+        if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    }
+}
+
+fn unsupported(what: alloc::string::String) -> whereat::At<BitmapError> {
+    at!(BitmapError::UnsupportedVariant(what))
+}
+
+fn parse_header(data: &[u8]) -> crate::Result<DpxHeader> {
+    let big_endian = match data.get(..4) {
+        Some(b"SDPX") => true,
+        Some(b"XPDS") => false,
+        Some(_) => return Err(at!(BitmapError::UnrecognizedFormat)),
+        None => return Err(at!(BitmapError::unexpected_eof())),
+    };
+    let header = data
+        .get(..HEADER_LEN)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let u16_at = |at: usize| {
+        let b = [header[at], header[at + 1]];
+        if big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        }
+    };
+    let u32_at = |at: usize| {
+        let b = [header[at], header[at + 1], header[at + 2], header[at + 3]];
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+
+    let (flip_h, flip_v) = match u16_at(768) {
+        0 | 0xFFFF => (false, false),
+        1 => (true, false),
+        2 => (false, true),
+        3 => (true, true),
+        o => {
+            return Err(unsupported(format!(
+                "DPX orientation {o} (transposed) is not supported"
+            )));
+        }
+    };
+    let elements = u16_at(770);
+    if elements == 0 || elements > 8 {
+        return Err(at!(BitmapError::invalid_header(format!(
+            "DPX has {elements} image elements; expected 1 to 8"
+        ))));
+    }
+    let (width, height) = (u32_at(772), u32_at(776));
+    if width == 0 || height == 0 || width == UNDEFINED || height == UNDEFINED {
+        return Err(at!(BitmapError::invalid_header(
+            "DPX width or height is zero or undefined"
+        )));
+    }
+
+    let descriptor = header[ELEMENT + 20];
+    let transfer = header[ELEMENT + 21];
+    let bits = header[ELEMENT + 23];
+    let packing = u16_at(ELEMENT + 24);
+    let encoding = u16_at(ELEMENT + 26);
+    if encoding != 0 {
+        return Err(unsupported(
+            "DPX run-length encoding is not supported".into(),
+        ));
+    }
+    if descriptor != DESCRIPTOR_RGB {
+        return Err(unsupported(format!(
+            "DPX descriptor {descriptor} is not supported; only RGB (50)"
+        )));
+    }
+    if bits != 10 {
+        return Err(unsupported(format!(
+            "DPX {bits}-bit samples are not supported; only 10-bit"
+        )));
+    }
+    let shifts = match packing {
+        1 => [22, 12, 2],
+        2 => [20, 10, 0],
+        p => {
+            return Err(unsupported(format!(
+                "DPX packing {p} is not supported; only filled words (1 or 2)"
+            )));
+        }
+    };
+
+    let data_offset = match u32_at(ELEMENT + 28) {
+        0 | UNDEFINED => u32_at(4),
+        offset => offset,
+    } as usize;
+    if data_offset < HEADER_LEN {
+        return Err(at!(BitmapError::invalid_header(format!(
+            "DPX image data offset {data_offset} overlaps the header"
+        ))));
+    }
+    let eol_padding = match u32_at(ELEMENT + 32) {
+        UNDEFINED => 0,
+        n => n as usize,
+    };
+
+    let log = matches!(transfer, TRANSFER_PRINTING_DENSITY | TRANSFER_LOGARITHMIC);
+    let (mut ref_black, mut ref_white) = (u32_at(ELEMENT + 4), u32_at(ELEMENT + 12));
+    if ref_white > 1023 || ref_black >= ref_white {
+        (ref_black, ref_white) = (DEFAULT_REF_BLACK, DEFAULT_REF_WHITE);
+    }
+
+    Ok(DpxHeader {
+        big_endian,
+        width,
+        height,
+        right_to_left: flip_h,
+        bottom_to_top: flip_v,
+        data_offset,
+        shifts,
+        eol_padding,
+        log,
+        ref_black,
+        ref_white,
+    })
+}
+
+/// Code value (0..=1023) to linear light.
+///
+/// Log (printing density) data uses the Cineon conversion: 0.002 density
+/// per code value over a negative gamma of 0.6, reference black at 0.0 and
+/// reference white at 1.0. Highlights above white exceed 1.0 and codes
+/// below black go slightly negative. Other transfers scale to 0.0..=1.0.
+fn transfer_table(header: &DpxHeader) -> [f32; 1024] {
+    let mut table = [0f32; 1024];
+    if !header.log {
+        for (code, v) in table.iter_mut().enumerate() {
+            *v = code as f32 / 1023.0;
+        }
+        return table;
+    }
+    // 10^(0.002 / 0.6) per code value. `core` has no `powf`, so the powers
+    // are built up by multiplication.
+    const STEP: f64 = 1.007_704_814_136_104_2;
+    let mut powers = [0f64; 1024];
+    let mut p = 1.0;
+    for slot in powers.iter_mut() {
+        *slot = p;
+        p *= STEP;
+    }
+    let white = powers[header.ref_white as usize];
+    let black = powers[header.ref_black as usize] / white;
+    for (v, p) in table.iter_mut().zip(&powers) {
+        *v = ((p / white - black) / (1.0 - black)) as f32;
+    }
+    table
+}
+
+/// Decode the first image element to `Rgb16` code values, or to linear
+/// `RgbF32` when `linear` is set.
+pub(crate) fn decode(
+    data: &[u8],
+    linear: bool,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'static>> {
+    limits::check_file_size(data.len(), limits)?;
+    let header = parse_header(data)?;
+    let (width, height) = (header.width, header.height);
+    limits::check_dimensions(width, height, limits)?;
+    let (w, h) = (width as usize, height as usize);
+
+    let too_large = || at!(BitmapError::DimensionsTooLarge { width, height });
+    // One word per pixel: three 10-bit samples.
+    let row_bytes = w.checked_mul(4).ok_or_else(too_large)?;
+    let stride = row_bytes
+        .checked_add(header.eol_padding)
+        .ok_or_else(too_large)?;
+    let needed = stride
+        .checked_mul(h - 1)
+        .and_then(|n| n.checked_add(row_bytes))
+        .and_then(|n| n.checked_add(header.data_offset))
+        .ok_or_else(too_large)?;
+    if data.len() < needed {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+
+    let (layout, sample_bytes) = if linear {
+        (PixelLayout::RgbF32, 4)
+    } else {
+        (PixelLayout::Rgb16, 2)
+    };
+    let pixel_bytes = 3 * sample_bytes;
+    let out_bytes = w
+        .checked_mul(h)
+        .and_then(|n| n.checked_mul(pixel_bytes))
+        .ok_or_else(too_large)?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+    let table = linear.then(|| transfer_table(&header));
+
+    for (y, dst) in out.chunks_exact_mut(w * pixel_bytes).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let src_y = if header.bottom_to_top { h - 1 - y } else { y };
+        let start = header.data_offset + src_y * stride;
+        let row = &data[start..start + row_bytes];
+        for (x, px) in dst.chunks_exact_mut(pixel_bytes).enumerate() {
+            let src_x = if header.right_to_left { w - 1 - x } else { x };
+            let word = header.u32([
+                row[src_x * 4],
+                row[src_x * 4 + 1],
+                row[src_x * 4 + 2],
+                row[src_x * 4 + 3],
+            ]);
+            for (sample, &shift) in px.chunks_exact_mut(sample_bytes).zip(&header.shifts) {
+                let code = (word >> shift) & 0x3FF;
+                match &table {
+                    Some(table) => sample.copy_from_slice(&table[code as usize].to_ne_bytes()),
+                    // Replicate the top bits so 1023 maps to 65535.
+                    None => sample.copy_from_slice(&((code << 6 | code >> 4) as u16).to_ne_bytes()),
+                }
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,214 @@
+//! Format-parameterized encoding: [`EncodeOptions`] and the dispatch
+//! behind [`crate::encode`] and [`crate::transcode`].
+
+#[cfg(not(feature = "bmp"))]
+use core::marker::PhantomData;
+
+use alloc::vec::Vec;
+use enough::Stop;
+#[cfg(not(feature = "convert"))]
+use whereat::at;
+
+#[cfg(feature = "bmp")]
+use crate::bmp::{self, BmpEncodeOptions};
+use crate::decode::DecodeOutput;
+#[cfg(not(feature = "convert"))]
+use crate::error::BitmapError;
+use crate::farbfeld::{self, FarbfeldEncodeOptions};
+use crate::pixel::{EncodeFormat, PixelLayout};
+use crate::pnm::{self, PnmEncodeOptions, PnmFormat};
+
+/// Per-format options for [`crate::encode`].
+///
+/// Holds one options value for each [`EncodeFormat`], so generic code can
+/// configure every format up front and pick the target later; only the
+/// target's options are used. Defaults match the plain `encode_*`
+/// functions.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let options = EncodeOptions::new()
+///     .with_pnm(PnmEncodeOptions::new().with_background([255, 255, 255]))
+///     .with_farbfeld(FarbfeldEncodeOptions::new().with_dither(FarbfeldDither::Ordered));
+/// assert_eq!(options.pnm().background(), Some([255, 255, 255]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct EncodeOptions<'a> {
+    pnm: PnmEncodeOptions,
+    farbfeld: FarbfeldEncodeOptions,
+    #[cfg(feature = "bmp")]
+    bmp: BmpEncodeOptions<'a>,
+    #[cfg(not(feature = "bmp"))]
+    _bmp: PhantomData<&'a ()>,
+}
+
+impl<'a> EncodeOptions<'a> {
+    /// Default options for every format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options for PGM and PPM output. PAM and PFM keep their alpha and
+    /// ignore them.
+    pub fn with_pnm(mut self, pnm: PnmEncodeOptions) -> Self {
+        self.pnm = pnm;
+        self
+    }
+
+    /// Options for farbfeld output.
+    pub fn with_farbfeld(mut self, farbfeld: FarbfeldEncodeOptions) -> Self {
+        self.farbfeld = farbfeld;
+        self
+    }
+
+    /// Options for BMP output.
+    #[cfg(feature = "bmp")]
+    pub fn with_bmp(mut self, bmp: BmpEncodeOptions<'a>) -> Self {
+        self.bmp = bmp;
+        self
+    }
+
+    /// The PGM/PPM options.
+    pub fn pnm(&self) -> PnmEncodeOptions {
+        self.pnm
+    }
+
+    /// The farbfeld options.
+    pub fn farbfeld(&self) -> FarbfeldEncodeOptions {
+        self.farbfeld
+    }
+
+    /// The BMP options.
+    #[cfg(feature = "bmp")]
+    pub fn bmp(&self) -> BmpEncodeOptions<'a> {
+        self.bmp
+    }
+}
+
+/// Encode with the encoder for `format`.
+pub(crate) fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    match format {
+        EncodeFormat::Pgm => pnm::encode_with(
+            pixels,
+            width,
+            height,
+            layout,
+            PnmFormat::Pgm,
+            &options.pnm,
+            stop,
+        ),
+        EncodeFormat::Ppm => pnm::encode_with(
+            pixels,
+            width,
+            height,
+            layout,
+            PnmFormat::Ppm,
+            &options.pnm,
+            stop,
+        ),
+        EncodeFormat::Pam => pnm::encode(pixels, width, height, layout, PnmFormat::Pam, stop),
+        EncodeFormat::Pfm => pnm::encode(pixels, width, height, layout, PnmFormat::Pfm, stop),
+        EncodeFormat::Farbfeld => {
+            farbfeld::encode_with(pixels, width, height, layout, &options.farbfeld, stop)
+        }
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp => bmp::encode(pixels, width, height, layout, &options.bmp, stop),
+    }
+}
+
+/// Re-encode decoded pixels as `format`, converting first when the target
+/// cannot take the decoded layout.
+pub(crate) fn transcode(
+    decoded: &DecodeOutput<'_>,
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    let (width, height) = (decoded.width, decoded.height);
+    let layout = transcode_layout(decoded.layout, format, options);
+    let pixels = decoded.packed_pixels();
+    if layout == decoded.layout {
+        return encode(&pixels, width, height, layout, format, options, stop);
+    }
+    #[cfg(feature = "convert")]
+    {
+        let converted = crate::convert::convert(
+            &pixels,
+            width,
+            height,
+            decoded.layout,
+            layout,
+            &crate::ConvertOptions::new(),
+            stop,
+        )?;
+        encode(&converted, width, height, layout, format, options, stop)
+    }
+    #[cfg(not(feature = "convert"))]
+    Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+        "transcoding {:?} to {format:?} needs {layout:?}, which requires the 'convert' feature",
+        decoded.layout
+    ))))
+}
+
+/// The layout to hand the `format` encoder for `from` pixels: `from`
+/// itself when the encoder takes it, otherwise the closest layout it does.
+fn transcode_layout(
+    from: PixelLayout,
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+) -> PixelLayout {
+    let gray = from.channels() <= 2;
+    let alpha = from.has_alpha();
+    let eight_bit = from.bytes_per_pixel() == from.channels();
+    let everyday = if alpha {
+        PixelLayout::Rgba8
+    } else if gray {
+        PixelLayout::Gray8
+    } else {
+        PixelLayout::Rgb8
+    };
+    match format {
+        // 64-bit BMP is rarely readable elsewhere; only write it on request.
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp if options.bmp.bit_depth() == Some(64) => PixelLayout::Rgba16,
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp if eight_bit && accepts(from, format, options) => from,
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp => everyday,
+        _ if accepts(from, format, options) => from,
+        EncodeFormat::Pgm | EncodeFormat::Ppm => everyday,
+        EncodeFormat::Pam if eight_bit => from.interleaved(),
+        EncodeFormat::Pam => match (gray, alpha) {
+            (true, false) => PixelLayout::Gray16,
+            (false, false) => PixelLayout::Rgb16,
+            (_, true) => PixelLayout::Rgba16,
+        },
+        EncodeFormat::Pfm if gray => PixelLayout::GrayF32,
+        EncodeFormat::Pfm => PixelLayout::RgbF32,
+        EncodeFormat::Farbfeld => PixelLayout::Rgba16,
+    }
+}
+
+/// Whether the `format` encoder takes `layout` pixels as they are.
+#[cfg_attr(not(feature = "bmp"), allow(unused_variables))]
+fn accepts(layout: PixelLayout, format: EncodeFormat, options: &EncodeOptions<'_>) -> bool {
+    match format {
+        EncodeFormat::Pgm => pnm::encoded_size(1, 1, layout, PnmFormat::Pgm).is_ok(),
+        EncodeFormat::Ppm => pnm::encoded_size(1, 1, layout, PnmFormat::Ppm).is_ok(),
+        EncodeFormat::Pam => pnm::encoded_size(1, 1, layout, PnmFormat::Pam).is_ok(),
+        EncodeFormat::Pfm => pnm::encoded_size(1, 1, layout, PnmFormat::Pfm).is_ok(),
+        // The farbfeld encoder widens or interleaves every layout itself.
+        EncodeFormat::Farbfeld => true,
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp => bmp::encoded_size(1, 1, layout, &options.bmp).is_ok(),
+    }
+}
@@ -31,14 +31,16 @@ pub enum BitmapError {
     #[error("unrecognized format magic bytes")]
     UnrecognizedFormat,
 
-    #[error("invalid header: {0}")]
-    InvalidHeader(String),
+    /// A header field is malformed or out of range.
+    #[error("invalid header: {0}{1}")]
+    InvalidHeader(String, ErrorContext),
 
     #[error("unsupported format variant: {0}")]
     UnsupportedVariant(String),
 
-    #[error("invalid pixel data: {0}")]
-    InvalidData(String),
+    /// The pixel data (or a buffer handed in) is malformed.
+    #[error("invalid pixel data: {0}{1}")]
+    InvalidData(String, ErrorContext),
 
     #[error("dimensions too large: {width}x{height}")]
     DimensionsTooLarge { width: u32, height: u32 },
@@ -46,8 +48,9 @@ pub enum BitmapError {
     #[error("limit exceeded: {0}")]
     LimitExceeded(String),
 
-    #[error("unexpected end of input")]
-    UnexpectedEof,
+    /// The input ended before the decoder was done with it.
+    #[error("unexpected end of input{0}")]
+    UnexpectedEof(ErrorContext),
 
     #[error("pixel layout mismatch: expected {expected:?}, got {actual:?}")]
     LayoutMismatch {
@@ -58,15 +61,211 @@ pub enum BitmapError {
     #[error("buffer too small: need {needed} bytes, got {actual}")]
     BufferTooSmall { needed: usize, actual: usize },
 
+    /// A setting the call cannot honor, such as a planar target layout
+    /// for row-by-row decoding.
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
     #[error("operation cancelled")]
     Cancelled(StopReason),
 
+    /// Writing encoded output failed.
+    #[cfg(feature = "std")]
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     /// Unsupported codec operation.
     #[cfg(feature = "zencodec")]
     #[error(transparent)]
     UnsupportedOperation(#[from] zencodec::UnsupportedOperation),
 }
 
+/// Where in the input a decoder gave up.
+///
+/// Carried by [`BitmapError::InvalidHeader`], [`BitmapError::InvalidData`]
+/// and [`BitmapError::UnexpectedEof`], and appended to their message
+/// (`unexpected end of input (BMP palette, byte 54)`). Either part may be
+/// unknown: not every check knows its byte offset, and errors raised
+/// outside a decoder have no stage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ErrorContext {
+    /// Byte offset into the input at which decoding failed.
+    pub offset: Option<u64>,
+    /// The part of the file being decoded, such as `"BMP palette"` or
+    /// `"PNM maxval token"`.
+    pub stage: Option<&'static str>,
+    /// Raised by a check that [`BmpPermissiveness::Permissive`] relaxes.
+    ///
+    /// [`BmpPermissiveness::Permissive`]: crate::BmpPermissiveness::Permissive
+    pub(crate) permissive_may_recover: bool,
+}
+
+impl core::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (self.stage, self.offset) {
+            (Some(stage), Some(offset)) => write!(f, " ({stage}, byte {offset})"),
+            (Some(stage), None) => write!(f, " ({stage})"),
+            (None, Some(offset)) => write!(f, " (byte {offset})"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Broad class of a [`BitmapError`], for bucketing failures without
+/// matching on messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input is corrupt, truncated or violates its format's spec.
+    Malformed,
+    /// The input is valid (or at least unrecognized), but uses a format,
+    /// variant or operation this crate does not implement.
+    Unsupported,
+    /// A [`Limits`](crate::Limits) cap or the allocation hook refused it.
+    LimitExceeded,
+    /// The [`Stop`](enough::Stop) token or the decode deadline fired.
+    Cancelled,
+    /// Reading or writing through `std::io` failed.
+    Io,
+    /// The caller passed a buffer or layout that doesn't fit the call.
+    InvalidArgument,
+}
+
+impl BitmapError {
+    /// Which [`ErrorKind`] this error belongs to.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::InvalidHeader(..) | Self::InvalidData(..) | Self::UnexpectedEof(_) => {
+                ErrorKind::Malformed
+            }
+            Self::UnrecognizedFormat | Self::UnsupportedVariant(_) => ErrorKind::Unsupported,
+            #[cfg(feature = "zencodec")]
+            Self::UnsupportedOperation(_) => ErrorKind::Unsupported,
+            Self::DimensionsTooLarge { .. } | Self::LimitExceeded(_) => ErrorKind::LimitExceeded,
+            Self::Cancelled(_) => ErrorKind::Cancelled,
+            #[cfg(feature = "std")]
+            Self::Io(_) => ErrorKind::Io,
+            Self::LayoutMismatch { .. }
+            | Self::BufferTooSmall { .. }
+            | Self::InvalidArgument(_) => ErrorKind::InvalidArgument,
+        }
+    }
+
+    /// Whether decoding the same input with
+    /// [`BmpPermissiveness::Permissive`] may get past this error.
+    ///
+    /// `true` for malformed-input errors raised by a BMP decode in
+    /// `Strict` or `Standard` mode. Errors from a permissive decode, from
+    /// formats without a permissive mode, and anything that isn't
+    /// [`ErrorKind::Malformed`] report `false`.
+    ///
+    /// [`BmpPermissiveness::Permissive`]: crate::BmpPermissiveness::Permissive
+    pub fn is_recoverable_with_permissive(&self) -> bool {
+        self.context().is_some_and(|c| c.permissive_may_recover)
+    }
+
+    pub(crate) fn invalid_header(message: impl Into<String>) -> Self {
+        Self::InvalidHeader(message.into(), ErrorContext::default())
+    }
+
+    pub(crate) fn invalid_data(message: impl Into<String>) -> Self {
+        Self::InvalidData(message.into(), ErrorContext::default())
+    }
+
+    pub(crate) fn unexpected_eof() -> Self {
+        Self::UnexpectedEof(ErrorContext::default())
+    }
+
+    /// Where decoding failed, for the errors that record it.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::InvalidHeader(_, context)
+            | Self::InvalidData(_, context)
+            | Self::UnexpectedEof(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The input byte offset at which decoding failed, if known.
+    pub fn offset(&self) -> Option<u64> {
+        self.context().and_then(|c| c.offset)
+    }
+
+    /// The decoding stage that failed, if known.
+    pub fn stage(&self) -> Option<&'static str> {
+        self.context().and_then(|c| c.stage)
+    }
+
+    /// This error at byte `offset`, unless it already has one.
+    pub(crate) fn at_offset(mut self, offset: usize) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.offset.get_or_insert(offset as u64);
+        }
+        self
+    }
+
+    /// This error in decoding `stage`, unless it already names one.
+    pub(crate) fn in_stage(mut self, stage: &'static str) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.stage.get_or_insert(stage);
+        }
+        self
+    }
+
+    fn context_mut(&mut self) -> Option<&mut ErrorContext> {
+        match self {
+            Self::InvalidHeader(_, context)
+            | Self::InvalidData(_, context)
+            | Self::UnexpectedEof(context) => Some(context),
+            _ => None,
+        }
+    }
+}
+
+/// Attach an [`ErrorContext`] to the error of a decoding step. The
+/// innermost step to know a stage or offset wins; outer ones only fill
+/// what is still missing.
+pub(crate) trait ResultExt {
+    /// Name the stage that failed.
+    fn in_stage(self, stage: &'static str) -> Self;
+    /// Record the byte offset at which it failed.
+    #[cfg_attr(not(feature = "bmp"), allow(dead_code))]
+    fn at_offset(self, offset: usize) -> Self;
+    /// Mark whether a permissive decode may get past it.
+    #[cfg_attr(not(feature = "bmp"), allow(dead_code))]
+    fn permissive_may_recover(self, recoverable: bool) -> Self;
+}
+
+impl<T> ResultExt for Result<T> {
+    fn in_stage(mut self, stage: &'static str) -> Self {
+        if let Err(e) = &mut self
+            && let Some(context) = e.error_mut().context_mut()
+        {
+            context.stage.get_or_insert(stage);
+        }
+        self
+    }
+
+    fn at_offset(mut self, offset: usize) -> Self {
+        if let Err(e) = &mut self
+            && let Some(context) = e.error_mut().context_mut()
+        {
+            context.offset.get_or_insert(offset as u64);
+        }
+        self
+    }
+
+    fn permissive_may_recover(mut self, recoverable: bool) -> Self {
+        if let Err(e) = &mut self
+            && let Some(context) = e.error_mut().context_mut()
+        {
+            context.permissive_may_recover = recoverable;
+        }
+        self
+    }
+}
+
 impl From<StopReason> for BitmapError {
     fn from(r: StopReason) -> Self {
         BitmapError::Cancelled(r)
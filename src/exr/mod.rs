@@ -0,0 +1,355 @@
+//! OpenEXR scanline decoding (internal).
+//!
+//! Use top-level [`crate::decode_exr`].
+//!
+//! An EXR file is a magic number, a version word with feature flags, a list
+//! of `name\0type\0size value` attributes ended by an empty name, a table of
+//! chunk offsets, then the chunks. Only single-part scanline files with
+//! compression `NONE` are read: each chunk is one scanline, `y` and a byte
+//! count followed by every channel's samples for that line in channel-list
+//! order. Tiled, deep, multi-part and compressed files are rejected as
+//! [`UnsupportedVariant`](crate::BitmapError::UnsupportedVariant).
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+const MAGIC: [u8; 4] = [0x76, 0x2F, 0x31, 0x01];
+
+/// Version word flags.
+const FLAG_TILED: u32 = 0x200;
+const FLAG_NON_IMAGE: u32 = 0x800;
+const FLAG_MULTIPART: u32 = 0x1000;
+
+const PIXEL_UINT: i32 = 0;
+const PIXEL_HALF: i32 = 1;
+const PIXEL_FLOAT: i32 = 2;
+
+const COMPRESSION_NAMES: [&str; 10] = [
+    "NONE", "RLE", "ZIPS", "ZIP", "PIZ", "PXR24", "B44", "B44A", "DWAA", "DWAB",
+];
+
+/// One entry of the `channels` attribute.
+struct Channel {
+    name: Vec<u8>,
+    pixel_type: i32,
+}
+
+impl Channel {
+    fn sample_bytes(&self) -> usize {
+        if self.pixel_type == PIXEL_HALF { 2 } else { 4 }
+    }
+}
+
+/// The header attributes the decoder uses.
+struct ExrHeader {
+    channels: Vec<Channel>,
+    width: u32,
+    height: u32,
+    min_y: i64,
+    /// Offset of the chunk offset table.
+    table_offset: usize,
+}
+
+fn invalid(what: String) -> whereat::At<BitmapError> {
+    at!(BitmapError::invalid_header(what))
+}
+
+fn unsupported(what: String) -> whereat::At<BitmapError> {
+    at!(BitmapError::UnsupportedVariant(what))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+    let b = data.get(pos..pos.checked_add(4)?)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn i32_at(data: &[u8], pos: usize) -> Option<i32> {
+    u32_at(data, pos).map(|v| v as i32)
+}
+
+/// A null-terminated string starting at `pos`, and the position after it.
+fn c_str(data: &[u8], pos: usize) -> crate::Result<(&[u8], usize)> {
+    let rest = data
+        .get(pos..)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let len = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    Ok((&rest[..len], pos + len + 1))
+}
+
+fn parse_channels(value: &[u8]) -> crate::Result<Vec<Channel>> {
+    let mut channels = Vec::new();
+    let mut pos = 0;
+    loop {
+        let (name, next) =
+            c_str(value, pos).map_err(|_| invalid("EXR channel list is truncated".into()))?;
+        if name.is_empty() {
+            return Ok(channels);
+        }
+        let field = |offset| {
+            i32_at(value, next + offset)
+                .ok_or_else(|| invalid("EXR channel list is truncated".into()))
+        };
+        // pixel type, pLinear + 3 reserved bytes, x and y sampling
+        let pixel_type = field(0)?;
+        let (x_sampling, y_sampling) = (field(8)?, field(12)?);
+        if !matches!(pixel_type, PIXEL_UINT | PIXEL_HALF | PIXEL_FLOAT) {
+            return Err(invalid(format!(
+                "EXR channel '{}' has unknown pixel type {pixel_type}",
+                name.escape_ascii()
+            )));
+        }
+        if (x_sampling, y_sampling) != (1, 1) {
+            return Err(unsupported(format!(
+                "EXR channel '{}' is subsampled {x_sampling}×{y_sampling}; only full \
+                 resolution channels are supported",
+                name.escape_ascii()
+            )));
+        }
+        channels.push(Channel {
+            name: name.to_vec(),
+            pixel_type,
+        });
+        pos = next + 16;
+    }
+}
+
+fn parse_header(data: &[u8]) -> crate::Result<ExrHeader> {
+    if data.len() < 8 {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    if data[..4] != MAGIC {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    let version = u32_at(data, 4).unwrap_or(0);
+    if version & 0xFF != 2 {
+        return Err(unsupported(format!(
+            "EXR format version {} is not supported",
+            version & 0xFF
+        )));
+    }
+    for (flag, what) in [
+        (FLAG_TILED, "tiled"),
+        (FLAG_NON_IMAGE, "deep"),
+        (FLAG_MULTIPART, "multi-part"),
+    ] {
+        if version & flag != 0 {
+            return Err(unsupported(format!(
+                "EXR {what} files are not supported; only single-part scanline images"
+            )));
+        }
+    }
+
+    let (mut channels, mut compression, mut data_window) = (None, None, None);
+    let mut pos = 8;
+    loop {
+        let (name, next) = c_str(data, pos)?;
+        if name.is_empty() {
+            pos = next;
+            break;
+        }
+        let (kind, next) = c_str(data, next)?;
+        let size = u32_at(data, next).ok_or_else(|| at!(BitmapError::unexpected_eof()))? as usize;
+        let start = next + 4;
+        let value = start
+            .checked_add(size)
+            .and_then(|end| data.get(start..end))
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        match (name, kind) {
+            (b"channels", b"chlist") => channels = Some(parse_channels(value)?),
+            (b"compression", b"compression") if size == 1 => compression = Some(value[0]),
+            (b"dataWindow", b"box2i") if size == 16 => {
+                let [x0, y0, x1, y1] = [0, 4, 8, 12].map(|o| i64::from(i32_at(value, o).unwrap()));
+                data_window = Some((x0, y0, x1, y1));
+            }
+            (b"channels" | b"compression" | b"dataWindow", _) => {
+                return Err(invalid(format!(
+                    "EXR attribute '{}' has type '{}' and size {size}",
+                    name.escape_ascii(),
+                    kind.escape_ascii()
+                )));
+            }
+            _ => {}
+        }
+        pos = start + size;
+    }
+
+    let (Some(channels), Some(compression), Some((x0, y0, x1, y1))) =
+        (channels, compression, data_window)
+    else {
+        return Err(invalid(
+            "EXR header is missing channels, compression or dataWindow".into(),
+        ));
+    };
+    if compression != 0 {
+        let name = COMPRESSION_NAMES
+            .get(usize::from(compression))
+            .copied()
+            .unwrap_or("unknown");
+        return Err(unsupported(format!(
+            "EXR compression {name} ({compression}) is not supported; only NONE"
+        )));
+    }
+    let (width, height) = (x1 - x0 + 1, y1 - y0 + 1);
+    if width <= 0 || height <= 0 {
+        return Err(invalid(format!(
+            "EXR data window ({x0}, {y0})-({x1}, {y1}) is empty"
+        )));
+    }
+    let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err(invalid("EXR data window exceeds u32 dimensions".into()));
+    };
+    Ok(ExrHeader {
+        channels,
+        width,
+        height,
+        min_y: y0,
+        table_offset: pos,
+    })
+}
+
+/// Widen an IEEE 754 half to `f32`.
+fn half_to_f32(h: u16) -> f32 {
+    let sign = u32::from(h & 0x8000) << 16;
+    let exponent = u32::from(h >> 10) & 0x1F;
+    let mantissa = u32::from(h & 0x3FF);
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        // Subnormal: mantissa × 2^-24, exact in f32.
+        0 => {
+            let magnitude = mantissa as f32 * (1.0 / 16_777_216.0);
+            return if sign != 0 { -magnitude } else { magnitude };
+        }
+        0x1F => sign | 0x7F80_0000 | mantissa << 13,
+        e => sign | (e + 112) << 23 | mantissa << 13,
+    };
+    f32::from_bits(bits)
+}
+
+/// Decode to `RgbF32`, or `RgbaF32` when there is an `A` channel.
+///
+/// Color comes from the `R`, `G` and `B` channels, or from a lone `Y`
+/// channel copied to all three. Other channels are skipped.
+pub(crate) fn decode(
+    data: &[u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'static>> {
+    limits::check_file_size(data.len(), limits)?;
+    let header = parse_header(data)?;
+    let (width, height) = (header.width, header.height);
+    limits::check_dimensions(width, height, limits)?;
+    let (w, h) = (width as usize, height as usize);
+    let too_large = || at!(BitmapError::DimensionsTooLarge { width, height });
+
+    // Byte offset of each channel within a line, and the line length.
+    let mut offsets = Vec::with_capacity(header.channels.len());
+    let mut line_bytes = 0usize;
+    for channel in &header.channels {
+        offsets.push(line_bytes);
+        line_bytes = w
+            .checked_mul(channel.sample_bytes())
+            .and_then(|n| n.checked_add(line_bytes))
+            .ok_or_else(too_large)?;
+    }
+    let find = |name: &[u8]| -> crate::Result<Option<usize>> {
+        let Some(i) = header.channels.iter().position(|c| c.name == name) else {
+            return Ok(None);
+        };
+        if header.channels[i].pixel_type == PIXEL_UINT {
+            return Err(unsupported(format!(
+                "EXR channel '{}' is UINT; only HALF and FLOAT color channels are supported",
+                name.escape_ascii()
+            )));
+        }
+        Ok(Some(i))
+    };
+    let color = match (find(b"R")?, find(b"G")?, find(b"B")?, find(b"Y")?) {
+        (Some(r), Some(g), Some(b), _) => [r, g, b],
+        (None, None, None, Some(y)) => [y; 3],
+        _ => {
+            return Err(unsupported(
+                "EXR image has no R, G and B channels or lone Y channel".into(),
+            ));
+        }
+    };
+    let alpha = find(b"A")?;
+    let (layout, sources) = match alpha {
+        Some(a) => (
+            PixelLayout::RgbaF32,
+            [color[0], color[1], color[2], a].to_vec(),
+        ),
+        None => (PixelLayout::RgbF32, color.to_vec()),
+    };
+
+    let table_end = h
+        .checked_mul(8)
+        .and_then(|n| n.checked_add(header.table_offset))
+        .ok_or_else(too_large)?;
+    if data.len() < table_end {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    let pixel_bytes = layout.bytes_per_pixel();
+    let out_bytes = w
+        .checked_mul(h)
+        .and_then(|n| n.checked_mul(pixel_bytes))
+        .ok_or_else(too_large)?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    for (y, dst) in out.chunks_exact_mut(w * pixel_bytes).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        // The offset table is in increasing y order whatever the line order.
+        let entry = header.table_offset + y * 8;
+        let offset = u64::from_le_bytes(data[entry..entry + 8].try_into().unwrap());
+        let chunk = usize::try_from(offset)
+            .ok()
+            .and_then(|o| data.get(o..))
+            .filter(|c| c.len() >= 8)
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        let chunk_y = i64::from(i32_at(chunk, 0).unwrap());
+        let size = u32_at(chunk, 4).unwrap() as usize;
+        if chunk_y != header.min_y + y as i64 || size != line_bytes {
+            return Err(at!(BitmapError::invalid_data(format!(
+                "EXR chunk for line {y} holds line {} with {size} bytes, expected {line_bytes}",
+                chunk_y - header.min_y
+            ))));
+        }
+        let line = chunk
+            .get(8..8 + line_bytes)
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+
+        for (c, &source) in sources.iter().enumerate() {
+            let channel = &header.channels[source];
+            let samples = &line[offsets[source]..offsets[source] + w * channel.sample_bytes()];
+            let dst_samples = dst
+                .chunks_exact_mut(pixel_bytes)
+                .map(|px| &mut px[c * 4..c * 4 + 4]);
+            if channel.pixel_type == PIXEL_HALF {
+                for (d, s) in dst_samples.zip(samples.chunks_exact(2)) {
+                    let v = half_to_f32(u16::from_le_bytes([s[0], s[1]]));
+                    d.copy_from_slice(&v.to_ne_bytes());
+                }
+            } else {
+                for (d, s) in dst_samples.zip(samples.chunks_exact(4)) {
+                    let v = f32::from_le_bytes([s[0], s[1], s[2], s[3]]);
+                    d.copy_from_slice(&v.to_ne_bytes());
+                }
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,110 @@
+//! gzip/bzip2-wrapped farbfeld (`.ff.gz`, `.ff.bz2`) (internal).
+//!
+//! suckless tools conventionally store farbfeld compressed. The header is
+//! decompressed first so limits apply before the pixel buffer is sized
+//! from it; the pixel data is then decompressed straight into the output
+//! buffer and swapped to native endian in place.
+
+use alloc::boxed::Box;
+use enough::Stop;
+use std::io::Read;
+use whereat::at;
+
+use super::decode::{FarbfeldDecodeOptions, parse_header};
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Decompressed bytes read between cancellation checks.
+const CHUNK: usize = 1 << 20;
+
+/// Whether `data` starts with a gzip or bzip2 magic this build can unwrap.
+pub(crate) fn is_compressed(data: &[u8]) -> bool {
+    reader(data).is_some()
+}
+
+fn reader(data: &[u8]) -> Option<Box<dyn Read + '_>> {
+    #[cfg(feature = "flate2")]
+    if data.starts_with(&[0x1F, 0x8B]) {
+        return Some(Box::new(flate2::read::MultiGzDecoder::new(data)));
+    }
+    #[cfg(feature = "bzip2")]
+    if data.starts_with(b"BZh") {
+        return Some(Box::new(bzip2::read::MultiBzDecoder::new(data)));
+    }
+    None
+}
+
+fn read_exact(reader: &mut dyn Read, buf: &mut [u8]) -> crate::Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => at!(BitmapError::unexpected_eof()),
+        _ => at!(BitmapError::invalid_data(alloc::format!(
+            "compressed farbfeld: {e}"
+        ))),
+    })
+}
+
+/// Decompress and decode; `None` if `data` is not compressed.
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    options: &FarbfeldDecodeOptions,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> Option<crate::Result<DecodeOutput<'a>>> {
+    let mut reader = reader(data)?;
+    Some(decode_from(&mut *reader, limits, options, alloc_pref, stop))
+}
+
+fn decode_from<'a>(
+    reader: &mut dyn Read,
+    limits: Option<&Limits>,
+    options: &FarbfeldDecodeOptions,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    let mut header = [0u8; 16];
+    read_exact(reader, &mut header)?;
+    let (width, height) = parse_header(&header)?;
+    limits::check_dimensions(width, height, limits)?;
+    let out_bytes = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(8))
+        .ok_or_else(|| {
+            at!(BitmapError::LimitExceeded(
+                "output size overflows usize".into()
+            ))
+        })?;
+    limits::check_output_size(out_bytes, limits)?;
+    limits::check_alloc(out_bytes, limits)?;
+    let mut pixels = alloc_util::alloc_zeroed(alloc_pref, true, out_bytes)?;
+    for chunk in pixels.chunks_mut(CHUNK) {
+        stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        read_exact(reader, chunk)?;
+        for pair in chunk.chunks_exact_mut(2) {
+            let v = u16::from_be_bytes([pair[0], pair[1]]);
+            pair.copy_from_slice(&v.to_ne_bytes());
+        }
+    }
+    if options.strict() {
+        let mut extra = [0u8; 1];
+        let n = reader.read(&mut extra).map_err(|e| {
+            at!(BitmapError::invalid_data(alloc::format!(
+                "compressed farbfeld: {e}"
+            )))
+        })?;
+        if n > 0 {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "farbfeld: trailing bytes after {width}x{height} pixel data"
+            ))));
+        }
+    }
+    Ok(DecodeOutput::owned(
+        pixels,
+        width,
+        height,
+        PixelLayout::Rgba16,
+    ))
+}
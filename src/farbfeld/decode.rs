@@ -8,10 +8,62 @@ use whereat::at;
 use crate::alloc_util::{self, AllocPref};
 use crate::error::BitmapError;
 
+/// Options for [`decode_farbfeld_with_options`](crate::decode_farbfeld_with_options).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FarbfeldDecodeOptions {
+    strict: bool,
+}
+
+impl FarbfeldDecodeOptions {
+    /// Default options: bytes after the pixel data are ignored.
+    pub const fn new() -> Self {
+        Self { strict: false }
+    }
+
+    /// Require the file to be exactly `16 + width * height * 8` bytes.
+    ///
+    /// Trailing bytes are rejected with [`BitmapError::InvalidData`]; short
+    /// files fail with [`BitmapError::UnexpectedEof`] either way. For
+    /// gzip/bzip2-wrapped files the check applies to the decompressed stream.
+    pub const fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether the payload length must match the header exactly.
+    pub const fn strict(&self) -> bool {
+        self.strict
+    }
+}
+
+/// Check that a `len`-byte file holds exactly the pixel data its header
+/// declares.
+pub(crate) fn check_exact_len(len: usize, width: u32, height: u32) -> crate::Result<()> {
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(8))
+        .and_then(|n| n.checked_add(16))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    match len.cmp(&expected) {
+        core::cmp::Ordering::Less => Err(at!(BitmapError::unexpected_eof()
+            .at_offset(len)
+            .in_stage("farbfeld pixel data"))),
+        core::cmp::Ordering::Equal => Ok(()),
+        core::cmp::Ordering::Greater => Err(at!(BitmapError::invalid_data(alloc::format!(
+            "farbfeld: {} trailing bytes after {width}x{height} pixel data",
+            len - expected
+        ))
+        .at_offset(expected)
+        .in_stage("farbfeld pixel data"))),
+    }
+}
+
 /// Parse farbfeld header, returning (width, height).
 pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(u32, u32)> {
     if data.len() < 16 {
-        return Err(at!(BitmapError::UnexpectedEof));
+        return Err(at!(BitmapError::unexpected_eof()
+            .at_offset(data.len())
+            .in_stage("farbfeld header")));
     }
     if &data[0..8] != b"farbfeld" {
         return Err(at!(BitmapError::UnrecognizedFormat));
@@ -20,14 +72,14 @@ pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(u32, u32)> {
     let height = u32::from_be_bytes([data[12], data[13], data[14], data[15]]);
 
     if width == 0 {
-        return Err(at!(BitmapError::InvalidHeader(
-            "farbfeld width is zero".into()
-        )));
+        return Err(at!(BitmapError::invalid_header("farbfeld width is zero")
+            .at_offset(8)
+            .in_stage("farbfeld header")));
     }
     if height == 0 {
-        return Err(at!(BitmapError::InvalidHeader(
-            "farbfeld height is zero".into()
-        )));
+        return Err(at!(BitmapError::invalid_header("farbfeld height is zero")
+            .at_offset(12)
+            .in_stage("farbfeld header")));
     }
     Ok((width, height))
 }
@@ -53,9 +105,11 @@ pub(crate) fn decode_pixels(
         .checked_mul(2)
         .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
 
-    let pixel_data = data
-        .get(16..16 + input_bytes)
-        .ok_or_else(|| at!(BitmapError::UnexpectedEof))?;
+    let pixel_data = data.get(16..16 + input_bytes).ok_or_else(|| {
+        at!(BitmapError::unexpected_eof()
+            .at_offset(data.len())
+            .in_stage("farbfeld pixel data"))
+    })?;
 
     // Pre-allocate output and write directly — no Vec growth
     let mut out = alloc_util::alloc_zeroed(alloc_pref, true, input_bytes)?;
@@ -77,13 +131,25 @@ pub(crate) fn decode_pixels(
 
 /// Batch big-endian u16 → native endian u16, writing directly into output.
 ///
-/// Processes 8 u16s (16 bytes) per iteration for pipeline-friendly throughput.
+/// With the `simd` feature on a little-endian host, whole vectors go
+/// through a byte shuffle and only the tail takes the scalar path.
 /// src and dst must have equal length and be a multiple of 2.
 #[inline]
-fn be16_to_ne_bulk(src: &[u8], dst: &mut [u8]) {
+pub(crate) fn be16_to_ne_bulk(src: &[u8], dst: &mut [u8]) {
     debug_assert_eq!(src.len(), dst.len());
     debug_assert_eq!(src.len() % 2, 0);
 
+    #[cfg(all(feature = "simd", target_endian = "little"))]
+    let done = super::swap::swap_u16_bytes(src, dst);
+    #[cfg(not(all(feature = "simd", target_endian = "little")))]
+    let done = 0;
+    be16_to_ne_scalar(&src[done..], &mut dst[done..]);
+}
+
+/// Scalar [`be16_to_ne_bulk`]: 8 u16s (16 bytes) per iteration for
+/// pipeline-friendly throughput.
+#[inline]
+fn be16_to_ne_scalar(src: &[u8], dst: &mut [u8]) {
     let mut i = 0;
     let len = src.len();
 
@@ -117,3 +183,12 @@ fn be16_to_ne_bulk(src: &[u8], dst: &mut [u8]) {
         i += 2;
     }
 }
+
+/// Big-endian u16 samples → u8, rounding to nearest (`(v + 128) / 257`).
+pub(crate) fn be16_to_u8_rounded(src: &[u8], dst: &mut [u8]) {
+    debug_assert_eq!(src.len(), dst.len() * 2);
+    for (pair, out) in src.chunks_exact(2).zip(dst.iter_mut()) {
+        let v = u32::from(u16::from_be_bytes([pair[0], pair[1]]));
+        *out = ((v + 128) / 257) as u8;
+    }
+}
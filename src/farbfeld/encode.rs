@@ -6,18 +6,171 @@ use alloc::vec::Vec;
 use enough::Stop;
 use whereat::at;
 
+use crate::alpha::{self, AlphaMode};
 use crate::error::BitmapError;
 use crate::pixel::PixelLayout;
 
+/// Dither applied when quantizing float input to farbfeld's 16 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum FarbfeldDither {
+    /// Round each sample to nearest.
+    #[default]
+    None,
+    /// 4×4 ordered (Bayer) dither.
+    Ordered,
+    /// Floyd–Steinberg error diffusion, per channel.
+    ErrorDiffusion,
+}
+
+/// Options for farbfeld encoding with [`crate::encode_farbfeld_with`].
+///
+/// ```
+/// use zenbitmaps::{FarbfeldDither, FarbfeldEncodeOptions};
+///
+/// let options = FarbfeldEncodeOptions::new().with_dither(FarbfeldDither::Ordered);
+/// assert_eq!(options.dither(), FarbfeldDither::Ordered);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FarbfeldEncodeOptions {
+    dither: FarbfeldDither,
+    alpha_mode: AlphaMode,
+}
+
+impl FarbfeldEncodeOptions {
+    /// Default options: no dithering, straight alpha.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dither used when quantizing `GrayF32`, `RgbF32` and `RgbaF32` color
+    /// channels to 16 bits. Alpha is always rounded to nearest; integer
+    /// layouts are unaffected. Default [`FarbfeldDither::None`].
+    pub fn with_dither(mut self, dither: FarbfeldDither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Whether the input's color is premultiplied by alpha. Farbfeld
+    /// stores straight alpha, so [`AlphaMode::Premultiplied`] input is
+    /// unpremultiplied before writing. Default [`AlphaMode::Straight`].
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Dither for float input.
+    pub fn dither(&self) -> FarbfeldDither {
+        self.dither
+    }
+
+    /// Alpha state of the input.
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
+
+/// 4×4 Bayer matrix, in sixteenths.
+const BAYER4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Scale a float sample to 16 bits after adding `offset` (in 16-bit
+/// steps); NaN and values outside 0.0..=1.0 clamp.
+fn quantize_f32(v: f32, offset: f32) -> u16 {
+    let v = if v.is_nan() { 0.0 } else { v };
+    (v * 65535.0 + offset).clamp(0.0, 65535.0) as u16
+}
+
+/// Quantize float pixels (`channels` = 1, 3 or 4; color then alpha) to
+/// big-endian RGBA16, gray expanded to RGB.
+fn encode_float(
+    pixels: &[u8],
+    w: usize,
+    channels: usize,
+    dither: FarbfeldDither,
+    out: &mut Vec<u8>,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    let colors = channels.min(3);
+    // Error-diffusion rows carry one pixel of padding on each side.
+    let err_len = match dither {
+        FarbfeldDither::ErrorDiffusion => (w + 2) * colors,
+        _ => 0,
+    };
+    let mut err_cur = alloc::vec![0f32; err_len];
+    let mut err_next = err_cur.clone();
+    let mut color = [0u16; 3];
+    for (y, row) in pixels.chunks_exact(w * channels * 4).enumerate() {
+        if y % 16 == 0 {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let sample = |i: usize| f32::from_ne_bytes(row[i * 4..i * 4 + 4].try_into().unwrap());
+        for x in 0..w {
+            for (c, q) in color.iter_mut().enumerate().take(colors) {
+                let v = sample(x * channels + c);
+                *q = match dither {
+                    FarbfeldDither::Ordered => {
+                        let t = f32::from(BAYER4[y % 4][x % 4]);
+                        quantize_f32(v, (t + 0.5) / 16.0)
+                    }
+                    FarbfeldDither::ErrorDiffusion => {
+                        let i = (x + 1) * colors + c;
+                        let v = if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+                        let target = v * 65535.0 + err_cur[i];
+                        let q = (target + 0.5).clamp(0.0, 65535.0) as u16;
+                        let e = target - f32::from(q);
+                        err_cur[i + colors] += e * 7.0 / 16.0;
+                        err_next[i - colors] += e * 3.0 / 16.0;
+                        err_next[i] += e * 5.0 / 16.0;
+                        err_next[i + colors] += e / 16.0;
+                        q
+                    }
+                    _ => quantize_f32(v, 0.5),
+                };
+            }
+            if colors == 1 {
+                color = [color[0]; 3];
+            }
+            let alpha = if channels == 4 {
+                quantize_f32(sample(x * 4 + 3), 0.5)
+            } else {
+                65535
+            };
+            for v in [color[0], color[1], color[2], alpha] {
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        core::mem::swap(&mut err_cur, &mut err_next);
+        err_next.fill(0.0);
+    }
+    Ok(())
+}
+
+/// Size in bytes of the file [`encode_farbfeld`] writes: a 16-byte header
+/// and 8 bytes per pixel.
+pub(crate) fn encoded_size(width: u32, height: u32) -> crate::Result<usize> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(8))
+        .and_then(|data| data.checked_add(16))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))
+}
+
 /// Encode pixels to farbfeld format.
 ///
-/// Accepts `Rgba16` (direct), `Rgba8` (expand via `val * 257`),
-/// or `Rgb8` (expand + alpha=65535).
+/// Accepts `Rgba16` (byte swap), `Rgba16Be` (copied as is), `Rgb16`,
+/// `Gray16` and `GrayA16` (gray expanded, alpha=65535 if absent), `GrayF32`,
+/// `RgbF32` and `RgbaF32` (clamped to 0.0..=1.0, dithered per `options`),
+/// `Rgba8` (expand via `val * 257`),
+/// or `Rgb8` (expand + alpha=65535). Planar layouts are interleaved
+/// first.
 pub(crate) fn encode_farbfeld(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
+    options: &FarbfeldEncodeOptions,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
     let w = width as usize;
@@ -33,6 +186,11 @@ pub(crate) fn encode_farbfeld(
             actual: pixels.len(),
         }));
     }
+    if options.alpha_mode == AlphaMode::Premultiplied && layout.has_alpha() {
+        let straight = alpha::straight(pixels, width, height, layout, options.alpha_mode, stop)?;
+        let options = options.with_alpha_mode(AlphaMode::Straight);
+        return encode_farbfeld(&straight, width, height, layout, &options, stop);
+    }
 
     // Output: 16 header + w*h*8 pixel bytes
     let pixel_bytes = w
@@ -53,6 +211,17 @@ pub(crate) fn encode_farbfeld(
     stop.check().map_err(|r| at!(BitmapError::from(r)))?;
 
     match layout {
+        PixelLayout::RgbPlanar8 | PixelLayout::RgbaPlanar8 | PixelLayout::RgbPlanarF32 => {
+            let interleaved = crate::planar::interleave(pixels, width, height, layout, stop)?;
+            return encode_farbfeld(
+                &interleaved,
+                width,
+                height,
+                layout.interleaved(),
+                options,
+                stop,
+            );
+        }
         PixelLayout::Rgba16 => {
             // Native endian u16 → big endian u16
             for (row_idx, row) in pixels[..expected].chunks_exact(w * 8).enumerate() {
@@ -65,6 +234,62 @@ pub(crate) fn encode_farbfeld(
                 }
             }
         }
+        PixelLayout::Rgba16Be => {
+            // Already in farbfeld byte order
+            for (row_idx, row) in pixels[..expected].chunks_exact(w * 8).enumerate() {
+                if row_idx % 16 == 0 {
+                    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+                }
+                out.extend_from_slice(row);
+            }
+        }
+        PixelLayout::GrayF32 | PixelLayout::RgbF32 | PixelLayout::RgbaF32 => {
+            let channels = layout.channels();
+            encode_float(
+                &pixels[..expected],
+                w,
+                channels,
+                options.dither,
+                &mut out,
+                stop,
+            )?;
+        }
+        PixelLayout::Rgb16 => {
+            // Native endian RGB u16 → big endian RGBA u16 (alpha = 65535)
+            for (row_idx, row) in pixels[..expected].chunks_exact(w * 6).enumerate() {
+                if row_idx % 16 == 0 {
+                    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+                }
+                for pixel in row.chunks_exact(6) {
+                    for pair in pixel.chunks_exact(2) {
+                        let val = u16::from_ne_bytes([pair[0], pair[1]]);
+                        out.extend_from_slice(&val.to_be_bytes());
+                    }
+                    out.extend_from_slice(&65535u16.to_be_bytes());
+                }
+            }
+        }
+        PixelLayout::Gray16 | PixelLayout::GrayA16 => {
+            // Native endian gray (+ alpha) u16 → big endian RGBA u16 (R=G=B=gray)
+            let has_alpha = layout == PixelLayout::GrayA16;
+            for (row_idx, row) in pixels[..expected].chunks_exact(w * bpp).enumerate() {
+                if row_idx % 16 == 0 {
+                    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+                }
+                for pixel in row.chunks_exact(bpp) {
+                    let gray = u16::from_ne_bytes([pixel[0], pixel[1]]).to_be_bytes();
+                    let alpha = if has_alpha {
+                        u16::from_ne_bytes([pixel[2], pixel[3]])
+                    } else {
+                        65535
+                    };
+                    out.extend_from_slice(&gray);
+                    out.extend_from_slice(&gray);
+                    out.extend_from_slice(&gray);
+                    out.extend_from_slice(&alpha.to_be_bytes());
+                }
+            }
+        }
         PixelLayout::Rgba8 => {
             // Expand u8 → u16 via val * 257
             for (row_idx, row) in pixels[..expected].chunks_exact(w * 4).enumerate() {
@@ -112,6 +337,20 @@ pub(crate) fn encode_farbfeld(
                 }
             }
         }
+        PixelLayout::Rgbx8 => {
+            // Expand RGBX u8 → RGBA u16 (alpha = 65535)
+            for (row_idx, row) in pixels[..expected].chunks_exact(w * 4).enumerate() {
+                if row_idx % 16 == 0 {
+                    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+                }
+                for pixel in row.chunks_exact(4) {
+                    for &byte in &pixel[..3] {
+                        out.extend_from_slice(&(byte as u16 * 257).to_be_bytes());
+                    }
+                    out.extend_from_slice(&65535u16.to_be_bytes());
+                }
+            }
+        }
         PixelLayout::Bgrx8 => {
             // Expand BGRX u8 → RGBA u16 (swap B↔R, alpha=65535)
             for (row_idx, row) in pixels[..expected].chunks_exact(w * 4).enumerate() {
@@ -161,12 +400,6 @@ pub(crate) fn encode_farbfeld(
                 }
             }
         }
-        _ => {
-            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
-                "cannot encode {:?} as farbfeld (supported: Rgba16, Rgba8, Rgb8, Gray8)",
-                layout
-            ))));
-        }
     }
 
     Ok(out)
@@ -6,10 +6,24 @@
 //! Implementation draws from [zune-farbfeld](https://github.com/etemesi254/zune-image)
 //! by Caleb Etemesi (MIT/Apache-2.0/Zlib licensed).
 
+#[cfg(any(feature = "flate2", feature = "bzip2"))]
+mod compressed;
 pub(crate) mod decode;
 mod encode;
+mod rows;
+pub(crate) mod stream;
+#[cfg(all(feature = "simd", target_endian = "little"))]
+mod swap;
 
-use crate::alloc_util::AllocPref;
+pub use decode::FarbfeldDecodeOptions;
+pub use encode::{FarbfeldDither, FarbfeldEncodeOptions};
+pub use rows::FarbfeldRows;
+pub use stream::FarbfeldStream;
+
+#[cfg(any(feature = "flate2", feature = "bzip2"))]
+pub(crate) use compressed::is_compressed;
+
+use crate::alloc_util::{self, AllocPref};
 use crate::decode::DecodeOutput;
 use crate::error::BitmapError;
 use crate::limits::{self, Limits};
@@ -33,13 +47,41 @@ pub(crate) fn decode<'a>(
 
 /// Decode farbfeld data, honoring an explicit [`AllocPref`] at the output-buffer
 /// allocation.
+///
+/// With the `flate2` / `bzip2` features, gzip- or bzip2-wrapped farbfeld is
+/// decompressed transparently.
 pub(crate) fn decode_with_alloc_pref<'a>(
-    data: &'a [u8],
+    data: &[u8],
+    limits: Option<&Limits>,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    decode_with_options(
+        data,
+        limits,
+        &FarbfeldDecodeOptions::new(),
+        alloc_pref,
+        stop,
+    )
+}
+
+/// Decode farbfeld data with explicit [`FarbfeldDecodeOptions`].
+pub(crate) fn decode_with_options<'a>(
+    data: &[u8],
     limits: Option<&Limits>,
+    options: &FarbfeldDecodeOptions,
     alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    #[cfg(any(feature = "flate2", feature = "bzip2"))]
+    if let Some(decoded) = compressed::decode(data, limits, options, alloc_pref, stop) {
+        return decoded;
+    }
     let (width, height) = decode::parse_header(data)?;
+    if options.strict() {
+        decode::check_exact_len(data.len(), width, height)?;
+    }
     limits::check_dimensions(width, height, limits)?;
     let out_bytes = (width as usize)
         .checked_mul(height as usize)
@@ -50,6 +92,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
             ))
         })?;
     limits::check_output_size(out_bytes, limits)?;
+    limits::check_alloc(out_bytes, limits)?;
     stop.check().map_err(|r| at!(BitmapError::from(r)))?;
     let pixels = decode::decode_pixels(data, width, height, alloc_pref, stop)?;
     Ok(DecodeOutput::owned(
@@ -60,6 +103,149 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     ))
 }
 
+/// Borrow farbfeld pixel data as stored, without the endian swap.
+///
+/// The output is [`PixelLayout::Rgba16Be`] and points into `data`.
+pub(crate) fn decode_native<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let (width, height) = decode::parse_header(data)?;
+    limits::check_dimensions(width, height, limits)?;
+    let pixel_bytes = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(8))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+    let pixels = data
+        .get(16..)
+        .and_then(|rest| rest.get(..pixel_bytes))
+        .ok_or_else(|| {
+            at!(BitmapError::unexpected_eof()
+                .at_offset(data.len())
+                .in_stage("farbfeld pixel data"))
+        })?;
+    Ok(DecodeOutput::borrowed(
+        pixels,
+        width,
+        height,
+        PixelLayout::Rgba16Be,
+    ))
+}
+
+/// Decode farbfeld to RGBA8, rounding each sample in the endian-swap pass.
+pub(crate) fn decode_rgba8<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    let (width, height) = decode::parse_header(data)?;
+    limits::check_dimensions(width, height, limits)?;
+    let out_bytes = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(4))
+        .ok_or_else(|| {
+            at!(BitmapError::LimitExceeded(
+                "output size overflows usize".into()
+            ))
+        })?;
+    limits::check_output_size(out_bytes, limits)?;
+    limits::check_alloc(out_bytes, limits)?;
+    let mut pixels = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+    decode_into(
+        data,
+        &mut pixels,
+        width as usize * 4,
+        PixelLayout::Rgba8,
+        limits,
+        stop,
+    )?;
+    Ok(DecodeOutput::owned(
+        pixels,
+        width,
+        height,
+        PixelLayout::Rgba8,
+    ))
+}
+
+/// Decode farbfeld into `out`, one row every `stride` bytes, as `layout`
+/// (`Rgba16`, `Rgba16Be` or `Rgba8`). Returns the image dimensions.
+pub(crate) fn decode_into(
+    data: &[u8],
+    out: &mut [u8],
+    stride: usize,
+    layout: PixelLayout,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<(u32, u32)> {
+    limits::check_file_size(data.len(), limits)?;
+    let convert: fn(&[u8], &mut [u8]) = match layout {
+        PixelLayout::Rgba16 => decode::be16_to_ne_bulk,
+        PixelLayout::Rgba16Be => |src, dst| dst.copy_from_slice(src),
+        PixelLayout::Rgba8 => decode::be16_to_u8_rounded,
+        _ => {
+            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "cannot decode farbfeld as {layout:?} (supported: Rgba16, Rgba16Be, Rgba8)"
+            ))));
+        }
+    };
+    let (width, height) = decode::parse_header(data)?;
+    limits::check_dimensions(width, height, limits)?;
+    let too_large = || at!(BitmapError::DimensionsTooLarge { width, height });
+    let src_row = (width as usize).checked_mul(8).ok_or_else(too_large)?;
+    let dst_row = (width as usize)
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(too_large)?;
+    if stride < dst_row {
+        return Err(at!(BitmapError::invalid_data(alloc::format!(
+            "stride {stride} is less than the row size {dst_row}"
+        ))));
+    }
+    let needed = (height as usize - 1)
+        .checked_mul(stride)
+        .and_then(|n| n.checked_add(dst_row))
+        .ok_or_else(too_large)?;
+    if out.len() < needed {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed,
+            actual: out.len(),
+        }));
+    }
+    let pixels = (height as usize)
+        .checked_mul(src_row)
+        .and_then(|len| data.get(16..)?.get(..len))
+        .ok_or_else(|| {
+            at!(BitmapError::unexpected_eof()
+                .at_offset(data.len())
+                .in_stage("farbfeld pixel data"))
+        })?;
+    for (y, src) in pixels.chunks_exact(src_row).enumerate() {
+        if y % 16 == 0 {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let start = y * stride;
+        convert(src, &mut out[start..start + dst_row]);
+    }
+    Ok((width, height))
+}
+
+/// Row-at-a-time farbfeld decoder.
+pub(crate) fn rows<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: S,
+) -> crate::Result<FarbfeldRows<'a, S>> {
+    limits::check_file_size(data.len(), limits)?;
+    rows::rows(data, limits, stop)
+}
+
+/// Size of the file [`encode`] writes.
+pub(crate) fn encoded_size(width: u32, height: u32) -> crate::Result<usize> {
+    encode::encoded_size(width, height)
+}
+
 /// Encode pixels as farbfeld.
 pub(crate) fn encode(
     pixels: &[u8],
@@ -68,5 +254,24 @@ pub(crate) fn encode(
     layout: PixelLayout,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
-    encode::encode_farbfeld(pixels, width, height, layout, stop)
+    encode_with(
+        pixels,
+        width,
+        height,
+        layout,
+        &FarbfeldEncodeOptions::new(),
+        stop,
+    )
+}
+
+/// Encode pixels as farbfeld with explicit options.
+pub(crate) fn encode_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &FarbfeldEncodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    encode::encode_farbfeld(pixels, width, height, layout, options, stop)
 }
@@ -0,0 +1,101 @@
+//! Row-at-a-time farbfeld decoding (internal).
+//!
+//! Use top-level [`crate::decode_farbfeld_rows`]. Only one converted row is
+//! held in memory, so the decode cost no longer scales with the full
+//! 8-bytes-per-pixel frame.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use super::decode;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Farbfeld decoder that converts one row at a time.
+///
+/// Returned by [`crate::decode_farbfeld_rows`]. Each call to
+/// [`next_row`](Self::next_row) yields the next row, top to bottom, as
+/// native-endian [`PixelLayout::Rgba16`] bytes in a buffer reused between
+/// rows. A row missing from truncated input yields
+/// [`BitmapError::UnexpectedEof`] and ends the decode.
+pub struct FarbfeldRows<'a, S> {
+    pixels: &'a [u8],
+    width: u32,
+    height: u32,
+    next: u32,
+    row: Vec<u8>,
+    stop: S,
+}
+
+impl<S: Stop> FarbfeldRows<'_, S> {
+    /// Image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Layout of each row: always [`PixelLayout::Rgba16`].
+    pub fn layout(&self) -> PixelLayout {
+        PixelLayout::Rgba16
+    }
+
+    /// Rows not yet returned by [`next_row`](Self::next_row).
+    pub fn rows_remaining(&self) -> u32 {
+        self.height - self.next
+    }
+
+    /// Convert and return the next row, or `None` after the last one.
+    pub fn next_row(&mut self) -> Option<crate::Result<&[u8]>> {
+        if self.next == self.height {
+            return None;
+        }
+        let y = self.next as usize;
+        if y.is_multiple_of(16)
+            && let Err(r) = self.stop.check()
+        {
+            self.next = self.height;
+            return Some(Err(at!(BitmapError::from(r))));
+        }
+        let row_bytes = self.row.len();
+        let Some(src) = self.pixels.get(y * row_bytes..(y + 1) * row_bytes) else {
+            self.next = self.height;
+            return Some(Err(at!(BitmapError::unexpected_eof())));
+        };
+        decode::be16_to_ne_bulk(src, &mut self.row);
+        self.next += 1;
+        Some(Ok(&self.row))
+    }
+}
+
+pub(crate) fn rows<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: S,
+) -> crate::Result<FarbfeldRows<'a, S>> {
+    let (width, height) = decode::parse_header(data)?;
+    limits::check_dimensions(width, height, limits)?;
+    let row_bytes = (width as usize)
+        .checked_mul(8)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(row_bytes, limits)?;
+    limits::check_alloc(row_bytes, limits)?;
+    (height as usize)
+        .checked_mul(row_bytes)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+    Ok(FarbfeldRows {
+        pixels: &data[16..],
+        width,
+        height,
+        next: 0,
+        row: vec![0; row_bytes],
+        stop,
+    })
+}
@@ -0,0 +1,121 @@
+//! Back-to-back farbfeld frames in one buffer (internal).
+//!
+//! Each frame is a complete farbfeld file; its header fixes its length, so
+//! the next frame starts right after `16 + width * height * 8` bytes.
+
+use enough::Stop;
+use whereat::at;
+
+use super::decode::parse_header;
+use crate::alloc_util::AllocPref;
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+
+/// Iterator over concatenated farbfeld frames, decoding lazily.
+///
+/// Returned by [`crate::decode_farbfeld_stream`]. Yields one native-endian
+/// [`Rgba16`](crate::PixelLayout::Rgba16) image per frame, in file order.
+/// Frames may differ in size. A damaged frame yields its error and ends the
+/// iteration, since the following frame boundary is unknown.
+pub struct FarbfeldStream<'a, S> {
+    data: &'a [u8],
+    offset: usize,
+    limits: Option<&'a Limits>,
+    stop: S,
+    failed: bool,
+}
+
+impl<S> FarbfeldStream<'_, S> {
+    /// Byte offset of the next frame.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a, S: Stop> Iterator for FarbfeldStream<'a, S> {
+    type Item = crate::Result<DecodeOutput<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.offset == self.data.len() {
+            return None;
+        }
+        let decoded = next_frame(
+            self.data,
+            self.offset,
+            self.limits,
+            AllocPref::CodecDefault,
+            &self.stop,
+        );
+        Some(match decoded {
+            Ok((image, end)) => {
+                self.offset = end;
+                Ok(image)
+            }
+            Err(e) => {
+                self.failed = true;
+                Err(e)
+            }
+        })
+    }
+}
+
+impl<S: Stop> core::iter::FusedIterator for FarbfeldStream<'_, S> {}
+
+pub(crate) fn stream<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&'a Limits>,
+    stop: S,
+) -> crate::Result<FarbfeldStream<'a, S>> {
+    limits::check_file_size(data.len(), limits)?;
+    parse_header(data)?;
+    Ok(FarbfeldStream {
+        data,
+        offset: 0,
+        limits,
+        stop,
+        failed: false,
+    })
+}
+
+/// Decode the frame at `offset`, returning it with the offset just past it.
+pub(crate) fn next_frame<'a>(
+    data: &[u8],
+    offset: usize,
+    limits: Option<&Limits>,
+    alloc_pref: AllocPref,
+    stop: &dyn Stop,
+) -> crate::Result<(DecodeOutput<'a>, usize)> {
+    let rest = &data[offset..];
+    let len = frame_len(rest)?;
+    let frame = rest
+        .get(..len)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let image = super::decode_with_alloc_pref(frame, limits, alloc_pref, stop)?;
+    Ok((image, offset + len))
+}
+
+/// Number of frames, found by walking the headers without decoding.
+#[cfg(feature = "zencodec")]
+pub(crate) fn count_frames(data: &[u8]) -> crate::Result<u32> {
+    let (mut offset, mut count) = (0usize, 0u32);
+    while offset < data.len() {
+        let len = frame_len(&data[offset..])?;
+        offset = offset
+            .checked_add(len)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        count = count.saturating_add(1);
+    }
+    Ok(count)
+}
+
+/// Total length (header + pixels) of the frame starting `data`.
+fn frame_len(data: &[u8]) -> crate::Result<usize> {
+    let (width, height) = parse_header(data)?;
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(8))
+        .and_then(|n| n.checked_add(16))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))
+}
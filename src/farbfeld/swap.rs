@@ -0,0 +1,59 @@
+//! SIMD byte swap of 16-bit samples (internal).
+//!
+//! On little-endian hosts the farbfeld big-endian → native conversion is a
+//! plain byte swap within every u16, which is one shuffle per vector.
+//! [`swap_u16_bytes`] converts as many whole vectors as the CPU allows and
+//! returns how many bytes it handled; the caller finishes the tail with
+//! the scalar loop.
+
+#[cfg(target_arch = "aarch64")]
+use archmage::NeonToken;
+#[cfg(target_arch = "x86_64")]
+use archmage::X64V3Token;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+use archmage::{SimdToken, arcane};
+
+/// Byte-swap each u16 of `src` into `dst`, returning the bytes converted
+/// (0 when no SIMD tier is available).
+pub(crate) fn swap_u16_bytes(src: &[u8], dst: &mut [u8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = X64V3Token::summon() {
+        return swap_avx2(token, src, dst);
+    }
+    #[cfg(target_arch = "aarch64")]
+    if let Some(token) = NeonToken::summon() {
+        return swap_neon(token, src, dst);
+    }
+    let _ = (src, dst);
+    0
+}
+
+#[cfg(target_arch = "x86_64")]
+#[arcane(import_intrinsics)]
+fn swap_avx2(_token: X64V3Token, src: &[u8], dst: &mut [u8]) -> usize {
+    let mask = _mm256_setr_epi8(
+        1, 0, 3, 2, 5, 4, 7, 6, 9, 8, 11, 10, 13, 12, 15, 14, //
+        1, 0, 3, 2, 5, 4, 7, 6, 9, 8, 11, 10, 13, 12, 15, 14,
+    );
+    let mut done = 0;
+    for (s, d) in src.chunks_exact(32).zip(dst.chunks_exact_mut(32)) {
+        let s: &[u8; 32] = s.try_into().unwrap();
+        let d: &mut [u8; 32] = d.try_into().unwrap();
+        _mm256_storeu_si256(d, _mm256_shuffle_epi8(_mm256_loadu_si256(s), mask));
+        done += 32;
+    }
+    done
+}
+
+#[cfg(target_arch = "aarch64")]
+#[arcane(import_intrinsics)]
+fn swap_neon(_token: NeonToken, src: &[u8], dst: &mut [u8]) -> usize {
+    let mut done = 0;
+    for (s, d) in src.chunks_exact(16).zip(dst.chunks_exact_mut(16)) {
+        let s: &[u8; 16] = s.try_into().unwrap();
+        let d: &mut [u8; 16] = d.try_into().unwrap();
+        vst1q_u8(d, vrev16q_u8(vld1q_u8(s)));
+        done += 16;
+    }
+    done
+}
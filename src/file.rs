@@ -0,0 +1,132 @@
+//! File-path entry points: [`FileData`] and the I/O behind
+//! [`crate::decode_file`] and [`crate::encode_file`].
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+use std::path::Path;
+
+use whereat::at;
+
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+
+/// The bytes of a file, read into memory or (with the `mmap` feature and
+/// the unsafe `map`) memory-mapped.
+///
+/// Derefs to `&[u8]`, so it can be handed to [`decode`](crate::decode) or
+/// [`DecodeRequest`](crate::DecodeRequest) directly. Zero-copy decodes
+/// (8-bit PNM, farbfeld with native order) then borrow their pixels from
+/// the file, and with a mapping nothing is copied between disk and pixels.
+///
+/// ```no_run
+/// use zenbitmaps::*;
+/// let file = FileData::open("huge.ppm")?;
+/// let decoded = decode(&file, Unstoppable)?;
+/// assert!(decoded.is_borrowed());
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[derive(Debug)]
+pub struct FileData {
+    bytes: Bytes,
+}
+
+#[derive(Debug)]
+enum Bytes {
+    Read(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl FileData {
+    /// Read the whole file at `path` into memory. The same as
+    /// [`read`](Self::read); mapping is only ever done by the unsafe `map`.
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::read(path)
+    }
+
+    /// Read the whole file at `path` into memory.
+    pub fn read(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| at!(BitmapError::Io(e)))?;
+        Ok(Self {
+            bytes: Bytes::Read(bytes),
+        })
+    }
+
+    /// Memory-map the file at `path` read-only. Empty files are read
+    /// instead, since they cannot be mapped everywhere.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or written to, by this or any other
+    /// process, while the returned value or anything borrowed from it is
+    /// alive. Shrinking the file makes reads of the mapping fault
+    /// (`SIGBUS`), and writes change bytes behind live `&[u8]` borrows,
+    /// including already-decoded borrowed pixels. Use [`read`](Self::read)
+    /// for files that may change.
+    #[cfg(feature = "mmap")]
+    #[allow(unsafe_code)]
+    pub unsafe fn map(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let file = std::fs::File::open(path).map_err(|e| at!(BitmapError::Io(e)))?;
+        let len = file.metadata().map_err(|e| at!(BitmapError::Io(e)))?.len();
+        if len == 0 {
+            return Ok(Self {
+                bytes: Bytes::Read(Vec::new()),
+            });
+        }
+        // SAFETY: the mapping is read-only and owned by this value; the
+        // caller guarantees the file is not modified while it is alive.
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| at!(BitmapError::Io(e)))?;
+        Ok(Self {
+            bytes: Bytes::Mapped(map),
+        })
+    }
+
+    /// The file's bytes.
+    pub fn bytes(&self) -> &[u8] {
+        match &self.bytes {
+            Bytes::Read(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            Bytes::Mapped(map) => map,
+        }
+    }
+
+    /// Whether the bytes are memory-mapped rather than read.
+    pub fn is_mapped(&self) -> bool {
+        match self.bytes {
+            Bytes::Read(_) => false,
+            #[cfg(feature = "mmap")]
+            Bytes::Mapped(_) => true,
+        }
+    }
+}
+
+impl Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.bytes()
+    }
+}
+
+impl AsRef<[u8]> for FileData {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes()
+    }
+}
+
+/// [`FileData::open`], failing before anything is read when the file is
+/// over `limits`' `max_file_size`.
+pub(crate) fn open_within(path: &Path, limits: Option<&Limits>) -> crate::Result<FileData> {
+    if limits.is_some_and(|l| l.max_file_size.is_some()) {
+        let len = std::fs::metadata(path)
+            .map_err(|e| at!(BitmapError::Io(e)))?
+            .len();
+        limits::check_file_size(usize::try_from(len).unwrap_or(usize::MAX), limits)?;
+    }
+    FileData::open(path)
+}
+
+/// Write `bytes` to a new file at `path`, replacing any existing one.
+pub(crate) fn write(path: &Path, bytes: &[u8]) -> crate::Result<()> {
+    std::fs::write(path, bytes).map_err(|e| at!(BitmapError::Io(e)))
+}
@@ -13,7 +13,7 @@ use crate::error::BitmapError;
 /// and parses the resolution line (`-Y <height> +X <width>`).
 pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(u32, u32, usize)> {
     if data.len() < 10 {
-        return Err(at!(BitmapError::UnexpectedEof));
+        return Err(at!(BitmapError::unexpected_eof()));
     }
     if !data.starts_with(b"#?RADIANCE") && !data.starts_with(b"#?RGBE") {
         return Err(at!(BitmapError::UnrecognizedFormat));
@@ -39,28 +39,25 @@ pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(u32, u32, usize)> {
                 break;
             }
         } else {
-            return Err(at!(BitmapError::InvalidHeader(
-                "HDR header: no newline found".into(),
+            return Err(at!(BitmapError::invalid_header(
+                "HDR header: no newline found",
             )));
         }
     }
 
     if !found_empty_line {
-        return Err(at!(BitmapError::InvalidHeader(
-            "HDR header: missing empty line separator".into(),
+        return Err(at!(BitmapError::invalid_header(
+            "HDR header: missing empty line separator",
         )));
     }
 
     // Now parse the resolution line: `-Y <height> +X <width>\n`
     let remaining = &data[pos..];
-    let nl = memchr_newline(remaining).ok_or_else(|| {
-        at!(BitmapError::InvalidHeader(
-            "HDR: missing resolution line".into()
-        ))
-    })?;
+    let nl = memchr_newline(remaining)
+        .ok_or_else(|| at!(BitmapError::invalid_header("HDR: missing resolution line")))?;
     let res_line = core::str::from_utf8(&remaining[..nl]).map_err(|_| {
-        at!(BitmapError::InvalidHeader(
-            "HDR: resolution line not UTF-8".into()
+        at!(BitmapError::invalid_header(
+            "HDR: resolution line not UTF-8"
         ))
     })?;
     let res_offset = pos + nl + 1; // byte after the resolution line's \n
@@ -68,8 +65,8 @@ pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(u32, u32, usize)> {
     let (width, height) = parse_resolution(res_line)?;
 
     if width == 0 || height == 0 {
-        return Err(at!(BitmapError::InvalidHeader(
-            "HDR: width or height is zero".into(),
+        return Err(at!(BitmapError::invalid_header(
+            "HDR: width or height is zero",
         )));
     }
 
@@ -80,7 +77,7 @@ pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(u32, u32, usize)> {
 fn parse_resolution(s: &str) -> crate::Result<(u32, u32)> {
     let parts: Vec<&str> = s.split_whitespace().collect();
     if parts.len() != 4 {
-        return Err(at!(BitmapError::InvalidHeader(alloc::format!(
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
             "HDR: expected 4 tokens in resolution line, got {}: '{s}'",
             parts.len()
         ))));
@@ -88,13 +85,13 @@ fn parse_resolution(s: &str) -> crate::Result<(u32, u32)> {
     // Standard orientation: -Y height +X width
     if parts[0] == "-Y" && parts[2] == "+X" {
         let height: u32 = parts[1].parse().map_err(|_| {
-            at!(BitmapError::InvalidHeader(alloc::format!(
+            at!(BitmapError::invalid_header(alloc::format!(
                 "HDR: invalid height '{}'",
                 parts[1]
             )))
         })?;
         let width: u32 = parts[3].parse().map_err(|_| {
-            at!(BitmapError::InvalidHeader(alloc::format!(
+            at!(BitmapError::invalid_header(alloc::format!(
                 "HDR: invalid width '{}'",
                 parts[3]
             )))
@@ -134,8 +131,11 @@ pub(crate) fn decode_pixels(
     let mut out_pos = 0;
     let mut pos = offset;
 
-    // Scanline buffer for new-style RLE (4 channels × width)
+    // Scanline buffer for either RLE style (4 channels × width)
     let mut scanline_buf = alloc_util::alloc_zeroed(alloc_pref, false, w * 4)?;
+    // Last pixel written, repeated by old-style run markers (which may
+    // refer back across a scanline boundary)
+    let mut prev = [0u8; 4];
 
     for row in 0..h {
         if row % 16 == 0 {
@@ -143,7 +143,7 @@ pub(crate) fn decode_pixels(
         }
 
         if pos + 4 > data.len() {
-            return Err(at!(BitmapError::UnexpectedEof));
+            return Err(at!(BitmapError::unexpected_eof()));
         }
 
         // Check for new-style RLE marker
@@ -152,7 +152,7 @@ pub(crate) fn decode_pixels(
             // New-style RLE scanline
             let encoded_width = ((data[pos + 2] as usize) << 8) | (data[pos + 3] as usize);
             if encoded_width != w {
-                return Err(at!(BitmapError::InvalidData(alloc::format!(
+                return Err(at!(BitmapError::invalid_data(alloc::format!(
                     "HDR RLE: scanline width mismatch (expected {w}, got {encoded_width})"
                 ))));
             }
@@ -163,7 +163,7 @@ pub(crate) fn decode_pixels(
                 let mut col = 0;
                 while col < w {
                     if pos >= data.len() {
-                        return Err(at!(BitmapError::UnexpectedEof));
+                        return Err(at!(BitmapError::unexpected_eof()));
                     }
                     let code = data[pos];
                     pos += 1;
@@ -172,12 +172,12 @@ pub(crate) fn decode_pixels(
                         // Run: (code - 128) copies of next byte
                         let count = (code - 128) as usize;
                         if col + count > w {
-                            return Err(at!(BitmapError::InvalidData(
-                                "HDR RLE: run overflows scanline".into(),
+                            return Err(at!(BitmapError::invalid_data(
+                                "HDR RLE: run overflows scanline",
                             )));
                         }
                         if pos >= data.len() {
-                            return Err(at!(BitmapError::UnexpectedEof));
+                            return Err(at!(BitmapError::unexpected_eof()));
                         }
                         let val = data[pos];
                         pos += 1;
@@ -189,17 +189,17 @@ pub(crate) fn decode_pixels(
                         // Literal: `code` distinct values
                         let count = code as usize;
                         if count == 0 {
-                            return Err(at!(BitmapError::InvalidData(
-                                "HDR RLE: zero-length literal run".into(),
+                            return Err(at!(BitmapError::invalid_data(
+                                "HDR RLE: zero-length literal run",
                             )));
                         }
                         if col + count > w {
-                            return Err(at!(BitmapError::InvalidData(
-                                "HDR RLE: literal overflows scanline".into(),
+                            return Err(at!(BitmapError::invalid_data(
+                                "HDR RLE: literal overflows scanline",
                             )));
                         }
                         if pos + count > data.len() {
-                            return Err(at!(BitmapError::UnexpectedEof));
+                            return Err(at!(BitmapError::unexpected_eof()));
                         }
                         for i in 0..count {
                             scanline_buf[(col + i) * 4 + ch] = data[pos + i];
@@ -215,21 +215,60 @@ pub(crate) fn decode_pixels(
             rgbe_deinterleaved_to_f32(&scanline_buf[..w * 4], w, row_out);
             out_pos += w * 12;
         } else {
-            // Uncompressed: read flat RGBE quads
-            let needed = w * 4;
-            if pos + needed > data.len() {
-                return Err(at!(BitmapError::UnexpectedEof));
-            }
+            // Flat RGBE quads, possibly with old-style run markers
+            pos = decode_old_scanline(data, pos, &mut scanline_buf[..w * 4], &mut prev)?;
             let row_out = &mut out[out_pos..out_pos + w * 12];
-            rgbe_scanline_to_f32(&data[pos..pos + needed], row_out);
+            rgbe_scanline_to_f32(&scanline_buf[..w * 4], row_out);
             out_pos += w * 12;
-            pos += needed;
         }
     }
 
     Ok(out)
 }
 
+/// Decode one flat or old-style RLE scanline into `scanline` (interleaved
+/// RGBE), returning the position after it.
+///
+/// A `(1, 1, 1, n)` pixel repeats the previous pixel `n` times; each
+/// consecutive marker shifts its count 8 bits further left.
+fn decode_old_scanline(
+    data: &[u8],
+    mut pos: usize,
+    scanline: &mut [u8],
+    prev: &mut [u8; 4],
+) -> crate::Result<usize> {
+    let mut col = 0;
+    let mut shift = 0u32;
+    while col < scanline.len() {
+        let px: [u8; 4] = data
+            .get(pos..pos + 4)
+            .and_then(|px| px.try_into().ok())
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        pos += 4;
+        if px[..3] == [1, 1, 1] {
+            let count = (px[3] as usize)
+                .checked_shl(shift)
+                .filter(|&n| n <= (scanline.len() - col) / 4)
+                .ok_or_else(|| {
+                    at!(BitmapError::invalid_data(
+                        "HDR RLE: old-style run overflows scanline",
+                    ))
+                })?;
+            for dst in scanline[col..col + count * 4].chunks_exact_mut(4) {
+                dst.copy_from_slice(prev);
+            }
+            col += count * 4;
+            shift += 8;
+        } else {
+            scanline[col..col + 4].copy_from_slice(&px);
+            *prev = px;
+            col += 4;
+            shift = 0;
+        }
+    }
+    Ok(pos)
+}
+
 /// Convert RGBE (4 bytes) to 3×f32 linear RGB.
 ///
 /// Uses bit manipulation to compute `2^(e - 136)` without libm or unsafe.
@@ -36,6 +36,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
     let (width, height, offset) = decode::parse_header(data)?;
     limits::check_dimensions(width, height, limits)?;
     let out_bytes = (width as usize)
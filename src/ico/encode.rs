@@ -0,0 +1,145 @@
+//! ICO writing: 32-bit BMP DIB entries with a derived AND mask.
+
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::bmp::{self, BmpEncodeOptions};
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+
+/// One image to store in an icon, for [`crate::encode_ico`].
+///
+/// ```
+/// use zenbitmaps::{IcoImage, PixelLayout};
+///
+/// let pixels = [0u8; 16 * 16 * 4];
+/// let image = IcoImage::new(&pixels, 16, 16, PixelLayout::Rgba8);
+/// assert_eq!((image.width(), image.height()), (16, 16));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IcoImage<'a> {
+    pixels: &'a [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+}
+
+impl<'a> IcoImage<'a> {
+    /// `width × height` pixels (each 1 to 256) in `layout`. Any layout
+    /// [`crate::encode_bmp_rgba`] accepts will do; layouts without alpha
+    /// are stored opaque.
+    pub fn new(pixels: &'a [u8], width: u32, height: u32, layout: PixelLayout) -> Self {
+        Self {
+            pixels,
+            width,
+            height,
+            layout,
+        }
+    }
+
+    /// Pixel bytes.
+    pub fn pixels(&self) -> &'a [u8] {
+        self.pixels
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Layout of [`pixels`](Self::pixels).
+    pub fn layout(&self) -> PixelLayout {
+        self.layout
+    }
+}
+
+/// Size of the `BITMAPINFOHEADER` the BMP encoder writes by default.
+const INFO_HEADER_SIZE: usize = 40;
+
+/// Write an ICO holding `images` in order, each as a 32-bit DIB.
+pub(crate) fn encode(images: &[IcoImage<'_>], stop: &dyn Stop) -> crate::Result<Vec<u8>> {
+    if images.is_empty() {
+        return Err(at!(BitmapError::invalid_data(
+            "ICO needs at least one image"
+        )));
+    }
+    let count = u16::try_from(images.len()).map_err(|_| {
+        at!(BitmapError::invalid_data(alloc::format!(
+            "ICO holds at most 65535 images, got {}",
+            images.len()
+        )))
+    })?;
+
+    let dibs = images
+        .iter()
+        .map(|image| dib(image, stop))
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    let mut out =
+        Vec::with_capacity(6 + 16 * images.len() + dibs.iter().map(Vec::len).sum::<usize>());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // icon
+    out.extend_from_slice(&count.to_le_bytes());
+    let mut offset = 6 + 16 * images.len();
+    for (image, dib) in images.iter().zip(&dibs) {
+        // 256 is stored as 0.
+        out.push(image.width as u8);
+        out.push(image.height as u8);
+        out.extend_from_slice(&[0, 0]); // no palette, reserved
+        out.extend_from_slice(&1u16.to_le_bytes()); // planes
+        out.extend_from_slice(&32u16.to_le_bytes()); // bits per pixel
+        out.extend_from_slice(&(dib.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += dib.len();
+    }
+    for dib in &dibs {
+        out.extend_from_slice(dib);
+    }
+    Ok(out)
+}
+
+/// A 32-bit bottom-up DIB with doubled height, followed by an AND mask
+/// that hides every pixel whose alpha is below 128, for readers that
+/// ignore the alpha channel.
+fn dib(image: &IcoImage<'_>, stop: &dyn Stop) -> crate::Result<Vec<u8>> {
+    let IcoImage { width, height, .. } = *image;
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "ICO image width or height is zero"
+        )));
+    }
+    if width > 256 || height > 256 {
+        return Err(at!(BitmapError::DimensionsTooLarge { width, height }));
+    }
+    let options = BmpEncodeOptions::new()
+        .with_alpha(true)
+        .with_pixels_per_meter(0, 0);
+    let bmp = bmp::encode(image.pixels, width, height, image.layout, &options, stop)?;
+    let mut dib = bmp[14..].to_vec();
+    let stored_height = 2 * height as i32;
+    dib[8..12].copy_from_slice(&stored_height.to_le_bytes());
+
+    let w = width as usize;
+    let mask_stride = w.div_ceil(32) * 4;
+    let mut mask = alloc::vec![0u8; mask_stride * height as usize];
+    // Rows are bottom-up in both bitmaps, so they pair up directly.
+    let xor = dib[INFO_HEADER_SIZE..].chunks_exact(w * 4);
+    for (y, (row, bits)) in xor.zip(mask.chunks_exact_mut(mask_stride)).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in row.chunks_exact(4).enumerate() {
+            if px[3] < 128 {
+                bits[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+    dib.extend_from_slice(&mask);
+    Ok(dib)
+}
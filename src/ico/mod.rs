@@ -0,0 +1,300 @@
+//! ICO / CUR container decoding and ICO encoding (internal).
+//!
+//! Use top-level [`crate::decode_ico`], [`crate::probe_ico`],
+//! [`crate::encode_ico`], etc.
+//!
+//! An ICO/CUR file is a small directory of images. Each entry is either a
+//! PNG stream or a headerless BMP DIB whose stored height is doubled to cover
+//! the XOR (color) bitmap followed by a 1-bit AND (transparency) mask. DIB
+//! entries are decoded by the BMP decoder and the AND mask folded into alpha.
+
+use crate::alloc_util::AllocPref;
+use crate::bmp::{self, BmpAlphaPolicy, BmpDecodeOptions, DibInfo};
+use crate::decode::DecodeOutput;
+use crate::error::{BitmapError, ResultExt};
+use crate::limits::Limits;
+use crate::pixel::PixelLayout;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+mod encode;
+
+pub use encode::IcoImage;
+pub(crate) use encode::encode;
+
+/// Whether an ICO-family file is an icon or a cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IcoKind {
+    /// `.ico` (resource type 1).
+    Icon,
+    /// `.cur` (resource type 2); entries carry a hotspot.
+    Cursor,
+}
+
+/// One image in an ICO/CUR directory.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct IcoEntry {
+    /// Width in pixels from the directory (a stored 0 means 256).
+    pub width: u32,
+
+    /// Height in pixels from the directory (a stored 0 means 256).
+    pub height: u32,
+
+    /// Bits per pixel. Taken from the directory for icons; for cursors (whose
+    /// directory slot holds the hotspot) and zero-valued slots it is read
+    /// from the embedded DIB header. 0 when unknown (e.g. PNG cursors).
+    pub bit_count: u16,
+
+    /// Cursor hotspot `(x, y)`; `None` for icons.
+    pub hotspot: Option<(u16, u16)>,
+
+    /// Byte offset of the image data within the file.
+    pub offset: u32,
+
+    /// Byte length of the image data.
+    pub size: u32,
+
+    /// The entry is a PNG stream rather than a DIB. zenbitmaps does not
+    /// decode PNG; slice `data[offset..offset + size]` and hand it to a PNG
+    /// decoder.
+    pub is_png: bool,
+}
+
+/// Parsed ICO/CUR directory.
+///
+/// Returned by [`crate::probe_ico`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct IcoDirectory {
+    /// Icon or cursor.
+    pub kind: IcoKind,
+
+    /// Directory entries in file order.
+    pub entries: Vec<IcoEntry>,
+}
+
+const PNG_MAGIC: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Parse the ICO/CUR directory.
+pub(crate) fn probe(data: &[u8]) -> crate::Result<IcoDirectory> {
+    if data.len() < 6 {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    let reserved = u16::from_le_bytes([data[0], data[1]]);
+    let kind = match (reserved, u16::from_le_bytes([data[2], data[3]])) {
+        (0, 1) => IcoKind::Icon,
+        (0, 2) => IcoKind::Cursor,
+        _ => return Err(at!(BitmapError::UnrecognizedFormat)),
+    };
+    let count = usize::from(u16::from_le_bytes([data[4], data[5]]));
+    if count == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "ICO directory has no entries"
+        )));
+    }
+    let dir = data
+        .get(6..6 + count * 16)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+
+    let entries = dir
+        .chunks_exact(16)
+        .map(|e| {
+            let dim = |b: u8| if b == 0 { 256 } else { u32::from(b) };
+            let field_a = u16::from_le_bytes([e[4], e[5]]);
+            let field_b = u16::from_le_bytes([e[6], e[7]]);
+            let size = u32::from_le_bytes([e[8], e[9], e[10], e[11]]);
+            let offset = u32::from_le_bytes([e[12], e[13], e[14], e[15]]);
+            let image = data.get(offset as usize..).unwrap_or(&[]);
+            let is_png = image.starts_with(PNG_MAGIC);
+            let (hotspot, dir_bpp) = match kind {
+                IcoKind::Icon => (None, field_b),
+                IcoKind::Cursor => (Some((field_a, field_b)), 0),
+            };
+            let bit_count = if dir_bpp != 0 || is_png {
+                dir_bpp
+            } else {
+                bmp::parse_dib_info(image, false).map_or(0, |info| info.bpp)
+            };
+            IcoEntry {
+                width: dim(e[0]),
+                height: dim(e[1]),
+                bit_count,
+                hotspot,
+                offset,
+                size,
+                is_png,
+            }
+        })
+        .collect();
+
+    Ok(IcoDirectory { kind, entries })
+}
+
+/// Decode the best entry: the largest DIB image, ties broken by bit depth.
+/// PNG entries are skipped.
+pub(crate) fn decode<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    let dir = probe(data)?;
+    let best = dir
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !e.is_png)
+        .max_by_key(|(_, e)| (u64::from(e.width) * u64::from(e.height), e.bit_count))
+        .map(|(i, _)| i)
+        .ok_or_else(|| {
+            at!(BitmapError::UnsupportedVariant(
+                "ICO contains only PNG entries".into()
+            ))
+        })?;
+    decode_entry_in(data, &dir, best, limits, stop)
+}
+
+/// Decode the entry at `index` (directory order).
+pub(crate) fn decode_entry<'a>(
+    data: &'a [u8],
+    index: usize,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    crate::limits::check_file_size(data.len(), limits)?;
+    let dir = probe(data)?;
+    decode_entry_in(data, &dir, index, limits, stop)
+}
+
+fn decode_entry_in<'a>(
+    data: &'a [u8],
+    dir: &IcoDirectory,
+    index: usize,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    let entry = dir.entries.get(index).ok_or_else(|| {
+        at!(BitmapError::invalid_data(alloc::format!(
+            "ICO entry index {index} out of range ({} entries)",
+            dir.entries.len()
+        )))
+    })?;
+    if entry.is_png {
+        return Err(at!(BitmapError::UnsupportedVariant(
+            "PNG-compressed ICO entry".into()
+        )));
+    }
+    let start = entry.offset as usize;
+    let end = start
+        .checked_add(entry.size as usize)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let dib = data
+        .get(start..end)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+
+    let info = bmp::parse_dib_info(dib, false)?;
+    // The stored height covers the XOR bitmap and the AND mask.
+    let height = info.height / 2;
+    if height == 0 {
+        return Err(at!(BitmapError::invalid_header("ICO DIB height is zero")));
+    }
+    let bmp_data = bmp::wrap_dib(dib, &info, height, limits)?;
+
+    // 32-bit entries carry real alpha in the fourth byte, including
+    // legitimately all-transparent pixels.
+    let options = BmpDecodeOptions::new().with_alpha_policy(BmpAlphaPolicy::Trust);
+    // ICO has no permissive mode to retry with.
+    let decoded = bmp::decode_to_vec(&bmp_data, limits, &options, AllocPref::CodecDefault, stop)
+        .permissive_may_recover(false)?;
+    let (width, out_height) = (decoded.width, decoded.height);
+
+    if decoded.layout != PixelLayout::Rgba8 {
+        // The widened copy is alive alongside the decoded pixels and the
+        // wrapped DIB.
+        let rgba_len = (width as usize * out_height as usize).saturating_mul(4);
+        let working = rgba_len
+            .saturating_add(decoded.pixels.len())
+            .saturating_add(bmp_data.len());
+        crate::limits::check_working_memory(working, limits)?;
+        crate::limits::check_alloc(rgba_len, limits)?;
+    }
+    let mut rgba = to_rgba8(decoded.pixels, decoded.layout)?;
+    let has_alpha = info.bpp == 32 && rgba.chunks_exact(4).any(|px| px[3] != 0);
+    if !has_alpha {
+        for px in rgba.chunks_exact_mut(4) {
+            px[3] = 255;
+        }
+        apply_and_mask(&mut rgba, dib, &info, width, out_height, stop)?;
+    }
+
+    Ok(DecodeOutput::owned(
+        rgba,
+        width,
+        out_height,
+        PixelLayout::Rgba8,
+    ))
+}
+
+/// Widen BMP decoder output to RGBA8 (alpha filled in later).
+fn to_rgba8(pixels: Vec<u8>, layout: PixelLayout) -> crate::Result<Vec<u8>> {
+    let expand = |bpp: usize, f: fn(&[u8]) -> [u8; 4]| {
+        pixels.chunks_exact(bpp).flat_map(f).collect::<Vec<u8>>()
+    };
+    match layout {
+        PixelLayout::Rgba8 => Ok(pixels),
+        PixelLayout::Rgb8 => Ok(expand(3, |p| [p[0], p[1], p[2], 255])),
+        PixelLayout::Gray8 => Ok(expand(1, |p| [p[0], p[0], p[0], 255])),
+        other => Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "ICO entry decodes to {other:?}, expected 8-bit channels"
+        )))),
+    }
+}
+
+/// Clear alpha where the AND mask bit is set. A missing or truncated mask
+/// leaves the remaining pixels opaque; compressed XOR bitmaps have no
+/// locatable mask and stay opaque.
+///
+/// "Inverted" pixels (AND = 1 with a non-zero XOR color) depend on the screen
+/// contents and cannot be represented; they come out transparent.
+fn apply_and_mask(
+    rgba: &mut [u8],
+    dib: &[u8],
+    info: &DibInfo,
+    width: u32,
+    height: u32,
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    if info.compression != 0 {
+        return Ok(());
+    }
+    let w = width as usize;
+    let h = height as usize;
+    let xor_stride = (w * usize::from(info.bpp)).div_ceil(32) * 4;
+    let mask_stride = w.div_ceil(32) * 4;
+    let mask_start = info
+        .pixel_offset()
+        .saturating_add(xor_stride.saturating_mul(h));
+    let Some(mask) = dib.get(mask_start..) else {
+        return Ok(());
+    };
+    let bottom_up = info.height > 0;
+
+    for (y, row) in rgba.chunks_exact_mut(w * 4).enumerate() {
+        if y % 16 == 0 {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let src_row = if bottom_up { h - 1 - y } else { y };
+        let Some(bits) = mask.get(src_row * mask_stride..(src_row + 1) * mask_stride) else {
+            continue;
+        };
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            if bits[x / 8] & (0x80 >> (x % 8)) != 0 {
+                px[3] = 0;
+            }
+        }
+    }
+    Ok(())
+}
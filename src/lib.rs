@@ -3,7 +3,8 @@
 //! PNM/PAM/PFM, BMP, farbfeld, QOI, TGA, and Radiance HDR image format decoder and encoder.
 //!
 //! Reference bitmap formats for codec testing and apples-to-apples comparisons.
-//! `no_std` compatible (with `alloc`), `forbid(unsafe_code)`, panic-free.
+//! `no_std` compatible (with `alloc`), `forbid(unsafe_code)` (except the
+//! opt-in `mmap` file mapping and `capi` C ABI), panic-free.
 //!
 //! ## Quick Start
 //!
@@ -42,11 +43,24 @@
 //! [`decode()`] uses this internally — you only need `detect_format()` if you
 //! want to inspect the format before committing to a full decode.
 //!
+//! [`decode_region()`] decodes just a crop window. BMP and binary PNM seek to
+//! the window's rows instead of decoding the whole image.
+//!
+//! [`decode_rows()`] hands each decoded row to a callback instead of
+//! returning the frame; binary PNM, uncompressed BMP, farbfeld and QOI are
+//! decoded a band at a time.
+//!
+//! [`decode_data_uri()`] decodes a base64 `data:image/...` URI, as embedded
+//! in HTML pages and test manifests.
+//!
 //! ## Zero-Copy Decoding
 //!
 //! For PNM files with maxval=255 (the common case), decoding returns a borrowed
 //! slice into the input buffer — no allocation or copy needed. Formats that
-//! require transformation (BMP row flip, farbfeld endian swap, etc.) allocate.
+//! require transformation (BMP row flip, farbfeld endian swap, etc.) allocate;
+//! [`decode_farbfeld_native()`] skips the swap and borrows farbfeld pixels
+//! as [`PixelLayout::Rgba16Be`]. Headerless dumps with tight rows in native
+//! byte order are borrowed by [`decode_raw()`].
 //!
 //! Use `DecodeOutput::as_pixels()` for a zero-copy typed pixel view,
 //! or `DecodeOutput::as_imgref()` for a zero-copy 2D view (with `imgref` feature):
@@ -84,6 +98,27 @@
 //! All encoders accept BGR/BGRA input and swizzle to the target format's
 //! channel order automatically.
 //!
+//! ## Layout Conversion
+//!
+//! With the `convert` feature, [`convert()`] turns pixels of any
+//! [`PixelLayout`] into any other: channel swizzles, gray ↔ RGB, adding or
+//! dropping alpha, 8 ↔ 16 bit, and integer ↔ float with the sRGB curve or
+//! none ([`Transfer`]). [`convert_into()`] writes into a caller buffer, and
+//! [`srgb_to_linear()`] / [`linear_to_srgb()`] apply the sRGB curve to
+//! plain sample buffers.
+//!
+//! ## Premultiplied Alpha
+//!
+//! [`premultiply_alpha()`] and [`unpremultiply_alpha()`] convert buffers
+//! with alpha in place. Encoder options take an [`AlphaMode`] to declare
+//! premultiplied input.
+//!
+//! ## Debug Text
+//!
+//! [`encode_debug_text()`] prints pixels as an aligned grid of decimal
+//! samples, so both sides of a failed round trip can be shown and diffed;
+//! [`decode_debug_text()`] reads that grid, or a hand-written one, back.
+//!
 //! ## Supported Formats
 //!
 //! ### PNM family (always available)
@@ -95,14 +130,125 @@
 //! ### Farbfeld (always available)
 //! - RGBA 16-bit per channel
 //! - Auto-detected by [`decode()`] via `"farbfeld"` magic
+//! - gzip/bzip2-compressed files decode transparently (`flate2` / `bzip2`
+//!   features)
+//! - Row-at-a-time decoding via [`decode_farbfeld_rows()`], or into a caller
+//!   buffer via [`decode_farbfeld_into()`]
+//! - Concatenated frames via [`decode_farbfeld_stream()`]
 //!
 //! ### BMP (`bmp` feature, opt-in)
-//! - All standard bit depths: 1, 2, 4, 8, 16, 24, 32
+//! - All standard bit depths: 1, 2, 4, 8, 16, 24, 32, and 64 (s2.13 linear)
 //! - Compression: uncompressed, RLE4, RLE8, BITFIELDS
 //! - Palette expansion, bottom-up/top-down, grayscale detection
 //! - `BmpPermissiveness` levels: Strict, Standard, Permissive
+//! - `BmpAlphaPolicy` for the ambiguous reserved byte of 32-bit `BI_RGB` files
+//! - Headerless DIBs (clipboard `CF_DIB`) via [`decode_dib()`]
+//! - OS/2 bitmap arrays (`"BA"`) via [`decode_bmp_array()`]
+//! - Recovered-problem reporting via [`decode_bmp_with_report()`]
+//! - Palette utilization counts via [`decode_bmp_with_palette_usage()`]
+//! - Linear-light f32 output via [`decode_bmp_linear()`]
+//! - Power-of-two preview decoding via [`BmpDownscale`]
+//! - Top-down (negative height), V4/V5-header (with a real alpha mask),
+//!   sRGB-tagged, ICC-profile and custom-DPI encode via [`BmpEncodeOptions`]
+//! - One configurable encoder, [`encode_bmp_with()`], for every bit depth,
+//!   compression and header option, including the OS/2 1.x core header
+//! - Row-at-a-time encode via [`BmpRowEncoder`]; output into a reused buffer
+//!   via [`encode_bmp_into()`], or (with `std`) to an `io::Write` via
+//!   `encode_bmp_to_writer()`
+//! - 16-bit encode via [`encode_bmp_rgb555()`] / [`encode_bmp_rgb565()`]
+//! - Paletted encode from indices + palette via [`encode_bmp_paletted()`] /
+//!   [`encode_bmp_paletted4()`], or quantized to 16 colors via [`encode_bmp_4bit()`]
+//! - RLE8/RLE4-compressed paletted encode via [`encode_bmp_rle8()`] and
+//!   [`encode_bmp_rle4()`]
 //! - Auto-detected by [`decode()`] via `"BM"` magic
 //!
+//! ### ICO / CUR (`ico` feature, opt-in; implies `bmp`)
+//! - Directory enumeration via [`probe_ico()`], per-entry decode
+//! - DIB entries decoded by the BMP decoder, AND mask folded into alpha
+//! - PNG entries are reported but not decoded
+//! - Multi-size ICO writing via [`encode_ico()`]: 32-bit DIBs with an AND
+//!   mask derived from alpha
+//! - Not auto-detected by [`decode()`] (the 4-byte signature is too weak)
+//!
+//! ### PCX (`pcx` feature, opt-in)
+//! - RLE and uncompressed; 1-bit mono, 2/4/8-bit and 2-4-plane paletted,
+//!   24-bit RGB and 32-bit RGBA
+//! - Header (16-color) and trailing VGA (256-color) palettes
+//! - Not auto-detected by [`decode()`] (no reliable magic)
+//!
+//! ### XPM (`xpm` feature, opt-in)
+//! - XPM3 C-source images, any characters-per-pixel up to 8
+//! - `c`/`g`/`g4`/`m` color keys; hex, common X11 names, and `None`
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### DDS (`dds` feature, opt-in; implies `bmp`)
+//! - Uncompressed RGB / luminance surfaces with arbitrary channel masks
+//! - DX10 header with 8-bit RGBA/BGRA DXGI formats
+//! - Top mip level of the first surface; block-compressed formats rejected
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### XWD (`xwd` feature, opt-in; implies `bmp`)
+//! - X Window Dump `ZPixmap` images, 24 or 32 bits per pixel, with
+//!   arbitrary channel masks, either byte order
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### Raw YUV (`yuv` feature, opt-in)
+//! - Headerless 8-bit I420, YV12, NV12, YUY2, I422, I444 and luma-only
+//!   frames to RGB via [`decode_yuv()`], with caller-supplied geometry
+//! - 10-bit V210 and P010 frames to `Rgb16` via [`decode_yuv10()`]
+//! - BT.601 / BT.709 / BT.2020 limited range, BT.601 full range
+//!
+//! ### Bayer mosaics (`bayer` feature, opt-in)
+//! - Headerless RGGB / BGGR / GRBG / GBRG sensor data, 1-16 bits per
+//!   sample, demosaiced bilinearly to `Rgb16` via [`decode_bayer()`]
+//!
+//! ### Y4M (`y4m` feature, opt-in; implies `yuv`)
+//! - YUV4MPEG2 header (geometry, frame rate, aspect, chroma) via [`probe_y4m()`]
+//! - 8-bit `420*`, `422`, `444` and `mono` streams
+//! - Zero-copy frame planes via [`decode_y4m_frames()`], or RGB via
+//!   [`Y4mFrame::to_rgb`] / [`decode_y4m()`]
+//! - Frame-at-a-time writing and appending via [`Y4mWriter`], from planes or
+//!   RGB
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### TIFF (`tiff` feature, opt-in)
+//! - Baseline uncompressed, strip-based, chunky TIFF in either byte order
+//! - 8/16-bit gray and RGB, with optional (straight or premultiplied) alpha
+//! - First image only; 8-bit files in contiguous strips decode zero-copy
+//! - Encode via [`encode_tiff()`] / [`encode_tiff_with()`]: one strip or
+//!   several, either byte order
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### DPX (`dpx` feature, opt-in)
+//! - 10-bit RGB in filled 32-bit words (packing method A or B), either
+//!   byte order, first image element
+//! - Code values as `Rgb16` via [`decode_dpx()`], or linear `RgbF32` via
+//!   [`decode_dpx_linear()`] (Cineon conversion for log data)
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### PGX (`pgx` feature, opt-in)
+//! - JPEG 2000 conformance raw format: one component, 1-16 bits, signed or
+//!   unsigned, either byte order
+//! - Decodes to `Gray8` / `Gray16` without rescaling (signed samples level
+//!   shifted); unsigned files decode zero-copy when no byte swap is needed
+//! - Header via [`probe_pgx()`]; encode via [`encode_pgx()`] /
+//!   [`encode_pgx_with()`]
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### NumPy `.npy` (`npy` feature, opt-in)
+//! - `uint8`, `uint16` and `float32` arrays shaped `(H, W)` or `(H, W, C)`
+//!   as gray, gray + alpha (16-bit), RGB or RGBA via [`decode_npy()`]
+//! - Native-byte-order arrays decode zero-copy
+//! - Written by [`encode_npy()`] for Python-side metrics scripts
+//! - Not auto-detected by [`decode()`]
+//!
+//! ### OpenEXR (`exr` feature, opt-in)
+//! - Single-part scanline files with compression `NONE` and `HALF` or
+//!   `FLOAT` channels
+//! - `R`/`G`/`B` (or a lone `Y`) and optional `A` to `RgbF32` / `RgbaF32`
+//!   via [`decode_exr()`]
+//! - Not auto-detected by [`decode()`]
+//!
 //! ### QOI (`qoi` feature, opt-in)
 //! - RGB8 and RGBA8, lossless
 //! - Row-level streaming decode and encode
@@ -115,10 +261,21 @@
 //! - Auto-detected by [`decode()`] via a header heuristic (TGA has no magic bytes)
 //!
 //! ### Radiance HDR (`hdr` feature, opt-in)
-//! - RGBE format with new-style per-channel RLE
+//! - RGBE format with new-style per-channel RLE; old-style run markers decoded
 //! - Decodes to `RgbF32` (linear float); encodes from `RgbF32` or `Rgb8`
 //! - Auto-detected by [`decode()`] via `"#?RADIANCE"` / `"#?RGBE"` magic
 //!
+//! ### Sixel (`sixel` feature, opt-in; implies `bmp`)
+//! - Encode only, via [`encode_sixel()`]: a DCS escape sequence that
+//!   sixel-capable terminals draw inline, for previewing test output
+//! - Up to 256 median-cut colors; alpha below 128 left transparent
+//!
+//! ## Output Size
+//!
+//! [`estimate_encoded_size()`] gives the size of the PNM, farbfeld or BMP
+//! file an encoder would write, without encoding, for pre-allocation and
+//! quota checks (`estimate_bmp_size()` takes full BMP options).
+//!
 //! ## Cooperative Cancellation
 //!
 //! Every function takes a `stop` parameter implementing [`enough::Stop`].
@@ -131,13 +288,11 @@
 //! ```
 //! # use zenbitmaps::*;
 //! # use enough::Unstoppable;
-//! let limits = Limits {
-//!     max_width: Some(4096),
-//!     max_height: Some(4096),
-//!     max_pixels: Some(16_000_000),
-//!     max_memory_bytes: Some(64 * 1024 * 1024),
-//!     ..Default::default()
-//! };
+//! let limits = Limits::new()
+//!     .with_max_dimensions(4096, 4096)
+//!     .with_max_pixels(16_000_000)
+//!     .with_max_memory(64 * 1024 * 1024)
+//!     .with_max_file_size(32 * 1024 * 1024);
 //! # let data = encode_ppm(&[0u8; 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
 //! let decoded = decode_with_limits(&data, &limits, Unstoppable)?;
 //! # Ok::<(), At<BitmapError>>(())
@@ -153,7 +308,10 @@
 //!   by Caleb Etemesi (MIT/Apache-2.0/Zlib)
 
 #![cfg_attr(not(feature = "std"), no_std)]
-#![forbid(unsafe_code)]
+#![cfg_attr(not(any(feature = "mmap", feature = "capi")), forbid(unsafe_code))]
+// Memory-mapping a file (the unsafe `file::FileData::map`) and the C ABI (`capi`) are
+// the only unsafe code, each behind its own feature.
+#![cfg_attr(any(feature = "mmap", feature = "capi"), deny(unsafe_code))]
 
 extern crate alloc;
 
@@ -162,14 +320,30 @@ extern crate alloc;
 whereat::define_at_crate_info!();
 
 #[cfg(feature = "rgb")]
-use rgb::{AsPixels as _, ComponentBytes as _};
-use whereat::at;
+use rgb::ComponentBytes as _;
 
 mod alloc_util;
+mod alpha;
+mod batch;
+#[cfg(feature = "capi")]
+#[allow(unsafe_code)]
+pub mod capi;
+mod data_uri;
+mod debug_text;
 mod decode;
+mod encode;
 mod error;
+#[cfg(feature = "std")]
+mod file;
 mod limits;
 mod pixel;
+mod planar;
+mod raw;
+mod region;
+mod request;
+mod rows;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 mod pnm;
 
@@ -187,6 +361,51 @@ mod qoi;
 #[cfg(feature = "bmp")]
 mod bmp;
 
+#[cfg(feature = "ico")]
+mod ico;
+
+#[cfg(feature = "pcx")]
+mod pcx;
+
+#[cfg(feature = "xpm")]
+mod xpm;
+
+#[cfg(feature = "dds")]
+mod dds;
+
+#[cfg(feature = "xwd")]
+mod xwd;
+
+#[cfg(feature = "yuv")]
+mod yuv;
+
+#[cfg(feature = "bayer")]
+mod bayer;
+
+#[cfg(feature = "y4m")]
+mod y4m;
+
+#[cfg(feature = "tiff")]
+mod tiff;
+
+#[cfg(feature = "dpx")]
+mod dpx;
+
+#[cfg(feature = "pgx")]
+mod pgx;
+
+#[cfg(feature = "npy")]
+mod npy;
+
+#[cfg(feature = "exr")]
+mod exr;
+
+#[cfg(feature = "sixel")]
+mod sixel;
+
+#[cfg(feature = "convert")]
+mod convert;
+
 #[cfg(feature = "rgb")]
 mod pixel_traits;
 
@@ -197,17 +416,57 @@ mod codec;
 // #[cfg(feature = "zennode")]
 // pub mod zennode_defs;
 
-pub use decode::DecodeOutput;
+pub use alpha::AlphaMode;
+pub use decode::{DecodeOutput, DecodeReport, DecodeWarning};
+pub use encode::EncodeOptions;
 pub use enough::{Stop, Unstoppable};
-pub use error::{BitmapError, Result};
-pub use limits::Limits;
-pub use pixel::{ImageFormat, PixelLayout};
+pub use error::{BitmapError, ErrorContext, ErrorKind, Result};
+pub use farbfeld::{
+    FarbfeldDecodeOptions, FarbfeldDither, FarbfeldEncodeOptions, FarbfeldRows, FarbfeldStream,
+};
+#[cfg(feature = "std")]
+pub use file::FileData;
+pub use limits::{AllocHook, Limits};
+pub use pixel::{EncodeFormat, ForeignFormat, Identified, ImageFormat, PixelLayout};
+pub use pnm::PnmEncodeOptions;
+#[cfg(feature = "qoi")]
+pub use qoi::QoiRows;
+pub use raw::Endianness;
+pub use request::DecodeRequest;
 /// Re-export of [`whereat::At`] so callers can name the public error type
 /// `At<BitmapError>` without depending on `whereat` directly.
 pub use whereat::At;
 
 #[cfg(feature = "bmp")]
-pub use bmp::{BmpMetadata, BmpPermissiveness};
+pub use bmp::{
+    BmpAlphaPolicy, BmpArrayEntry, BmpArrayFrame, BmpArrayFrames, BmpColorSpace, BmpCompression,
+    BmpDecodeOptions, BmpDownscale, BmpEncodeOptions, BmpHeaderVariant, BmpMetadata,
+    BmpPaletteUsage, BmpPermissiveness, BmpRowEncoder,
+};
+
+#[cfg(feature = "ico")]
+pub use ico::{IcoDirectory, IcoEntry, IcoImage, IcoKind};
+
+#[cfg(feature = "convert")]
+pub use convert::{ConvertOptions, Transfer};
+
+#[cfg(feature = "yuv")]
+pub use yuv::{Yuv10Layout, YuvLayout, YuvMatrix};
+
+#[cfg(feature = "bayer")]
+pub use bayer::BayerPattern;
+
+#[cfg(feature = "y4m")]
+pub use y4m::{Y4mFrame, Y4mFrames, Y4mHeader, Y4mWriter};
+
+#[cfg(feature = "tiff")]
+pub use tiff::TiffEncodeOptions;
+
+#[cfg(feature = "pgx")]
+pub use pgx::{PgxEncodeOptions, PgxHeader};
+
+#[cfg(feature = "sixel")]
+pub use sixel::SixelEncodeOptions;
 
 #[cfg(feature = "rgb")]
 pub use pixel_traits::{DecodePixel, EncodePixel};
@@ -225,7 +484,7 @@ pub use codec::{
 #[cfg(feature = "zencodec")]
 pub use codec::{
     FarbfeldDecodeJob, FarbfeldDecoder, FarbfeldDecoderConfig, FarbfeldEncodeJob, FarbfeldEncoder,
-    FarbfeldEncoderConfig,
+    FarbfeldEncoderConfig, FarbfeldFrameDecoder,
 };
 
 #[cfg(all(feature = "zencodec", feature = "qoi"))]
@@ -265,6 +524,15 @@ pub type BGR8 = rgb::alt::BGR<u8>;
 /// 8-bit BGRA pixel.
 #[cfg(feature = "rgb")]
 pub type BGRA8 = rgb::alt::BGRA<u8>;
+/// 16-bit RGB pixel (native endian).
+#[cfg(feature = "rgb")]
+pub type RGB16 = rgb::RGB<u16>;
+/// 16-bit RGBA pixel (native endian), as decoded from farbfeld.
+#[cfg(feature = "rgb")]
+pub type RGBA16 = rgb::RGBA<u16>;
+/// 16-bit gray pixel (native endian), as decoded from 16-bit PGM/PAM.
+#[cfg(feature = "rgb")]
+pub type GRAY16 = rgb::Gray<u16>;
 
 // ── Format detection ──────────────────────────────────────────────────
 
@@ -287,6 +555,13 @@ pub fn detect_format(data: &[u8]) -> Option<ImageFormat> {
     if data.len() >= 2 && data[0] == b'B' && data[1] == b'M' {
         return Some(ImageFormat::Bmp);
     }
+    // OS/2 bitmap array: "BA" header, then an embedded bitmap file header.
+    if data.len() >= 16
+        && data.starts_with(b"BA")
+        && matches!(&data[14..16], b"BM" | b"IC" | b"CI" | b"PT" | b"CP")
+    {
+        return Some(ImageFormat::Bmp);
+    }
     if data.len() >= 8 && &data[0..8] == b"farbfeld" {
         return Some(ImageFormat::Farbfeld);
     }
@@ -372,14 +647,71 @@ pub fn detect_format(data: &[u8]) -> Option<ImageFormat> {
     None
 }
 
+/// Identify the format from magic bytes, including common formats this
+/// crate does not decode.
+///
+/// Returns [`Identified::Supported`] for anything [`detect_format`]
+/// recognizes and [`Identified::Foreign`] for PNG, JPEG, GIF, WebP, TIFF,
+/// JPEG XL, AVIF and JPEG 2000, so a dispatcher can answer "this is a PNG,
+/// use a PNG decoder" instead of [`BitmapError::UnrecognizedFormat`].
+/// Foreign magic is checked first, so it never falls through to the TGA
+/// heuristic.
+///
+/// ```
+/// use zenbitmaps::*;
+///
+/// let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+/// assert_eq!(identify(png), Some(Identified::Foreign(ForeignFormat::Png)));
+/// assert!(!identify(png).unwrap().is_decodable());
+/// assert_eq!(identify(b"P6 ..."), Some(Identified::Supported(ImageFormat::Pnm)));
+/// assert_eq!(identify(b"unknown"), None);
+/// ```
+pub fn identify(data: &[u8]) -> Option<Identified> {
+    let foreign = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ForeignFormat::Png)
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(ForeignFormat::Jpeg)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(ForeignFormat::Gif)
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some(ForeignFormat::WebP)
+    } else if [b"II*\0", b"MM\0*", b"II+\0", b"MM\0+"]
+        .iter()
+        .any(|magic| data.starts_with(*magic))
+    {
+        Some(ForeignFormat::Tiff)
+    } else if data.starts_with(&[0xFF, 0x0A]) || data.starts_with(b"\0\0\0\x0CJXL \r\n\x87\n") {
+        Some(ForeignFormat::JpegXl)
+    } else if data.len() >= 12
+        && &data[4..8] == b"ftyp"
+        && matches!(&data[8..12], b"avif" | b"avis")
+    {
+        Some(ForeignFormat::Avif)
+    } else if data.starts_with(b"\0\0\0\x0CjP  \r\n\x87\n")
+        || data.starts_with(&[0xFF, 0x4F, 0xFF, 0x51])
+    {
+        Some(ForeignFormat::Jpeg2000)
+    } else {
+        None
+    };
+    match foreign {
+        Some(format) => Some(Identified::Foreign(format)),
+        None => detect_format(data).map(Identified::Supported),
+    }
+}
+
 // ── Auto-detect decode (PNM, BMP, farbfeld from magic bytes) ─────────
 
 /// Decode any supported format (auto-detected from magic bytes).
 ///
 /// Detects PNM (P5/P6/P7/PFM), farbfeld, and BMP (if the `bmp` feature is enabled).
 /// Zero-copy when possible — PNM with maxval=255 returns a borrowed slice.
+///
+/// Shorthand for `DecodeRequest::new(data).with_stop(stop).decode()`; see
+/// [`DecodeRequest`] for limits, BMP permissiveness, byte order and layout
+/// conversion in one call.
 pub fn decode(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
-    decode_dispatch(data, None, &stop)
+    DecodeRequest::new(data).with_stop(stop).decode()
 }
 
 /// Decode any supported format with resource limits.
@@ -388,7 +720,85 @@ pub fn decode_with_limits<'a>(
     limits: &'a Limits,
     stop: impl Stop,
 ) -> Result<DecodeOutput<'a>> {
-    decode_dispatch(data, Some(limits), &stop)
+    DecodeRequest::new(data)
+        .with_limits(limits)
+        .with_stop(stop)
+        .decode()
+}
+
+/// Decode any supported format, passing each row to `f` as `f(y, row)`,
+/// top to bottom, instead of materializing the whole frame.
+///
+/// Meant for streaming hashes, progressive display and row-wise metrics.
+/// Binary PNM (P4–P7, PFM) and uncompressed BMP are decoded 16 rows at a
+/// time, farbfeld and QOI a row at a time, so memory stays at one band of
+/// rows whatever the image size; 8-bit PNM rows are
+/// borrowed straight from `data`. ASCII PNM, RLE and 32-bit `BI_RGB` BMP,
+/// TGA, HDR and compressed farbfeld decode in full first. Rows are packed,
+/// in the layout [`decode`] would return; the width, height and layout are
+/// returned once all rows have been handed out. Limits on dimensions apply
+/// to the whole image, memory limits to what is held at once.
+///
+/// Shorthand for `DecodeRequest::new(data).with_stop(stop).decode_rows(f)`;
+/// see [`DecodeRequest::decode_rows`] to add limits, byte order or a target
+/// layout (converted band by band).
+///
+/// ```
+/// use zenbitmaps::*;
+/// let ppm = encode_ppm(&[10, 20, 30, 40, 50, 60], 1, 2, PixelLayout::Rgb8, Unstoppable)?;
+/// let mut sums = Vec::new();
+/// let (width, height, layout) = decode_rows(&ppm, Unstoppable, |y, row| {
+///     sums.push((y, row.iter().map(|&v| u32::from(v)).sum::<u32>()));
+/// })?;
+/// assert_eq!((width, height, layout), (1, 2, PixelLayout::Rgb8));
+/// assert_eq!(sums, [(0, 60), (1, 150)]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_rows(
+    data: &[u8],
+    stop: impl Stop,
+    f: impl FnMut(u32, &[u8]),
+) -> Result<(u32, u32, PixelLayout)> {
+    DecodeRequest::new(data).with_stop(stop).decode_rows(f)
+}
+
+/// Decode any supported format row by row, with resource limits.
+pub fn decode_rows_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+    f: impl FnMut(u32, &[u8]),
+) -> Result<(u32, u32, PixelLayout)> {
+    DecodeRequest::new(data)
+        .with_limits(limits)
+        .with_stop(stop)
+        .decode_rows(f)
+}
+
+/// Decode many inputs with the same limits, returning one result per
+/// input, in input order.
+///
+/// Each input is decoded as by [`decode_with_limits`]: `limits` (its
+/// `max_duration` included) applies to every input on its own. One input
+/// failing does not stop the others; `stop` does, and inputs not yet
+/// started then fail with [`BitmapError::Cancelled`]. With the `rayon`
+/// feature the inputs are decoded in parallel on the rayon thread pool.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let ppm = encode_ppm(&[255, 128, 0], 1, 1, PixelLayout::Rgb8, Unstoppable)?;
+/// let inputs: [&[u8]; 2] = [&ppm, b"not an image"];
+/// let results = decode_batch(&inputs, &Limits::web_safe(), Unstoppable);
+/// assert_eq!(results[0].as_ref().unwrap().pixels(), &[255, 128, 0]);
+/// assert!(results[1].is_err());
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_batch<'a>(
+    inputs: &[&'a [u8]],
+    limits: &Limits,
+    stop: impl Stop,
+) -> alloc::vec::Vec<Result<DecodeOutput<'a>>> {
+    batch::decode_batch(inputs, limits, &stop)
 }
 
 fn decode_dispatch<'a>(
@@ -396,330 +806,2483 @@ fn decode_dispatch<'a>(
     limits: Option<&Limits>,
     stop: &dyn enough::Stop,
 ) -> Result<DecodeOutput<'a>> {
+    request::dispatch(data, limits, &request::Settings::default(), stop)
+}
+
+// ── Data URIs ────────────────────────────────────────────────────────
+
+/// Decode a base64 `data:` URI, auto-detecting the format like [`decode()`].
+///
+/// The payload after `;base64,` is decoded and dispatched on its magic
+/// bytes; the media type is not consulted, so `data:image/bmp` holding a
+/// PPM decodes as PPM. Whitespace around the URI and inside the payload is
+/// skipped, padding is optional, and the URL-safe alphabet is accepted.
+/// The pixels are always owned, since the decoded payload is temporary.
+///
+/// A string without the `data:` scheme or the comma is
+/// [`BitmapError::InvalidHeader`]; percent-encoded (non-base64) URIs are
+/// [`BitmapError::UnsupportedVariant`]; bad base64 is
+/// [`BitmapError::InvalidData`].
+///
+/// ```
+/// use zenbitmaps::*;
+/// // A 1×1 red PPM
+/// let uri = "data:image/x-portable-pixmap;base64,UDYKMSAxCjI1NQr/AAA=";
+/// let decoded = decode_data_uri(uri, Unstoppable)?;
+/// assert_eq!(decoded.pixels(), &[255, 0, 0]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_data_uri(uri: &str, stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    let data = data_uri::payload(uri)?;
+    Ok(decode_dispatch(&data, None, &stop)?.into_owned())
+}
+
+/// Decode a base64 `data:` URI with resource limits.
+///
+/// [`Limits::max_file_size`] applies to the decoded payload.
+pub fn decode_data_uri_with_limits(
+    uri: &str,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    let data = data_uri::payload(uri)?;
+    Ok(decode_dispatch(&data, Some(limits), &limits.deadline(&stop))?.into_owned())
+}
+
+/// Decode only the `width`×`height` rectangle at (`x`, `y`) of an image,
+/// auto-detecting the format.
+///
+/// BMP and binary PNM (P4–P7, PFM) seek to the first row of the window and
+/// never decode the rows above or below it; RLE BMPs are run from the
+/// start of the stream but only the window's rows are stored. For these,
+/// [`Limits`] apply to the window, so a small patch of an image larger than
+/// the default pixel cap still decodes. ASCII PNM and the other formats
+/// decode in full (limits apply to the full image) and are cropped. An empty
+/// window, or one reaching outside the image, is [`BitmapError::InvalidData`].
+pub fn decode_region(
+    data: &[u8],
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'_>> {
+    let region = region::Region {
+        x,
+        y,
+        width,
+        height,
+    };
+    decode_region_dispatch(data, region, None, &stop)
+}
+
+/// Decode a rectangle of an image with resource limits.
+pub fn decode_region_with_limits<'a>(
+    data: &'a [u8],
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    let region = region::Region {
+        x,
+        y,
+        width,
+        height,
+    };
+    decode_region_dispatch(data, region, Some(limits), &limits.deadline(&stop))
+}
+
+fn decode_region_dispatch<'a>(
+    data: &'a [u8],
+    region: region::Region,
+    limits: Option<&Limits>,
+    stop: &dyn enough::Stop,
+) -> Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
     match detect_format(data) {
+        Some(ImageFormat::Pnm) => pnm::decode_region(data, region, limits, stop),
+        #[cfg(feature = "bmp")]
         Some(ImageFormat::Bmp) => {
-            #[cfg(feature = "bmp")]
-            return bmp::decode(data, limits, stop);
-            #[cfg(not(feature = "bmp"))]
-            return Err(at!(BitmapError::UnsupportedVariant(
-                "BMP support requires the 'bmp' feature".into(),
-            )));
-        }
-        Some(ImageFormat::Farbfeld) => farbfeld::decode(data, limits, stop),
-        Some(ImageFormat::Qoi) => {
-            #[cfg(feature = "qoi")]
-            return qoi::decode(data, limits, stop);
-            #[cfg(not(feature = "qoi"))]
-            return Err(at!(BitmapError::UnsupportedVariant(
-                "QOI support requires the 'qoi' feature".into(),
-            )));
+            bmp::decode_region(data, region, limits, &BmpDecodeOptions::new(), stop)
         }
-        Some(ImageFormat::Pnm) => pnm::decode(data, limits, stop),
-        Some(ImageFormat::Hdr) => {
-            #[cfg(feature = "hdr")]
-            return hdr::decode(data, limits, stop);
-            #[cfg(not(feature = "hdr"))]
-            return Err(at!(BitmapError::UnsupportedVariant(
-                "HDR support requires the 'hdr' feature".into(),
-            )));
+        _ => {
+            let full = decode_dispatch(data, limits, stop)?;
+            region.check(full.width, full.height)?;
+            Ok(region.crop(full))
         }
-        Some(ImageFormat::Tga) => {
-            #[cfg(feature = "tga")]
-            return tga::decode(data, limits, stop);
-            #[cfg(not(feature = "tga"))]
-            return Err(at!(BitmapError::UnsupportedVariant(
-                "TGA support requires the 'tga' feature".into(),
-            )));
-        }
-        None => Err(at!(BitmapError::UnrecognizedFormat)),
     }
 }
 
-// ── PNM encode ───────────────────────────────────────────────────────
+// ── Raw pixel dumps ──────────────────────────────────────────────────
 
-/// Encode pixels as PPM (P6, binary RGB).
-pub fn encode_ppm(
-    pixels: &[u8],
+/// Wrap a headerless pixel dump (e.g. a Photoshop `.raw` file or a camera
+/// pipeline buffer) in a [`DecodeOutput`].
+///
+/// Rows start `stride` bytes apart, and each holds `width` pixels of
+/// `layout`. The last row need not be padded. Multi-byte samples (16-bit and `f32` layouts) are
+/// read in `endianness` and returned in native byte order.
+/// [`PixelLayout::Rgba16Be`] is always big-endian, so `endianness` is
+/// ignored for it. Planar layouts have no rows to pad, so for them
+/// `stride` must be exactly `width * layout.bytes_per_pixel()`.
+///
+/// When no byte swap is needed, the pixels are borrowed from `data`
+/// without copying, keeping `stride` as the output's
+/// [`stride`](DecodeOutput::stride); [`rows`](DecodeOutput::rows) and
+/// [`packed_pixels`](DecodeOutput::packed_pixels) skip the padding.
+/// Swapped samples are copied into packed rows.
+///
+/// ```
+/// use zenbitmaps::*;
+/// // 2×2 RGB rows padded to 8 bytes
+/// let data = [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12];
+/// let decoded = decode_raw(&data, 2, 2, PixelLayout::Rgb8, 8, Endianness::Native, Unstoppable)?;
+/// assert!(decoded.is_borrowed() && decoded.stride() == 8);
+/// assert_eq!(&*decoded.packed_pixels(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+///
+/// let tight = decode_raw(&data[8..], 2, 1, PixelLayout::Rgb8, 6, Endianness::Native, Unstoppable)?;
+/// assert!(tight.is_borrowed());
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_raw(
+    data: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
+    stride: usize,
+    endianness: Endianness,
     stop: impl Stop,
-) -> Result<alloc::vec::Vec<u8>> {
-    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Ppm, &stop)
+) -> Result<DecodeOutput<'_>> {
+    raw::decode(data, width, height, layout, stride, endianness, None, &stop)
 }
 
-/// Encode pixels as PGM (P5, binary grayscale).
-pub fn encode_pgm(
-    pixels: &[u8],
+/// Wrap a headerless pixel dump with resource limits.
+#[allow(clippy::too_many_arguments)]
+pub fn decode_raw_with_limits<'a>(
+    data: &'a [u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
+    stride: usize,
+    endianness: Endianness,
+    limits: &Limits,
     stop: impl Stop,
-) -> Result<alloc::vec::Vec<u8>> {
-    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Pgm, &stop)
+) -> Result<DecodeOutput<'a>> {
+    raw::decode(
+        data,
+        width,
+        height,
+        layout,
+        stride,
+        endianness,
+        Some(limits),
+        &limits.deadline(&stop),
+    )
 }
 
-/// Encode pixels as PAM (P7, arbitrary channels).
-pub fn encode_pam(
+// ── Planar layouts ───────────────────────────────────────────────────
+
+/// Interleave a planar image: [`PixelLayout::RgbPlanar8`] to `Rgb8`,
+/// [`PixelLayout::RgbaPlanar8`] to `Rgba8`, [`PixelLayout::RgbPlanarF32`]
+/// to `RgbF32`.
+///
+/// Samples are moved as they are; the output layout is
+/// [`PixelLayout::interleaved`] of `layout`. Interleaved input is
+/// [`BitmapError::UnsupportedVariant`].
+///
+/// ```
+/// use zenbitmaps::*;
+/// // 2×1 planar RGB: R plane, G plane, B plane
+/// let planes = [1, 2, 10, 20, 100, 200];
+/// let rgb = interleave_planes(&planes, 2, 1, PixelLayout::RgbPlanar8, Unstoppable)?;
+/// assert_eq!(rgb, [1, 10, 100, 2, 20, 200]);
+/// assert_eq!(split_planes(&rgb, 2, 1, PixelLayout::Rgb8, Unstoppable)?, planes);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn interleave_planes(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
     stop: impl Stop,
 ) -> Result<alloc::vec::Vec<u8>> {
-    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Pam, &stop)
+    planar::interleave(pixels, width, height, layout, &stop)
 }
 
-/// Encode pixels as PFM (floating-point).
-pub fn encode_pfm(
+/// Split an interleaved `Rgb8`, `Rgba8` or `RgbF32` image into planes, in
+/// the matching [`PixelLayout::planar`] layout.
+///
+/// The inverse of [`interleave_planes`]. Layouts without a planar
+/// counterpart are [`BitmapError::UnsupportedVariant`].
+pub fn split_planes(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
     stop: impl Stop,
 ) -> Result<alloc::vec::Vec<u8>> {
-    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Pfm, &stop)
+    planar::split(pixels, width, height, layout, &stop)
 }
 
-// ── Farbfeld encode/decode ────────────────────────────────────────────
-
-/// Decode farbfeld data to pixels.
+/// Split an image of any layout into one buffer per channel, in the
+/// layout's channel order (`Bgra8` gives B, G, R, A; the padding byte of
+/// `Bgrx8` / `Rgbx8` is a channel too).
 ///
-/// Also auto-detected by [`decode()`] via the `"farbfeld"` magic bytes.
-/// Output layout is always [`PixelLayout::Rgba16`].
-pub fn decode_farbfeld(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
-    farbfeld::decode(data, None, &stop)
-}
-
-/// Decode farbfeld with resource limits.
-pub fn decode_farbfeld_with_limits<'a>(
-    data: &'a [u8],
-    limits: &'a Limits,
+/// Each plane holds `width × height` samples in the layout's sample type
+/// and byte order, with no row padding. Planar input is copied plane by
+/// plane. [`DecodeOutput::split_channels`] does the same for a decode.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let rgba = [1, 2, 3, 255, 4, 5, 6, 128];
+/// let planes = split_channels(&rgba, 2, 1, PixelLayout::Rgba8, Unstoppable)?;
+/// assert_eq!(planes, [vec![1, 4], vec![2, 5], vec![3, 6], vec![255, 128]]);
+///
+/// let planes: Vec<&[u8]> = planes.iter().map(Vec::as_slice).collect();
+/// assert_eq!(merge_channels(&planes, 2, 1, PixelLayout::Rgba8, Unstoppable)?, rgba);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn split_channels(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
     stop: impl Stop,
-) -> Result<DecodeOutput<'a>> {
-    farbfeld::decode(data, Some(limits), &stop)
+) -> Result<alloc::vec::Vec<alloc::vec::Vec<u8>>> {
+    planar::split_channels(pixels, width, height, layout, &stop)
 }
 
-/// Encode pixels as farbfeld.
+/// Merge one buffer per channel into an image of `layout`, the inverse of
+/// [`split_channels`].
 ///
-/// Accepts `Rgba16` (direct), `Rgba8` (expand via val*257),
-/// `Rgb8` (expand + alpha=65535), or `Gray8` (expand to RGBA).
-pub fn encode_farbfeld(
-    pixels: &[u8],
+/// `planes` must have exactly [`PixelLayout::channels`] entries
+/// ([`BitmapError::InvalidData`] otherwise), each at least `width × height`
+/// samples long. The result can go straight to an encoder.
+pub fn merge_channels(
+    planes: &[&[u8]],
     width: u32,
     height: u32,
     layout: PixelLayout,
     stop: impl Stop,
 ) -> Result<alloc::vec::Vec<u8>> {
-    farbfeld::encode(pixels, width, height, layout, &stop)
+    planar::merge_channels(planes, width, height, layout, &stop)
 }
 
-// ── TGA encode/decode ────────────────────────────────────────────────
+// ── Alpha premultiplication ──────────────────────────────────────────
 
-/// Decode TGA data to pixels.
+/// Multiply the color samples of `pixels` by alpha, in place.
 ///
-/// Also auto-detected by [`decode()`] via header heuristics (TGA has no magic bytes).
-/// Output layout is [`PixelLayout::Rgb8`], [`PixelLayout::Rgba8`], or [`PixelLayout::Gray8`].
-#[cfg(feature = "tga")]
-pub fn decode_tga(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
-    tga::decode(data, None, &stop)
-}
-
-/// Decode TGA with resource limits.
-#[cfg(feature = "tga")]
-pub fn decode_tga_with_limits<'a>(
-    data: &'a [u8],
-    limits: &'a Limits,
-    stop: impl Stop,
-) -> Result<DecodeOutput<'a>> {
-    tga::decode(data, Some(limits), &stop)
-}
-
-/// Encode pixels as TGA.
+/// Accepts the layouts with alpha: `Rgba8`, `Bgra8`, `GrayA16`, `Rgba16`,
+/// `Rgba16Be`, `RgbaF32` and `RgbaPlanar8`; others are
+/// [`BitmapError::UnsupportedVariant`]. Integer samples round to nearest.
 ///
-/// Accepts `Gray8`, `Rgb8`, `Rgba8`, `Bgr8`, `Bgra8` input layouts.
-/// Writes uncompressed TGA with bottom-left origin.
-#[cfg(feature = "tga")]
-pub fn encode_tga(
-    pixels: &[u8],
+/// ```
+/// use zenbitmaps::*;
+/// let mut rgba = [200, 100, 0, 128];
+/// premultiply_alpha(&mut rgba, 1, 1, PixelLayout::Rgba8, Unstoppable)?;
+/// assert_eq!(rgba, [100, 50, 0, 128]);
+/// unpremultiply_alpha(&mut rgba, 1, 1, PixelLayout::Rgba8, Unstoppable)?;
+/// assert_eq!(rgba, [199, 100, 0, 128]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn premultiply_alpha(
+    pixels: &mut [u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
     stop: impl Stop,
-) -> Result<alloc::vec::Vec<u8>> {
-    tga::encode(pixels, width, height, layout, &stop)
+) -> Result<()> {
+    alpha::apply(pixels, width, height, layout, true, &stop)
 }
 
-// ── HDR encode/decode ────────────────────────────────────────────────
-
-/// Decode Radiance HDR data to pixels.
+/// Divide the color samples of premultiplied `pixels` by alpha, in place.
 ///
-/// Also auto-detected by [`decode()`] via `#?RADIANCE` / `#?RGBE` magic.
-/// Output layout is always [`PixelLayout::RgbF32`].
-#[cfg(feature = "hdr")]
-pub fn decode_hdr(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
-    hdr::decode(data, None, &stop)
-}
-
-/// Decode Radiance HDR with resource limits.
-#[cfg(feature = "hdr")]
-pub fn decode_hdr_with_limits<'a>(
-    data: &'a [u8],
-    limits: &'a Limits,
+/// The inverse of [`premultiply_alpha`], up to the precision premultiplying
+/// lost. Pixels with zero alpha become black; colors above their alpha
+/// clamp to the maximum.
+pub fn unpremultiply_alpha(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
     stop: impl Stop,
-) -> Result<DecodeOutput<'a>> {
-    hdr::decode(data, Some(limits), &stop)
+) -> Result<()> {
+    alpha::apply(pixels, width, height, layout, false, &stop)
 }
 
-/// Encode pixels as Radiance HDR (RGBE with new-style RLE).
+/// Blend straight-alpha `pixels` over an opaque sRGB `background`, in
+/// place, and make them opaque.
 ///
-/// Accepts `RgbF32` (3×f32) or `Rgb8` (converted via /255.0).
-#[cfg(feature = "hdr")]
-pub fn encode_hdr(
-    pixels: &[u8],
+/// Accepts the same layouts as [`premultiply_alpha`]. `GrayA16` uses the
+/// background's BT.709 luma; 16-bit and float layouts scale it to their
+/// range. Encoders that drop alpha do this for you when given a background
+/// ([`PnmEncodeOptions::with_background`], `BmpEncodeOptions::with_background`).
+///
+/// ```
+/// use zenbitmaps::*;
+/// let mut rgba = [200, 0, 0, 128];
+/// flatten_alpha(&mut rgba, 1, 1, PixelLayout::Rgba8, [0, 0, 255], Unstoppable)?;
+/// assert_eq!(rgba, [100, 0, 127, 255]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn flatten_alpha(
+    pixels: &mut [u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
+    background: [u8; 3],
     stop: impl Stop,
-) -> Result<alloc::vec::Vec<u8>> {
-    hdr::encode(pixels, width, height, layout, &stop)
+) -> Result<()> {
+    alpha::flatten(pixels, width, height, layout, background, &stop)
 }
 
-// ── QOI encode/decode ────────────────────────────────────────────────
+// ── Layout conversion (convert feature) ──────────────────────────────
 
-/// Decode QOI data to pixels.
+/// Convert pixels from one [`PixelLayout`] to another.
 ///
-/// Also auto-detected by [`decode()`] via the `"qoif"` magic bytes.
-/// Output layout is [`PixelLayout::Rgb8`] or [`PixelLayout::Rgba8`].
+/// Every pair of layouts is supported. Integer conversions are exact where
+/// the target can hold the value (8 → 16 → 8 bit is lossless); 16 → 8 bit
+/// rounds. Integer ↔ float conversions apply the [`Transfer`] in `options`:
+/// by default integer samples are sRGB-encoded and floats linear light.
+/// RGB becomes gray by BT.709 weights, missing alpha becomes opaque, and
+/// dropped alpha is discarded. Converting a layout to itself copies.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let bgra = [30, 20, 10, 255, 0, 0, 255, 128];
+/// let options = ConvertOptions::new();
+/// let (bgra8, rgb8) = (PixelLayout::Bgra8, PixelLayout::Rgb8);
+/// let rgb = convert(&bgra, 2, 1, bgra8, rgb8, &options, Unstoppable)?;
+/// assert_eq!(rgb, [10, 20, 30, 255, 0, 0]);
+///
+/// let wide = convert(&rgb, 2, 1, rgb8, PixelLayout::Rgb16, &options, Unstoppable)?;
+/// assert_eq!(&wide[..2], &2570u16.to_ne_bytes());
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "convert")]
+pub fn convert(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    from: PixelLayout,
+    to: PixelLayout,
+    options: &ConvertOptions,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    convert::convert(pixels, width, height, from, to, options, &stop)
+}
+
+/// Convert pixels into a caller buffer, like [`convert`].
+///
+/// `out` must hold at least `width × height × to.bytes_per_pixel()` bytes;
+/// only that prefix is written.
+#[cfg(feature = "convert")]
+#[allow(clippy::too_many_arguments)]
+pub fn convert_into(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    from: PixelLayout,
+    to: PixelLayout,
+    options: &ConvertOptions,
+    out: &mut [u8],
+    stop: impl Stop,
+) -> Result<()> {
+    convert::convert_into(pixels, width, height, from, to, options, out, &stop)
+}
+
+/// Decode 8-bit sRGB samples to linear-light `f32`, one sample at a time.
+///
+/// The sRGB transfer curve is applied to every sample, so pass color
+/// channels only, or use [`convert`] with an `F32` layout to keep alpha
+/// linear. `linear` must hold at least `srgb.len()` values
+/// ([`BitmapError::BufferTooSmall`] otherwise); the rest is left alone.
+/// Runs the `linear-srgb` SIMD kernels that [`convert`] and the BMP
+/// decoder use.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let mut linear = [0.0f32; 3];
+/// srgb_to_linear(&[0, 188, 255], &mut linear)?;
+/// assert_eq!(linear[0], 0.0);
+/// assert!((linear[1] - 0.5).abs() < 0.01);
+/// assert_eq!(linear[2], 1.0);
+///
+/// let mut srgb = [0u8; 3];
+/// linear_to_srgb(&linear, &mut srgb)?;
+/// assert_eq!(srgb, [0, 188, 255]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "convert")]
+pub fn srgb_to_linear(srgb: &[u8], linear: &mut [f32]) -> Result<()> {
+    convert::srgb_u8_to_linear(srgb, linear)
+}
+
+/// Encode linear-light `f32` samples as 8-bit sRGB, the inverse of
+/// [`srgb_to_linear`].
+///
+/// Values are clamped to `0.0..=1.0` and rounded to nearest. `srgb` must
+/// hold at least `linear.len()` values.
+#[cfg(feature = "convert")]
+pub fn linear_to_srgb(linear: &[f32], srgb: &mut [u8]) -> Result<()> {
+    convert::linear_to_srgb_u8(linear, srgb)
+}
+
+// ── Debug text ───────────────────────────────────────────────────────
+
+/// Render pixels as human-readable text, one line per row.
+///
+/// The first line is a `# W×H Layout` comment. Each sample is written in
+/// decimal (16-bit layouts as their numeric value, floats in shortest
+/// round-trip form), right-aligned to the widest sample in the image;
+/// channels are joined by `,` and pixels separated by two spaces. Every
+/// [`PixelLayout`] is accepted, padding bytes of `Rgbx8` / `Bgrx8`
+/// included; planar layouts are shown one interleaved pixel per group.
+/// [`decode_debug_text`] reads the result back exactly.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let pixels = [255, 0, 0, 0, 128, 7];
+/// let text = encode_debug_text(&pixels, 2, 1, PixelLayout::Rgb8, Unstoppable)?;
+/// assert_eq!(text, "# 2×1 Rgb8\n255,  0,  0    0,128,  7\n");
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn encode_debug_text(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::string::String> {
+    debug_text::encode(pixels, width, height, layout, &stop)
+}
+
+/// Parse a text grid of samples, as written by [`encode_debug_text`].
+///
+/// The caller names the `layout`; width and height come from the text.
+/// Blank lines and lines starting with `#` are skipped. Spaces, tabs, `,`,
+/// `;` and `|` all separate samples, so hand-written grids such as
+/// `"1 2 3 | 4 5 6"` work too. Every row must hold the same whole number
+/// of pixels; anything else, and any sample that does not parse as the
+/// layout's sample type, is [`BitmapError::InvalidData`].
+///
+/// ```
+/// use zenbitmaps::*;
+/// let decoded = decode_debug_text("0 1\n2 3\n", PixelLayout::Gray8, Unstoppable)?;
+/// assert_eq!((decoded.width, decoded.height), (2, 2));
+/// assert_eq!(decoded.pixels(), &[0, 1, 2, 3]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_debug_text(
+    text: &str,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    debug_text::decode(text, layout, None, &stop)
+}
+
+/// Parse a text grid of samples with resource limits.
+pub fn decode_debug_text_with_limits(
+    text: &str,
+    layout: PixelLayout,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    debug_text::decode(text, layout, Some(limits), &limits.deadline(&stop))
+}
+
+// ── PNM encode ───────────────────────────────────────────────────────
+
+/// Encode pixels as PPM (P6, binary RGB).
+pub fn encode_ppm(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Ppm, &stop)
+}
+
+/// Encode pixels as PGM (P5, binary grayscale).
+pub fn encode_pgm(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Pgm, &stop)
+}
+
+/// Encode pixels as PPM with explicit [`PnmEncodeOptions`].
+///
+/// With [`PnmEncodeOptions::with_background`], `Rgba8` and `Bgra8` input
+/// is blended over the background instead of losing its alpha as is.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let rgba = [255, 0, 0, 255, 255, 0, 0, 0];
+/// let options = PnmEncodeOptions::new().with_background([255, 255, 255]);
+/// let ppm = encode_ppm_with(&rgba, 2, 1, PixelLayout::Rgba8, &options, Unstoppable)?;
+/// assert_eq!(decode(&ppm, Unstoppable)?.pixels(), &[255, 0, 0, 255, 255, 255]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn encode_ppm_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &PnmEncodeOptions,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pnm::encode_with(
+        pixels,
+        width,
+        height,
+        layout,
+        pnm::PnmFormat::Ppm,
+        options,
+        &stop,
+    )
+}
+
+/// Encode pixels as PGM with explicit [`PnmEncodeOptions`].
+pub fn encode_pgm_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &PnmEncodeOptions,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pnm::encode_with(
+        pixels,
+        width,
+        height,
+        layout,
+        pnm::PnmFormat::Pgm,
+        options,
+        &stop,
+    )
+}
+
+/// Encode pixels as PAM (P7, arbitrary channels).
+///
+/// 8-bit layouts are written at `MAXVAL 255`; `Gray16`, `Rgb16` and
+/// `Rgba16` at `MAXVAL 65535`, big-endian.
+pub fn encode_pam(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Pam, &stop)
+}
+
+/// Encode pixels as PFM (floating-point).
+pub fn encode_pfm(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pnm::encode(pixels, width, height, layout, pnm::PnmFormat::Pfm, &stop)
+}
+
+// ── Farbfeld encode/decode ────────────────────────────────────────────
+
+/// Decode farbfeld data to pixels.
+///
+/// Also auto-detected by [`decode()`] via the `"farbfeld"` magic bytes.
+/// With the `flate2` / `bzip2` features, gzip- or bzip2-compressed farbfeld
+/// (`.ff.gz`, `.ff.bz2`) is decompressed transparently, here and in
+/// [`decode()`].
+/// Output layout is always [`PixelLayout::Rgba16`].
+pub fn decode_farbfeld(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    farbfeld::decode(data, None, &stop)
+}
+
+/// Decode farbfeld with resource limits.
+pub fn decode_farbfeld_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    farbfeld::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode farbfeld with explicit [`FarbfeldDecodeOptions`].
+///
+/// With [`FarbfeldDecodeOptions::with_strict`], the file must be exactly
+/// as long as its header says — useful for validating other writers.
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let mut data = encode_farbfeld(&[0u8; 4], 1, 1, PixelLayout::Rgba8, Unstoppable)?;
+/// let strict = FarbfeldDecodeOptions::new().with_strict(true);
+/// assert!(decode_farbfeld_with_options(&data, &strict, Unstoppable).is_ok());
+/// data.push(0);
+/// assert!(decode_farbfeld_with_options(&data, &strict, Unstoppable).is_err());
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_farbfeld_with_options<'a>(
+    data: &[u8],
+    options: &FarbfeldDecodeOptions,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    farbfeld::decode_with_options(
+        data,
+        None,
+        options,
+        alloc_util::AllocPref::CodecDefault,
+        &stop,
+    )
+}
+
+/// Decode farbfeld with explicit [`FarbfeldDecodeOptions`] and resource limits.
+pub fn decode_farbfeld_with_options_and_limits<'a>(
+    data: &[u8],
+    options: &FarbfeldDecodeOptions,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    farbfeld::decode_with_options(
+        data,
+        Some(limits),
+        options,
+        alloc_util::AllocPref::CodecDefault,
+        &limits.deadline(&stop),
+    )
+}
+
+/// Decode farbfeld without converting from big endian.
+///
+/// Returns a borrowed [`PixelLayout::Rgba16Be`] view of the pixel data in
+/// `data` — no allocation or copy. Useful when host byte order doesn't
+/// matter (hashing, comparison, re-encoding with [`encode_farbfeld`]).
+///
+/// ```
+/// # use zenbitmaps::*;
+/// # use enough::Unstoppable;
+/// let data = encode_farbfeld(&[0u8; 16], 2, 2, PixelLayout::Rgba8, Unstoppable)?;
+/// let decoded = decode_farbfeld_native(&data, Unstoppable)?;
+/// assert!(decoded.is_borrowed());
+/// assert_eq!(decoded.layout, PixelLayout::Rgba16Be);
+/// assert_eq!(decoded.pixels(), &data[16..]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_farbfeld_native(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    farbfeld::decode_native(data, None, &stop)
+}
+
+/// Decode farbfeld without converting from big endian, with resource limits.
+pub fn decode_farbfeld_native_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    farbfeld::decode_native(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode farbfeld to 8-bit RGBA.
+///
+/// Each 16-bit sample is rounded to nearest (`(v + 128) / 257`) in the
+/// same pass as the endian swap. Output layout is [`PixelLayout::Rgba8`].
+///
+/// ```
+/// # use zenbitmaps::*;
+/// # use enough::Unstoppable;
+/// let samples: Vec<u8> = [0u16, 128, 129, 65535].iter().flat_map(|v| v.to_ne_bytes()).collect();
+/// let data = encode_farbfeld(&samples, 1, 1, PixelLayout::Rgba16, Unstoppable)?;
+/// let decoded = decode_farbfeld_rgba8(&data, Unstoppable)?;
+/// assert_eq!(decoded.pixels(), &[0, 0, 1, 255]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_farbfeld_rgba8(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    farbfeld::decode_rgba8(data, None, &stop)
+}
+
+/// Decode farbfeld to 8-bit RGBA, with resource limits.
+pub fn decode_farbfeld_rgba8_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    farbfeld::decode_rgba8(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode farbfeld into a caller-provided buffer.
+///
+/// Rows are written `stride` bytes apart as `layout`: [`PixelLayout::Rgba16`]
+/// (native endian), [`PixelLayout::Rgba16Be`] (as stored), or
+/// [`PixelLayout::Rgba8`] (rounded to nearest). Bytes between rows are left
+/// untouched. Returns `(width, height)`; `output` must hold at least
+/// `stride * (height - 1) + width * bytes_per_pixel` bytes.
+///
+/// ```
+/// # use zenbitmaps::*;
+/// # use enough::Unstoppable;
+/// let data = encode_farbfeld(&[200u8; 16], 2, 2, PixelLayout::Rgba8, Unstoppable)?;
+/// let mut frame = vec![0u8; 12 * 2];
+/// let (w, h) = decode_farbfeld_into(&data, &mut frame, 12, PixelLayout::Rgba8, Unstoppable)?;
+/// assert_eq!((w, h), (2, 2));
+/// assert_eq!(&frame[..8], &[200; 8]);
+/// assert_eq!(&frame[8..12], &[0; 4]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_farbfeld_into(
+    data: &[u8],
+    output: &mut [u8],
+    stride: usize,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<(u32, u32)> {
+    farbfeld::decode_into(data, output, stride, layout, None, &stop)
+}
+
+/// Decode farbfeld into a caller-provided buffer, with resource limits.
+pub fn decode_farbfeld_into_with_limits(
+    data: &[u8],
+    output: &mut [u8],
+    stride: usize,
+    layout: PixelLayout,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<(u32, u32)> {
+    farbfeld::decode_into(
+        data,
+        output,
+        stride,
+        layout,
+        Some(limits),
+        &limits.deadline(&stop),
+    )
+}
+
+/// Decode farbfeld one row at a time.
+///
+/// Only a single converted row is held in memory, so very large RGBA16
+/// files can be processed without allocating the full frame. Rows are
+/// native-endian [`PixelLayout::Rgba16`], top to bottom.
+///
+/// ```
+/// # use zenbitmaps::*;
+/// # use enough::Unstoppable;
+/// let data = encode_farbfeld(&[0u8; 24], 2, 3, PixelLayout::Rgb8, Unstoppable)?;
+/// let mut rows = decode_farbfeld_rows(&data, Unstoppable)?;
+/// while let Some(row) = rows.next_row() {
+///     assert_eq!(row?.len(), 2 * 8);
+/// }
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_farbfeld_rows<S: Stop>(data: &[u8], stop: S) -> Result<FarbfeldRows<'_, S>> {
+    farbfeld::rows(data, None, stop)
+}
+
+/// Decode farbfeld one row at a time, with resource limits.
+///
+/// `max_memory_bytes` applies to the single row buffer.
+pub fn decode_farbfeld_rows_with_limits<'a, S: Stop>(
+    data: &'a [u8],
+    limits: &Limits,
+    stop: S,
+) -> Result<FarbfeldRows<'a, S>> {
+    farbfeld::rows(data, Some(limits), stop)
+}
+
+/// Decode concatenated farbfeld frames, one at a time.
+///
+/// For files that hold several farbfeld images back to back (e.g. video
+/// dumped frame by frame). Each frame decodes to native-endian `Rgba16`.
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let mut data = encode_farbfeld(&[0u8; 4], 1, 1, PixelLayout::Rgba8, Unstoppable)?;
+/// data.extend(encode_farbfeld(&[255u8; 8], 2, 1, PixelLayout::Rgba8, Unstoppable)?);
+/// let widths: Vec<u32> = decode_farbfeld_stream(&data, Unstoppable)?
+///     .map(|frame| frame.map(|f| f.width))
+///     .collect::<Result<_>>()?;
+/// assert_eq!(widths, [1, 2]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn decode_farbfeld_stream<S: Stop>(data: &[u8], stop: S) -> Result<FarbfeldStream<'_, S>> {
+    farbfeld::stream::stream(data, None, stop)
+}
+
+/// Decode concatenated farbfeld frames with resource limits (applied per
+/// frame; `max_file_size` applies to the whole input).
+pub fn decode_farbfeld_stream_with_limits<'a, S: Stop>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: S,
+) -> Result<FarbfeldStream<'a, S>> {
+    farbfeld::stream::stream(data, Some(limits), stop)
+}
+
+/// Encode pixels as farbfeld.
+///
+/// Accepts `Rgba16` (native endian), `Rgba16Be` (copied as is), `Rgb16`
+/// (alpha=65535), `Gray16` / `GrayA16` (expand to RGBA),
+/// `GrayF32` / `RgbF32` / `RgbaF32` (clamped to 0.0..=1.0 and rounded; no
+/// transfer function is applied), `Rgba8` (expand via val*257),
+/// `Rgb8` (expand + alpha=65535), or `Gray8` (expand to RGBA).
+pub fn encode_farbfeld(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    farbfeld::encode(pixels, width, height, layout, &stop)
+}
+
+/// Encode pixels as farbfeld with [`FarbfeldEncodeOptions`].
+///
+/// The options matter for float input, which can be dithered while
+/// quantizing to 16 bits:
+///
+/// ```
+/// # use zenbitmaps::*;
+/// # use enough::Unstoppable;
+/// let gray: Vec<u8> = [0.25f32; 16].iter().flat_map(|v| v.to_ne_bytes()).collect();
+/// let options = FarbfeldEncodeOptions::new().with_dither(FarbfeldDither::ErrorDiffusion);
+/// let data = encode_farbfeld_with(&gray, 4, 4, PixelLayout::GrayF32, &options, Unstoppable)?;
+/// assert_eq!(data.len(), 16 + 4 * 4 * 8);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn encode_farbfeld_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &FarbfeldEncodeOptions,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    farbfeld::encode_with(pixels, width, height, layout, options, &stop)
+}
+
+// ── Format-parameterized encode ──────────────────────────────────────
+
+/// Encode pixels as `format`, with the matching encoder from `options`.
+///
+/// For code that picks the output format at runtime: equivalent to calling
+/// [`encode_pgm_with`], [`encode_ppm_with`], [`encode_pam`], [`encode_pfm`],
+/// [`encode_farbfeld_with`] or `encode_bmp_with` directly, and accepts the
+/// same layouts as each. [`estimate_encoded_size`] gives the output size
+/// for the default options.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let rgb = [255, 0, 0, 0, 255, 0];
+/// let options = EncodeOptions::new();
+/// for format in [EncodeFormat::Ppm, EncodeFormat::Pam, EncodeFormat::Farbfeld] {
+///     let file = encode(&rgb, 2, 1, PixelLayout::Rgb8, format, &options, Unstoppable)?;
+///     assert_eq!(file.len(), estimate_encoded_size(2, 1, PixelLayout::Rgb8, format)?);
+///     assert_eq!(decode(&file, Unstoppable)?.width, 2);
+/// }
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    encode::encode(pixels, width, height, layout, format, options, &stop)
+}
+
+/// Decode `data` (format auto-detected, as [`decode`]) and re-encode it as
+/// `format`.
+///
+/// Pixels go to the encoder unchanged when it takes the decoded layout.
+/// Otherwise they are converted to the closest layout the target takes:
+/// 16-bit and float sources become 16-bit PAM (`Gray16`, `Rgb16` or
+/// `Rgba16`), PGM/PPM and BMP get 8-bit gray, RGB, or RGBA (so
+/// [`PnmEncodeOptions::with_background`] applies to alpha), PFM gets
+/// `GrayF32` or `RgbF32`, and farbfeld takes everything. Conversions need
+/// the `convert` feature; without it they fail with
+/// [`BitmapError::UnsupportedVariant`].
+///
+/// ```
+/// use zenbitmaps::*;
+/// let ppm = encode_ppm(&[255, 128, 0], 1, 1, PixelLayout::Rgb8, Unstoppable)?;
+/// let ff = transcode(&ppm, EncodeFormat::Farbfeld, &EncodeOptions::new(), Unstoppable)?;
+/// assert_eq!(detect_format(&ff), Some(ImageFormat::Farbfeld));
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn transcode(
+    data: &[u8],
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let decoded = decode(data, &stop)?;
+    encode::transcode(&decoded, format, options, &stop)
+}
+
+/// [`transcode`] with resource limits on the decode. The `max_duration`
+/// deadline covers the encode as well.
+pub fn transcode_with_limits(
+    data: &[u8],
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let stop = limits.deadline(&stop);
+    let decoded = request::dispatch(data, Some(limits), &Default::default(), &stop)?;
+    encode::transcode(&decoded, format, options, &stop)
+}
+
+// ── Files (std) ──────────────────────────────────────────────────────
+
+/// Decode the file at `path`, detecting its format like [`decode`].
+///
+/// The file is read into memory and the output owns its pixels. To keep
+/// zero-copy decodes borrowing from the file, open it with
+/// [`FileData::open`] (or map it with the unsafe `FileData::map`) and
+/// decode that instead.
+#[cfg(feature = "std")]
+pub fn decode_file(
+    path: impl AsRef<std::path::Path>,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    let file = file::open_within(path.as_ref(), None)?;
+    Ok(decode(&file, stop)?.into_owned())
+}
+
+/// [`decode_file`] with resource limits. A file over `max_file_size` is
+/// rejected before it is read.
+#[cfg(feature = "std")]
+pub fn decode_file_with_limits(
+    path: impl AsRef<std::path::Path>,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    let file = file::open_within(path.as_ref(), Some(limits))?;
+    Ok(decode_with_limits(&file, limits, stop)?.into_owned())
+}
+
+/// Encode pixels as `format`, like [`encode`], and write the result to
+/// `path`, replacing any existing file.
+///
+/// Nothing is written if encoding fails.
+#[cfg(feature = "std")]
+#[allow(clippy::too_many_arguments)]
+pub fn encode_file(
+    path: impl AsRef<std::path::Path>,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    format: EncodeFormat,
+    options: &EncodeOptions<'_>,
+    stop: impl Stop,
+) -> Result<()> {
+    let bytes = encode::encode(pixels, width, height, layout, format, options, &stop)?;
+    file::write(path.as_ref(), &bytes)
+}
+
+// ── Encoded size ─────────────────────────────────────────────────────
+
+/// Size in bytes of the file an encoder would write for a `width` ×
+/// `height` image of `layout` pixels, without encoding anything.
+///
+/// Lets callers pre-allocate output, or check a quota before spending time
+/// on an encode. Exact for every format here; fails like the encoder would
+/// ([`BitmapError::UnsupportedVariant`] for a layout the format cannot
+/// take, [`BitmapError::DimensionsTooLarge`] when the size overflows).
+///
+/// ```
+/// use zenbitmaps::{estimate_encoded_size, EncodeFormat, PixelLayout};
+///
+/// let size = estimate_encoded_size(640, 480, PixelLayout::Rgba8, EncodeFormat::Farbfeld)?;
+/// assert_eq!(size, 16 + 640 * 480 * 8);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+pub fn estimate_encoded_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    format: EncodeFormat,
+) -> Result<usize> {
+    match format {
+        EncodeFormat::Pgm => pnm::encoded_size(width, height, layout, pnm::PnmFormat::Pgm),
+        EncodeFormat::Ppm => pnm::encoded_size(width, height, layout, pnm::PnmFormat::Ppm),
+        EncodeFormat::Pam => pnm::encoded_size(width, height, layout, pnm::PnmFormat::Pam),
+        EncodeFormat::Pfm => pnm::encoded_size(width, height, layout, pnm::PnmFormat::Pfm),
+        EncodeFormat::Farbfeld => farbfeld::encoded_size(width, height),
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp => bmp::encoded_size(width, height, layout, &BmpEncodeOptions::new()),
+    }
+}
+
+/// Size in bytes of the file [`encode_bmp_with`] would write with
+/// `options`; see [`estimate_encoded_size`].
+///
+/// Exact for uncompressed output with a fixed palette (or none). RLE output
+/// depends on the pixels, so its worst case is returned (two bytes per
+/// pixel, plus the end-of-line escapes), and a palette quantized from the
+/// image is assumed to use every entry the bit depth allows.
+#[cfg(feature = "bmp")]
+pub fn estimate_bmp_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+) -> Result<usize> {
+    bmp::encoded_size(width, height, layout, options)
+}
+
+// ── TGA encode/decode ────────────────────────────────────────────────
+
+/// Decode TGA data to pixels.
+///
+/// Also auto-detected by [`decode()`] via header heuristics (TGA has no magic bytes).
+/// Output layout is [`PixelLayout::Rgb8`], [`PixelLayout::Rgba8`], or [`PixelLayout::Gray8`].
+#[cfg(feature = "tga")]
+pub fn decode_tga(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    tga::decode(data, None, &stop)
+}
+
+/// Decode TGA with resource limits.
+#[cfg(feature = "tga")]
+pub fn decode_tga_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    tga::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Encode pixels as TGA.
+///
+/// Accepts `Gray8`, `Rgb8`, `Rgba8`, `Bgr8`, `Bgra8` input layouts.
+/// Writes uncompressed TGA with bottom-left origin.
+#[cfg(feature = "tga")]
+pub fn encode_tga(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    tga::encode(pixels, width, height, layout, &stop)
+}
+
+// ── HDR encode/decode ────────────────────────────────────────────────
+
+/// Decode Radiance HDR data to pixels.
+///
+/// Also auto-detected by [`decode()`] via `#?RADIANCE` / `#?RGBE` magic.
+/// Output layout is always [`PixelLayout::RgbF32`].
+#[cfg(feature = "hdr")]
+pub fn decode_hdr(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    hdr::decode(data, None, &stop)
+}
+
+/// Decode Radiance HDR with resource limits.
+#[cfg(feature = "hdr")]
+pub fn decode_hdr_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    hdr::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Encode pixels as Radiance HDR (RGBE with new-style RLE).
+///
+/// Accepts `RgbF32` (3×f32) or `Rgb8` (converted via /255.0).
+#[cfg(feature = "hdr")]
+pub fn encode_hdr(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    hdr::encode(pixels, width, height, layout, &stop)
+}
+
+// ── QOI encode/decode ────────────────────────────────────────────────
+
+/// Decode QOI data to pixels.
+///
+/// Also auto-detected by [`decode()`] via the `"qoif"` magic bytes.
+/// Output layout is [`PixelLayout::Rgb8`] or [`PixelLayout::Rgba8`].
 #[cfg(feature = "qoi")]
 pub fn decode_qoi(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
     qoi::decode(data, None, &stop)
 }
 
-/// Decode QOI with resource limits.
-#[cfg(feature = "qoi")]
-pub fn decode_qoi_with_limits<'a>(
+/// Decode QOI with resource limits.
+#[cfg(feature = "qoi")]
+pub fn decode_qoi_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    qoi::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode QOI one row at a time.
+///
+/// Only one decoded row is held in memory; see [`QoiRows`].
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let data = encode_qoi(&[9u8; 2 * 3 * 3], 2, 3, PixelLayout::Rgb8, Unstoppable)?;
+/// let mut rows = decode_qoi_rows(&data, Unstoppable)?;
+/// while let Some(row) = rows.next_row() {
+///     assert_eq!(row?, [9u8; 6]);
+/// }
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "qoi")]
+pub fn decode_qoi_rows<S: Stop>(data: &[u8], stop: S) -> Result<QoiRows<'_, S>> {
+    qoi::rows(data, None, stop)
+}
+
+/// Decode QOI one row at a time, with resource limits.
+#[cfg(feature = "qoi")]
+pub fn decode_qoi_rows_with_limits<'a, S: Stop>(
+    data: &'a [u8],
+    limits: &Limits,
+    stop: S,
+) -> Result<QoiRows<'a, S>> {
+    qoi::rows(data, Some(limits), stop)
+}
+
+/// Encode pixels as QOI.
+///
+/// Accepts `Rgb8`, `Rgba8`, `Bgr8`, `Bgra8` input layouts.
+#[cfg(feature = "qoi")]
+pub fn encode_qoi(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    qoi::encode(pixels, width, height, layout, &stop)
+}
+
+// ── BMP (auto-detected, or explicit) ─────────────────────────────────
+
+/// Probe BMP metadata without decoding pixels.
+///
+/// Returns resolution (DPI), pixel layout, dimensions, and color table
+/// information from the BMP header. This is much faster than a full decode
+/// when you only need metadata.
+#[cfg(feature = "bmp")]
+pub fn probe_bmp(data: &[u8]) -> Result<BmpMetadata> {
+    bmp::probe(data)
+}
+
+/// Decode BMP data to pixels.
+///
+/// Also auto-detected by [`decode()`] via the `"BM"` magic bytes.
+/// BMP always allocates (BGR→RGB conversion + row flip).
+#[cfg(feature = "bmp")]
+pub fn decode_bmp(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    bmp::decode(data, None, &stop)
+}
+
+/// Decode BMP with resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode BMP data in native byte order (BGR for 24-bit, BGRA for 32-bit).
+///
+/// Unlike [`decode_bmp`], this skips the BGR→RGB channel swizzle,
+/// returning pixels in the BMP-native byte order. The output layout will be
+/// [`PixelLayout::Bgr8`], [`PixelLayout::Bgra8`], or [`PixelLayout::Gray8`]
+/// ([`PixelLayout::Rgba16`] for BITFIELDS masks wider than 8 bits).
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_native(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    bmp::decode_native(data, None, &BmpDecodeOptions::new(), &stop)
+}
+
+/// Decode BMP in native byte order with resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_native_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_native(
+        data,
+        Some(limits),
+        &BmpDecodeOptions::new(),
+        &limits.deadline(&stop),
+    )
+}
+
+/// Decode BMP data to linear-light f32.
+///
+/// Applies the sRGB → linear transfer while decoding, for comparisons in a
+/// linear (HDR) domain. Alpha stays straight and is scaled to `0.0..=1.0`
+/// without a transfer curve. The output layout is [`PixelLayout::RgbF32`],
+/// [`PixelLayout::RgbaF32`] (files with an alpha channel, including 32-bit
+/// files whose alpha is synthesized as opaque), or [`PixelLayout::GrayF32`].
+/// Samples are native-endian `f32`.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_linear(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    bmp::decode_linear(
+        data,
+        None,
+        &BmpDecodeOptions::new(),
+        alloc_util::AllocPref::CodecDefault,
+        &stop,
+    )
+}
+
+/// Decode BMP to linear-light f32 with resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_linear_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_linear(
+        data,
+        Some(limits),
+        &BmpDecodeOptions::new(),
+        alloc_util::AllocPref::CodecDefault,
+        &limits.deadline(&stop),
+    )
+}
+
+/// Decode BMP to linear-light f32 with explicit [`BmpDecodeOptions`].
+///
+/// The permissiveness and [`BmpAlphaPolicy`] apply as in
+/// [`decode_bmp_with_options`]; the output is that of
+/// [`decode_bmp_linear`]. [`BmpDecodeOptions::with_keep_padding`] has no
+/// effect, since a float layout has no padding byte.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_linear_with_options<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_linear(
+        data,
+        None,
+        options,
+        alloc_util::AllocPref::CodecDefault,
+        &stop,
+    )
+}
+
+/// Decode BMP to linear-light f32 with explicit [`BmpDecodeOptions`] and
+/// resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_linear_with_options_and_limits<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_linear(
+        data,
+        Some(limits),
+        options,
+        alloc_util::AllocPref::CodecDefault,
+        &limits.deadline(&stop),
+    )
+}
+
+/// Decode BMP with a specific permissiveness level.
+///
+/// - [`BmpPermissiveness::Strict`]: reject any spec violation
+/// - [`BmpPermissiveness::Standard`]: default, reject corrupted files but
+///   accept benign metadata errors (bad DPI, wrong file size field)
+/// - [`BmpPermissiveness::Permissive`]: best-effort recovery (zero-pad
+///   truncated files, clamp RLE overflows, accept unknown compression)
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_permissive(
+    data: &[u8],
+    permissiveness: BmpPermissiveness,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'_>> {
+    bmp::decode_with_permissiveness(data, None, permissiveness, &stop)
+}
+
+/// Decode BMP with a specific permissiveness level and resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_permissive_with_limits<'a>(
+    data: &'a [u8],
+    permissiveness: BmpPermissiveness,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_with_permissiveness(data, Some(limits), permissiveness, &limits.deadline(&stop))
+}
+
+/// Decode BMP with explicit [`BmpDecodeOptions`].
+///
+/// Covers everything [`decode_bmp_permissive`] does, plus the
+/// [`BmpAlphaPolicy`] for 32-bit `BI_RGB` files and the alpha-less
+/// [`PixelLayout::Rgbx8`] output of [`BmpDecodeOptions::with_keep_padding`].
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_options<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_with_options(data, None, options, &stop)
+}
+
+/// Decode BMP with explicit [`BmpDecodeOptions`] and resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_options_and_limits<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_with_options(data, Some(limits), options, &limits.deadline(&stop))
+}
+
+/// Decode BMP and report the problems the decoder recovered from.
+///
+/// Pixels are identical to [`decode_bmp_with_options`]; the [`DecodeReport`]
+/// lists what was patched over (zero-filled truncation, skipped RLE runs,
+/// clamped palette counts, …) so a clean decode can be told apart from a
+/// recovered one. [`BmpPermissiveness::Strict`] rejects most of these
+/// outright.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_report<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    stop: impl Stop,
+) -> Result<(DecodeOutput<'a>, DecodeReport)> {
+    bmp::decode_with_report(data, None, options, &stop)
+}
+
+/// Decode BMP with resource limits and report recovered problems.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_report_and_limits<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<(DecodeOutput<'a>, DecodeReport)> {
+    bmp::decode_with_report(data, Some(limits), options, &limits.deadline(&stop))
+}
+
+/// Decode BMP and count how many pixels reference each palette entry.
+///
+/// Pixels are identical to [`decode_bmp_with_options`]. The
+/// [`BmpPaletteUsage`] is `None` for direct-color files (16/24/32-bit, or
+/// 8-bit without a palette); indices past the palette, which only
+/// [`BmpPermissiveness::Permissive`] accepts, are counted apart in
+/// [`BmpPaletteUsage::out_of_range`]. A downscale in `options` fails with
+/// [`BitmapError::InvalidArgument`].
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_palette_usage<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    stop: impl Stop,
+) -> Result<(DecodeOutput<'a>, Option<BmpPaletteUsage>)> {
+    bmp::decode_with_palette_usage(data, None, options, &stop)
+}
+
+/// Decode BMP with resource limits and count palette entry usage.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_with_palette_usage_and_limits<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<(DecodeOutput<'a>, Option<BmpPaletteUsage>)> {
+    bmp::decode_with_palette_usage(data, Some(limits), options, &limits.deadline(&stop))
+}
+
+/// Encode pixels as 24-bit BMP (RGB, no alpha).
+#[cfg(feature = "bmp")]
+pub fn encode_bmp(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    bmp::encode(
+        pixels,
+        width,
+        height,
+        layout,
+        &BmpEncodeOptions::new(),
+        &stop,
+    )
+}
+
+/// Encode pixels as 32-bit BMP (RGBA with alpha).
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_rgba(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let options = BmpEncodeOptions::new().with_alpha(true);
+    bmp::encode(pixels, width, height, layout, &options, &stop)
+}
+
+/// Encode pixels as BMP with explicit [`BmpEncodeOptions`].
+///
+/// Every BMP encoder in the crate is a preset of this one: default options
+/// give [`encode_bmp`], `with_alpha(true)` [`encode_bmp_rgba`],
+/// `with_bit_depth(16)` [`encode_bmp_rgb555`], and so on. Bit depth,
+/// compression, palette, header version, row order, resolution and ICC
+/// profile combine freely where the format allows; combinations it does
+/// not (RLE top-down, RLE4 at 8 bits, a palette at 24 bits, …) fail with
+/// [`BitmapError::UnsupportedVariant`].
+///
+/// Paletted depths (1/2/4/8) map `pixels` to the nearest entry of
+/// [`BmpEncodeOptions::with_palette`], or quantize a palette from the image
+/// when none is given; alpha is dropped.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    bmp::encode(pixels, width, height, layout, options, &stop)
+}
+
+/// Encode pixels as BMP into `out`, like [`encode_bmp_with`].
+///
+/// `out` is cleared first and its allocation reused, so a loop encoding
+/// many images pays for the buffer once. On error it may hold part of a
+/// file.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_into(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    out: &mut alloc::vec::Vec<u8>,
+    stop: impl Stop,
+) -> Result<()> {
+    out.clear();
+    out.reserve(bmp::whole_file_capacity(
+        pixels, width, height, layout, options,
+    ));
+    bmp::encode_into(
+        pixels,
+        width,
+        height,
+        layout,
+        options,
+        out,
+        &mut |_| Ok(()),
+        &stop,
+    )
+}
+
+/// Encode pixels as BMP straight to `writer`, like [`encode_bmp_with`].
+///
+/// Direct-color output (16/24/32/64-bit and grayscale) is written every 16
+/// rows, so the whole file is never held in memory; paletted output is
+/// assembled first, since its palette and RLE size depend on every row.
+/// The writer is not flushed. Write failures return [`BitmapError::Io`].
+#[cfg(all(feature = "bmp", feature = "std"))]
+pub fn encode_bmp_to_writer<W: std::io::Write>(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &BmpEncodeOptions<'_>,
+    mut writer: W,
+    stop: impl Stop,
+) -> Result<()> {
+    let mut out = alloc::vec::Vec::new();
+    bmp::encode_into(
+        pixels,
+        width,
+        height,
+        layout,
+        options,
+        &mut out,
+        &mut |buf| {
+            writer
+                .write_all(buf)
+                .map_err(|e| whereat::at!(BitmapError::Io(e)))?;
+            buf.clear();
+            Ok(())
+        },
+        &stop,
+    )
+}
+
+/// Encode pixels as 16-bit RGB555 BMP (`BI_RGB`, 5 bits per channel).
+///
+/// Each channel is rounded to the nearest 5-bit value; alpha is dropped.
+/// Accepts the same layouts as [`encode_bmp`]. Decodes back through
+/// [`decode_bmp`] to `Rgb8`.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_rgb555(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let options = BmpEncodeOptions::new().with_bit_depth(16);
+    bmp::encode(pixels, width, height, layout, &options, &stop)
+}
+
+/// Encode pixels as 16-bit RGB565 BMP (`BI_BITFIELDS`, 6-bit green).
+///
+/// As [`encode_bmp_rgb555`], with the `0xF800` / `0x07E0` / `0x001F` masks
+/// written after the info header.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_rgb565(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let options = BmpEncodeOptions::new()
+        .with_bit_depth(16)
+        .with_compression(BmpCompression::Bitfields);
+    bmp::encode(pixels, width, height, layout, &options, &stop)
+}
+
+/// Encode palette indices as an uncompressed 8-bit BMP with a color table.
+///
+/// `indices` holds one palette index per pixel, top-down; `palette` (1 to
+/// 256 RGB entries) is written verbatim as the color table, in order, so
+/// the file is a byte-exact reference for a given index/palette pair.
+/// Every index must be inside the palette. Decodes back through
+/// [`decode_bmp`] to `Rgb8`.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_paletted(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    bmp::encode_indexed(
+        indices,
+        palette,
+        width,
+        height,
+        8,
+        &BmpEncodeOptions::new(),
+        &stop,
+    )
+}
+
+/// Encode palette indices as an uncompressed 4-bit BMP with a color table.
+///
+/// As [`encode_bmp_paletted`], with a palette of 1 to 16 entries; two
+/// pixels are packed per byte, high nibble first.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_paletted4(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    bmp::encode_indexed(
+        indices,
+        palette,
+        width,
+        height,
+        4,
+        &BmpEncodeOptions::new(),
+        &stop,
+    )
+}
+
+/// Encode 8-bit pixels as a 4-bit paletted BMP, choosing the palette.
+///
+/// Images with at most 16 distinct colors are stored exactly. Others are
+/// reduced to 16 colors by median cut and mapped to the nearest entry,
+/// without dithering. Accepts the same layouts as [`encode_bmp`]; alpha is
+/// dropped.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_4bit(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let options = BmpEncodeOptions::new().with_bit_depth(4);
+    bmp::encode(pixels, width, height, layout, &options, &stop)
+}
+
+/// Encode palette indices as an 8-bit RLE8-compressed BMP.
+///
+/// `indices` holds one palette index per pixel, top-down; `palette` (1 to
+/// 256 RGB entries) becomes the color table. Every index must be inside
+/// the palette. Decodes back through [`decode_bmp`] to `Rgb8`.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_rle8(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let options = BmpEncodeOptions::new().with_compression(BmpCompression::Rle8);
+    bmp::encode_indexed(indices, palette, width, height, 8, &options, &stop)
+}
+
+/// Encode palette indices as a 4-bit RLE4-compressed BMP.
+///
+/// As [`encode_bmp_rle8`], with a palette of 1 to 16 entries.
+#[cfg(feature = "bmp")]
+pub fn encode_bmp_rle4(
+    indices: &[u8],
+    palette: &[[u8; 3]],
+    width: u32,
+    height: u32,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    let options = BmpEncodeOptions::new().with_compression(BmpCompression::Rle4);
+    bmp::encode_indexed(indices, palette, width, height, 4, &options, &stop)
+}
+
+/// Decode a headerless DIB: a `BITMAPINFOHEADER` (or V4/V5, or OS/2 core
+/// header) followed directly by masks, palette, and pixels, with no `"BM"`
+/// file header.
+///
+/// This is the layout of Windows clipboard `CF_DIB` / `CF_DIBV5` data and of
+/// DIBs embedded in other containers. Output layouts match [`decode_bmp`].
+/// Not auto-detected by [`decode()`].
+#[cfg(feature = "bmp")]
+pub fn decode_dib(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    bmp::decode_dib(data, None, &BmpDecodeOptions::new(), &stop)
+}
+
+/// Decode a headerless DIB with resource limits.
+#[cfg(feature = "bmp")]
+pub fn decode_dib_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_dib(
+        data,
+        Some(limits),
+        &BmpDecodeOptions::new(),
+        &limits.deadline(&stop),
+    )
+}
+
+/// Decode a headerless DIB with explicit [`BmpDecodeOptions`].
+#[cfg(feature = "bmp")]
+pub fn decode_dib_with_options<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_dib(data, None, options, &stop)
+}
+
+/// Decode a headerless DIB with explicit [`BmpDecodeOptions`] and resource
+/// limits.
+#[cfg(feature = "bmp")]
+pub fn decode_dib_with_options_and_limits<'a>(
+    data: &'a [u8],
+    options: &BmpDecodeOptions,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    bmp::decode_dib(data, Some(limits), options, &limits.deadline(&stop))
+}
+
+/// List the images in an OS/2 bitmap array (`BA` magic) without decoding.
+///
+/// The plain BMP entry points (and [`decode()`]) accept bitmap arrays too
+/// and decode the largest bitmap; use [`decode_bmp_array`] for all of them.
+#[cfg(feature = "bmp")]
+pub fn probe_bmp_array(data: &[u8]) -> Result<alloc::vec::Vec<BmpArrayEntry>> {
+    bmp::array::probe(data)
+}
+
+/// Decode every image in an OS/2 bitmap array, one frame at a time.
+///
+/// Frames are decoded lazily in file order. Icon and pointer entries yield
+/// [`BitmapError::UnsupportedVariant`] and can be skipped.
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_array<S: Stop>(data: &[u8], stop: S) -> Result<BmpArrayFrames<'_, S>> {
+    bmp::array::frames(data, None, stop)
+}
+
+/// Decode every image in an OS/2 bitmap array with resource limits
+/// (applied per frame).
+#[cfg(feature = "bmp")]
+pub fn decode_bmp_array_with_limits<'a, S: Stop>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: S,
+) -> Result<BmpArrayFrames<'a, S>> {
+    bmp::array::frames(data, Some(limits), stop)
+}
+
+// ── ICO / CUR ────────────────────────────────────────────────────────
+
+/// Read the ICO/CUR directory without decoding any images.
+///
+/// Lists every entry with its size, bit depth, cursor hotspot, and whether
+/// it is PNG-compressed. Pass an index into [`decode_ico_entry`] to decode
+/// a specific entry.
+#[cfg(feature = "ico")]
+pub fn probe_ico(data: &[u8]) -> Result<IcoDirectory> {
+    ico::probe(data)
+}
+
+/// Decode the best image in an ICO/CUR file.
+///
+/// Picks the largest DIB entry (ties broken by bit depth); PNG entries are
+/// skipped. Output is always [`PixelLayout::Rgba8`], with the AND mask
+/// folded into alpha for entries without an alpha channel. ICO is not
+/// auto-detected by [`decode()`].
+#[cfg(feature = "ico")]
+pub fn decode_ico(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    ico::decode(data, None, &stop)
+}
+
+/// Decode the best image in an ICO/CUR file with resource limits.
+#[cfg(feature = "ico")]
+pub fn decode_ico_with_limits<'a>(
+    data: &'a [u8],
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    ico::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode one ICO/CUR entry by directory index (see [`probe_ico`]).
+///
+/// Returns [`BitmapError::UnsupportedVariant`] for PNG-compressed entries.
+#[cfg(feature = "ico")]
+pub fn decode_ico_entry(data: &[u8], index: usize, stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    ico::decode_entry(data, index, None, &stop)
+}
+
+/// Decode one ICO/CUR entry by directory index with resource limits.
+#[cfg(feature = "ico")]
+pub fn decode_ico_entry_with_limits<'a>(
+    data: &'a [u8],
+    index: usize,
+    limits: &'a Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'a>> {
+    ico::decode_entry(data, index, Some(limits), &limits.deadline(&stop))
+}
+
+/// Encode one or more images as an ICO.
+///
+/// Each [`IcoImage`] (1 to 256 pixels on a side) becomes a 32-bit DIB
+/// entry, in order: BGRA color with alpha, doubled stored height, and a
+/// 1-bit AND mask set wherever alpha is below 128 for readers that ignore
+/// alpha. Layouts are those of [`encode_bmp_rgba`]. Typical icons hold
+/// the same picture at 16, 32, 48 and 256 pixels.
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let small = [255u8; 16 * 16 * 4];
+/// let large = [0u8; 32 * 32 * 4];
+/// let ico = encode_ico(
+///     &[
+///         IcoImage::new(&small, 16, 16, PixelLayout::Rgba8),
+///         IcoImage::new(&large, 32, 32, PixelLayout::Rgba8),
+///     ],
+///     Unstoppable,
+/// )?;
+/// assert_eq!(probe_ico(&ico)?.entries.len(), 2);
+/// assert_eq!(decode_ico_entry(&ico, 0, Unstoppable)?.pixels(), &small);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "ico")]
+pub fn encode_ico(images: &[IcoImage<'_>], stop: impl Stop) -> Result<alloc::vec::Vec<u8>> {
+    ico::encode(images, &stop)
+}
+
+// ── PCX ──────────────────────────────────────────────────────────────
+
+/// Decode a ZSoft PCX image.
+///
+/// Monochrome images decode to [`PixelLayout::Gray8`], paletted ones
+/// (2 to 256 colors) to [`PixelLayout::Rgb8`], and 24/32-bit ones to
+/// `Rgb8` / [`PixelLayout::Rgba8`]. 8-bit images without the trailing VGA
+/// palette decode as `Gray8`. PCX is not auto-detected by [`decode()`].
+#[cfg(feature = "pcx")]
+pub fn decode_pcx(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    pcx::decode(data, None, &stop)
+}
+
+/// Decode a ZSoft PCX image with resource limits.
+#[cfg(feature = "pcx")]
+pub fn decode_pcx_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    pcx::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+// ── XPM ──────────────────────────────────────────────────────────────
+
+/// Decode an XPM3 (X PixMap) image.
+///
+/// Colors come from each definition's `c` key, falling back to `g`, `g4`
+/// and `m`; values may be `#rgb` hex (1-4 digits per channel), common X11
+/// color names, or `None`. Output is [`PixelLayout::Rgba8`] when any color
+/// is `None` (transparent), otherwise [`PixelLayout::Rgb8`]. XPM is not
+/// auto-detected by [`decode()`].
+#[cfg(feature = "xpm")]
+pub fn decode_xpm(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    xpm::decode(data, None, &stop)
+}
+
+/// Decode an XPM3 (X PixMap) image with resource limits.
+#[cfg(feature = "xpm")]
+pub fn decode_xpm_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    xpm::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+// ── DDS ──────────────────────────────────────────────────────────────
+
+/// Decode an uncompressed DDS (DirectDraw Surface) image.
+///
+/// Handles any RGB or luminance mask layout (A8R8G8B8, X8R8G8B8, R8G8B8,
+/// A1R5G5B5, R5G6B5, A4R4G4B4, L8, A8L8, A2R10G10B10, …) and DX10 headers
+/// with 8-bit RGBA/BGRA DXGI formats. Only the top mip level of the first
+/// surface is decoded. Output is [`PixelLayout::Gray8`] for luminance,
+/// [`PixelLayout::Rgb8`] / [`PixelLayout::Rgba8`] otherwise, and
+/// [`PixelLayout::Gray16`] / [`PixelLayout::Rgba16`] when a mask is wider
+/// than 8 bits. Block-compressed (FourCC) files return
+/// [`BitmapError::UnsupportedVariant`]. DDS is not auto-detected by
+/// [`decode()`].
+#[cfg(feature = "dds")]
+pub fn decode_dds(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    dds::decode(data, None, &stop)
+}
+
+/// Decode an uncompressed DDS image with resource limits.
+#[cfg(feature = "dds")]
+pub fn decode_dds_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    dds::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+// ── XWD ──────────────────────────────────────────────────────────────
+
+/// Decode an XWD (X Window Dump) image, as written by `xwd` and X11
+/// screenshot tools.
+///
+/// Handles `ZPixmap` images with 24 or 32 bits per pixel and any channel
+/// masks, with the header and pixels in either byte order. Output is
+/// [`PixelLayout::Rgb8`], or [`PixelLayout::Rgba8`] for depth-32 (ARGB
+/// visual) dumps, with the bits outside the color masks as alpha; masks
+/// wider than 8 bits give [`PixelLayout::Rgb16`] / [`PixelLayout::Rgba16`].
+/// Bitmaps, `XYPixmap` and colormapped dumps return
+/// [`BitmapError::UnsupportedVariant`]. XWD is not auto-detected by
+/// [`decode()`].
+#[cfg(feature = "xwd")]
+pub fn decode_xwd(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    xwd::decode(data, None, &stop)
+}
+
+/// Decode an XWD image with resource limits.
+#[cfg(feature = "xwd")]
+pub fn decode_xwd_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    xwd::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+// ── Raw YUV ──────────────────────────────────────────────────────────
+
+/// Convert one raw 8-bit YUV frame to [`PixelLayout::Rgb8`].
+///
+/// Raw YUV has no header: `width`, `height` and `layout` describe the frame
+/// and `matrix` selects the YCbCr coefficients and range. Chroma is
+/// upsampled by replication. `data` may hold more than one frame; only the
+/// first [`YuvLayout::frame_size`] bytes are read, so slice at multiples of
+/// it to reach later frames.
+///
+/// ```
+/// use zenbitmaps::*;
+/// // 2×2 I420: four Y samples, one U, one V (mid-gray)
+/// let frame = [126, 126, 126, 126, 128, 128];
+/// let rgb = decode_yuv(&frame, 2, 2, YuvLayout::I420, YuvMatrix::Bt601, Unstoppable)?;
+/// assert_eq!(&rgb.pixels()[..3], &[128, 128, 128]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "yuv")]
+pub fn decode_yuv(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    layout: YuvLayout,
+    matrix: YuvMatrix,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    yuv::decode(data, width, height, layout, matrix, None, &stop)
+}
+
+/// Convert one raw 8-bit YUV frame to RGB with resource limits.
+#[cfg(feature = "yuv")]
+pub fn decode_yuv_with_limits(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    layout: YuvLayout,
+    matrix: YuvMatrix,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    yuv::decode(
+        data,
+        width,
+        height,
+        layout,
+        matrix,
+        Some(limits),
+        &limits.deadline(&stop),
+    )
+}
+
+/// Convert one raw 10-bit V210 or P010 frame to [`PixelLayout::Rgb16`].
+///
+/// Like [`decode_yuv`], geometry comes from the caller and only the first
+/// [`Yuv10Layout::frame_size`] bytes are read. Limited-range matrices take
+/// the 10-bit ranges (Y 64-940, chroma 64-960); output spans the full
+/// 16-bit range, clamped.
+///
+/// ```
+/// use zenbitmaps::*;
+/// // 2×2 P010: four Y samples, one U/V pair, values in the top 10 bits
+/// let words = [940u16, 940, 64, 64, 512, 512].map(|v| (v << 6).to_le_bytes());
+/// let (layout, matrix) = (Yuv10Layout::P010, YuvMatrix::Bt709);
+/// let rgb = decode_yuv10(words.as_flattened(), 2, 2, layout, matrix, Unstoppable)?;
+/// assert_eq!(rgb.layout, PixelLayout::Rgb16);
+/// assert_eq!(&rgb.pixels()[..6], &[0xFF; 6]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "yuv")]
+pub fn decode_yuv10(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    layout: Yuv10Layout,
+    matrix: YuvMatrix,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    yuv::decode_10bit(data, width, height, layout, matrix, None, &stop)
+}
+
+/// Convert one raw 10-bit YUV frame to `Rgb16` with resource limits.
+#[cfg(feature = "yuv")]
+pub fn decode_yuv10_with_limits(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    layout: Yuv10Layout,
+    matrix: YuvMatrix,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    yuv::decode_10bit(
+        data,
+        width,
+        height,
+        layout,
+        matrix,
+        Some(limits),
+        &limits.deadline(&stop),
+    )
+}
+
+// ── Bayer mosaics ────────────────────────────────────────────────────
+
+/// Demosaic one raw Bayer frame to [`PixelLayout::Rgb16`].
+///
+/// Like raw YUV, a mosaic has no header: `width`, `height`, the filter
+/// `pattern` and `bit_depth` (1 to 16) come from the caller. Depths up to 8
+/// take one byte per sample, deeper ones a little-endian `u16` with the
+/// value in the low bits (higher bits are ignored). Only the first
+/// `width × height` samples are read. Missing channels are interpolated
+/// bilinearly and every channel is rescaled to the full 16-bit range.
+/// Both dimensions must be at least 2.
+///
+/// ```
+/// use zenbitmaps::*;
+/// // A 2×2 RGGB tile with only the red site lit
+/// let rgb = decode_bayer(&[255, 0, 0, 0], 2, 2, BayerPattern::Rggb, 8, Unstoppable)?;
+/// let red = 65535u16.to_ne_bytes();
+/// assert_eq!(&rgb.pixels()[..6], &[red[0], red[1], 0, 0, 0, 0]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "bayer")]
+pub fn decode_bayer(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    pattern: BayerPattern,
+    bit_depth: u8,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    bayer::decode(data, width, height, pattern, bit_depth, None, &stop)
+}
+
+/// Demosaic one raw Bayer frame to `Rgb16` with resource limits.
+#[cfg(feature = "bayer")]
+pub fn decode_bayer_with_limits(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    pattern: BayerPattern,
+    bit_depth: u8,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    bayer::decode(
+        data,
+        width,
+        height,
+        pattern,
+        bit_depth,
+        Some(limits),
+        &limits.deadline(&stop),
+    )
+}
+
+// ── Y4M ──────────────────────────────────────────────────────────────
+
+/// Parse a YUV4MPEG2 header without touching the frames.
+#[cfg(feature = "y4m")]
+pub fn probe_y4m(data: &[u8]) -> Result<Y4mHeader> {
+    y4m::parse_header(data).map(|(header, _)| header)
+}
+
+/// Decode the first frame of a YUV4MPEG2 stream to [`PixelLayout::Rgb8`].
+///
+/// Y4M does not record a matrix; pass the one the stream was made with
+/// (see [`Y4mHeader::full_range`]).
+#[cfg(feature = "y4m")]
+pub fn decode_y4m(
+    data: &[u8],
+    matrix: YuvMatrix,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    y4m::decode(data, matrix, None, &stop)
+}
+
+/// Decode the first frame of a YUV4MPEG2 stream with resource limits.
+#[cfg(feature = "y4m")]
+pub fn decode_y4m_with_limits(
+    data: &[u8],
+    matrix: YuvMatrix,
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    y4m::decode(data, matrix, Some(limits), &limits.deadline(&stop))
+}
+
+/// Iterate over the frames of a YUV4MPEG2 stream.
+///
+/// Frames borrow their planes from `data`; convert with
+/// [`Y4mFrame::to_rgb`] when RGB is wanted.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let data = b"YUV4MPEG2 W2 H1 F30:1 C444\nFRAME\n\x10\xeb\x80\x80\x80\x80\
+///              FRAME\n\xeb\x10\x80\x80\x80\x80";
+/// let frames = decode_y4m_frames(data, Unstoppable)?;
+/// assert_eq!(frames.header().frame_rate, Some((30, 1)));
+/// let lumas: Vec<&[u8]> = frames.map(|f| f.map(|f| f.y())).collect::<Result<_>>()?;
+/// assert_eq!(lumas, [&[16, 235], &[235, 16]]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "y4m")]
+pub fn decode_y4m_frames<S: Stop>(data: &[u8], stop: S) -> Result<Y4mFrames<'_, S>> {
+    y4m::frames(data, None, stop)
+}
+
+/// Iterate over the frames of a YUV4MPEG2 stream with resource limits
+/// (applied per frame; `max_file_size` applies to the whole input).
+#[cfg(feature = "y4m")]
+pub fn decode_y4m_frames_with_limits<'a, S: Stop>(
     data: &'a [u8],
     limits: &'a Limits,
+    stop: S,
+) -> Result<Y4mFrames<'a, S>> {
+    y4m::frames(data, Some(limits), stop)
+}
+
+// ── TIFF ─────────────────────────────────────────────────────────────
+
+/// Decode the first image of a baseline uncompressed TIFF.
+///
+/// Reads strip-based, chunky 8/16-bit gray or RGB, with or without an
+/// alpha channel, in either byte order. Output is `Gray8`, `Gray16`,
+/// `GrayA16`, `Rgb8`, `Rgb16`, `Rgba8` or `Rgba16` (16-bit samples in
+/// native byte order). 8-bit gray + alpha expands to `Rgba8`, and
+/// premultiplied alpha is converted to straight. 8-bit images whose strips
+/// are contiguous and need no conversion borrow the input.
+///
+/// Compressed, tiled, planar, paletted, CMYK and floating-point files are
+/// [`BitmapError::UnsupportedVariant`].
+#[cfg(feature = "tiff")]
+pub fn decode_tiff(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    tiff::decode::decode(data, None, &stop)
+}
+
+/// Decode a baseline uncompressed TIFF with resource limits.
+#[cfg(feature = "tiff")]
+pub fn decode_tiff_with_limits<'a>(
+    data: &'a [u8],
+    limits: &Limits,
     stop: impl Stop,
 ) -> Result<DecodeOutput<'a>> {
-    qoi::decode(data, Some(limits), &stop)
+    tiff::decode::decode(data, Some(limits), &limits.deadline(&stop))
 }
 
-/// Encode pixels as QOI.
+/// Encode pixels as a baseline uncompressed TIFF, one strip, little-endian.
 ///
-/// Accepts `Rgb8`, `Rgba8`, `Bgr8`, `Bgra8` input layouts.
-#[cfg(feature = "qoi")]
-pub fn encode_qoi(
+/// Accepts `Gray8`, `Gray16`, `GrayA16`, `Rgb8`, `Rgb16`, `Rgba8`, `Rgba16`
+/// (16-bit samples native-endian), and `Bgr8`, `Bgra8`, `Rgbx8`, `Bgrx8`,
+/// which are written as RGB or RGBA. Alpha is stored as straight
+/// (unassociated). Output always reads back with [`decode_tiff`].
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let pixels = [255u8, 0, 0, 0, 0, 255];
+/// let tiff = encode_tiff(&pixels, 2, 1, PixelLayout::Rgb8, Unstoppable)?;
+/// let decoded = decode_tiff(&tiff, Unstoppable)?;
+/// assert_eq!(decoded.pixels(), &pixels);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "tiff")]
+pub fn encode_tiff(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
     stop: impl Stop,
 ) -> Result<alloc::vec::Vec<u8>> {
-    qoi::encode(pixels, width, height, layout, &stop)
+    tiff::encode(
+        pixels,
+        width,
+        height,
+        layout,
+        &TiffEncodeOptions::new(),
+        &stop,
+    )
 }
 
-// ── BMP (auto-detected, or explicit) ─────────────────────────────────
+/// Encode pixels as TIFF with explicit [`TiffEncodeOptions`] (strip height,
+/// byte order).
+#[cfg(feature = "tiff")]
+pub fn encode_tiff_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &TiffEncodeOptions,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    tiff::encode(pixels, width, height, layout, options, &stop)
+}
 
-/// Probe BMP metadata without decoding pixels.
+// ── DPX ──────────────────────────────────────────────────────────────
+
+/// Decode a 10-bit RGB DPX image to [`PixelLayout::Rgb16`] code values.
 ///
-/// Returns resolution (DPI), pixel layout, dimensions, and color table
-/// information from the BMP header. This is much faster than a full decode
-/// when you only need metadata.
-#[cfg(feature = "bmp")]
-pub fn probe_bmp(data: &[u8]) -> Result<BmpMetadata> {
-    bmp::probe(data)
+/// Reads the first image element of the common film/video variant: RGB
+/// (descriptor 50), three 10-bit samples filled into each 32-bit word
+/// (packing method A or B), uncompressed, big-endian (`SDPX`) or
+/// little-endian (`XPDS`). Code values are scaled to 16 bits by bit
+/// replication, so 1023 becomes 65535; no transfer function is applied.
+/// Use [`decode_dpx_linear`] for linear light.
+///
+/// Other descriptors, bit depths, packings and RLE are
+/// [`BitmapError::UnsupportedVariant`]. DPX is not auto-detected by
+/// [`decode()`].
+#[cfg(feature = "dpx")]
+pub fn decode_dpx(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    dpx::decode(data, false, None, &stop)
 }
 
-/// Decode BMP data to pixels.
+/// Decode a 10-bit RGB DPX image to `Rgb16` code values, with resource
+/// limits.
+#[cfg(feature = "dpx")]
+pub fn decode_dpx_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    dpx::decode(data, false, Some(limits), &limits.deadline(&stop))
+}
+
+/// Decode a 10-bit RGB DPX image to linear [`PixelLayout::RgbF32`].
 ///
-/// Also auto-detected by [`decode()`] via the `"BM"` magic bytes.
-/// BMP always allocates (BGR→RGB conversion + row flip).
-#[cfg(feature = "bmp")]
-pub fn decode_bmp(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
-    bmp::decode(data, None, &stop)
+/// Logarithmic data (transfer 1, printing density, or 3) goes through the
+/// Cineon conversion: 0.002 density per code value at a film gamma of 0.6,
+/// with the element's reference black and white codes (95 and 685 when
+/// unset) mapped to 0.0 and 1.0. Highlights above reference white exceed
+/// 1.0. Any other transfer is scaled from 0..=1023 to 0.0..=1.0.
+#[cfg(feature = "dpx")]
+pub fn decode_dpx_linear(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    dpx::decode(data, true, None, &stop)
 }
 
-/// Decode BMP with resource limits.
-#[cfg(feature = "bmp")]
-pub fn decode_bmp_with_limits<'a>(
-    data: &'a [u8],
-    limits: &'a Limits,
+/// Decode a 10-bit RGB DPX image to linear `RgbF32`, with resource limits.
+#[cfg(feature = "dpx")]
+pub fn decode_dpx_linear_with_limits(
+    data: &[u8],
+    limits: &Limits,
     stop: impl Stop,
-) -> Result<DecodeOutput<'a>> {
-    bmp::decode(data, Some(limits), &stop)
+) -> Result<DecodeOutput<'static>> {
+    dpx::decode(data, true, Some(limits), &limits.deadline(&stop))
 }
 
-/// Decode BMP data in native byte order (BGR for 24-bit, BGRA for 32-bit).
+// ── PGX ──────────────────────────────────────────────────────────────
+
+/// Parse a PGX header (dimensions, bit depth, sign, byte order).
+#[cfg(feature = "pgx")]
+pub fn probe_pgx(data: &[u8]) -> Result<PgxHeader> {
+    pgx::parse_header(data).map(|(header, _)| header)
+}
+
+/// Decode a PGX image (JPEG 2000 conformance format).
 ///
-/// Unlike [`decode_bmp`], this skips the BGR→RGB channel swizzle,
-/// returning pixels in the BMP-native byte order. The output layout will be
-/// [`PixelLayout::Bgr8`], [`PixelLayout::Bgra8`], or [`PixelLayout::Gray8`].
-#[cfg(feature = "bmp")]
-pub fn decode_bmp_native(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
-    bmp::decode_native(data, None, &stop)
+/// Depths up to 8 bits decode to [`PixelLayout::Gray8`], up to 16 to
+/// native-endian [`PixelLayout::Gray16`]. Values are not rescaled: a
+/// 12-bit image spans `0..=4095`, so decoded JPEG 2000 output compares
+/// sample for sample. Signed samples are level-shifted by `2^(depth - 1)`
+/// (as JPEG 2000 does internally) and clamped to the depth's range; use
+/// [`probe_pgx`] to recover the depth and sign. Unsigned files needing no
+/// byte swap borrow the input.
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let data = b"PG ML - 8 2 1\n\x80\x7f";
+/// let header = probe_pgx(data)?;
+/// assert!(header.signed);
+/// assert_eq!(decode_pgx(data, Unstoppable)?.pixels(), &[0, 255]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "pgx")]
+pub fn decode_pgx(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    pgx::decode(data, None, &stop)
 }
 
-/// Decode BMP in native byte order with resource limits.
-#[cfg(feature = "bmp")]
-pub fn decode_bmp_native_with_limits<'a>(
+/// Decode a PGX image with resource limits.
+#[cfg(feature = "pgx")]
+pub fn decode_pgx_with_limits<'a>(
     data: &'a [u8],
-    limits: &'a Limits,
+    limits: &Limits,
     stop: impl Stop,
 ) -> Result<DecodeOutput<'a>> {
-    bmp::decode_native(data, Some(limits), &stop)
+    pgx::decode(data, Some(limits), &limits.deadline(&stop))
 }
 
-/// Decode BMP with a specific permissiveness level.
+/// Encode `Gray8` or `Gray16` pixels as an unsigned, big-endian PGX at
+/// the layout's full depth (8 or 16 bits).
+#[cfg(feature = "pgx")]
+pub fn encode_pgx(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: impl Stop,
+) -> Result<alloc::vec::Vec<u8>> {
+    pgx::encode(
+        pixels,
+        width,
+        height,
+        layout,
+        &PgxEncodeOptions::new(),
+        &stop,
+    )
+}
+
+/// Encode `Gray8` or `Gray16` pixels as PGX with explicit
+/// [`PgxEncodeOptions`] (bit depth, sign, byte order).
 ///
-/// - [`BmpPermissiveness::Strict`]: reject any spec violation
-/// - [`BmpPermissiveness::Standard`]: default, reject corrupted files but
-///   accept benign metadata errors (bad DPI, wrong file size field)
-/// - [`BmpPermissiveness::Permissive`]: best-effort recovery (zero-pad
-///   truncated files, clamp RLE overflows, accept unknown compression)
-#[cfg(feature = "bmp")]
-pub fn decode_bmp_permissive(
-    data: &[u8],
-    permissiveness: BmpPermissiveness,
+/// The inverse of [`decode_pgx`]: encoding its output with the depth and
+/// sign from [`probe_pgx`] (and the same byte order) reproduces the file.
+/// Samples that do not fit the bit depth are [`BitmapError::InvalidData`].
+#[cfg(feature = "pgx")]
+pub fn encode_pgx_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &PgxEncodeOptions,
     stop: impl Stop,
-) -> Result<DecodeOutput<'_>> {
-    bmp::decode_with_permissiveness(data, None, permissiveness, &stop)
+) -> Result<alloc::vec::Vec<u8>> {
+    pgx::encode(pixels, width, height, layout, options, &stop)
 }
 
-/// Decode BMP with a specific permissiveness level and resource limits.
-#[cfg(feature = "bmp")]
-pub fn decode_bmp_permissive_with_limits<'a>(
+// ── NumPy .npy ───────────────────────────────────────────────────────
+
+/// Decode a NumPy `.npy` array as an image.
+///
+/// Accepts C-order `uint8`, `uint16` and `float32` arrays of shape
+/// `(H, W)` or `(H, W, C)` in either byte order. `C` of 1, 3 or 4 gives
+/// gray, RGB or RGBA at the array's depth ([`PixelLayout::Gray8`],
+/// [`PixelLayout::Rgb16`], [`PixelLayout::RgbaF32`], …); `uint16` with
+/// `C = 2` gives [`PixelLayout::GrayA16`]. Samples are native-endian, and
+/// native-order arrays borrow the input. Other dtypes, shapes and
+/// Fortran-order arrays are [`BitmapError::UnsupportedVariant`].
+#[cfg(feature = "npy")]
+pub fn decode_npy(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'_>> {
+    npy::decode(data, None, &stop)
+}
+
+/// Decode a NumPy `.npy` array as an image, with resource limits.
+#[cfg(feature = "npy")]
+pub fn decode_npy_with_limits<'a>(
     data: &'a [u8],
-    permissiveness: BmpPermissiveness,
-    limits: &'a Limits,
+    limits: &Limits,
     stop: impl Stop,
 ) -> Result<DecodeOutput<'a>> {
-    bmp::decode_with_permissiveness(data, Some(limits), permissiveness, &stop)
+    npy::decode(data, Some(limits), &limits.deadline(&stop))
 }
 
-/// Encode pixels as 24-bit BMP (RGB, no alpha).
-#[cfg(feature = "bmp")]
-pub fn encode_bmp(
+/// Encode pixels as a NumPy `.npy` array.
+///
+/// Writes shape `(H, W)` for gray and `(H, W, C)` otherwise, dtype `uint8`,
+/// `uint16` or `float32` to match the layout, in native byte order, so
+/// `numpy.load` reads it without copying. Accepts the layouts
+/// [`decode_npy`] produces.
+///
+/// ```
+/// use zenbitmaps::*;
+/// use enough::Unstoppable;
+///
+/// let npy = encode_npy(&[1, 2, 3, 4, 5, 6], 2, 1, PixelLayout::Rgb8, Unstoppable)?;
+/// assert!(npy.starts_with(b"\x93NUMPY"));
+/// assert_eq!(npy.len() % 64, 6);
+/// let decoded = decode_npy(&npy, Unstoppable)?;
+/// assert_eq!(decoded.layout, PixelLayout::Rgb8);
+/// assert_eq!(decoded.pixels(), &[1, 2, 3, 4, 5, 6]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "npy")]
+pub fn encode_npy(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
     stop: impl Stop,
 ) -> Result<alloc::vec::Vec<u8>> {
-    bmp::encode(pixels, width, height, layout, false, &stop)
+    npy::encode(pixels, width, height, layout, &stop)
 }
 
-/// Encode pixels as 32-bit BMP (RGBA with alpha).
-#[cfg(feature = "bmp")]
-pub fn encode_bmp_rgba(
+// ── OpenEXR ──────────────────────────────────────────────────────────
+
+/// Decode an uncompressed scanline OpenEXR file to linear `RgbF32`, or
+/// `RgbaF32` when it has an `A` channel.
+///
+/// `HALF` channels are widened to `f32`; values are not clamped, so float
+/// reference renders compare like PFM. A lone `Y` channel is
+/// copied to R, G and B; channels other than `R`, `G`, `B`, `A` and `Y`
+/// are ignored. Tiled, deep, multi-part and compressed files are
+/// [`BitmapError::UnsupportedVariant`].
+#[cfg(feature = "exr")]
+pub fn decode_exr(data: &[u8], stop: impl Stop) -> Result<DecodeOutput<'static>> {
+    exr::decode(data, None, &stop)
+}
+
+/// Decode an uncompressed scanline OpenEXR file, with resource limits.
+#[cfg(feature = "exr")]
+pub fn decode_exr_with_limits(
+    data: &[u8],
+    limits: &Limits,
+    stop: impl Stop,
+) -> Result<DecodeOutput<'static>> {
+    exr::decode(data, Some(limits), &limits.deadline(&stop))
+}
+
+// ── Sixel ────────────────────────────────────────────────────────────
+
+/// Encode pixels as a sixel escape sequence for inline preview in
+/// terminals that support it (xterm `-ti vt340`, mlterm, foot, WezTerm,
+/// iTerm2, …).
+///
+/// Colors are reduced to [`SixelEncodeOptions::max_colors`] by median cut,
+/// without dithering, and stored in percent, so this is a preview, not a
+/// lossless format. Pixels with alpha below 128 are not drawn. Accepts
+/// 8-bit gray, RGB and BGR layouts with or without alpha or padding, and
+/// `Gray16` / `GrayA16` / `Rgb16` / `Rgba16` (high byte used).
+///
+/// ```
+/// use zenbitmaps::*;
+///
+/// let pixels = [255, 0, 0, 0, 0, 255];
+/// let options = SixelEncodeOptions::new();
+/// let sixel = encode_sixel(&pixels, 2, 1, PixelLayout::Rgb8, &options, Unstoppable)?;
+/// assert!(sixel.starts_with(b"\x1bP") && sixel.ends_with(b"\x1b\\"));
+/// // Write `sixel` to stdout to see the image in the terminal.
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[cfg(feature = "sixel")]
+pub fn encode_sixel(
     pixels: &[u8],
     width: u32,
     height: u32,
     layout: PixelLayout,
+    options: &SixelEncodeOptions,
     stop: impl Stop,
 ) -> Result<alloc::vec::Vec<u8>> {
-    bmp::encode(pixels, width, height, layout, true, &stop)
+    sixel::encode(pixels, width, height, layout, options, &stop)
 }
 
 // ── Typed pixel API (rgb feature) ────────────────────────────────────
@@ -729,10 +3292,7 @@ pub fn encode_bmp_rgba(
 pub fn decode_pixels<P: DecodePixel>(
     data: &[u8],
     stop: impl Stop,
-) -> Result<(alloc::vec::Vec<P>, u32, u32)>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<(alloc::vec::Vec<P>, u32, u32)> {
     let decoded = decode(data, stop)?;
     decoded_to_pixels(decoded)
 }
@@ -743,10 +3303,7 @@ pub fn decode_pixels_with_limits<P: DecodePixel>(
     data: &[u8],
     limits: &Limits,
     stop: impl Stop,
-) -> Result<(alloc::vec::Vec<P>, u32, u32)>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<(alloc::vec::Vec<P>, u32, u32)> {
     let decoded = decode_with_limits(data, limits, stop)?;
     decoded_to_pixels(decoded)
 }
@@ -756,10 +3313,7 @@ where
 pub fn decode_bmp_pixels<P: DecodePixel>(
     data: &[u8],
     stop: impl Stop,
-) -> Result<(alloc::vec::Vec<P>, u32, u32)>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<(alloc::vec::Vec<P>, u32, u32)> {
     let decoded = decode_bmp(data, stop)?;
     decoded_to_pixels(decoded)
 }
@@ -770,10 +3324,7 @@ pub fn decode_bmp_pixels_with_limits<P: DecodePixel>(
     data: &[u8],
     limits: &Limits,
     stop: impl Stop,
-) -> Result<(alloc::vec::Vec<P>, u32, u32)>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<(alloc::vec::Vec<P>, u32, u32)> {
     let decoded = decode_bmp_with_limits(data, limits, stop)?;
     decoded_to_pixels(decoded)
 }
@@ -781,18 +3332,10 @@ where
 #[cfg(feature = "rgb")]
 fn decoded_to_pixels<P: DecodePixel>(
     decoded: DecodeOutput<'_>,
-) -> Result<(alloc::vec::Vec<P>, u32, u32)>
-where
-    [u8]: rgb::AsPixels<P>,
-{
-    if !decoded.layout.is_memory_compatible(P::layout()) {
-        return Err(at!(BitmapError::LayoutMismatch {
-            expected: P::layout(),
-            actual: decoded.layout,
-        }));
-    }
-    let pixels: &[P] = decoded.pixels().as_pixels();
-    Ok((pixels.to_vec(), decoded.width, decoded.height))
+) -> Result<(alloc::vec::Vec<P>, u32, u32)> {
+    decoded.check_pixel_type::<P>()?;
+    let pixels = pixel_traits::pixels_to_vec(&decoded.packed_pixels());
+    Ok((pixels, decoded.width, decoded.height))
 }
 
 // ── Typed pixel encode (rgb feature) ─────────────────────────────────
@@ -885,10 +3428,7 @@ where
 
 /// Decode any PNM format to an [`imgref::ImgVec`].
 #[cfg(feature = "imgref")]
-pub fn decode_img<P: DecodePixel>(data: &[u8], stop: impl Stop) -> Result<imgref::ImgVec<P>>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+pub fn decode_img<P: DecodePixel>(data: &[u8], stop: impl Stop) -> Result<imgref::ImgVec<P>> {
     let (pixels, w, h) = decode_pixels::<P>(data, stop)?;
     Ok(imgref::ImgVec::new(pixels, w as usize, h as usize))
 }
@@ -899,20 +3439,14 @@ pub fn decode_img_with_limits<P: DecodePixel>(
     data: &[u8],
     limits: &Limits,
     stop: impl Stop,
-) -> Result<imgref::ImgVec<P>>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<imgref::ImgVec<P>> {
     let (pixels, w, h) = decode_pixels_with_limits::<P>(data, limits, stop)?;
     Ok(imgref::ImgVec::new(pixels, w as usize, h as usize))
 }
 
 /// Decode BMP to an [`imgref::ImgVec`].
 #[cfg(all(feature = "bmp", feature = "imgref"))]
-pub fn decode_bmp_img<P: DecodePixel>(data: &[u8], stop: impl Stop) -> Result<imgref::ImgVec<P>>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+pub fn decode_bmp_img<P: DecodePixel>(data: &[u8], stop: impl Stop) -> Result<imgref::ImgVec<P>> {
     let (pixels, w, h) = decode_bmp_pixels::<P>(data, stop)?;
     Ok(imgref::ImgVec::new(pixels, w as usize, h as usize))
 }
@@ -923,10 +3457,7 @@ pub fn decode_bmp_img_with_limits<P: DecodePixel>(
     data: &[u8],
     limits: &Limits,
     stop: impl Stop,
-) -> Result<imgref::ImgVec<P>>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<imgref::ImgVec<P>> {
     let (pixels, w, h) = decode_bmp_pixels_with_limits::<P>(data, limits, stop)?;
     Ok(imgref::ImgVec::new(pixels, w as usize, h as usize))
 }
@@ -940,10 +3471,7 @@ pub fn decode_into<P: DecodePixel>(
     data: &[u8],
     output: imgref::ImgRefMut<'_, P>,
     stop: impl Stop,
-) -> Result<()>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<()> {
     let decoded = decode(data, stop)?;
     copy_decoded_into(decoded, output)
 }
@@ -954,10 +3482,7 @@ pub fn decode_bmp_into<P: DecodePixel>(
     data: &[u8],
     output: imgref::ImgRefMut<'_, P>,
     stop: impl Stop,
-) -> Result<()>
-where
-    [u8]: rgb::AsPixels<P>,
-{
+) -> Result<()> {
     let decoded = decode_bmp(data, stop)?;
     copy_decoded_into(decoded, output)
 }
@@ -966,20 +3491,12 @@ where
 fn copy_decoded_into<P: DecodePixel>(
     decoded: DecodeOutput<'_>,
     mut output: imgref::ImgRefMut<'_, P>,
-) -> Result<()>
-where
-    [u8]: rgb::AsPixels<P>,
-{
-    if !decoded.layout.is_memory_compatible(P::layout()) {
-        return Err(at!(BitmapError::LayoutMismatch {
-            expected: P::layout(),
-            actual: decoded.layout,
-        }));
-    }
+) -> Result<()> {
+    decoded.check_pixel_type::<P>()?;
     let out_w = output.width();
     let out_h = output.height();
     if decoded.width as usize != out_w || decoded.height as usize != out_h {
-        return Err(at!(BitmapError::InvalidData(alloc::format!(
+        return Err(whereat::at!(BitmapError::invalid_data(alloc::format!(
             "dimension mismatch: decoded {}x{}, output buffer {}x{}",
             decoded.width,
             decoded.height,
@@ -987,7 +3504,8 @@ where
             out_h
         ))));
     }
-    let src_pixels: &[P] = decoded.pixels().as_pixels();
+    let packed = decoded.packed_pixels();
+    let src_pixels = pixel_traits::pixels_cow::<P>(&packed);
     for (src_row, dst_row) in src_pixels.chunks_exact(out_w).zip(output.rows_mut()) {
         <[P]>::copy_from_slice(dst_row, src_row);
     }
@@ -1,3 +1,9 @@
+use alloc::sync::Arc;
+use core::time::Duration;
+use enough::{Stop, StopReason};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
 /// Default memory cap when no explicit `max_memory_bytes` is set (1 GiB).
 ///
 /// Prevents OOM from crafted headers declaring enormous dimensions.
@@ -20,19 +26,176 @@ pub const DEFAULT_MAX_PIXELS: u64 = 120_000_000;
 /// ceilings: [`DEFAULT_MAX_MEMORY_BYTES`] (1 GiB output bytes) and
 /// [`DEFAULT_MAX_PIXELS`] (120 MP). Set `max_memory_bytes` / `max_pixels`
 /// explicitly to raise or lower either (use `Some(u64::MAX)` to opt out).
+///
+/// Build with the `with_*` methods, or start from a preset such as
+/// [`Limits::web_safe`]:
+///
+/// ```
+/// use zenbitmaps::Limits;
+///
+/// let limits = Limits::new()
+///     .with_max_dimensions(4096, 4096)
+///     .with_max_pixels(16_000_000)
+///     .with_max_memory(64 * 1024 * 1024);
+/// assert_eq!(limits.max_width, Some(4096));
+/// ```
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Limits {
     pub max_width: Option<u64>,
     pub max_height: Option<u64>,
     /// Maximum pixel count (width * height).
     /// Defaults to [`DEFAULT_MAX_PIXELS`] (120 MP) when `None`.
     pub max_pixels: Option<u64>,
-    /// Maximum memory bytes for output buffer allocation.
+    /// Maximum memory bytes for output buffer allocation. The BMP and ICO
+    /// decoders also count intermediate buffers (scanline scratch, widened
+    /// copies) that are alive alongside the output.
     /// Defaults to [`DEFAULT_MAX_MEMORY_BYTES`] (1 GiB) when `None`.
     pub max_memory_bytes: Option<u64>,
+    /// Maximum input size in bytes, checked by every decode entry point
+    /// before any header parsing or allocation. No cap when `None`.
+    pub max_file_size: Option<u64>,
+    /// Maximum wall-clock time for one decode, checked wherever the decoder
+    /// checks its `Stop` (every 16 rows). Exceeding it fails with
+    /// [`BitmapError::Cancelled`](crate::BitmapError::Cancelled)`(StopReason::TimedOut)`.
+    /// Applies to the one-shot `_with_limits` decoders, not to row, frame or
+    /// stream iterators, which run at the caller's pace. No cap when `None`.
+    /// Enforced only with the `std` feature, which provides the clock; the
+    /// field exists either way so struct literals compile under both.
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub max_duration: Option<Duration>,
+    /// Called with the size of each buffer the PNM, BMP, ICO and farbfeld
+    /// decoders are about to allocate, after the byte caps pass; returning
+    /// `false` fails the decode with
+    /// [`BitmapError::LimitExceeded`](crate::BitmapError::LimitExceeded).
+    /// Lets an embedder charge decodes against a shared memory budget.
+    /// Borrowed (zero-copy) output allocates nothing and is not reported.
+    /// Not serialized; deserialized limits have no hook.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub on_alloc: Option<AllocHook>,
+}
+
+/// The [`Limits::on_alloc`] callback: given a size in bytes, whether the
+/// decoder may allocate it.
+///
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::Arc;
+/// use zenbitmaps::Limits;
+///
+/// // A budget shared by every decode in the process.
+/// let budget = Arc::new(AtomicU64::new(64 * 1024 * 1024));
+/// let limits = Limits::new().with_on_alloc(move |bytes| {
+///     budget
+///         .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| left.checked_sub(bytes))
+///         .is_ok()
+/// });
+/// ```
+#[derive(Clone)]
+pub struct AllocHook(Arc<dyn Fn(u64) -> bool + Send + Sync>);
+
+impl AllocHook {
+    /// Wrap `f`, which returns `false` to veto an allocation.
+    pub fn new(f: impl Fn(u64) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Whether an allocation of `bytes` may go ahead.
+    pub fn allows(&self, bytes: u64) -> bool {
+        (self.0)(bytes)
+    }
+}
+
+impl core::fmt::Debug for AllocHook {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("AllocHook(..)")
+    }
+}
+
+/// Hooks are equal when they share one callback (clones of each other).
+impl PartialEq for AllocHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl Limits {
+    /// No explicit limits: only the default memory and pixel caps apply.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A preset for untrusted uploads: at most 16384 × 16384 pixels and
+    /// 512 MiB of decode buffers, with the default 120 MP pixel cap.
+    /// Refine it with the `with_*` methods.
+    pub fn web_safe() -> Self {
+        Self::new()
+            .with_max_dimensions(16_384, 16_384)
+            .with_max_memory(512 * 1024 * 1024)
+    }
+
+    /// Maximum width and height in pixels.
+    pub fn with_max_dimensions(self, width: u64, height: u64) -> Self {
+        self.with_max_width(width).with_max_height(height)
+    }
+
+    /// Maximum width in pixels.
+    pub fn with_max_width(mut self, width: u64) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Maximum height in pixels.
+    pub fn with_max_height(mut self, height: u64) -> Self {
+        self.max_height = Some(height);
+        self
+    }
+
+    /// Maximum pixel count (width × height), replacing the default
+    /// 120 MP cap.
+    pub fn with_max_pixels(mut self, pixels: u64) -> Self {
+        self.max_pixels = Some(pixels);
+        self
+    }
+
+    /// Maximum bytes of decode buffers, replacing the default 1 GiB cap.
+    pub fn with_max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Maximum input size in bytes.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Maximum wall-clock time for one decode (enforced with `std`).
+    pub fn with_max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Ask `f` before each decoder allocation; see [`Limits::on_alloc`].
+    pub fn with_on_alloc(mut self, f: impl Fn(u64) -> bool + Send + Sync + 'static) -> Self {
+        self.on_alloc = Some(AllocHook::new(f));
+        self
+    }
+
+    /// `stop`, also timing out once [`Limits::max_duration`] has passed
+    /// from now.
+    pub(crate) fn deadline<'s>(&self, stop: &'s dyn Stop) -> Deadline<'s> {
+        Deadline {
+            stop,
+            #[cfg(feature = "std")]
+            until: self
+                .max_duration
+                .and_then(|d| Instant::now().checked_add(d)),
+        }
+    }
+
     /// Check dimensions against limits. Returns Ok(()) or LimitExceeded error.
     ///
     /// Only the `zencodec` codec adapters call this directly; gate it so the
@@ -44,6 +207,49 @@ impl Limits {
     }
 }
 
+/// The [`Stop`] of a decode under [`Limits::max_duration`].
+pub(crate) struct Deadline<'s> {
+    stop: &'s dyn Stop,
+    #[cfg(feature = "std")]
+    until: Option<Instant>,
+}
+
+impl Stop for Deadline<'_> {
+    fn check(&self) -> Result<(), StopReason> {
+        self.stop.check()?;
+        #[cfg(feature = "std")]
+        if let Some(until) = self.until
+            && Instant::now() >= until
+        {
+            return Err(StopReason::TimedOut);
+        }
+        Ok(())
+    }
+
+    fn may_stop(&self) -> bool {
+        #[cfg(feature = "std")]
+        if self.until.is_some() {
+            return true;
+        }
+        self.stop.may_stop()
+    }
+}
+
+/// Reject oversized input before anything else looks at it.
+///
+/// Decoders call this first, ahead of header parsing; unlike the other
+/// checks there is no default cap.
+pub(crate) fn check_file_size(len: usize, limits: Option<&Limits>) -> crate::Result<()> {
+    if let Some(max) = limits.and_then(|l| l.max_file_size)
+        && len as u64 > max
+    {
+        return Err(whereat::at!(crate::BitmapError::LimitExceeded(
+            alloc::format!("input size {len} bytes exceeds file size limit {max}")
+        )));
+    }
+    Ok(())
+}
+
 /// Check header-declared dimensions against limits (user-provided or defaults).
 ///
 /// Every decoder must call this before allocating the output buffer, passing
@@ -98,6 +304,36 @@ pub(crate) fn check_output_size(bytes: usize, limits: Option<&Limits>) -> crate:
     Ok(())
 }
 
+/// Offer a `bytes` allocation to [`Limits::on_alloc`], if set.
+///
+/// Called right before the allocation, after the byte caps have passed.
+pub(crate) fn check_alloc(bytes: usize, limits: Option<&Limits>) -> crate::Result<()> {
+    if let Some(hook) = limits.and_then(|l| l.on_alloc.as_ref())
+        && !hook.allows(bytes as u64)
+    {
+        return Err(whereat::at!(crate::BitmapError::LimitExceeded(
+            alloc::format!("allocation of {bytes} bytes refused by on_alloc")
+        )));
+    }
+    Ok(())
+}
+
+/// Check the peak working memory of a decode — the output buffer plus any
+/// intermediate buffers alive alongside it — against the same byte cap as
+/// [`check_output_size`].
+#[cfg(any(feature = "bmp", feature = "zencodec"))]
+pub(crate) fn check_working_memory(bytes: usize, limits: Option<&Limits>) -> crate::Result<()> {
+    let max = limits
+        .and_then(|l| l.max_memory_bytes)
+        .unwrap_or(DEFAULT_MAX_MEMORY_BYTES);
+    if bytes as u64 > max {
+        return Err(whereat::at!(crate::BitmapError::LimitExceeded(
+            alloc::format!("decode buffers need {bytes} bytes, exceeding memory limit {max}")
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,6 +355,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn builder_sets_fields() {
+        let limits = Limits::new()
+            .with_max_dimensions(640, 480)
+            .with_max_pixels(300_000)
+            .with_max_memory(1 << 20)
+            .with_max_file_size(1 << 16);
+        assert_eq!(
+            limits,
+            Limits {
+                max_width: Some(640),
+                max_height: Some(480),
+                max_pixels: Some(300_000),
+                max_memory_bytes: Some(1 << 20),
+                max_file_size: Some(1 << 16),
+                max_duration: None,
+                on_alloc: None,
+            }
+        );
+        assert_eq!(Limits::new(), Limits::default());
+    }
+
+    #[test]
+    fn web_safe_preset() {
+        let limits = Limits::web_safe();
+        assert!(check_dimensions(16_384, 7_000, Some(&limits)).is_ok());
+        assert!(check_dimensions(16_385, 1, Some(&limits)).is_err());
+        // The default 120 MP cap still applies inside the 16k square.
+        assert!(is_pixel_limit_err(check_dimensions(
+            16_384,
+            16_384,
+            Some(&limits)
+        )));
+        assert!(check_output_size(512 * 1024 * 1024, Some(&limits)).is_ok());
+        assert!(check_output_size(512 * 1024 * 1024 + 1, Some(&limits)).is_err());
+    }
+
     #[test]
     fn default_pixel_cap_rejects_over_120mp_with_no_limits() {
         // No explicit Limits (the untrusted-decode path) must still reject a
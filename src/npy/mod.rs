@@ -0,0 +1,364 @@
+//! NumPy `.npy` array reading and writing (internal).
+//!
+//! Use top-level [`crate::decode_npy`] and [`crate::encode_npy`].
+//!
+//! An `.npy` file is the magic `\x93NUMPY`, a format version, a
+//! little-endian header length (two bytes in version 1, four in 2 and 3),
+//! then a Python dict literal such as `{'descr': '<u2', 'fortran_order':
+//! False, 'shape': (480, 640, 3), }` padded with spaces to a multiple of 64
+//! bytes, then the raw array. Only C-order `uint8`, `uint16` and `float32`
+//! arrays of shape `(H, W)` or `(H, W, C)` are images.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+use crate::raw::{self, Endianness};
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// Element type from the `descr` field.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dtype {
+    U8,
+    U16,
+    F32,
+}
+
+/// The parts of the header dict the decoder uses.
+struct NpyHeader {
+    dtype: Dtype,
+    endianness: Endianness,
+    fortran_order: bool,
+    shape: Vec<u64>,
+}
+
+fn invalid(what: String) -> whereat::At<BitmapError> {
+    at!(BitmapError::invalid_header(what))
+}
+
+/// Cursor over the header dict text.
+struct Literal<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Literal<'a> {
+    fn skip_space(&mut self) {
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `byte` after optional whitespace, if it is next.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_space();
+        let found = self.text.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> crate::Result<()> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "npy header: expected '{}' at byte {}",
+                char::from(byte),
+                self.pos
+            )))
+        }
+    }
+
+    /// A single- or double-quoted string, without escapes.
+    fn string(&mut self) -> crate::Result<&'a [u8]> {
+        self.skip_space();
+        let quote = match self.text.get(self.pos) {
+            Some(&q @ (b'\'' | b'"')) => q,
+            _ => return Err(invalid("npy header: expected a string".into())),
+        };
+        let start = self.pos + 1;
+        let len = self.text[start..]
+            .iter()
+            .position(|&b| b == quote)
+            .ok_or_else(|| invalid("npy header: unterminated string".into()))?;
+        self.pos = start + len + 1;
+        Ok(&self.text[start..start + len])
+    }
+
+    fn boolean(&mut self) -> crate::Result<bool> {
+        self.skip_space();
+        for (word, value) in [(&b"True"[..], true), (b"False", false)] {
+            if self.text[self.pos..].starts_with(word) {
+                self.pos += word.len();
+                return Ok(value);
+            }
+        }
+        Err(invalid("npy header: expected True or False".into()))
+    }
+
+    /// A tuple of non-negative integers: `()`, `(5,)`, `(480, 640, 3)`.
+    fn shape(&mut self) -> crate::Result<Vec<u64>> {
+        self.expect(b'(')?;
+        let mut dims = Vec::new();
+        loop {
+            if self.eat(b')') {
+                return Ok(dims);
+            }
+            let start = self.pos;
+            let mut value = 0u64;
+            while let Some(&d @ b'0'..=b'9') = self.text.get(self.pos) {
+                value = value
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add(u64::from(d - b'0')))
+                    .ok_or_else(|| invalid("npy header: dimension is too large".into()))?;
+                self.pos += 1;
+            }
+            if self.pos == start {
+                return Err(invalid("npy header: expected a dimension".into()));
+            }
+            // Python 2 long suffix.
+            if self.text.get(self.pos) == Some(&b'L') {
+                self.pos += 1;
+            }
+            dims.push(value);
+            if !self.eat(b',') {
+                self.expect(b')')?;
+                return Ok(dims);
+            }
+        }
+    }
+}
+
+fn parse_header(data: &[u8]) -> crate::Result<(NpyHeader, usize)> {
+    if data.len() < 6 {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    if &data[..6] != MAGIC {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    let (len, start) = match data.get(6) {
+        Some(1) => (
+            data.get(8..10)
+                .map(|b| usize::from(u16::from_le_bytes([b[0], b[1]]))),
+            10,
+        ),
+        Some(2 | 3) => (
+            data.get(8..12)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize),
+            12,
+        ),
+        Some(v) => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "npy format version {v} is not supported"
+            ))));
+        }
+        None => return Err(at!(BitmapError::unexpected_eof())),
+    };
+    let len = len.ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let text = data
+        .get(start..)
+        .and_then(|d| d.get(..len))
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+
+    let mut dict = Literal { text, pos: 0 };
+    let (mut descr, mut fortran_order, mut shape) = (None, None, None);
+    dict.expect(b'{')?;
+    while !dict.eat(b'}') {
+        let key = dict.string()?;
+        dict.expect(b':')?;
+        match key {
+            b"descr" => descr = Some(dict.string()?),
+            b"fortran_order" => fortran_order = Some(dict.boolean()?),
+            b"shape" => shape = Some(dict.shape()?),
+            other => {
+                return Err(invalid(format!(
+                    "npy header: unknown key '{}'",
+                    other.escape_ascii()
+                )));
+            }
+        }
+        if !dict.eat(b',') {
+            dict.expect(b'}')?;
+            break;
+        }
+    }
+    let (Some(descr), Some(fortran_order), Some(shape)) = (descr, fortran_order, shape) else {
+        return Err(invalid(
+            "npy header is missing descr, fortran_order or shape".into(),
+        ));
+    };
+
+    let (order, kind) = match descr {
+        [o @ (b'<' | b'>' | b'|' | b'='), rest @ ..] => (*o, rest),
+        rest => (b'|', rest),
+    };
+    let dtype = match kind {
+        b"u1" | b"B" => Dtype::U8,
+        b"u2" | b"H" => Dtype::U16,
+        b"f4" | b"f" => Dtype::F32,
+        _ => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "npy dtype '{}' is not supported; only uint8, uint16 and float32",
+                descr.escape_ascii()
+            ))));
+        }
+    };
+    let endianness = match order {
+        b'<' => Endianness::Little,
+        b'>' => Endianness::Big,
+        _ => Endianness::Native,
+    };
+    let header = NpyHeader {
+        dtype,
+        endianness,
+        fortran_order,
+        shape,
+    };
+    Ok((header, start + len))
+}
+
+/// Decode an `(H, W)` or `(H, W, C)` array.
+///
+/// `uint8` becomes `Gray8` / `Rgb8` / `Rgba8`, `uint16` `Gray16` /
+/// `GrayA16` / `Rgb16` / `Rgba16`, and `float32` `GrayF32` / `RgbF32` /
+/// `RgbaF32`, samples in native byte order. Native-order arrays are
+/// borrowed.
+pub(crate) fn decode<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let (header, start) = parse_header(data)?;
+    if header.fortran_order {
+        return Err(at!(BitmapError::UnsupportedVariant(
+            "npy Fortran-order (column-major) arrays are not supported".into()
+        )));
+    }
+    let (height, width, channels) = match header.shape[..] {
+        [h, w] => (h, w, 1),
+        [h, w, c] => (h, w, c),
+        _ => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "npy array of shape {:?} is not an image; expected (H, W) or (H, W, C)",
+                header.shape
+            ))));
+        }
+    };
+    let layout = match (header.dtype, channels) {
+        (Dtype::U8, 1) => PixelLayout::Gray8,
+        (Dtype::U8, 3) => PixelLayout::Rgb8,
+        (Dtype::U8, 4) => PixelLayout::Rgba8,
+        (Dtype::U16, 1) => PixelLayout::Gray16,
+        (Dtype::U16, 2) => PixelLayout::GrayA16,
+        (Dtype::U16, 3) => PixelLayout::Rgb16,
+        (Dtype::U16, 4) => PixelLayout::Rgba16,
+        (Dtype::F32, 1) => PixelLayout::GrayF32,
+        (Dtype::F32, 3) => PixelLayout::RgbF32,
+        (Dtype::F32, 4) => PixelLayout::RgbaF32,
+        _ => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "npy arrays with {channels} channels of this dtype have no pixel layout"
+            ))));
+        }
+    };
+    let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err(invalid(format!(
+            "npy image of {width}×{height} exceeds u32 dimensions"
+        )));
+    };
+    if width == 0 || height == 0 {
+        return Err(invalid("npy image width or height is zero".into()));
+    }
+    let stride = (width as usize).saturating_mul(layout.bytes_per_pixel());
+    raw::decode(
+        &data[start..],
+        width,
+        height,
+        layout,
+        stride,
+        header.endianness,
+        limits,
+        stop,
+    )
+}
+
+/// Encode pixels as a version 1.0 `.npy` array in native byte order.
+pub(crate) fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    let (kind, channels) = match layout {
+        PixelLayout::Gray8 => ("u1", 1),
+        PixelLayout::Rgb8 => ("u1", 3),
+        PixelLayout::Rgba8 => ("u1", 4),
+        PixelLayout::Gray16 => ("u2", 1),
+        PixelLayout::GrayA16 => ("u2", 2),
+        PixelLayout::Rgb16 => ("u2", 3),
+        PixelLayout::Rgba16 => ("u2", 4),
+        PixelLayout::GrayF32 => ("f4", 1),
+        PixelLayout::RgbF32 => ("f4", 3),
+        PixelLayout::RgbaF32 => ("f4", 4),
+        other => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "cannot encode {other:?} as npy (supported: Gray, Rgb and Rgba in 8, 16 \
+                 and f32 bits, and GrayA16)"
+            ))));
+        }
+    };
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "npy width or height is zero"
+        )));
+    }
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(layout.bytes_per_pixel()))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+
+    let order = match kind {
+        "u1" => '|',
+        _ if cfg!(target_endian = "big") => '>',
+        _ => '<',
+    };
+    let shape = match channels {
+        1 => format!("({height}, {width})"),
+        c => format!("({height}, {width}, {c})"),
+    };
+    let mut dict =
+        format!("{{'descr': '{order}{kind}', 'fortran_order': False, 'shape': {shape}, }}");
+    // Magic, version and length take 10 bytes; pad so the data starts on a
+    // 64-byte boundary, ending the header with a newline.
+    let padded = (10 + dict.len() + 1).next_multiple_of(64) - 10;
+    while dict.len() < padded - 1 {
+        dict.push(' ');
+    }
+    dict.push('\n');
+
+    let mut out = Vec::with_capacity(10 + dict.len() + expected);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    out.extend_from_slice(dict.as_bytes());
+    out.extend_from_slice(&pixels[..expected]);
+    Ok(out)
+}
@@ -0,0 +1,283 @@
+//! ZSoft PCX decoding (internal).
+//!
+//! Use top-level [`crate::decode_pcx`].
+//!
+//! A PCX file is a 128-byte header followed by scanlines. Each scanline
+//! stores its color planes one after another, `bytes_per_line` bytes per
+//! plane, usually RLE-compressed as one stream across the whole image.
+//! 8-bit single-plane images keep their 256-color palette in the last 769
+//! bytes of the file (a `0x0C` marker and 768 bytes of RGB); images with up
+//! to 16 colors use the 16-entry palette in the header.
+
+use alloc::vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+const HEADER_LEN: usize = 128;
+/// `0x0C` marker plus 256 RGB entries.
+const VGA_PALETTE_LEN: usize = 769;
+
+/// Default 16-color EGA palette, for version 3 files (no palette stored).
+const EGA_PALETTE: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0xAA],
+    [0x00, 0xAA, 0x00],
+    [0x00, 0xAA, 0xAA],
+    [0xAA, 0x00, 0x00],
+    [0xAA, 0x00, 0xAA],
+    [0xAA, 0x55, 0x00],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+    [0x55, 0x55, 0xFF],
+    [0x55, 0xFF, 0x55],
+    [0x55, 0xFF, 0xFF],
+    [0xFF, 0x55, 0x55],
+    [0xFF, 0x55, 0xFF],
+    [0xFF, 0xFF, 0x55],
+    [0xFF, 0xFF, 0xFF],
+];
+
+/// Parsed PCX header.
+struct PcxHeader {
+    version: u8,
+    rle: bool,
+    bits_per_pixel: u8,
+    width: u32,
+    height: u32,
+    planes: u8,
+    bytes_per_line: usize,
+}
+
+/// How the planes of a scanline become output pixels.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// One plane, 1 bit: black and white.
+    Mono,
+    /// Palette indices: one plane of 1/2/4/8 bits, or 2-4 one-bit planes.
+    Indexed,
+    /// 8 bits per plane, one plane per channel (3 = RGB, 4 = RGBA).
+    Direct,
+}
+
+fn parse_header(data: &[u8]) -> crate::Result<PcxHeader> {
+    if data.len() < HEADER_LEN {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    if data[0] != 0x0A {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    let version = data[1];
+    if !matches!(version, 0 | 2 | 3 | 4 | 5) {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "PCX version {version} is invalid"
+        ))));
+    }
+    let rle = match data[2] {
+        0 => false,
+        1 => true,
+        other => {
+            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "PCX encoding {other} is not supported"
+            ))));
+        }
+    };
+    let word = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let (x_min, y_min, x_max, y_max) = (word(4), word(6), word(8), word(10));
+    if x_max < x_min || y_max < y_min {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "PCX window ({x_min},{y_min})-({x_max},{y_max}) is inverted"
+        ))));
+    }
+    let header = PcxHeader {
+        version,
+        rle,
+        bits_per_pixel: data[3],
+        width: u32::from(x_max - x_min) + 1,
+        height: u32::from(y_max - y_min) + 1,
+        planes: data[65],
+        bytes_per_line: word(66) as usize,
+    };
+    let min_line = (header.width as usize * header.bits_per_pixel as usize).div_ceil(8);
+    if header.bytes_per_line < min_line {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "PCX bytes_per_line {} is too small for width {}",
+            header.bytes_per_line,
+            header.width
+        ))));
+    }
+    Ok(header)
+}
+
+fn kind(header: &PcxHeader) -> crate::Result<Kind> {
+    match (header.bits_per_pixel, header.planes) {
+        (1, 1) => Ok(Kind::Mono),
+        (1, 2..=4) | (2 | 4 | 8, 1) => Ok(Kind::Indexed),
+        (8, 3 | 4) => Ok(Kind::Direct),
+        (bits, planes) => Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "PCX with {planes} plane(s) of {bits} bits is not supported"
+        )))),
+    }
+}
+
+/// RLE byte stream. Runs may continue across scanline boundaries.
+struct Rle<'a> {
+    data: &'a [u8],
+    pos: usize,
+    run_value: u8,
+    run_left: usize,
+}
+
+impl Rle<'_> {
+    fn fill(&mut self, out: &mut [u8]) -> crate::Result<()> {
+        let mut i = 0;
+        while i < out.len() {
+            if self.run_left > 0 {
+                let n = self.run_left.min(out.len() - i);
+                out[i..i + n].fill(self.run_value);
+                self.run_left -= n;
+                i += n;
+                continue;
+            }
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+            self.pos += 1;
+            if byte & 0xC0 == 0xC0 {
+                self.run_value = *self
+                    .data
+                    .get(self.pos)
+                    .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+                self.pos += 1;
+                self.run_left = (byte & 0x3F) as usize;
+            } else {
+                out[i] = byte;
+                i += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let header = parse_header(data)?;
+    let kind = kind(&header)?;
+    let (width, height) = (header.width, header.height);
+    limits::check_dimensions(width, height, limits)?;
+
+    // 8-bit single-plane version 5 images end with the VGA palette; without
+    // one (some writers omit it for grayscale) the indices are gray levels.
+    let wants_vga = kind == Kind::Indexed && header.bits_per_pixel == 8 && header.version == 5;
+    let vga_palette = data
+        .len()
+        .checked_sub(VGA_PALETTE_LEN)
+        .filter(|&at| wants_vga && at >= HEADER_LEN && data[at] == 0x0C)
+        .map(|at| &data[at + 1..]);
+    let (layout, channels) = match kind {
+        Kind::Mono => (PixelLayout::Gray8, 1),
+        Kind::Indexed if header.bits_per_pixel == 8 && vga_palette.is_none() => {
+            (PixelLayout::Gray8, 1)
+        }
+        Kind::Indexed => (PixelLayout::Rgb8, 3),
+        Kind::Direct if header.planes == 4 => (PixelLayout::Rgba8, 4),
+        Kind::Direct => (PixelLayout::Rgb8, 3),
+    };
+    let mut palette = [[0u8; 3]; 256];
+    if let Some(vga) = vga_palette {
+        for (entry, rgb) in palette.iter_mut().zip(vga.chunks_exact(3)) {
+            entry.copy_from_slice(rgb);
+        }
+    } else if header.version == 3 {
+        palette[..16].copy_from_slice(&EGA_PALETTE);
+    } else {
+        for (entry, rgb) in palette.iter_mut().zip(data[16..64].chunks_exact(3)) {
+            entry.copy_from_slice(rgb);
+        }
+    }
+
+    let w = width as usize;
+    let row_bytes = w
+        .checked_mul(channels)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let out_bytes = row_bytes
+        .checked_mul(height as usize)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    let body_end = vga_palette.map_or(data.len(), |_| data.len() - VGA_PALETTE_LEN);
+    let mut rle = Rle {
+        data: &data[HEADER_LEN..body_end],
+        pos: 0,
+        run_value: 0,
+        run_left: 0,
+    };
+    let bpl = header.bytes_per_line;
+    let mut line = vec![0u8; bpl * header.planes as usize];
+    for (y, row) in out.chunks_exact_mut(row_bytes).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        if header.rle {
+            rle.fill(&mut line)?;
+        } else {
+            let start = y * line.len();
+            let src = rle
+                .data
+                .get(start..start + line.len())
+                .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+            line.copy_from_slice(src);
+        }
+        match kind {
+            Kind::Mono => {
+                for (x, px) in row.iter_mut().enumerate() {
+                    *px = if line[x / 8] & (0x80 >> (x % 8)) != 0 {
+                        255
+                    } else {
+                        0
+                    };
+                }
+            }
+            Kind::Indexed => {
+                let bits = header.bits_per_pixel as usize;
+                for x in 0..w {
+                    let index = if header.planes == 1 {
+                        let bit = x * bits;
+                        let shift = 8 - bits - bit % 8;
+                        (line[bit / 8] >> shift) as usize & ((1 << bits) - 1)
+                    } else {
+                        (0..header.planes as usize).fold(0, |index, plane| {
+                            let byte = line[plane * bpl + x / 8];
+                            index | (((byte >> (7 - x % 8)) & 1) as usize) << plane
+                        })
+                    };
+                    if channels == 1 {
+                        row[x] = index as u8;
+                    } else {
+                        row[x * 3..x * 3 + 3].copy_from_slice(&palette[index]);
+                    }
+                }
+            }
+            Kind::Direct => {
+                for (c, plane) in line.chunks_exact(bpl).enumerate() {
+                    for (x, &v) in plane[..w].iter().enumerate() {
+                        row[x * channels + c] = v;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,385 @@
+//! PGX decoding and encoding (internal).
+//!
+//! Use top-level [`crate::decode_pgx`], [`crate::probe_pgx`] and
+//! [`crate::encode_pgx`].
+//!
+//! PGX is the single-component raw format of the JPEG 2000 conformance
+//! suite: a one-line text header, `PG <ML|LM> [+|-]<depth> <width>
+//! <height>`, then samples of 1 to 16 bits in one byte (depth up to 8) or
+//! two (up to 16), big-endian (`ML`) or little-endian (`LM`), signed
+//! samples in two's complement.
+
+use alloc::format;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+use crate::raw::{self, Endianness};
+
+/// Parsed PGX header.
+///
+/// Returned by [`crate::probe_pgx`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PgxHeader {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Significant bits per sample, 1 to 16.
+    pub bit_depth: u8,
+    /// Samples are signed (`-` in the header).
+    pub signed: bool,
+    /// Samples are big-endian (`ML`) rather than little-endian (`LM`).
+    pub big_endian: bool,
+}
+
+impl PgxHeader {
+    /// Bytes per stored sample: 1 up to 8 bits, else 2.
+    fn sample_bytes(&self) -> usize {
+        if self.bit_depth <= 8 { 1 } else { 2 }
+    }
+}
+
+/// Options for PGX encoding with [`crate::encode_pgx_with`].
+///
+/// ```
+/// use zenbitmaps::PgxEncodeOptions;
+///
+/// let options = PgxEncodeOptions::new().with_bit_depth(12).with_signed(true);
+/// assert_eq!(options.bit_depth(), Some(12));
+/// assert!(options.signed());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgxEncodeOptions {
+    bit_depth: Option<u8>,
+    signed: bool,
+    byte_order: Endianness,
+}
+
+impl Default for PgxEncodeOptions {
+    fn default() -> Self {
+        Self {
+            bit_depth: None,
+            signed: false,
+            byte_order: Endianness::Big,
+        }
+    }
+}
+
+impl PgxEncodeOptions {
+    /// Default options: the layout's full depth (8 or 16), unsigned,
+    /// big-endian (`ML`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bits per sample, 1 to 16. Every input value must fit; a depth above
+    /// 8 is stored in two bytes even for `Gray8` input.
+    pub fn with_bit_depth(mut self, bit_depth: u8) -> Self {
+        self.bit_depth = Some(bit_depth);
+        self
+    }
+
+    /// Write signed samples. Input values are level-shifted down by
+    /// `2^(depth - 1)`, the inverse of what [`crate::decode_pgx`] does.
+    pub fn with_signed(mut self, signed: bool) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    /// Sample byte order: [`Endianness::Big`] (`ML`, the default),
+    /// [`Endianness::Little`] (`LM`), or [`Endianness::Native`].
+    pub fn with_byte_order(mut self, byte_order: Endianness) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Bit depth, or `None` for the layout's own.
+    pub fn bit_depth(&self) -> Option<u8> {
+        self.bit_depth
+    }
+
+    /// Whether samples are written signed.
+    pub fn signed(&self) -> bool {
+        self.signed
+    }
+
+    /// Sample byte order.
+    pub fn byte_order(&self) -> Endianness {
+        self.byte_order
+    }
+}
+
+/// Parse the header line; returns it with the offset of the first sample.
+pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(PgxHeader, usize)> {
+    if data.len() < 2 {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    if &data[..2] != b"PG" {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    let mut pos = 2;
+    let skip_blanks = |pos: &mut usize| {
+        while matches!(data.get(*pos), Some(b' ' | b'\t')) {
+            *pos += 1;
+        }
+    };
+    let number = |pos: &mut usize, what: &str| -> crate::Result<u32> {
+        let start = *pos;
+        let mut value = 0u32;
+        while let Some(&d @ b'0'..=b'9') = data.get(*pos) {
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(u32::from(d - b'0')))
+                .ok_or_else(|| {
+                    at!(BitmapError::invalid_header(format!(
+                        "PGX {what} is too large"
+                    )))
+                })?;
+            *pos += 1;
+        }
+        if *pos == start {
+            return Err(if *pos >= data.len() {
+                at!(BitmapError::unexpected_eof())
+            } else {
+                at!(BitmapError::invalid_header(format!(
+                    "PGX {what} is missing"
+                )))
+            });
+        }
+        Ok(value)
+    };
+
+    skip_blanks(&mut pos);
+    let big_endian = match data.get(pos..pos + 2) {
+        Some(b"ML") => true,
+        Some(b"LM") => false,
+        Some(_) => {
+            return Err(at!(BitmapError::invalid_header(
+                "PGX byte order must be ML or LM"
+            )));
+        }
+        None => return Err(at!(BitmapError::unexpected_eof())),
+    };
+    pos += 2;
+    skip_blanks(&mut pos);
+    let signed = data.get(pos) == Some(&b'-');
+    if matches!(data.get(pos), Some(b'+' | b'-')) {
+        pos += 1;
+        skip_blanks(&mut pos);
+    }
+    let depth = number(&mut pos, "bit depth")?;
+    skip_blanks(&mut pos);
+    let width = number(&mut pos, "width")?;
+    skip_blanks(&mut pos);
+    let height = number(&mut pos, "height")?;
+    skip_blanks(&mut pos);
+    if data.get(pos) == Some(&b'\r') {
+        pos += 1;
+    }
+    match data.get(pos) {
+        Some(b'\n') => pos += 1,
+        Some(_) => {
+            return Err(at!(BitmapError::invalid_header(
+                "PGX header line does not end after the height"
+            )));
+        }
+        None => return Err(at!(BitmapError::unexpected_eof())),
+    }
+
+    if depth == 0 {
+        return Err(at!(BitmapError::invalid_header("PGX bit depth is zero")));
+    }
+    if depth > 16 {
+        return Err(at!(BitmapError::UnsupportedVariant(format!(
+            "PGX {depth}-bit samples are not supported; at most 16"
+        ))));
+    }
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "PGX width or height is zero"
+        )));
+    }
+    let header = PgxHeader {
+        width,
+        height,
+        bit_depth: depth as u8,
+        signed,
+        big_endian,
+    };
+    Ok((header, pos))
+}
+
+/// Decode to `Gray8` (depth up to 8) or native-endian `Gray16`.
+///
+/// Unsigned samples are taken as stored, borrowing the input when no byte
+/// swap is needed. Signed samples are shifted up by `2^(depth - 1)` and
+/// clamped to `0..2^depth`.
+pub(crate) fn decode<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let (header, start) = parse_header(data)?;
+    let PgxHeader { width, height, .. } = header;
+    let layout = match header.sample_bytes() {
+        1 => PixelLayout::Gray8,
+        _ => PixelLayout::Gray16,
+    };
+    let endianness = if header.big_endian {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    };
+    if !header.signed {
+        let stride = (width as usize).saturating_mul(header.sample_bytes());
+        return raw::decode(
+            &data[start..],
+            width,
+            height,
+            layout,
+            stride,
+            endianness,
+            limits,
+            stop,
+        );
+    }
+
+    limits::check_dimensions(width, height, limits)?;
+    let samples = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let bytes = header.sample_bytes();
+    let needed = samples
+        .checked_mul(bytes)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let src = data
+        .get(start..)
+        .and_then(|d| d.get(..needed))
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    limits::check_output_size(needed, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, needed)?;
+
+    let offset = 1i32 << (header.bit_depth - 1);
+    let max = (1i32 << header.bit_depth) - 1;
+    let row = width as usize * bytes;
+    for (y, (dst, src)) in out
+        .chunks_exact_mut(row)
+        .zip(src.chunks_exact(row))
+        .enumerate()
+    {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (d, s) in dst.chunks_exact_mut(bytes).zip(src.chunks_exact(bytes)) {
+            let value = match *s {
+                [b] => i32::from(b as i8),
+                [a, b] if header.big_endian => i32::from(i16::from_be_bytes([a, b])),
+                [a, b] => i32::from(i16::from_le_bytes([a, b])),
+                _ => 0,
+            };
+            let shifted = (value + offset).clamp(0, max);
+            match d {
+                [d] => *d = shifted as u8,
+                _ => d.copy_from_slice(&(shifted as u16).to_ne_bytes()),
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
+
+/// Encode `Gray8` or `Gray16` pixels as PGX.
+pub(crate) fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &PgxEncodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    let layout_depth = match layout {
+        PixelLayout::Gray8 => 8,
+        PixelLayout::Gray16 => 16,
+        other => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "cannot encode {other:?} as PGX (supported: Gray8, Gray16)"
+            ))));
+        }
+    };
+    let depth = options.bit_depth.unwrap_or(layout_depth);
+    if !(1..=16).contains(&depth) {
+        return Err(at!(BitmapError::UnsupportedVariant(format!(
+            "PGX bit depth {depth} is not 1 to 16"
+        ))));
+    }
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "PGX width or height is zero"
+        )));
+    }
+    let (w, h) = (width as usize, height as usize);
+    let bpp = layout.bytes_per_pixel();
+    let expected = w
+        .checked_mul(h)
+        .and_then(|n| n.checked_mul(bpp))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+
+    let big = match options.byte_order {
+        Endianness::Big => true,
+        Endianness::Little => false,
+        Endianness::Native => cfg!(target_endian = "big"),
+    };
+    let header = format!(
+        "PG {} {} {depth} {width} {height}\n",
+        if big { "ML" } else { "LM" },
+        if options.signed { '-' } else { '+' },
+    );
+    let out_bytes = if depth <= 8 { 1 } else { 2 };
+    let mut out = Vec::with_capacity(header.len() + w * h * out_bytes);
+    out.extend_from_slice(header.as_bytes());
+
+    let max = (1u32 << depth) - 1;
+    let offset = if options.signed {
+        1i32 << (depth - 1)
+    } else {
+        0
+    };
+    for (y, row) in pixels[..expected].chunks_exact(w * bpp).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for s in row.chunks_exact(bpp) {
+            let value = match *s {
+                [v] => u32::from(v),
+                [a, b] => u32::from(u16::from_ne_bytes([a, b])),
+                _ => 0,
+            };
+            if value > max {
+                return Err(at!(BitmapError::invalid_data(format!(
+                    "sample {value} does not fit in {depth} bits"
+                ))));
+            }
+            // Two's complement in the stored width; unsigned is unchanged.
+            let stored = (value as i32 - offset) as u16;
+            match (out_bytes, big) {
+                (1, _) => out.push(stored as u8),
+                (_, true) => out.extend_from_slice(&stored.to_be_bytes()),
+                (_, false) => out.extend_from_slice(&stored.to_le_bytes()),
+            }
+        }
+    }
+    Ok(out)
+}
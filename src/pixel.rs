@@ -1,6 +1,7 @@
 /// Image format detected from magic bytes.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImageFormat {
     /// PNM family: PGM (P5), PPM (P6), PAM (P7), PFM (Pf/PF).
     Pnm,
@@ -16,9 +17,109 @@ pub enum ImageFormat {
     Hdr,
 }
 
+impl ImageFormat {
+    /// Whether [`decode`](crate::decode) handles this format in this build;
+    /// BMP, QOI, TGA and HDR need their cargo features.
+    pub fn is_decodable(self) -> bool {
+        match self {
+            Self::Pnm | Self::Farbfeld => true,
+            Self::Bmp => cfg!(feature = "bmp"),
+            Self::Qoi => cfg!(feature = "qoi"),
+            Self::Tga => cfg!(feature = "tga"),
+            Self::Hdr => cfg!(feature = "hdr"),
+        }
+    }
+}
+
+/// A format recognized by its magic bytes that [`decode`](crate::decode)
+/// does not handle, as reported by [`identify`](crate::identify).
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ForeignFormat {
+    /// PNG.
+    Png,
+    /// JPEG (JFIF, Exif or raw SOI marker).
+    Jpeg,
+    /// GIF (87a or 89a).
+    Gif,
+    /// WebP (RIFF container).
+    WebP,
+    /// TIFF, either byte order, classic or BigTIFF. The `tiff` feature's
+    /// [`decode_tiff`](crate::decode_tiff) reads baseline uncompressed files.
+    Tiff,
+    /// JPEG XL, bare codestream or ISOBMFF container.
+    JpegXl,
+    /// AVIF (ISOBMFF `ftyp` brand `avif` or `avis`).
+    Avif,
+    /// JPEG 2000, JP2 container or bare codestream.
+    Jpeg2000,
+}
+
+impl ForeignFormat {
+    /// Short display name, for "this is a PNG" style messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::Gif => "GIF",
+            Self::WebP => "WebP",
+            Self::Tiff => "TIFF",
+            Self::JpegXl => "JPEG XL",
+            Self::Avif => "AVIF",
+            Self::Jpeg2000 => "JPEG 2000",
+        }
+    }
+}
+
+/// Result of [`identify`](crate::identify).
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Identified {
+    /// A format [`detect_format`](crate::detect_format) reports. It may
+    /// still need a cargo feature; see [`ImageFormat::is_decodable`].
+    Supported(ImageFormat),
+    /// A format this crate does not decode through
+    /// [`decode`](crate::decode); hand it to another decoder.
+    Foreign(ForeignFormat),
+}
+
+impl Identified {
+    /// Whether [`decode`](crate::decode) handles the data in this build.
+    pub fn is_decodable(self) -> bool {
+        match self {
+            Self::Supported(format) => format.is_decodable(),
+            Self::Foreign(_) => false,
+        }
+    }
+}
+
+/// Output of one of the flat encoders, for [`encode`](crate::encode) and
+/// [`estimate_encoded_size`](crate::estimate_encoded_size).
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum EncodeFormat {
+    /// PGM (P5), as written by [`encode_pgm`](crate::encode_pgm).
+    Pgm,
+    /// PPM (P6), as written by [`encode_ppm`](crate::encode_ppm).
+    Ppm,
+    /// PAM (P7), as written by [`encode_pam`](crate::encode_pam).
+    Pam,
+    /// PFM, as written by [`encode_pfm`](crate::encode_pfm).
+    Pfm,
+    /// Farbfeld, as written by [`encode_farbfeld`](crate::encode_farbfeld).
+    Farbfeld,
+    /// BMP, as written by `encode_bmp_with`. Estimates assume default
+    /// options; see `estimate_bmp_size` for others.
+    #[cfg(feature = "bmp")]
+    Bmp,
+}
+
 /// Pixel memory layout.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PixelLayout {
     /// Single channel, 8-bit grayscale.
     Gray8,
@@ -34,25 +135,73 @@ pub enum PixelLayout {
     Bgra8,
     /// 4 channels, 8-bit BGRX (opaque; 4th byte is padding, not alpha).
     Bgrx8,
+    /// 4 channels, 8-bit RGBX (opaque; 4th byte is padding, not alpha).
+    Rgbx8,
     /// Single channel, 32-bit float grayscale.
     GrayF32,
     /// 3 channels, 32-bit float RGB.
     RgbF32,
+    /// 4 channels, 32-bit float RGBA (straight alpha).
+    RgbaF32,
     /// 4 channels, 16-bit RGBA (native endian).
     Rgba16,
+    /// 4 channels, 16-bit RGBA (big endian, as stored in farbfeld).
+    Rgba16Be,
+    /// 3 channels, 16-bit RGB (native endian).
+    Rgb16,
+    /// 2 channels, 16-bit grayscale + alpha (native endian).
+    GrayA16,
+    /// 3 channels, 8-bit, planar: the whole R plane, then G, then B, each
+    /// `width × height` bytes with no row padding.
+    RgbPlanar8,
+    /// 4 channels, 8-bit, planar: R, G, B, then A planes.
+    RgbaPlanar8,
+    /// 3 channels, 32-bit float, planar: R, G, then B planes (the `CHW`
+    /// order of ML tensors).
+    RgbPlanarF32,
 }
 
 impl PixelLayout {
+    /// Every layout, in declaration order. Their indices are the layout
+    /// codes of the C and JavaScript bindings.
+    #[cfg_attr(not(any(feature = "capi", feature = "wasm")), allow(dead_code))]
+    pub(crate) const ALL: [PixelLayout; 18] = [
+        Self::Gray8,
+        Self::Gray16,
+        Self::Rgb8,
+        Self::Rgba8,
+        Self::Bgr8,
+        Self::Bgra8,
+        Self::Bgrx8,
+        Self::Rgbx8,
+        Self::GrayF32,
+        Self::RgbF32,
+        Self::RgbaF32,
+        Self::Rgba16,
+        Self::Rgba16Be,
+        Self::Rgb16,
+        Self::GrayA16,
+        Self::RgbPlanar8,
+        Self::RgbaPlanar8,
+        Self::RgbPlanarF32,
+    ];
+
     /// Bytes per pixel for this layout.
     pub fn bytes_per_pixel(&self) -> usize {
         match self {
             Self::Gray8 => 1,
             Self::Gray16 => 2,
+            Self::GrayA16 => 4,
+            Self::Rgb16 => 6,
             Self::Rgb8 | Self::Bgr8 => 3,
-            Self::Rgba8 | Self::Bgra8 | Self::Bgrx8 => 4,
+            Self::Rgba8 | Self::Bgra8 | Self::Bgrx8 | Self::Rgbx8 => 4,
             Self::GrayF32 => 4,
             Self::RgbF32 => 12,
-            Self::Rgba16 => 8,
+            Self::RgbaF32 => 16,
+            Self::Rgba16 | Self::Rgba16Be => 8,
+            Self::RgbPlanar8 => 3,
+            Self::RgbaPlanar8 => 4,
+            Self::RgbPlanarF32 => 12,
         }
     }
 
@@ -60,21 +209,86 @@ impl PixelLayout {
     pub fn channels(&self) -> usize {
         match self {
             Self::Gray8 | Self::Gray16 | Self::GrayF32 => 1,
-            Self::Rgb8 | Self::Bgr8 | Self::RgbF32 => 3,
-            Self::Rgba8 | Self::Bgra8 | Self::Bgrx8 | Self::Rgba16 => 4,
+            Self::GrayA16 => 2,
+            Self::Rgb8
+            | Self::Bgr8
+            | Self::Rgb16
+            | Self::RgbF32
+            | Self::RgbPlanar8
+            | Self::RgbPlanarF32 => 3,
+            Self::Rgba8
+            | Self::Bgra8
+            | Self::Bgrx8
+            | Self::Rgbx8
+            | Self::Rgba16
+            | Self::Rgba16Be
+            | Self::RgbaF32
+            | Self::RgbaPlanar8 => 4,
+        }
+    }
+
+    /// Whether the last channel is alpha. The padding byte of `Bgrx8` and
+    /// `Rgbx8` is not.
+    pub fn has_alpha(&self) -> bool {
+        matches!(
+            self,
+            Self::Rgba8
+                | Self::Bgra8
+                | Self::GrayA16
+                | Self::Rgba16
+                | Self::Rgba16Be
+                | Self::RgbaF32
+                | Self::RgbaPlanar8
+        )
+    }
+
+    /// Whether samples are stored as one whole-image plane per channel
+    /// rather than interleaved per pixel.
+    pub fn is_planar(&self) -> bool {
+        matches!(
+            self,
+            Self::RgbPlanar8 | Self::RgbaPlanar8 | Self::RgbPlanarF32
+        )
+    }
+
+    /// The interleaved layout with the same channels and samples:
+    /// `Rgb8` for `RgbPlanar8`, and so on. Interleaved layouts return
+    /// themselves.
+    pub fn interleaved(&self) -> PixelLayout {
+        match self {
+            Self::RgbPlanar8 => Self::Rgb8,
+            Self::RgbaPlanar8 => Self::Rgba8,
+            Self::RgbPlanarF32 => Self::RgbF32,
+            other => *other,
+        }
+    }
+
+    /// The planar layout with the same channels and samples, if there is
+    /// one: `RgbPlanar8` for `Rgb8`, and so on. Planar layouts return
+    /// themselves.
+    pub fn planar(&self) -> Option<PixelLayout> {
+        match self {
+            Self::Rgb8 | Self::RgbPlanar8 => Some(Self::RgbPlanar8),
+            Self::Rgba8 | Self::RgbaPlanar8 => Some(Self::RgbaPlanar8),
+            Self::RgbF32 | Self::RgbPlanarF32 => Some(Self::RgbPlanarF32),
+            _ => None,
         }
     }
 
     /// Whether this layout has the same memory representation as `other`.
     ///
-    /// For example, `Bgra8` and `Bgrx8` are compatible (same 4-byte B,G,R,X/A layout).
+    /// For example, `Bgra8` and `Bgrx8` are compatible (same 4-byte B,G,R,X/A
+    /// layout), as are `Rgba8` and `Rgbx8`.
     pub fn is_memory_compatible(&self, other: PixelLayout) -> bool {
         if *self == other {
             return true;
         }
         matches!(
             (*self, other),
-            (Self::Bgra8, Self::Bgrx8) | (Self::Bgrx8, Self::Bgra8)
+            (Self::Bgra8, Self::Bgrx8)
+                | (Self::Bgrx8, Self::Bgra8)
+                | (Self::Rgba8, Self::Rgbx8)
+                | (Self::Rgbx8, Self::Rgba8)
         )
     }
 }
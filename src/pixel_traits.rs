@@ -1,13 +1,22 @@
 //! Sealed traits mapping `rgb` crate pixel types to [`PixelLayout`].
 
+#[cfg(feature = "imgref")]
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use rgb::bytemuck;
+
 use crate::PixelLayout;
 
 mod private {
     pub trait Sealed {}
 }
 
-/// Pixel type that can be decoded from PNM/BMP data.
-pub trait DecodePixel: Copy + 'static + private::Sealed {
+/// Pixel type that can be decoded from PNM/BMP/farbfeld data.
+///
+/// 16-bit pixel types view native-endian output ([`PixelLayout::Rgba16`]
+/// and friends); decode functions copy when the byte buffer is not aligned
+/// for `u16`.
+pub trait DecodePixel: bytemuck::Pod + private::Sealed {
     /// The [`PixelLayout`] this pixel type corresponds to.
     fn layout() -> PixelLayout;
 }
@@ -34,7 +43,50 @@ macro_rules! impl_pixel {
     };
 }
 
+macro_rules! impl_decode_pixel {
+    ($ty:ty, $layout:expr) => {
+        impl private::Sealed for $ty {}
+        impl DecodePixel for $ty {
+            fn layout() -> PixelLayout {
+                $layout
+            }
+        }
+    };
+}
+
 impl_pixel!(rgb::RGB<u8>, PixelLayout::Rgb8);
 impl_pixel!(rgb::RGBA<u8>, PixelLayout::Rgba8);
 impl_pixel!(rgb::alt::BGR<u8>, PixelLayout::Bgr8);
 impl_pixel!(rgb::alt::BGRA<u8>, PixelLayout::Bgra8);
+impl_decode_pixel!(rgb::RGBA<u16>, PixelLayout::Rgba16);
+impl_decode_pixel!(rgb::RGB<u16>, PixelLayout::Rgb16);
+impl_decode_pixel!(rgb::Gray<u16>, PixelLayout::Gray16);
+
+/// View `bytes` as pixels, or `None` if the buffer is misaligned for `P`.
+pub(crate) fn cast_pixels<P: DecodePixel>(bytes: &[u8]) -> Option<&[P]> {
+    bytemuck::try_cast_slice(bytes).ok()
+}
+
+/// View `bytes` as pixels, copying only when the buffer is misaligned.
+#[cfg(feature = "imgref")]
+pub(crate) fn pixels_cow<P: DecodePixel>(bytes: &[u8]) -> Cow<'_, [P]> {
+    match cast_pixels(bytes) {
+        Some(pixels) => Cow::Borrowed(pixels),
+        None => Cow::Owned(copy_unaligned(bytes)),
+    }
+}
+
+/// Copy `bytes` into a new pixel vector.
+pub(crate) fn pixels_to_vec<P: DecodePixel>(bytes: &[u8]) -> Vec<P> {
+    match cast_pixels(bytes) {
+        Some(pixels) => pixels.to_vec(),
+        None => copy_unaligned(bytes),
+    }
+}
+
+fn copy_unaligned<P: DecodePixel>(bytes: &[u8]) -> Vec<P> {
+    let mut pixels = alloc::vec![P::zeroed(); bytes.len() / core::mem::size_of::<P>()];
+    let dst: &mut [u8] = bytemuck::cast_slice_mut(&mut pixels);
+    dst.copy_from_slice(&bytes[..dst.len()]);
+    pixels
+}
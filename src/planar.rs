@@ -0,0 +1,208 @@
+//! Planar ↔ interleaved sample reordering (internal).
+//!
+//! Use top-level [`crate::interleave_planes`], [`crate::split_planes`],
+//! [`crate::split_channels`] and [`crate::merge_channels`].
+//!
+//! A planar image stores each channel as one `width × height` plane with
+//! no row padding, planes in channel order. Sample bytes are moved as they
+//! are, so byte order and values never change. The channel functions work
+//! on any layout and keep each plane in its own buffer.
+
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+
+/// Check the buffer and return (pixel count, plane bytes, sample bytes).
+fn geometry(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+) -> crate::Result<(usize, usize, usize)> {
+    let (count, plane, sample) = plane_size(width, height, layout)?;
+    let expected = plane
+        .checked_mul(layout.channels())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+    Ok((count, plane, sample))
+}
+
+/// (pixel count, plane bytes, sample bytes) of a `width × height` image.
+fn plane_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+) -> crate::Result<(usize, usize, usize)> {
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "planar width or height is zero"
+        )));
+    }
+    let count = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let sample = layout.bytes_per_pixel() / layout.channels();
+    let plane = count
+        .checked_mul(sample)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    Ok((count, plane, sample))
+}
+
+/// Planar `layout` → its [`PixelLayout::interleaved`] counterpart.
+pub(crate) fn interleave(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    if !layout.is_planar() {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "{layout:?} is not planar (supported: RgbPlanar8, RgbaPlanar8, RgbPlanarF32)"
+        ))));
+    }
+    let (count, plane, sample) = geometry(pixels, width, height, layout)?;
+    let bpp = layout.bytes_per_pixel();
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, count * bpp)?;
+    let w = width as usize;
+    for (y, row) in out.chunks_exact_mut(w * bpp).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in row.chunks_exact_mut(bpp).enumerate() {
+            let offset = (y * w + x) * sample;
+            for (c, dst) in px.chunks_exact_mut(sample).enumerate() {
+                let src = c * plane + offset;
+                dst.copy_from_slice(&pixels[src..src + sample]);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Interleaved `layout` → its [`PixelLayout::planar`] counterpart.
+pub(crate) fn split(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    if layout.is_planar() || layout.planar().is_none() {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "{layout:?} has no planar counterpart (supported: Rgb8, Rgba8, RgbF32)"
+        ))));
+    }
+    let (count, plane, sample) = geometry(pixels, width, height, layout)?;
+    let bpp = layout.bytes_per_pixel();
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, count * bpp)?;
+    let w = width as usize;
+    for (y, row) in pixels[..count * bpp].chunks_exact(w * bpp).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in row.chunks_exact(bpp).enumerate() {
+            let offset = (y * w + x) * sample;
+            for (c, src) in px.chunks_exact(sample).enumerate() {
+                let dst = c * plane + offset;
+                out[dst..dst + sample].copy_from_slice(src);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Any `layout` → one buffer per channel, in the layout's channel order.
+pub(crate) fn split_channels(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<Vec<u8>>> {
+    let (count, plane, sample) = geometry(pixels, width, height, layout)?;
+    let mut planes = Vec::with_capacity(layout.channels());
+    if layout.is_planar() {
+        for src in pixels.chunks_exact(plane).take(layout.channels()) {
+            let mut dst = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, plane)?;
+            dst.copy_from_slice(src);
+            planes.push(dst);
+        }
+        return Ok(planes);
+    }
+    for _ in 0..layout.channels() {
+        planes.push(alloc_util::alloc_zeroed(
+            AllocPref::CodecDefault,
+            true,
+            plane,
+        )?);
+    }
+    let bpp = layout.bytes_per_pixel();
+    let w = width as usize;
+    for (y, row) in pixels[..count * bpp].chunks_exact(w * bpp).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in row.chunks_exact(bpp).enumerate() {
+            let offset = (y * w + x) * sample;
+            for (dst, src) in planes.iter_mut().zip(px.chunks_exact(sample)) {
+                dst[offset..offset + sample].copy_from_slice(src);
+            }
+        }
+    }
+    Ok(planes)
+}
+
+/// One buffer per channel → `layout`, the inverse of [`split_channels`].
+pub(crate) fn merge_channels(
+    planes: &[&[u8]],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    if planes.len() != layout.channels() {
+        return Err(at!(BitmapError::invalid_data(alloc::format!(
+            "{layout:?} has {} channels, got {} planes",
+            layout.channels(),
+            planes.len()
+        ))));
+    }
+    let (count, plane, sample) = plane_size(width, height, layout)?;
+    if let Some(short) = planes.iter().find(|p| p.len() < plane) {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: plane,
+            actual: short.len(),
+        }));
+    }
+    let bpp = layout.bytes_per_pixel();
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, count * bpp)?;
+    if layout.is_planar() {
+        for (dst, src) in out.chunks_exact_mut(plane).zip(planes) {
+            dst.copy_from_slice(&src[..plane]);
+        }
+        return Ok(out);
+    }
+    let w = width as usize;
+    for (y, row) in out.chunks_exact_mut(w * bpp).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, px) in row.chunks_exact_mut(bpp).enumerate() {
+            let offset = (y * w + x) * sample;
+            for (dst, src) in px.chunks_exact_mut(sample).zip(planes) {
+                dst.copy_from_slice(&src[offset..offset + sample]);
+            }
+        }
+    }
+    Ok(out)
+}
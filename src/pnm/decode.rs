@@ -9,7 +9,7 @@
 
 use super::PnmHeader;
 use crate::alloc_util::{self, AllocPref};
-use crate::error::BitmapError;
+use crate::error::{BitmapError, ResultExt};
 use crate::pixel::PixelLayout;
 use crate::pnm::PnmFormat;
 use alloc::string::String;
@@ -19,44 +19,44 @@ use enough::Stop;
 /// Parse header from raw data.
 pub(crate) fn parse_header(data: &[u8]) -> crate::Result<PnmHeader> {
     if data.len() < 3 {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(BitmapError::unexpected_eof()));
     }
 
     match &data[..2] {
         b"P5" => parse_p5_p6_header(data, PnmFormat::Pgm),
         b"P6" => parse_p5_p6_header(data, PnmFormat::Ppm),
-        b"P7" => parse_p7_header(data),
-        b"Pf" | b"PF" => parse_pfm_header(data),
+        b"P7" => parse_p7_header(data).in_stage("PAM header"),
+        b"Pf" | b"PF" => parse_pfm_header(data).in_stage("PFM header"),
         b"P1" | b"P4" => parse_pbm_header(data),
         b"P2" => parse_p5_p6_header(data, PnmFormat::Pgm),
         b"P3" => parse_p5_p6_header(data, PnmFormat::Ppm),
         _ => Err(whereat::at!(BitmapError::UnrecognizedFormat)),
     }
+    .in_stage("PNM header")
 }
 
 fn parse_p5_p6_header(data: &[u8], format: PnmFormat) -> crate::Result<PnmHeader> {
-    let mut pos = 2;
-
-    pos = skip_whitespace_and_comments(data, pos)?;
-    let (width, new_pos) = parse_u32(data, pos)?;
-    pos = skip_whitespace_and_comments(data, new_pos)?;
-    let (height, new_pos) = parse_u32(data, pos)?;
-    pos = skip_whitespace_and_comments(data, new_pos)?;
-    let (maxval, new_pos) = parse_u32(data, pos)?;
+    let (width, width_at, pos) = header_u32(data, 2, "PNM width token")?;
+    let (height, _, pos) = header_u32(data, pos, "PNM height token")?;
+    let (maxval, maxval_at, new_pos) = header_u32(data, pos, "PNM maxval token")?;
 
     if width == 0 || height == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "width and height must be non-zero".into(),
-        )));
+        return Err(whereat::at!(
+            BitmapError::invalid_header("width and height must be non-zero").at_offset(width_at)
+        ));
     }
     if maxval == 0 || maxval > 65535 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(alloc::format!(
-            "maxval must be 1-65535, got {maxval}"
-        ))));
+        return Err(whereat::at!(
+            BitmapError::invalid_header(alloc::format!("maxval must be 1-65535, got {maxval}"))
+                .at_offset(maxval_at)
+                .in_stage("PNM maxval token")
+        ));
     }
 
     if new_pos >= data.len() {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(
+            BitmapError::unexpected_eof().at_offset(new_pos)
+        ));
     }
     let data_offset = new_pos + 1;
 
@@ -85,28 +85,27 @@ fn parse_p5_p6_header(data: &[u8], format: PnmFormat) -> crate::Result<PnmHeader
         layout,
         pfm_scale: 0.0,
         data_offset,
+        rescale_16bit: false,
     })
 }
 
 /// Parse P1/P4 (PBM) header. PBM has width and height but no maxval.
 fn parse_pbm_header(data: &[u8]) -> crate::Result<PnmHeader> {
-    let mut pos = 2;
-
-    pos = skip_whitespace_and_comments(data, pos)?;
-    let (width, new_pos) = parse_u32(data, pos)?;
-    pos = skip_whitespace_and_comments(data, new_pos)?;
-    let (height, new_pos) = parse_u32(data, pos)?;
+    let (width, width_at, pos) = header_u32(data, 2, "PNM width token")?;
+    let (height, _, new_pos) = header_u32(data, pos, "PNM height token")?;
 
     if width == 0 || height == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "width and height must be non-zero".into(),
-        )));
+        return Err(whereat::at!(
+            BitmapError::invalid_header("width and height must be non-zero").at_offset(width_at)
+        ));
     }
 
     // P1: single whitespace separates header from ASCII data
     // P4: single whitespace byte separates header from binary data
     if new_pos >= data.len() {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(
+            BitmapError::unexpected_eof().at_offset(new_pos)
+        ));
     }
     let data_offset = new_pos + 1;
 
@@ -119,6 +118,7 @@ fn parse_pbm_header(data: &[u8]) -> crate::Result<PnmHeader> {
         layout: PixelLayout::Gray8,
         pfm_scale: 0.0,
         data_offset,
+        rescale_16bit: false,
     })
 }
 
@@ -139,7 +139,9 @@ fn parse_p7_header(data: &[u8]) -> crate::Result<PnmHeader> {
             .map(|i| pos + i)
             .unwrap_or(data.len());
         let line = core::str::from_utf8(&data[pos..line_end])
-            .map_err(|_| whereat::at!(BitmapError::InvalidHeader("non-UTF8 in PAM header".into())))?
+            .map_err(|_| {
+                whereat::at!(BitmapError::invalid_header("non-UTF8 in PAM header").at_offset(pos))
+            })?
             .trim();
 
         if line == "ENDHDR" {
@@ -148,29 +150,21 @@ fn parse_p7_header(data: &[u8]) -> crate::Result<PnmHeader> {
         }
 
         if let Some(rest) = line.strip_prefix("WIDTH ") {
-            width = Some(
-                rest.trim()
-                    .parse()
-                    .map_err(|_| whereat::at!(BitmapError::InvalidHeader("bad WIDTH".into())))?,
-            );
+            width = Some(rest.trim().parse().map_err(|_| {
+                whereat::at!(BitmapError::invalid_header("bad WIDTH").at_offset(pos))
+            })?);
         } else if let Some(rest) = line.strip_prefix("HEIGHT ") {
-            height = Some(
-                rest.trim()
-                    .parse()
-                    .map_err(|_| whereat::at!(BitmapError::InvalidHeader("bad HEIGHT".into())))?,
-            );
+            height = Some(rest.trim().parse().map_err(|_| {
+                whereat::at!(BitmapError::invalid_header("bad HEIGHT").at_offset(pos))
+            })?);
         } else if let Some(rest) = line.strip_prefix("DEPTH ") {
-            depth = Some(
-                rest.trim()
-                    .parse()
-                    .map_err(|_| whereat::at!(BitmapError::InvalidHeader("bad DEPTH".into())))?,
-            );
+            depth = Some(rest.trim().parse().map_err(|_| {
+                whereat::at!(BitmapError::invalid_header("bad DEPTH").at_offset(pos))
+            })?);
         } else if let Some(rest) = line.strip_prefix("MAXVAL ") {
-            maxval = Some(
-                rest.trim()
-                    .parse()
-                    .map_err(|_| whereat::at!(BitmapError::InvalidHeader("bad MAXVAL".into())))?,
-            );
+            maxval = Some(rest.trim().parse().map_err(|_| {
+                whereat::at!(BitmapError::invalid_header("bad MAXVAL").at_offset(pos))
+            })?);
         } else if let Some(rest) = line.strip_prefix("TUPLTYPE ") {
             tupltype = Some(rest.trim().into());
         } else if line.starts_with('#') {
@@ -183,29 +177,25 @@ fn parse_p7_header(data: &[u8]) -> crate::Result<PnmHeader> {
             data.len()
         };
         if pos >= data.len() {
-            return Err(whereat::at!(BitmapError::InvalidHeader(
-                "no ENDHDR found".into()
-            )));
+            return Err(whereat::at!(BitmapError::invalid_header("no ENDHDR found")));
         }
     }
 
-    let width =
-        width.ok_or_else(|| whereat::at!(BitmapError::InvalidHeader("missing WIDTH".into())))?;
+    let width = width.ok_or_else(|| whereat::at!(BitmapError::invalid_header("missing WIDTH")))?;
     let height =
-        height.ok_or_else(|| whereat::at!(BitmapError::InvalidHeader("missing HEIGHT".into())))?;
-    let depth =
-        depth.ok_or_else(|| whereat::at!(BitmapError::InvalidHeader("missing DEPTH".into())))?;
+        height.ok_or_else(|| whereat::at!(BitmapError::invalid_header("missing HEIGHT")))?;
+    let depth = depth.ok_or_else(|| whereat::at!(BitmapError::invalid_header("missing DEPTH")))?;
     let maxval =
-        maxval.ok_or_else(|| whereat::at!(BitmapError::InvalidHeader("missing MAXVAL".into())))?;
+        maxval.ok_or_else(|| whereat::at!(BitmapError::invalid_header("missing MAXVAL")))?;
 
     if width == 0 || height == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "width and height must be non-zero".into(),
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "width and height must be non-zero",
         )));
     }
     if depth == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "DEPTH must be non-zero".into()
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "DEPTH must be non-zero"
         )));
     }
 
@@ -234,32 +224,34 @@ fn parse_p7_header(data: &[u8]) -> crate::Result<PnmHeader> {
         layout,
         pfm_scale: 0.0,
         data_offset: pos,
+        rescale_16bit: false,
     })
 }
 
 fn parse_pfm_header(data: &[u8]) -> crate::Result<PnmHeader> {
     let is_color = data[1] == b'F';
-    let mut pos = 2;
-
-    pos = skip_whitespace_and_comments(data, pos)?;
-    let (width, new_pos) = parse_u32(data, pos)?;
-    pos = skip_whitespace_and_comments(data, new_pos)?;
-    let (height, new_pos) = parse_u32(data, pos)?;
-    pos = skip_whitespace_and_comments(data, new_pos)?;
+    let (width, width_at, pos) = header_u32(data, 2, "PNM width token")?;
+    let (height, _, pos) = header_u32(data, pos, "PNM height token")?;
+    let pos = skip_whitespace_and_comments(data, pos).in_stage("PFM scale token")?;
 
     let line_end = data[pos..]
         .iter()
         .position(|&b| b == b'\n')
         .map(|i| pos + i)
         .unwrap_or(data.len());
+    let scale_error = |message: String| {
+        whereat::at!(
+            BitmapError::invalid_header(message)
+                .at_offset(pos)
+                .in_stage("PFM scale token")
+        )
+    };
     let scale_str = core::str::from_utf8(&data[pos..line_end])
-        .map_err(|_| whereat::at!(BitmapError::InvalidHeader("non-UTF8 scale".into())))?
+        .map_err(|_| scale_error("non-UTF8 scale".into()))?
         .trim();
-    let scale: f32 = scale_str.parse().map_err(|_| {
-        whereat::at!(BitmapError::InvalidHeader(alloc::format!(
-            "bad scale: {scale_str}"
-        )))
-    })?;
+    let scale: f32 = scale_str
+        .parse()
+        .map_err(|_| scale_error(alloc::format!("bad scale: {scale_str}")))?;
 
     // The PFM specification (Pat Hanrahan, "PFM image format") defines the
     // scale as a non-zero finite number whose sign communicates byte order
@@ -268,15 +260,15 @@ fn parse_pfm_header(data: &[u8]) -> crate::Result<PnmHeader> {
     // poisons subsequent products; ±Inf saturates; zero is meaningless as
     // a scale factor and is excluded by spec).
     if !scale.is_finite() || scale == 0.0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(alloc::format!(
+        return Err(scale_error(alloc::format!(
             "PFM scale must be a non-zero finite value, got: {scale_str}"
-        ))));
+        )));
     }
 
     if width == 0 || height == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "width and height must be non-zero".into(),
-        )));
+        return Err(whereat::at!(
+            BitmapError::invalid_header("width and height must be non-zero").at_offset(width_at)
+        ));
     }
 
     let data_offset = line_end + 1;
@@ -296,6 +288,7 @@ fn parse_pfm_header(data: &[u8]) -> crate::Result<PnmHeader> {
         layout,
         pfm_scale: scale,
         data_offset,
+        rescale_16bit: false,
     })
 }
 
@@ -330,7 +323,7 @@ pub(crate) fn decode_integer_transform(
         Ok(out)
     } else {
         match header.layout {
-            PixelLayout::Gray16 => {
+            PixelLayout::Gray16 | PixelLayout::Rgb16 | PixelLayout::Rgba16 => {
                 // PNM binary 16-bit samples are big-endian on disk (PGM/PAM
                 // spec: "the most significant byte is first"). `Gray16` is
                 // documented native-endian, and the ASCII P2 path
@@ -345,7 +338,10 @@ pub(crate) fn decode_integer_transform(
                         stop.check()
                             .map_err(|r| whereat::at!(BitmapError::from(r)))?;
                     }
-                    let val = u16::from_be_bytes([pair[0], pair[1]]);
+                    let mut val = u16::from_be_bytes([pair[0], pair[1]]);
+                    if header.rescale_16bit {
+                        val = widen_16bit(val.into(), header.maxval);
+                    }
                     out.extend_from_slice(&val.to_ne_bytes());
                 }
                 Ok(out)
@@ -368,7 +364,7 @@ pub(crate) fn decode_integer_transform(
                     })
                 })?;
                 if pixel_data.len() < needed_bytes {
-                    return Err(whereat::at!(BitmapError::UnexpectedEof));
+                    return Err(whereat::at!(BitmapError::unexpected_eof()));
                 }
                 let scale = 255.0 / header.maxval as f32;
                 let stop_interval = w.saturating_mul(depth).saturating_mul(16).max(1);
@@ -419,7 +415,7 @@ pub(crate) fn decode_pfm(
     })?;
 
     if pixel_data.len() < expected_bytes {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(BitmapError::unexpected_eof()));
     }
 
     let is_little_endian = header.pfm_scale < 0.0;
@@ -511,17 +507,22 @@ pub(crate) fn decode_ascii_pbm(
             }
         }
         if pos >= pixel_data.len() {
-            return Err(whereat::at!(BitmapError::UnexpectedEof));
+            return Err(whereat::at!(
+                BitmapError::unexpected_eof().at_offset(header.data_offset + pos)
+            ));
         }
         // PBM: 1 = black (0), 0 = white (255)
         let val = match pixel_data[pos] {
             b'0' => 255,
             b'1' => 0,
             c => {
-                return Err(whereat::at!(BitmapError::InvalidData(alloc::format!(
-                    "P1: expected '0' or '1', got '{}'",
-                    c as char
-                ))));
+                return Err(whereat::at!(
+                    BitmapError::invalid_data(alloc::format!(
+                        "P1: expected '0' or '1', got '{}'",
+                        c as char
+                    ))
+                    .at_offset(header.data_offset + pos)
+                ));
             }
         };
         out.push(val);
@@ -553,7 +554,7 @@ pub(crate) fn decode_p4_bitpacked(
     })?;
 
     if pixel_data.len() < total_bytes {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(BitmapError::unexpected_eof()));
     }
 
     let out_size = w.checked_mul(h).ok_or_else(|| {
@@ -610,13 +611,13 @@ pub(crate) fn decode_ascii_samples(
     // so the ASCII path produces byte-for-byte the same buffer the binary path
     // (`decode_integer_transform`) does for the same logical image:
     //
-    // * A genuinely 16-bit-per-channel layout (Gray16 — the only one the P2/P3
-    //   ASCII path produces; Rgba16 listed for completeness) keeps 2 raw
+    // * A genuinely 16-bit-per-channel layout (Gray16, or Rgb16 when the
+    //   zencodec path asks to keep 16-bit P3 samples) keeps 2 raw
     //   native-endian bytes per sample. Emitting a single downscaled u8 here
     //   produced HALF the declared bytes — an OOB panic in
     //   `PixelBuffer::as_slice` and silent 16-bit precision loss (fuzz zenpipe#51).
-    // * An 8-bit layout (Rgb8 — produced by 16-bit *P3 PPM*, since there is no
-    //   Rgb16 layout) downscales 16-bit samples to one u8 via `val·255/maxval`,
+    // * An 8-bit layout (Rgb8 — what 16-bit *P3 PPM* decodes to by default)
+    //   downscales 16-bit samples to one u8 via `val·255/maxval`,
     //   exactly like the binary P6 16-bit path. The pre-fix code keyed the byte
     //   width on `maxval > 255` alone, so 16-bit P3 emitted 2 bytes/sample while
     //   tagging the buffer Rgb8 (1 byte/channel) — a 6-byte 1×1 "Rgb8" image.
@@ -654,22 +655,30 @@ pub(crate) fn decode_ascii_samples(
         while pos < pixel_data.len() && pixel_data[pos].is_ascii_digit() {
             pos += 1;
         }
+        let at = header.data_offset + start;
         if pos == start {
-            return Err(whereat::at!(BitmapError::UnexpectedEof));
+            return Err(whereat::at!(BitmapError::unexpected_eof().at_offset(at)));
         }
-        let s = core::str::from_utf8(&pixel_data[start..pos])
-            .map_err(|_| whereat::at!(BitmapError::InvalidData("non-UTF8 in ASCII PNM".into())))?;
+        let s = core::str::from_utf8(&pixel_data[start..pos]).map_err(|_| {
+            whereat::at!(BitmapError::invalid_data("non-UTF8 in ASCII PNM").at_offset(at))
+        })?;
         let val: u32 = s.parse().map_err(|_| {
-            whereat::at!(BitmapError::InvalidData(alloc::format!(
-                "bad sample value: {s}"
-            )))
+            whereat::at!(
+                BitmapError::invalid_data(alloc::format!("bad sample value: {s}")).at_offset(at)
+            )
         })?;
 
         // Clamp out-of-range samples (a malformed ASCII value may exceed maxval).
         let val = val.min(header.maxval);
         if layout_is_16bit {
-            // 16-bit-per-channel layout (Gray16): raw native-endian u16.
-            out.extend_from_slice(&(val as u16).to_ne_bytes());
+            // 16-bit-per-channel layout (Gray16, Rgb16): native-endian u16,
+            // raw unless the header asks for the full range.
+            let val = if header.rescale_16bit {
+                widen_16bit(val, header.maxval)
+            } else {
+                val as u16
+            };
+            out.extend_from_slice(&val.to_ne_bytes());
         } else if let Some(s) = scale8 {
             // 8-bit layout fed by a wider maxval (incl. 16-bit P3 PPM →
             // Rgb8): downscale to 0..=255, matching the binary 16-bit path.
@@ -682,10 +691,17 @@ pub(crate) fn decode_ascii_samples(
     Ok(out)
 }
 
+/// Scale a sample from `0..=maxval` to `0..=65535`, rounding to nearest.
+/// Samples above `maxval` clamp to 65535.
+fn widen_16bit(val: u32, maxval: u32) -> u16 {
+    // At most 65535 * 65535 + 32767, which fits in u32.
+    ((val.min(maxval) * 65535 + maxval / 2) / maxval) as u16
+}
+
 fn skip_whitespace_and_comments(data: &[u8], mut pos: usize) -> crate::Result<usize> {
     loop {
         if pos >= data.len() {
-            return Err(whereat::at!(BitmapError::UnexpectedEof));
+            return Err(whereat::at!(BitmapError::unexpected_eof().at_offset(pos)));
         }
         match data[pos] {
             b' ' | b'\t' | b'\n' | b'\r' => pos += 1,
@@ -702,6 +718,14 @@ fn skip_whitespace_and_comments(data: &[u8], mut pos: usize) -> crate::Result<us
     }
 }
 
+/// Skip to the header number at or after `pos` and parse it, naming
+/// `stage` on failure. Returns the value, its offset and the offset after it.
+fn header_u32(data: &[u8], pos: usize, stage: &'static str) -> crate::Result<(u32, usize, usize)> {
+    let start = skip_whitespace_and_comments(data, pos).in_stage(stage)?;
+    let (value, end) = parse_u32(data, start).in_stage(stage)?;
+    Ok((value, start, end))
+}
+
 fn parse_u32(data: &[u8], pos: usize) -> crate::Result<(u32, usize)> {
     let mut end = pos;
     // Limit to 10 digits (u32::MAX = 4294967295, 10 digits)
@@ -710,16 +734,16 @@ fn parse_u32(data: &[u8], pos: usize) -> crate::Result<(u32, usize)> {
         end += 1;
     }
     if end == pos {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "expected number".into()
-        )));
+        return Err(whereat::at!(
+            BitmapError::invalid_header("expected number").at_offset(pos)
+        ));
     }
     let s = core::str::from_utf8(&data[pos..end])
-        .map_err(|_| whereat::at!(BitmapError::InvalidHeader("non-UTF8 number".into())))?;
+        .map_err(|_| whereat::at!(BitmapError::invalid_header("non-UTF8 number").at_offset(pos)))?;
     let val: u32 = s.parse().map_err(|_| {
-        whereat::at!(BitmapError::InvalidHeader(alloc::format!(
-            "number too large: {s}"
-        )))
+        whereat::at!(
+            BitmapError::invalid_header(alloc::format!("number too large: {s}")).at_offset(pos)
+        )
     })?;
     Ok((val, end))
 }
@@ -742,7 +766,7 @@ mod tests {
         match parse_pfm_header(&data) {
             Ok(_) => panic!("expected InvalidHeader for scale {scale_text:?}"),
             Err(e) => match e.error() {
-                BitmapError::InvalidHeader(_) => {}
+                BitmapError::InvalidHeader(..) => {}
                 _ => panic!("expected InvalidHeader for scale {scale_text:?}, got {e}"),
             },
         }
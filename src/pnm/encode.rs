@@ -9,6 +9,121 @@ use alloc::format;
 use alloc::vec::Vec;
 use enough::Stop;
 
+/// Options for PPM and PGM encoding with [`crate::encode_ppm_with`] and
+/// [`crate::encode_pgm_with`].
+///
+/// ```
+/// use zenbitmaps::PnmEncodeOptions;
+///
+/// let options = PnmEncodeOptions::new().with_background([255, 255, 255]);
+/// assert_eq!(options.background(), Some([255, 255, 255]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PnmEncodeOptions {
+    background: Option<[u8; 3]>,
+    maxval: Option<u16>,
+}
+
+impl PnmEncodeOptions {
+    /// Default options: alpha is dropped as is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blend input with alpha over this opaque sRGB color before its alpha
+    /// is dropped, instead of writing the color samples unchanged, so
+    /// transparent areas come out as the background rather than whatever
+    /// color they happen to hold. Default none.
+    pub fn with_background(mut self, background: [u8; 3]) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Write samples scaled to `0..=maxval` instead of the input's own
+    /// range (0–255, or 0–65535 for 16-bit layouts), with rounding; above
+    /// 255 each sample takes two big-endian bytes, so 65535 stores 8-bit
+    /// input as exact 16-bit. PFM output ignores it, and a maxval of 0
+    /// fails to encode. Default none.
+    pub fn with_maxval(mut self, maxval: u16) -> Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// Background color for input with alpha, if set.
+    pub fn background(&self) -> Option<[u8; 3]> {
+        self.background
+    }
+
+    /// The output maxval, if set.
+    pub fn maxval(&self) -> Option<u16> {
+        self.maxval
+    }
+}
+
+/// Output buffer that scales samples from the input's maxval (255 or
+/// 65535) to the file's as they are written.
+struct SampleWriter {
+    out: Vec<u8>,
+    from: u32,
+    to: u32,
+}
+
+impl SampleWriter {
+    /// A buffer holding `header`, with room for `samples` samples.
+    fn new(header: &str, from: u32, to: u32, samples: usize) -> Self {
+        let sample_bytes = if to > 255 { 2 } else { 1 };
+        let mut out = Vec::with_capacity(header.len() + samples * sample_bytes);
+        out.extend_from_slice(header.as_bytes());
+        Self { out, from, to }
+    }
+
+    fn push(&mut self, v: u8) {
+        if self.from == self.to {
+            self.out.push(v);
+        } else {
+            self.put(v.into());
+        }
+    }
+
+    fn push16(&mut self, v: u16) {
+        if self.from == self.to {
+            self.out.extend_from_slice(&v.to_be_bytes());
+        } else {
+            self.put(v.into());
+        }
+    }
+
+    /// Write 8-bit `rows` of `row_len` samples each.
+    fn extend_rows(&mut self, rows: &[u8], row_len: usize, stop: &dyn Stop) -> crate::Result<()> {
+        if self.from == self.to {
+            self.out.extend_from_slice(rows);
+            return Ok(());
+        }
+        for band in rows.chunks(row_len.saturating_mul(16).max(1)) {
+            stop.check()
+                .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+            for &v in band {
+                self.put(v.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn put(&mut self, v: u32) {
+        let scaled = (v * self.to + self.from / 2) / self.from;
+        if self.to > 255 {
+            self.out.extend_from_slice(&(scaled as u16).to_be_bytes());
+        } else {
+            self.out.push(scaled as u8);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
 /// Encode pixels to PNM format.
 pub(crate) fn encode_pnm(
     pixels: &[u8],
@@ -16,8 +131,14 @@ pub(crate) fn encode_pnm(
     height: u32,
     layout: PixelLayout,
     fmt: PnmFormat,
+    maxval: Option<u16>,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
+    if maxval == Some(0) {
+        return Err(whereat::at!(BitmapError::UnsupportedVariant(
+            "PNM maxval must be at least 1".into()
+        )));
+    }
     let w = width as usize;
     let h = height as usize;
     let expected = w
@@ -38,13 +159,61 @@ pub(crate) fn encode_pnm(
         PnmFormat::Pbm => Err(whereat::at!(BitmapError::UnsupportedVariant(
             "PBM encode not supported, use PGM (encode_pgm)".into(),
         ))),
-        PnmFormat::Pgm => encode_pgm(pixels, width, height, w, h, layout, stop),
-        PnmFormat::Ppm => encode_ppm(pixels, width, height, w, h, layout, stop),
-        PnmFormat::Pam => encode_pam(pixels, width, height, w, h, layout, stop),
+        PnmFormat::Pgm => encode_pgm(pixels, width, height, w, h, layout, maxval, stop),
+        PnmFormat::Ppm => encode_ppm(pixels, width, height, w, h, layout, maxval, stop),
+        PnmFormat::Pam => encode_pam(pixels, width, height, w, h, layout, maxval, stop),
         PnmFormat::Pfm => encode_pfm(pixels, width, height, w, h, layout, stop),
     }
 }
 
+/// Size in bytes of the file [`encode_pnm`] writes.
+pub(crate) fn encoded_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    fmt: PnmFormat,
+) -> crate::Result<usize> {
+    use PixelLayout::*;
+    let (header, channel_bytes) = match (fmt, layout) {
+        (PnmFormat::Pgm, Gray8 | Rgb8 | Bgr8 | Rgba8 | Rgbx8 | Bgra8 | Bgrx8) => {
+            (format!("P5\n{width} {height}\n255\n"), 1)
+        }
+        (PnmFormat::Ppm, Gray8 | Rgb8 | Bgr8 | Rgba8 | Rgbx8 | Bgra8 | Bgrx8) => {
+            (format!("P6\n{width} {height}\n255\n"), 3)
+        }
+        (
+            PnmFormat::Pam,
+            Gray8 | Gray16 | Rgb8 | Bgr8 | Rgb16 | Rgba8 | Rgbx8 | Bgra8 | Bgrx8 | Rgba16,
+        ) => {
+            let (depth, tupltype, maxval) = match layout {
+                Gray8 => (1, "GRAYSCALE", 255),
+                Gray16 => (1, "GRAYSCALE", 65535),
+                Rgb8 | Bgr8 => (3, "RGB", 255),
+                Rgb16 => (3, "RGB", 65535),
+                Rgba16 => (4, "RGB_ALPHA", 65535),
+                _ => (4, "RGB_ALPHA", 255),
+            };
+            let header = format!(
+                "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH {depth}\nMAXVAL {maxval}\nTUPLTYPE {tupltype}\nENDHDR\n"
+            );
+            (header, layout.bytes_per_pixel())
+        }
+        (PnmFormat::Pfm, GrayF32) => (format!("Pf\n{width} {height}\n-1.0\n"), 4),
+        (PnmFormat::Pfm, RgbF32) => (format!("PF\n{width} {height}\n-1.0\n"), 12),
+        _ => {
+            return Err(whereat::at!(BitmapError::UnsupportedVariant(format!(
+                "cannot encode {layout:?} as {fmt:?}"
+            ))));
+        }
+    };
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(channel_bytes))
+        .and_then(|data| data.checked_add(header.len()))
+        .ok_or_else(|| whereat::at!(BitmapError::DimensionsTooLarge { width, height }))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn encode_pgm(
     pixels: &[u8],
     width: u32,
@@ -52,15 +221,16 @@ fn encode_pgm(
     w: usize,
     h: usize,
     layout: PixelLayout,
+    maxval: Option<u16>,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
-    let header = format!("P5\n{width} {height}\n255\n");
-    let mut out = Vec::with_capacity(header.len() + w * h);
-    out.extend_from_slice(header.as_bytes());
+    let maxval = maxval.map_or(255, u32::from);
+    let header = format!("P5\n{width} {height}\n{maxval}\n");
+    let mut out = SampleWriter::new(&header, 255, maxval, w * h);
 
     match layout {
         PixelLayout::Gray8 => {
-            out.extend_from_slice(&pixels[..w * h]);
+            out.extend_rows(&pixels[..w * h], w, stop)?;
         }
         PixelLayout::Rgb8 => {
             for i in 0..(w * h) {
@@ -88,7 +258,7 @@ fn encode_pgm(
                 out.push(((r * 299 + g * 587 + b * 114 + 500) / 1000) as u8);
             }
         }
-        PixelLayout::Rgba8 => {
+        PixelLayout::Rgba8 | PixelLayout::Rgbx8 => {
             for i in 0..(w * h) {
                 if i % w.saturating_mul(16).max(1) == 0 {
                     stop.check()
@@ -122,9 +292,10 @@ fn encode_pgm(
         }
     }
 
-    Ok(out)
+    Ok(out.finish())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn encode_ppm(
     pixels: &[u8],
     width: u32,
@@ -132,15 +303,16 @@ fn encode_ppm(
     w: usize,
     h: usize,
     layout: PixelLayout,
+    maxval: Option<u16>,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
-    let header = format!("P6\n{width} {height}\n255\n");
-    let mut out = Vec::with_capacity(header.len() + w * h * 3);
-    out.extend_from_slice(header.as_bytes());
+    let maxval = maxval.map_or(255, u32::from);
+    let header = format!("P6\n{width} {height}\n{maxval}\n");
+    let mut out = SampleWriter::new(&header, 255, maxval, w * h * 3);
 
     match layout {
         PixelLayout::Rgb8 => {
-            out.extend_from_slice(&pixels[..w * h * 3]);
+            out.extend_rows(&pixels[..w * h * 3], w * 3, stop)?;
         }
         PixelLayout::Bgr8 => {
             for i in 0..(w * h) {
@@ -154,7 +326,7 @@ fn encode_ppm(
                 out.push(pixels[off]);
             }
         }
-        PixelLayout::Rgba8 => {
+        PixelLayout::Rgba8 | PixelLayout::Rgbx8 => {
             for i in 0..(w * h) {
                 if i % w.saturating_mul(16).max(1) == 0 {
                     stop.check()
@@ -193,9 +365,10 @@ fn encode_ppm(
         }
     }
 
-    Ok(out)
+    Ok(out.finish())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn encode_pam(
     pixels: &[u8],
     width: u32,
@@ -203,16 +376,19 @@ fn encode_pam(
     w: usize,
     h: usize,
     layout: PixelLayout,
+    maxval: Option<u16>,
     stop: &dyn Stop,
 ) -> crate::Result<Vec<u8>> {
-    let (depth, tupltype, maxval) = match layout {
+    let (depth, tupltype, from) = match layout {
         PixelLayout::Gray8 => (1, "GRAYSCALE", 255),
         PixelLayout::Gray16 => (1, "GRAYSCALE", 65535),
         PixelLayout::Rgb8 => (3, "RGB", 255),
         PixelLayout::Rgba8 => (4, "RGB_ALPHA", 255),
         PixelLayout::Bgr8 => (3, "RGB", 255),
         PixelLayout::Bgra8 => (4, "RGB_ALPHA", 255),
-        PixelLayout::Bgrx8 => (4, "RGB_ALPHA", 255),
+        PixelLayout::Bgrx8 | PixelLayout::Rgbx8 => (4, "RGB_ALPHA", 255),
+        PixelLayout::Rgb16 => (3, "RGB", 65535),
+        PixelLayout::Rgba16 => (4, "RGB_ALPHA", 65535),
         _ => {
             return Err(whereat::at!(BitmapError::UnsupportedVariant(format!(
                 "cannot encode {:?} as PAM",
@@ -221,16 +397,16 @@ fn encode_pam(
         }
     };
 
+    let maxval = maxval.map_or(from, u32::from);
     let header = format!(
         "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH {depth}\nMAXVAL {maxval}\nTUPLTYPE {tupltype}\nENDHDR\n"
     );
 
     let pixel_count = w * h;
-    // Capacity by byte width, not channel count: `Gray16` has DEPTH 1 but two
-    // bytes per pixel, so `pixel_count * depth` would under-allocate by half.
+    // Input bytes by byte width, not channel count: `Gray16` has DEPTH 1 but
+    // two bytes per pixel, so `pixel_count * depth` would read half of it.
     let out_bytes = pixel_count * layout.bytes_per_pixel();
-    let mut out = Vec::with_capacity(header.len() + out_bytes);
-    out.extend_from_slice(header.as_bytes());
+    let mut out = SampleWriter::new(&header, from, maxval, pixel_count * depth);
 
     match layout {
         PixelLayout::Bgr8 => {
@@ -274,30 +450,42 @@ fn encode_pam(
                 out.push(255); // A (opaque)
             }
         }
-        PixelLayout::Gray16 => {
+        PixelLayout::Rgbx8 => {
+            // RGBX → RGBA (A=255)
+            for (i, px) in pixels[..pixel_count * 4].chunks_exact(4).enumerate() {
+                if i % w.saturating_mul(16).max(1) == 0 {
+                    stop.check()
+                        .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+                }
+                for &v in &px[..3] {
+                    out.push(v);
+                }
+                out.push(255); // A (opaque)
+            }
+        }
+        PixelLayout::Gray16 | PixelLayout::Rgb16 | PixelLayout::Rgba16 => {
             // 16-bit samples are stored big-endian on disk (PAM spec); `Gray16`
             // is native-endian in memory (issue #12). Convert native → big-endian,
             // mirroring the decode path (`decode_integer_transform`) and farbfeld
             // so `decode → encode_pam → decode` stays pixel-lossless and the
             // on-disk bytes are spec-compliant. A no-op on big-endian hosts.
-            for i in 0..pixel_count {
-                if i % w.saturating_mul(16).max(1) == 0 {
+            let row_samples = w * depth;
+            let samples = pixels[..out_bytes].chunks_exact(2);
+            for (i, sample) in samples.enumerate() {
+                if i % row_samples.saturating_mul(16).max(1) == 0 {
                     stop.check()
                         .map_err(|r| whereat::at!(BitmapError::from(r)))?;
                 }
-                let off = i * 2;
-                let val = u16::from_ne_bytes([pixels[off], pixels[off + 1]]);
-                out.extend_from_slice(&val.to_be_bytes());
+                out.push16(u16::from_ne_bytes([sample[0], sample[1]]));
             }
         }
         _ => {
             // Direct copy for native-order formats
-            let pixel_bytes = pixel_count * layout.bytes_per_pixel();
-            out.extend_from_slice(&pixels[..pixel_bytes]);
+            out.extend_rows(&pixels[..out_bytes], w * depth, stop)?;
         }
     }
 
-    Ok(out)
+    Ok(out.finish())
 }
 
 fn encode_pfm(
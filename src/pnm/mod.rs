@@ -6,11 +6,14 @@
 pub(crate) mod decode;
 mod encode;
 
+pub use encode::PnmEncodeOptions;
+
 use crate::alloc_util::AllocPref;
 use crate::decode::DecodeOutput;
-use crate::error::BitmapError;
+use crate::error::{BitmapError, ResultExt};
 use crate::limits::{self, Limits};
 use crate::pixel::PixelLayout;
+use crate::region::Region;
 use enough::Stop;
 
 /// Which PNM sub-format to use (internal).
@@ -33,6 +36,21 @@ pub(crate) struct PnmHeader {
     pub layout: PixelLayout,
     pub pfm_scale: f32,
     pub data_offset: usize,
+    /// Whether 16-bit layouts stretch samples from `0..=maxval` to the full
+    /// `0..=65535` range; otherwise they keep the raw sample values.
+    pub rescale_16bit: bool,
+}
+
+impl PnmHeader {
+    /// The layout that keeps every bit of the samples: `Rgb16`/`Rgba16`
+    /// for 16-bit color, which [`layout`](Self::layout) downscales to 8-bit.
+    pub(crate) fn wide_layout(&self) -> PixelLayout {
+        match self.layout {
+            PixelLayout::Rgb8 if self.maxval > 255 => PixelLayout::Rgb16,
+            PixelLayout::Rgba8 if self.maxval > 255 => PixelLayout::Rgba16,
+            layout => layout,
+        }
+    }
 }
 
 /// Decode PNM data (called from top-level decode functions).
@@ -62,8 +80,23 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    decode_with_depth(data, limits, alloc_pref, false, stop)
+}
+
+/// [`decode_with_alloc_pref`], but with `keep_16bit` set, 16-bit PPM and
+/// RGB/RGBA PAM decode to `Rgb16`/`Rgba16` (native-endian, like `Gray16`)
+/// instead of being downscaled to 8-bit, and every 16-bit layout is
+/// rescaled from `0..=maxval` to `0..=65535`.
+pub(crate) fn decode_with_depth<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    alloc_pref: AllocPref,
+    keep_16bit: bool,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
     if data.len() < 3 {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(BitmapError::unexpected_eof()));
     }
 
     // Verify magic bytes
@@ -72,7 +105,11 @@ pub(crate) fn decode_with_alloc_pref<'a>(
         _ => return Err(whereat::at!(BitmapError::UnrecognizedFormat)),
     }
 
-    let header = decode::parse_header(data)?;
+    let mut header = decode::parse_header(data)?;
+    if keep_16bit {
+        header.layout = header.wide_layout();
+        header.rescale_16bit = header.maxval != 65535;
+    }
 
     limits::check_dimensions(header.width, header.height, limits)?;
 
@@ -81,7 +118,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
 
     let pixel_data = data
         .get(header.data_offset..)
-        .ok_or_else(|| whereat::at!(BitmapError::UnexpectedEof))?;
+        .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof().at_offset(data.len())))?;
 
     let w = header.width as usize;
     let h = header.height as usize;
@@ -98,10 +135,13 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                 })
             })?;
             limits::check_output_size(out_bytes, limits)?;
+            limits::check_alloc(out_bytes, limits)?;
             let pixels = if is_ascii {
-                decode::decode_ascii_pbm(pixel_data, &header, alloc_pref, stop)?
+                decode::decode_ascii_pbm(pixel_data, &header, alloc_pref, stop)
+                    .in_stage("PNM pixel data")?
             } else {
-                decode::decode_p4_bitpacked(pixel_data, &header, alloc_pref, stop)?
+                decode::decode_p4_bitpacked(pixel_data, &header, alloc_pref, stop)
+                    .in_stage("PNM pixel data")?
             };
             Ok(DecodeOutput::owned(
                 pixels,
@@ -122,7 +162,9 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                     })
                 })?;
             limits::check_output_size(out_bytes, limits)?;
-            let pixels = decode::decode_pfm(pixel_data, &header, alloc_pref, stop)?;
+            limits::check_alloc(out_bytes, limits)?;
+            let pixels = decode::decode_pfm(pixel_data, &header, alloc_pref, stop)
+                .in_stage("PNM pixel data")?;
             Ok(DecodeOutput::owned(
                 pixels,
                 header.width,
@@ -135,7 +177,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
             if is_ascii {
                 let out_bytes = w
                     .checked_mul(h)
-                    .and_then(|wh| wh.checked_mul(depth))
+                    .and_then(|wh| wh.checked_mul(header.layout.bytes_per_pixel()))
                     .ok_or_else(|| {
                         whereat::at!(BitmapError::DimensionsTooLarge {
                             width: header.width,
@@ -143,7 +185,9 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                         })
                     })?;
                 limits::check_output_size(out_bytes, limits)?;
-                let pixels = decode::decode_ascii_samples(pixel_data, &header, alloc_pref, stop)?;
+                limits::check_alloc(out_bytes, limits)?;
+                let pixels = decode::decode_ascii_samples(pixel_data, &header, alloc_pref, stop)
+                    .in_stage("PNM pixel data")?;
                 Ok(DecodeOutput::owned(
                     pixels,
                     header.width,
@@ -166,7 +210,11 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                     })?;
 
                 if pixel_data.len() < expected_src {
-                    return Err(whereat::at!(BitmapError::UnexpectedEof));
+                    return Err(whereat::at!(
+                        BitmapError::unexpected_eof()
+                            .at_offset(data.len())
+                            .in_stage("PNM pixel data")
+                    ));
                 }
 
                 if !is_16bit && header.maxval == 255 {
@@ -179,7 +227,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                 } else {
                     let out_bytes = w
                         .checked_mul(h)
-                        .and_then(|wh| wh.checked_mul(depth))
+                        .and_then(|wh| wh.checked_mul(header.layout.bytes_per_pixel()))
                         .ok_or_else(|| {
                             whereat::at!(BitmapError::DimensionsTooLarge {
                                 width: header.width,
@@ -187,13 +235,15 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                             })
                         })?;
                     limits::check_output_size(out_bytes, limits)?;
+                    limits::check_alloc(out_bytes, limits)?;
                     let pixels = decode::decode_integer_transform(
                         pixel_data,
                         &header,
                         expected_src,
                         alloc_pref,
                         stop,
-                    )?;
+                    )
+                    .in_stage("PNM pixel data")?;
                     Ok(DecodeOutput::owned(
                         pixels,
                         header.width,
@@ -218,7 +268,11 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                     })
                 })?;
             if pixel_data.len() < expected_src {
-                return Err(whereat::at!(BitmapError::UnexpectedEof));
+                return Err(whereat::at!(
+                    BitmapError::unexpected_eof()
+                        .at_offset(data.len())
+                        .in_stage("PNM pixel data")
+                ));
             }
             if !is_16bit && header.maxval == 255 {
                 Ok(DecodeOutput::borrowed(
@@ -230,7 +284,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
             } else {
                 let out_bytes = w
                     .checked_mul(h)
-                    .and_then(|wh| wh.checked_mul(depth))
+                    .and_then(|wh| wh.checked_mul(header.layout.bytes_per_pixel()))
                     .ok_or_else(|| {
                         whereat::at!(BitmapError::DimensionsTooLarge {
                             width: header.width,
@@ -238,13 +292,15 @@ pub(crate) fn decode_with_alloc_pref<'a>(
                         })
                     })?;
                 limits::check_output_size(out_bytes, limits)?;
+                limits::check_alloc(out_bytes, limits)?;
                 let pixels = decode::decode_integer_transform(
                     pixel_data,
                     &header,
                     expected_src,
                     alloc_pref,
                     stop,
-                )?;
+                )
+                .in_stage("PNM pixel data")?;
                 Ok(DecodeOutput::owned(
                     pixels,
                     header.width,
@@ -256,6 +312,109 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     }
 }
 
+/// Decode the `region` window of a PNM image.
+///
+/// Binary formats have a fixed row stride, so only the window's rows are
+/// handed to the sample decoders (and an 8-bit full-width window is
+/// borrowed straight from `data`). ASCII formats decode in full and are
+/// cropped.
+pub(crate) fn decode_region<'a>(
+    data: &'a [u8],
+    region: Region,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let header = decode::parse_header(data)?;
+    region.check(header.width, header.height)?;
+    if matches!(data[1], b'1' | b'2' | b'3') {
+        return Ok(region.crop(decode(data, limits, stop)?));
+    }
+    limits::check_dimensions(region.width, region.height, limits)?;
+
+    let too_large = || {
+        whereat::at!(BitmapError::DimensionsTooLarge {
+            width: header.width,
+            height: header.height,
+        })
+    };
+    let w = header.width as usize;
+    let depth = header.depth as usize;
+    let is_16bit = header.maxval > 255;
+    let row_bytes = match header.format {
+        PnmFormat::Pbm => Some(w.div_ceil(8)),
+        PnmFormat::Pfm => w.checked_mul(depth).and_then(|n| n.checked_mul(4)),
+        _ => w
+            .checked_mul(depth)
+            .and_then(|n| n.checked_mul(if is_16bit { 2 } else { 1 })),
+    }
+    .ok_or_else(too_large)?;
+    let out_bytes = w
+        .checked_mul(region.height as usize)
+        .and_then(|n| n.checked_mul(header.layout.bytes_per_pixel()))
+        .ok_or_else(too_large)?;
+    limits::check_output_size(out_bytes, limits)?;
+    limits::check_alloc(out_bytes, limits)?;
+    stop.check()
+        .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+
+    // PFM stores rows bottom-up.
+    let first_row = match header.format {
+        PnmFormat::Pfm => header.height - region.y - region.height,
+        _ => region.y,
+    } as usize;
+    let rows = data
+        .get(header.data_offset..)
+        .and_then(|d| d.get(first_row.checked_mul(row_bytes)?..))
+        .and_then(|d| d.get(..row_bytes.checked_mul(region.height as usize)?))
+        .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof().at_offset(data.len())))?;
+
+    let layout = header.layout;
+    let window = PnmHeader {
+        height: region.height,
+        ..header
+    };
+    let pref = AllocPref::CodecDefault;
+    let pixels = match window.format {
+        PnmFormat::Pbm => {
+            decode::decode_p4_bitpacked(rows, &window, pref, stop).in_stage("PNM pixel data")?
+        }
+        PnmFormat::Pfm => {
+            decode::decode_pfm(rows, &window, pref, stop).in_stage("PNM pixel data")?
+        }
+        _ if !is_16bit && window.maxval == 255 => {
+            if region.width == window.width {
+                return Ok(DecodeOutput::borrowed(
+                    rows,
+                    region.width,
+                    region.height,
+                    layout,
+                ));
+            }
+            rows.to_vec()
+        }
+        _ => decode::decode_integer_transform(rows, &window, rows.len(), pref, stop)
+            .in_stage("PNM pixel data")?,
+    };
+    let pixels = region.crop_columns(pixels, window.width, layout.bytes_per_pixel());
+    Ok(DecodeOutput::owned(
+        pixels,
+        region.width,
+        region.height,
+        layout,
+    ))
+}
+
+/// Size of the file [`encode`] writes.
+pub(crate) fn encoded_size(
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    format: PnmFormat,
+) -> crate::Result<usize> {
+    encode::encoded_size(width, height, layout, format)
+}
+
 /// Encode to PNM.
 pub(crate) fn encode(
     pixels: &[u8],
@@ -265,5 +424,28 @@ pub(crate) fn encode(
     format: PnmFormat,
     stop: &dyn Stop,
 ) -> crate::Result<alloc::vec::Vec<u8>> {
-    encode::encode_pnm(pixels, width, height, layout, format, stop)
+    encode::encode_pnm(pixels, width, height, layout, format, None, stop)
+}
+
+/// Encode to PNM with options.
+pub(crate) fn encode_with(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    format: PnmFormat,
+    options: &PnmEncodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<alloc::vec::Vec<u8>> {
+    let pixels =
+        crate::alpha::flattened(pixels, width, height, layout, options.background(), stop)?;
+    encode::encode_pnm(
+        &pixels,
+        width,
+        height,
+        layout,
+        format,
+        options.maxval(),
+        stop,
+    )
 }
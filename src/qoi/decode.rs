@@ -24,16 +24,16 @@ pub(crate) struct QoiHeaderInfo {
 /// Parse QOI header, returning dimensions, alpha, and colorspace.
 pub(crate) fn parse_header(data: &[u8]) -> crate::Result<QoiHeaderInfo> {
     let qoi = rapid_qoi::Qoi::decode_header(data)
-        .map_err(|e| whereat::at!(BitmapError::InvalidHeader(alloc::format!("{e:?}"))))?;
+        .map_err(|e| whereat::at!(BitmapError::invalid_header(alloc::format!("{e:?}"))))?;
 
     if qoi.width == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "QOI width is zero".into()
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "QOI width is zero"
         )));
     }
     if qoi.height == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "QOI height is zero".into()
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "QOI height is zero"
         )));
     }
 
@@ -74,7 +74,7 @@ pub(crate) fn decode_pixels(
     // and carried across rows).
     let encoded = data
         .get(14..)
-        .ok_or_else(|| whereat::at!(BitmapError::UnexpectedEof))?;
+        .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))?;
 
     if has_alpha {
         let mut state = QoiDecodeState::<4>::new();
@@ -89,7 +89,7 @@ pub(crate) fn decode_pixels(
             let row_end = row_start + row_bytes;
             let consumed = state
                 .decode_into(&encoded[offset..], &mut output[row_start..row_end])
-                .map_err(|()| whereat::at!(BitmapError::UnexpectedEof))?;
+                .map_err(|()| whereat::at!(BitmapError::unexpected_eof()))?;
             offset += consumed;
         }
     } else {
@@ -105,7 +105,7 @@ pub(crate) fn decode_pixels(
             let row_end = row_start + row_bytes;
             let consumed = state
                 .decode_into(&encoded[offset..], &mut output[row_start..row_end])
-                .map_err(|()| whereat::at!(BitmapError::UnexpectedEof))?;
+                .map_err(|()| whereat::at!(BitmapError::unexpected_eof()))?;
             offset += consumed;
         }
     }
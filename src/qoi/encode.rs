@@ -70,6 +70,16 @@ pub(crate) fn encode_qoi(
             }
             (Some(rgba), rapid_qoi::Colors::SrgbLinA)
         }
+        PixelLayout::Rgbx8 => {
+            // RGBX → RGBA (set alpha=255)
+            stop.check()
+                .map_err(|r| whereat::at!(BitmapError::from(r)))?;
+            let mut rgba = pixels[..expected].to_vec();
+            for pixel in rgba.chunks_exact_mut(4) {
+                pixel[3] = 255;
+            }
+            (Some(rgba), rapid_qoi::Colors::SrgbLinA)
+        }
         PixelLayout::Bgrx8 => {
             // Swizzle BGRX → RGBA (set alpha=255)
             stop.check()
@@ -105,7 +115,7 @@ pub(crate) fn encode_qoi(
     let encode_data = qoi_pixels.as_deref().unwrap_or(&pixels[..expected]);
     let encoded = qoi
         .encode_alloc(encode_data)
-        .map_err(|e| whereat::at!(BitmapError::InvalidData(alloc::format!("{e:?}"))))?;
+        .map_err(|e| whereat::at!(BitmapError::invalid_data(alloc::format!("{e:?}"))))?;
 
     Ok(encoded)
 }
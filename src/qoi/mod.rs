@@ -10,6 +10,9 @@
 pub(crate) mod decode;
 mod encode;
 pub(crate) mod rapid_qoi;
+mod rows;
+
+pub use rows::QoiRows;
 
 use crate::alloc_util::AllocPref;
 use crate::decode::DecodeOutput;
@@ -40,6 +43,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
     let hdr = decode::parse_header(data)?;
     let (width, height, has_alpha) = (hdr.width, hdr.height, hdr.has_alpha);
     limits::check_dimensions(width, height, limits)?;
@@ -64,6 +68,15 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     Ok(DecodeOutput::owned(pixels, width, height, layout))
 }
 
+/// Row-at-a-time QOI decoder.
+pub(crate) fn rows<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: S,
+) -> crate::Result<QoiRows<'a, S>> {
+    rows::rows(data, limits, stop)
+}
+
 /// Encode pixels as QOI.
 pub(crate) fn encode(
     pixels: &[u8],
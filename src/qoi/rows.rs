@@ -0,0 +1,121 @@
+//! Row-at-a-time QOI decoding (internal).
+//!
+//! Use top-level [`crate::decode_qoi_rows`]. Decode state (the running index,
+//! previous pixel and any unfinished run) carries across rows, so only one
+//! decoded row is held in memory.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use super::decode::{self, QoiDecodeState};
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+enum State {
+    Rgb(QoiDecodeState<3>),
+    Rgba(QoiDecodeState<4>),
+}
+
+/// QOI decoder that decodes one row at a time.
+///
+/// Returned by [`crate::decode_qoi_rows`]. Each call to
+/// [`next_row`](Self::next_row) yields the next row, top to bottom, as
+/// [`PixelLayout::Rgb8`] or [`PixelLayout::Rgba8`] bytes in a buffer reused
+/// between rows. Truncated input yields [`BitmapError::UnexpectedEof`] and
+/// ends the decode.
+pub struct QoiRows<'a, S> {
+    data: &'a [u8],
+    offset: usize,
+    width: u32,
+    height: u32,
+    next: u32,
+    state: State,
+    row: Vec<u8>,
+    stop: S,
+}
+
+impl<S: Stop> QoiRows<'_, S> {
+    /// Image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Layout of each row: [`PixelLayout::Rgba8`] for files with alpha,
+    /// [`PixelLayout::Rgb8`] otherwise.
+    pub fn layout(&self) -> PixelLayout {
+        match self.state {
+            State::Rgb(_) => PixelLayout::Rgb8,
+            State::Rgba(_) => PixelLayout::Rgba8,
+        }
+    }
+
+    /// Rows not yet returned by [`next_row`](Self::next_row).
+    pub fn rows_remaining(&self) -> u32 {
+        self.height - self.next
+    }
+
+    /// Decode and return the next row, or `None` after the last one.
+    pub fn next_row(&mut self) -> Option<crate::Result<&[u8]>> {
+        if self.next == self.height {
+            return None;
+        }
+        if self.next.is_multiple_of(16)
+            && let Err(r) = self.stop.check()
+        {
+            self.next = self.height;
+            return Some(Err(at!(BitmapError::from(r))));
+        }
+        let encoded = &self.data[self.offset..];
+        let consumed = match &mut self.state {
+            State::Rgb(state) => state.decode_into(encoded, &mut self.row),
+            State::Rgba(state) => state.decode_into(encoded, &mut self.row),
+        };
+        let Ok(consumed) = consumed else {
+            self.next = self.height;
+            return Some(Err(at!(BitmapError::unexpected_eof())));
+        };
+        self.offset += consumed;
+        self.next += 1;
+        Some(Ok(&self.row))
+    }
+}
+
+pub(crate) fn rows<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: S,
+) -> crate::Result<QoiRows<'a, S>> {
+    limits::check_file_size(data.len(), limits)?;
+    let header = decode::parse_header(data)?;
+    let (width, height) = (header.width, header.height);
+    limits::check_dimensions(width, height, limits)?;
+    let channels = if header.has_alpha { 4 } else { 3 };
+    let row_bytes = (width as usize)
+        .checked_mul(channels)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(row_bytes, limits)?;
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+    let state = if header.has_alpha {
+        State::Rgba(QoiDecodeState::new())
+    } else {
+        State::Rgb(QoiDecodeState::new())
+    };
+    Ok(QoiRows {
+        data,
+        offset: 14,
+        width,
+        height,
+        next: 0,
+        state,
+        row: vec![0; row_bytes],
+        stop,
+    })
+}
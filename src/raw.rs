@@ -0,0 +1,122 @@
+//! Headerless raw pixel dumps (internal).
+//!
+//! Use top-level [`crate::decode_raw`]. Rows are `stride` bytes apart; only
+//! the first `width * bytes_per_pixel` bytes of each are pixels, and the
+//! last row needs no padding. Rows whose samples are already in native byte
+//! order are borrowed rather than copied, padding and all.
+
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Byte order of multi-byte samples in raw input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Endianness {
+    /// Least significant byte first (x86, ARM, most camera pipelines).
+    Little,
+    /// Most significant byte first (network order, Photoshop on old Macs).
+    Big,
+    /// The byte order of the target this code runs on.
+    #[default]
+    Native,
+}
+
+impl Endianness {
+    fn is_native(self) -> bool {
+        match self {
+            Self::Native => true,
+            Self::Little => cfg!(target_endian = "little"),
+            Self::Big => cfg!(target_endian = "big"),
+        }
+    }
+}
+
+/// Bytes per sample that need swapping for `layout`; 1 when none do.
+fn sample_size(layout: PixelLayout) -> usize {
+    match layout {
+        PixelLayout::Gray16 | PixelLayout::Rgb16 | PixelLayout::Rgba16 | PixelLayout::GrayA16 => 2,
+        PixelLayout::GrayF32
+        | PixelLayout::RgbF32
+        | PixelLayout::RgbaF32
+        | PixelLayout::RgbPlanarF32 => 4,
+        // 8-bit samples, and `Rgba16Be`, which is big-endian by definition.
+        _ => 1,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode<'a>(
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    stride: usize,
+    endianness: Endianness,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "raw width or height is zero"
+        )));
+    }
+    limits::check_dimensions(width, height, limits)?;
+    let (w, h) = (width as usize, height as usize);
+    let row_bytes = w
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if stride < row_bytes {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "raw stride {stride} is less than the {row_bytes}-byte row"
+        ))));
+    }
+    if layout.is_planar() && stride != row_bytes {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "raw {layout:?} planes have no row padding; stride must be {row_bytes}"
+        ))));
+    }
+    let needed = stride
+        .checked_mul(h - 1)
+        .and_then(|n| n.checked_add(row_bytes))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if data.len() < needed {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+
+    let swap = sample_size(layout);
+    let swap = if swap > 1 && !endianness.is_native() {
+        swap
+    } else {
+        1
+    };
+    if swap == 1 {
+        return Ok(
+            DecodeOutput::borrowed(&data[..needed], width, height, layout).with_stride(stride),
+        );
+    }
+
+    let out_bytes = row_bytes
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+    for (y, dst) in out.chunks_exact_mut(row_bytes).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        dst.copy_from_slice(&data[y * stride..y * stride + row_bytes]);
+        if swap > 1 {
+            for sample in dst.chunks_exact_mut(swap) {
+                sample.reverse();
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,75 @@
+//! Crop-window helpers shared by the region decoders.
+//!
+//! BMP and binary PNM seek straight to the first needed row and decode only
+//! the rows of the window; everything else decodes in full and is cropped
+//! here.
+
+use alloc::vec::Vec;
+use whereat::at;
+
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+
+/// A crop window in output (top-down) pixel coordinates.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// Reject empty windows and windows reaching outside a
+    /// `width`×`height` image.
+    pub(crate) fn check(&self, width: u32, height: u32) -> crate::Result<()> {
+        let fits = |start: u32, len: u32, max: u32| {
+            len > 0 && start.checked_add(len).is_some_and(|end| end <= max)
+        };
+        if !fits(self.x, self.width, width) || !fits(self.y, self.height, height) {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "region {}x{} at ({}, {}) is empty or outside the {width}x{height} image",
+                self.width,
+                self.height,
+                self.x,
+                self.y
+            ))));
+        }
+        Ok(())
+    }
+
+    /// Output rows covered by the window.
+    #[cfg_attr(not(feature = "bmp"), allow(dead_code))]
+    pub(crate) fn rows(&self) -> core::ops::Range<usize> {
+        self.y as usize..self.y as usize + self.height as usize
+    }
+
+    /// Keep columns `x..x + width` of each `row_width`-pixel row in `pixels`,
+    /// compacting in place.
+    pub(crate) fn crop_columns(&self, mut pixels: Vec<u8>, row_width: u32, bpp: usize) -> Vec<u8> {
+        let row_bytes = row_width as usize * bpp;
+        let keep = self.width as usize * bpp;
+        if keep == row_bytes {
+            return pixels;
+        }
+        let skip = self.x as usize * bpp;
+        let rows = pixels.len() / row_bytes.max(1);
+        for row in 0..rows {
+            let src = row * row_bytes + skip;
+            pixels.copy_within(src..src + keep, row * keep);
+        }
+        pixels.truncate(rows * keep);
+        pixels
+    }
+
+    /// Crop a fully decoded image.
+    pub(crate) fn crop<'a>(&self, full: DecodeOutput<'_>) -> DecodeOutput<'a> {
+        let bpp = full.layout.bytes_per_pixel();
+        let mut rows = Vec::with_capacity(self.height as usize * full.width as usize * bpp);
+        for row in full.rows().skip(self.y as usize).take(self.height as usize) {
+            rows.extend_from_slice(row);
+        }
+        let pixels = self.crop_columns(rows, full.width, bpp);
+        DecodeOutput::owned(pixels, self.width, self.height, full.layout)
+    }
+}
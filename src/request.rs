@@ -0,0 +1,239 @@
+//! [`DecodeRequest`]: one builder for the auto-detecting decode paths.
+
+use enough::{Stop, Unstoppable};
+use whereat::at;
+
+#[cfg(feature = "bmp")]
+use crate::bmp::{self, BmpDecodeOptions, BmpPermissiveness};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::{ImageFormat, PixelLayout};
+use crate::{detect_format, farbfeld, pnm, rows};
+
+/// A decode of any auto-detected format, configured step by step.
+///
+/// Covers every format [`decode`](crate::decode) detects (PNM, farbfeld,
+/// and BMP, QOI, TGA and HDR with their features) and folds the knobs of
+/// the per-format variants into one call chain: resource limits, BMP
+/// permissiveness, storage byte order and a final layout conversion.
+/// [`decode`](crate::decode) and [`decode_with_limits`](crate::decode_with_limits)
+/// are shorthands for the plain request.
+///
+/// ```
+/// use zenbitmaps::*;
+/// let ppm = encode_ppm(&[255, 128, 0], 1, 1, PixelLayout::Rgb8, Unstoppable)?;
+/// let limits = Limits::web_safe();
+/// let decoded = DecodeRequest::new(&ppm)
+///     .with_limits(&limits)
+///     .with_stop(Unstoppable)
+///     .decode()?;
+/// assert_eq!(decoded.pixels(), &[255, 128, 0]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct DecodeRequest<'a, S = Unstoppable> {
+    data: &'a [u8],
+    limits: Option<&'a Limits>,
+    settings: Settings,
+    stop: S,
+}
+
+/// The knobs of a [`DecodeRequest`] that shape the output.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Settings {
+    #[cfg(feature = "bmp")]
+    pub(crate) permissiveness: BmpPermissiveness,
+    pub(crate) native_order: bool,
+    #[cfg(feature = "convert")]
+    pub(crate) target_layout: Option<PixelLayout>,
+}
+
+impl<'a> DecodeRequest<'a> {
+    /// A request to decode `data` with default settings: no limits beyond
+    /// the built-in pixel cap, `BmpPermissiveness::Standard`, RGB byte
+    /// order, the format's own layout, and no cancellation.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            limits: None,
+            settings: Settings::default(),
+            stop: Unstoppable,
+        }
+    }
+}
+
+impl<'a, S: Stop> DecodeRequest<'a, S> {
+    /// Enforce `limits`, including its `max_duration` deadline.
+    pub fn with_limits(mut self, limits: &'a Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Validation level for BMP input; other formats ignore it.
+    #[cfg(feature = "bmp")]
+    pub fn with_permissiveness(mut self, permissiveness: BmpPermissiveness) -> Self {
+        self.settings.permissiveness = permissiveness;
+        self
+    }
+
+    /// Return pixels in the order the file stores them where that saves
+    /// work: BGR/BGRA for BMP (as `decode_bmp_native`) and big-endian [`PixelLayout::Rgba16Be`](crate::PixelLayout::Rgba16Be)
+    /// borrowed from the input for farbfeld (as
+    /// [`decode_farbfeld_native`](crate::decode_farbfeld_native)). Other
+    /// formats decode as usual.
+    pub fn with_native_order(mut self, native_order: bool) -> Self {
+        self.settings.native_order = native_order;
+        self
+    }
+
+    /// Convert the decoded pixels to `layout`, as
+    /// [`DecodeOutput::convert_to`] does. Output already in `layout` is
+    /// returned as is.
+    #[cfg(feature = "convert")]
+    pub fn with_target_layout(mut self, layout: PixelLayout) -> Self {
+        self.settings.target_layout = Some(layout);
+        self
+    }
+
+    /// Check `stop` for cancellation while decoding.
+    pub fn with_stop<T: Stop>(self, stop: T) -> DecodeRequest<'a, T> {
+        DecodeRequest {
+            data: self.data,
+            limits: self.limits,
+            settings: self.settings,
+            stop,
+        }
+    }
+
+    /// The limits set with [`with_limits`](Self::with_limits).
+    pub fn limits(&self) -> Option<&'a Limits> {
+        self.limits
+    }
+
+    /// The BMP validation level.
+    #[cfg(feature = "bmp")]
+    pub fn permissiveness(&self) -> BmpPermissiveness {
+        self.settings.permissiveness
+    }
+
+    /// Whether storage byte order is preferred.
+    pub fn native_order(&self) -> bool {
+        self.settings.native_order
+    }
+
+    /// The layout the output is converted to, if any.
+    #[cfg(feature = "convert")]
+    pub fn target_layout(&self) -> Option<PixelLayout> {
+        self.settings.target_layout
+    }
+
+    /// Detect the format and decode.
+    pub fn decode(self) -> crate::Result<DecodeOutput<'a>> {
+        match self.limits {
+            Some(limits) => dispatch(
+                self.data,
+                Some(limits),
+                &self.settings,
+                &limits.deadline(&self.stop),
+            ),
+            None => dispatch(self.data, None, &self.settings, &self.stop),
+        }
+    }
+
+    /// Detect the format and decode, passing each output row to `f` as
+    /// `f(y, row)`, top to bottom, instead of returning the whole frame.
+    /// Returns the width, height and layout of the rows. A planar target
+    /// layout has no rows and fails with [`BitmapError::InvalidArgument`].
+    ///
+    /// See [`decode_rows`](crate::decode_rows) for which formats stream.
+    ///
+    /// [`BitmapError::InvalidArgument`]: crate::BitmapError::InvalidArgument
+    pub fn decode_rows(
+        self,
+        mut f: impl FnMut(u32, &[u8]),
+    ) -> crate::Result<(u32, u32, PixelLayout)> {
+        match self.limits {
+            Some(limits) => rows::decode_rows(
+                self.data,
+                Some(limits),
+                &self.settings,
+                &limits.deadline(&self.stop),
+                &mut f,
+            ),
+            None => rows::decode_rows(self.data, None, &self.settings, &self.stop, &mut f),
+        }
+    }
+}
+
+/// Detect the format of `data` and decode it according to `settings`.
+pub(crate) fn dispatch<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    settings: &Settings,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    let decoded = decode_detected(data, limits, settings, stop)?;
+    #[cfg(feature = "convert")]
+    if let Some(layout) = settings.target_layout {
+        return decoded.convert_to(layout);
+    }
+    Ok(decoded)
+}
+
+fn decode_detected<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    settings: &Settings,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    match detect_format(data) {
+        #[cfg(feature = "bmp")]
+        Some(ImageFormat::Bmp) => {
+            let options = BmpDecodeOptions::new().with_permissiveness(settings.permissiveness);
+            if settings.native_order {
+                bmp::decode_native(data, limits, &options, stop)
+            } else {
+                bmp::decode_with_options(data, limits, &options, stop)
+            }
+        }
+        #[cfg(not(feature = "bmp"))]
+        Some(ImageFormat::Bmp) => Err(at!(BitmapError::UnsupportedVariant(
+            "BMP support requires the 'bmp' feature".into(),
+        ))),
+        Some(ImageFormat::Farbfeld) if settings.native_order => {
+            farbfeld::decode_native(data, limits, stop)
+        }
+        Some(ImageFormat::Farbfeld) => farbfeld::decode(data, limits, stop),
+        Some(ImageFormat::Qoi) => {
+            #[cfg(feature = "qoi")]
+            return crate::qoi::decode(data, limits, stop);
+            #[cfg(not(feature = "qoi"))]
+            return Err(at!(BitmapError::UnsupportedVariant(
+                "QOI support requires the 'qoi' feature".into(),
+            )));
+        }
+        Some(ImageFormat::Pnm) => pnm::decode(data, limits, stop),
+        Some(ImageFormat::Hdr) => {
+            #[cfg(feature = "hdr")]
+            return crate::hdr::decode(data, limits, stop);
+            #[cfg(not(feature = "hdr"))]
+            return Err(at!(BitmapError::UnsupportedVariant(
+                "HDR support requires the 'hdr' feature".into(),
+            )));
+        }
+        Some(ImageFormat::Tga) => {
+            #[cfg(feature = "tga")]
+            return crate::tga::decode(data, limits, stop);
+            #[cfg(not(feature = "tga"))]
+            return Err(at!(BitmapError::UnsupportedVariant(
+                "TGA support requires the 'tga' feature".into(),
+            )));
+        }
+        #[cfg(any(feature = "flate2", feature = "bzip2"))]
+        None if farbfeld::is_compressed(data) => farbfeld::decode(data, limits, stop),
+        None => Err(at!(BitmapError::UnrecognizedFormat)),
+    }
+}
@@ -0,0 +1,193 @@
+//! Row-callback decoding behind [`crate::decode_rows`] and
+//! [`DecodeRequest::decode_rows`](crate::DecodeRequest::decode_rows).
+//!
+//! Binary PNM and uncompressed BMP are decoded a band of rows at a time
+//! through their region decoders, and farbfeld and QOI through their row
+//! decoders, so only one band is ever held in memory (8-bit PNM rows are
+//! borrowed from the input). Everything else decodes in full and is handed
+//! out row by row.
+
+use enough::Stop;
+
+#[cfg(feature = "bmp")]
+use crate::bmp::{self, BmpCompression, BmpDecodeOptions};
+#[cfg(feature = "convert")]
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::{ImageFormat, PixelLayout};
+use crate::region::Region;
+use crate::request::{self, Settings};
+use crate::{detect_format, farbfeld, pnm};
+
+/// Rows decoded per band by the region decoders.
+const BAND_ROWS: u32 = 16;
+
+/// Decode `data` according to `settings`, passing each output row to `f`
+/// top to bottom. Returns the image's width, height and row layout.
+pub(crate) fn decode_rows(
+    data: &[u8],
+    limits: Option<&Limits>,
+    settings: &Settings,
+    stop: &dyn Stop,
+    f: &mut dyn FnMut(u32, &[u8]),
+) -> crate::Result<(u32, u32, PixelLayout)> {
+    // A planar image has no rows to hand out: each band would carry its
+    // own planes.
+    #[cfg(feature = "convert")]
+    if let Some(target) = settings.target_layout.filter(PixelLayout::is_planar) {
+        return Err(whereat::at!(BitmapError::InvalidArgument(alloc::format!(
+            "decode_rows cannot produce planar {target:?} rows"
+        ))));
+    }
+    limits::check_file_size(data.len(), limits)?;
+    match detect_format(data) {
+        // ASCII PNM has no fixed row stride to seek by.
+        Some(ImageFormat::Pnm) if !matches!(data[1], b'1' | b'2' | b'3') => {
+            let header = pnm::decode::parse_header(data)?;
+            limits::check_dimensions(header.width, header.height, limits)?;
+            let mut sink = Sink::new(header.width, header.layout, settings, f);
+            for region in bands(header.width, header.height) {
+                let band = pnm::decode_region(data, region, limits, stop)?;
+                sink.band(band.pixels(), band.layout, stop)?;
+            }
+            Ok(sink.finish(header.height))
+        }
+        #[cfg(feature = "bmp")]
+        Some(ImageFormat::Bmp) if !settings.native_order && decodes_in_bands(data) => {
+            let meta = bmp::probe(data)?;
+            limits::check_dimensions(meta.width, meta.height, limits)?;
+            let options = BmpDecodeOptions::new().with_permissiveness(settings.permissiveness);
+            let mut sink = Sink::new(meta.width, meta.layout, settings, f);
+            for region in bands(meta.width, meta.height) {
+                let band = bmp::decode_region(data, region, limits, &options, stop)?;
+                sink.band(band.pixels(), band.layout, stop)?;
+            }
+            Ok(sink.finish(meta.height))
+        }
+        Some(ImageFormat::Farbfeld) if !settings.native_order => {
+            let mut rows = farbfeld::rows(data, limits, stop)?;
+            let mut sink = Sink::new(rows.width(), rows.layout(), settings, f);
+            while let Some(row) = rows.next_row() {
+                sink.band(row?, PixelLayout::Rgba16, stop)?;
+            }
+            Ok(sink.finish(rows.height()))
+        }
+        #[cfg(feature = "qoi")]
+        Some(ImageFormat::Qoi) => {
+            let mut rows = crate::qoi::rows(data, limits, stop)?;
+            let layout = rows.layout();
+            let mut sink = Sink::new(rows.width(), layout, settings, f);
+            while let Some(row) = rows.next_row() {
+                sink.band(row?, layout, stop)?;
+            }
+            Ok(sink.finish(rows.height()))
+        }
+        _ => {
+            let decoded = request::dispatch(data, limits, settings, stop)?;
+            for (y, row) in (0..).zip(decoded.rows()) {
+                f(y, row);
+            }
+            Ok((decoded.width, decoded.height, decoded.layout))
+        }
+    }
+}
+
+/// Full-width windows of at most [`BAND_ROWS`] rows covering a
+/// `width`×`height` image, top to bottom.
+fn bands(width: u32, height: u32) -> impl Iterator<Item = Region> {
+    (0..height)
+        .step_by(BAND_ROWS as usize)
+        .map(move |y| Region {
+            x: 0,
+            y,
+            width,
+            height: BAND_ROWS.min(height - y),
+        })
+}
+
+/// Whether a band-by-band BMP decode matches a full one: uncompressed
+/// data, except 32-bit `BI_RGB`, whose `BmpAlphaPolicy::Auto` test looks at
+/// every pixel. RLE streams would have to be rerun for every band, and
+/// truncated files are left to the full decode, which zero-fills the rows
+/// past the end.
+#[cfg(feature = "bmp")]
+fn decodes_in_bands(data: &[u8]) -> bool {
+    bmp::probe(data).is_ok_and(|meta| match meta.compression {
+        BmpCompression::Rgb => meta.bit_count != 32,
+        BmpCompression::Bitfields => true,
+        _ => false,
+    }) && bmp::rows_complete(data)
+}
+
+/// Numbers decoded rows, converts them to the requested layout, if any,
+/// and hands them to the callback.
+struct Sink<'f> {
+    width: u32,
+    layout: PixelLayout,
+    #[cfg(feature = "convert")]
+    target: Option<PixelLayout>,
+    next: u32,
+    f: &'f mut dyn FnMut(u32, &[u8]),
+}
+
+impl<'f> Sink<'f> {
+    #[cfg_attr(not(feature = "convert"), allow(unused_variables))]
+    fn new(
+        width: u32,
+        layout: PixelLayout,
+        settings: &Settings,
+        f: &'f mut dyn FnMut(u32, &[u8]),
+    ) -> Self {
+        Self {
+            width,
+            layout,
+            #[cfg(feature = "convert")]
+            target: settings.target_layout,
+            next: 0,
+            f,
+        }
+    }
+
+    /// Pass on the packed `layout` rows in `pixels`.
+    #[cfg_attr(not(feature = "convert"), allow(unused_variables))]
+    fn band(&mut self, pixels: &[u8], layout: PixelLayout, stop: &dyn Stop) -> crate::Result<()> {
+        self.layout = layout;
+        #[cfg(feature = "convert")]
+        if let Some(target) = self.target.filter(|&target| target != layout) {
+            let rows = pixels.len() / self.row_bytes(layout);
+            let converted = crate::convert::convert(
+                pixels,
+                self.width,
+                rows as u32,
+                layout,
+                target,
+                &crate::ConvertOptions::new(),
+                stop,
+            )?;
+            self.emit(&converted, target);
+            return Ok(());
+        }
+        self.emit(pixels, layout);
+        Ok(())
+    }
+
+    fn emit(&mut self, pixels: &[u8], layout: PixelLayout) {
+        for row in pixels.chunks_exact(self.row_bytes(layout)) {
+            (self.f)(self.next, row);
+            self.next += 1;
+        }
+    }
+
+    fn row_bytes(&self, layout: PixelLayout) -> usize {
+        (self.width as usize * layout.bytes_per_pixel()).max(1)
+    }
+
+    /// Width, height and the layout the rows were handed out in.
+    fn finish(self, height: u32) -> (u32, u32, PixelLayout) {
+        #[cfg(feature = "convert")]
+        if let Some(target) = self.target {
+            return (self.width, height, target);
+        }
+        (self.width, height, self.layout)
+    }
+}
@@ -0,0 +1,206 @@
+//! Sixel encoding for inline terminal preview (internal).
+//!
+//! Use top-level [`crate::encode_sixel`].
+//!
+//! A sixel image is a DCS escape sequence: `ESC P 0;1;0 q`, a raster
+//! attribute `"1;1;W;H`, color register definitions `#n;2;r;g;b` in
+//! percent, then bands six pixels tall. Each band is drawn one color at a
+//! time: `#n` selects the register, each character from `?` to `~` sets
+//! the band's six pixels in one column (`!count` repeats it), `$` returns
+//! to the band's start and `-` moves to the next band. `ESC \` ends the
+//! image. Colors go through the BMP median-cut quantizer; pixels with alpha
+//! below 128 are left unset, which terminals show as background.
+
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::bmp::quantize;
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+
+/// Options for sixel encoding with [`crate::encode_sixel`].
+///
+/// ```
+/// use zenbitmaps::SixelEncodeOptions;
+///
+/// let options = SixelEncodeOptions::new().with_max_colors(16);
+/// assert_eq!(options.max_colors(), 16);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixelEncodeOptions {
+    max_colors: u16,
+}
+
+impl Default for SixelEncodeOptions {
+    fn default() -> Self {
+        Self { max_colors: 256 }
+    }
+}
+
+impl SixelEncodeOptions {
+    /// Default options: a palette of up to 256 colors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Palette size, 1 to 256. Images with at most this many distinct
+    /// colors keep them (to sixel's percent precision); others are reduced
+    /// by median cut without dithering. Many terminals only have 256
+    /// color registers.
+    pub fn with_max_colors(mut self, max_colors: u16) -> Self {
+        self.max_colors = max_colors;
+        self
+    }
+
+    /// Palette size.
+    pub fn max_colors(&self) -> u16 {
+        self.max_colors
+    }
+}
+
+/// RGBA of pixel `i`; 16-bit layouts keep the high byte.
+fn rgba(pixels: &[u8], i: usize, layout: PixelLayout) -> [u8; 4] {
+    let p = &pixels[i * layout.bytes_per_pixel()..];
+    let hi = |s: usize| (u16::from_ne_bytes([p[2 * s], p[2 * s + 1]]) >> 8) as u8;
+    match layout {
+        PixelLayout::Gray8 => [p[0], p[0], p[0], 255],
+        PixelLayout::Rgb8 | PixelLayout::Rgbx8 => [p[0], p[1], p[2], 255],
+        PixelLayout::Bgr8 | PixelLayout::Bgrx8 => [p[2], p[1], p[0], 255],
+        PixelLayout::Rgba8 => [p[0], p[1], p[2], p[3]],
+        PixelLayout::Bgra8 => [p[2], p[1], p[0], p[3]],
+        PixelLayout::Gray16 => [hi(0), hi(0), hi(0), 255],
+        PixelLayout::GrayA16 => [hi(0), hi(0), hi(0), hi(1)],
+        PixelLayout::Rgb16 => [hi(0), hi(1), hi(2), 255],
+        _ => [hi(0), hi(1), hi(2), hi(3)],
+    }
+}
+
+/// Append `count` repeats of the sixel character for `bits`.
+fn push_run(out: &mut Vec<u8>, bits: u8, count: usize) {
+    let c = b'?' + bits;
+    if count > 3 {
+        out.extend_from_slice(alloc::format!("!{count}").as_bytes());
+        out.push(c);
+    } else {
+        out.extend(core::iter::repeat_n(c, count));
+    }
+}
+
+pub(crate) fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &SixelEncodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    if !matches!(
+        layout,
+        PixelLayout::Gray8
+            | PixelLayout::Rgb8
+            | PixelLayout::Bgr8
+            | PixelLayout::Rgba8
+            | PixelLayout::Bgra8
+            | PixelLayout::Rgbx8
+            | PixelLayout::Bgrx8
+            | PixelLayout::Gray16
+            | PixelLayout::GrayA16
+            | PixelLayout::Rgb16
+            | PixelLayout::Rgba16
+    ) {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "cannot encode {layout:?} as sixel (supported: 8-bit gray/RGB/BGR with or \
+             without alpha or padding, Gray16, GrayA16, Rgb16, Rgba16)"
+        ))));
+    }
+    if !(1..=256).contains(&options.max_colors) {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "sixel palette of {} colors is not supported; use 1 to 256",
+            options.max_colors
+        ))));
+    }
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "sixel width or height is zero"
+        )));
+    }
+    let (w, h) = (width as usize, height as usize);
+    let expected = w
+        .checked_mul(h)
+        .and_then(|n| n.checked_mul(layout.bytes_per_pixel()))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+    stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+
+    // Quantize the opaque pixels; `None` marks transparent ones.
+    let mut opaque = Vec::with_capacity(w * h);
+    let mut colors = Vec::with_capacity(w * h);
+    for i in 0..w * h {
+        let [r, g, b, a] = rgba(pixels, i, layout);
+        opaque.push(a >= 128);
+        if a >= 128 {
+            colors.push([r, g, b]);
+        }
+    }
+    let (palette, indices) = quantize::quantize(&colors, usize::from(options.max_colors), stop)?;
+    let mut indices = indices.into_iter();
+    let index: Vec<Option<u8>> = opaque
+        .iter()
+        .map(|&o| if o { indices.next() } else { None })
+        .collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"\x1bP0;1;0q");
+    out.extend_from_slice(alloc::format!("\"1;1;{width};{height}").as_bytes());
+    let percent = |v: u8| (u32::from(v) * 100 + 127) / 255;
+    for (n, &[r, g, b]) in palette.iter().enumerate() {
+        let (r, g, b) = (percent(r), percent(g), percent(b));
+        out.extend_from_slice(alloc::format!("#{n};2;{r};{g};{b}").as_bytes());
+    }
+
+    let mut bits = alloc::vec![0u8; w];
+    for (band, top) in (0..h).step_by(6).enumerate() {
+        if band.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let rows = &index[top * w..(top + 6).min(h) * w];
+        let mut used = alloc::vec![false; palette.len()];
+        for &i in rows.iter().flatten() {
+            used[usize::from(i)] = true;
+        }
+        let mut first = true;
+        for color in (0..palette.len()).filter(|&c| used[c]) {
+            for (x, column) in bits.iter_mut().enumerate() {
+                *column = rows
+                    .chunks_exact(w)
+                    .enumerate()
+                    .filter(|(_, row)| row[x] == Some(color as u8))
+                    .fold(0, |acc, (dy, _)| acc | 1 << dy);
+            }
+            // Trailing empty columns need not be drawn.
+            let end = bits.iter().rposition(|&b| b != 0).map_or(0, |e| e + 1);
+            if !first {
+                out.push(b'$');
+            }
+            first = false;
+            out.extend_from_slice(alloc::format!("#{color}").as_bytes());
+            let mut x = 0;
+            while x < end {
+                let run = bits[x..end].iter().take_while(|&&b| b == bits[x]).count();
+                push_run(&mut out, bits[x], run);
+                x += run;
+            }
+        }
+        if top + 6 < h {
+            out.push(b'-');
+        }
+    }
+    out.extend_from_slice(b"\x1b\\");
+    Ok(out)
+}
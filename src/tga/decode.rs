@@ -64,7 +64,7 @@ impl TgaHeader {
 /// Parse and validate the 18-byte TGA header.
 pub(crate) fn parse_header(data: &[u8]) -> crate::Result<TgaHeader> {
     if data.len() < 18 {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(BitmapError::unexpected_eof()));
     }
 
     let header = TgaHeader {
@@ -91,19 +91,19 @@ pub(crate) fn parse_header(data: &[u8]) -> crate::Result<TgaHeader> {
 
     // Validate dimensions
     if header.width == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "TGA width is zero".into()
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "TGA width is zero"
         )));
     }
     if header.height == 0 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "TGA height is zero".into()
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "TGA height is zero"
         )));
     }
 
     // Validate color map type
     if header.color_map_type > 1 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(alloc::format!(
+        return Err(whereat::at!(BitmapError::invalid_header(alloc::format!(
             "TGA color_map_type {} is invalid (must be 0 or 1)",
             header.color_map_type
         ))));
@@ -111,8 +111,8 @@ pub(crate) fn parse_header(data: &[u8]) -> crate::Result<TgaHeader> {
 
     // Color-mapped images must have a color map
     if header.is_color_mapped() && header.color_map_type != 1 {
-        return Err(whereat::at!(BitmapError::InvalidHeader(
-            "TGA color-mapped image must have color_map_type=1".into()
+        return Err(whereat::at!(BitmapError::invalid_header(
+            "TGA color-mapped image must have color_map_type=1"
         )));
     }
 
@@ -196,15 +196,13 @@ pub(crate) fn decode_pixels(
         };
         let map_size = (header.color_map_length as usize)
             .checked_mul(entry_bytes)
-            .ok_or_else(|| {
-                whereat::at!(BitmapError::InvalidHeader("color map size overflow".into()))
-            })?;
+            .ok_or_else(|| whereat::at!(BitmapError::invalid_header("color map size overflow")))?;
         let map_start = pixel_data_offset;
         let map_end = map_start
             .checked_add(map_size)
-            .ok_or_else(|| whereat::at!(BitmapError::UnexpectedEof))?;
+            .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))?;
         if data.len() < map_end {
-            return Err(whereat::at!(BitmapError::UnexpectedEof));
+            return Err(whereat::at!(BitmapError::unexpected_eof()));
         }
         (Some(&data[map_start..map_end]), map_end)
     } else {
@@ -213,7 +211,7 @@ pub(crate) fn decode_pixels(
 
     let pixel_data = data
         .get(color_map_end..)
-        .ok_or_else(|| whereat::at!(BitmapError::UnexpectedEof))?;
+        .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))?;
 
     // Determine output layout
     let (layout, out_channels) = if header.is_grayscale() {
@@ -275,6 +273,20 @@ pub(crate) fn decode_pixels(
         )?;
     }
 
+    // The TGA 2.0 extension area, when present, says what the attribute
+    // bits hold; without it they are taken as straight alpha.
+    if out_channels == 4 {
+        match attributes_type(data) {
+            Some(ATTRIBUTES_NONE | ATTRIBUTES_IGNORE) => {
+                for pixel in out.chunks_exact_mut(4) {
+                    pixel[3] = 255;
+                }
+            }
+            Some(ATTRIBUTES_PREMULTIPLIED) => unpremultiply(&mut out),
+            _ => {}
+        }
+    }
+
     // Handle right-to-left origin
     if header.is_right_to_left() {
         flip_horizontal(&mut out, w, h, out_channels);
@@ -288,6 +300,44 @@ pub(crate) fn decode_pixels(
     Ok((out, layout))
 }
 
+/// Extension-area attributes type: no alpha data.
+const ATTRIBUTES_NONE: u8 = 0;
+/// Extension-area attributes type: undefined data, may be ignored.
+const ATTRIBUTES_IGNORE: u8 = 1;
+/// Extension-area attributes type: premultiplied alpha.
+const ATTRIBUTES_PREMULTIPLIED: u8 = 4;
+
+/// Attributes type (extension area byte 494) from a TGA 2.0 file.
+///
+/// `None` for original TGA files, which have no footer, and for footers
+/// pointing at a missing or malformed extension area.
+fn attributes_type(data: &[u8]) -> Option<u8> {
+    let footer_start = data.len().checked_sub(26)?;
+    let footer = &data[footer_start..];
+    if &footer[8..] != b"TRUEVISION-XFILE.\0" {
+        return None;
+    }
+    let ext = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]) as usize;
+    let area = data.get(ext..ext.checked_add(495)?.min(footer_start))?;
+    // Offset 0 means "no extension area"; the area starts with its own size.
+    if ext == 0 || area.len() != 495 || area[..2] != 495u16.to_le_bytes() {
+        return None;
+    }
+    Some(area[494])
+}
+
+/// Convert premultiplied RGBA8 to straight alpha in place.
+fn unpremultiply(buf: &mut [u8]) {
+    for pixel in buf.chunks_exact_mut(4) {
+        let a = pixel[3] as u16;
+        if a != 0 && a != 255 {
+            for c in &mut pixel[..3] {
+                *c = ((*c as u16 * 255 + a / 2) / a).min(255) as u8;
+            }
+        }
+    }
+}
+
 /// Decode uncompressed pixel data.
 fn decode_raw(
     pixel_data: &[u8],
@@ -315,7 +365,7 @@ fn decode_raw(
     })?;
 
     if pixel_data.len() < total_src_bytes {
-        return Err(whereat::at!(BitmapError::UnexpectedEof));
+        return Err(whereat::at!(BitmapError::unexpected_eof()));
     }
 
     // Fast path: 24-bit or 32-bit non-color-mapped — memcpy + batch swizzle
@@ -395,7 +445,7 @@ fn decode_rle(
         }
 
         if src_pos >= pixel_data.len() {
-            return Err(whereat::at!(BitmapError::UnexpectedEof));
+            return Err(whereat::at!(BitmapError::unexpected_eof()));
         }
 
         let packet_header = pixel_data[src_pos];
@@ -405,15 +455,15 @@ fn decode_rle(
         let is_rle_packet = packet_header & 0x80 != 0;
 
         if pixel_idx + run_count > total_pixels {
-            return Err(whereat::at!(BitmapError::InvalidData(
-                "TGA RLE packet exceeds image bounds".into()
+            return Err(whereat::at!(BitmapError::invalid_data(
+                "TGA RLE packet exceeds image bounds"
             )));
         }
 
         if is_rle_packet {
             // Run-length packet: one pixel value repeated
             if src_pos + src_bpp > pixel_data.len() {
-                return Err(whereat::at!(BitmapError::UnexpectedEof));
+                return Err(whereat::at!(BitmapError::unexpected_eof()));
             }
             let src = &pixel_data[src_pos..src_pos + src_bpp];
             src_pos += src_bpp;
@@ -431,9 +481,9 @@ fn decode_rle(
             // Raw packet: run_count literal pixels
             let needed = run_count
                 .checked_mul(src_bpp)
-                .ok_or_else(|| whereat::at!(BitmapError::UnexpectedEof))?;
+                .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))?;
             if src_pos + needed > pixel_data.len() {
-                return Err(whereat::at!(BitmapError::UnexpectedEof));
+                return Err(whereat::at!(BitmapError::unexpected_eof()));
             }
 
             for _ in 0..run_count {
@@ -468,15 +518,15 @@ fn convert_pixel(
         // Color-mapped: src is a single-byte index
         let index = src[0] as usize;
         let map = color_map.ok_or_else(|| {
-            whereat::at!(BitmapError::InvalidData(
-                "color-mapped image has no color map".into()
+            whereat::at!(BitmapError::invalid_data(
+                "color-mapped image has no color map"
             ))
         })?;
 
         let adjusted_index = index
             .checked_sub(header.color_map_start as usize)
             .ok_or_else(|| {
-                whereat::at!(BitmapError::InvalidData(alloc::format!(
+                whereat::at!(BitmapError::invalid_data(alloc::format!(
                     "palette index {index} is below color_map_start {}",
                     header.color_map_start
                 )))
@@ -491,9 +541,9 @@ fn convert_pixel(
 
         let entry_offset = adjusted_index
             .checked_mul(entry_bytes)
-            .ok_or_else(|| whereat::at!(BitmapError::UnexpectedEof))?;
+            .ok_or_else(|| whereat::at!(BitmapError::unexpected_eof()))?;
         if entry_offset + entry_bytes > map.len() {
-            return Err(whereat::at!(BitmapError::InvalidData(alloc::format!(
+            return Err(whereat::at!(BitmapError::invalid_data(alloc::format!(
                 "palette index {index} out of range"
             ))));
         }
@@ -532,6 +582,10 @@ fn convert_color(src: &[u8], dst: &mut [u8], depth: u8) {
             dst[0] = src[2];
             dst[1] = src[1];
             dst[2] = src[0];
+            if dst.len() >= 4 {
+                // Attribute bits declared, but 24-bit pixels carry none
+                dst[3] = 255;
+            }
         }
         32 => {
             // BGRA → RGBA
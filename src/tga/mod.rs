@@ -36,6 +36,7 @@ pub(crate) fn decode_with_alloc_pref<'a>(
     alloc_pref: AllocPref,
     stop: &dyn Stop,
 ) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
     let header = decode::parse_header(data)?;
     let width = header.width as u32;
     let height = header.height as u32;
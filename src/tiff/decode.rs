@@ -0,0 +1,375 @@
+//! TIFF header and first-IFD parsing, strip reading.
+
+use alloc::format;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use super::*;
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Byte-order-aware reads from the whole file.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, at: usize) -> crate::Result<[u8; N]> {
+        self.data
+            .get(at..)
+            .and_then(|d| d.first_chunk::<N>())
+            .copied()
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))
+    }
+
+    fn u16(&self, at: usize) -> crate::Result<u16> {
+        let b = self.bytes::<2>(at)?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    fn u32(&self, at: usize) -> crate::Result<u32> {
+        let b = self.bytes::<4>(at)?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    /// All values of an integer field, whether stored inline or at an offset.
+    fn values(&self, entry: usize) -> crate::Result<Vec<u32>> {
+        let tag = self.u16(entry)?;
+        let kind = self.u16(entry + 2)?;
+        let count = self.u32(entry + 4)? as usize;
+        let size = match kind {
+            TYPE_BYTE => 1,
+            TYPE_SHORT => 2,
+            TYPE_LONG => 4,
+            _ => {
+                return Err(at!(BitmapError::invalid_header(format!(
+                    "TIFF tag {tag} has non-integer field type {kind}"
+                ))));
+            }
+        };
+        let total = count
+            .checked_mul(size)
+            .filter(|&n| n <= self.data.len())
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        let start = if total <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+        (0..count)
+            .map(|i| {
+                let at = start + i * size;
+                match size {
+                    1 => self
+                        .data
+                        .get(at)
+                        .map(|&b| u32::from(b))
+                        .ok_or_else(|| at!(BitmapError::unexpected_eof())),
+                    2 => self.u16(at).map(u32::from),
+                    _ => self.u32(at),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The fields of the first IFD that the decoder uses.
+struct Ifd {
+    width: u32,
+    height: u32,
+    bits: u32,
+    samples: usize,
+    rgb: bool,
+    white_is_zero: bool,
+    alpha: bool,
+    premultiplied: bool,
+    rows_per_strip: u32,
+    strip_offsets: Vec<u32>,
+    /// Empty when the file omits StripByteCounts.
+    strip_byte_counts: Vec<u32>,
+}
+
+fn unsupported(what: alloc::string::String) -> whereat::At<BitmapError> {
+    at!(BitmapError::UnsupportedVariant(what))
+}
+
+fn parse(r: &Reader<'_>) -> crate::Result<Ifd> {
+    let ifd = r.u32(4)? as usize;
+    let entries = r.u16(ifd)? as usize;
+
+    let (mut width, mut height, mut photometric) = (None, None, None);
+    let (mut bits, mut extra, mut strip_offsets) = (Vec::new(), Vec::new(), None);
+    let (mut samples, mut rows_per_strip) = (1u32, u32::MAX);
+    let (mut planar, mut sample_formats, mut strip_byte_counts) = (1, Vec::new(), Vec::new());
+    for i in 0..entries {
+        let entry = ifd + 2 + i * 12;
+        let tag = r.u16(entry)?;
+        let first = || -> crate::Result<u32> {
+            r.values(entry)?.first().copied().ok_or_else(|| {
+                at!(BitmapError::invalid_header(format!(
+                    "TIFF tag {tag} has no values"
+                )))
+            })
+        };
+        match tag {
+            IMAGE_WIDTH => width = Some(first()?),
+            IMAGE_LENGTH => height = Some(first()?),
+            BITS_PER_SAMPLE => bits = r.values(entry)?,
+            COMPRESSION => {
+                let c = first()?;
+                if c != 1 {
+                    return Err(unsupported(format!(
+                        "TIFF compression {c} is not supported; only uncompressed (1)"
+                    )));
+                }
+            }
+            PHOTOMETRIC => photometric = Some(first()?),
+            STRIP_OFFSETS => strip_offsets = Some(r.values(entry)?),
+            STRIP_BYTE_COUNTS => strip_byte_counts = r.values(entry)?,
+            SAMPLES_PER_PIXEL => samples = first()?,
+            ROWS_PER_STRIP => rows_per_strip = first()?,
+            PLANAR_CONFIGURATION => planar = first()?,
+            TILE_WIDTH => return Err(unsupported("tiled TIFF is not supported".into())),
+            EXTRA_SAMPLES => extra = r.values(entry)?,
+            SAMPLE_FORMAT => sample_formats = r.values(entry)?,
+            // Other tags are metadata.
+            _ => {}
+        }
+    }
+
+    if planar == 2 && samples > 1 {
+        return Err(unsupported(
+            "TIFF planar (separate) sample layout is not supported".into(),
+        ));
+    }
+    if sample_formats.iter().any(|&f| f != 1) {
+        return Err(unsupported(
+            "TIFF sample formats other than unsigned integer are not supported".into(),
+        ));
+    }
+    let (Some(width), Some(height)) = (width, height) else {
+        return Err(at!(BitmapError::invalid_header(
+            "TIFF is missing ImageWidth or ImageLength"
+        )));
+    };
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "TIFF width or height is zero"
+        )));
+    }
+    let rgb = match photometric {
+        Some(WHITE_IS_ZERO | BLACK_IS_ZERO) => false,
+        Some(RGB) => true,
+        Some(p) => {
+            return Err(unsupported(format!(
+                "TIFF photometric interpretation {p} is not supported; only gray and RGB"
+            )));
+        }
+        None => {
+            return Err(at!(BitmapError::invalid_header(
+                "TIFF is missing PhotometricInterpretation"
+            )));
+        }
+    };
+    let color = if rgb { 3 } else { 1 };
+    if samples < color || samples > 16 {
+        return Err(at!(BitmapError::invalid_header(format!(
+            "TIFF has {samples} samples per pixel for a {color}-channel image"
+        ))));
+    }
+    let bits = match bits.as_slice() {
+        [] => 1,
+        [b, rest @ ..] if rest.iter().all(|r| r == b) => *b,
+        _ => {
+            return Err(unsupported(
+                "TIFF with differing bits per sample is not supported".into(),
+            ));
+        }
+    };
+    if bits != 8 && bits != 16 {
+        return Err(unsupported(format!(
+            "TIFF {bits}-bit samples are not supported; only 8 and 16"
+        )));
+    }
+    let strip_offsets = strip_offsets
+        .ok_or_else(|| at!(BitmapError::invalid_header("TIFF has no StripOffsets")))?;
+    let rows_per_strip = rows_per_strip.clamp(1, height);
+    let strips = height.div_ceil(rows_per_strip) as usize;
+    if strip_offsets.len() < strips
+        || (!strip_byte_counts.is_empty() && strip_byte_counts.len() < strips)
+    {
+        return Err(at!(BitmapError::invalid_header(
+            "TIFF has fewer StripOffsets or StripByteCounts than strips"
+        )));
+    }
+
+    Ok(Ifd {
+        width,
+        height,
+        bits,
+        samples: samples as usize,
+        rgb,
+        white_is_zero: photometric == Some(WHITE_IS_ZERO),
+        alpha: samples > color,
+        premultiplied: extra.first() == Some(&ASSOCIATED_ALPHA),
+        rows_per_strip,
+        strip_offsets,
+        strip_byte_counts,
+    })
+}
+
+pub(crate) fn decode<'a>(
+    data: &'a [u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let big_endian = match data.get(..4) {
+        Some(b"II*\0") => false,
+        Some(b"MM\0*") => true,
+        Some(b"II+\0" | b"MM\0+") => {
+            return Err(unsupported("BigTIFF is not supported".into()));
+        }
+        Some(_) => return Err(at!(BitmapError::UnrecognizedFormat)),
+        None => return Err(at!(BitmapError::unexpected_eof())),
+    };
+    let r = Reader { data, big_endian };
+    let ifd = parse(&r)?;
+    let (width, height) = (ifd.width, ifd.height);
+    limits::check_dimensions(width, height, limits)?;
+
+    let layout = match (ifd.rgb, ifd.alpha, ifd.bits) {
+        (false, false, 8) => PixelLayout::Gray8,
+        (false, false, _) => PixelLayout::Gray16,
+        // There is no 8-bit gray + alpha layout; expand to RGBA.
+        (false, true, 8) => PixelLayout::Rgba8,
+        (false, true, _) => PixelLayout::GrayA16,
+        (true, false, 8) => PixelLayout::Rgb8,
+        (true, false, _) => PixelLayout::Rgb16,
+        (true, true, 8) => PixelLayout::Rgba8,
+        (true, true, _) => PixelLayout::Rgba16,
+    };
+    let (w, h) = (width as usize, height as usize);
+    let sample_bytes = ifd.bits as usize / 8;
+    let src_row = w
+        .checked_mul(ifd.samples * sample_bytes)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let out_row = w
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let out_bytes = out_row
+        .checked_mul(h)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+
+    // A strip shorter than its rows is corrupt, even if the bytes after it
+    // happen to exist.
+    let rps = ifd.rows_per_strip as usize;
+    for (s, &count) in ifd
+        .strip_byte_counts
+        .iter()
+        .take(h.div_ceil(rps))
+        .enumerate()
+    {
+        let rows = rps.min(h - s * rps);
+        if (count as usize) < rows * src_row {
+            return Err(at!(BitmapError::invalid_data(format!(
+                "TIFF strip {s} holds {count} bytes, expected {}",
+                rows * src_row
+            ))));
+        }
+    }
+
+    // Source row `y`, bounds-checked.
+    let row = |y: usize| -> crate::Result<&'a [u8]> {
+        let start = (ifd.strip_offsets[y / rps] as usize)
+            .checked_add((y % rps) * src_row)
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        data.get(start..)
+            .and_then(|d| d.get(..src_row))
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))
+    };
+
+    // 8-bit rows already in output form, in contiguous strips, are borrowed.
+    let verbatim = sample_bytes == 1
+        && ifd.samples == layout.bytes_per_pixel()
+        && (ifd.rgb || !ifd.alpha)
+        && !ifd.white_is_zero
+        && !ifd.premultiplied;
+    if verbatim {
+        let first = ifd.strip_offsets[0] as usize;
+        let contiguous = (0..h.div_ceil(rps))
+            .all(|s| ifd.strip_offsets[s] as usize == first + s * rps * src_row);
+        if contiguous && let Some(pixels) = data.get(first..).and_then(|d| d.get(..out_bytes)) {
+            return Ok(DecodeOutput::borrowed(pixels, width, height, layout));
+        }
+    }
+
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+    let color = if ifd.rgb { 3 } else { 1 };
+    let max = if sample_bytes == 1 { 255 } else { 65535 };
+    for (y, dst) in out.chunks_exact_mut(out_row).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let src = row(y)?;
+        for (px, dst) in src
+            .chunks_exact(ifd.samples * sample_bytes)
+            .zip(dst.chunks_exact_mut(layout.bytes_per_pixel()))
+        {
+            let sample = |i: usize| -> u32 {
+                let s = &px[i * sample_bytes..];
+                match (sample_bytes, big_endian) {
+                    (1, _) => u32::from(s[0]),
+                    (_, true) => u32::from(u16::from_be_bytes([s[0], s[1]])),
+                    (_, false) => u32::from(u16::from_le_bytes([s[0], s[1]])),
+                }
+            };
+            let alpha = if ifd.alpha { sample(color) } else { max };
+            let mut values = [0u32; 4];
+            for (c, v) in values[..color].iter_mut().enumerate() {
+                *v = sample(c);
+                if ifd.white_is_zero {
+                    *v = max - *v;
+                }
+                if ifd.premultiplied && alpha != 0 && alpha != max {
+                    *v = ((*v * max + alpha / 2) / alpha).min(max);
+                }
+            }
+            if !ifd.rgb {
+                values = [values[0]; 4];
+            }
+            values[3] = alpha;
+            let gray_alpha = [values[0], alpha];
+            let channels: &[u32] = match layout {
+                PixelLayout::Gray8 | PixelLayout::Gray16 => &values[..1],
+                PixelLayout::GrayA16 => &gray_alpha,
+                PixelLayout::Rgb8 | PixelLayout::Rgb16 => &values[..3],
+                _ => &values,
+            };
+            if sample_bytes == 1 {
+                for (d, &v) in dst.iter_mut().zip(channels) {
+                    *d = v as u8;
+                }
+            } else {
+                for (d, &v) in dst.chunks_exact_mut(2).zip(channels) {
+                    d.copy_from_slice(&(v as u16).to_ne_bytes());
+                }
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,322 @@
+//! Baseline uncompressed TIFF writing.
+
+use alloc::format;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use super::*;
+use crate::alpha::AlphaMode;
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+use crate::raw::Endianness;
+
+/// Options for TIFF encoding with [`crate::encode_tiff_with`].
+///
+/// ```
+/// use zenbitmaps::{Endianness, TiffEncodeOptions};
+///
+/// let options = TiffEncodeOptions::new()
+///     .with_rows_per_strip(16)
+///     .with_byte_order(Endianness::Big);
+/// assert_eq!(options.rows_per_strip(), 16);
+/// assert_eq!(options.byte_order(), Endianness::Big);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TiffEncodeOptions {
+    rows_per_strip: u32,
+    byte_order: Endianness,
+    alpha_mode: AlphaMode,
+}
+
+impl Default for TiffEncodeOptions {
+    fn default() -> Self {
+        Self {
+            rows_per_strip: 0,
+            byte_order: Endianness::Little,
+            alpha_mode: AlphaMode::Straight,
+        }
+    }
+}
+
+impl TiffEncodeOptions {
+    /// Default options: one strip, little-endian (`II`), straight alpha.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rows in each strip; the last strip may be shorter. `0` (the
+    /// default) writes the whole image as a single strip.
+    pub fn with_rows_per_strip(mut self, rows: u32) -> Self {
+        self.rows_per_strip = rows;
+        self
+    }
+
+    /// Byte order of the file: [`Endianness::Little`] (`II`, the default)
+    /// or [`Endianness::Big`] (`MM`). [`Endianness::Native`] picks the
+    /// target's, so 16-bit samples are copied without swapping.
+    pub fn with_byte_order(mut self, byte_order: Endianness) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Whether the input's color is premultiplied by alpha. The samples are
+    /// written as they are, with `ExtraSamples` declaring associated
+    /// (premultiplied) or unassociated (straight, the default) alpha.
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Rows per strip, `0` for a single strip.
+    pub fn rows_per_strip(&self) -> u32 {
+        self.rows_per_strip
+    }
+
+    /// File byte order.
+    pub fn byte_order(&self) -> Endianness {
+        self.byte_order
+    }
+
+    /// Alpha state of the input.
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
+
+/// One IFD entry: tag, field type, value count, and the value bytes.
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    bytes: Vec<u8>,
+}
+
+/// Encode pixels as a baseline uncompressed TIFF.
+///
+/// Accepts `Gray8`, `Gray16`, `GrayA16`, `Rgb8`, `Rgb16`, `Rgba8`,
+/// `Rgba16`, `Bgr8`, `Bgra8`, `Rgbx8` and `Bgrx8`. BGR input is swizzled
+/// to RGB, padding bytes are dropped, and alpha is written as unassociated
+/// (straight) or, with [`AlphaMode::Premultiplied`], associated. 16-bit input is native-endian.
+pub(crate) fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    options: &TiffEncodeOptions,
+    stop: &dyn Stop,
+) -> crate::Result<Vec<u8>> {
+    // (color channels, has alpha, bits per sample)
+    let (color, alpha, bits) = match layout {
+        PixelLayout::Gray8 => (1, false, 8),
+        PixelLayout::Gray16 => (1, false, 16),
+        PixelLayout::GrayA16 => (1, true, 16),
+        PixelLayout::Rgb8 | PixelLayout::Bgr8 | PixelLayout::Rgbx8 | PixelLayout::Bgrx8 => {
+            (3, false, 8)
+        }
+        PixelLayout::Rgb16 => (3, false, 16),
+        PixelLayout::Rgba8 | PixelLayout::Bgra8 => (3, true, 8),
+        PixelLayout::Rgba16 => (3, true, 16),
+        other => {
+            return Err(at!(BitmapError::UnsupportedVariant(format!(
+                "cannot encode {other:?} as TIFF (supported: Gray8, Gray16, GrayA16, \
+                 Rgb8, Rgb16, Rgba8, Rgba16, Bgr8, Bgra8, Rgbx8, Bgrx8)"
+            ))));
+        }
+    };
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_data(
+            "TIFF width or height is zero"
+        )));
+    }
+    let (w, h) = (width as usize, height as usize);
+    let bpp = layout.bytes_per_pixel();
+    let expected = w
+        .checked_mul(h)
+        .and_then(|wh| wh.checked_mul(bpp))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    if pixels.len() < expected {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed: expected,
+            actual: pixels.len(),
+        }));
+    }
+
+    let big = match options.byte_order {
+        Endianness::Little => false,
+        Endianness::Big => true,
+        Endianness::Native => cfg!(target_endian = "big"),
+    };
+    let u16b = |v: u16| {
+        if big {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let u32b = |v: u32| {
+        if big {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+
+    let samples = color + usize::from(alpha);
+    let row_bytes = w * samples * (bits / 8);
+    let rows_per_strip = match options.rows_per_strip {
+        0 => height,
+        n => n.min(height),
+    };
+    let strips = h.div_ceil(rows_per_strip as usize);
+    let strip_bytes = row_bytes * rows_per_strip as usize;
+    let image_bytes = row_bytes * h;
+    // Offsets are 32-bit; classic TIFF tops out at 4 GiB.
+    if u32::try_from(image_bytes).is_err() {
+        return Err(at!(BitmapError::DimensionsTooLarge { width, height }));
+    }
+
+    let shorts = |values: &[u16]| values.iter().flat_map(|&v| u16b(v)).collect::<Vec<u8>>();
+    let longs = |values: &[u32]| values.iter().flat_map(|&v| u32b(v)).collect::<Vec<u8>>();
+    let entry = |tag, kind, count: usize, bytes| Entry {
+        tag,
+        kind,
+        count: count as u32,
+        bytes,
+    };
+    // 72 dpi, the customary default for files without a real resolution.
+    let dpi = longs(&[72, 1]);
+    let mut entries = Vec::with_capacity(14);
+    entries.push(entry(IMAGE_WIDTH, TYPE_LONG, 1, longs(&[width])));
+    entries.push(entry(IMAGE_LENGTH, TYPE_LONG, 1, longs(&[height])));
+    entries.push(entry(
+        BITS_PER_SAMPLE,
+        TYPE_SHORT,
+        samples,
+        shorts(&[bits as u16; 4][..samples]),
+    ));
+    entries.push(entry(COMPRESSION, TYPE_SHORT, 1, shorts(&[1])));
+    let photometric = if color == 3 { RGB } else { BLACK_IS_ZERO };
+    entries.push(entry(
+        PHOTOMETRIC,
+        TYPE_SHORT,
+        1,
+        shorts(&[photometric as u16]),
+    ));
+    // Placeholder; filled in once the data offset is known.
+    entries.push(entry(
+        STRIP_OFFSETS,
+        TYPE_LONG,
+        strips,
+        alloc::vec![0; strips * 4],
+    ));
+    entries.push(entry(
+        SAMPLES_PER_PIXEL,
+        TYPE_SHORT,
+        1,
+        shorts(&[samples as u16]),
+    ));
+    entries.push(entry(
+        ROWS_PER_STRIP,
+        TYPE_LONG,
+        1,
+        longs(&[rows_per_strip]),
+    ));
+    let counts: Vec<u32> = (0..strips)
+        .map(|i| strip_bytes.min(image_bytes - i * strip_bytes) as u32)
+        .collect();
+    entries.push(entry(STRIP_BYTE_COUNTS, TYPE_LONG, strips, longs(&counts)));
+    entries.push(entry(X_RESOLUTION, TYPE_RATIONAL, 1, dpi.clone()));
+    entries.push(entry(Y_RESOLUTION, TYPE_RATIONAL, 1, dpi));
+    entries.push(entry(PLANAR_CONFIGURATION, TYPE_SHORT, 1, shorts(&[1])));
+    entries.push(entry(RESOLUTION_UNIT, TYPE_SHORT, 1, shorts(&[INCH])));
+    if alpha {
+        entries.push(entry(
+            EXTRA_SAMPLES,
+            TYPE_SHORT,
+            1,
+            shorts(&[match options.alpha_mode {
+                AlphaMode::Premultiplied => ASSOCIATED_ALPHA as u16,
+                AlphaMode::Straight => UNASSOCIATED_ALPHA as u16,
+            }]),
+        ));
+    }
+
+    // Header, IFD, out-of-line values (each on a word boundary), strips.
+    let ifd_bytes = 2 + entries.len() * 12 + 4;
+    let values_bytes: usize = entries
+        .iter()
+        .filter(|e| e.bytes.len() > 4)
+        .map(|e| e.bytes.len().next_multiple_of(2))
+        .sum();
+    let data_start = 8 + ifd_bytes + values_bytes;
+    let total = data_start
+        .checked_add(image_bytes)
+        .filter(|&n| u32::try_from(n).is_ok())
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let offsets: Vec<u32> = (0..strips)
+        .map(|i| (data_start + i * strip_bytes) as u32)
+        .collect();
+    if let Some(e) = entries.iter_mut().find(|e| e.tag == STRIP_OFFSETS) {
+        e.bytes = longs(&offsets);
+    }
+
+    let mut out = Vec::with_capacity(total);
+    out.extend_from_slice(if big { b"MM\0*" } else { b"II*\0" });
+    out.extend_from_slice(&u32b(8));
+    out.extend_from_slice(&u16b(entries.len() as u16));
+    let mut value_at = 8 + ifd_bytes;
+    for e in &entries {
+        out.extend_from_slice(&u16b(e.tag));
+        out.extend_from_slice(&u16b(e.kind));
+        out.extend_from_slice(&u32b(e.count));
+        if e.bytes.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..e.bytes.len()].copy_from_slice(&e.bytes);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&u32b(value_at as u32));
+            value_at += e.bytes.len().next_multiple_of(2);
+        }
+    }
+    out.extend_from_slice(&[0; 4]); // no next IFD
+    for e in entries.iter().filter(|e| e.bytes.len() > 4) {
+        out.extend_from_slice(&e.bytes);
+        if e.bytes.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+
+    for (y, row) in pixels[..expected].chunks_exact(w * bpp).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        match layout {
+            PixelLayout::Gray8 | PixelLayout::Rgb8 | PixelLayout::Rgba8 => {
+                out.extend_from_slice(row);
+            }
+            PixelLayout::Rgbx8 => {
+                for px in row.chunks_exact(4) {
+                    out.extend_from_slice(&px[..3]);
+                }
+            }
+            PixelLayout::Bgr8 | PixelLayout::Bgrx8 => {
+                for px in row.chunks_exact(bpp) {
+                    out.extend_from_slice(&[px[2], px[1], px[0]]);
+                }
+            }
+            PixelLayout::Bgra8 => {
+                for px in row.chunks_exact(4) {
+                    out.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+                }
+            }
+            // 16-bit layouts: native-endian samples in the file's order.
+            _ => {
+                for s in row.chunks_exact(2) {
+                    out.extend_from_slice(&u16b(u16::from_ne_bytes([s[0], s[1]])));
+                }
+            }
+        }
+    }
+    Ok(out)
+}
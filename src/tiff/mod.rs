@@ -0,0 +1,52 @@
+//! Baseline uncompressed TIFF (internal).
+//!
+//! Use top-level [`crate::decode_tiff`] and [`crate::encode_tiff`].
+//!
+//! Only the first image (IFD) of a file is read, and only the narrow
+//! profile that scanners and scientific tools write uncompressed: strips,
+//! chunky (interleaved) samples, 8 or 16 bits of unsigned integer per
+//! sample, gray or RGB with an optional alpha channel. Everything else
+//! (compression, tiles, palettes, planar data, CMYK, floats) is rejected
+//! as [`UnsupportedVariant`](crate::BitmapError::UnsupportedVariant).
+
+pub(crate) mod decode;
+mod encode;
+
+pub use encode::TiffEncodeOptions;
+pub(crate) use encode::encode;
+
+// Baseline tags.
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const PHOTOMETRIC: u16 = 262;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const X_RESOLUTION: u16 = 282;
+const Y_RESOLUTION: u16 = 283;
+const PLANAR_CONFIGURATION: u16 = 284;
+const RESOLUTION_UNIT: u16 = 296;
+const TILE_WIDTH: u16 = 322;
+const EXTRA_SAMPLES: u16 = 338;
+const SAMPLE_FORMAT: u16 = 339;
+
+// Field types.
+const TYPE_BYTE: u16 = 1;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+// PhotometricInterpretation values.
+const WHITE_IS_ZERO: u32 = 0;
+const BLACK_IS_ZERO: u32 = 1;
+const RGB: u32 = 2;
+
+// ExtraSamples values.
+const ASSOCIATED_ALPHA: u32 = 1;
+const UNASSOCIATED_ALPHA: u32 = 2;
+
+// ResolutionUnit values.
+const INCH: u16 = 2;
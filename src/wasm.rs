@@ -0,0 +1,262 @@
+//! JavaScript bindings via wasm-bindgen (`wasm` feature).
+//!
+//! Thin wrappers over the auto-detecting decoder, the format-parameterized
+//! encoder and the header parsers, so browser tools run the same reference
+//! codecs as the native test suite. Pixel data crosses as typed arrays
+//! (`Uint8Array`, `Uint16Array`, `Float32Array`); layouts are named as in
+//! [`PixelLayout`]'s `Debug` output (`"Rgba8"`, `"RgbF32"`, ...). Failures
+//! throw a JavaScript `Error` carrying the [`BitmapError`] message.
+//!
+//! Build with `cargo rustc --release --lib --target wasm32-unknown-unknown
+//! --features wasm --crate-type cdylib`, then run `wasm-bindgen` on the
+//! output.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use enough::Unstoppable;
+use wasm_bindgen::prelude::*;
+use whereat::At;
+
+use crate::error::BitmapError;
+use crate::pixel::{EncodeFormat, Identified, ImageFormat, PixelLayout};
+
+fn js_error(error: At<BitmapError>) -> JsError {
+    JsError::new(&error.error().to_string())
+}
+
+/// A decoded image, as returned by `decode` and `decodeRgba8`.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct DecodedImage {
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    pixels: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecodedImage {
+    /// Width in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixel layout name, such as `"Rgb8"` or `"Rgba16"`.
+    #[wasm_bindgen(getter)]
+    pub fn layout(&self) -> String {
+        format!("{:?}", self.layout)
+    }
+
+    /// The packed pixel bytes, as a `Uint8Array`. For `Rgba8` output this
+    /// is what `ImageData` expects.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+
+    /// The samples of a 16-bit layout, as a `Uint16Array`.
+    pub fn samples16(&self) -> Result<Vec<u16>, JsError> {
+        let from_bytes = match self.layout {
+            PixelLayout::Gray16
+            | PixelLayout::GrayA16
+            | PixelLayout::Rgb16
+            | PixelLayout::Rgba16 => u16::from_ne_bytes,
+            PixelLayout::Rgba16Be => u16::from_be_bytes,
+            layout => return Err(JsError::new(&format!("{layout:?} is not a 16-bit layout"))),
+        };
+        Ok(self
+            .pixels
+            .chunks_exact(2)
+            .map(|s| from_bytes([s[0], s[1]]))
+            .collect())
+    }
+
+    /// The samples of a float layout, as a `Float32Array`.
+    #[wasm_bindgen(js_name = samplesF32)]
+    pub fn samples_f32(&self) -> Result<Vec<f32>, JsError> {
+        match self.layout {
+            PixelLayout::GrayF32
+            | PixelLayout::RgbF32
+            | PixelLayout::RgbaF32
+            | PixelLayout::RgbPlanarF32 => Ok(self
+                .pixels
+                .chunks_exact(4)
+                .map(|s| f32::from_ne_bytes([s[0], s[1], s[2], s[3]]))
+                .collect()),
+            layout => Err(JsError::new(&format!("{layout:?} is not a float layout"))),
+        }
+    }
+}
+
+impl From<crate::DecodeOutput<'_>> for DecodedImage {
+    fn from(decoded: crate::DecodeOutput<'_>) -> Self {
+        Self {
+            width: decoded.width,
+            height: decoded.height,
+            layout: decoded.layout,
+            pixels: decoded.packed_pixels().into_owned(),
+        }
+    }
+}
+
+/// What `probe` learned from a file's header.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct ImageProbe {
+    format: &'static str,
+    dimensions: Option<(u32, u32)>,
+    decodable: bool,
+}
+
+#[wasm_bindgen]
+impl ImageProbe {
+    /// Format name: `"PNM"`, `"BMP"`, `"farbfeld"`, ..., or the name of a
+    /// format this crate does not decode (`"PNG"`, `"JPEG"`, ...).
+    #[wasm_bindgen(getter)]
+    pub fn format(&self) -> String {
+        self.format.into()
+    }
+
+    /// Width in pixels, when the header was parsed.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> Option<u32> {
+        self.dimensions.map(|(w, _)| w)
+    }
+
+    /// Height in pixels, when the header was parsed.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> Option<u32> {
+        self.dimensions.map(|(_, h)| h)
+    }
+
+    /// Whether `decode` handles the file in this build.
+    #[wasm_bindgen(getter)]
+    pub fn decodable(&self) -> bool {
+        self.decodable
+    }
+}
+
+/// Decode any supported format, detected from its magic bytes, in the
+/// format's own layout.
+#[wasm_bindgen]
+pub fn decode(data: &[u8]) -> Result<DecodedImage, JsError> {
+    crate::decode(data, Unstoppable)
+        .map(DecodedImage::from)
+        .map_err(js_error)
+}
+
+/// Decode any supported format to `Rgba8`, ready for `ImageData`.
+#[wasm_bindgen(js_name = decodeRgba8)]
+pub fn decode_rgba8(data: &[u8]) -> Result<DecodedImage, JsError> {
+    crate::DecodeRequest::new(data)
+        .with_target_layout(PixelLayout::Rgba8)
+        .decode()
+        .map(DecodedImage::from)
+        .map_err(js_error)
+}
+
+/// Encode packed `layout` pixels (a layout name, as `DecodedImage.layout`)
+/// as `format` (`"pgm"`, `"ppm"`, `"pam"`, `"pfm"`, `"farbfeld"` or
+/// `"bmp"`) with default options.
+#[wasm_bindgen]
+pub fn encode(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    layout: &str,
+    format: &str,
+) -> Result<Vec<u8>, JsError> {
+    let layout = PixelLayout::ALL
+        .into_iter()
+        .find(|l| format!("{l:?}") == layout)
+        .ok_or_else(|| JsError::new(&format!("unknown pixel layout {layout:?}")))?;
+    let format = encode_format(format)
+        .ok_or_else(|| JsError::new(&format!("cannot encode as {format:?}")))?;
+    let options = crate::EncodeOptions::new();
+    crate::encode(pixels, width, height, layout, format, &options, Unstoppable).map_err(js_error)
+}
+
+/// Identify `data` and read its dimensions from the header, without
+/// decoding pixels.
+#[wasm_bindgen]
+pub fn probe(data: &[u8]) -> Result<ImageProbe, JsError> {
+    match crate::identify(data) {
+        Some(Identified::Supported(format)) => Ok(ImageProbe {
+            format: format_name(format),
+            dimensions: dimensions(format, data).map_err(js_error)?,
+            decodable: format.is_decodable(),
+        }),
+        Some(Identified::Foreign(format)) => Ok(ImageProbe {
+            format: format.name(),
+            dimensions: None,
+            decodable: false,
+        }),
+        None => Err(js_error(whereat::at!(BitmapError::UnrecognizedFormat))),
+    }
+}
+
+fn encode_format(name: &str) -> Option<EncodeFormat> {
+    match name {
+        "pgm" => Some(EncodeFormat::Pgm),
+        "ppm" => Some(EncodeFormat::Ppm),
+        "pam" => Some(EncodeFormat::Pam),
+        "pfm" => Some(EncodeFormat::Pfm),
+        "farbfeld" => Some(EncodeFormat::Farbfeld),
+        #[cfg(feature = "bmp")]
+        "bmp" => Some(EncodeFormat::Bmp),
+        _ => None,
+    }
+}
+
+fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Pnm => "PNM",
+        ImageFormat::Bmp => "BMP",
+        ImageFormat::Farbfeld => "farbfeld",
+        ImageFormat::Qoi => "QOI",
+        ImageFormat::Tga => "TGA",
+        ImageFormat::Hdr => "HDR",
+    }
+}
+
+/// Dimensions from the header of a detected `format`, or `None` when its
+/// feature is off.
+fn dimensions(format: ImageFormat, data: &[u8]) -> crate::Result<Option<(u32, u32)>> {
+    Ok(Some(match format {
+        ImageFormat::Pnm => {
+            let header = crate::pnm::decode::parse_header(data)?;
+            (header.width, header.height)
+        }
+        ImageFormat::Farbfeld => crate::farbfeld::decode::parse_header(data)?,
+        #[cfg(feature = "bmp")]
+        ImageFormat::Bmp => {
+            let meta = crate::bmp::probe(data)?;
+            (meta.width, meta.height)
+        }
+        #[cfg(feature = "qoi")]
+        ImageFormat::Qoi => {
+            let header = crate::qoi::decode::parse_header(data)?;
+            (header.width, header.height)
+        }
+        #[cfg(feature = "tga")]
+        ImageFormat::Tga => {
+            let header = crate::tga::decode::parse_header(data)?;
+            (u32::from(header.width), u32::from(header.height))
+        }
+        #[cfg(feature = "hdr")]
+        ImageFormat::Hdr => {
+            let (width, height, _) = crate::hdr::decode::parse_header(data)?;
+            (width, height)
+        }
+        #[cfg(not(all(feature = "bmp", feature = "qoi", feature = "tga", feature = "hdr")))]
+        _ => return Ok(None),
+    }))
+}
@@ -0,0 +1,296 @@
+//! XPM3 (X PixMap) decoding (internal).
+//!
+//! Use top-level [`crate::decode_xpm`].
+//!
+//! An XPM3 file is a C source fragment: an array of string literals holding
+//! a `"width height ncolors cpp"` values line, `ncolors` color definitions
+//! (`cpp` key characters followed by `c`/`m`/`g4`/`g`/`s` key–value pairs),
+//! and `height` pixel rows of `width * cpp` characters. Comments and any
+//! `XPMEXT` extension strings after the pixels are ignored.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Most characters per pixel accepted; real files use 1-3.
+const MAX_CPP: usize = 8;
+
+/// Color keys in order of preference: color, grayscale, 4-level grayscale, mono.
+const KEY_PREFERENCE: [&str; 4] = ["c", "g", "g4", "m"];
+
+/// X11 color names accepted in color definitions (lowercase, no spaces).
+/// `grayN` / `greyN` (0-100) are computed rather than listed.
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+    ("black", [0, 0, 0]),
+    ("white", [255, 255, 255]),
+    ("red", [255, 0, 0]),
+    ("green", [0, 255, 0]),
+    ("blue", [0, 0, 255]),
+    ("yellow", [255, 255, 0]),
+    ("cyan", [0, 255, 255]),
+    ("magenta", [255, 0, 255]),
+    ("gray", [190, 190, 190]),
+    ("grey", [190, 190, 190]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgrey", [169, 169, 169]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgrey", [211, 211, 211]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("orange", [255, 165, 0]),
+    ("brown", [165, 42, 42]),
+    ("pink", [255, 192, 203]),
+    ("purple", [160, 32, 240]),
+    ("violet", [238, 130, 238]),
+    ("navy", [0, 0, 128]),
+    ("navyblue", [0, 0, 128]),
+    ("darkblue", [0, 0, 139]),
+    ("lightblue", [173, 216, 230]),
+    ("skyblue", [135, 206, 235]),
+    ("darkgreen", [0, 100, 0]),
+    ("lightgreen", [144, 238, 144]),
+    ("forestgreen", [34, 139, 34]),
+    ("darkred", [139, 0, 0]),
+    ("maroon", [176, 48, 96]),
+    ("gold", [255, 215, 0]),
+    ("khaki", [240, 230, 140]),
+    ("beige", [245, 245, 220]),
+    ("tan", [210, 180, 140]),
+    ("salmon", [250, 128, 114]),
+    ("coral", [255, 127, 80]),
+    ("turquoise", [64, 224, 208]),
+    ("steelblue", [70, 130, 180]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("gainsboro", [220, 220, 220]),
+    ("whitesmoke", [245, 245, 245]),
+];
+
+/// Collect the string literals of the C array, skipping comments.
+fn string_literals(data: &[u8]) -> crate::Result<Vec<&[u8]>> {
+    let mut strings = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        match data[pos] {
+            b'/' if data.get(pos + 1) == Some(&b'*') => {
+                let end = data[pos + 2..]
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+                pos += 2 + end + 2;
+            }
+            b'"' => {
+                let start = pos + 1;
+                let len = data[start..]
+                    .iter()
+                    .position(|&b| b == b'"' || b == b'\n')
+                    .filter(|&n| data[start + n] == b'"')
+                    .ok_or_else(|| at!(BitmapError::invalid_data("XPM: unterminated string")))?;
+                strings.push(&data[start..start + len]);
+                pos = start + len + 1;
+            }
+            _ => pos += 1,
+        }
+    }
+    Ok(strings)
+}
+
+/// Parse a color value (`#hex`, an X11 name, or `None`); `None` means
+/// transparent.
+fn parse_color(value: &str) -> crate::Result<Option<[u8; 3]>> {
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits = hex.len() / 3;
+        if hex.len() % 3 != 0 || !(1..=4).contains(&digits) {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "XPM: invalid color '{value}'"
+            ))));
+        }
+        let mut rgb = [0u8; 3];
+        for (c, channel) in rgb.iter_mut().enumerate() {
+            let field = &hex[c * digits..(c + 1) * digits];
+            let v = u16::from_str_radix(field, 16).map_err(|_| {
+                at!(BitmapError::invalid_data(alloc::format!(
+                    "XPM: invalid color '{value}'"
+                )))
+            })?;
+            // Scale to 8 bits: replicate short fields, truncate long ones.
+            *channel = match digits {
+                1 => (v * 17) as u8,
+                2 => v as u8,
+                3 => (v >> 4) as u8,
+                _ => (v >> 8) as u8,
+            };
+        }
+        return Ok(Some(rgb));
+    }
+    let name: alloc::string::String = value
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if let Some(level) = name
+        .strip_prefix("gray")
+        .or_else(|| name.strip_prefix("grey"))
+        .and_then(|n| n.parse::<u16>().ok())
+        .filter(|&n| n <= 100)
+    {
+        let v = ((level * 255 + 50) / 100) as u8;
+        return Ok(Some([v; 3]));
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, rgb)| Some(rgb))
+        .ok_or_else(|| {
+            at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "XPM: unknown color name '{value}'"
+            )))
+        })
+}
+
+/// Pick the preferred color from the key–value pairs after the pixel key.
+fn parse_definition(line: &str) -> crate::Result<Option<[u8; 3]>> {
+    let mut pairs: Vec<(&str, alloc::string::String)> = Vec::new();
+    for token in line.split_ascii_whitespace() {
+        match pairs.last_mut() {
+            // Values (like X11 names) may contain spaces; a key starts a new pair.
+            Some((_, value)) if !matches!(token, "c" | "m" | "g" | "g4" | "s") => {
+                if !value.is_empty() {
+                    value.push(' ');
+                }
+                value.push_str(token);
+            }
+            _ => pairs.push((token, alloc::string::String::new())),
+        }
+    }
+    let value = KEY_PREFERENCE
+        .iter()
+        .find_map(|key| pairs.iter().find(|(k, v)| k == key && !v.is_empty()))
+        .map(|(_, v)| v)
+        .ok_or_else(|| {
+            at!(BitmapError::invalid_data(alloc::format!(
+                "XPM: color definition '{line}' has no c/g/g4/m value"
+            )))
+        })?;
+    parse_color(value)
+}
+
+fn invalid_values() -> whereat::At<BitmapError> {
+    at!(BitmapError::invalid_header(
+        "XPM: values line must be \"width height ncolors cpp\""
+    ))
+}
+
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    if !data[start..].starts_with(b"/* XPM */") {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    let strings = string_literals(data)?;
+    let mut lines = strings.iter().copied();
+
+    let values = lines
+        .next()
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let values = core::str::from_utf8(values).map_err(|_| invalid_values())?;
+    let mut fields = values.split_ascii_whitespace().map(|f| f.parse::<u32>());
+    let mut field = || {
+        fields
+            .next()
+            .and_then(|f| f.ok())
+            .ok_or_else(invalid_values)
+    };
+    let (width, height, ncolors, cpp) = (field()?, field()?, field()?, field()? as usize);
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "XPM: width or height is zero"
+        )));
+    }
+    if !(1..=MAX_CPP).contains(&cpp) {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "XPM: {cpp} characters per pixel is not supported"
+        ))));
+    }
+    limits::check_dimensions(width, height, limits)?;
+
+    let mut colors: BTreeMap<&[u8], [u8; 4]> = BTreeMap::new();
+    for _ in 0..ncolors {
+        let line = lines
+            .next()
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        let (key, rest) = line.split_at_checked(cpp).ok_or_else(|| {
+            at!(BitmapError::invalid_data(
+                "XPM: color definition shorter than the pixel key"
+            ))
+        })?;
+        let rest = core::str::from_utf8(rest).map_err(|_| {
+            at!(BitmapError::invalid_data(
+                "XPM: color definition is not UTF-8"
+            ))
+        })?;
+        let rgba = match parse_definition(rest)? {
+            Some([r, g, b]) => [r, g, b, 255],
+            None => [0; 4],
+        };
+        colors.insert(key, rgba);
+    }
+
+    let has_alpha = colors.values().any(|c| c[3] == 0);
+    let (layout, channels) = if has_alpha {
+        (PixelLayout::Rgba8, 4)
+    } else {
+        (PixelLayout::Rgb8, 3)
+    };
+    let w = width as usize;
+    let out_bytes = w
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(channels))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    for (y, row) in out.chunks_exact_mut(w * channels).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let line = lines
+            .next()
+            .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+        if line.len() < w * cpp {
+            return Err(at!(BitmapError::invalid_data(alloc::format!(
+                "XPM: row {y} has {} characters, expected {}",
+                line.len(),
+                w * cpp
+            ))));
+        }
+        for (key, px) in line.chunks_exact(cpp).zip(row.chunks_exact_mut(channels)) {
+            let rgba = colors.get(key).ok_or_else(|| {
+                at!(BitmapError::invalid_data(alloc::format!(
+                    "XPM: row {y} uses undefined color '{}'",
+                    alloc::string::String::from_utf8_lossy(key)
+                )))
+            })?;
+            px.copy_from_slice(&rgba[..channels]);
+        }
+    }
+
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
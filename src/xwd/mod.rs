@@ -0,0 +1,181 @@
+//! XWD (X Window Dump) decoding (internal).
+//!
+//! Use top-level [`crate::decode_xwd`].
+//!
+//! An XWD file is a header of 25 32-bit fields, the window name (padding
+//! the header out to its recorded size), a colormap of 12-byte entries,
+//! then the image. `xwd` writes the header most-significant byte first;
+//! headers in the other order are accepted too. Only `ZPixmap` images with
+//! 24 or 32 bits per pixel are decoded, which covers every TrueColor and
+//! DirectColor screenshot. Channel masks go through the BMP bitfield
+//! scaling, so any mask layout works, including 10-bit channels.
+
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::bmp::utils::{scale_mask_to_u8, scale_mask_to_u16};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+const HEADER_LEN: usize = 100;
+const FILE_VERSION: u32 = 7;
+const Z_PIXMAP: u32 = 2;
+const MSB_FIRST: u32 = 1;
+const COLOR_ENTRY_LEN: usize = 12;
+
+/// The header fields the decoder uses.
+struct XwdHeader {
+    width: u32,
+    height: u32,
+    depth: u32,
+    bits_per_pixel: u32,
+    bytes_per_line: usize,
+    /// Pixel values are stored most significant byte first.
+    msb_first: bool,
+    masks: [u32; 3],
+    data_offset: usize,
+}
+
+fn parse_header(data: &[u8]) -> crate::Result<XwdHeader> {
+    if data.len() < HEADER_LEN {
+        return Err(at!(BitmapError::unexpected_eof()));
+    }
+    let be = |i: usize| u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    let le = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    let field: &dyn Fn(usize) -> u32 = if be(4) == FILE_VERSION {
+        &|i| be(i * 4)
+    } else if le(4) == FILE_VERSION {
+        &|i| le(i * 4)
+    } else {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    };
+
+    let header_size = field(0) as usize;
+    if header_size < HEADER_LEN {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "XWD header size {header_size} is smaller than {HEADER_LEN}"
+        ))));
+    }
+    let pixmap_format = field(2);
+    if pixmap_format != Z_PIXMAP {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "XWD pixmap format {pixmap_format} is not supported; only ZPixmap (2)"
+        ))));
+    }
+    let bits_per_pixel = field(11);
+    if !matches!(bits_per_pixel, 24 | 32) {
+        return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+            "XWD {bits_per_pixel} bits per pixel is not supported; only 24 and 32"
+        ))));
+    }
+    let (width, height) = (field(4), field(5));
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "XWD width or height is zero"
+        )));
+    }
+    let masks = [field(14), field(15), field(16)];
+    if masks.contains(&0) {
+        return Err(at!(BitmapError::UnsupportedVariant(
+            "XWD image has an empty red, green or blue mask (colormapped visual)".into()
+        )));
+    }
+    let bytes_per_line = field(12) as usize;
+    if (bytes_per_line as u64) < u64::from(width) * u64::from(bits_per_pixel / 8) {
+        return Err(at!(BitmapError::invalid_header(alloc::format!(
+            "XWD bytes per line {bytes_per_line} is too small for width {width}"
+        ))));
+    }
+    let data_offset = (field(19) as usize)
+        .checked_mul(COLOR_ENTRY_LEN)
+        .and_then(|n| n.checked_add(header_size))
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    Ok(XwdHeader {
+        width,
+        height,
+        depth: field(3),
+        bits_per_pixel,
+        bytes_per_line,
+        msb_first: field(7) == MSB_FIRST,
+        masks,
+        data_offset,
+    })
+}
+
+/// Decode a `ZPixmap` to `Rgb8`, or `Rgb16` when a mask is wider than 8
+/// bits. Depth-32 images carry alpha in the bits outside the color masks
+/// and decode to `Rgba8` / `Rgba16`.
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    let header = parse_header(data)?;
+    let (width, height) = (header.width, header.height);
+    limits::check_dimensions(width, height, limits)?;
+
+    let [mr, mg, mb] = header.masks;
+    let ma = if header.depth == 32 && header.bits_per_pixel == 32 {
+        !(mr | mg | mb)
+    } else {
+        0
+    };
+    let wide = [mr, mg, mb, ma].iter().any(|m| m.count_ones() > 8);
+    let (layout, channels) = match (ma != 0, wide) {
+        (false, false) => (PixelLayout::Rgb8, 3),
+        (true, false) => (PixelLayout::Rgba8, 4),
+        (false, true) => (PixelLayout::Rgb16, 3),
+        (true, true) => (PixelLayout::Rgba16, 4),
+    };
+
+    let (w, h) = (width as usize, height as usize);
+    let src_bpp = header.bits_per_pixel as usize / 8;
+    let too_large = || at!(BitmapError::DimensionsTooLarge { width, height });
+    let out_row = w
+        .checked_mul(layout.bytes_per_pixel())
+        .ok_or_else(too_large)?;
+    let out_bytes = out_row.checked_mul(h).ok_or_else(too_large)?;
+    limits::check_output_size(out_bytes, limits)?;
+    // The last row need only hold its pixels, not the full line padding.
+    let pixels = header
+        .bytes_per_line
+        .checked_mul(h - 1)
+        .and_then(|n| n.checked_add(w * src_bpp))
+        .and_then(|n| data.get(header.data_offset..)?.get(..n))
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    for (y, dst) in out.chunks_exact_mut(out_row).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let start = y * header.bytes_per_line;
+        let src = &pixels[start..start + w * src_bpp];
+        for (px, dst) in src
+            .chunks_exact(src_bpp)
+            .zip(dst.chunks_exact_mut(layout.bytes_per_pixel()))
+        {
+            let v = if header.msb_first {
+                px.iter().fold(0u32, |v, &b| v << 8 | u32::from(b))
+            } else {
+                px.iter().rev().fold(0u32, |v, &b| v << 8 | u32::from(b))
+            };
+            let masks = [mr, mg, mb, ma];
+            if wide {
+                let values = masks.map(|m| scale_mask_to_u16(v, m));
+                for (d, s) in dst.chunks_exact_mut(2).zip(&values[..channels]) {
+                    d.copy_from_slice(&s.to_ne_bytes());
+                }
+            } else {
+                let values = masks.map(|m| scale_mask_to_u8(v, m));
+                dst.copy_from_slice(&values[..channels]);
+            }
+        }
+    }
+
+    Ok(DecodeOutput::owned(out, width, height, layout))
+}
@@ -0,0 +1,188 @@
+//! YUV4MPEG2 writing.
+
+use alloc::format;
+use alloc::vec::Vec;
+use enough::{Stop, Unstoppable};
+use whereat::at;
+
+use super::{Y4mHeader, frames, parse_header};
+use crate::error::BitmapError;
+use crate::pixel::PixelLayout;
+use crate::yuv::{self, YuvLayout, YuvMatrix};
+
+/// Frame-at-a-time YUV4MPEG2 writer.
+///
+/// Writes the header line on creation, then one `FRAME` per
+/// [`push_frame`](Self::push_frame) (planes as they are) or
+/// [`push_rgb`](Self::push_rgb) (converted with a [`YuvMatrix`]).
+/// [`append`](Self::append) continues an existing stream instead.
+///
+/// ```
+/// use zenbitmaps::*;
+///
+/// let header = Y4mHeader::new(2, 1, YuvLayout::I444).with_frame_rate(30, 1);
+/// let mut writer = Y4mWriter::new(header)?;
+/// writer.push_frame(&[16, 235, 128, 128, 128, 128])?;
+/// writer.push_rgb(&[255, 255, 255, 0, 0, 0], PixelLayout::Rgb8, YuvMatrix::Bt601, Unstoppable)?;
+/// let y4m = writer.finish();
+/// assert!(y4m.starts_with(b"YUV4MPEG2 W2 H1 F30:1 C444\n"));
+///
+/// let lumas: Vec<&[u8]> = decode_y4m_frames(&y4m, Unstoppable)?
+///     .map(|f| f.map(|f| f.y()))
+///     .collect::<Result<_>>()?;
+/// assert_eq!(lumas, [&[16, 235], &[235, 16]]);
+/// # Ok::<(), zenbitmaps::At<zenbitmaps::BitmapError>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Y4mWriter {
+    header: Y4mHeader,
+    out: Vec<u8>,
+    frames: u32,
+}
+
+impl Y4mWriter {
+    /// Start a stream described by `header`.
+    ///
+    /// The layout must be one Y4M can hold: [`YuvLayout::I420`]
+    /// (written as `420jpeg`), [`YuvLayout::I422`], [`YuvLayout::I444`] or
+    /// [`YuvLayout::Gray`] (`mono`).
+    pub fn new(header: Y4mHeader) -> crate::Result<Self> {
+        let Y4mHeader { width, height, .. } = header;
+        if width == 0 || height == 0 {
+            return Err(at!(BitmapError::invalid_data(
+                "Y4M width or height is zero"
+            )));
+        }
+        // Room for the largest RGB input `push_rgb` accepts.
+        if (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|px| px.checked_mul(4))
+            .is_none()
+        {
+            return Err(at!(BitmapError::DimensionsTooLarge { width, height }));
+        }
+        let chroma = match header.layout {
+            YuvLayout::I420 => "420jpeg",
+            YuvLayout::I422 => "422",
+            YuvLayout::I444 => "444",
+            YuvLayout::Gray => "mono",
+            other => {
+                return Err(at!(BitmapError::UnsupportedVariant(format!(
+                    "Y4M cannot hold {other:?} frames"
+                ))));
+            }
+        };
+        if !matches!(header.interlace, b'p' | b't' | b'b' | b'm' | b'?') {
+            return Err(at!(BitmapError::invalid_data(format!(
+                "Y4M interlace tag '{}' is not one of p, t, b, m",
+                header.interlace.escape_ascii()
+            ))));
+        }
+
+        let mut line = format!("YUV4MPEG2 W{width} H{height}");
+        if let Some((num, den)) = header.frame_rate {
+            line += &format!(" F{num}:{den}");
+        }
+        if header.interlace != b'?' {
+            line += &format!(" I{}", char::from(header.interlace));
+        }
+        if let Some((num, den)) = header.pixel_aspect {
+            line += &format!(" A{num}:{den}");
+        }
+        line += &format!(" C{chroma}");
+        if header.full_range {
+            line += " XCOLORRANGE=FULL";
+        }
+        line.push('\n');
+        Ok(Self {
+            header,
+            out: line.into_bytes(),
+            frames: 0,
+        })
+    }
+
+    /// Continue the stream in `data`, keeping its header and frames.
+    ///
+    /// Every existing frame is checked, so `data` must end on a frame
+    /// boundary.
+    pub fn append(data: Vec<u8>) -> crate::Result<Self> {
+        let (header, _) = parse_header(&data)?;
+        let mut count = 0u32;
+        for frame in frames(&data, None, Unstoppable)? {
+            frame?;
+            count = count.saturating_add(1);
+        }
+        Ok(Self {
+            header,
+            out: data,
+            frames: count,
+        })
+    }
+
+    /// Stream parameters.
+    pub fn header(&self) -> &Y4mHeader {
+        &self.header
+    }
+
+    /// Frames in the stream so far, including any from [`append`](Self::append).
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Append one frame of planes in the header's layout: Y, then U and V,
+    /// exactly [`Y4mHeader::frame_size`] bytes.
+    pub fn push_frame(&mut self, planes: &[u8]) -> crate::Result<()> {
+        let size = self.header.frame_size();
+        if planes.len() != size {
+            return Err(at!(BitmapError::invalid_data(format!(
+                "Y4M frame is {} bytes, expected {size}",
+                planes.len()
+            ))));
+        }
+        self.out.extend_from_slice(b"FRAME\n");
+        self.out.extend_from_slice(planes);
+        self.frames = self.frames.saturating_add(1);
+        Ok(())
+    }
+
+    /// Convert one `width × height` image and append it as a frame.
+    ///
+    /// Accepts `Gray8`, `Rgb8`, `Rgba8`, `Rgbx8`, `Bgr8`, `Bgra8` and
+    /// `Bgrx8`; alpha is dropped. Chroma samples average the pixels they
+    /// cover. The header's `full_range` flag is not consulted; `matrix`
+    /// alone decides the range.
+    pub fn push_rgb(
+        &mut self,
+        pixels: &[u8],
+        layout: PixelLayout,
+        matrix: YuvMatrix,
+        stop: impl Stop,
+    ) -> crate::Result<()> {
+        let Y4mHeader { width, height, .. } = self.header;
+        let start = self.out.len() + b"FRAME\n".len();
+        self.out.extend_from_slice(b"FRAME\n");
+        self.out.resize(start + self.header.frame_size(), 0);
+        let converted = yuv::rgb_to_frame(
+            pixels,
+            width,
+            height,
+            layout,
+            self.header.layout,
+            matrix,
+            &mut self.out[start..],
+            &stop,
+        );
+        if let Err(e) = converted {
+            // Leave the stream as it was.
+            self.out.truncate(start - b"FRAME\n".len());
+            return Err(e);
+        }
+        self.frames = self.frames.saturating_add(1);
+        Ok(())
+    }
+
+    /// The finished stream.
+    pub fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
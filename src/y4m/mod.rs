@@ -0,0 +1,385 @@
+//! YUV4MPEG2 (Y4M) container decoding (internal).
+//!
+//! Use top-level [`crate::probe_y4m`], [`crate::decode_y4m`] and
+//! [`crate::decode_y4m_frames`] to read, and [`Y4mWriter`] to write.
+//!
+//! A Y4M file is one text header line (`YUV4MPEG2` followed by
+//! space-separated tagged parameters), then frames, each a `FRAME` line
+//! followed by the raw planes. The planes are the same as
+//! [`YuvLayout::I420`], [`YuvLayout::I422`], [`YuvLayout::I444`] or
+//! [`YuvLayout::Gray`], so conversion goes through [`crate::yuv`].
+
+mod encode;
+pub use encode::Y4mWriter;
+
+use alloc::format;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+use crate::yuv::{self, YuvLayout, YuvMatrix};
+
+const MAGIC: &[u8] = b"YUV4MPEG2";
+const FRAME: &[u8] = b"FRAME";
+
+/// Stream parameters from a Y4M header line.
+///
+/// Returned by [`crate::probe_y4m`] and [`Y4mFrames::header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Y4mHeader {
+    /// Frame width in pixels (`W`).
+    pub width: u32,
+
+    /// Frame height in pixels (`H`).
+    pub height: u32,
+
+    /// Frames per second as `(numerator, denominator)` (`F`); `None` when
+    /// absent or `0:0`.
+    pub frame_rate: Option<(u32, u32)>,
+
+    /// Pixel aspect ratio as `(numerator, denominator)` (`A`); `None` when
+    /// absent or `0:0` (unknown).
+    pub pixel_aspect: Option<(u32, u32)>,
+
+    /// Interlacing tag (`I`): `b'p'` progressive, `b't'` top field first,
+    /// `b'b'` bottom field first, `b'm'` mixed, `b'?'` when absent.
+    pub interlace: u8,
+
+    /// Plane layout from the chroma tag (`C`). `420jpeg`, `420mpeg2`,
+    /// `420paldv` and `420` (which differ only in chroma siting) map to
+    /// [`YuvLayout::I420`], `422` to [`YuvLayout::I422`], `444` to
+    /// [`YuvLayout::I444`] and `mono` to [`YuvLayout::Gray`]. The default,
+    /// when absent, is `420jpeg`.
+    pub layout: YuvLayout,
+
+    /// `XCOLORRANGE=FULL` was present. Y4M has no matrix tag, so this is
+    /// only a hint for choosing a [`YuvMatrix`].
+    pub full_range: bool,
+}
+
+impl Y4mHeader {
+    /// Header for a `width × height` stream with the given plane layout;
+    /// frame rate, aspect and interlacing unspecified, limited range.
+    pub fn new(width: u32, height: u32, layout: YuvLayout) -> Self {
+        Self {
+            width,
+            height,
+            frame_rate: None,
+            pixel_aspect: None,
+            interlace: b'?',
+            layout,
+            full_range: false,
+        }
+    }
+
+    /// Frames per second as `numerator / denominator`.
+    pub fn with_frame_rate(mut self, numerator: u32, denominator: u32) -> Self {
+        self.frame_rate = Some((numerator, denominator));
+        self
+    }
+
+    /// Pixel aspect ratio as `numerator : denominator`.
+    pub fn with_pixel_aspect(mut self, numerator: u32, denominator: u32) -> Self {
+        self.pixel_aspect = Some((numerator, denominator));
+        self
+    }
+
+    /// Interlacing tag: `b'p'`, `b't'`, `b'b'` or `b'm'`; `b'?'` omits it.
+    pub fn with_interlace(mut self, interlace: u8) -> Self {
+        self.interlace = interlace;
+        self
+    }
+
+    /// Mark the stream full range (`XCOLORRANGE=FULL`).
+    pub fn with_full_range(mut self, full_range: bool) -> Self {
+        self.full_range = full_range;
+        self
+    }
+
+    /// Bytes of plane data in each frame.
+    pub fn frame_size(&self) -> usize {
+        // Overflow was ruled out when parsing.
+        self.layout.frame_size(self.width, self.height).unwrap_or(0)
+    }
+}
+
+/// One frame of a Y4M stream, borrowing its planes from the input.
+///
+/// Yielded by [`Y4mFrames`].
+#[derive(Debug, Clone, Copy)]
+pub struct Y4mFrame<'a> {
+    header: Y4mHeader,
+    data: &'a [u8],
+    limits: Option<&'a Limits>,
+}
+
+impl<'a> Y4mFrame<'a> {
+    /// All plane data of the frame: Y, then U and V (if any).
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Luma plane, `width × height` bytes.
+    pub fn y(&self) -> &'a [u8] {
+        &self.data[..self.header.width as usize * self.header.height as usize]
+    }
+
+    /// Cb plane; empty for `mono` streams.
+    pub fn u(&self) -> &'a [u8] {
+        let (start, len) = self.chroma_plane();
+        &self.data[start..start + len]
+    }
+
+    /// Cr plane; empty for `mono` streams.
+    pub fn v(&self) -> &'a [u8] {
+        let (start, len) = self.chroma_plane();
+        &self.data[start + len..]
+    }
+
+    /// Convert to [`PixelLayout::Rgb8`] with the given matrix.
+    pub fn to_rgb(
+        &self,
+        matrix: YuvMatrix,
+        stop: impl Stop,
+    ) -> crate::Result<DecodeOutput<'static>> {
+        let Y4mHeader {
+            width,
+            height,
+            layout,
+            ..
+        } = self.header;
+        let out_bytes = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|px| px.checked_mul(3))
+            .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+        limits::check_output_size(out_bytes, self.limits)?;
+        let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+        yuv::frame_to_rgb(self.data, width, height, layout, matrix, &mut out, &stop)?;
+        Ok(DecodeOutput::owned(out, width, height, PixelLayout::Rgb8))
+    }
+
+    /// Offset and length of the U plane (the V plane follows it).
+    fn chroma_plane(&self) -> (usize, usize) {
+        let luma = self.header.width as usize * self.header.height as usize;
+        (luma, (self.data.len() - luma) / 2)
+    }
+}
+
+/// Iterator over the frames of a Y4M stream.
+///
+/// Returned by [`crate::decode_y4m_frames`]. Yields frames in file order
+/// without copying; call [`Y4mFrame::to_rgb`] to convert. A damaged or
+/// truncated frame yields its error and ends the iteration.
+pub struct Y4mFrames<'a, S> {
+    header: Y4mHeader,
+    data: &'a [u8],
+    offset: usize,
+    limits: Option<&'a Limits>,
+    stop: S,
+    failed: bool,
+}
+
+impl<S> Y4mFrames<'_, S> {
+    /// Stream parameters.
+    pub fn header(&self) -> &Y4mHeader {
+        &self.header
+    }
+
+    /// Byte offset of the next `FRAME` marker.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a, S: Stop> Iterator for Y4mFrames<'a, S> {
+    type Item = crate::Result<Y4mFrame<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.offset == self.data.len() {
+            return None;
+        }
+        let frame = self
+            .stop
+            .check()
+            .map_err(|r| at!(BitmapError::from(r)))
+            .and_then(|()| next_frame(self.data, self.offset, &self.header));
+        Some(match frame {
+            Ok((data, end)) => {
+                self.offset = end;
+                Ok(Y4mFrame {
+                    header: self.header,
+                    data,
+                    limits: self.limits,
+                })
+            }
+            Err(e) => {
+                self.failed = true;
+                Err(e)
+            }
+        })
+    }
+}
+
+impl<S: Stop> core::iter::FusedIterator for Y4mFrames<'_, S> {}
+
+/// Parse the header line, returning it with the offset just past its `\n`.
+pub(crate) fn parse_header(data: &[u8]) -> crate::Result<(Y4mHeader, usize)> {
+    if !data.starts_with(MAGIC) {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+    let end = data
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let line = &data[MAGIC.len()..end];
+    if !line.is_empty() && line[0] != b' ' {
+        return Err(at!(BitmapError::UnrecognizedFormat));
+    }
+
+    let (mut width, mut height) = (None, None);
+    let mut header = Y4mHeader::new(0, 0, YuvLayout::I420);
+    for token in line.split(|&b| b == b' ').filter(|t| !t.is_empty()) {
+        let (tag, value) = (token[0], &token[1..]);
+        match tag {
+            b'W' => width = Some(parse_u32(value, "width")?),
+            b'H' => height = Some(parse_u32(value, "height")?),
+            b'F' => header.frame_rate = parse_ratio(value, "frame rate")?,
+            b'A' => header.pixel_aspect = parse_ratio(value, "pixel aspect")?,
+            b'I' => header.interlace = value.first().copied().unwrap_or(b'?'),
+            b'C' => {
+                header.layout = match value {
+                    b"420jpeg" | b"420mpeg2" | b"420paldv" | b"420" => YuvLayout::I420,
+                    b"422" => YuvLayout::I422,
+                    b"444" => YuvLayout::I444,
+                    b"mono" => YuvLayout::Gray,
+                    _ => {
+                        return Err(at!(BitmapError::UnsupportedVariant(format!(
+                            "Y4M colorspace '{}' is not supported",
+                            value.escape_ascii()
+                        ))));
+                    }
+                }
+            }
+            b'X' if value == b"COLORRANGE=FULL" => header.full_range = true,
+            // Other `X` extensions and unknown tags are skipped.
+            _ => {}
+        }
+    }
+
+    let (Some(width), Some(height)) = (width, height) else {
+        return Err(at!(BitmapError::invalid_header(
+            "Y4M header is missing W or H"
+        )));
+    };
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "Y4M width or height is zero"
+        )));
+    }
+    if header.layout.frame_size(width, height).is_none() {
+        return Err(at!(BitmapError::DimensionsTooLarge { width, height }));
+    }
+    header.width = width;
+    header.height = height;
+    Ok((header, end + 1))
+}
+
+fn parse_u32(value: &[u8], what: &str) -> crate::Result<u32> {
+    core::str::from_utf8(value)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            at!(BitmapError::invalid_header(format!(
+                "Y4M {what} '{}' is not a number",
+                value.escape_ascii()
+            )))
+        })
+}
+
+/// Parse `n:d`; `0:0` means unknown.
+fn parse_ratio(value: &[u8], what: &str) -> crate::Result<Option<(u32, u32)>> {
+    let colon = value.iter().position(|&b| b == b':').ok_or_else(|| {
+        at!(BitmapError::invalid_header(format!(
+            "Y4M {what} '{}' is not a ratio",
+            value.escape_ascii()
+        )))
+    })?;
+    let num = parse_u32(&value[..colon], what)?;
+    let den = parse_u32(&value[colon + 1..], what)?;
+    Ok(if num == 0 && den == 0 {
+        None
+    } else {
+        Some((num, den))
+    })
+}
+
+/// Locate the frame at `offset`, returning its planes and the offset just
+/// past them.
+fn next_frame<'a>(
+    data: &'a [u8],
+    offset: usize,
+    header: &Y4mHeader,
+) -> crate::Result<(&'a [u8], usize)> {
+    let rest = &data[offset..];
+    let bad_marker = || {
+        at!(BitmapError::invalid_data(format!(
+            "expected Y4M FRAME marker at byte {offset}"
+        )))
+    };
+    if !rest.starts_with(FRAME) {
+        return Err(if FRAME.starts_with(rest) {
+            at!(BitmapError::unexpected_eof())
+        } else {
+            bad_marker()
+        });
+    }
+    let line_end = rest
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    if !matches!(rest[FRAME.len()], b' ' | b'\n') {
+        return Err(bad_marker());
+    }
+    let start = offset + line_end + 1;
+    let end = start + header.frame_size();
+    let planes = data
+        .get(start..end)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    Ok((planes, end))
+}
+
+pub(crate) fn frames<'a, S: Stop>(
+    data: &'a [u8],
+    limits: Option<&'a Limits>,
+    stop: S,
+) -> crate::Result<Y4mFrames<'a, S>> {
+    limits::check_file_size(data.len(), limits)?;
+    let (header, offset) = parse_header(data)?;
+    limits::check_dimensions(header.width, header.height, limits)?;
+    Ok(Y4mFrames {
+        header,
+        data,
+        offset,
+        limits,
+        stop,
+        failed: false,
+    })
+}
+
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    matrix: YuvMatrix,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    let mut frames = frames(data, limits, stop)?;
+    let frame = frames
+        .next()
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))??;
+    frame.to_rgb(matrix, stop)
+}
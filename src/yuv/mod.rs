@@ -0,0 +1,357 @@
+//! Raw 8-bit and 10-bit YUV frames to RGB (internal).
+//!
+//! Use top-level [`crate::decode_yuv`] and [`crate::decode_yuv10`].
+//!
+//! Frames are headerless, so geometry and layout come from the caller.
+//! Chroma is upsampled by replication (each chroma sample covers its
+//! 2×1 or 2×2 luma block), which keeps the result exactly reproducible
+//! across tools that agree on the matrix.
+
+use alloc::vec;
+use enough::Stop;
+use whereat::at;
+
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+mod ten_bit;
+pub use ten_bit::Yuv10Layout;
+pub(crate) use ten_bit::decode as decode_10bit;
+
+/// Memory layout of a raw 8-bit YUV frame.
+///
+/// Chroma planes of subsampled layouts are `ceil(width / 2)` wide (and
+/// `ceil(height / 2)` tall for 4:2:0), with no row padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum YuvLayout {
+    /// 4:2:0 planar: Y plane, then U, then V.
+    I420,
+    /// 4:2:0 planar with the chroma planes swapped: Y, V, U.
+    Yv12,
+    /// 4:2:0 semi-planar: Y plane, then one plane of interleaved U/V pairs.
+    Nv12,
+    /// 4:2:2 packed: `Y0 U Y1 V` per pair of pixels.
+    Yuy2,
+    /// 4:2:2 planar: Y, U, V with full-height chroma.
+    I422,
+    /// 4:4:4 planar: Y, U, V all full size.
+    I444,
+    /// Luma plane only (Y800); converts to neutral gray.
+    Gray,
+}
+
+impl YuvLayout {
+    /// Horizontal and vertical chroma subsampling shifts.
+    pub(crate) fn chroma_shift(self) -> (u32, u32) {
+        match self {
+            Self::I420 | Self::Yv12 | Self::Nv12 => (1, 1),
+            Self::Yuy2 | Self::I422 => (1, 0),
+            Self::I444 | Self::Gray => (0, 0),
+        }
+    }
+
+    /// Bytes in one `width × height` frame, or `None` on overflow.
+    pub fn frame_size(self, width: u32, height: u32) -> Option<usize> {
+        if self == Self::Gray {
+            return (width as usize).checked_mul(height as usize);
+        }
+        let (sx, sy) = self.chroma_shift();
+        let chroma = (width.div_ceil(1 << sx) as usize)
+            .checked_mul(height.div_ceil(1 << sy) as usize)?
+            .checked_mul(2)?;
+        if self == Self::Yuy2 {
+            // An odd final pixel still occupies a whole `Y U Y V` quad.
+            return chroma.checked_mul(2);
+        }
+        (width as usize)
+            .checked_mul(height as usize)?
+            .checked_add(chroma)
+    }
+}
+
+/// YCbCr → RGB conversion matrix and range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum YuvMatrix {
+    /// BT.601, limited range (Y 16-235, chroma 16-240). SD video.
+    Bt601,
+    /// BT.709, limited range. HD video.
+    Bt709,
+    /// BT.2020 non-constant luminance, limited range.
+    Bt2020,
+    /// BT.601, full range (0-255), as used by JPEG/JFIF.
+    Bt601Full,
+}
+
+impl YuvMatrix {
+    /// `(Kr, Kb, full_range)`.
+    fn params(self) -> (f32, f32, bool) {
+        match self {
+            Self::Bt601 => (0.299, 0.114, false),
+            Self::Bt709 => (0.2126, 0.0722, false),
+            Self::Bt2020 => (0.2627, 0.0593, false),
+            Self::Bt601Full => (0.299, 0.114, true),
+        }
+    }
+}
+
+/// Per-matrix conversion constants, for `u8` YCbCr in and `u8` RGB out
+/// (or 10-bit in and `u16` out).
+pub(crate) struct Converter {
+    y_offset: f32,
+    y_scale: f32,
+    c_center: f32,
+    c_scale: f32,
+    r_cr: f32,
+    g_cb: f32,
+    g_cr: f32,
+    b_cb: f32,
+}
+
+impl Converter {
+    fn new(matrix: YuvMatrix) -> Self {
+        Self::with_depth(matrix, 8, 255.0)
+    }
+
+    /// Constants for `bits`-deep YCbCr (the limited range scaled up from
+    /// 8 bits) to RGB in `0..=out_max`.
+    pub(crate) fn with_depth(matrix: YuvMatrix, bits: u32, out_max: f32) -> Self {
+        let (kr, kb, full) = matrix.params();
+        let kg = 1.0 - kr - kb;
+        let step = (1u32 << (bits - 8)) as f32;
+        let (y_offset, y_scale, c_scale) = if full {
+            let max = ((1u32 << bits) - 1) as f32;
+            (0.0, out_max / max, out_max / max)
+        } else {
+            (
+                16.0 * step,
+                out_max / (219.0 * step),
+                out_max / (224.0 * step),
+            )
+        };
+        Self {
+            y_offset,
+            y_scale,
+            c_center: 128.0 * step,
+            c_scale,
+            r_cr: 2.0 * (1.0 - kr),
+            g_cb: 2.0 * kb * (1.0 - kb) / kg,
+            g_cr: 2.0 * kr * (1.0 - kr) / kg,
+            b_cb: 2.0 * (1.0 - kb),
+        }
+    }
+
+    /// RGB in the output scale, unclamped.
+    #[inline]
+    fn rgb_f32(&self, y: f32, cb: f32, cr: f32) -> [f32; 3] {
+        let y = (y - self.y_offset) * self.y_scale;
+        let cb = (cb - self.c_center) * self.c_scale;
+        let cr = (cr - self.c_center) * self.c_scale;
+        [
+            y + self.r_cr * cr,
+            y - self.g_cb * cb - self.g_cr * cr,
+            y + self.b_cb * cb,
+        ]
+    }
+
+    #[inline]
+    fn rgb(&self, y: u8, cb: u8, cr: u8) -> [u8; 3] {
+        // `as u8` saturates; +0.5 rounds the non-negative values.
+        self.rgb_f32(f32::from(y), f32::from(cb), f32::from(cr))
+            .map(|v| (v + 0.5) as u8)
+    }
+
+    #[inline]
+    pub(crate) fn rgb16(&self, y: u16, cb: u16, cr: u16) -> [u16; 3] {
+        self.rgb_f32(f32::from(y), f32::from(cb), f32::from(cr))
+            .map(|v| (v + 0.5) as u16)
+    }
+}
+
+/// Convert one frame (exactly [`YuvLayout::frame_size`] bytes) into `out`
+/// (`width * height * 3` bytes of RGB).
+pub(crate) fn frame_to_rgb(
+    frame: &[u8],
+    width: u32,
+    height: u32,
+    layout: YuvLayout,
+    matrix: YuvMatrix,
+    out: &mut [u8],
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    let (w, h) = (width as usize, height as usize);
+    let (sx, sy) = layout.chroma_shift();
+    let cw = width.div_ceil(1 << sx) as usize;
+    let ch = height.div_ceil(1 << sy) as usize;
+    let conv = Converter::new(matrix);
+    // Plane offsets; unused by packed YUY2.
+    let luma_len = w * h;
+    let (first, second) = (luma_len, luma_len + cw * ch);
+    // Chroma stays at the neutral 128 for `Gray`.
+    let mut rows = vec![128u8; w + 2 * cw];
+    let (y_row, uv) = rows.split_at_mut(w);
+    let (u_row, v_row) = uv.split_at_mut(cw);
+
+    for (y, out_row) in out.chunks_exact_mut(w * 3).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        let c = (y >> sy) * cw;
+        match layout {
+            YuvLayout::I420 | YuvLayout::I422 | YuvLayout::I444 => {
+                y_row.copy_from_slice(&frame[y * w..(y + 1) * w]);
+                u_row.copy_from_slice(&frame[first + c..first + c + cw]);
+                v_row.copy_from_slice(&frame[second + c..second + c + cw]);
+            }
+            YuvLayout::Yv12 => {
+                y_row.copy_from_slice(&frame[y * w..(y + 1) * w]);
+                v_row.copy_from_slice(&frame[first + c..first + c + cw]);
+                u_row.copy_from_slice(&frame[second + c..second + c + cw]);
+            }
+            YuvLayout::Gray => y_row.copy_from_slice(&frame[y * w..(y + 1) * w]),
+            YuvLayout::Nv12 => {
+                y_row.copy_from_slice(&frame[y * w..(y + 1) * w]);
+                let pairs = &frame[first + c * 2..first + (c + cw) * 2];
+                for ((u, v), pair) in u_row
+                    .iter_mut()
+                    .zip(v_row.iter_mut())
+                    .zip(pairs.chunks_exact(2))
+                {
+                    (*u, *v) = (pair[0], pair[1]);
+                }
+            }
+            YuvLayout::Yuy2 => {
+                let packed = &frame[y * cw * 4..(y + 1) * cw * 4];
+                for (i, quad) in packed.chunks_exact(4).enumerate() {
+                    y_row[2 * i] = quad[0];
+                    if let Some(y1) = y_row.get_mut(2 * i + 1) {
+                        *y1 = quad[2];
+                    }
+                    (u_row[i], v_row[i]) = (quad[1], quad[3]);
+                }
+            }
+        }
+        for (x, px) in out_row.chunks_exact_mut(3).enumerate() {
+            px.copy_from_slice(&conv.rgb(y_row[x], u_row[x >> sx], v_row[x >> sx]));
+        }
+    }
+    Ok(())
+}
+
+/// Convert 8-bit RGB-family pixels into one Y, U, V planar frame (I420,
+/// I422, I444 or Gray; exactly [`YuvLayout::frame_size`] bytes in `out`).
+/// Chroma is the average of the pixels each sample covers; alpha and
+/// padding bytes are ignored.
+#[cfg(feature = "y4m")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn rgb_to_frame(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    pixel_layout: PixelLayout,
+    layout: YuvLayout,
+    matrix: YuvMatrix,
+    out: &mut [u8],
+    stop: &dyn Stop,
+) -> crate::Result<()> {
+    let [ri, gi, bi] = match pixel_layout {
+        PixelLayout::Gray8 => [0, 0, 0],
+        PixelLayout::Rgb8 | PixelLayout::Rgba8 | PixelLayout::Rgbx8 => [0, 1, 2],
+        PixelLayout::Bgr8 | PixelLayout::Bgra8 | PixelLayout::Bgrx8 => [2, 1, 0],
+        other => {
+            return Err(at!(BitmapError::UnsupportedVariant(alloc::format!(
+                "cannot convert {other:?} to YUV; expected 8-bit gray, RGB or BGR"
+            ))));
+        }
+    };
+    let bpp = pixel_layout.bytes_per_pixel();
+    let (w, h) = (width as usize, height as usize);
+    let needed = w * h * bpp;
+    if pixels.len() < needed {
+        return Err(at!(BitmapError::BufferTooSmall {
+            needed,
+            actual: pixels.len(),
+        }));
+    }
+    let pixels = &pixels[..needed];
+    let (kr, kb, full) = matrix.params();
+    let kg = 1.0 - kr - kb;
+    let (y_offset, y_scale, c_scale) = if full {
+        (0.0, 1.0, 1.0)
+    } else {
+        (16.0, 219.0 / 255.0, 224.0 / 255.0)
+    };
+    let (sx, sy) = layout.chroma_shift();
+    let cw = width.div_ceil(1 << sx) as usize;
+    let ch = height.div_ceil(1 << sy) as usize;
+    // Per chroma sample: Cb sum, Cr sum, pixel count.
+    let mut sums = if layout == YuvLayout::Gray {
+        vec![]
+    } else {
+        vec![(0.0f32, 0.0f32, 0.0f32); cw * ch]
+    };
+    let (luma, chroma) = out.split_at_mut(w * h);
+
+    for (y, (src, dst)) in pixels
+        .chunks_exact(w * bpp)
+        .zip(luma.chunks_exact_mut(w))
+        .enumerate()
+    {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        for (x, (px, dst)) in src.chunks_exact(bpp).zip(dst.iter_mut()).enumerate() {
+            let (r, g, b) = (f32::from(px[ri]), f32::from(px[gi]), f32::from(px[bi]));
+            let luma = kr * r + kg * g + kb * b;
+            *dst = (luma * y_scale + y_offset + 0.5) as u8;
+            if let Some(sum) = sums.get_mut((y >> sy) * cw + (x >> sx)) {
+                sum.0 += (b - luma) / (2.0 * (1.0 - kb));
+                sum.1 += (r - luma) / (2.0 * (1.0 - kr));
+                sum.2 += 1.0;
+            }
+        }
+    }
+
+    let to_u8 = |v: f32| (v * c_scale + 128.5) as u8;
+    let (u_plane, v_plane) = chroma.split_at_mut(cw * ch);
+    for ((&(cb, cr, n), u), v) in sums.iter().zip(u_plane).zip(v_plane) {
+        (*u, *v) = (to_u8(cb / n), to_u8(cr / n));
+    }
+    Ok(())
+}
+
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    layout: YuvLayout,
+    matrix: YuvMatrix,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "YUV width or height is zero"
+        )));
+    }
+    limits::check_dimensions(width, height, limits)?;
+    let frame_size = layout
+        .frame_size(width, height)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let frame = data
+        .get(..frame_size)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let out_bytes = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(3))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+    frame_to_rgb(frame, width, height, layout, matrix, &mut out, stop)?;
+    Ok(DecodeOutput::owned(out, width, height, PixelLayout::Rgb8))
+}
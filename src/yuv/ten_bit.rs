@@ -0,0 +1,140 @@
+//! Raw 10-bit YUV frames (V210, P010) to 16-bit RGB.
+
+use alloc::vec;
+use enough::Stop;
+use whereat::at;
+
+use super::{Converter, YuvMatrix};
+use crate::alloc_util::{self, AllocPref};
+use crate::decode::DecodeOutput;
+use crate::error::BitmapError;
+use crate::limits::{self, Limits};
+use crate::pixel::PixelLayout;
+
+/// Memory layout of a raw 10-bit YUV frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Yuv10Layout {
+    /// 4:2:2 packed: three 10-bit samples per little-endian 32-bit word,
+    /// six pixels (`Cb Y Cr Y Cb Y Cr Y Cb Y Cr Y`) per 16 bytes, rows
+    /// padded to a multiple of 128 bytes (48 pixels).
+    V210,
+    /// 4:2:0 semi-planar: a Y plane, then one plane of interleaved U/V
+    /// pairs, each sample a little-endian `u16` with the value in the top
+    /// 10 bits. No row padding; chroma is `ceil(width / 2)` pairs wide and
+    /// `ceil(height / 2)` rows tall.
+    P010,
+}
+
+impl Yuv10Layout {
+    /// Bytes in one row of a V210 frame.
+    fn v210_stride(width: u32) -> Option<usize> {
+        (width.div_ceil(48) as usize).checked_mul(128)
+    }
+
+    /// Bytes in one `width × height` frame, or `None` on overflow.
+    pub fn frame_size(self, width: u32, height: u32) -> Option<usize> {
+        match self {
+            Self::V210 => Self::v210_stride(width)?.checked_mul(height as usize),
+            Self::P010 => {
+                let luma = (width as usize).checked_mul(height as usize)?;
+                let chroma = (width.div_ceil(2) as usize)
+                    .checked_mul(height.div_ceil(2) as usize)?
+                    .checked_mul(2)?;
+                luma.checked_add(chroma)?.checked_mul(2)
+            }
+        }
+    }
+}
+
+fn sample(data: &[u8], index: usize) -> u16 {
+    u16::from_le_bytes([data[2 * index], data[2 * index + 1]]) >> 6
+}
+
+/// Convert the first frame to [`PixelLayout::Rgb16`], chroma upsampled by
+/// replication.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode<'a>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    layout: Yuv10Layout,
+    matrix: YuvMatrix,
+    limits: Option<&Limits>,
+    stop: &dyn Stop,
+) -> crate::Result<DecodeOutput<'a>> {
+    limits::check_file_size(data.len(), limits)?;
+    if width == 0 || height == 0 {
+        return Err(at!(BitmapError::invalid_header(
+            "YUV width or height is zero"
+        )));
+    }
+    limits::check_dimensions(width, height, limits)?;
+    let frame_size = layout
+        .frame_size(width, height)
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    let frame = data
+        .get(..frame_size)
+        .ok_or_else(|| at!(BitmapError::unexpected_eof()))?;
+    let out_bytes = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|px| px.checked_mul(6))
+        .ok_or_else(|| at!(BitmapError::DimensionsTooLarge { width, height }))?;
+    limits::check_output_size(out_bytes, limits)?;
+    let mut out = alloc_util::alloc_zeroed(AllocPref::CodecDefault, true, out_bytes)?;
+
+    let conv = Converter::with_depth(matrix, 10, 65535.0);
+    let (w, h) = (width as usize, height as usize);
+    let cw = w.div_ceil(2);
+    let mut rows = vec![0u16; w + 2 * cw];
+    let (y_row, uv) = rows.split_at_mut(w);
+    let (u_row, v_row) = uv.split_at_mut(cw);
+    let stride = Yuv10Layout::v210_stride(width).unwrap_or(0);
+    let (sy, luma_len) = match layout {
+        Yuv10Layout::V210 => (0, 0),
+        Yuv10Layout::P010 => (1, w * h),
+    };
+
+    for (y, out_row) in out.chunks_exact_mut(w * 6).enumerate() {
+        if y.is_multiple_of(16) {
+            stop.check().map_err(|r| at!(BitmapError::from(r)))?;
+        }
+        match layout {
+            Yuv10Layout::V210 => {
+                let row = &frame[y * stride..(y + 1) * stride];
+                // Samples in stream order: Cb Y Cr Y, repeating.
+                let samples = row
+                    .chunks_exact(4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .flat_map(|word| [0, 10, 20].map(|s| (word >> s & 0x3FF) as u16));
+                for (i, v) in samples.take(2 * w.next_multiple_of(2)).enumerate() {
+                    let slot = match i % 4 {
+                        0 => u_row.get_mut(i / 4),
+                        2 => v_row.get_mut(i / 4),
+                        _ => y_row.get_mut(i / 2),
+                    };
+                    if let Some(slot) = slot {
+                        *slot = v;
+                    }
+                }
+            }
+            Yuv10Layout::P010 => {
+                let c = (y >> sy) * cw;
+                for (x, v) in y_row.iter_mut().enumerate() {
+                    *v = sample(frame, y * w + x);
+                }
+                for (i, (u, v)) in u_row.iter_mut().zip(v_row.iter_mut()).enumerate() {
+                    *u = sample(frame, luma_len + 2 * (c + i));
+                    *v = sample(frame, luma_len + 2 * (c + i) + 1);
+                }
+            }
+        }
+        for (x, px) in out_row.chunks_exact_mut(6).enumerate() {
+            let rgb = conv.rgb16(y_row[x], u_row[x >> 1], v_row[x >> 1]);
+            for (d, s) in px.chunks_exact_mut(2).zip(rgb) {
+                d.copy_from_slice(&s.to_ne_bytes());
+            }
+        }
+    }
+    Ok(DecodeOutput::owned(out, width, height, PixelLayout::Rgb16))
+}
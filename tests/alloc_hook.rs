@@ -0,0 +1,151 @@
+//! `Limits::on_alloc` sees, and can refuse, decoder allocations.
+
+use std::sync::{Arc, Mutex};
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Limits recording every offered size, refusing those above `max`.
+fn recording(max: u64) -> (Limits, Arc<Mutex<Vec<u64>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    let limits = Limits::new().with_on_alloc(move |bytes| {
+        log.lock().unwrap().push(bytes);
+        bytes <= max
+    });
+    (limits, seen)
+}
+
+fn is_limit(err: &At<BitmapError>) -> bool {
+    matches!(err.error(), BitmapError::LimitExceeded(_))
+}
+
+#[test]
+fn pnm_output_is_offered() {
+    // 16-bit samples are widened into a new buffer.
+    let mut file = b"P5\n4 3\n65535\n".to_vec();
+    file.extend((0..4 * 3 * 2).map(|i| i as u8));
+    let (limits, seen) = recording(u64::MAX);
+    let decoded = decode_with_limits(&file, &limits, Unstoppable).unwrap();
+    assert_eq!(*seen.lock().unwrap(), [decoded.pixels().len() as u64]);
+
+    let (limits, _) = recording(0);
+    assert!(is_limit(
+        &decode_with_limits(&file, &limits, Unstoppable).unwrap_err()
+    ));
+}
+
+#[test]
+fn borrowed_output_is_not_offered() {
+    let pixels = [7u8; 4 * 3 * 3];
+    let file = encode_ppm(&pixels, 4, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let (limits, seen) = recording(0);
+    let decoded = decode_with_limits(&file, &limits, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_scratch_and_output_are_offered() {
+    let pixels: Vec<u8> = (0..5 * 4 * 3).map(|i| i as u8).collect();
+    let file = encode_bmp(&pixels, 5, 4, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let (limits, seen) = recording(u64::MAX);
+    let decoded = decode_bmp_with_limits(&file, &limits, Unstoppable).unwrap();
+    let seen = seen.lock().unwrap();
+    assert!(seen.contains(&(decoded.pixels().len() as u64)), "{seen:?}");
+
+    // Refusing only the largest buffer still fails the decode.
+    let largest = *seen.iter().max().unwrap();
+    let (limits, _) = recording(largest - 1);
+    assert!(is_limit(
+        &decode_bmp_with_limits(&file, &limits, Unstoppable).unwrap_err()
+    ));
+}
+
+/// Offered sizes of a decode that must see one of `expected` bytes, then
+/// check that refusing that size fails the decode.
+#[cfg(feature = "bmp")]
+fn assert_offered(expected: usize, decode: impl Fn(&Limits) -> Result<DecodeOutput<'static>>) {
+    let (limits, seen) = recording(u64::MAX);
+    decode(&limits).unwrap();
+    let seen = seen.lock().unwrap().clone();
+    assert!(
+        seen.contains(&(expected as u64)),
+        "{expected} not in {seen:?}"
+    );
+    let limits = Limits::new().with_on_alloc(move |bytes| bytes != expected as u64);
+    assert!(is_limit(&decode(&limits).unwrap_err()));
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_palette_table_is_offered() {
+    let palette = [[0, 0, 0], [255, 0, 0], [0, 128, 255]];
+    let file = encode_bmp_paletted(&[0, 1, 2, 1], &palette, 2, 2, Unstoppable).unwrap();
+    let entries = probe_bmp(&file).unwrap().color_table.unwrap().len();
+    assert_offered(entries * 4, |limits| {
+        decode_bmp_with_limits(&file, limits, Unstoppable).map(|d| d.into_owned())
+    });
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn dib_copy_is_offered() {
+    let pixels: Vec<u8> = (0..3 * 2 * 3).map(|i| i as u8).collect();
+    let file = encode_bmp(&pixels, 3, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let dib = &file[14..];
+    // The DIB gets a file header prepended.
+    assert_offered(file.len(), |limits| {
+        decode_dib_with_limits(dib, limits, Unstoppable).map(|d| d.into_owned())
+    });
+}
+
+#[cfg(feature = "ico")]
+#[test]
+fn ico_entry_copy_is_offered() {
+    let pixels = [9u8; 4 * 4 * 4];
+    let ico = encode_ico(
+        &[IcoImage::new(&pixels, 4, 4, PixelLayout::Rgba8)],
+        Unstoppable,
+    )
+    .unwrap();
+    let entry_size = probe_ico(&ico).unwrap().entries[0].size as usize;
+    assert_offered(entry_size + 14, |limits| {
+        decode_ico_with_limits(&ico, limits, Unstoppable).map(|d| d.into_owned())
+    });
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_array_entry_copy_is_offered() {
+    let pixels: Vec<u8> = (0..3 * 2 * 3).map(|i| i as u8).collect();
+    let file = encode_bmp(&pixels, 3, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    // A one-entry array: the embedded pixel offset is file-absolute.
+    let mut array = b"BA".to_vec();
+    array.extend_from_slice(&[0; 12]);
+    let mut embedded = file.clone();
+    let bits = u32::from_le_bytes(file[10..14].try_into().unwrap()) + 14;
+    embedded[10..14].copy_from_slice(&bits.to_le_bytes());
+    array.extend_from_slice(&embedded);
+    assert_offered(file.len(), |limits| {
+        decode_bmp_with_limits(&array, limits, Unstoppable).map(|d| d.into_owned())
+    });
+}
+
+#[test]
+fn farbfeld_output_is_offered() {
+    let pixels: Vec<u8> = (0..3 * 2 * 8).map(|i| i as u8).collect();
+    let file = encode_farbfeld(&pixels, 3, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let (limits, seen) = recording(u64::MAX);
+    decode_farbfeld_with_limits(&file, &limits, Unstoppable).unwrap();
+    assert_eq!(*seen.lock().unwrap(), [48]);
+
+    let (limits, _) = recording(47);
+    let err = decode_farbfeld_with_limits(&file, &limits, Unstoppable).unwrap_err();
+    assert!(is_limit(&err), "{err}");
+    // 8-bit output is half the size.
+    let (limits, _) = recording(23);
+    let err = decode_farbfeld_rgba8_with_limits(&file, &limits, Unstoppable).unwrap_err();
+    assert!(is_limit(&err), "{err}");
+}
@@ -0,0 +1,251 @@
+//! Alpha premultiplication and flattening helpers, and the encoder options
+//! that use them.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn integer_round_trips() {
+    let rgba8 = [
+        255, 128, 0, 255, 200, 100, 50, 128, 9, 9, 9, 0, 77, 66, 55, 1,
+    ];
+    let mut p = rgba8;
+    premultiply_alpha(&mut p, 4, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    assert_eq!(
+        p,
+        [255, 128, 0, 255, 100, 50, 25, 128, 0, 0, 0, 0, 0, 0, 0, 1]
+    );
+    unpremultiply_alpha(&mut p, 4, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    assert_eq!(&p[..8], &[255, 128, 0, 255, 199, 100, 50, 128]);
+    assert_eq!(&p[8..], &[0, 0, 0, 0, 0, 0, 0, 1]);
+
+    // 16-bit, native and big-endian: half alpha halves the color, and the
+    // round trip is within one step.
+    let samples: [u16; 4] = [60000, 30001, 1234, 32768];
+    for big in [false, true] {
+        type Codec = (fn(u16) -> [u8; 2], fn([u8; 2]) -> u16);
+        let (layout, (to, from)): (_, Codec) = match big {
+            true => (
+                PixelLayout::Rgba16Be,
+                (u16::to_be_bytes, u16::from_be_bytes),
+            ),
+            false => (PixelLayout::Rgba16, (u16::to_ne_bytes, u16::from_ne_bytes)),
+        };
+        let mut p: Vec<u8> = samples.iter().flat_map(|&v| to(v)).collect();
+        let read = |p: &[u8]| -> Vec<u16> { p.chunks(2).map(|c| from([c[0], c[1]])).collect() };
+        premultiply_alpha(&mut p, 1, 1, layout, Unstoppable).unwrap();
+        assert_eq!(read(&p), [30000, 15001, 617, 32768], "{layout:?}");
+        unpremultiply_alpha(&mut p, 1, 1, layout, Unstoppable).unwrap();
+        for (got, want) in read(&p).into_iter().zip(samples) {
+            assert!(got.abs_diff(want) <= 1, "{layout:?}: {got} vs {want}");
+        }
+    }
+    let mut gray: Vec<u8> = [1000u16, 0].iter().flat_map(|v| v.to_ne_bytes()).collect();
+    premultiply_alpha(&mut gray, 1, 1, PixelLayout::GrayA16, Unstoppable).unwrap();
+    assert_eq!(gray, [0; 4]);
+}
+
+#[test]
+fn float_and_planar() {
+    let rgba: [f32; 4] = [0.5, 1.0, 2.0, 0.25];
+    let mut p: Vec<u8> = rgba.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    premultiply_alpha(&mut p, 1, 1, PixelLayout::RgbaF32, Unstoppable).unwrap();
+    let get = |p: &[u8], i: usize| f32::from_ne_bytes(p[i * 4..i * 4 + 4].try_into().unwrap());
+    assert_eq!(
+        [get(&p, 0), get(&p, 1), get(&p, 2), get(&p, 3)],
+        [0.125, 0.25, 0.5, 0.25]
+    );
+    unpremultiply_alpha(&mut p, 1, 1, PixelLayout::RgbaF32, Unstoppable).unwrap();
+    assert_eq!(get(&p, 2), 2.0);
+
+    // Two pixels, planes R R G G B B A A
+    let mut planes = [200, 10, 100, 20, 50, 30, 128, 255];
+    premultiply_alpha(&mut planes, 2, 1, PixelLayout::RgbaPlanar8, Unstoppable).unwrap();
+    assert_eq!(planes, [100, 10, 50, 20, 25, 30, 128, 255]);
+}
+
+#[test]
+fn errors() {
+    for layout in [PixelLayout::Rgb8, PixelLayout::Rgbx8, PixelLayout::Gray16] {
+        let err = premultiply_alpha(&mut [0; 16], 1, 1, layout, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+            "{layout:?}"
+        );
+    }
+    let err = unpremultiply_alpha(&mut [0; 7], 2, 1, PixelLayout::Bgra8, Unstoppable).unwrap_err();
+    assert!(matches!(
+        err.error(),
+        BitmapError::BufferTooSmall {
+            needed: 8,
+            actual: 7
+        }
+    ));
+}
+
+#[test]
+fn farbfeld_unpremultiplies_input() {
+    let premultiplied = [100, 50, 0, 128];
+    let options = FarbfeldEncodeOptions::new().with_alpha_mode(AlphaMode::Premultiplied);
+    assert_eq!(options.alpha_mode(), AlphaMode::Premultiplied);
+    let ff = encode_farbfeld_with(
+        &premultiplied,
+        1,
+        1,
+        PixelLayout::Rgba8,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    let decoded = decode(&ff, Unstoppable).unwrap();
+    let r = u16::from_ne_bytes([decoded.pixels()[0], decoded.pixels()[1]]);
+    assert_eq!(r, 199 * 257);
+    // Layouts without alpha are unaffected.
+    let rgb = encode_farbfeld_with(&[1, 2, 3], 1, 1, PixelLayout::Rgb8, &options, Unstoppable);
+    let plain = encode_farbfeld(&[1, 2, 3], 1, 1, PixelLayout::Rgb8, Unstoppable);
+    assert_eq!(rgb.unwrap(), plain.unwrap());
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_unpremultiplies_input() {
+    let premultiplied = [100, 50, 0, 128, 0, 0, 0, 0];
+    let options = BmpEncodeOptions::new()
+        .with_alpha(true)
+        .with_alpha_mode(AlphaMode::Premultiplied);
+    let bmp = encode_bmp_with(
+        &premultiplied,
+        2,
+        1,
+        PixelLayout::Rgba8,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    let decoded = decode(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[199, 100, 0, 128, 0, 0, 0, 0]);
+
+    // The row encoder goes through the same path.
+    let mut rows = BmpRowEncoder::new(2, PixelLayout::Rgba8, &options).unwrap();
+    rows.push_rows(&premultiplied).unwrap();
+    assert_eq!(rows.finish(Unstoppable).unwrap(), bmp);
+}
+
+#[cfg(feature = "tiff")]
+#[test]
+fn tiff_declares_associated_alpha() {
+    let premultiplied = [100, 50, 0, 128];
+    let options = TiffEncodeOptions::new().with_alpha_mode(AlphaMode::Premultiplied);
+    let tiff = encode_tiff_with(
+        &premultiplied,
+        1,
+        1,
+        PixelLayout::Rgba8,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    // Written as is; the decoder sees associated alpha and unpremultiplies.
+    assert!(tiff.windows(4).any(|w| w == premultiplied));
+    let decoded = decode_tiff(&tiff, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[199, 100, 0, 128]);
+}
+
+#[test]
+fn flatten_layouts() {
+    // Half-transparent red over blue, in each layout's channel order.
+    let mut rgba = [200, 0, 0, 128, 9, 9, 9, 0, 1, 2, 3, 255];
+    flatten_alpha(
+        &mut rgba,
+        3,
+        1,
+        PixelLayout::Rgba8,
+        [0, 0, 255],
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(rgba, [100, 0, 127, 255, 0, 0, 255, 255, 1, 2, 3, 255]);
+    let mut bgra = [0, 0, 200, 128];
+    flatten_alpha(
+        &mut bgra,
+        1,
+        1,
+        PixelLayout::Bgra8,
+        [0, 0, 255],
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(bgra, [127, 0, 100, 255]);
+
+    let mut gray: Vec<u8> = [u16::MAX, 0].iter().flat_map(|v| v.to_ne_bytes()).collect();
+    flatten_alpha(
+        &mut gray,
+        1,
+        1,
+        PixelLayout::GrayA16,
+        [0, 255, 0],
+        Unstoppable,
+    )
+    .unwrap();
+    let luma = u16::from_ne_bytes([gray[0], gray[1]]);
+    assert_eq!(luma, 182 * 257, "BT.709 luma of pure green");
+    assert_eq!(&gray[2..], &u16::MAX.to_ne_bytes());
+
+    let mut f: Vec<u8> = [1.0f32, 1.0, 1.0, 0.25]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    flatten_alpha(&mut f, 1, 1, PixelLayout::RgbaF32, [0, 0, 0], Unstoppable).unwrap();
+    assert_eq!(f32::from_ne_bytes(f[..4].try_into().unwrap()), 0.25);
+    assert_eq!(f32::from_ne_bytes(f[12..].try_into().unwrap()), 1.0);
+
+    let err = flatten_alpha(&mut [0; 3], 1, 1, PixelLayout::Rgb8, [0; 3], Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+}
+
+#[test]
+fn pnm_background() {
+    let rgba = [255, 0, 0, 255, 10, 20, 30, 0];
+    let options = PnmEncodeOptions::new().with_background([255, 255, 255]);
+    let ppm = encode_ppm_with(&rgba, 2, 1, PixelLayout::Rgba8, &options, Unstoppable).unwrap();
+    assert_eq!(
+        decode(&ppm, Unstoppable).unwrap().pixels(),
+        &[255, 0, 0, 255, 255, 255]
+    );
+    let pgm = encode_pgm_with(&rgba, 2, 1, PixelLayout::Rgba8, &options, Unstoppable).unwrap();
+    assert_eq!(decode(&pgm, Unstoppable).unwrap().pixels()[1], 255);
+
+    // Without a background, alpha is dropped as before.
+    let plain = encode_ppm(&rgba, 2, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let default = encode_ppm_with(
+        &rgba,
+        2,
+        1,
+        PixelLayout::Rgba8,
+        &PnmEncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(default, plain);
+    assert_eq!(
+        &decode(&plain, Unstoppable).unwrap().pixels()[3..],
+        &[10, 20, 30]
+    );
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_background() {
+    let rgba = [255, 0, 0, 255, 10, 20, 30, 0];
+    let options = BmpEncodeOptions::new().with_background([0, 255, 0]);
+    assert_eq!(options.background(), Some([0, 255, 0]));
+    let bmp = encode_bmp_with(&rgba, 2, 1, PixelLayout::Rgba8, &options, Unstoppable).unwrap();
+    let decoded = decode(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(decoded.pixels(), &[255, 0, 0, 0, 255, 0]);
+
+    // 32-bit output keeps alpha, so the background does not apply.
+    let with_alpha = options.with_alpha(true);
+    let bmp = encode_bmp_with(&rgba, 2, 1, PixelLayout::Rgba8, &with_alpha, Unstoppable).unwrap();
+    assert_eq!(decode(&bmp, Unstoppable).unwrap().pixels(), &rgba);
+}
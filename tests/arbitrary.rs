@@ -0,0 +1,98 @@
+//! `Arbitrary` inputs drive the encoders and decoders like the
+//! `fuzz_encode_decode` target, over a fixed set of pseudo-random buffers.
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use zenbitmaps::*;
+
+#[derive(Debug, Arbitrary)]
+struct Input<'a> {
+    width: u8,
+    height: u8,
+    layout: PixelLayout,
+    format: EncodeFormat,
+    options: EncodeOptions<'a>,
+    limits: Limits,
+    pixels: &'a [u8],
+}
+
+fn bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn whatever_encodes_decodes() {
+    let mut encoded = 0;
+    for seed in 0..3000 {
+        let data = bytes(seed, 256);
+        let Ok(input) = Input::arbitrary(&mut Unstructured::new(&data)) else {
+            continue;
+        };
+        let (width, height) = (u32::from(input.width % 16), u32::from(input.height % 16));
+        // Encoders write empty images, but every format's reader rejects them.
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let len = width as usize * height as usize * input.layout.bytes_per_pixel();
+        let mut pixels: Vec<u8> = input.pixels.iter().copied().cycle().take(len).collect();
+        pixels.resize(len, 0);
+
+        let Ok(file) = encode(
+            &pixels,
+            width,
+            height,
+            input.layout,
+            input.format,
+            &input.options,
+            Unstoppable,
+        ) else {
+            continue;
+        };
+        encoded += 1;
+        // BMP output size depends on the options, not just the layout, as
+        // does PGM/PPM output with a maxval.
+        let pnm_sized = input.options.pnm().maxval().is_none()
+            || !matches!(input.format, EncodeFormat::Pgm | EncodeFormat::Ppm);
+        #[cfg(feature = "bmp")]
+        let sized = pnm_sized && input.format != EncodeFormat::Bmp;
+        #[cfg(not(feature = "bmp"))]
+        let sized = pnm_sized;
+        if sized {
+            let size = estimate_encoded_size(width, height, input.layout, input.format).unwrap();
+            assert_eq!(
+                file.len(),
+                size,
+                "seed {seed}: {:?} {:?}",
+                input.format,
+                input.layout
+            );
+        }
+        match decode_with_limits(&file, &input.limits, Unstoppable) {
+            Ok(decoded) => assert_eq!((decoded.width, decoded.height), (width, height)),
+            Err(e) => assert_eq!(
+                e.error().kind(),
+                ErrorKind::LimitExceeded,
+                "seed {seed}: {:?} {:?}: {e}",
+                input.format,
+                input.layout
+            ),
+        }
+    }
+    assert!(encoded > 100, "only {encoded} inputs encoded");
+}
+
+#[test]
+fn limits_never_carry_a_hook() {
+    let data = bytes(7, 128);
+    let limits = Limits::arbitrary(&mut Unstructured::new(&data)).unwrap();
+    assert!(limits.on_alloc.is_none());
+    assert!(limits.max_duration.is_none());
+}
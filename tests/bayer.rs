@@ -0,0 +1,127 @@
+#![cfg(feature = "bayer")]
+//! Bayer demosaic tests: mosaics sampled from known scenes.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const PATTERNS: [(BayerPattern, [usize; 4]); 4] = [
+    (BayerPattern::Rggb, [0, 1, 1, 2]),
+    (BayerPattern::Bggr, [2, 1, 1, 0]),
+    (BayerPattern::Grbg, [1, 0, 2, 1]),
+    (BayerPattern::Gbrg, [1, 2, 0, 1]),
+];
+
+/// Sample `scene(x, y)` through the filter tile, encoded at `bit_depth`.
+fn mosaic(
+    w: usize,
+    h: usize,
+    tile: [usize; 4],
+    bit_depth: u8,
+    scene: impl Fn(usize, usize) -> [u16; 3],
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    for y in 0..h {
+        for x in 0..w {
+            let v = scene(x, y)[tile[(y & 1) * 2 + (x & 1)]];
+            if bit_depth > 8 {
+                out.extend_from_slice(&v.to_le_bytes());
+            } else {
+                out.push(v as u8);
+            }
+        }
+    }
+    out
+}
+
+fn rgb16(decoded: &DecodeOutput<'_>) -> Vec<[u16; 3]> {
+    decoded
+        .pixels()
+        .chunks_exact(6)
+        .map(|p| [0, 2, 4].map(|i| u16::from_ne_bytes([p[i], p[i + 1]])))
+        .collect()
+}
+
+#[test]
+fn flat_scene_is_exact_at_every_depth() {
+    for (pattern, tile) in PATTERNS {
+        for (bit_depth, color) in [
+            (8, [200, 10, 99]),
+            (12, [4095, 0, 2048]),
+            (16, [1, 65535, 7]),
+        ] {
+            let max = (1u32 << bit_depth) - 1;
+            let data = mosaic(5, 3, tile, bit_depth, |_, _| color);
+            let decoded = decode_bayer(&data, 5, 3, pattern, bit_depth, Unstoppable).unwrap();
+            assert_eq!(decoded.layout, PixelLayout::Rgb16);
+            let expected = color.map(|v| ((u32::from(v) * 65535 + max / 2) / max) as u16);
+            assert!(
+                rgb16(&decoded).iter().all(|&p| p == expected),
+                "{pattern:?} {bit_depth}-bit"
+            );
+        }
+    }
+}
+
+#[test]
+fn linear_ramps_are_exact_inside() {
+    // Bilinear interpolation reproduces planes exactly away from the edges.
+    let scene = |x: usize, y: usize| {
+        [
+            (10 * x + 3 * y) as u16,
+            (5 * x + 9 * y) as u16,
+            200 - (7 * x + 4 * y) as u16,
+        ]
+    };
+    let (w, h) = (8, 7);
+    for (pattern, tile) in PATTERNS {
+        let data = mosaic(w, h, tile, 8, scene);
+        let decoded = decode_bayer(&data, w as u32, h as u32, pattern, 8, Unstoppable).unwrap();
+        let pixels = rgb16(&decoded);
+        for y in 1..h - 1 {
+            for x in 1..w - 1 {
+                assert_eq!(
+                    pixels[y * w + x],
+                    scene(x, y).map(|v| v * 257),
+                    "{pattern:?} at ({x}, {y})"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn high_bits_are_ignored_and_extra_data_is_not_read() {
+    let mut data = mosaic(2, 2, [0, 1, 1, 2], 10, |_, _| [1023, 0, 512]);
+    for s in data.chunks_exact_mut(2) {
+        s[1] |= 0xF0;
+    }
+    data.extend_from_slice(&[0xAB; 5]);
+    let decoded = decode_bayer(&data, 2, 2, BayerPattern::Rggb, 10, Unstoppable).unwrap();
+    assert!(rgb16(&decoded).iter().all(|&p| p == [65535, 0, 32800]));
+}
+
+#[test]
+fn errors_and_limits() {
+    let data = [0u8; 32];
+    let rggb = BayerPattern::Rggb;
+    for bit_depth in [0, 17] {
+        let err = decode_bayer(&data, 2, 2, rggb, bit_depth, Unstoppable).unwrap_err();
+        assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    }
+    for (w, h) in [(0, 2), (1, 4), (4, 1)] {
+        let err = decode_bayer(&data, w, h, rggb, 8, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::InvalidHeader(..)),
+            "{w}×{h}"
+        );
+    }
+    let err = decode_bayer(&data, 5, 4, rggb, 12, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_bayer_with_limits(&data, 4, 4, rggb, 8, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
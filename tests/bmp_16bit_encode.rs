@@ -0,0 +1,92 @@
+//! 16-bit RGB555 / RGB565 BMP encoding through the bitfield decode path.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::{fixture, gradient};
+
+#[test]
+fn regenerates_fixtures_byte_for_byte() {
+    for (name, rgb565) in [("rgb16.bmp", false), ("rgb16-565.bmp", true)] {
+        let data = fixture(name);
+        let decoded = decode_bmp(&data, Unstoppable).unwrap();
+        let (w, h, layout) = (decoded.width, decoded.height, decoded.layout);
+        let bmp = if rgb565 {
+            encode_bmp_rgb565(decoded.pixels(), w, h, layout, Unstoppable)
+        } else {
+            encode_bmp_rgb555(decoded.pixels(), w, h, layout, Unstoppable)
+        }
+        .unwrap();
+        assert_eq!(bmp, data, "{name}");
+    }
+}
+
+#[test]
+fn headers_declare_the_masks() {
+    let rgb = gradient(3, 2, 3);
+    let meta =
+        probe_bmp(&encode_bmp_rgb555(&rgb, 3, 2, PixelLayout::Rgb8, Unstoppable).unwrap()).unwrap();
+    assert_eq!(
+        (meta.bit_count, meta.compression),
+        (16, BmpCompression::Rgb)
+    );
+    let meta =
+        probe_bmp(&encode_bmp_rgb565(&rgb, 3, 2, PixelLayout::Rgb8, Unstoppable).unwrap()).unwrap();
+    assert_eq!(
+        (meta.bit_count, meta.compression),
+        (16, BmpCompression::Bitfields)
+    );
+    assert_eq!(meta.masks, Some([0xF800, 0x07E0, 0x001F, 0]));
+}
+
+#[test]
+fn rounds_to_nearest_and_is_stable() {
+    for (width, height) in [(1, 1), (2, 3), (5, 4), (64, 2)] {
+        let rgb = gradient(width, height, 3);
+        let (w, h) = (width as u32, height as u32);
+        for rgb565 in [false, true] {
+            let encode = |px: &[u8]| {
+                if rgb565 {
+                    encode_bmp_rgb565(px, w, h, PixelLayout::Rgb8, Unstoppable).unwrap()
+                } else {
+                    encode_bmp_rgb555(px, w, h, PixelLayout::Rgb8, Unstoppable).unwrap()
+                }
+            };
+            let bmp = encode(&rgb);
+            let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+            assert_eq!(decoded.layout, PixelLayout::Rgb8);
+            for (i, (&got, &want)) in decoded.pixels().iter().zip(&rgb).enumerate() {
+                let tolerance = if rgb565 && i % 3 == 1 { 2 } else { 4 };
+                assert!(
+                    got.abs_diff(want) <= tolerance,
+                    "{width}x{height} 565={rgb565} byte {i}: {got} vs {want}"
+                );
+            }
+            // Decoded values are exactly representable.
+            assert_eq!(encode(decoded.pixels()), bmp, "{width}x{height}");
+        }
+    }
+}
+
+#[test]
+fn accepts_gray_and_bgra() {
+    let gray: Vec<u8> = (0..12).map(|i| i * 20).collect();
+    let rgb: Vec<u8> = gray.iter().flat_map(|&g| [g, g, g]).collect();
+    let bgra: Vec<u8> = rgb
+        .chunks_exact(3)
+        .flat_map(|p| [p[2], p[1], p[0], 9])
+        .collect();
+    let want = encode_bmp_rgb565(&rgb, 4, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let got = encode_bmp_rgb565(&gray, 4, 3, PixelLayout::Gray8, Unstoppable).unwrap();
+    assert_eq!(got, want);
+    let got = encode_bmp_rgb565(&bgra, 4, 3, PixelLayout::Bgra8, Unstoppable).unwrap();
+    assert_eq!(got, want);
+
+    let err = encode_bmp_rgb555(&rgb[..5], 4, 3, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::BufferTooSmall { .. }),
+        "{err}"
+    );
+}
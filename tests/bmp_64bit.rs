@@ -0,0 +1,141 @@
+//! 64-bit BMP (s2.13 fixed-point BGRA) encode and decode.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn rgba16(values: &[[u16; 4]]) -> Vec<u8> {
+    values
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect()
+}
+
+fn samples(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+        .collect()
+}
+
+/// Largest sRGB-domain error allowed for a 16-bit value after a trip
+/// through 13 linear fraction bits: about half a linear step, which is
+/// ~100 units of 16-bit sRGB near black and a few near white.
+fn tolerance(v: u16) -> u16 {
+    match v {
+        0..8192 => 64,
+        8192..24576 => 24,
+        _ => 8,
+    }
+}
+
+fn assert_close(decoded: &[u8], original: &[u8]) {
+    for (i, (got, want)) in samples(decoded)
+        .into_iter()
+        .zip(samples(original))
+        .enumerate()
+    {
+        let allowed = if i % 4 == 3 { 4 } else { tolerance(want) };
+        assert!(got.abs_diff(want) <= allowed, "sample {i}: {got} vs {want}");
+    }
+}
+
+#[test]
+fn farbfeld_fixture_roundtrips() {
+    let path = format!("{}/tests/fixtures/rgba16.ff", env!("CARGO_MANIFEST_DIR"));
+    let ff = std::fs::read(path).unwrap();
+    let image = decode_farbfeld(&ff, Unstoppable).unwrap();
+    assert_eq!(image.layout, PixelLayout::Rgba16);
+    let bmp = encode_bmp_with(
+        image.pixels(),
+        image.width,
+        image.height,
+        PixelLayout::Rgba16,
+        &BmpEncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap();
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(
+        (meta.bit_count, meta.compression),
+        (64, BmpCompression::Rgb)
+    );
+    assert_eq!(meta.layout, PixelLayout::Rgba16);
+
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    assert_close(decoded.pixels(), image.pixels());
+}
+
+#[test]
+fn stored_values_are_linear_fixed_point() {
+    let pixels = rgba16(&[[65535, 0, 32768, 65535], [0, 65535, 0, 0]]);
+    let options = BmpEncodeOptions::new().with_bit_depth(64);
+    let bmp = encode_bmp_with(&pixels, 2, 1, PixelLayout::Rgba16, &options, Unstoppable).unwrap();
+    let data: Vec<u16> = bmp[54..]
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    // B, G, R, A; sRGB 0.5 is about 0.214 linear.
+    assert_eq!(data[..4], [(0.2140 * 8192.0) as u16 + 1, 0, 8192, 8192]);
+    assert_eq!(data[4..], [0, 8192, 0, 0]);
+}
+
+#[test]
+fn decoded_values_reencode_exactly() {
+    let values: Vec<[u16; 4]> = (0..64u16)
+        .map(|i| [i * 1031, 65535 - i * 977, i * i * 16, i * 1000])
+        .collect();
+    let pixels = rgba16(&values);
+    let encode = |px: &[u8]| {
+        encode_bmp_with(
+            px,
+            8,
+            8,
+            PixelLayout::Rgba16,
+            &BmpEncodeOptions::new(),
+            Unstoppable,
+        )
+        .unwrap()
+    };
+    let bmp = encode(&pixels);
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    assert_close(decoded.pixels(), &pixels);
+    assert_eq!(encode(decoded.pixels()), bmp);
+}
+
+#[test]
+fn out_of_range_fixed_point_is_clamped() {
+    // Hand-built 2x1 64-bit BMP: negative and above-1.0 samples.
+    let mut bmp = encode_bmp_with(
+        &rgba16(&[[0; 4], [0; 4]]),
+        2,
+        1,
+        PixelLayout::Rgba16,
+        &BmpEncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap();
+    let raw: [i16; 8] = [-100, 9000, 4096, 16000, 0, 0, 0, -1];
+    for (i, v) in raw.iter().enumerate() {
+        bmp[54 + i * 2..56 + i * 2].copy_from_slice(&v.to_le_bytes());
+    }
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    let px = samples(decoded.pixels());
+    // R 4096 is 0.5 linear, about 0.735 sRGB; G 9000, B -100, A 16000.
+    assert!((48100..48300).contains(&px[0]), "{}", px[0]);
+    assert_eq!(px[1..4], [65535, 0, 65535]);
+    assert_eq!(px[7], 0);
+}
+
+#[test]
+fn only_rgba16_input() {
+    let options = BmpEncodeOptions::new().with_bit_depth(64);
+    let err =
+        encode_bmp_with(&[0; 4], 1, 1, PixelLayout::Rgba8, &options, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+        "{err}"
+    );
+}
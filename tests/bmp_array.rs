@@ -0,0 +1,137 @@
+//! OS/2 bitmap arrays (`BA` magic).
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Wrap standalone BMP files (with per-entry display size and header type)
+/// in a bitmap array. All headers come first and all pixel data after, so
+/// the embedded `bfOffBits` are genuinely file-absolute.
+fn bitmap_array(images: &[(&[u8], [u16; 2], &[u8; 2])]) -> Vec<u8> {
+    let header_len = |bmp: &[u8]| u32::from_le_bytes(bmp[10..14].try_into().unwrap()) as usize;
+    let headers_total: usize = images.iter().map(|(b, ..)| 14 + header_len(b)).sum();
+
+    let mut out = Vec::new();
+    let mut pixels = Vec::new();
+    for (i, (bmp, [cx, cy], kind)) in images.iter().enumerate() {
+        let hlen = header_len(bmp);
+        let next = if i + 1 == images.len() {
+            0
+        } else {
+            out.len() + 14 + hlen
+        };
+        out.extend_from_slice(b"BA");
+        out.extend_from_slice(&40u32.to_le_bytes());
+        out.extend_from_slice(&(next as u32).to_le_bytes());
+        out.extend_from_slice(&cx.to_le_bytes());
+        out.extend_from_slice(&cy.to_le_bytes());
+        let mut header = bmp[..hlen].to_vec();
+        header[..2].copy_from_slice(*kind);
+        let bits = headers_total + pixels.len();
+        header[10..14].copy_from_slice(&(bits as u32).to_le_bytes());
+        out.extend_from_slice(&header);
+        pixels.extend_from_slice(&bmp[hlen..]);
+    }
+    out.extend_from_slice(&pixels);
+    out
+}
+
+fn rgb(width: u32, height: u32, seed: u8) -> (Vec<u8>, Vec<u8>) {
+    let pixels: Vec<u8> = (0..width * height * 3)
+        .map(|i| seed.wrapping_add(i as u8))
+        .collect();
+    let bmp = encode_bmp(&pixels, width, height, PixelLayout::Rgb8, Unstoppable).unwrap();
+    (pixels, bmp)
+}
+
+#[test]
+fn probe_lists_entries() {
+    let (_, small) = rgb(2, 1, 0);
+    let (_, large) = rgb(4, 3, 50);
+    let array = bitmap_array(&[(&small, [640, 480], b"BM"), (&large, [1024, 768], b"BM")]);
+    let entries = probe_bmp_array(&array).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        (entries[0].display_width, entries[0].display_height),
+        (640, 480)
+    );
+    assert_eq!((entries[1].width, entries[1].height), (4, 3));
+    assert_eq!(entries[1].bit_count, 24);
+    assert!(entries.iter().all(|e| e.is_bitmap));
+}
+
+#[test]
+fn frames_decode_every_bitmap() {
+    let (small_px, small) = rgb(2, 1, 0);
+    let (large_px, large) = rgb(4, 3, 50);
+    let array = bitmap_array(&[(&small, [0, 0], b"BM"), (&large, [0, 0], b"BM")]);
+    let frames: Vec<_> = decode_bmp_array(&array, Unstoppable)
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].image.pixels(), &small_px[..]);
+    assert_eq!(frames[1].image.pixels(), &large_px[..]);
+    assert_eq!((frames[1].image.width, frames[1].image.height), (4, 3));
+}
+
+#[test]
+fn plain_entry_points_pick_the_largest_bitmap() {
+    let (_, small) = rgb(2, 1, 0);
+    let (large_px, large) = rgb(4, 3, 50);
+    let array = bitmap_array(&[(&small, [0, 0], b"BM"), (&large, [0, 0], b"BM")]);
+    assert_eq!(detect_format(&array), Some(ImageFormat::Bmp));
+    assert_eq!(decode(&array, Unstoppable).unwrap().pixels(), &large_px[..]);
+    assert_eq!(
+        decode_bmp(&array, Unstoppable).unwrap().pixels(),
+        &large_px[..]
+    );
+    assert_eq!(probe_bmp(&array).unwrap().width, 4);
+}
+
+#[test]
+fn icon_entries_are_listed_but_not_decoded() {
+    let (small_px, small) = rgb(2, 1, 0);
+    let (_, icon) = rgb(4, 3, 50);
+    let array = bitmap_array(&[(&icon, [0, 0], b"IC"), (&small, [0, 0], b"BM")]);
+    let entries = probe_bmp_array(&array).unwrap();
+    assert!(!entries[0].is_bitmap);
+
+    let mut frames = decode_bmp_array(&array, Unstoppable).unwrap();
+    assert!(frames.next().unwrap().is_err());
+    assert_eq!(
+        frames.next().unwrap().unwrap().image.pixels(),
+        &small_px[..]
+    );
+    assert!(frames.next().is_none());
+
+    assert_eq!(
+        decode_bmp(&array, Unstoppable).unwrap().pixels(),
+        &small_px[..]
+    );
+}
+
+#[test]
+fn backward_link_is_rejected() {
+    let (_, small) = rgb(2, 1, 0);
+    let mut array = bitmap_array(&[(&small, [0, 0], b"BM"), (&small, [0, 0], b"BM")]);
+    let second = u32::from_le_bytes(array[6..10].try_into().unwrap()) as usize;
+    array[second + 6..second + 10].copy_from_slice(&0u32.to_le_bytes());
+    array[second + 6] = 1; // points back into the first header
+    assert!(probe_bmp_array(&array).is_err());
+    assert!(decode_bmp(&array, Unstoppable).is_err());
+}
+
+#[test]
+fn link_past_the_end_is_truncation() {
+    let (_, small) = rgb(2, 1, 0);
+    let mut array = bitmap_array(&[(&small, [0, 0], b"BM")]);
+    // Near the top of the address space on 32-bit targets.
+    array[6..10].copy_from_slice(&(u32::MAX - 4).to_le_bytes());
+    let err = probe_bmp_array(&array).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+    assert!(decode_bmp(&array, Unstoppable).is_err());
+}
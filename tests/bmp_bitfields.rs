@@ -0,0 +1,173 @@
+//! BITFIELDS BMPs with channel masks wider than 8 bits decode to `Rgba16`.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Build a BITMAPV4HEADER BI_BITFIELDS BMP from raw little-endian pixels.
+fn v4_bitfields(width: u32, height: i32, bpp: u16, masks: [u32; 4], pixels: &[u32]) -> Vec<u8> {
+    let bytes_pp = usize::from(bpp / 8);
+    let row = (width as usize * bytes_pp).div_ceil(4) * 4;
+    let data_len = row * height.unsigned_abs() as usize;
+    let offset = 14 + 108;
+    let mut out = Vec::with_capacity(offset + data_len);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&((offset + data_len) as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(offset as u32).to_le_bytes());
+    out.extend_from_slice(&108u32.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&bpp.to_le_bytes());
+    out.extend_from_slice(&3u32.to_le_bytes()); // BI_BITFIELDS
+    out.extend_from_slice(&(data_len as u32).to_le_bytes());
+    out.extend_from_slice(&2835u32.to_le_bytes());
+    out.extend_from_slice(&2835u32.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    for m in masks {
+        out.extend_from_slice(&m.to_le_bytes());
+    }
+    out.extend_from_slice(&0x7352_4742u32.to_le_bytes()); // 'sRGB'
+    out.resize(14 + 108, 0); // endpoints + gamma
+    for r in pixels.chunks_exact(width as usize) {
+        let start = out.len();
+        for &p in r {
+            out.extend_from_slice(&p.to_le_bytes()[..bytes_pp]);
+        }
+        out.resize(start + row, 0);
+    }
+    out
+}
+
+fn rgba16(decoded: &DecodeOutput<'_>) -> Vec<[u16; 4]> {
+    decoded
+        .pixels()
+        .chunks_exact(8)
+        .map(|p| core::array::from_fn(|i| u16::from_ne_bytes([p[i * 2], p[i * 2 + 1]])))
+        .collect()
+}
+
+const R10: u32 = 0x3FF0_0000;
+const G10: u32 = 0x000F_FC00;
+const B10: u32 = 0x0000_03FF;
+const A2: u32 = 0xC000_0000;
+
+fn a2r10g10b10(r: u32, g: u32, b: u32, a: u32) -> u32 {
+    (a << 30) | (r << 20) | (g << 10) | b
+}
+
+#[test]
+fn ten_bit_masks_decode_to_rgba16() {
+    // Top-down 2x1
+    let data = v4_bitfields(
+        2,
+        -1,
+        32,
+        [R10, G10, B10, A2],
+        &[a2r10g10b10(1023, 512, 0, 3), a2r10g10b10(1, 0, 1023, 1)],
+    );
+    let decoded = decode_bmp(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    assert_eq!((decoded.width, decoded.height), (2, 1));
+    assert_eq!(
+        rgba16(&decoded),
+        vec![[65535, 32800, 0, 65535], [64, 0, 65535, 21845]]
+    );
+}
+
+#[test]
+fn wide_masks_without_alpha_are_opaque() {
+    // Bottom-up 1x2: first stored row is the bottom of the image.
+    let data = v4_bitfields(
+        1,
+        2,
+        32,
+        [R10, G10, B10, 0],
+        &[a2r10g10b10(0, 0, 1023, 0), a2r10g10b10(1023, 0, 0, 0)],
+    );
+    let decoded = decode_bmp(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    assert_eq!(
+        rgba16(&decoded),
+        vec![[65535, 0, 0, 65535], [0, 0, 65535, 65535]]
+    );
+}
+
+#[test]
+fn wide_masks_in_16_bit_pixels() {
+    // 16-bit pixel: 0-bit red, 16-bit green (odd, but valid BITFIELDS).
+    let data = v4_bitfields(3, -1, 16, [0, 0xFFFF, 0, 0], &[0, 0x8000, 0xFFFF]);
+    let decoded = decode_bmp(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    let px = rgba16(&decoded);
+    assert_eq!(px[0], [0, 0, 0, 65535]);
+    assert_eq!(px[1], [0, 0x8000, 0, 65535]);
+    assert_eq!(px[2], [0, 0xFFFF, 0, 65535]);
+}
+
+#[test]
+fn eight_bit_masks_stay_rgba8() {
+    let data = v4_bitfields(
+        1,
+        -1,
+        32,
+        [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000],
+        &[0x8011_2233],
+    );
+    let decoded = decode_bmp(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[0x11, 0x22, 0x33, 0x80]);
+}
+
+#[test]
+fn probe_reports_rgba16() {
+    let data = v4_bitfields(1, 1, 32, [R10, G10, B10, A2], &[0]);
+    let meta = probe_bmp(&data).unwrap();
+    assert_eq!(meta.layout, PixelLayout::Rgba16);
+}
+
+#[test]
+fn eight_bit_masks_without_alpha_keep_padding() {
+    let masks = [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0];
+    let bmp = v4_bitfields(1, 1, 32, masks, &[0x0010_2030]);
+    let options = BmpDecodeOptions::new().with_keep_padding(true);
+    let decoded = decode_bmp_with_options(&bmp, &options, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgbx8);
+    assert_eq!(decoded.pixels(), &[0x10, 0x20, 0x30, 255]);
+    assert_eq!(
+        decode_bmp(&bmp, Unstoppable).unwrap().layout,
+        PixelLayout::Rgba8
+    );
+}
+
+#[test]
+fn probe_reports_header_and_masks() {
+    let masks = [R10, G10, B10, A2];
+    let data = v4_bitfields(1, 1, 32, masks, &[0]);
+    let meta = probe_bmp(&data).unwrap();
+    assert_eq!(meta.header_variant, BmpHeaderVariant::V4);
+    assert_eq!(meta.compression, BmpCompression::Bitfields);
+    assert_eq!(meta.bit_count, 32);
+    assert_eq!(meta.masks, Some(masks));
+
+    let rgb = encode_bmp(&[1, 2, 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let meta = probe_bmp(&rgb).unwrap();
+    assert_eq!(meta.compression, BmpCompression::Rgb);
+    assert_eq!(meta.bit_count, 24);
+    assert_eq!(meta.masks, Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0]));
+}
+
+#[test]
+fn wide_masks_decode_to_linear_rgba_f32() {
+    let data = v4_bitfields(1, 1, 32, [R10, G10, B10, A2], &[0xFFF0_0000 | 0x3FF]);
+    let decoded = decode_bmp_linear(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbaF32);
+    let px: Vec<f32> = decoded
+        .pixels()
+        .chunks_exact(4)
+        .map(|c| f32::from_ne_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(px, [1.0, 0.0, 1.0, 1.0]);
+}
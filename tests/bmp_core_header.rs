@@ -0,0 +1,108 @@
+//! OS/2 1.x `BITMAPCOREHEADER` output.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::gradient;
+
+fn core() -> BmpEncodeOptions<'static> {
+    BmpEncodeOptions::new().with_header(BmpHeaderVariant::Core)
+}
+
+fn strict_decode(bmp: &[u8]) -> DecodeOutput<'_> {
+    let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+    let (decoded, report) = decode_bmp_with_report(bmp, &strict, Unstoppable).unwrap();
+    assert!(report.is_clean(), "{:?}", report.warnings);
+    decoded
+}
+
+#[test]
+fn twenty_four_bit_roundtrips() {
+    let rgb = gradient(5, 3, 3);
+    let bmp = encode_bmp_with(&rgb, 5, 3, PixelLayout::Rgb8, &core(), Unstoppable).unwrap();
+    assert_eq!(u32::from_le_bytes(bmp[14..18].try_into().unwrap()), 12);
+    assert_eq!(&bmp[18..26], &[5, 0, 3, 0, 1, 0, 24, 0]);
+    assert_eq!(bmp.len(), 14 + 12 + 3 * 16);
+
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.header_variant, BmpHeaderVariant::Core);
+    assert_eq!(meta.dpi_x, None);
+    assert_eq!(strict_decode(&bmp).pixels(), rgb);
+}
+
+#[test]
+fn palettes_use_three_byte_entries() {
+    let palette = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+    for (depth, used) in [(1, &palette[..2]), (4, &palette[..]), (8, &palette[..])] {
+        let rgb: Vec<u8> = (0..7 * 4).flat_map(|i| used[i % used.len()]).collect();
+        let options = core().with_bit_depth(depth).with_palette(used);
+        let bmp = encode_bmp_with(&rgb, 7, 4, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+        let offset = u32::from_le_bytes(bmp[10..14].try_into().unwrap()) as usize;
+        assert_eq!(offset, 14 + 12 + 3 * used.len(), "{depth}-bit");
+
+        let meta = probe_bmp(&bmp).unwrap();
+        assert_eq!(meta.bit_count, depth);
+        let table: Vec<[u8; 3]> = meta
+            .color_table
+            .unwrap()
+            .iter()
+            .map(|&[b, g, r, _]| [r, g, b])
+            .collect();
+        assert_eq!(table, used, "{depth}-bit");
+        assert_eq!(strict_decode(&bmp).pixels(), rgb, "{depth}-bit");
+    }
+}
+
+#[test]
+fn gray_gets_a_palette() {
+    let gray = gradient(6, 6, 1);
+    let bmp = encode_bmp_with(&gray, 6, 6, PixelLayout::Gray8, &core(), Unstoppable).unwrap();
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.bit_count, 8);
+    assert!(meta.color_table.is_some());
+    let decoded = strict_decode(&bmp);
+    let expected: Vec<u8> = match decoded.layout {
+        PixelLayout::Gray8 => gray,
+        _ => gray.iter().flat_map(|&v| [v, v, v]).collect(),
+    };
+    assert_eq!(decoded.pixels(), expected);
+}
+
+#[test]
+fn rejects_what_core_cannot_hold() {
+    let rgb = gradient(2, 2, 3);
+    let rgba = gradient(2, 2, 4);
+    for (pixels, layout, options) in [
+        (&rgb, PixelLayout::Rgb8, core().with_top_down(true)),
+        (&rgb, PixelLayout::Rgb8, core().with_bit_depth(16)),
+        (&rgb, PixelLayout::Rgb8, core().with_bit_depth(2)),
+        (
+            &rgb,
+            PixelLayout::Rgb8,
+            core().with_compression(BmpCompression::Rle8),
+        ),
+        (&rgba, PixelLayout::Rgba8, core().with_alpha(true)),
+        (
+            &rgb,
+            PixelLayout::Rgb8,
+            core().with_color_space(BmpColorSpace::Srgb),
+        ),
+        (&rgb, PixelLayout::Rgb8, core().with_icc_profile(b"icc")),
+    ] {
+        let err = encode_bmp_with(pixels, 2, 2, layout, &options, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+            "{options:?}: {err}"
+        );
+    }
+
+    let wide = vec![0; 65536 * 3];
+    let err =
+        encode_bmp_with(&wide, 65536, 1, PixelLayout::Rgb8, &core(), Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::DimensionsTooLarge { .. }),
+        "{err}"
+    );
+}
@@ -0,0 +1,111 @@
+//! `BmpDecodeOptions` coverage: alpha policy and padding layout for 32-bit
+//! `BI_RGB` files.
+
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// 2x1 RGBA image with the given alpha values, written as 32-bit `BI_RGB`
+/// (alpha lands in the "reserved" byte).
+fn bi_rgb_32(alphas: [u8; 2]) -> Vec<u8> {
+    let pixels = [10, 20, 30, alphas[0], 40, 50, 60, alphas[1]];
+    encode_bmp_rgba(&pixels, 2, 1, PixelLayout::Rgba8, Unstoppable).unwrap()
+}
+
+fn decode_alpha(data: &[u8], policy: BmpAlphaPolicy) -> Vec<u8> {
+    let options = BmpDecodeOptions::new().with_alpha_policy(policy);
+    let decoded = decode_bmp_with_options(data, &options, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    decoded.pixels().chunks_exact(4).map(|p| p[3]).collect()
+}
+
+#[test]
+fn default_policy_is_auto() {
+    assert_eq!(BmpDecodeOptions::new().alpha_policy(), BmpAlphaPolicy::Auto);
+    assert_eq!(
+        BmpDecodeOptions::new().permissiveness(),
+        BmpPermissiveness::Standard
+    );
+}
+
+#[test]
+fn auto_treats_all_zero_reserved_byte_as_opaque() {
+    let data = bi_rgb_32([0, 0]);
+    assert_eq!(decode_alpha(&data, BmpAlphaPolicy::Auto), [255, 255]);
+    // The plain entry point uses the same default.
+    let decoded = decode_bmp(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[10, 20, 30, 255, 40, 50, 60, 255]);
+}
+
+#[test]
+fn auto_keeps_real_alpha() {
+    let data = bi_rgb_32([0, 128]);
+    assert_eq!(decode_alpha(&data, BmpAlphaPolicy::Auto), [0, 128]);
+}
+
+#[test]
+fn trust_keeps_zero_alpha() {
+    let data = bi_rgb_32([0, 0]);
+    assert_eq!(decode_alpha(&data, BmpAlphaPolicy::Trust), [0, 0]);
+}
+
+#[test]
+fn opaque_ignores_reserved_byte() {
+    let data = bi_rgb_32([0, 128]);
+    assert_eq!(decode_alpha(&data, BmpAlphaPolicy::Opaque), [255, 255]);
+}
+
+#[test]
+fn policy_applies_to_native_order_colors_unchanged() {
+    let data = bi_rgb_32([7, 9]);
+    let options = BmpDecodeOptions::new().with_alpha_policy(BmpAlphaPolicy::Opaque);
+    let decoded = decode_bmp_with_options(&data, &options, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[10, 20, 30, 255, 40, 50, 60, 255]);
+}
+
+#[test]
+fn options_with_limits_enforced() {
+    let data = bi_rgb_32([0, 0]);
+    let limits = Limits {
+        max_width: Some(1),
+        ..Default::default()
+    };
+    let r =
+        decode_bmp_with_options_and_limits(&data, &BmpDecodeOptions::new(), &limits, Unstoppable);
+    assert!(matches!(
+        r.as_ref().map_err(|e| e.error()),
+        Err(BitmapError::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn keep_padding_reports_rgbx8_when_alpha_is_synthesized() {
+    let options = BmpDecodeOptions::new().with_keep_padding(true);
+    assert!(options.keep_padding());
+    assert!(!BmpDecodeOptions::new().keep_padding());
+
+    let data = bi_rgb_32([0, 0]);
+    let decoded = decode_bmp_with_options(&data, &options, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgbx8);
+    assert_eq!(decoded.pixels(), &[10, 20, 30, 255, 40, 50, 60, 255]);
+
+    // Opaque forces the reserved byte to padding even when it is non-zero.
+    let opaque = options.with_alpha_policy(BmpAlphaPolicy::Opaque);
+    let data = bi_rgb_32([0, 128]);
+    let decoded = decode_bmp_with_options(&data, &opaque, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgbx8);
+}
+
+#[test]
+fn keep_padding_leaves_real_alpha_as_rgba8() {
+    let options = BmpDecodeOptions::new().with_keep_padding(true);
+    let data = bi_rgb_32([0, 128]);
+    let decoded = decode_bmp_with_options(&data, &options, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+
+    let trust = options.with_alpha_policy(BmpAlphaPolicy::Trust);
+    let data = bi_rgb_32([0, 0]);
+    let decoded = decode_bmp_with_options(&data, &trust, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+}
@@ -0,0 +1,121 @@
+//! Headerless DIB (clipboard `CF_DIB`) decoding.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn strip_file_header(bmp: &[u8]) -> &[u8] {
+    &bmp[14..]
+}
+
+#[test]
+fn dib_matches_bmp_for_encoder_output() {
+    let rgb: Vec<u8> = (0..5 * 3 * 3).map(|i| (i * 7) as u8).collect();
+    let rgba: Vec<u8> = (0..5 * 3 * 4).map(|i| (i * 11) as u8).collect();
+    let gray: Vec<u8> = (0..5 * 3).map(|i| (i * 13) as u8).collect();
+    let files = [
+        encode_bmp(&rgb, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap(),
+        encode_bmp_rgba(&rgba, 5, 3, PixelLayout::Rgba8, Unstoppable).unwrap(),
+        encode_bmp(&gray, 5, 3, PixelLayout::Gray8, Unstoppable).unwrap(),
+    ];
+    for bmp in &files {
+        let full = decode_bmp(bmp, Unstoppable).unwrap();
+        let dib = decode_dib(strip_file_header(bmp), Unstoppable).unwrap();
+        assert_eq!((dib.width, dib.height, dib.layout), (5, 3, full.layout));
+        assert_eq!(dib.pixels(), full.pixels());
+    }
+}
+
+#[test]
+fn dib_with_trailing_bitfield_masks() {
+    // BITMAPINFOHEADER + BI_BITFIELDS: the three masks follow the header.
+    let mut dib = Vec::new();
+    dib.extend_from_slice(&40u32.to_le_bytes());
+    dib.extend_from_slice(&1i32.to_le_bytes());
+    dib.extend_from_slice(&(-1i32).to_le_bytes());
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&16u16.to_le_bytes());
+    dib.extend_from_slice(&3u32.to_le_bytes());
+    dib.extend_from_slice(&[0u8; 20]);
+    for m in [0xF800u32, 0x07E0, 0x001F] {
+        dib.extend_from_slice(&m.to_le_bytes());
+    }
+    dib.extend_from_slice(&[0x00, 0xF8, 0, 0]); // pure red, 565, padded
+    let decoded = decode_dib(&dib, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(decoded.pixels(), &[255, 0, 0]);
+}
+
+#[test]
+fn dib_options_and_limits() {
+    let rgba = [1, 2, 3, 0, 4, 5, 6, 0];
+    let bmp = encode_bmp_rgba(&rgba, 2, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let dib = strip_file_header(&bmp);
+
+    let trust = BmpDecodeOptions::new().with_alpha_policy(BmpAlphaPolicy::Trust);
+    let decoded = decode_dib_with_options(dib, &trust, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &rgba);
+
+    let limits = Limits {
+        max_pixels: Some(1),
+        ..Default::default()
+    };
+    assert!(decode_dib_with_limits(dib, &limits, Unstoppable).is_err());
+    assert!(decode_dib_with_options_and_limits(dib, &trust, &limits, Unstoppable).is_err());
+}
+
+#[test]
+fn dib_rejects_file_header_and_truncation() {
+    let bmp = encode_bmp(&[0u8; 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    // A full BMP file is not a DIB: "BM" + file size is not a header size.
+    assert!(matches!(
+        decode_dib(&bmp, Unstoppable).unwrap_err().error(),
+        BitmapError::InvalidHeader(..)
+    ));
+    assert!(matches!(
+        decode_dib(&[40, 0, 0], Unstoppable).unwrap_err().error(),
+        BitmapError::UnexpectedEof(_)
+    ));
+}
+
+/// 1x1 24-bit DIB with a `biClrUsed`-sized color table ahead of the pixel.
+fn dib_with_color_table(colors: u32) -> Vec<u8> {
+    let mut dib = Vec::new();
+    dib.extend_from_slice(&40u32.to_le_bytes());
+    dib.extend_from_slice(&1i32.to_le_bytes());
+    dib.extend_from_slice(&1i32.to_le_bytes());
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&24u16.to_le_bytes());
+    dib.extend_from_slice(&[0u8; 16]);
+    dib.extend_from_slice(&colors.to_le_bytes());
+    dib.extend_from_slice(&0u32.to_le_bytes());
+    dib.extend((0..colors * 4).map(|i| i as u8 | 0x80));
+    dib.extend_from_slice(&[30, 20, 10, 0]); // BGR + row padding
+    dib
+}
+
+#[test]
+fn dib_skips_color_table_of_deep_images() {
+    for colors in [0, 2, 64] {
+        let dib = dib_with_color_table(colors);
+        let decoded = decode_dib(&dib, Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), &[10, 20, 30], "biClrUsed {colors}");
+    }
+}
+
+#[test]
+fn dib_copy_counts_against_memory_limit() {
+    let dib = dib_with_color_table(64);
+    let limits = |max| Limits {
+        max_memory_bytes: Some(max),
+        ..Default::default()
+    };
+    // The decoder works on a copy with a file header prepended.
+    let copy = dib.len() as u64 + 14;
+    assert!(decode_dib_with_limits(&dib, &limits(copy), Unstoppable).is_ok());
+    let err = decode_dib_with_limits(&dib, &limits(copy - 1), Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+}
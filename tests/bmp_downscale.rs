@@ -0,0 +1,129 @@
+//! Power-of-two downscale during BMP decode.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::{fixture, gradient};
+
+const FACTORS: [BmpDownscale; 3] = [
+    BmpDownscale::Half,
+    BmpDownscale::Quarter,
+    BmpDownscale::Eighth,
+];
+
+fn downscaled(data: &[u8], downscale: BmpDownscale) -> DecodeOutput<'_> {
+    let options = BmpDecodeOptions::new().with_downscale(downscale);
+    decode_bmp_with_options(data, &options, Unstoppable).unwrap()
+}
+
+/// Every `step`-th row and column of a full decode.
+fn nearest(full: &DecodeOutput<'_>, step: usize) -> Vec<u8> {
+    let bpp = full.layout.bytes_per_pixel();
+    full.pixels()
+        .chunks_exact(full.width as usize * bpp)
+        .step_by(step)
+        .flat_map(|row| row.chunks(bpp * step).flat_map(|px| &px[..bpp]))
+        .copied()
+        .collect()
+}
+
+fn assert_matches_full(data: &[u8]) {
+    let full = decode_bmp(data, Unstoppable).unwrap();
+    for downscale in FACTORS {
+        let step = downscale.factor();
+        let small = downscaled(data, downscale);
+        assert_eq!(small.width, full.width.div_ceil(step), "{downscale:?}");
+        assert_eq!(small.height, full.height.div_ceil(step), "{downscale:?}");
+        assert_eq!(small.layout, full.layout, "{downscale:?}");
+        assert_eq!(
+            small.pixels(),
+            nearest(&full, step as usize),
+            "{downscale:?}"
+        );
+    }
+}
+
+#[test]
+fn fixtures_match_nearest_subsample() {
+    for name in [
+        "pal1.bmp",
+        "pal4.bmp",
+        "pal4rle.bmp",
+        "pal8.bmp",
+        "pal8os2.bmp",
+        "pal8rle.bmp",
+        "pal8topdown.bmp",
+        "rgb16.bmp",
+        "rgb16-565.bmp",
+        "rgb24.bmp",
+        "rgb32.bmp",
+        "rgba32abf.bmp",
+    ] {
+        assert_matches_full(&fixture(name));
+    }
+}
+
+#[test]
+fn odd_dimensions_round_up() {
+    let rgb = gradient(9, 7, 3);
+    assert_matches_full(&encode_bmp(&rgb, 9, 7, PixelLayout::Rgb8, Unstoppable).unwrap());
+    let gray = gradient(17, 3, 1);
+    assert_matches_full(&encode_bmp(&gray, 17, 3, PixelLayout::Gray8, Unstoppable).unwrap());
+    let rgba = gradient(5, 11, 4);
+    assert_matches_full(&encode_bmp_rgba(&rgba, 5, 11, PixelLayout::Rgba8, Unstoppable).unwrap());
+}
+
+#[test]
+fn none_is_a_full_decode() {
+    let data = fixture("rgb24.bmp");
+    let full = decode_bmp(&data, Unstoppable).unwrap();
+    let same = downscaled(&data, BmpDownscale::None);
+    assert_eq!(same.pixels(), full.pixels());
+    assert_eq!(BmpDecodeOptions::new().downscale(), BmpDownscale::None);
+}
+
+#[test]
+fn zero_reserved_byte_is_opaque_under_auto() {
+    // 32-bit BI_RGB with every reserved byte zero.
+    let mut rgbx = gradient(6, 6, 4);
+    rgbx.iter_mut().skip(3).step_by(4).for_each(|a| *a = 0);
+    let bmp = encode_bmp_rgba(&rgbx, 6, 6, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let small = downscaled(&bmp, BmpDownscale::Half);
+    assert!(small.pixels().chunks_exact(4).all(|px| px[3] == 255));
+    assert_matches_full(&bmp);
+}
+
+#[test]
+fn limits_apply_to_the_reduced_output() {
+    let data = fixture("rgb24.bmp");
+    let meta = probe_bmp(&data).unwrap();
+    let limits = Limits {
+        max_width: Some(meta.width.div_ceil(8).into()),
+        max_height: Some(meta.height.div_ceil(8).into()),
+        ..Default::default()
+    };
+    let err = decode_bmp_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+    let options = BmpDecodeOptions::new().with_downscale(BmpDownscale::Eighth);
+    let small = decode_bmp_with_options_and_limits(&data, &options, &limits, Unstoppable);
+    assert!(small.is_ok());
+}
+
+#[test]
+fn report_rows_are_output_rows() {
+    let rgb = gradient(4, 8, 3);
+    let mut bmp = encode_bmp(&rgb, 4, 8, PixelLayout::Rgb8, Unstoppable).unwrap();
+    // Drop the top three stored-last rows of the bottom-up image.
+    bmp.truncate(bmp.len() - 3 * 12);
+    let options = BmpDecodeOptions::new()
+        .with_permissiveness(BmpPermissiveness::Permissive)
+        .with_downscale(BmpDownscale::Half);
+    let (small, report) = decode_bmp_with_report(&bmp, &options, Unstoppable).unwrap();
+    assert_eq!(small.height, 4);
+    assert_eq!(report.valid_rows, Some(2..4));
+}
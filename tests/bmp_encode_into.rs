@@ -0,0 +1,132 @@
+//! BMP encoding into a reused buffer and into an `io::Write` sink.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::gradient;
+
+fn cases() -> Vec<(PixelLayout, BmpEncodeOptions<'static>)> {
+    vec![
+        (PixelLayout::Rgb8, BmpEncodeOptions::new()),
+        (
+            PixelLayout::Gray8,
+            BmpEncodeOptions::new().with_top_down(true),
+        ),
+        (PixelLayout::Rgba8, BmpEncodeOptions::new().with_alpha(true)),
+        (
+            PixelLayout::Rgb8,
+            BmpEncodeOptions::new().with_bit_depth(16),
+        ),
+        (PixelLayout::Rgba16, BmpEncodeOptions::new()),
+        (
+            PixelLayout::Rgb8,
+            BmpEncodeOptions::new().with_compression(BmpCompression::Rle8),
+        ),
+        (
+            PixelLayout::Rgb8,
+            BmpEncodeOptions::new().with_icc_profile(b"not really a profile"),
+        ),
+    ]
+}
+
+#[test]
+fn into_matches_encode_and_reuses_the_buffer() {
+    let mut out = vec![0xAA; 7];
+    for (layout, options) in cases() {
+        // Tall enough to cross several 16-row boundaries.
+        let pixels = gradient(9, 40, layout.bytes_per_pixel());
+        let expected = encode_bmp_with(&pixels, 9, 40, layout, &options, Unstoppable).unwrap();
+        encode_bmp_into(&pixels, 9, 40, layout, &options, &mut out, Unstoppable).unwrap();
+        assert_eq!(out, expected, "{layout:?} {options:?}");
+    }
+
+    let capacity = out.capacity();
+    let small = gradient(2, 2, 3);
+    let options = BmpEncodeOptions::new();
+    encode_bmp_into(
+        &small,
+        2,
+        2,
+        PixelLayout::Rgb8,
+        &options,
+        &mut out,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(out.len(), 14 + 40 + 2 * 8);
+    assert_eq!(out.capacity(), capacity);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn writer_matches_encode() {
+    for (layout, options) in cases() {
+        let pixels = gradient(9, 40, layout.bytes_per_pixel());
+        let expected = encode_bmp_with(&pixels, 9, 40, layout, &options, Unstoppable).unwrap();
+        let mut written = Vec::new();
+        encode_bmp_to_writer(&pixels, 9, 40, layout, &options, &mut written, Unstoppable).unwrap();
+        assert_eq!(written, expected, "{layout:?} {options:?}");
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn writer_streams_rows() {
+    /// Records the size of every write.
+    struct Writes(Vec<usize>);
+    impl std::io::Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.len());
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let pixels = gradient(10, 64, 3);
+    let mut writes = Writes(Vec::new());
+    let options = BmpEncodeOptions::new();
+    encode_bmp_to_writer(
+        &pixels,
+        10,
+        64,
+        PixelLayout::Rgb8,
+        &options,
+        &mut writes,
+        Unstoppable,
+    )
+    .unwrap();
+    assert!(writes.0.len() > 2, "{:?}", writes.0);
+    assert!(writes.0.iter().all(|&n| n <= 14 + 40 + 16 * 32));
+    assert_eq!(writes.0.iter().sum::<usize>(), 14 + 40 + 64 * 32);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_errors_surface_as_io() {
+    struct Full;
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::StorageFull.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let options = BmpEncodeOptions::new();
+    let err = encode_bmp_to_writer(
+        &[0; 3],
+        1,
+        1,
+        PixelLayout::Rgb8,
+        &options,
+        Full,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::Io(_)), "{err}");
+}
@@ -0,0 +1,370 @@
+//! BMP encoding through `encode_bmp_with` and `BmpEncodeOptions`.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::gradient;
+
+fn stored_height(bmp: &[u8]) -> i32 {
+    i32::from_le_bytes(bmp[22..26].try_into().unwrap())
+}
+
+#[test]
+fn defaults_match_the_flat_encoders() {
+    let rgba = gradient(5, 3, 4);
+    let layout = PixelLayout::Rgba8;
+    let options = BmpEncodeOptions::new();
+    assert_eq!(
+        encode_bmp_with(&rgba, 5, 3, layout, &options, Unstoppable).unwrap(),
+        encode_bmp(&rgba, 5, 3, layout, Unstoppable).unwrap()
+    );
+    let options = options.with_alpha(true);
+    assert_eq!(
+        encode_bmp_with(&rgba, 5, 3, layout, &options, Unstoppable).unwrap(),
+        encode_bmp_rgba(&rgba, 5, 3, layout, Unstoppable).unwrap()
+    );
+}
+
+#[test]
+fn top_down_roundtrips() {
+    for (layout, alpha) in [
+        (PixelLayout::Rgb8, false),
+        (PixelLayout::Bgr8, false),
+        (PixelLayout::Gray8, false),
+        (PixelLayout::Rgba8, true),
+        (PixelLayout::Bgra8, true),
+    ] {
+        let (width, height) = (7, 5);
+        let pixels = gradient(width, height, layout.bytes_per_pixel());
+        let (w, h) = (width as u32, height as u32);
+        let bottom_up = BmpEncodeOptions::new().with_alpha(alpha);
+        let top_down = bottom_up.with_top_down(true);
+        let reference = encode_bmp_with(&pixels, w, h, layout, &bottom_up, Unstoppable).unwrap();
+        let bmp = encode_bmp_with(&pixels, w, h, layout, &top_down, Unstoppable).unwrap();
+        assert_eq!(stored_height(&reference), 5);
+        assert_eq!(stored_height(&bmp), -5, "{layout:?}");
+        assert_eq!(bmp.len(), reference.len());
+
+        let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+        let (decoded, report) = decode_bmp_with_report(&bmp, &strict, Unstoppable).unwrap();
+        assert!(report.is_clean(), "{:?}", report.warnings);
+        let expected = decode_bmp(&reference, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, expected.layout, "{layout:?}");
+        assert_eq!(decoded.pixels(), expected.pixels(), "{layout:?}");
+    }
+}
+
+#[test]
+fn top_down_rows_are_in_memory_order() {
+    let rgb = gradient(2, 3, 3);
+    let options = BmpEncodeOptions::new().with_top_down(true);
+    let bmp = encode_bmp_with(&rgb, 2, 3, PixelLayout::Bgr8, &options, Unstoppable).unwrap();
+    // 6-byte rows padded to 8, first source row first.
+    for row in 0..3 {
+        let stored = &bmp[54 + row * 8..][..6];
+        assert_eq!(stored, &rgb[row * 6..][..6], "row {row}");
+    }
+}
+
+#[test]
+fn v4_v5_headers_declare_alpha() {
+    // Fully transparent pixels would be read as opaque from a plain
+    // BITMAPINFOHEADER under the default alpha policy.
+    let mut rgba = gradient(3, 4, 4);
+    rgba.iter_mut().skip(3).step_by(4).for_each(|a| *a = 0);
+    rgba[3] = 200;
+    for (header, size) in [(BmpHeaderVariant::V4, 108), (BmpHeaderVariant::V5, 124)] {
+        let options = BmpEncodeOptions::new().with_alpha(true).with_header(header);
+        let bmp = encode_bmp_with(&rgba, 3, 4, PixelLayout::Rgba8, &options, Unstoppable).unwrap();
+        let meta = probe_bmp(&bmp).unwrap();
+        assert_eq!(meta.header_variant, header);
+        assert_eq!(meta.compression, BmpCompression::Bitfields);
+        assert_eq!(
+            meta.masks,
+            Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000])
+        );
+        let offset = u32::from_le_bytes(bmp[10..14].try_into().unwrap());
+        assert_eq!(offset, 14 + size);
+        assert_eq!(bmp.len(), 14 + size as usize + rgba.len());
+
+        let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+        let (decoded, report) = decode_bmp_with_report(&bmp, &strict, Unstoppable).unwrap();
+        assert!(report.is_clean(), "{:?}", report.warnings);
+        assert_eq!(decoded.layout, PixelLayout::Rgba8);
+        assert_eq!(decoded.pixels(), &rgba[..], "{header:?}");
+    }
+}
+
+#[test]
+fn v5_header_without_alpha_is_uncompressed() {
+    let rgb = gradient(5, 2, 3);
+    let options = BmpEncodeOptions::new().with_header(BmpHeaderVariant::V5);
+    let bmp = encode_bmp_with(&rgb, 5, 2, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.header_variant, BmpHeaderVariant::V5);
+    assert_eq!(
+        (meta.bit_count, meta.compression),
+        (24, BmpCompression::Rgb)
+    );
+    assert_eq!(decode_bmp(&bmp, Unstoppable).unwrap().pixels(), &rgb[..]);
+}
+
+#[test]
+fn rejects_headers_it_cannot_write() {
+    let options = BmpEncodeOptions::new().with_header(BmpHeaderVariant::V2);
+    let err = encode_bmp_with(&[0; 3], 1, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+        "{err}"
+    );
+}
+
+fn u32_at(bmp: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bmp[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn icc_profile_is_embedded_after_the_pixels() {
+    let profile: Vec<u8> = (0..=255u8).cycle().take(601).collect();
+    let rgb = gradient(3, 3, 3);
+    for header in [BmpHeaderVariant::Info, BmpHeaderVariant::V4] {
+        let options = BmpEncodeOptions::new()
+            .with_header(header)
+            .with_icc_profile(&profile);
+        let bmp = encode_bmp_with(&rgb, 3, 3, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+        assert_eq!(
+            probe_bmp(&bmp).unwrap().header_variant,
+            BmpHeaderVariant::V5
+        );
+        assert_eq!(u32_at(&bmp, 2) as usize, bmp.len());
+        assert_eq!(&bmp[14 + 56..14 + 60], b"DEBM"); // 'MBED' little-endian
+        assert_eq!(u32_at(&bmp, 14 + 108), 4); // LCS_GM_IMAGES
+
+        // Offset counts from the info header; the profile ends the file.
+        let profile_offset = 14 + u32_at(&bmp, 14 + 112) as usize;
+        let profile_size = u32_at(&bmp, 14 + 116) as usize;
+        let pixel_end = u32_at(&bmp, 10) as usize + u32_at(&bmp, 34) as usize;
+        assert_eq!(profile_offset, pixel_end);
+        assert_eq!(&bmp[profile_offset..][..profile_size], &profile[..]);
+        assert_eq!(profile_offset + profile_size, bmp.len());
+
+        let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+        let (decoded, report) = decode_bmp_with_report(&bmp, &strict, Unstoppable).unwrap();
+        assert!(report.is_clean(), "{:?}", report.warnings);
+        assert_eq!(decoded.pixels(), &rgb[..]);
+    }
+}
+
+#[test]
+fn color_space_tags() {
+    let rgb = gradient(2, 2, 3);
+    for (color_space, tag) in [
+        (BmpColorSpace::Srgb, b"BGRs"), // 'sRGB' little-endian
+        (BmpColorSpace::WindowsColorSpace, b" niW"),
+    ] {
+        for (header, written) in [
+            (BmpHeaderVariant::Info, BmpHeaderVariant::V4),
+            (BmpHeaderVariant::V4, BmpHeaderVariant::V4),
+            (BmpHeaderVariant::V5, BmpHeaderVariant::V5),
+        ] {
+            let options = BmpEncodeOptions::new()
+                .with_header(header)
+                .with_color_space(color_space);
+            let bmp =
+                encode_bmp_with(&rgb, 2, 2, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+            assert_eq!(probe_bmp(&bmp).unwrap().header_variant, written);
+            assert_eq!(&bmp[14 + 56..14 + 60], tag, "{color_space:?}");
+            assert_eq!(decode_bmp(&bmp, Unstoppable).unwrap().pixels(), &rgb[..]);
+        }
+    }
+
+    // Untagged by default; an embedded profile takes precedence.
+    let bmp = encode_bmp_with(
+        &rgb,
+        2,
+        2,
+        PixelLayout::Rgb8,
+        &BmpEncodeOptions::new().with_header(BmpHeaderVariant::V4),
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(u32_at(&bmp, 14 + 56), 0);
+    let options = BmpEncodeOptions::new()
+        .with_color_space(BmpColorSpace::Srgb)
+        .with_icc_profile(b"icc");
+    let bmp = encode_bmp_with(&rgb, 2, 2, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    assert_eq!(&bmp[14 + 56..14 + 60], b"DEBM");
+}
+
+#[test]
+fn resolution_is_written() {
+    let rgb = gradient(2, 1, 3);
+    let encode = |options: &BmpEncodeOptions<'_>| {
+        encode_bmp_with(&rgb, 2, 1, PixelLayout::Rgb8, options, Unstoppable).unwrap()
+    };
+
+    let bmp = encode(&BmpEncodeOptions::new().with_pixels_per_meter(3780, 1000));
+    assert_eq!((u32_at(&bmp, 38), u32_at(&bmp, 42)), (3780, 1000));
+
+    for dpi in [72.0, 96.0, 150.0, 300.0, 600.0, 1200.5] {
+        let options = BmpEncodeOptions::new().with_dpi(dpi, dpi / 2.0);
+        let meta = probe_bmp(&encode(&options)).unwrap();
+        let (x, y) = (meta.dpi_x.unwrap(), meta.dpi_y.unwrap());
+        assert!((x - dpi).abs() < 0.013, "{dpi}: {x}");
+        assert!((y - dpi / 2.0).abs() < 0.013, "{dpi}: {y}");
+    }
+
+    // Unspecified resolution reads back as None.
+    for dpi in [0.0, -1.0, f32::NAN] {
+        let meta = probe_bmp(&encode(&BmpEncodeOptions::new().with_dpi(dpi, dpi))).unwrap();
+        assert_eq!((meta.dpi_x, meta.dpi_y), (None, None), "{dpi}");
+    }
+}
+
+fn expand(indices: &[u8], palette: &[[u8; 3]]) -> Vec<u8> {
+    indices
+        .iter()
+        .flat_map(|&i| palette[usize::from(i)])
+        .collect()
+}
+
+#[test]
+fn presets_match_the_flat_encoders() {
+    let rgb = gradient(9, 4, 3);
+    let layout = PixelLayout::Rgb8;
+    let with = |options: BmpEncodeOptions<'_>| {
+        encode_bmp_with(&rgb, 9, 4, layout, &options, Unstoppable).unwrap()
+    };
+    assert_eq!(
+        with(BmpEncodeOptions::new().with_bit_depth(16)),
+        encode_bmp_rgb555(&rgb, 9, 4, layout, Unstoppable).unwrap()
+    );
+    assert_eq!(
+        with(
+            BmpEncodeOptions::new()
+                .with_bit_depth(16)
+                .with_compression(BmpCompression::Bitfields)
+        ),
+        encode_bmp_rgb565(&rgb, 9, 4, layout, Unstoppable).unwrap()
+    );
+    assert_eq!(
+        with(BmpEncodeOptions::new().with_bit_depth(4)),
+        encode_bmp_4bit(&rgb, 9, 4, layout, Unstoppable).unwrap()
+    );
+
+    // Pixels drawn from a palette map back to their indices exactly.
+    let palette = [[0, 0, 0], [255, 0, 0], [0, 128, 255], [9, 9, 9]];
+    let indices: Vec<u8> = (0..36).map(|i| (i * 7 % 4) as u8).collect();
+    let pixels = expand(&indices, &palette);
+    for (compression, flat) in [
+        (
+            BmpCompression::Rgb,
+            encode_bmp_paletted(&indices, &palette, 9, 4, Unstoppable).unwrap(),
+        ),
+        (
+            BmpCompression::Rle8,
+            encode_bmp_rle8(&indices, &palette, 9, 4, Unstoppable).unwrap(),
+        ),
+    ] {
+        let options = BmpEncodeOptions::new()
+            .with_palette(&palette)
+            .with_compression(compression);
+        let bmp = encode_bmp_with(&pixels, 9, 4, layout, &options, Unstoppable).unwrap();
+        assert_eq!(bmp, flat, "{compression:?}");
+    }
+    let options = BmpEncodeOptions::new()
+        .with_palette(&palette)
+        .with_compression(BmpCompression::Rle4);
+    let bmp = encode_bmp_with(&pixels, 9, 4, layout, &options, Unstoppable).unwrap();
+    assert_eq!(
+        bmp,
+        encode_bmp_rle4(&indices, &palette, 9, 4, Unstoppable).unwrap()
+    );
+}
+
+#[test]
+fn options_combine() {
+    let palette = [[10, 20, 30], [200, 100, 0]];
+    let indices: Vec<u8> = (0..15).map(|i| (i % 3 == 0) as u8).collect();
+    let pixels = expand(&indices, &palette);
+    for depth in [1, 2, 4, 8] {
+        let options = BmpEncodeOptions::new()
+            .with_bit_depth(depth)
+            .with_palette(&palette)
+            .with_top_down(true)
+            .with_header(BmpHeaderVariant::V5)
+            .with_color_space(BmpColorSpace::Srgb)
+            .with_dpi(300.0, 300.0);
+        let bmp = encode_bmp_with(&pixels, 5, 3, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+        let meta = probe_bmp(&bmp).unwrap();
+        assert_eq!(meta.bit_count, depth);
+        assert_eq!(meta.header_variant, BmpHeaderVariant::V5);
+        assert_eq!(meta.color_table.unwrap().len(), 2);
+        assert!((meta.dpi_x.unwrap() - 300.0).abs() < 0.02);
+        assert_eq!(stored_height(&bmp), -3);
+        let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+        let (decoded, report) = decode_bmp_with_report(&bmp, &strict, Unstoppable).unwrap();
+        assert!(report.is_clean(), "{:?}", report.warnings);
+        assert_eq!(decoded.pixels(), &pixels[..], "{depth}-bit");
+    }
+
+    // 16-bit top-down; 32-bit BI_BITFIELDS in a BITMAPINFOHEADER.
+    let rgb = gradient(3, 3, 3);
+    let options = BmpEncodeOptions::new()
+        .with_bit_depth(16)
+        .with_top_down(true);
+    let bmp = encode_bmp_with(&rgb, 3, 3, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    let bottom_up = encode_bmp_rgb555(&rgb, 3, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert_eq!(
+        decode_bmp(&bmp, Unstoppable).unwrap().pixels(),
+        decode_bmp(&bottom_up, Unstoppable).unwrap().pixels()
+    );
+    let options = BmpEncodeOptions::new()
+        .with_bit_depth(32)
+        .with_compression(BmpCompression::Bitfields);
+    let bmp = encode_bmp_with(&rgb, 3, 3, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.header_variant, BmpHeaderVariant::Info);
+    assert_eq!(meta.compression, BmpCompression::Bitfields);
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    let rgba: Vec<u8> = rgb
+        .chunks_exact(3)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect();
+    assert_eq!(decoded.pixels(), &rgba[..]);
+}
+
+#[test]
+fn rejects_impossible_combinations() {
+    let palette = [[0, 0, 0]];
+    for options in [
+        BmpEncodeOptions::new().with_bit_depth(12),
+        BmpEncodeOptions::new()
+            .with_bit_depth(24)
+            .with_compression(BmpCompression::Bitfields),
+        BmpEncodeOptions::new()
+            .with_bit_depth(8)
+            .with_compression(BmpCompression::Rle4),
+        BmpEncodeOptions::new()
+            .with_compression(BmpCompression::Rle8)
+            .with_top_down(true),
+        BmpEncodeOptions::new()
+            .with_bit_depth(24)
+            .with_palette(&palette),
+        BmpEncodeOptions::new().with_compression(BmpCompression::Unknown(7)),
+    ] {
+        let err =
+            encode_bmp_with(&[0; 3], 1, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+            "{options:?}: {err}"
+        );
+    }
+    // A palette too large for the depth.
+    let big = [[0u8; 3]; 17];
+    let options = BmpEncodeOptions::new().with_bit_depth(4).with_palette(&big);
+    let err = encode_bmp_with(&[0; 3], 1, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+}
@@ -0,0 +1,96 @@
+//! Linear-light f32 BMP decode.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn floats(decoded: &DecodeOutput<'_>) -> Vec<f32> {
+    decoded
+        .pixels()
+        .chunks_exact(4)
+        .map(|c| f32::from_ne_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn rgb_applies_srgb_transfer() {
+    let bmp = encode_bmp(&[0, 128, 255], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let decoded = decode_bmp_linear(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbF32);
+    let px = floats(&decoded);
+    assert_eq!(px[0], 0.0);
+    assert!((px[1] - 0.2158).abs() < 1e-3, "{}", px[1]);
+    assert_eq!(px[2], 1.0);
+}
+
+#[test]
+fn alpha_is_scaled_linearly() {
+    let bmp = encode_bmp_rgba(&[255, 0, 0, 128], 1, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let decoded = decode_bmp_linear(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbaF32);
+    assert_eq!(floats(&decoded), [1.0, 0.0, 0.0, 128.0 / 255.0]);
+}
+
+#[test]
+fn matches_eight_bit_decode_row_for_row() {
+    let pixels: Vec<u8> = (0..5 * 3 * 3).map(|i| (i * 17) as u8).collect();
+    let bmp = encode_bmp(&pixels, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let decoded = decode_bmp_linear(&bmp, Unstoppable).unwrap();
+    let linear = floats(&decoded);
+    assert_eq!(linear.len(), pixels.len());
+    for (&v, &f) in pixels.iter().zip(&linear) {
+        let single = encode_bmp(&[v, v, v], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+        let expected = floats(&decode_bmp_linear(&single, Unstoppable).unwrap())[0];
+        assert_eq!(f, expected);
+    }
+}
+
+#[test]
+fn memory_limit_covers_float_output() {
+    let bmp = encode_bmp(&[0u8; 4 * 4 * 3], 4, 4, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let limits = Limits {
+        max_memory_bytes: Some(4 * 4 * 3 * 2),
+        ..Default::default()
+    };
+    assert!(decode_bmp_with_limits(&bmp, &limits, Unstoppable).is_ok());
+    let err = decode_bmp_linear_with_limits(&bmp, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
+
+#[test]
+fn decode_options_are_honored() {
+    // 32-bit BI_RGB with an all-zero reserved byte.
+    let pixels = [10, 20, 30, 0, 40, 50, 60, 0];
+    let bmp = encode_bmp_rgba(&pixels, 2, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let alpha = |policy| {
+        let options = BmpDecodeOptions::new().with_alpha_policy(policy);
+        let decoded = decode_bmp_linear_with_options(&bmp, &options, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::RgbaF32);
+        floats(&decoded)
+            .chunks_exact(4)
+            .map(|p| p[3])
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(alpha(BmpAlphaPolicy::Auto), [1.0, 1.0]);
+    assert_eq!(alpha(BmpAlphaPolicy::Trust), [0.0, 0.0]);
+
+    // Missing the last stored row: zero-filled only when permissive.
+    let bmp = encode_bmp(&[7; 2 * 2 * 3], 2, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let truncated = &bmp[..bmp.len() - 8];
+    assert!(decode_bmp_linear(truncated, Unstoppable).is_err());
+    let permissive = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Permissive);
+    let decoded = decode_bmp_linear_with_options(truncated, &permissive, Unstoppable).unwrap();
+    assert_eq!(floats(&decoded)[..6], [0.0; 6]);
+}
+
+#[test]
+fn rle_matches_uncompressed() {
+    let palette = [[0, 0, 0], [255, 0, 0], [0, 128, 255], [9, 9, 9]];
+    let indices: Vec<u8> = (0..36).map(|i| (i * 7 % 4) as u8).collect();
+    let flat = encode_bmp_paletted(&indices, &palette, 9, 4, Unstoppable).unwrap();
+    let rle = encode_bmp_rle8(&indices, &palette, 9, 4, Unstoppable).unwrap();
+    let expected = decode_bmp_linear(&flat, Unstoppable).unwrap();
+    let decoded = decode_bmp_linear(&rle, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, expected.layout);
+    assert_eq!(floats(&decoded), floats(&expected));
+}
@@ -0,0 +1,154 @@
+//! Palette index usage counts from paletted BMP decode.
+#![cfg(feature = "bmp")]
+
+use std::collections::HashSet;
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::fixture;
+
+/// 8-bit bottom-up BMP with a 4-entry grayscale palette; `rows` top-down.
+fn indexed_bmp(width: usize, rows: &[&[u8]]) -> Vec<u8> {
+    let stride = width.div_ceil(4) * 4;
+    let offset = 14 + 40 + 4 * 4;
+    let size = offset + stride * rows.len();
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"BM");
+    buf.extend_from_slice(&(size as u32).to_le_bytes());
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(&(offset as u32).to_le_bytes());
+    buf.extend_from_slice(&40u32.to_le_bytes());
+    buf.extend_from_slice(&(width as i32).to_le_bytes());
+    buf.extend_from_slice(&(rows.len() as i32).to_le_bytes());
+    buf.extend_from_slice(&1u16.to_le_bytes());
+    buf.extend_from_slice(&8u16.to_le_bytes());
+    buf.extend_from_slice(&[0; 16]);
+    buf.extend_from_slice(&4u32.to_le_bytes()); // colors used
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    for v in [0u8, 85, 170, 255] {
+        buf.extend_from_slice(&[v, v, v, 0]);
+    }
+    for row in rows.iter().rev() {
+        buf.extend_from_slice(row);
+        buf.resize(buf.len() + stride - width, 0);
+    }
+    buf
+}
+
+#[test]
+fn counts_each_index() {
+    let bmp = indexed_bmp(4, &[&[0, 0, 2, 2], &[2, 2, 2, 0]]);
+    let (decoded, usage) =
+        decode_bmp_with_palette_usage(&bmp, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+    assert_eq!(
+        decoded.pixels(),
+        decode_bmp(&bmp, Unstoppable).unwrap().pixels()
+    );
+    let usage = usage.expect("paletted file");
+    assert_eq!(usage.counts, [3, 0, 5, 0]);
+    assert_eq!(usage.out_of_range, 0);
+    assert_eq!(usage.used().collect::<Vec<_>>(), [0, 2]);
+    assert_eq!(usage.used_count(), 2);
+}
+
+#[test]
+fn out_of_range_indices_are_counted_apart() {
+    let bmp = indexed_bmp(4, &[&[0, 9, 2, 200], &[2, 2, 9, 3]]);
+    for level in [BmpPermissiveness::Standard, BmpPermissiveness::Strict] {
+        let options = BmpDecodeOptions::new().with_permissiveness(level);
+        assert!(decode_bmp_with_palette_usage(&bmp, &options, Unstoppable).is_err());
+    }
+    let options = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Permissive);
+    let (_, usage) = decode_bmp_with_palette_usage(&bmp, &options, Unstoppable).unwrap();
+    let usage = usage.expect("paletted file");
+    assert_eq!(usage.counts, [1, 0, 3, 1]);
+    assert_eq!(usage.out_of_range, 3);
+}
+
+#[test]
+fn downscale_is_rejected() {
+    let bmp = indexed_bmp(4, &[&[0, 0, 2, 2], &[2, 2, 2, 0]]);
+    let options = BmpDecodeOptions::new().with_downscale(BmpDownscale::Half);
+    let err = decode_bmp_with_palette_usage(&bmp, &options, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::InvalidArgument(_)),
+        "{err}"
+    );
+}
+
+#[test]
+fn direct_color_has_no_usage() {
+    let bmp = encode_bmp(&[1, 2, 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let (_, usage) =
+        decode_bmp_with_palette_usage(&bmp, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+    assert!(usage.is_none());
+}
+
+#[test]
+fn uncompressed_fixtures_count_every_pixel() {
+    for name in [
+        "pal1.bmp",
+        "pal4.bmp",
+        "pal8.bmp",
+        "pal8os2.bmp",
+        "pal8topdown.bmp",
+    ] {
+        let data = fixture(name);
+        let (decoded, usage) =
+            decode_bmp_with_palette_usage(&data, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+        let usage = usage.unwrap_or_else(|| panic!("{name}: no usage"));
+        let meta = probe_bmp(&data).unwrap();
+        let palette = meta.color_table.unwrap();
+        assert_eq!(usage.counts.len(), palette.len(), "{name}");
+        let total: u64 = usage.counts.iter().sum();
+        assert_eq!(total, u64::from(decoded.width * decoded.height), "{name}");
+
+        // The referenced entries are exactly the colors in the output.
+        let bpp = decoded.layout.bytes_per_pixel();
+        let seen: HashSet<&[u8]> = decoded.pixels().chunks_exact(bpp).collect();
+        let used: HashSet<[u8; 3]> = usage
+            .used()
+            .map(|i| {
+                let [b, g, r, _] = palette[usize::from(i)];
+                [r, g, b]
+            })
+            .collect();
+        assert_eq!(seen.len(), used.len(), "{name}");
+        assert!(seen.iter().all(|px| used.contains(&px[..3])), "{name}");
+    }
+}
+
+#[test]
+fn rle_fixtures_count_written_pixels() {
+    for name in ["pal4rle.bmp", "pal8rle.bmp"] {
+        let data = fixture(name);
+        let (decoded, usage) =
+            decode_bmp_with_palette_usage(&data, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+        let usage = usage.unwrap_or_else(|| panic!("{name}: no usage"));
+        let total: u64 = usage.counts.iter().sum();
+        assert!(total > 0, "{name}");
+        assert!(total <= u64::from(decoded.width * decoded.height), "{name}");
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let bmp = indexed_bmp(4, &[&[0, 1, 2, 3]]);
+    let limits = Limits {
+        max_width: Some(2),
+        ..Default::default()
+    };
+    let err = decode_bmp_with_palette_usage_and_limits(
+        &bmp,
+        &BmpDecodeOptions::new(),
+        &limits,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+}
@@ -0,0 +1,151 @@
+//! Uncompressed paletted BMP encoding with a caller-supplied palette.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn palette(len: usize) -> Vec<[u8; 3]> {
+    (0..len)
+        .map(|i| [(i * 5) as u8, 255 - i as u8, (i * 11) as u8])
+        .collect()
+}
+
+fn pattern(width: usize, height: usize, colors: usize) -> Vec<u8> {
+    (0..width * height)
+        .map(|i| ((i * 7 + i / width) % colors) as u8)
+        .collect()
+}
+
+fn expand(indices: &[u8], palette: &[[u8; 3]]) -> Vec<u8> {
+    indices
+        .iter()
+        .flat_map(|&i| palette[usize::from(i)])
+        .collect()
+}
+
+#[test]
+fn eight_bit_roundtrips() {
+    for (width, height) in [(1, 1), (3, 2), (4, 4), (5, 3), (33, 7)] {
+        for colors in [1, 2, 100, 256] {
+            let pal = palette(colors);
+            let idx = pattern(width, height, colors);
+            let bmp =
+                encode_bmp_paletted(&idx, &pal, width as u32, height as u32, Unstoppable).unwrap();
+            let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+            let (decoded, report) = decode_bmp_with_report(&bmp, &strict, Unstoppable).unwrap();
+            assert!(report.is_clean(), "{:?}", report.warnings);
+            assert_eq!(decoded.layout, PixelLayout::Rgb8);
+            assert_eq!(decoded.pixels(), expand(&idx, &pal), "{width}x{height}");
+        }
+    }
+}
+
+#[test]
+fn color_table_is_written_verbatim() {
+    let pal = palette(7);
+    let idx = pattern(6, 2, 7);
+    let bmp = encode_bmp_paletted(&idx, &pal, 6, 2, Unstoppable).unwrap();
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.bit_count, 8);
+    assert_eq!(meta.compression, BmpCompression::Rgb);
+    let table: Vec<[u8; 3]> = meta
+        .color_table
+        .unwrap()
+        .iter()
+        .map(|&[b, g, r, _]| [r, g, b])
+        .collect();
+    assert_eq!(table, pal);
+
+    // Headers, then the 7-entry table, then two bottom-up rows padded to 8.
+    let offset = u32::from_le_bytes(bmp[10..14].try_into().unwrap()) as usize;
+    assert_eq!(offset, 14 + 40 + 7 * 4);
+    assert_eq!(bmp.len(), offset + 2 * 8);
+    assert_eq!(&bmp[offset..offset + 6], &idx[6..]);
+    assert_eq!(&bmp[offset + 8..offset + 14], &idx[..6]);
+}
+
+#[test]
+fn indices_are_counted_back() {
+    let idx = pattern(10, 10, 9);
+    let bmp = encode_bmp_paletted(&idx, &palette(9), 10, 10, Unstoppable).unwrap();
+    let (_, usage) =
+        decode_bmp_with_palette_usage(&bmp, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+    let usage = usage.unwrap();
+    assert_eq!(usage.counts.len(), 9);
+    assert_eq!(usage.counts.iter().sum::<u64>(), 100);
+}
+
+#[test]
+fn rejects_indices_outside_the_palette() {
+    let err = encode_bmp_paletted(&[0, 3], &palette(3), 2, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    let err = encode_bmp_paletted(&[0], &palette(257), 1, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+}
+
+#[test]
+fn four_bit_roundtrips() {
+    for (width, height) in [(1, 1), (2, 1), (3, 2), (7, 3), (9, 4), (33, 5)] {
+        for colors in [1, 2, 16] {
+            let pal = palette(colors);
+            let idx = pattern(width, height, colors);
+            let bmp =
+                encode_bmp_paletted4(&idx, &pal, width as u32, height as u32, Unstoppable).unwrap();
+            let meta = probe_bmp(&bmp).unwrap();
+            assert_eq!(meta.bit_count, 4);
+            assert_eq!(meta.color_table.unwrap().len(), colors);
+            let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+            assert_eq!(decoded.pixels(), expand(&idx, &pal), "{width}x{height}");
+        }
+    }
+    let err = encode_bmp_paletted4(&[0], &palette(17), 1, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+}
+
+#[test]
+fn four_bit_keeps_few_colors_exact() {
+    let pal = palette(16);
+    let rgb = expand(&pattern(11, 6, 16), &pal);
+    let bmp = encode_bmp_4bit(&rgb, 11, 6, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert_eq!(probe_bmp(&bmp).unwrap().bit_count, 4);
+    assert_eq!(decode_bmp(&bmp, Unstoppable).unwrap().pixels(), rgb);
+
+    // Alpha is dropped; BGRA input is swizzled.
+    let bgra: Vec<u8> = rgb
+        .chunks_exact(3)
+        .flat_map(|p| [p[2], p[1], p[0], 7])
+        .collect();
+    let bmp = encode_bmp_4bit(&bgra, 11, 6, PixelLayout::Bgra8, Unstoppable).unwrap();
+    assert_eq!(decode_bmp(&bmp, Unstoppable).unwrap().pixels(), rgb);
+}
+
+#[test]
+fn four_bit_quantizes_many_colors() {
+    let (width, height) = (64usize, 16usize);
+    let rgb: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let (x, y) = (i % width, i / width);
+            [(x * 4) as u8, (y * 16) as u8, 128]
+        })
+        .collect();
+    let bmp = encode_bmp_4bit(
+        &rgb,
+        width as u32,
+        height as u32,
+        PixelLayout::Rgb8,
+        Unstoppable,
+    )
+    .unwrap();
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.bit_count, 4);
+    assert_eq!(meta.color_table.unwrap().len(), 16);
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    let mean_error = decoded
+        .pixels()
+        .iter()
+        .zip(&rgb)
+        .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+        .sum::<u64>() as f64
+        / rgb.len() as f64;
+    assert!(mean_error < 16.0, "mean error {mean_error}");
+}
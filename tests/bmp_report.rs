@@ -0,0 +1,143 @@
+//! `DecodeReport` warnings for BMP files the decoder patches over.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn permissive() -> BmpDecodeOptions {
+    BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Permissive)
+}
+
+/// 8-bit RLE BMP with a two-entry palette and `palette_count` in biClrUsed.
+fn rle8_bmp(width: i32, height: i32, palette_count: u32, stream: &[u8]) -> Vec<u8> {
+    let offset = 14 + 40 + 2 * 4;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&((offset + stream.len()) as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(offset as u32).to_le_bytes());
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&8u16.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+    out.extend_from_slice(&(stream.len() as u32).to_le_bytes());
+    out.extend_from_slice(&[0u8; 8]);
+    out.extend_from_slice(&palette_count.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&[0, 0, 0, 0, 0, 0, 255, 0]);
+    out.extend_from_slice(stream);
+    out
+}
+
+#[test]
+fn clean_file_has_empty_report() {
+    let pixels: Vec<u8> = (0..4 * 3 * 3).map(|i| i as u8).collect();
+    let bmp = encode_bmp(&pixels, 4, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    for options in [BmpDecodeOptions::new(), permissive()] {
+        let (decoded, report) = decode_bmp_with_report(&bmp, &options, Unstoppable).unwrap();
+        assert!(report.is_clean(), "{:?}", report.warnings);
+        assert_eq!(report.valid_rows, Some(0..3));
+        assert_eq!(decoded.pixels(), &pixels[..]);
+    }
+}
+
+#[test]
+fn truncated_pixel_data_is_reported_and_pixels_match() {
+    let pixels = vec![200u8; 4 * 4 * 3];
+    let mut bmp = encode_bmp(&pixels, 4, 4, PixelLayout::Rgb8, Unstoppable).unwrap();
+    bmp.truncate(bmp.len() - 20);
+    let (decoded, report) = decode_bmp_with_report(&bmp, &permissive(), Unstoppable).unwrap();
+    assert!(
+        report
+            .warnings
+            .contains(&DecodeWarning::TruncatedPixelData {
+                expected: 48,
+                available: 28,
+            })
+    );
+    assert!(
+        report
+            .warnings
+            .iter()
+            .any(|w| matches!(w, DecodeWarning::FileSizeMismatch { .. }))
+    );
+    let plain = decode_bmp_with_options(&bmp, &permissive(), Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), plain.pixels());
+
+    // 28 bytes hold two complete 12-byte rows (plus part of a third);
+    // bottom-up, so they are the bottom two output rows.
+    assert_eq!(report.valid_rows, Some(2..4));
+    let rows: Vec<&[u8]> = decoded.pixels().chunks(4 * 3).collect();
+    assert!(rows[0].iter().all(|&b| b == 0));
+    assert!(rows[1].contains(&0));
+    assert!(rows[2..].iter().all(|r| r.iter().all(|&b| b == 200)));
+}
+
+#[test]
+fn truncated_top_down_keeps_leading_rows() {
+    let pixels = vec![200u8; 4 * 4 * 3];
+    let mut bmp = encode_bmp(&pixels, 4, 4, PixelLayout::Rgb8, Unstoppable).unwrap();
+    bmp[22..26].copy_from_slice(&(-4i32).to_le_bytes());
+    bmp.truncate(bmp.len() - 30);
+    let (decoded, report) = decode_bmp_with_report(&bmp, &permissive(), Unstoppable).unwrap();
+    assert_eq!(report.valid_rows, Some(0..1));
+    assert!(decoded.pixels()[..12].iter().all(|&b| b == 200));
+}
+
+#[test]
+fn rle_without_end_marker_is_reported() {
+    // One row of 2×red, EOL, then the stream just stops.
+    let bmp = rle8_bmp(2, 2, 2, &[2, 1, 0, 0]);
+    let (_, report) = decode_bmp_with_report(&bmp, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+    assert_eq!(report.warnings, [DecodeWarning::RleTruncated]);
+    // Only the bottom (first stored) row was completed.
+    assert_eq!(report.valid_rows, Some(1..2));
+
+    let terminated = rle8_bmp(2, 2, 2, &[2, 1, 0, 0, 2, 0, 0, 1]);
+    let (_, report) =
+        decode_bmp_with_report(&terminated, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.valid_rows, Some(0..2));
+}
+
+#[test]
+fn permissive_palette_count_clamp_is_reported() {
+    // Palette count 300 > 256: clamping to 256 entries reads over the pixel
+    // data offset, which is reported too.
+    let bmp = rle8_bmp(2, 1, 300, &[2, 1, 0, 1]);
+    assert!(decode_bmp_with_report(&bmp, &BmpDecodeOptions::new(), Unstoppable).is_err());
+    let (_, report) = decode_bmp_with_report(&bmp, &permissive(), Unstoppable).unwrap();
+    assert!(
+        report
+            .warnings
+            .contains(&DecodeWarning::PaletteCountClamped {
+                declared: 300,
+                max: 256,
+            })
+    );
+    assert!(
+        report
+            .warnings
+            .iter()
+            .any(|w| matches!(w, DecodeWarning::PixelOffsetAdjusted { declared: 62, .. }))
+    );
+}
+
+#[test]
+fn repeated_problems_are_reported_once() {
+    // Three 2-pixel runs into a 2-pixel image: the last two are skipped.
+    let bmp = rle8_bmp(2, 1, 2, &[2, 1, 2, 1, 2, 1, 0, 1]);
+    assert!(decode_bmp_with_report(&bmp, &BmpDecodeOptions::new(), Unstoppable).is_err());
+    let (_, report) = decode_bmp_with_report(&bmp, &permissive(), Unstoppable).unwrap();
+    assert_eq!(report.warnings, [DecodeWarning::RleRunSkipped]);
+}
+
+#[test]
+fn out_of_range_index_is_reported() {
+    let bmp = rle8_bmp(2, 1, 2, &[2, 7, 0, 1]);
+    let (_, report) = decode_bmp_with_report(&bmp, &permissive(), Unstoppable).unwrap();
+    assert_eq!(report.warnings, [DecodeWarning::PaletteIndexOutOfRange]);
+    assert!(!report.warnings[0].to_string().is_empty());
+}
@@ -0,0 +1,100 @@
+//! Pixel-exact RLE4/RLE8 decoding on hand-built streams.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Build a BITMAPINFOHEADER RLE BMP (`bpp` 4 → RLE4, 8 → RLE8).
+fn rle_bmp(width: i32, height: i32, bpp: u16, palette: &[[u8; 3]], stream: &[u8]) -> Vec<u8> {
+    let offset = 14 + 40 + palette.len() * 4;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&((offset + stream.len()) as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(offset as u32).to_le_bytes());
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&bpp.to_le_bytes());
+    let compression: u32 = if bpp == 8 { 1 } else { 2 };
+    out.extend_from_slice(&compression.to_le_bytes());
+    out.extend_from_slice(&(stream.len() as u32).to_le_bytes());
+    out.extend_from_slice(&[0u8; 8]);
+    out.extend_from_slice(&(palette.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    for [r, g, b] in palette {
+        out.extend_from_slice(&[*b, *g, *r, 0]);
+    }
+    out.extend_from_slice(stream);
+    out
+}
+
+const PAL: [[u8; 3]; 3] = [[0, 0, 0], [255, 0, 0], [0, 0, 255]];
+
+fn px(indices: &[usize]) -> Vec<u8> {
+    indices.iter().flat_map(|&i| PAL[i]).collect()
+}
+
+#[test]
+fn rle8_runs_absolute_and_bottom_up() {
+    // Stored bottom row first: run of 2×red + absolute [blue, red, blue]
+    // (padded to even), EOL, then 5×blue, end of bitmap.
+    let stream = [
+        2, 1, 0, 3, 2, 1, 2, 0, 0, 0, //
+        5, 2, 0, 1,
+    ];
+    let bmp = rle_bmp(5, 2, 8, &PAL, &stream);
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(
+        decoded.pixels(),
+        &[px(&[2, 2, 2, 2, 2]), px(&[1, 1, 2, 1, 2])].concat()[..]
+    );
+
+    let native = decode_bmp_native(&bmp, Unstoppable).unwrap();
+    assert_eq!(native.layout, PixelLayout::Bgr8);
+    assert_eq!(&native.pixels()[..3], &[255, 0, 0]); // blue in BGR order
+}
+
+#[test]
+fn rle8_delta_skips_leave_palette_zero() {
+    // Delta (2, 1) from the bottom-left, then one red pixel; everything the
+    // stream never touches is palette entry 0.
+    let mut pal = PAL;
+    pal[0] = [9, 9, 9];
+    let stream = [0, 2, 2, 1, 1, 1, 0, 1];
+    let bmp = rle_bmp(3, 2, 8, &pal, &stream);
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    let expected: Vec<u8> = [
+        [9, 9, 9],
+        [9, 9, 9],
+        [255, 0, 0],
+        [9, 9, 9],
+        [9, 9, 9],
+        [9, 9, 9],
+    ]
+    .concat();
+    assert_eq!(decoded.pixels(), &expected[..]);
+}
+
+#[test]
+fn rle4_alternating_run_and_absolute() {
+    // Run of 3 alternating nibbles (1, 2, 1), then absolute [2, 1, 0]
+    // (2 bytes, padded to a word), end of bitmap.
+    let stream = [3, 0x12, 0, 3, 0x21, 0x00, 0, 1];
+    let bmp = rle_bmp(6, 1, 4, &PAL, &stream);
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &px(&[1, 2, 1, 2, 1, 0])[..]);
+}
+
+#[test]
+fn rle_palette_index_out_of_range() {
+    let stream = [2, 7, 0, 1];
+    let bmp = rle_bmp(2, 1, 8, &PAL, &stream);
+    let err = decode_bmp(&bmp, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    // Permissive maps it through the (zeroed) 256-entry table instead.
+    let decoded = decode_bmp_permissive(&bmp, BmpPermissiveness::Permissive, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0; 6]);
+}
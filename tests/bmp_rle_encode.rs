@@ -0,0 +1,153 @@
+//! RLE8/RLE4-compressed BMP encoding, checked against the crate's own decoder.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn palette(len: usize) -> Vec<[u8; 3]> {
+    (0..len)
+        .map(|i| [i as u8, (i * 3) as u8, 255 - i as u8])
+        .collect()
+}
+
+/// Indices mixing long runs, pairs and noise (xorshift).
+fn indices(width: usize, height: usize, colors: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed | 1;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+    let mut out = Vec::with_capacity(width * height);
+    while out.len() < width * height {
+        let v = (next() as usize % colors) as u8;
+        let len = match next() % 4 {
+            0 => 1,
+            1 => 2,
+            2 => 1 + next() as usize % 5,
+            _ => 1 + next() as usize % 400,
+        };
+        out.extend(std::iter::repeat_n(v, len));
+    }
+    out.truncate(width * height);
+    out
+}
+
+fn expand(indices: &[u8], palette: &[[u8; 3]]) -> Vec<u8> {
+    indices
+        .iter()
+        .flat_map(|&i| palette[usize::from(i)])
+        .collect()
+}
+
+fn assert_roundtrip(depth: u16, indices: &[u8], palette: &[[u8; 3]], width: u32, height: u32) {
+    let (bmp, compression) = if depth == 4 {
+        let bmp = encode_bmp_rle4(indices, palette, width, height, Unstoppable);
+        (bmp.unwrap(), BmpCompression::Rle4)
+    } else {
+        let bmp = encode_bmp_rle8(indices, palette, width, height, Unstoppable);
+        (bmp.unwrap(), BmpCompression::Rle8)
+    };
+    let meta = probe_bmp(&bmp).unwrap();
+    assert_eq!(meta.compression, compression);
+    assert_eq!(meta.bit_count, depth);
+    let strict = BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Strict);
+    let (decoded, report) = decode_bmp_with_report(&bmp, &strict, Unstoppable).unwrap();
+    assert!(report.is_clean(), "{:?}", report.warnings);
+    assert_eq!((decoded.width, decoded.height), (width, height));
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(
+        decoded.pixels(),
+        expand(indices, palette),
+        "RLE{depth} {width}x{height}"
+    );
+}
+
+#[test]
+fn rle8_roundtrips() {
+    for (width, height) in [(1, 1), (2, 1), (3, 2), (7, 5), (255, 3), (256, 2), (600, 4)] {
+        for colors in [1, 2, 16, 256] {
+            let idx = indices(width, height, colors, (width * 31 + colors) as u32);
+            assert_roundtrip(8, &idx, &palette(colors), width as u32, height as u32);
+        }
+    }
+}
+
+#[test]
+fn rle4_roundtrips() {
+    for (width, height) in [(1, 1), (2, 1), (3, 2), (7, 5), (255, 3), (256, 2), (601, 4)] {
+        for colors in [1, 2, 5, 16] {
+            let idx = indices(width, height, colors, (width * 17 + colors) as u32);
+            assert_roundtrip(4, &idx, &palette(colors), width as u32, height as u32);
+        }
+    }
+}
+
+#[test]
+fn short_literals_and_long_runs() {
+    let pal = palette(4);
+    // Literals of 1, 2 and 3 pixels between runs; a run longer than 255.
+    let row: Vec<u8> = [
+        &[0u8][..],
+        &[1, 1, 1],
+        &[2, 3],
+        &[0; 300],
+        &[1, 2, 3],
+        &[3, 3],
+        &[0, 1, 0, 1, 0],
+    ]
+    .concat();
+    for depth in [8, 4] {
+        assert_roundtrip(depth, &row, &pal, row.len() as u32, 1);
+        // Odd-length absolute runs at every alignment.
+        for skip in 1..4 {
+            assert_roundtrip(depth, &row[skip..], &pal, (row.len() - skip) as u32, 1);
+        }
+    }
+}
+
+#[test]
+fn rle8_compresses_flat_images() {
+    let idx = vec![5u8; 64 * 64];
+    let bmp = encode_bmp_rle8(&idx, &palette(8), 64, 64, Unstoppable).unwrap();
+    assert!(bmp.len() < 64 * 64 / 4, "{} bytes", bmp.len());
+    let bmp = encode_bmp_rle4(&idx, &palette(8), 64, 64, Unstoppable).unwrap();
+    assert!(bmp.len() < 64 * 64 / 8, "{} bytes", bmp.len());
+}
+
+#[test]
+fn palette_usage_matches_histogram() {
+    let idx = indices(40, 30, 12, 7);
+    let mut histogram = vec![0u64; 12];
+    for &i in &idx {
+        histogram[usize::from(i)] += 1;
+    }
+    for bmp in [
+        encode_bmp_rle8(&idx, &palette(12), 40, 30, Unstoppable).unwrap(),
+        encode_bmp_rle4(&idx, &palette(12), 40, 30, Unstoppable).unwrap(),
+    ] {
+        let (_, usage) =
+            decode_bmp_with_palette_usage(&bmp, &BmpDecodeOptions::new(), Unstoppable).unwrap();
+        assert_eq!(usage.unwrap().counts, histogram);
+    }
+}
+
+#[test]
+fn rejects_bad_palettes() {
+    let err = encode_bmp_rle8(&[0], &[], 1, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    let err = encode_bmp_rle8(&[0], &palette(257), 1, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    let err = encode_bmp_rle8(&[0, 4], &palette(4), 2, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    let err = encode_bmp_rle4(&[0], &palette(17), 1, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    let err = encode_bmp_rle4(&[0, 16], &palette(16), 2, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    let err = encode_bmp_rle8(&[0], &palette(4), 2, 1, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::BufferTooSmall { .. }),
+        "{err}"
+    );
+}
@@ -0,0 +1,112 @@
+//! Row-streaming BMP encoding via `BmpRowEncoder`.
+#![cfg(feature = "bmp")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::gradient;
+
+/// Push `pixels` in strips of 1, 2, 3, … rows and finish.
+fn streamed(pixels: &[u8], width: u32, layout: PixelLayout, options: &BmpEncodeOptions) -> Vec<u8> {
+    let row_bytes = width as usize * layout.bytes_per_pixel();
+    let mut encoder = BmpRowEncoder::new(width, layout, options).unwrap();
+    let (mut start, mut strip) = (0, 1);
+    while start < pixels.len() {
+        let end = (start + strip * row_bytes).min(pixels.len());
+        encoder.push_rows(&pixels[start..end]).unwrap();
+        (start, strip) = (end, strip + 1);
+    }
+    assert_eq!(encoder.rows() as usize, pixels.len() / row_bytes);
+    encoder.finish(Unstoppable).unwrap()
+}
+
+#[test]
+fn matches_one_shot_encode() {
+    let palette = [[0, 0, 0], [255, 255, 255], [200, 10, 10]];
+    let cases = [
+        (PixelLayout::Rgb8, BmpEncodeOptions::new()),
+        (
+            PixelLayout::Rgb8,
+            BmpEncodeOptions::new().with_top_down(true),
+        ),
+        (PixelLayout::Gray8, BmpEncodeOptions::new()),
+        (PixelLayout::Rgba8, BmpEncodeOptions::new().with_alpha(true)),
+        (
+            PixelLayout::Bgra8,
+            BmpEncodeOptions::new().with_header(BmpHeaderVariant::V5),
+        ),
+        (
+            PixelLayout::Rgb8,
+            BmpEncodeOptions::new()
+                .with_bit_depth(16)
+                .with_compression(BmpCompression::Bitfields),
+        ),
+        (PixelLayout::Rgb8, BmpEncodeOptions::new().with_bit_depth(4)),
+        (
+            PixelLayout::Rgb8,
+            BmpEncodeOptions::new()
+                .with_compression(BmpCompression::Rle8)
+                .with_palette(&palette),
+        ),
+        (PixelLayout::Rgba16, BmpEncodeOptions::new()),
+    ];
+    for (layout, options) in cases {
+        let (width, height) = (7, 9);
+        let pixels = gradient(width, height, layout.bytes_per_pixel());
+        let expected = encode_bmp_with(
+            &pixels,
+            width as u32,
+            height as u32,
+            layout,
+            &options,
+            Unstoppable,
+        )
+        .unwrap();
+        assert_eq!(
+            streamed(&pixels, width as u32, layout, &options),
+            expected,
+            "{layout:?} {options:?}"
+        );
+    }
+}
+
+#[test]
+fn rows_come_out_top_to_bottom() {
+    let pixels = gradient(5, 6, 3);
+    let bmp = streamed(&pixels, 5, PixelLayout::Rgb8, &BmpEncodeOptions::new());
+    let decoded = decode_bmp(&bmp, Unstoppable).unwrap();
+    assert_eq!((decoded.width, decoded.height), (5, 6));
+    assert_eq!(decoded.pixels(), pixels);
+}
+
+#[test]
+fn rejects_partial_rows() {
+    let options = BmpEncodeOptions::new();
+    let mut encoder = BmpRowEncoder::new(4, PixelLayout::Rgb8, &options).unwrap();
+    let err = encoder.push_rows(&[0; 13]).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    assert_eq!(encoder.rows(), 0);
+    encoder.push_rows(&[0; 24]).unwrap();
+    assert_eq!(encoder.rows(), 2);
+}
+
+#[test]
+fn rejects_bad_setups_up_front() {
+    let options = BmpEncodeOptions::new();
+    let err = BmpRowEncoder::new(0, PixelLayout::Rgb8, &options).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+
+    let top_down_rle = BmpEncodeOptions::new()
+        .with_compression(BmpCompression::Rle8)
+        .with_top_down(true);
+    let err = BmpRowEncoder::new(4, PixelLayout::Rgb8, &top_down_rle).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+        "{err}"
+    );
+
+    let encoder = BmpRowEncoder::new(4, PixelLayout::Rgb8, &options).unwrap();
+    let err = encoder.finish(Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+}
@@ -0,0 +1,73 @@
+//! `encode_bmp_to_writer` never allocates the whole file: its largest
+//! allocation is about one 16-row band, measured with a counting
+//! allocator. Kept in its own test binary so no other test allocates
+//! alongside it.
+#![cfg(all(feature = "bmp", feature = "std"))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use zenbitmaps::*;
+
+/// The system allocator, recording the largest allocation since reset.
+struct Largest;
+
+static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Largest {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LARGEST.fetch_max(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LARGEST.fetch_max(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Largest = Largest;
+
+#[test]
+fn writer_allocates_one_band_not_the_file() {
+    let (width, height) = (1000u32, 1000u32);
+    let pixels = vec![0x5Au8; width as usize * height as usize * 3];
+    let options = BmpEncodeOptions::new();
+
+    LARGEST.store(0, Ordering::Relaxed);
+    encode_bmp_to_writer(
+        &pixels,
+        width,
+        height,
+        PixelLayout::Rgb8,
+        &options,
+        std::io::sink(),
+        Unstoppable,
+    )
+    .unwrap();
+    let largest = LARGEST.load(Ordering::Relaxed);
+    // One band is 16 rows of 3000 bytes; the file is 3,000,054 bytes.
+    let band = 14 + 40 + 16 * 3000;
+    assert!(largest <= 2 * band, "largest allocation {largest} bytes");
+
+    // The in-memory encoders still size the file in one allocation.
+    LARGEST.store(0, Ordering::Relaxed);
+    let mut out = Vec::new();
+    encode_bmp_into(
+        &pixels,
+        width,
+        height,
+        PixelLayout::Rgb8,
+        &options,
+        &mut out,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(out.capacity(), out.len());
+    assert_eq!(LARGEST.load(Ordering::Relaxed), out.len());
+}
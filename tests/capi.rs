@@ -0,0 +1,165 @@
+//! The C ABI: caller-owned buffers, size queries and error codes.
+#![cfg(feature = "capi")]
+
+use core::ptr;
+use std::ffi::CStr;
+
+use zenbitmaps::capi::*;
+use zenbitmaps::*;
+
+fn rgb(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 3)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect()
+}
+
+fn encode_ppm_c(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut len = 0;
+    let code = unsafe {
+        zenbitmaps_encode_ppm(
+            pixels.as_ptr(),
+            pixels.len(),
+            width,
+            height,
+            ZENBITMAPS_LAYOUT_RGB8,
+            ptr::null_mut(),
+            0,
+            &mut len,
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_ERROR_BUFFER_TOO_SMALL);
+    let mut out = vec![0; len];
+    let code = unsafe {
+        zenbitmaps_encode_ppm(
+            pixels.as_ptr(),
+            pixels.len(),
+            width,
+            height,
+            ZENBITMAPS_LAYOUT_RGB8,
+            out.as_mut_ptr(),
+            out.len(),
+            &mut len,
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_OK);
+    assert_eq!(len, out.len());
+    out
+}
+
+#[test]
+fn encode_matches_the_rust_encoder() {
+    let pixels = rgb(5, 3);
+    let file = encode_ppm_c(&pixels, 5, 3);
+    assert_eq!(
+        file,
+        encode_ppm(&pixels, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap()
+    );
+
+    let mut size = 0;
+    let code = unsafe {
+        zenbitmaps_encoded_size(
+            5,
+            3,
+            ZENBITMAPS_LAYOUT_RGB8,
+            ZENBITMAPS_FORMAT_PPM,
+            &mut size,
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_OK);
+    assert_eq!(size, file.len());
+}
+
+#[test]
+fn decode_sizes_then_fills() {
+    let pixels = rgb(4, 2);
+    let file = encode_ppm_c(&pixels, 4, 2);
+
+    let mut info = ZenbitmapsImageInfo::default();
+    let code =
+        unsafe { zenbitmaps_decode(file.as_ptr(), file.len(), ptr::null_mut(), 0, &mut info) };
+    assert_eq!(code, ZENBITMAPS_ERROR_BUFFER_TOO_SMALL);
+    assert_eq!(
+        info,
+        ZenbitmapsImageInfo {
+            width: 4,
+            height: 2,
+            layout: ZENBITMAPS_LAYOUT_RGB8,
+            size: pixels.len(),
+        }
+    );
+
+    let mut out = vec![0; info.size];
+    let code = unsafe {
+        zenbitmaps_decode(
+            file.as_ptr(),
+            file.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_OK);
+    assert_eq!(out, pixels);
+}
+
+#[test]
+fn limits_and_errors_map_to_codes() {
+    let file = encode_ppm_c(&rgb(16, 16), 16, 16);
+    let limits = ZenbitmapsLimits {
+        max_pixels: 100,
+        ..Default::default()
+    };
+    let mut info = ZenbitmapsImageInfo::default();
+    let code = unsafe {
+        zenbitmaps_decode_with_limits(
+            file.as_ptr(),
+            file.len(),
+            &limits,
+            ptr::null_mut(),
+            0,
+            &mut info,
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_ERROR_LIMIT_EXCEEDED);
+
+    let code = unsafe { zenbitmaps_decode(file.as_ptr(), 20, ptr::null_mut(), 0, ptr::null_mut()) };
+    assert_eq!(code, ZENBITMAPS_ERROR_MALFORMED);
+    let junk = b"not an image";
+    let code = unsafe {
+        zenbitmaps_decode(
+            junk.as_ptr(),
+            junk.len(),
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_ERROR_UNSUPPORTED);
+    let code = unsafe { zenbitmaps_decode(ptr::null(), 8, ptr::null_mut(), 0, ptr::null_mut()) };
+    assert_eq!(code, ZENBITMAPS_ERROR_INVALID_ARGUMENT);
+
+    let mut size = 0;
+    let code = unsafe { zenbitmaps_encoded_size(1, 1, 99, ZENBITMAPS_FORMAT_PPM, &mut size) };
+    assert_eq!(code, ZENBITMAPS_ERROR_INVALID_ARGUMENT);
+    let code = unsafe {
+        zenbitmaps_encoded_size(
+            1,
+            1,
+            ZENBITMAPS_LAYOUT_GRAY16,
+            ZENBITMAPS_FORMAT_PPM,
+            &mut size,
+        )
+    };
+    assert_eq!(code, ZENBITMAPS_ERROR_UNSUPPORTED);
+}
+
+#[test]
+fn error_messages_are_static_strings() {
+    let message = |code| unsafe { CStr::from_ptr(zenbitmaps_error_message(code)) };
+    assert_eq!(message(ZENBITMAPS_OK).to_str().unwrap(), "ok");
+    assert_eq!(
+        message(ZENBITMAPS_ERROR_BUFFER_TOO_SMALL).to_str().unwrap(),
+        "output buffer too small"
+    );
+    assert_eq!(message(42).to_str().unwrap(), "unknown error code");
+}
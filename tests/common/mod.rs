@@ -0,0 +1,18 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+/// `width × height` pixels of `channels` bytes each, filled with a ramp
+/// that steps through every byte value below 251.
+pub fn gradient(width: usize, height: usize, channels: usize) -> Vec<u8> {
+    (0..width * height * channels)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect()
+}
+
+/// A file from `tests/bmp-fixtures`.
+pub fn fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/bmp-fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read(&path).unwrap_or_else(|e| panic!("failed to read {path}: {e}"))
+}
@@ -0,0 +1,313 @@
+#![cfg(feature = "convert")]
+//! Layout conversion: exactness of the integer paths, the sRGB transfer,
+//! and every pair of layouts.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const LAYOUTS: [PixelLayout; 18] = [
+    PixelLayout::Gray8,
+    PixelLayout::Gray16,
+    PixelLayout::GrayA16,
+    PixelLayout::Rgb8,
+    PixelLayout::Rgba8,
+    PixelLayout::Bgr8,
+    PixelLayout::Bgra8,
+    PixelLayout::Bgrx8,
+    PixelLayout::Rgbx8,
+    PixelLayout::Rgb16,
+    PixelLayout::Rgba16,
+    PixelLayout::Rgba16Be,
+    PixelLayout::GrayF32,
+    PixelLayout::RgbF32,
+    PixelLayout::RgbaF32,
+    PixelLayout::RgbPlanar8,
+    PixelLayout::RgbaPlanar8,
+    PixelLayout::RgbPlanarF32,
+];
+
+fn run(pixels: &[u8], w: u32, h: u32, from: PixelLayout, to: PixelLayout) -> Vec<u8> {
+    convert(pixels, w, h, from, to, &ConvertOptions::new(), Unstoppable).unwrap()
+}
+
+fn u16s(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+        .collect()
+}
+
+fn f32s(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+#[test]
+fn swizzles_and_padding() {
+    let rgba = [1, 2, 3, 4, 5, 6, 7, 8];
+    assert_eq!(
+        run(&rgba, 2, 1, PixelLayout::Rgba8, PixelLayout::Bgra8),
+        [3, 2, 1, 4, 7, 6, 5, 8]
+    );
+    assert_eq!(
+        run(&rgba, 2, 1, PixelLayout::Rgba8, PixelLayout::Bgr8),
+        [3, 2, 1, 7, 6, 5]
+    );
+    let bgrx = run(&rgba, 2, 1, PixelLayout::Rgba8, PixelLayout::Bgrx8);
+    assert_eq!(bgrx, [3, 2, 1, 255, 7, 6, 5, 255]);
+    // The padding byte is not alpha
+    assert_eq!(
+        run(&[1, 2, 3, 0], 1, 1, PixelLayout::Rgbx8, PixelLayout::Rgba8),
+        [1, 2, 3, 255]
+    );
+    let planar = run(&rgba, 2, 1, PixelLayout::Rgba8, PixelLayout::RgbPlanar8);
+    assert_eq!(planar, [1, 5, 2, 6, 3, 7]);
+    assert_eq!(
+        run(&planar, 2, 1, PixelLayout::RgbPlanar8, PixelLayout::Bgr8),
+        [3, 2, 1, 7, 6, 5]
+    );
+}
+
+#[test]
+fn bit_depths() {
+    let rgba: Vec<u8> = (0..=255).collect();
+    let wide = run(&rgba, 64, 1, PixelLayout::Rgba8, PixelLayout::Rgba16);
+    assert_eq!(
+        u16s(&wide),
+        (0..=255u16).map(|v| v * 257).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        run(&wide, 64, 1, PixelLayout::Rgba16, PixelLayout::Rgba8),
+        rgba
+    );
+    let be = run(&wide, 64, 1, PixelLayout::Rgba16, PixelLayout::Rgba16Be);
+    assert_eq!(&be[2..4], &257u16.to_be_bytes());
+    assert_eq!(
+        run(&be, 64, 1, PixelLayout::Rgba16Be, PixelLayout::Rgba8),
+        rgba
+    );
+
+    // 16 → 8 rounds to nearest
+    let samples: Vec<u8> = [128u16, 129, 385, 65535]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    assert_eq!(
+        run(&samples, 4, 1, PixelLayout::Gray16, PixelLayout::Gray8),
+        [0, 1, 1, 255]
+    );
+}
+
+#[test]
+fn gray_and_alpha() {
+    assert_eq!(
+        run(&[7, 200], 2, 1, PixelLayout::Gray8, PixelLayout::Rgb8),
+        [7, 7, 7, 200, 200, 200]
+    );
+    let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 90, 90, 90];
+    assert_eq!(
+        run(&rgb, 4, 1, PixelLayout::Rgb8, PixelLayout::Gray8),
+        [54, 182, 18, 90]
+    );
+
+    let rgba = [10, 10, 10, 77];
+    let gray_alpha = run(&rgba, 1, 1, PixelLayout::Rgba8, PixelLayout::GrayA16);
+    assert_eq!(u16s(&gray_alpha), [2570, 77 * 257]);
+    assert_eq!(
+        run(&gray_alpha, 1, 1, PixelLayout::GrayA16, PixelLayout::Rgba8),
+        rgba
+    );
+    assert_eq!(
+        run(&rgba, 1, 1, PixelLayout::Rgba8, PixelLayout::Rgb8),
+        [10, 10, 10]
+    );
+}
+
+#[test]
+fn float_transfer() {
+    let all: Vec<u8> = (0..=255).flat_map(|v| [v, v, v]).collect();
+    let linear = run(&all, 256, 1, PixelLayout::Rgb8, PixelLayout::RgbF32);
+    let values = f32s(&linear);
+    assert_eq!((values[0], values[765]), (0.0, 1.0));
+    // sRGB 188 is close to half intensity
+    assert!((values[188 * 3] - 0.5).abs() < 0.01, "{}", values[188 * 3]);
+    assert_eq!(
+        run(&linear, 256, 1, PixelLayout::RgbF32, PixelLayout::Rgb8),
+        all
+    );
+
+    let identity = ConvertOptions::new().with_transfer(Transfer::Identity);
+    let scaled = convert(
+        &all,
+        256,
+        1,
+        PixelLayout::Rgb8,
+        PixelLayout::RgbF32,
+        &identity,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(f32s(&scaled)[188 * 3], 188.0 / 255.0);
+    let back = convert(
+        &scaled,
+        256,
+        1,
+        PixelLayout::RgbF32,
+        PixelLayout::Rgb8,
+        &identity,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(back, all);
+
+    // Alpha is never transfer-encoded; out-of-range floats clamp
+    let rgba: Vec<u8> = [2.0f32, -1.0, f32::NAN, 0.5]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    assert_eq!(
+        run(&rgba, 1, 1, PixelLayout::RgbaF32, PixelLayout::Rgba8),
+        [255, 0, 0, 128]
+    );
+}
+
+#[test]
+fn every_pair_converts() {
+    let (w, h) = (3, 2);
+    for from in LAYOUTS {
+        let pixels: Vec<u8> = if matches!(
+            from,
+            PixelLayout::GrayF32
+                | PixelLayout::RgbF32
+                | PixelLayout::RgbaF32
+                | PixelLayout::RgbPlanarF32
+        ) {
+            (0..from.bytes_per_pixel() * 6 / 4)
+                .flat_map(|i| (i as f32 / 24.0).to_ne_bytes())
+                .collect()
+        } else {
+            (0..from.bytes_per_pixel() * 6)
+                .map(|i| (i * 29) as u8)
+                .collect()
+        };
+        for to in LAYOUTS {
+            let out = run(&pixels, w, h, from, to);
+            assert_eq!(out.len(), to.bytes_per_pixel() * 6, "{from:?} → {to:?}");
+            let mut buffer = vec![0xAA; out.len() + 3];
+            let options = ConvertOptions::new();
+            convert_into(&pixels, w, h, from, to, &options, &mut buffer, Unstoppable).unwrap();
+            assert_eq!(&buffer[..out.len()], &out[..], "{from:?} → {to:?}");
+            assert_eq!(&buffer[out.len()..], &[0xAA; 3]);
+        }
+        assert_eq!(run(&pixels, w, h, from, from), pixels, "{from:?}");
+    }
+}
+
+#[test]
+fn errors() {
+    let options = ConvertOptions::new();
+    let (rgb, rgba) = (PixelLayout::Rgb8, PixelLayout::Rgba8);
+    let err = convert(&[], 0, 1, rgb, rgba, &options, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    let err = convert(&[0; 5], 2, 1, rgb, rgba, &options, Unstoppable).unwrap_err();
+    assert!(matches!(
+        err.error(),
+        BitmapError::BufferTooSmall {
+            needed: 6,
+            actual: 5
+        }
+    ));
+    let mut out = [0; 7];
+    let err = convert_into(&[0; 6], 2, 1, rgb, rgba, &options, &mut out, Unstoppable).unwrap_err();
+    assert!(matches!(
+        err.error(),
+        BitmapError::BufferTooSmall {
+            needed: 8,
+            actual: 7
+        }
+    ));
+}
+
+#[test]
+fn decode_output_normalizes_heterogeneous_decodes() {
+    let gray = [0u8, 64, 128, 255];
+    let pgm = encode_pgm(&gray, 2, 2, PixelLayout::Gray8, Unstoppable).unwrap();
+    let rgba: Vec<u8> = gray.iter().flat_map(|&v| [v, v, v, 255]).collect();
+    let ff = encode_farbfeld(&rgba, 2, 2, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let ppm_pixels: Vec<u8> = gray.iter().flat_map(|&v| [v, v, v]).collect();
+    let ppm = encode_ppm(&ppm_pixels, 2, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+
+    let decoded = [&pgm, &ff, &ppm].map(|data| decode(data, Unstoppable).unwrap());
+    assert_eq!(
+        decoded.each_ref().map(|d| d.layout),
+        [PixelLayout::Gray8, PixelLayout::Rgba16, PixelLayout::Rgb8]
+    );
+    for d in decoded {
+        let copy = d.to_layout(PixelLayout::Rgba8).unwrap();
+        let normalized = d.convert_to(PixelLayout::Rgba8).unwrap();
+        assert_eq!(normalized.layout, PixelLayout::Rgba8);
+        assert_eq!(normalized.pixels(), &rgba[..]);
+        assert_eq!(copy.pixels(), &rgba[..]);
+    }
+
+    // Same layout: no conversion, still borrowed
+    let same = decode(&ppm, Unstoppable)
+        .unwrap()
+        .convert_to(PixelLayout::Rgb8)
+        .unwrap();
+    assert!(same.is_borrowed());
+    let owned = decode(&ppm, Unstoppable)
+        .unwrap()
+        .to_layout(PixelLayout::Rgb8)
+        .unwrap();
+    assert!(!owned.is_borrowed());
+
+    let identity = ConvertOptions::new().with_transfer(Transfer::Identity);
+    let linear = decode(&pgm, Unstoppable)
+        .unwrap()
+        .convert_to_with(PixelLayout::GrayF32, &identity)
+        .unwrap();
+    assert_eq!(f32s(linear.pixels())[1], 64.0 / 255.0);
+}
+
+#[test]
+fn srgb_linear_slice_helpers() {
+    let srgb: Vec<u8> = (0..=255).collect();
+    let mut linear = vec![0.0f32; 256];
+    srgb_to_linear(&srgb, &mut linear).unwrap();
+    // Same values as the Gray8 → GrayF32 conversion.
+    let via_convert = convert(
+        &srgb,
+        256,
+        1,
+        PixelLayout::Gray8,
+        PixelLayout::GrayF32,
+        &ConvertOptions::new(),
+        Unstoppable,
+    )
+    .unwrap();
+    for (i, (c, &l)) in via_convert.chunks_exact(4).zip(&linear).enumerate() {
+        let c = f32::from_ne_bytes(c.try_into().unwrap());
+        assert!((c - l).abs() < 1e-5, "{i}: {c} vs {l}");
+    }
+    let mut back = vec![0u8; 257];
+    linear_to_srgb(&linear, &mut back).unwrap();
+    assert_eq!(&back[..256], &srgb[..]);
+    assert_eq!(back[256], 0, "past the input is left alone");
+
+    linear_to_srgb(&[-1.0, 2.0, f32::NAN], &mut back[..3]).unwrap();
+    assert_eq!(&back[..2], &[0, 255]);
+
+    let err = srgb_to_linear(&srgb, &mut linear[..255]).unwrap_err();
+    assert!(matches!(
+        err.error(),
+        BitmapError::BufferTooSmall {
+            needed: 256,
+            actual: 255
+        }
+    ));
+    let err = linear_to_srgb(&linear, &mut back[..10]).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+}
@@ -608,6 +608,96 @@ mod bmp_fixtures {
         assert_fixture("rgba32abf.bmp", PixelLayout::Rgba8);
     }
 
+    #[test]
+    fn fixtures_decode_to_linear_f32() {
+        for name in [
+            "pal1.bmp",
+            "pal4rle.bmp",
+            "pal8os2.bmp",
+            "pal8rle.bmp",
+            "rgb16-565.bmp",
+            "rgb24.bmp",
+            "rgb32.bmp",
+            "rgba32abf.bmp",
+        ] {
+            let data = fixture(name);
+            let eight = decode_bmp(&data, Unstoppable).unwrap();
+            let linear = decode_bmp_linear(&data, Unstoppable).unwrap();
+            assert_eq!(linear.layout.channels(), eight.layout.channels(), "{name}");
+            assert_eq!(linear.pixels().len(), eight.pixels().len() * 4, "{name}");
+        }
+    }
+
+    #[test]
+    fn fixture_memory_limit_counts_scratch() {
+        let data = fixture("pal1.bmp");
+        let meta = probe_bmp(&data).unwrap();
+        let output = meta.width as u64 * meta.height as u64 * 3;
+        // The 1-bit rows are unpacked through scanline scratch buffers on
+        // top of the output.
+        let exact = Limits {
+            max_memory_bytes: Some(output),
+            ..Default::default()
+        };
+        let err = decode_bmp_with_limits(&data, &exact, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::LimitExceeded(_)),
+            "{err}"
+        );
+        let roomy = Limits {
+            max_memory_bytes: Some(output + 16 * u64::from(meta.width)),
+            ..Default::default()
+        };
+        decode_bmp_with_limits(&data, &roomy, Unstoppable).unwrap();
+    }
+
+    #[test]
+    fn fixture_probe_classifies_headers() {
+        for (name, variant, compression, bit_count, has_masks) in [
+            (
+                "pal8os2.bmp",
+                BmpHeaderVariant::Core,
+                BmpCompression::Rgb,
+                8,
+                false,
+            ),
+            (
+                "pal8rle.bmp",
+                BmpHeaderVariant::Info,
+                BmpCompression::Rle8,
+                8,
+                false,
+            ),
+            (
+                "pal4rle.bmp",
+                BmpHeaderVariant::Info,
+                BmpCompression::Rle4,
+                4,
+                false,
+            ),
+            (
+                "rgb16-565.bmp",
+                BmpHeaderVariant::Info,
+                BmpCompression::Bitfields,
+                16,
+                true,
+            ),
+            (
+                "rgb24.bmp",
+                BmpHeaderVariant::Info,
+                BmpCompression::Rgb,
+                24,
+                true,
+            ),
+        ] {
+            let meta = probe_bmp(&fixture(name)).unwrap();
+            assert_eq!(meta.header_variant, variant, "{name}: header variant");
+            assert_eq!(meta.compression, compression, "{name}: compression");
+            assert_eq!(meta.bit_count, bit_count, "{name}: bit count");
+            assert_eq!(meta.masks.is_some(), has_masks, "{name}: masks");
+        }
+    }
+
     /// All fixtures must also work through auto-detection.
     #[test]
     fn fixtures_auto_detect() {
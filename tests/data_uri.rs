@@ -0,0 +1,111 @@
+//! `decode_data_uri`: base64 payloads dispatched through `decode()`.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let v = u32::from_be_bytes([0, block[0], block[1], block[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(v >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn ppm() -> (Vec<u8>, Vec<u8>) {
+    // 5×3 so the file length is not a multiple of 3 and needs padding
+    let pixels: Vec<u8> = (0..45).map(|i| (i * 37 % 251) as u8 + 2).collect();
+    let file = encode_ppm(&pixels, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert_ne!(file.len() % 3, 0);
+    (pixels, file)
+}
+
+#[test]
+fn payload_spellings() {
+    let (pixels, file) = ppm();
+    let encoded = base64(&file);
+    let url_safe = encoded.replace('+', "-").replace('/', "_");
+    let wrapped: String = encoded
+        .as_bytes()
+        .chunks(8)
+        .map(|c| format!("{}\r\n  ", std::str::from_utf8(c).unwrap()))
+        .collect();
+    let uris = [
+        format!("data:image/x-portable-pixmap;base64,{encoded}"),
+        format!(
+            "data:image/x-portable-pixmap;base64,{}",
+            encoded.trim_end_matches('=')
+        ),
+        format!("  DATA:image/png;charset=binary;BASE64,{url_safe}\n"),
+        format!("data:;base64,{wrapped}"),
+    ];
+    for uri in &uris {
+        let decoded = decode_data_uri(uri, Unstoppable).unwrap();
+        assert!(!decoded.is_borrowed());
+        assert_eq!((decoded.width, decoded.height), (5, 3));
+        assert_eq!(decoded.pixels(), &pixels[..], "{uri}");
+    }
+}
+
+#[test]
+fn farbfeld_payload() {
+    let pixels: Vec<u8> = (0..32).collect();
+    let file = encode_farbfeld(&pixels, 2, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let uri = format!("data:image/farbfeld;base64,{}", base64(&file));
+    let decoded = decode_data_uri(&uri, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    assert_eq!(decoded.pixels(), &pixels[..]);
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let header: Check = |e| matches!(e, BitmapError::InvalidHeader(..));
+    let data: Check = |e| matches!(e, BitmapError::InvalidData(..));
+    let unsupported: Check = |e| matches!(e, BitmapError::UnsupportedVariant(_));
+    let cases: [(&str, Check); 8] = [
+        ("", header),
+        ("image/png;base64,AAAA", header),
+        ("data:image/png;base64", header),
+        ("data:image/svg+xml,%3Csvg%3E", unsupported),
+        ("data:image/png;base64x,AAAA", unsupported),
+        ("data:image/png;base64,AA*A", data),
+        ("data:image/png;base64,AA=A", data),
+        ("data:image/png;base64,UDYKM", data),
+    ];
+    for (uri, check) in cases {
+        let err = decode_data_uri(uri, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "{uri:?}: {err}");
+    }
+    // Valid base64 of something that is not a known image
+    let err = decode_data_uri("data:image/png;base64,iVBORw0KGgo=", Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnrecognizedFormat));
+}
+
+#[test]
+fn limits_apply_to_the_payload() {
+    let (_, file) = ppm();
+    let uri = format!("data:image/x-portable-pixmap;base64,{}", base64(&file));
+    let limits = Limits {
+        max_file_size: Some(file.len() as u64 - 1),
+        ..Default::default()
+    };
+    let err = decode_data_uri_with_limits(&uri, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    let limits = Limits {
+        max_pixels: Some(14),
+        ..Default::default()
+    };
+    let err = decode_data_uri_with_limits(&uri, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
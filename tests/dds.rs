@@ -0,0 +1,172 @@
+#![cfg(feature = "dds")]
+//! Uncompressed DDS decoding tests using synthetic files.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const RGB: u32 = 0x40;
+const ALPHAPIXELS: u32 = 0x1;
+const LUMINANCE: u32 = 0x2_0000;
+const FOURCC: u32 = 0x4;
+
+/// Build a DDS file: magic, 124-byte header with the given pixel format.
+fn dds(width: u32, height: u32, flags: u32, bits: u32, masks: [u32; 4], pixels: &[u8]) -> Vec<u8> {
+    let mut d = b"DDS ".to_vec();
+    let mut h = [0u32; 31];
+    h[0] = 124;
+    h[1] = 0x1 | 0x2 | 0x4 | 0x1000; // CAPS | HEIGHT | WIDTH | PIXELFORMAT
+    h[2] = height;
+    h[3] = width;
+    h[18] = 32; // pixel format size
+    h[19] = flags;
+    h[21] = bits;
+    h[22..26].copy_from_slice(&masks);
+    h[26] = 0x1000; // DDSCAPS_TEXTURE
+    for v in h {
+        d.extend_from_slice(&v.to_le_bytes());
+    }
+    d.extend_from_slice(pixels);
+    d
+}
+
+#[test]
+fn a8r8g8b8() {
+    let masks = [0xFF_0000, 0xFF00, 0xFF, 0xFF00_0000];
+    let data = dds(
+        2,
+        1,
+        RGB | ALPHAPIXELS,
+        32,
+        masks,
+        &[3, 2, 1, 128, 30, 20, 10, 255],
+    );
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[1, 2, 3, 128, 10, 20, 30, 255]);
+}
+
+#[test]
+fn x8r8g8b8_and_r8g8b8() {
+    let masks = [0xFF_0000, 0xFF00, 0xFF, 0];
+    let data = dds(1, 2, RGB, 32, masks, &[3, 2, 1, 0, 6, 5, 4, 0]);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(decoded.pixels(), &[1, 2, 3, 4, 5, 6]);
+
+    let data = dds(2, 1, RGB, 24, masks, &[3, 2, 1, 6, 5, 4]);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn a1r5g5b5_and_r5g6b5() {
+    let masks = [0x7C00, 0x03E0, 0x001F, 0x8000];
+    let pixels: Vec<u8> = [0xFC00u16, 0x03E0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let data = dds(2, 1, RGB | ALPHAPIXELS, 16, masks, &pixels);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[255, 0, 0, 255, 0, 255, 0, 0]);
+
+    let masks = [0xF800, 0x07E0, 0x001F, 0];
+    let pixels: Vec<u8> = [0xF800u16, 0x07E0, 0x001F]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let data = dds(3, 1, RGB, 16, masks, &pixels);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(decoded.pixels(), &[255, 0, 0, 0, 255, 0, 0, 0, 255]);
+}
+
+#[test]
+fn l8_and_a8l8() {
+    let data = dds(3, 1, LUMINANCE, 8, [0xFF, 0, 0, 0], &[0, 128, 255]);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Gray8);
+    assert_eq!(decoded.pixels(), &[0, 128, 255]);
+
+    let data = dds(
+        1,
+        1,
+        LUMINANCE | ALPHAPIXELS,
+        16,
+        [0xFF, 0, 0, 0xFF00],
+        &[90, 200],
+    );
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[90, 90, 90, 200]);
+}
+
+#[test]
+fn a2r10g10b10_is_rgba16() {
+    let masks = [0x3FF0_0000, 0x000F_FC00, 0x0000_03FF, 0xC000_0000];
+    let v: u32 = (3 << 30) | (1023 << 20) | 0x3FF;
+    let data = dds(1, 1, RGB | ALPHAPIXELS, 32, masks, &v.to_le_bytes());
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    let px: Vec<u16> = decoded
+        .pixels()
+        .chunks_exact(2)
+        .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+        .collect();
+    assert_eq!(px, [65535, 0, 65535, 65535]);
+}
+
+#[test]
+fn dx10_rgba8() {
+    let mut data = dds(1, 1, FOURCC, 0, [0; 4], &[]);
+    data[84..88].copy_from_slice(b"DX10");
+    for v in [28u32, 3, 0, 1, 0] {
+        data.extend_from_slice(&v.to_le_bytes()); // format, dimension, misc, array size, misc2
+    }
+    data.extend_from_slice(&[1, 2, 3, 4]);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[1, 2, 3, 4]);
+}
+
+#[test]
+fn trailing_mips_ignored() {
+    let masks = [0xFF, 0, 0, 0];
+    let data = dds(2, 2, LUMINANCE, 8, masks, &[1, 2, 3, 4, 9]);
+    let decoded = decode_dds(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[1, 2, 3, 4]);
+}
+
+#[test]
+fn errors() {
+    let mut dxt1 = dds(4, 4, FOURCC, 0, [0; 4], &[0; 8]);
+    dxt1[84..88].copy_from_slice(b"DXT1");
+    let err = decode_dds(&dxt1, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+        "{err}"
+    );
+
+    let err = decode_dds(b"DDX ", Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnrecognizedFormat));
+
+    let truncated = dds(4, 4, LUMINANCE, 8, [0xFF, 0, 0, 0], &[0; 15]);
+    let err = decode_dds(&truncated, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+
+    let mut bad = dds(1, 1, LUMINANCE, 8, [0xFF, 0, 0, 0], &[0]);
+    bad[4] = 123;
+    let err = decode_dds(&bad, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+}
+
+#[test]
+fn limits_apply() {
+    let data = dds(4, 4, LUMINANCE, 8, [0xFF, 0, 0, 0], &[0; 16]);
+    let limits = Limits {
+        max_memory_bytes: Some(15),
+        ..Default::default()
+    };
+    let err = decode_dds_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
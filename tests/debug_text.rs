@@ -0,0 +1,143 @@
+//! Debug text grids: exact round trips and the relaxed parser.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn every_layout_round_trips() {
+    let floats: Vec<u8> = [
+        0.0f32,
+        -1.5,
+        1e-7,
+        0.1,
+        3.4e38,
+        f32::INFINITY,
+        0.333_333_34,
+        2.0,
+    ]
+    .iter()
+    .cycle()
+    .take(24)
+    .flat_map(|v| v.to_ne_bytes())
+    .collect();
+    let layouts = [
+        PixelLayout::Gray8,
+        PixelLayout::Gray16,
+        PixelLayout::GrayA16,
+        PixelLayout::Rgb8,
+        PixelLayout::Bgr8,
+        PixelLayout::Rgba8,
+        PixelLayout::Bgra8,
+        PixelLayout::Rgbx8,
+        PixelLayout::Bgrx8,
+        PixelLayout::Rgb16,
+        PixelLayout::Rgba16,
+        PixelLayout::Rgba16Be,
+        PixelLayout::GrayF32,
+        PixelLayout::RgbF32,
+        PixelLayout::RgbaF32,
+        PixelLayout::RgbPlanar8,
+        PixelLayout::RgbaPlanar8,
+        PixelLayout::RgbPlanarF32,
+    ];
+    for layout in layouts {
+        let (w, h) = (3u32, 2u32);
+        let len = layout.bytes_per_pixel() * 6;
+        let pixels: Vec<u8> = if matches!(
+            layout,
+            PixelLayout::GrayF32
+                | PixelLayout::RgbF32
+                | PixelLayout::RgbaF32
+                | PixelLayout::RgbPlanarF32
+        ) {
+            floats[..len].to_vec()
+        } else {
+            (0..len).map(|i| (i * 97 % 256) as u8).collect()
+        };
+        let text = encode_debug_text(&pixels, w, h, layout, Unstoppable).unwrap();
+        assert!(text.starts_with(&format!("# 3×2 {layout:?}\n")), "{text}");
+        let rows: Vec<&str> = text.lines().skip(1).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].len(), rows[1].len(), "{layout:?} columns align");
+        let decoded = decode_debug_text(&text, layout, Unstoppable).unwrap();
+        assert_eq!(
+            (decoded.width, decoded.height, decoded.layout),
+            (w, h, layout)
+        );
+        assert_eq!(decoded.pixels(), &pixels[..], "{layout:?}\n{text}");
+    }
+}
+
+#[test]
+fn sixteen_bit_values_are_numeric() {
+    let native: Vec<u8> = [65535u16, 256, 7]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    let text = encode_debug_text(&native, 1, 1, PixelLayout::Rgb16, Unstoppable).unwrap();
+    assert_eq!(text, "# 1×1 Rgb16\n65535,  256,    7\n");
+    let big: Vec<u8> = [1u16, 2, 3, 65535]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    let text = encode_debug_text(&big, 1, 1, PixelLayout::Rgba16Be, Unstoppable).unwrap();
+    assert_eq!(text, "# 1×1 Rgba16Be\n    1,    2,    3,65535\n");
+}
+
+#[test]
+fn relaxed_parsing() {
+    let text = "
+        # a hand-written fixture
+
+        1 2 3 | 4 5 6
+        7;8;9\t10,11,12
+        # trailing comment
+    ";
+    let decoded = decode_debug_text(text, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert_eq!((decoded.width, decoded.height), (2, 2));
+    assert_eq!(decoded.pixels(), &(1..=12).collect::<Vec<u8>>()[..]);
+
+    let decoded = decode_debug_text("0.5, -2, 1e3", PixelLayout::GrayF32, Unstoppable).unwrap();
+    let values: Vec<f32> = decoded
+        .pixels()
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert_eq!(values, [0.5, -2.0, 1000.0]);
+}
+
+#[test]
+fn errors_and_limits() {
+    let cases = [
+        ("", PixelLayout::Gray8),
+        ("# only a comment\n", PixelLayout::Gray8),
+        ("1 2\n3\n", PixelLayout::Gray8),
+        ("1 2\n", PixelLayout::Rgb8),
+        ("256\n", PixelLayout::Gray8),
+        ("-1\n", PixelLayout::Gray16),
+        ("1.5\n", PixelLayout::Gray8),
+        ("x\n", PixelLayout::GrayF32),
+        ("| ,\n", PixelLayout::Gray8),
+    ];
+    for (text, layout) in cases {
+        let err = decode_debug_text(text, layout, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::InvalidData(..)),
+            "{text:?}: {err}"
+        );
+    }
+
+    let err = encode_debug_text(&[0; 5], 2, 1, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+    let err = encode_debug_text(&[], 0, 1, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+
+    let limits = Limits {
+        max_pixels: Some(5),
+        ..Default::default()
+    };
+    let err =
+        decode_debug_text_with_limits("1 2 3\n4 5 6\n", PixelLayout::Gray8, &limits, Unstoppable)
+            .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
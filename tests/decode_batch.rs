@@ -0,0 +1,63 @@
+//! `decode_batch` decodes many inputs with shared limits.
+
+use enough::StopReason;
+use zenbitmaps::*;
+
+struct AlreadyStopped;
+
+impl Stop for AlreadyStopped {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        Err(StopReason::Cancelled)
+    }
+}
+
+fn gray(width: u32, height: u32, value: u8) -> Vec<u8> {
+    let pixels = vec![value; (width * height) as usize];
+    encode_pgm(&pixels, width, height, PixelLayout::Gray8, Unstoppable).unwrap()
+}
+
+#[test]
+fn outputs_follow_input_order() {
+    let files: Vec<Vec<u8>> = (0..200).map(|i| gray(i % 7 + 1, 3, i as u8)).collect();
+    let mut inputs: Vec<&[u8]> = files.iter().map(Vec::as_slice).collect();
+    inputs.insert(100, b"P5\n2 2\n255\n");
+    let results = decode_batch(&inputs, &Limits::default(), Unstoppable);
+    assert_eq!(results.len(), 201);
+    for (i, result) in results.iter().enumerate() {
+        match i.cmp(&100) {
+            core::cmp::Ordering::Equal => {
+                let err = result.as_ref().unwrap_err();
+                assert_eq!(err.error().kind(), ErrorKind::Malformed, "{err}");
+            }
+            order => {
+                let n = if order.is_lt() { i } else { i - 1 } as u32;
+                let decoded = result.as_ref().unwrap();
+                assert_eq!((decoded.width, decoded.height), (n % 7 + 1, 3));
+                assert!(decoded.pixels().iter().all(|&v| v == n as u8));
+            }
+        }
+    }
+}
+
+#[test]
+fn limits_apply_to_each_input() {
+    let (small, large) = (gray(4, 4, 1), gray(8, 8, 2));
+    let limits = Limits {
+        max_pixels: Some(16),
+        ..Default::default()
+    };
+    let results = decode_batch(&[&small, &large, &small], &limits, Unstoppable);
+    assert!(results[0].is_ok() && results[2].is_ok());
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::LimitExceeded, "{err}");
+}
+
+#[test]
+fn cancellation_fails_every_input() {
+    let file = gray(2, 2, 0);
+    let results = decode_batch(&[&file, &file], &Limits::default(), AlreadyStopped);
+    for result in results {
+        let err = result.unwrap_err();
+        assert!(matches!(err.error(), BitmapError::Cancelled(_)), "{err}");
+    }
+}
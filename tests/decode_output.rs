@@ -0,0 +1,68 @@
+//! `DecodeOutput` accessors shared by every format.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn row_access() {
+    let pixels: Vec<u8> = (0..24).collect();
+    let ppm = encode_ppm(&pixels, 4, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let decoded = decode(&ppm, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.row(0), Some(&pixels[..12]));
+    assert_eq!(decoded.row(1), Some(&pixels[12..]));
+    assert_eq!(decoded.row(2), None);
+    assert_eq!(decoded.row(u32::MAX), None);
+
+    let rows: Vec<&[u8]> = decoded.rows().collect();
+    assert_eq!(rows, [&pixels[..12], &pixels[12..]]);
+    assert_eq!(decoded.rows().len(), 2);
+    assert_eq!(decoded.rows().next_back(), Some(&pixels[12..]));
+}
+
+#[test]
+fn rows_mut_copies_borrowed_pixels() {
+    let pixels: Vec<u8> = (0..12).collect();
+    let pgm = encode_pgm(&pixels, 3, 4, PixelLayout::Gray8, Unstoppable).unwrap();
+    let mut decoded = decode(&pgm, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    for (y, row) in decoded.rows_mut().enumerate() {
+        row.fill(y as u8 * 10);
+    }
+    assert!(!decoded.is_borrowed());
+    assert_eq!(
+        decoded.pixels(),
+        &[0, 0, 0, 10, 10, 10, 20, 20, 20, 30, 30, 30]
+    );
+    // The encoded file is untouched.
+    assert_eq!(decode(&pgm, Unstoppable).unwrap().pixels(), &pixels[..]);
+}
+
+#[test]
+fn rows_follow_bytes_per_pixel() {
+    let pixels: Vec<u8> = (0..48).collect();
+    let ff = encode_farbfeld(&pixels, 3, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let decoded = decode(&ff, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16);
+    assert!(decoded.rows().all(|r| r.len() == 24));
+    assert_eq!(decoded.rows().len(), 2);
+}
+
+#[test]
+fn into_parts_moves_owned_pixels() {
+    let pixels: Vec<u8> = (0..32).collect();
+    let ff = encode_farbfeld(&pixels, 2, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let decoded = decode(&ff, Unstoppable).unwrap();
+    assert!(!decoded.is_borrowed());
+    let ptr = decoded.pixels().as_ptr();
+    let (vec, width, height, layout) = decoded.into_parts();
+    assert_eq!(vec.as_ptr(), ptr);
+    assert_eq!(vec, pixels);
+    assert_eq!((width, height, layout), (2, 2, PixelLayout::Rgba16));
+
+    // Borrowed pixels are copied out of the input.
+    let pgm = encode_pgm(&[7, 8, 9], 3, 1, PixelLayout::Gray8, Unstoppable).unwrap();
+    let decoded = decode(&pgm, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.into_vec(), [7, 8, 9]);
+}
@@ -0,0 +1,114 @@
+//! `DecodeRequest` covers the auto-detecting decode variants in one builder.
+
+use enough::{Stop, StopReason, Unstoppable};
+use zenbitmaps::*;
+
+struct AlreadyStopped;
+
+impl Stop for AlreadyStopped {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        Err(StopReason::Cancelled)
+    }
+}
+
+fn rgb(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 3)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect()
+}
+
+#[test]
+fn plain_request_matches_decode() {
+    let ppm = encode_ppm(&rgb(5, 3), 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let request = DecodeRequest::new(&ppm);
+    assert!(request.limits().is_none());
+    assert!(!request.native_order());
+    let decoded = request.decode().unwrap();
+    let plain = decode(&ppm, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, plain.layout);
+    assert_eq!(decoded.pixels(), plain.pixels());
+    assert!(decoded.is_borrowed());
+}
+
+#[test]
+fn limits_and_stop_apply() {
+    let ppm = encode_ppm(&rgb(32, 32), 32, 32, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let limits = Limits::new().with_max_pixels(100);
+    let err = DecodeRequest::new(&ppm)
+        .with_limits(&limits)
+        .decode()
+        .unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::LimitExceeded);
+
+    let err = DecodeRequest::new(&ppm)
+        .with_stop(AlreadyStopped)
+        .decode()
+        .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::Cancelled(_)));
+}
+
+#[test]
+fn farbfeld_native_order_borrows_big_endian() {
+    let ff = encode_farbfeld(&[9u8; 16], 2, 2, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let decoded = DecodeRequest::new(&ff)
+        .with_native_order(true)
+        .decode()
+        .unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba16Be);
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &ff[16..]);
+}
+
+#[test]
+fn native_order_leaves_pnm_alone() {
+    let ppm = encode_ppm(&rgb(2, 2), 2, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let decoded = DecodeRequest::new(&ppm)
+        .with_native_order(true)
+        .decode()
+        .unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_native_order_and_permissiveness() {
+    let pixels = rgb(16, 16);
+    let bmp = encode_bmp(&pixels, 16, 16, PixelLayout::Rgb8, Unstoppable).unwrap();
+
+    let native = DecodeRequest::new(&bmp)
+        .with_native_order(true)
+        .decode()
+        .unwrap();
+    let expected = decode_bmp_native(&bmp, Unstoppable).unwrap();
+    assert_eq!(native.layout, PixelLayout::Bgr8);
+    assert_eq!(native.pixels(), expected.pixels());
+
+    let truncated = &bmp[..bmp.len() - 100];
+    assert!(DecodeRequest::new(truncated).decode().is_err());
+    let request = DecodeRequest::new(truncated).with_permissiveness(BmpPermissiveness::Permissive);
+    assert_eq!(request.permissiveness(), BmpPermissiveness::Permissive);
+    let recovered = request.clone().decode().unwrap();
+    let expected =
+        decode_bmp_permissive(truncated, BmpPermissiveness::Permissive, Unstoppable).unwrap();
+    assert_eq!(recovered.pixels(), expected.pixels());
+
+    let native = request.with_native_order(true).decode().unwrap();
+    assert_eq!(native.layout, PixelLayout::Bgr8);
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn target_layout_converts() {
+    let ppm = encode_ppm(&[255, 128, 0], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let request = DecodeRequest::new(&ppm).with_target_layout(PixelLayout::Rgba8);
+    assert_eq!(request.target_layout(), Some(PixelLayout::Rgba8));
+    let decoded = request.decode().unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[255, 128, 0, 255]);
+
+    let same = DecodeRequest::new(&ppm)
+        .with_target_layout(PixelLayout::Rgb8)
+        .decode()
+        .unwrap();
+    assert!(same.is_borrowed());
+}
@@ -0,0 +1,160 @@
+//! Row-callback decoding (`decode_rows`, `DecodeRequest::decode_rows`).
+
+use enough::StopReason;
+use zenbitmaps::*;
+
+mod common;
+use common::gradient;
+
+struct AlreadyStopped;
+
+impl Stop for AlreadyStopped {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        Err(StopReason::Cancelled)
+    }
+}
+
+/// Collect the rows of a row-callback decode, checking they arrive in
+/// order, and compare them with a full decode.
+fn assert_rows_match(data: &[u8]) {
+    assert_request_rows_match(DecodeRequest::new(data));
+}
+
+fn assert_request_rows_match(request: DecodeRequest<'_>) {
+    let full = request.clone().decode().unwrap();
+    let mut pixels = Vec::new();
+    let mut next = 0;
+    let (width, height, layout) = request
+        .decode_rows(|y, row| {
+            assert_eq!(y, next);
+            next += 1;
+            pixels.extend_from_slice(row);
+        })
+        .unwrap();
+    assert_eq!(
+        (width, height, layout),
+        (full.width, full.height, full.layout)
+    );
+    assert_eq!(next, height);
+    assert_eq!(pixels, full.packed_pixels().as_ref());
+}
+
+#[test]
+fn binary_pnm_matches_full_decode() {
+    // 37 rows: two full bands and a partial one.
+    let rgb = gradient(7, 37, 3);
+    assert_rows_match(&encode_ppm(&rgb, 7, 37, PixelLayout::Rgb8, Unstoppable).unwrap());
+    assert_rows_match(&encode_pam(&rgb, 7, 37, PixelLayout::Rgb8, Unstoppable).unwrap());
+    let gray16 = gradient(6, 33, 2);
+    assert_rows_match(&encode_pam(&gray16, 6, 33, PixelLayout::Gray16, Unstoppable).unwrap());
+    let floats: Vec<u8> = (0..5 * 20 * 3)
+        .flat_map(|i| (i as f32 / 8.0).to_ne_bytes())
+        .collect();
+    assert_rows_match(&encode_pfm(&floats, 5, 20, PixelLayout::RgbF32, Unstoppable).unwrap());
+
+    let mut p4 = b"P4\n10 18\n".to_vec();
+    p4.extend((0..36u8).map(|i| i.wrapping_mul(37)));
+    assert_rows_match(&p4);
+}
+
+#[test]
+fn ascii_pnm_and_farbfeld_match_full_decode() {
+    assert_rows_match(b"P3\n3 2\n255\n1 2 3 4 5 6 7 8 9\n10 11 12 13 14 15 16 17 18\n");
+    let rgba16 = gradient(3, 19, 8);
+    let ff = encode_farbfeld(&rgba16, 3, 19, PixelLayout::Rgba16, Unstoppable).unwrap();
+    assert_rows_match(&ff);
+    assert_request_rows_match(DecodeRequest::new(&ff).with_native_order(true));
+}
+
+#[cfg(feature = "qoi")]
+#[test]
+fn qoi_matches_full_decode() {
+    let rgba = gradient(9, 21, 4);
+    assert_rows_match(&encode_qoi(&rgba, 9, 21, PixelLayout::Rgba8, Unstoppable).unwrap());
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn converts_to_the_target_layout() {
+    let ppm = encode_ppm(&gradient(4, 18, 3), 4, 18, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert_request_rows_match(DecodeRequest::new(&ppm).with_target_layout(PixelLayout::Rgba16));
+    let ff = encode_farbfeld(&gradient(4, 3, 8), 4, 3, PixelLayout::Rgba16, Unstoppable).unwrap();
+    assert_request_rows_match(DecodeRequest::new(&ff).with_target_layout(PixelLayout::Rgb8));
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn planar_targets_are_rejected() {
+    let ppm = encode_ppm(&gradient(3, 20, 3), 3, 20, PixelLayout::Rgb8, Unstoppable).unwrap();
+    for target in [
+        PixelLayout::RgbPlanar8,
+        PixelLayout::RgbaPlanar8,
+        PixelLayout::RgbPlanarF32,
+    ] {
+        let err = DecodeRequest::new(&ppm)
+            .with_target_layout(target)
+            .decode_rows(|_, _| panic!("no rows for a planar target"))
+            .unwrap_err();
+        assert_eq!(err.error().kind(), ErrorKind::InvalidArgument, "{err}");
+    }
+}
+
+#[test]
+fn limits_and_cancellation_apply() {
+    let ppm = encode_ppm(&gradient(8, 8, 3), 8, 8, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let limits = Limits {
+        max_pixels: Some(16),
+        ..Default::default()
+    };
+    let err = decode_rows_with_limits(&ppm, &limits, Unstoppable, |_, _| {
+        panic!("no rows past a limit")
+    })
+    .unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::LimitExceeded, "{err}");
+
+    let mut rows = 0;
+    let err = decode_rows(&ppm, AlreadyStopped, |_, _| rows += 1).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::Cancelled(_)), "{err}");
+    assert_eq!(rows, 0);
+}
+
+#[cfg(feature = "bmp")]
+mod bmp {
+    use super::*;
+    use crate::common::fixture;
+
+    #[test]
+    fn fixtures_match_full_decode() {
+        for name in [
+            "pal1.bmp",
+            "pal4rle.bmp",
+            "pal8.bmp",
+            "pal8os2.bmp",
+            "pal8rle.bmp",
+            "pal8topdown.bmp",
+            "rgb16-565.bmp",
+            "rgb24.bmp",
+            "rgb32.bmp",
+            "rgba32abf.bmp",
+        ] {
+            assert_rows_match(&fixture(name));
+        }
+    }
+
+    #[test]
+    fn truncated_pixel_data_matches_full_decode() {
+        let mut data = fixture("rgb16-565.bmp");
+        data.truncate(data.len() * 2 / 3);
+        assert_rows_match(&data);
+    }
+
+    #[test]
+    fn permissiveness_applies() {
+        let mut data = fixture("rgb24.bmp");
+        data.truncate(data.len() - 100);
+        assert!(decode_rows(&data, Unstoppable, |_, _| {}).is_err());
+        assert_request_rows_match(
+            DecodeRequest::new(&data).with_permissiveness(BmpPermissiveness::Permissive),
+        );
+    }
+}
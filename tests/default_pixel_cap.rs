@@ -199,7 +199,7 @@ fn pnm_binary_truncated_under_cap_fails_on_eof_not_alloc() {
     assert!(
         matches!(
             r.as_ref().map_err(|e| e.error()),
-            Err(BitmapError::UnexpectedEof)
+            Err(BitmapError::UnexpectedEof(_))
         ),
         "under-cap truncated binary PPM must fail on EOF (data absent), got {r:?}"
     );
@@ -0,0 +1,191 @@
+#![cfg(feature = "dpx")]
+//! DPX decoding tests using synthetic 10-bit RGB files.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const DATA_OFFSET: usize = 2048;
+
+/// Patch a header field in the file's byte order.
+fn put(data: &mut [u8], at: usize, value: u32, size: usize) {
+    let big = &data[..4] == b"SDPX";
+    let bytes = if big {
+        value.to_be_bytes()[4 - size..].to_vec()
+    } else {
+        value.to_le_bytes()[..size].to_vec()
+    };
+    data[at..at + size].copy_from_slice(&bytes);
+}
+
+/// A 10-bit RGB DPX: log transfer, unset reference codes, orientation 0,
+/// `padding` bytes after each row, and `packing` 1 (method A) or 2 (B).
+fn dpx(big: bool, width: u32, packing: u16, padding: u32, pixels: &[[u32; 3]]) -> Vec<u8> {
+    let height = pixels.len() as u32 / width;
+    let mut data = vec![0xFF; DATA_OFFSET];
+    data[..4].copy_from_slice(if big { b"SDPX" } else { b"XPDS" });
+    put(&mut data, 4, DATA_OFFSET as u32, 4);
+    put(&mut data, 768, 0, 2);
+    put(&mut data, 770, 1, 2);
+    put(&mut data, 772, width, 4);
+    put(&mut data, 776, height, 4);
+    data[800..804].copy_from_slice(&[50, 1, 1, 10]);
+    put(&mut data, 804, u32::from(packing), 2);
+    put(&mut data, 806, 0, 2);
+    put(&mut data, 808, DATA_OFFSET as u32, 4);
+    put(&mut data, 812, padding, 4);
+    let shift = if packing == 1 { 2 } else { 0 };
+    for row in pixels.chunks_exact(width as usize) {
+        for &[r, g, b] in row {
+            let word = (r << 20 | g << 10 | b) << shift;
+            data.extend_from_slice(&if big {
+                word.to_be_bytes()
+            } else {
+                word.to_le_bytes()
+            });
+        }
+        data.extend(std::iter::repeat_n(0xAA, padding as usize));
+    }
+    data
+}
+
+fn rgb16(decoded: &DecodeOutput<'_>) -> Vec<u16> {
+    decoded
+        .pixels()
+        .chunks_exact(2)
+        .map(|s| u16::from_ne_bytes([s[0], s[1]]))
+        .collect()
+}
+
+fn rgbf32(decoded: &DecodeOutput<'_>) -> Vec<f32> {
+    decoded
+        .pixels()
+        .chunks_exact(4)
+        .map(|s| f32::from_ne_bytes(s.try_into().unwrap()))
+        .collect()
+}
+
+const PIXELS: [[u32; 3]; 4] = [[0, 1, 2], [1023, 512, 64], [95, 685, 300], [7, 8, 9]];
+
+#[test]
+fn code_values_every_packing_and_byte_order() {
+    let expected: Vec<u16> = PIXELS
+        .iter()
+        .flatten()
+        .map(|&v| (v << 6 | v >> 4) as u16)
+        .collect();
+    for big in [false, true] {
+        for packing in [1, 2] {
+            for padding in [0, 4] {
+                let data = dpx(big, 2, packing, padding, &PIXELS);
+                let decoded = decode_dpx(&data, Unstoppable).unwrap();
+                assert_eq!(decoded.layout, PixelLayout::Rgb16);
+                assert_eq!((decoded.width, decoded.height), (2, 2));
+                assert_eq!(
+                    rgb16(&decoded),
+                    expected,
+                    "big {big}, packing {packing}, padding {padding}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn log_to_linear() {
+    let data = dpx(true, 2, 1, 0, &PIXELS);
+    let decoded = decode_dpx_linear(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbF32);
+    let v = rgbf32(&decoded);
+    // Reference black and white (95 and 685 when unset)
+    assert!(v[6].abs() < 1e-6, "{}", v[6]);
+    assert!((v[7] - 1.0).abs() < 1e-6, "{}", v[7]);
+    // Highlights above white, and a mid code in between
+    assert!(v[3] > 10.0 && v[3] < 15.0, "{}", v[3]);
+    assert!(v[4] > 0.0 && v[4] < 1.0);
+    // Codes below black dip slightly negative
+    assert!(v[0] < 0.0 && v[0] > -0.02, "{}", v[0]);
+
+    // Reference codes from the element header move the curve
+    let mut data = dpx(true, 2, 1, 0, &PIXELS);
+    put(&mut data, 784, 0, 4);
+    put(&mut data, 792, 1023, 4);
+    let v = rgbf32(&decode_dpx_linear(&data, Unstoppable).unwrap());
+    assert!(v[0].abs() < 1e-6 && (v[3] - 1.0).abs() < 1e-6);
+
+    // A linear transfer is plain normalization
+    let mut data = dpx(false, 2, 1, 0, &PIXELS);
+    data[801] = 2;
+    let v = rgbf32(&decode_dpx_linear(&data, Unstoppable).unwrap());
+    assert_eq!(v[3], 1.0);
+    assert_eq!(v[4], 512.0 / 1023.0);
+}
+
+#[test]
+fn orientation_flips() {
+    let code = |px: [u32; 3]| (px[0] << 6 | px[0] >> 4) as u16;
+    for (orientation, order) in [(1, [1, 0, 3, 2]), (2, [2, 3, 0, 1]), (3, [3, 2, 1, 0])] {
+        let mut data = dpx(false, 2, 1, 0, &PIXELS);
+        put(&mut data, 768, orientation, 2);
+        let reds: Vec<u16> = rgb16(&decode_dpx(&data, Unstoppable).unwrap())
+            .into_iter()
+            .step_by(3)
+            .collect();
+        let expected: Vec<u16> = order.iter().map(|&i| code(PIXELS[i])).collect();
+        assert_eq!(reds, expected, "orientation {orientation}");
+    }
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let unsupported: Check = |e| matches!(e, BitmapError::UnsupportedVariant(_));
+    let invalid: Check = |e| matches!(e, BitmapError::InvalidHeader(..));
+    let patched = |at: usize, value: u32, size: usize| {
+        let mut data = dpx(true, 2, 1, 0, &PIXELS);
+        put(&mut data, at, value, size);
+        data
+    };
+    let cases: [(Vec<u8>, Check); 11] = [
+        (patched(800, 51, 1), unsupported), // RGBA descriptor
+        (patched(803, 12, 1), unsupported), // 12-bit
+        (patched(804, 0, 2), unsupported),  // packed
+        (patched(806, 1, 2), unsupported),  // RLE
+        (patched(768, 4, 2), unsupported),  // transposed
+        (patched(770, 0, 2), invalid),
+        (patched(772, 0, 4), invalid),
+        (patched(808, 100, 4), invalid),
+        (b"SDPY".to_vec(), |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (dpx(true, 2, 1, 0, &PIXELS)[..800].to_vec(), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+        (
+            dpx(true, 2, 1, 0, &PIXELS)[..DATA_OFFSET + 15].to_vec(),
+            |e| matches!(e, BitmapError::UnexpectedEof(_)),
+        ),
+    ];
+    for (i, (data, check)) in cases.into_iter().enumerate() {
+        let err = decode_dpx(&data, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let data = dpx(false, 2, 1, 0, &PIXELS);
+    let limits = Limits {
+        max_pixels: Some(3),
+        ..Default::default()
+    };
+    let err = decode_dpx_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    // 24 bytes of Rgb16 fit; 48 of RgbF32 do not
+    let limits = Limits {
+        max_memory_bytes: Some(24),
+        ..Default::default()
+    };
+    assert!(decode_dpx_with_limits(&data, &limits, Unstoppable).is_ok());
+    let err = decode_dpx_linear_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
@@ -0,0 +1,174 @@
+//! `encode()` dispatches to the per-format encoders.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn rgba(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 4)
+        .map(|i| (i * 13 % 251) as u8)
+        .collect()
+}
+
+#[test]
+fn matches_direct_encoders() {
+    let (w, h) = (5, 3);
+    let px = rgba(w, h);
+    let (w, h) = (w as u32, h as u32);
+    let layout = PixelLayout::Rgba8;
+    let options = EncodeOptions::new();
+    let via = |format| encode(&px, w, h, layout, format, &options, Unstoppable).unwrap();
+
+    assert_eq!(
+        via(EncodeFormat::Ppm),
+        encode_ppm(&px, w, h, layout, Unstoppable).unwrap()
+    );
+    assert_eq!(
+        via(EncodeFormat::Pgm),
+        encode_pgm(&px, w, h, layout, Unstoppable).unwrap()
+    );
+    assert_eq!(
+        via(EncodeFormat::Pam),
+        encode_pam(&px, w, h, layout, Unstoppable).unwrap()
+    );
+    assert_eq!(
+        via(EncodeFormat::Farbfeld),
+        encode_farbfeld(&px, w, h, layout, Unstoppable).unwrap()
+    );
+    #[cfg(feature = "bmp")]
+    assert_eq!(
+        via(EncodeFormat::Bmp),
+        encode_bmp(&px, w, h, layout, Unstoppable).unwrap()
+    );
+
+    let float: Vec<u8> = [0.5f32; 9].iter().flat_map(|v| v.to_ne_bytes()).collect();
+    assert_eq!(
+        encode(
+            &float,
+            3,
+            1,
+            PixelLayout::RgbF32,
+            EncodeFormat::Pfm,
+            &options,
+            Unstoppable
+        )
+        .unwrap(),
+        encode_pfm(&float, 3, 1, PixelLayout::RgbF32, Unstoppable).unwrap()
+    );
+}
+
+#[test]
+fn default_output_size_matches_estimate() {
+    let px = rgba(7, 4);
+    let options = EncodeOptions::new();
+    let formats = [
+        EncodeFormat::Pgm,
+        EncodeFormat::Ppm,
+        EncodeFormat::Pam,
+        EncodeFormat::Farbfeld,
+        #[cfg(feature = "bmp")]
+        EncodeFormat::Bmp,
+    ];
+    for format in formats {
+        let file = encode(&px, 7, 4, PixelLayout::Rgba8, format, &options, Unstoppable).unwrap();
+        let size = estimate_encoded_size(7, 4, PixelLayout::Rgba8, format).unwrap();
+        assert_eq!(file.len(), size, "{format:?}");
+    }
+}
+
+#[test]
+fn uses_the_target_formats_options() {
+    let px = [255, 0, 0, 255, 0, 0, 255, 0];
+    let background = PnmEncodeOptions::new().with_background([255, 255, 255]);
+    let options = EncodeOptions::new().with_pnm(background);
+    assert_eq!(options.pnm(), background);
+    let file = encode(
+        &px,
+        2,
+        1,
+        PixelLayout::Rgba8,
+        EncodeFormat::Ppm,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    let direct = encode_ppm_with(&px, 2, 1, PixelLayout::Rgba8, &background, Unstoppable).unwrap();
+    assert_eq!(file, direct);
+    assert_eq!(
+        decode(&file, Unstoppable).unwrap().pixels(),
+        &[255, 0, 0, 255, 255, 255]
+    );
+
+    // PAM keeps alpha, so the PNM background does not apply.
+    let pam = encode(
+        &px,
+        2,
+        1,
+        PixelLayout::Rgba8,
+        EncodeFormat::Pam,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(decode(&pam, Unstoppable).unwrap().pixels(), &px);
+
+    let gray: Vec<u8> = [0.25f32; 16].iter().flat_map(|v| v.to_ne_bytes()).collect();
+    let dither = FarbfeldEncodeOptions::new().with_dither(FarbfeldDither::ErrorDiffusion);
+    let options = EncodeOptions::new().with_farbfeld(dither);
+    assert_eq!(
+        encode(
+            &gray,
+            4,
+            4,
+            PixelLayout::GrayF32,
+            EncodeFormat::Farbfeld,
+            &options,
+            Unstoppable
+        )
+        .unwrap(),
+        encode_farbfeld_with(&gray, 4, 4, PixelLayout::GrayF32, &dither, Unstoppable).unwrap()
+    );
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_options_apply() {
+    let px = rgba(4, 4);
+    let bmp = BmpEncodeOptions::new()
+        .with_bit_depth(16)
+        .with_compression(BmpCompression::Bitfields);
+    let options = EncodeOptions::new().with_bmp(bmp);
+    assert_eq!(options.bmp(), bmp);
+    let file = encode(
+        &px,
+        4,
+        4,
+        PixelLayout::Rgba8,
+        EncodeFormat::Bmp,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(
+        file,
+        encode_bmp_with(&px, 4, 4, PixelLayout::Rgba8, &bmp, Unstoppable).unwrap()
+    );
+    assert_eq!(probe_bmp(&file).unwrap().bit_count, 16);
+}
+
+#[test]
+fn rejects_layouts_the_target_cannot_take() {
+    let err = encode(
+        &[0; 8],
+        2,
+        2,
+        PixelLayout::Gray16,
+        EncodeFormat::Ppm,
+        &EncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+        "{err}"
+    );
+}
@@ -0,0 +1,192 @@
+//! `estimate_encoded_size` / `estimate_bmp_size` against real encodes.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const LAYOUTS: [PixelLayout; 15] = [
+    PixelLayout::Gray8,
+    PixelLayout::Gray16,
+    PixelLayout::Rgb8,
+    PixelLayout::Rgba8,
+    PixelLayout::Bgr8,
+    PixelLayout::Bgra8,
+    PixelLayout::Bgrx8,
+    PixelLayout::Rgbx8,
+    PixelLayout::GrayF32,
+    PixelLayout::RgbF32,
+    PixelLayout::RgbaF32,
+    PixelLayout::Rgba16,
+    PixelLayout::Rgba16Be,
+    PixelLayout::Rgb16,
+    PixelLayout::GrayA16,
+];
+
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x9E37_79B9u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+/// Noise, kept in 0..=1 for float layouts.
+fn pixels(width: u32, height: u32, layout: PixelLayout) -> Vec<u8> {
+    let len = (width * height) as usize * layout.bytes_per_pixel();
+    match layout {
+        PixelLayout::GrayF32 | PixelLayout::RgbF32 | PixelLayout::RgbaF32 => noise(len / 4)
+            .iter()
+            .flat_map(|&v| (f32::from(v) / 255.0).to_ne_bytes())
+            .collect(),
+        _ => noise(len),
+    }
+}
+
+#[test]
+fn pnm_and_farbfeld_are_exact() {
+    type Encoder = fn(&[u8], u32, u32, PixelLayout, Unstoppable) -> Result<Vec<u8>>;
+    let formats: [(EncodeFormat, Encoder); 5] = [
+        (EncodeFormat::Pgm, encode_pgm),
+        (EncodeFormat::Ppm, encode_ppm),
+        (EncodeFormat::Pam, encode_pam),
+        (EncodeFormat::Pfm, encode_pfm),
+        (EncodeFormat::Farbfeld, encode_farbfeld),
+    ];
+    for (format, encode) in formats {
+        for layout in LAYOUTS {
+            for (width, height) in [(1, 1), (7, 3), (10, 100), (123, 45)] {
+                let data = pixels(width, height, layout);
+                let estimate = estimate_encoded_size(width, height, layout, format);
+                match encode(&data, width, height, layout, Unstoppable) {
+                    Ok(out) => assert_eq!(
+                        estimate.unwrap(),
+                        out.len(),
+                        "{format:?} {layout:?} {width}x{height}"
+                    ),
+                    Err(_) => assert!(
+                        matches!(
+                            estimate.unwrap_err().error(),
+                            BitmapError::UnsupportedVariant(_)
+                        ),
+                        "{format:?} {layout:?}"
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn overflow_is_rejected() {
+    let err = estimate_encoded_size(
+        u32::MAX,
+        u32::MAX,
+        PixelLayout::Rgba16,
+        EncodeFormat::Farbfeld,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::DimensionsTooLarge { .. }),
+        "{err}"
+    );
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_is_exact_without_rle_or_quantizing() {
+    let palette = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [9, 9, 9], [1, 2, 3]];
+    let options = [
+        BmpEncodeOptions::new(),
+        BmpEncodeOptions::new().with_alpha(true),
+        BmpEncodeOptions::new().with_top_down(true),
+        BmpEncodeOptions::new().with_header(BmpHeaderVariant::V4),
+        BmpEncodeOptions::new().with_header(BmpHeaderVariant::Core),
+        BmpEncodeOptions::new().with_icc_profile(b"profile bytes"),
+        BmpEncodeOptions::new().with_color_space(BmpColorSpace::Srgb),
+        BmpEncodeOptions::new().with_bit_depth(16),
+        BmpEncodeOptions::new()
+            .with_bit_depth(16)
+            .with_compression(BmpCompression::Bitfields),
+        BmpEncodeOptions::new()
+            .with_bit_depth(32)
+            .with_compression(BmpCompression::Bitfields),
+        BmpEncodeOptions::new().with_palette(&palette),
+        BmpEncodeOptions::new()
+            .with_bit_depth(4)
+            .with_palette(&palette)
+            .with_header(BmpHeaderVariant::Core),
+        BmpEncodeOptions::new()
+            .with_bit_depth(2)
+            .with_palette(&palette[..4]),
+    ];
+    for options in options {
+        for layout in LAYOUTS {
+            for (width, height) in [(1, 1), (5, 3), (33, 17)] {
+                let data = pixels(width, height, layout);
+                let estimate = estimate_bmp_size(width, height, layout, &options);
+                match encode_bmp_with(&data, width, height, layout, &options, Unstoppable) {
+                    // A Core header has no palette-less grayscale; it quantizes.
+                    Ok(out)
+                        if options.header() == BmpHeaderVariant::Core
+                            && layout == PixelLayout::Gray8 =>
+                    {
+                        assert!(estimate.unwrap() >= out.len())
+                    }
+                    Ok(out) => assert_eq!(
+                        estimate.unwrap(),
+                        out.len(),
+                        "{options:?} {layout:?} {width}x{height}"
+                    ),
+                    Err(_) => assert!(estimate.is_err(), "{options:?} {layout:?}"),
+                }
+            }
+        }
+    }
+    let defaults = estimate_encoded_size(9, 9, PixelLayout::Rgb8, EncodeFormat::Bmp).unwrap();
+    let out = encode_bmp(
+        &pixels(9, 9, PixelLayout::Rgb8),
+        9,
+        9,
+        PixelLayout::Rgb8,
+        Unstoppable,
+    );
+    assert_eq!(defaults, out.unwrap().len());
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_bounds_rle_and_quantized_output() {
+    let options = [
+        BmpEncodeOptions::new().with_bit_depth(1),
+        BmpEncodeOptions::new().with_bit_depth(4),
+        BmpEncodeOptions::new().with_bit_depth(8),
+        BmpEncodeOptions::new().with_compression(BmpCompression::Rle8),
+        BmpEncodeOptions::new().with_compression(BmpCompression::Rle4),
+    ];
+    for options in options {
+        for (width, height) in [(1, 1), (2, 1), (7, 5), (300, 4)] {
+            for data in [
+                pixels(width, height, PixelLayout::Rgb8),
+                vec![7; (width * height * 3) as usize],
+            ] {
+                let out = encode_bmp_with(
+                    &data,
+                    width,
+                    height,
+                    PixelLayout::Rgb8,
+                    &options,
+                    Unstoppable,
+                )
+                .unwrap();
+                let estimate = estimate_bmp_size(width, height, PixelLayout::Rgb8, &options);
+                assert!(
+                    estimate.unwrap() >= out.len(),
+                    "{options:?} {width}x{height}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Decode errors report the byte offset and stage where parsing failed.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn bad_pnm_maxval_names_token_and_offset() {
+    let file = b"P5\n2 2\n70000\n";
+    let err = decode(file, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+    assert_eq!(err.error().stage(), Some("PNM maxval token"));
+    assert_eq!(err.error().offset(), Some(7));
+    let msg = err.error().to_string();
+    assert!(msg.ends_with("(PNM maxval token, byte 7)"), "{msg}");
+}
+
+#[test]
+fn garbled_pnm_width_names_token() {
+    let err = decode(b"P6\nxx 2\n255\n", Unstoppable).unwrap_err();
+    assert_eq!(err.error().stage(), Some("PNM width token"));
+    assert_eq!(err.error().offset(), Some(3));
+}
+
+#[test]
+fn truncated_pnm_pixels_report_eof_offset() {
+    let file = b"P5\n4 4\n255\n\x01\x02\x03";
+    let err = decode(file, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+    assert_eq!(err.error().stage(), Some("PNM pixel data"));
+    assert_eq!(err.error().offset(), Some(file.len() as u64));
+}
+
+#[test]
+fn farbfeld_zero_height_points_at_field() {
+    let mut file = b"farbfeld".to_vec();
+    file.extend_from_slice(&1u32.to_be_bytes());
+    file.extend_from_slice(&0u32.to_be_bytes());
+    let err = decode(&file, Unstoppable).unwrap_err();
+    assert_eq!(err.error().stage(), Some("farbfeld header"));
+    assert_eq!(err.error().offset(), Some(12));
+}
+
+#[test]
+fn errors_without_context_render_plainly() {
+    let err = decode(b"P5\n2 2\n255\n", Unstoppable).unwrap_err();
+    let ctx = err.error().context().copied().unwrap_or_default();
+    assert_eq!(
+        err.error().to_string(),
+        format!("unexpected end of input{ctx}")
+    );
+    assert_eq!(ErrorContext::default().to_string(), "");
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn truncated_bmp_masks_name_stage_and_offset() {
+    let options = BmpEncodeOptions::new()
+        .with_bit_depth(16)
+        .with_compression(BmpCompression::Bitfields);
+    let bmp = encode_bmp_with(&[0u8; 12], 2, 2, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    assert_eq!(u32::from_le_bytes(bmp[14..18].try_into().unwrap()), 40);
+    let err = decode_bmp(&bmp[..60], Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+    assert_eq!(err.error().stage(), Some("BMP bitfield masks"));
+    assert_eq!(err.error().offset(), Some(58));
+    let msg = err.error().to_string();
+    assert!(msg.ends_with("(BMP bitfield masks, byte 58)"), "{msg}");
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn truncated_bmp_info_header_names_stage() {
+    let bmp = encode_bmp(&[0u8; 12], 2, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let err = decode_bmp(&bmp[..30], Unstoppable).unwrap_err();
+    assert_eq!(err.error().stage(), Some("BMP info header"), "{err:?}");
+    assert!(err.error().offset().is_some(), "{err:?}");
+}
@@ -0,0 +1,89 @@
+//! `BitmapError::kind()` buckets and `is_recoverable_with_permissive()`.
+
+use enough::{Stop, StopReason, Unstoppable};
+use zenbitmaps::*;
+
+struct AlreadyStopped;
+
+impl Stop for AlreadyStopped {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        Err(StopReason::Cancelled)
+    }
+}
+
+fn ppm() -> Vec<u8> {
+    let pixels: Vec<u8> = (0..32 * 32 * 3).map(|i| (i % 251) as u8).collect();
+    encode_ppm(&pixels, 32, 32, PixelLayout::Rgb8, Unstoppable).unwrap()
+}
+
+#[test]
+fn kinds_bucket_failures() {
+    let file = ppm();
+    let kind = |r: Result<DecodeOutput<'_>>| r.unwrap_err().error().kind();
+
+    assert_eq!(kind(decode(&file[..40], Unstoppable)), ErrorKind::Malformed);
+    assert_eq!(
+        kind(decode(b"P6\n0 1\n255\n", Unstoppable)),
+        ErrorKind::Malformed
+    );
+    assert_eq!(
+        kind(decode(b"not an image", Unstoppable)),
+        ErrorKind::Unsupported
+    );
+    assert_eq!(kind(decode(&file, AlreadyStopped)), ErrorKind::Cancelled);
+
+    let limits = Limits::new().with_max_pixels(100);
+    assert_eq!(
+        kind(decode_with_limits(&file, &limits, Unstoppable)),
+        ErrorKind::LimitExceeded
+    );
+
+    let err = encode_ppm(&[0; 5], 4, 3, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::InvalidArgument, "{err}");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn io_errors_are_io() {
+    let err = BitmapError::from(std::io::Error::other("disk full"));
+    assert_eq!(err.kind(), ErrorKind::Io);
+    assert!(!err.is_recoverable_with_permissive());
+}
+
+#[test]
+fn pnm_errors_are_not_recoverable() {
+    let file = ppm();
+    let err = decode(&file[..40], Unstoppable).unwrap_err();
+    assert!(!err.error().is_recoverable_with_permissive());
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn truncated_bmp_is_recoverable_with_permissive() {
+    let pixels: Vec<u8> = (0..16 * 16 * 3).map(|i| (i % 251) as u8).collect();
+    let bmp = encode_bmp(&pixels, 16, 16, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let truncated = &bmp[..bmp.len() - 100];
+
+    let err = decode_bmp(truncated, Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Malformed);
+    assert!(err.error().is_recoverable_with_permissive(), "{err}");
+
+    let err = decode_bmp_permissive(truncated, BmpPermissiveness::Strict, Unstoppable).unwrap_err();
+    assert!(err.error().is_recoverable_with_permissive(), "{err}");
+
+    decode_bmp_permissive(truncated, BmpPermissiveness::Permissive, Unstoppable).unwrap();
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn permissive_failures_are_not_recoverable() {
+    let err =
+        decode_bmp_permissive(b"BM\0\0", BmpPermissiveness::Permissive, Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Malformed);
+    assert!(!err.error().is_recoverable_with_permissive());
+
+    // The header is parsed permissively first, so a header too short for
+    // any mode reports `false` from a strict decode as well.
+    let err = decode_bmp(b"BM\0\0", Unstoppable).unwrap_err();
+    assert!(!err.error().is_recoverable_with_permissive());
+}
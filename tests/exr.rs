@@ -0,0 +1,188 @@
+#![cfg(feature = "exr")]
+//! OpenEXR decoding tests using synthetic uncompressed scanline files.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const HALF: i32 = 1;
+const FLOAT: i32 = 2;
+
+fn attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for s in [name, kind] {
+        out.extend_from_slice(s.as_bytes());
+        out.push(0);
+    }
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+/// A scanline EXR with data window origin `(3, -2)`, one chunk per line in
+/// `line_order`, and `lines[y][channel]` as each channel's sample bytes.
+fn exr(
+    channels: &[(&str, i32)],
+    compression: u8,
+    width: i32,
+    lines: &[Vec<Vec<u8>>],
+    line_order: &[usize],
+) -> Vec<u8> {
+    let mut out = vec![0x76, 0x2F, 0x31, 0x01, 2, 0, 0, 0];
+    let mut chlist = Vec::new();
+    for &(name, pixel_type) in channels {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&pixel_type.to_le_bytes());
+        chlist.extend_from_slice(&[0; 4]);
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+    attribute(&mut out, "channels", "chlist", &chlist);
+    attribute(&mut out, "compression", "compression", &[compression]);
+    let window: Vec<u8> = [3, -2, 3 + width - 1, -2 + lines.len() as i32 - 1]
+        .iter()
+        .flat_map(|v: &i32| v.to_le_bytes())
+        .collect();
+    attribute(&mut out, "dataWindow", "box2i", &window);
+    attribute(&mut out, "displayWindow", "box2i", &window);
+    attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut out, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    out.push(0);
+
+    let table = out.len();
+    out.resize(table + 8 * lines.len(), 0);
+    for &y in line_order {
+        let offset = out.len() as u64;
+        out[table + 8 * y..table + 8 * y + 8].copy_from_slice(&offset.to_le_bytes());
+        let line = lines[y].concat();
+        out.extend_from_slice(&(y as i32 - 2).to_le_bytes());
+        out.extend_from_slice(&(line.len() as u32).to_le_bytes());
+        out.extend_from_slice(&line);
+    }
+    out
+}
+
+fn halves(values: &[u16]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decoded_f32(decoded: &DecodeOutput<'_>) -> Vec<f32> {
+    decoded
+        .pixels()
+        .chunks_exact(4)
+        .map(|s| f32::from_ne_bytes(s.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn half_rgba_any_line_order() {
+    // Channels are stored alphabetically: A, B, G, R
+    let channels = [("A", HALF), ("B", HALF), ("G", HALF), ("R", HALF)];
+    let lines = vec![
+        // 1.0, 0.5, 2.0 / 0.0, -1.0, 65504
+        vec![
+            halves(&[0x3C00, 0x3800]),
+            halves(&[0x4000, 0x0000]),
+            halves(&[0x3800, 0xBC00]),
+            halves(&[0x3C00, 0x7BFF]),
+        ],
+        // smallest subnormal, negative zero, infinity
+        vec![
+            halves(&[0x3C00, 0x3C00]),
+            halves(&[0x0001, 0x0000]),
+            halves(&[0x8000, 0x0000]),
+            halves(&[0x7C00, 0x0000]),
+        ],
+    ];
+    let expected = [
+        [1.0, 0.5, 2.0, 1.0, 65504.0, -1.0, 0.0, 0.5],
+        [f32::INFINITY, -0.0, 2f32.powi(-24), 1.0, 0.0, 0.0, 0.0, 1.0],
+    ]
+    .concat();
+    for order in [[0, 1], [1, 0]] {
+        let data = exr(&channels, 0, 2, &lines, &order);
+        let decoded = decode_exr(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::RgbaF32);
+        assert_eq!((decoded.width, decoded.height), (2, 2));
+        assert_eq!(decoded_f32(&decoded), expected, "{order:?}");
+    }
+}
+
+#[test]
+fn float_rgb_and_luminance() {
+    let channels = [("B", FLOAT), ("G", HALF), ("R", FLOAT), ("Z", FLOAT)];
+    let lines = vec![vec![
+        floats(&[0.25, 3.5]),
+        halves(&[0x3C00, 0x3400]),
+        floats(&[-0.125, 100.0]),
+        floats(&[9.0, 9.0]),
+    ]];
+    let data = exr(&channels, 0, 2, &lines, &[0]);
+    let decoded = decode_exr(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbF32);
+    assert_eq!(decoded_f32(&decoded), [-0.125, 1.0, 0.25, 100.0, 0.25, 3.5]);
+
+    let lines = vec![vec![floats(&[0.5])], vec![floats(&[4.0])]];
+    let data = exr(&[("Y", FLOAT)], 0, 1, &lines, &[0, 1]);
+    let decoded = decode_exr(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbF32);
+    assert_eq!(decoded_f32(&decoded), [0.5, 0.5, 0.5, 4.0, 4.0, 4.0]);
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let unsupported: Check = |e| matches!(e, BitmapError::UnsupportedVariant(_));
+    let eof: Check = |e| matches!(e, BitmapError::UnexpectedEof(_));
+    let rgb = [("B", HALF), ("G", HALF), ("R", HALF)];
+    let line = || vec![vec![halves(&[0]), halves(&[0]), halves(&[0])]];
+    let good = exr(&rgb, 0, 1, &line(), &[0]);
+    let mut tiled = good.clone();
+    tiled[5] = 0x02;
+    let mut bad_y = good.clone();
+    let chunk = good.len() - 14;
+    bad_y[chunk] = 7;
+    let cases: [(Vec<u8>, Check); 8] = [
+        (exr(&rgb, 3, 1, &line(), &[0]), unsupported), // ZIP
+        (tiled, unsupported),
+        (
+            exr(&[("R", 0), ("G", HALF), ("B", HALF)], 0, 1, &line(), &[0]),
+            unsupported,
+        ),
+        (
+            exr(&[("Z", FLOAT)], 0, 1, &[vec![floats(&[0.0])]], &[0]),
+            unsupported,
+        ),
+        (bad_y, |e| matches!(e, BitmapError::InvalidData(..))),
+        (b"\x76\x2F\x31\x02\x02\0\0\0".to_vec(), |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (good[..40].to_vec(), eof),
+        (good[..good.len() - 1].to_vec(), eof),
+    ];
+    for (i, (data, check)) in cases.into_iter().enumerate() {
+        let err = decode_exr(&data, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let lines = vec![vec![floats(&[0.0; 4])]; 4];
+    let data = exr(&[("Y", FLOAT)], 0, 4, &lines, &[0, 1, 2, 3]);
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_exr_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    let limits = Limits {
+        max_memory_bytes: Some(16 * 12 - 1),
+        ..Default::default()
+    };
+    let err = decode_exr_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
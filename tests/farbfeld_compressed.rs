@@ -0,0 +1,107 @@
+//! gzip/bzip2-wrapped farbfeld, decompressed transparently.
+#![cfg(any(feature = "flate2", feature = "bzip2"))]
+
+use std::io::Write;
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn farbfeld() -> Vec<u8> {
+    let samples: Vec<u8> = (0..6 * 5 * 4u16)
+        .flat_map(|i| i.wrapping_mul(0x9E37).to_ne_bytes())
+        .collect();
+    encode_farbfeld(&samples, 6, 5, PixelLayout::Rgba16, Unstoppable).unwrap()
+}
+
+type Compressor = fn(&[u8]) -> Vec<u8>;
+
+fn compressors() -> Vec<(&'static str, Compressor)> {
+    let mut out: Vec<(&'static str, Compressor)> = Vec::new();
+    #[cfg(feature = "flate2")]
+    out.push(("gzip", |data| {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }));
+    #[cfg(feature = "bzip2")]
+    out.push(("bzip2", |data| {
+        let mut enc = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }));
+    out
+}
+
+#[test]
+fn decodes_like_the_uncompressed_file() {
+    let ff = farbfeld();
+    let plain = decode_farbfeld(&ff, Unstoppable).unwrap();
+    for (name, compress) in compressors() {
+        let packed = compress(&ff);
+        for decoded in [
+            decode_farbfeld(&packed, Unstoppable).unwrap(),
+            decode(&packed, Unstoppable).unwrap(),
+        ] {
+            assert_eq!(decoded.layout, PixelLayout::Rgba16, "{name}");
+            assert_eq!((decoded.width, decoded.height), (6, 5), "{name}");
+            assert_eq!(decoded.pixels(), plain.pixels(), "{name}");
+        }
+    }
+}
+
+#[test]
+fn truncated_and_foreign_payloads_fail() {
+    let ff = farbfeld();
+    for (name, compress) in compressors() {
+        let short = compress(&ff[..ff.len() - 8]);
+        let err = decode_farbfeld(&short, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnexpectedEof(_)),
+            "{name}"
+        );
+
+        let foreign = compress(b"P5\n1 1\n255\n\0 and then some padding");
+        let err = decode(&foreign, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnrecognizedFormat),
+            "{name}: {err}"
+        );
+    }
+}
+
+#[test]
+fn limits_apply_before_decompressing_pixels() {
+    // A header claiming 60000x60000 followed by nothing.
+    let mut bomb = b"farbfeld".to_vec();
+    bomb.extend_from_slice(&60000u32.to_be_bytes());
+    bomb.extend_from_slice(&60000u32.to_be_bytes());
+    let limits = Limits {
+        max_pixels: Some(1 << 20),
+        ..Default::default()
+    };
+    for (name, compress) in compressors() {
+        let err = decode_farbfeld_with_limits(&compress(&bomb), &limits, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::LimitExceeded(_)),
+            "{name}: {err}"
+        );
+    }
+}
+
+#[test]
+fn strict_checks_the_decompressed_length() {
+    let strict = FarbfeldDecodeOptions::new().with_strict(true);
+    let mut padded = farbfeld();
+    padded.push(0);
+    for (name, compress) in compressors() {
+        let exact = compress(&farbfeld());
+        assert!(decode_farbfeld_with_options(&exact, &strict, Unstoppable).is_ok());
+        let packed = compress(&padded);
+        assert!(decode_farbfeld(&packed, Unstoppable).is_ok(), "{name}");
+        let err = decode_farbfeld_with_options(&packed, &strict, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::InvalidData(..)),
+            "{name}: {err}"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Farbfeld encoding from 16-bit layouts other than RGBA16.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const SAMPLES: [u16; 6] = [0x1234, 0xABCD, 0x00FF, 0xFF00, 0x0001, 0xFFFE];
+
+fn ne(samples: &[u16]) -> Vec<u8> {
+    samples.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+fn decoded_rgba(ff: &[u8]) -> Vec<u16> {
+    let decoded = decode_farbfeld(ff, Unstoppable).unwrap();
+    decoded
+        .pixels()
+        .chunks_exact(2)
+        .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+        .collect()
+}
+
+#[test]
+fn rgb16_gets_opaque_alpha() {
+    let ff = encode_farbfeld(&ne(&SAMPLES), 2, 1, PixelLayout::Rgb16, Unstoppable).unwrap();
+    assert_eq!(
+        decoded_rgba(&ff),
+        [
+            0x1234, 0xABCD, 0x00FF, 0xFFFF, 0xFF00, 0x0001, 0xFFFE, 0xFFFF
+        ]
+    );
+}
+
+#[test]
+fn gray16_expands_to_rgb() {
+    let ff = encode_farbfeld(&ne(&SAMPLES[..2]), 1, 2, PixelLayout::Gray16, Unstoppable).unwrap();
+    assert_eq!(
+        decoded_rgba(&ff),
+        [
+            0x1234, 0x1234, 0x1234, 0xFFFF, 0xABCD, 0xABCD, 0xABCD, 0xFFFF
+        ]
+    );
+}
+
+#[test]
+fn graya16_keeps_alpha() {
+    let ff = encode_farbfeld(&ne(&SAMPLES[..4]), 2, 1, PixelLayout::GrayA16, Unstoppable).unwrap();
+    assert_eq!(
+        decoded_rgba(&ff),
+        [
+            0x1234, 0x1234, 0x1234, 0xABCD, 0x00FF, 0x00FF, 0x00FF, 0xFF00
+        ]
+    );
+}
+
+#[test]
+fn short_buffers_and_sizes() {
+    let err = encode_farbfeld(&[0; 11], 1, 2, PixelLayout::Rgb16, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::BufferTooSmall { .. }),
+        "{err}"
+    );
+    for layout in [
+        PixelLayout::Gray16,
+        PixelLayout::GrayA16,
+        PixelLayout::Rgb16,
+    ] {
+        let pixels = vec![0u8; 3 * 5 * layout.bytes_per_pixel()];
+        let ff = encode_farbfeld(&pixels, 3, 5, layout, Unstoppable).unwrap();
+        let size = estimate_encoded_size(3, 5, layout, EncodeFormat::Farbfeld).unwrap();
+        assert_eq!(size, ff.len(), "{layout:?}");
+    }
+}
@@ -0,0 +1,71 @@
+//! Farbfeld encoding from f32 layouts, with and without dithering.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+fn samples(ff: &[u8]) -> Vec<u16> {
+    ff[16..]
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect()
+}
+
+#[test]
+fn clamps_and_rounds() {
+    let rgb = floats(&[0.0, 0.5, 1.0, -1.0, 2.0, f32::NAN]);
+    let ff = encode_farbfeld(&rgb, 2, 1, PixelLayout::RgbF32, Unstoppable).unwrap();
+    assert_eq!(samples(&ff), [0, 32768, 65535, 65535, 0, 65535, 0, 65535]);
+
+    let rgba = floats(&[0.25, 0.25, 0.25, 0.5]);
+    let ff = encode_farbfeld(&rgba, 1, 1, PixelLayout::RgbaF32, Unstoppable).unwrap();
+    assert_eq!(samples(&ff), [16384, 16384, 16384, 32768]);
+
+    let gray = floats(&[0.75]);
+    let ff = encode_farbfeld(&gray, 1, 1, PixelLayout::GrayF32, Unstoppable).unwrap();
+    assert_eq!(samples(&ff), [49151, 49151, 49151, 65535]);
+}
+
+#[test]
+fn dithers_preserve_the_mean() {
+    // A flat field a quarter of a 16-bit step above 1000.
+    let (width, height) = (32u32, 32u32);
+    let level = 1000.25 / 65535.0;
+    let gray = floats(&vec![level; (width * height) as usize]);
+    let mean = |dither| {
+        let options = FarbfeldEncodeOptions::new().with_dither(dither);
+        let ff = encode_farbfeld_with(
+            &gray,
+            width,
+            height,
+            PixelLayout::GrayF32,
+            &options,
+            Unstoppable,
+        )
+        .unwrap();
+        let reds: Vec<u16> = samples(&ff).into_iter().step_by(4).collect();
+        assert!(reds.iter().all(|&v| v == 1000 || v == 1001), "{dither:?}");
+        reds.iter().map(|&v| f64::from(v)).sum::<f64>() / reds.len() as f64
+    };
+    assert_eq!(mean(FarbfeldDither::None), 1000.0);
+    assert!((mean(FarbfeldDither::Ordered) - 1000.25).abs() < 0.01);
+    assert!((mean(FarbfeldDither::ErrorDiffusion) - 1000.25).abs() < 0.02);
+}
+
+#[test]
+fn alpha_and_integer_layouts_are_not_dithered() {
+    let options = FarbfeldEncodeOptions::new().with_dither(FarbfeldDither::Ordered);
+    let rgba = floats(&[0.5; 16]);
+    let ff =
+        encode_farbfeld_with(&rgba, 2, 2, PixelLayout::RgbaF32, &options, Unstoppable).unwrap();
+    assert!(samples(&ff).iter().skip(3).step_by(4).all(|&a| a == 32768));
+
+    let rgb8 = [10u8, 20, 30];
+    assert_eq!(
+        encode_farbfeld_with(&rgb8, 1, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap(),
+        encode_farbfeld(&rgb8, 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap()
+    );
+}
@@ -0,0 +1,121 @@
+//! Farbfeld decoding to RGBA8 and into caller-provided strided buffers.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn farbfeld(width: u32, height: u32) -> Vec<u8> {
+    let samples: Vec<u8> = (0..width * height * 4)
+        .flat_map(|i| (i as u16).wrapping_mul(0x9E37).to_ne_bytes())
+        .collect();
+    encode_farbfeld(&samples, width, height, PixelLayout::Rgba16, Unstoppable).unwrap()
+}
+
+#[test]
+fn strided_rows_match_full_decode() {
+    let ff = farbfeld(5, 4);
+    let full = decode_farbfeld(&ff, Unstoppable).unwrap();
+    let native = decode_farbfeld_native(&ff, Unstoppable).unwrap();
+    for (layout, expected) in [
+        (PixelLayout::Rgba16, full.pixels()),
+        (PixelLayout::Rgba16Be, native.pixels()),
+    ] {
+        let row = 5 * 8;
+        let stride = row + 6;
+        let mut frame = vec![0xEE; stride * 3 + row];
+        let dims = decode_farbfeld_into(&ff, &mut frame, stride, layout, Unstoppable).unwrap();
+        assert_eq!(dims, (5, 4));
+        for (y, expected) in expected.chunks_exact(row).enumerate() {
+            let start = y * stride;
+            assert_eq!(&frame[start..start + row], expected, "{layout:?} row {y}");
+            if y < 3 {
+                assert_eq!(&frame[start + row..start + stride], &[0xEE; 6]);
+            }
+        }
+    }
+}
+
+#[test]
+fn rgba8_rounds_to_nearest() {
+    let samples: [u16; 8] = [0, 128, 129, 385, 386, 65406, 65407, 65535];
+    let bytes: Vec<u8> = samples.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    let ff = encode_farbfeld(&bytes, 2, 1, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let mut out = [0u8; 8];
+    decode_farbfeld_into(&ff, &mut out, 8, PixelLayout::Rgba8, Unstoppable).unwrap();
+    assert_eq!(out, [0, 0, 1, 1, 2, 254, 255, 255]);
+}
+
+#[test]
+fn rejects_bad_buffers_and_layouts() {
+    let ff = farbfeld(3, 2);
+    let mut out = vec![0u8; 3 * 8 * 2];
+    let err = decode_farbfeld_into(&ff, &mut out, 23, PixelLayout::Rgba16, Unstoppable);
+    assert!(matches!(
+        err.unwrap_err().error(),
+        BitmapError::InvalidData(..)
+    ));
+    let err = decode_farbfeld_into(&ff, &mut out[..47], 24, PixelLayout::Rgba16, Unstoppable);
+    assert!(matches!(
+        err.unwrap_err().error(),
+        BitmapError::BufferTooSmall {
+            needed: 48,
+            actual: 47
+        }
+    ));
+    let err = decode_farbfeld_into(&ff, &mut out, 9, PixelLayout::Rgb8, Unstoppable);
+    assert!(matches!(
+        err.unwrap_err().error(),
+        BitmapError::UnsupportedVariant(_)
+    ));
+    let err = decode_farbfeld_into(
+        &ff[..ff.len() - 1],
+        &mut out,
+        24,
+        PixelLayout::Rgba16,
+        Unstoppable,
+    );
+    assert!(matches!(
+        err.unwrap_err().error(),
+        BitmapError::UnexpectedEof(_)
+    ));
+    let limits = Limits {
+        max_pixels: Some(5),
+        ..Default::default()
+    };
+    let err = decode_farbfeld_into_with_limits(
+        &ff,
+        &mut out,
+        24,
+        PixelLayout::Rgba16,
+        &limits,
+        Unstoppable,
+    );
+    assert!(matches!(
+        err.unwrap_err().error(),
+        BitmapError::LimitExceeded(_)
+    ));
+}
+
+#[test]
+fn rgba8_decode_matches_into() {
+    let ff = farbfeld(7, 3);
+    let decoded = decode_farbfeld_rgba8(&ff, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!((decoded.width, decoded.height), (7, 3));
+    let mut out = vec![0u8; 7 * 3 * 4];
+    decode_farbfeld_into(&ff, &mut out, 28, PixelLayout::Rgba8, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), out);
+
+    // 8-bit input survives the 16-bit round trip exactly.
+    let rgba: Vec<u8> = (0..=255).collect();
+    let ff = encode_farbfeld(&rgba, 8, 8, PixelLayout::Rgba8, Unstoppable).unwrap();
+    assert_eq!(
+        decode_farbfeld_rgba8(&ff, Unstoppable).unwrap().pixels(),
+        rgba
+    );
+    let limits = Limits {
+        max_memory_bytes: Some(255),
+        ..Default::default()
+    };
+    let err = decode_farbfeld_rgba8_with_limits(&ff, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
@@ -0,0 +1,85 @@
+//! Zero-copy farbfeld decode in stored (big-endian) byte order.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn samples(width: usize, height: usize) -> Vec<u16> {
+    (0..width * height * 4)
+        .map(|i| (i as u16).wrapping_mul(0x9E37) ^ 0x1234)
+        .collect()
+}
+
+fn native(samples: &[u16]) -> Vec<u8> {
+    samples.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+#[test]
+fn borrows_the_input() {
+    let values = samples(5, 3);
+    let ff = encode_farbfeld(&native(&values), 5, 3, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let decoded = decode_farbfeld_native(&ff, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!((decoded.width, decoded.height), (5, 3));
+    assert_eq!(decoded.layout, PixelLayout::Rgba16Be);
+    assert_eq!(decoded.pixels().as_ptr(), ff[16..].as_ptr());
+    let be: Vec<u16> = decoded
+        .pixels()
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+    assert_eq!(be, values);
+}
+
+#[test]
+fn reencodes_byte_for_byte() {
+    let ff = encode_farbfeld(
+        &native(&samples(4, 4)),
+        4,
+        4,
+        PixelLayout::Rgba16,
+        Unstoppable,
+    )
+    .unwrap();
+    let decoded = decode_farbfeld_native(&ff, Unstoppable).unwrap();
+    let again = encode_farbfeld(
+        decoded.pixels(),
+        decoded.width,
+        decoded.height,
+        decoded.layout,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(again, ff);
+    assert_eq!(
+        estimate_encoded_size(4, 4, PixelLayout::Rgba16Be, EncodeFormat::Farbfeld).unwrap(),
+        ff.len()
+    );
+}
+
+#[test]
+fn trailing_bytes_are_excluded() {
+    let mut ff = encode_farbfeld(&[7u8; 8], 1, 1, PixelLayout::Rgba16, Unstoppable).unwrap();
+    ff.extend_from_slice(b"junk");
+    let decoded = decode_farbfeld_native(&ff, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels().len(), 8);
+}
+
+#[test]
+fn truncated_and_limited_inputs_fail() {
+    let ff = encode_farbfeld(&[0u8; 32], 2, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let err = decode_farbfeld_native(&ff[..ff.len() - 1], Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+
+    let limits = Limits {
+        max_width: Some(1),
+        ..Default::default()
+    };
+    let err = decode_farbfeld_native_with_limits(&ff, &limits, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+}
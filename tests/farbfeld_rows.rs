@@ -0,0 +1,69 @@
+//! Row-at-a-time farbfeld decoding.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn image(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 4)
+        .flat_map(|i| ((i as u16).wrapping_mul(0x9E37) ^ 0x1234).to_ne_bytes())
+        .collect()
+}
+
+#[test]
+fn rows_match_full_decode() {
+    for (width, height) in [(1, 1), (3, 2), (7, 17), (40, 33)] {
+        let ff = encode_farbfeld(
+            &image(width, height),
+            width as u32,
+            height as u32,
+            PixelLayout::Rgba16,
+            Unstoppable,
+        )
+        .unwrap();
+        let full = decode_farbfeld(&ff, Unstoppable).unwrap();
+        let mut rows = decode_farbfeld_rows(&ff, Unstoppable).unwrap();
+        assert_eq!((rows.width(), rows.height()), (full.width, full.height));
+        assert_eq!(rows.layout(), PixelLayout::Rgba16);
+        let mut joined = Vec::new();
+        while let Some(row) = rows.next_row() {
+            joined.extend_from_slice(row.unwrap());
+        }
+        assert_eq!(rows.rows_remaining(), 0);
+        assert_eq!(joined, full.pixels(), "{width}x{height}");
+    }
+}
+
+#[test]
+fn truncated_input_fails_at_the_missing_row() {
+    let ff = encode_farbfeld(&image(2, 3), 2, 3, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let mut rows = decode_farbfeld_rows(&ff[..ff.len() - 1], Unstoppable).unwrap();
+    assert!(rows.next_row().unwrap().is_ok());
+    assert!(rows.next_row().unwrap().is_ok());
+    let err = rows.next_row().unwrap().unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+    assert!(rows.next_row().is_none());
+}
+
+#[test]
+fn limits_apply() {
+    let ff = encode_farbfeld(&image(4, 2), 4, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let limits = Limits {
+        max_height: Some(1),
+        ..Default::default()
+    };
+    let err = decode_farbfeld_rows_with_limits(&ff, &limits, Unstoppable)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+    let limits = Limits {
+        max_memory_bytes: Some(32),
+        ..Default::default()
+    };
+    assert!(decode_farbfeld_rows_with_limits(&ff, &limits, Unstoppable).is_ok());
+}
@@ -0,0 +1,33 @@
+//! The SIMD farbfeld endian swap agrees with the scalar path at every tier.
+#![cfg(feature = "simd")]
+
+use archmage::testing::{CompileTimePolicy, for_each_token_permutation};
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn every_tier_matches_reference() {
+    // Widths chosen so rows end mid-vector for both 16- and 32-byte lanes.
+    for (width, height) in [(1, 1), (3, 5), (4, 4), (5, 3), (67, 9)] {
+        let samples: Vec<u16> = (0..width * height * 4)
+            .map(|i| (i as u16).wrapping_mul(0x9E37) ^ 0x5A3C)
+            .collect();
+        let mut ff = b"farbfeld".to_vec();
+        ff.extend_from_slice(&(width as u32).to_be_bytes());
+        ff.extend_from_slice(&(height as u32).to_be_bytes());
+        ff.extend(samples.iter().flat_map(|v| v.to_be_bytes()));
+        let expected: Vec<u8> = samples.iter().flat_map(|v| v.to_ne_bytes()).collect();
+
+        let report = for_each_token_permutation(CompileTimePolicy::Warn, |perm| {
+            let decoded = decode_farbfeld(&ff, Unstoppable).unwrap();
+            assert_eq!(decoded.pixels(), expected, "{width}x{height} {perm}");
+            let mut rows = decode_farbfeld_rows(&ff, Unstoppable).unwrap();
+            let mut joined = Vec::new();
+            while let Some(row) = rows.next_row() {
+                joined.extend_from_slice(row.unwrap());
+            }
+            assert_eq!(joined, expected, "{width}x{height} {perm}");
+        });
+        eprintln!("{report}");
+    }
+}
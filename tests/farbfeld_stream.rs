@@ -0,0 +1,93 @@
+//! Concatenated farbfeld frames via `decode_farbfeld_stream`.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn frame(width: u32, height: u32, value: u8) -> Vec<u8> {
+    let rgba = vec![value; (width * height * 4) as usize];
+    encode_farbfeld(&rgba, width, height, PixelLayout::Rgba8, Unstoppable).unwrap()
+}
+
+#[test]
+fn yields_each_frame_in_order() {
+    let frames = [frame(2, 2, 10), frame(3, 1, 20), frame(2, 2, 30)];
+    let data = frames.concat();
+    let mut stream = decode_farbfeld_stream(&data, Unstoppable).unwrap();
+    let mut offset = 0;
+    for (bytes, value) in frames.iter().zip([10u8, 20, 30]) {
+        assert_eq!(stream.offset(), offset);
+        let decoded = stream.next().unwrap().unwrap();
+        assert_eq!(decoded.layout, PixelLayout::Rgba16);
+        assert_eq!(
+            decoded.pixels(),
+            decode_farbfeld(bytes, Unstoppable).unwrap().pixels(),
+            "frame with value {value}"
+        );
+        offset += bytes.len();
+    }
+    assert_eq!(stream.offset(), data.len());
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn single_frame_is_a_stream_of_one() {
+    let data = frame(4, 4, 7);
+    assert_eq!(
+        decode_farbfeld_stream(&data, Unstoppable).unwrap().count(),
+        1
+    );
+}
+
+#[test]
+fn damaged_tail_ends_the_stream() {
+    let mut data = [frame(2, 2, 1), frame(2, 2, 2)].concat();
+    data.truncate(data.len() - 3);
+    let results: Vec<_> = decode_farbfeld_stream(&data, Unstoppable)
+        .unwrap()
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    let err = results[1].as_ref().unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+
+    let mut data = frame(2, 2, 1);
+    data.extend_from_slice(b"garbage!garbage!");
+    let mut stream = decode_farbfeld_stream(&data, Unstoppable).unwrap();
+    assert!(stream.next().unwrap().is_ok());
+    let err = stream.next().unwrap().unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnrecognizedFormat),
+        "{err}"
+    );
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn rejects_non_farbfeld_up_front() {
+    let err = decode_farbfeld_stream(b"P5\n1 1\n255\n\0 and some padding", Unstoppable)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(err.error(), BitmapError::UnrecognizedFormat),
+        "{err}"
+    );
+}
+
+#[test]
+fn limits_apply_per_frame() {
+    let data = [frame(2, 2, 1), frame(8, 1, 2)].concat();
+    let limits = Limits {
+        max_width: Some(4),
+        ..Default::default()
+    };
+    let mut stream = decode_farbfeld_stream_with_limits(&data, &limits, Unstoppable).unwrap();
+    assert!(stream.next().unwrap().is_ok());
+    let err = stream.next().unwrap().unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+}
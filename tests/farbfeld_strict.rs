@@ -0,0 +1,63 @@
+//! Strict farbfeld payload-length validation.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn sample() -> Vec<u8> {
+    let rgba: Vec<u8> = (0..3 * 2 * 4).map(|i| (i * 11) as u8).collect();
+    encode_farbfeld(&rgba, 3, 2, PixelLayout::Rgba8, Unstoppable).unwrap()
+}
+
+fn strict() -> FarbfeldDecodeOptions {
+    FarbfeldDecodeOptions::new().with_strict(true)
+}
+
+#[test]
+fn exact_length_is_accepted() {
+    let data = sample();
+    assert_eq!(data.len(), 16 + 3 * 2 * 8);
+    let decoded = decode_farbfeld_with_options(&data, &strict(), Unstoppable).unwrap();
+    assert_eq!(
+        decoded.pixels(),
+        decode_farbfeld(&data, Unstoppable).unwrap().pixels()
+    );
+}
+
+#[test]
+fn trailing_bytes_are_rejected_only_when_strict() {
+    let mut data = sample();
+    data.extend_from_slice(b"junk");
+    assert!(!FarbfeldDecodeOptions::new().strict());
+    let lenient = FarbfeldDecodeOptions::new();
+    assert!(decode_farbfeld_with_options(&data, &lenient, Unstoppable).is_ok());
+    let err = decode_farbfeld_with_options(&data, &strict(), Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    assert!(err.to_string().contains("4 trailing bytes"), "{err}");
+}
+
+#[test]
+fn short_data_is_eof() {
+    let mut data = sample();
+    data.pop();
+    for options in [FarbfeldDecodeOptions::new(), strict()] {
+        let err = decode_farbfeld_with_options(&data, &options, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnexpectedEof(_)),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let limits = Limits {
+        max_width: Some(2),
+        ..Default::default()
+    };
+    let err = decode_farbfeld_with_options_and_limits(&sample(), &strict(), &limits, Unstoppable)
+        .unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+}
@@ -0,0 +1,158 @@
+//! `decode_file` / `encode_file` and `FileData` (read or memory-mapped).
+#![cfg(feature = "std")]
+
+use std::path::PathBuf;
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("zenbitmaps-{}-{name}", std::process::id()))
+}
+
+fn rgb(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 3)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect()
+}
+
+#[test]
+fn encode_then_decode_file() {
+    let path = temp_path("round-trip.ppm");
+    let pixels = rgb(6, 4);
+    let options = EncodeOptions::new();
+    encode_file(
+        &path,
+        &pixels,
+        6,
+        4,
+        PixelLayout::Rgb8,
+        EncodeFormat::Ppm,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        encode_ppm(&pixels, 6, 4, PixelLayout::Rgb8, Unstoppable).unwrap()
+    );
+
+    let decoded = decode_file(&path, Unstoppable).unwrap();
+    assert_eq!((decoded.width, decoded.height), (6, 4));
+    assert_eq!(decoded.pixels(), &pixels[..]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn file_data_keeps_pnm_zero_copy() {
+    let path = temp_path("zero-copy.ppm");
+    let pixels = rgb(3, 3);
+    std::fs::write(
+        &path,
+        encode_ppm(&pixels, 3, 3, PixelLayout::Rgb8, Unstoppable).unwrap(),
+    )
+    .unwrap();
+
+    let file = FileData::open(&path).unwrap();
+    assert!(!file.is_mapped());
+    let decoded = decode(&file, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &pixels[..]);
+
+    let read = FileData::read(&path).unwrap();
+    assert!(!read.is_mapped());
+    assert_eq!(read.bytes(), file.bytes());
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn limits_reject_large_files_before_reading() {
+    let path = temp_path("limits.ppm");
+    std::fs::write(
+        &path,
+        encode_ppm(&rgb(8, 8), 8, 8, PixelLayout::Rgb8, Unstoppable).unwrap(),
+    )
+    .unwrap();
+    let limits = Limits::new().with_max_file_size(64);
+    let err = decode_file_with_limits(&path, &limits, Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::LimitExceeded);
+    let limits = Limits::new().with_max_file_size(1 << 20);
+    decode_file_with_limits(&path, &limits, Unstoppable).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn io_failures_are_io_errors() {
+    let missing = temp_path("missing.ppm");
+    let err = decode_file(&missing, Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Io);
+    assert!(FileData::open(&missing).is_err());
+
+    let err = encode_file(
+        temp_path("no-such-dir").join("out.ppm"),
+        &[0; 3],
+        1,
+        1,
+        PixelLayout::Rgb8,
+        EncodeFormat::Ppm,
+        &EncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Io);
+}
+
+#[test]
+fn failed_encodes_write_nothing() {
+    let path = temp_path("never-written.ppm");
+    let err = encode_file(
+        &path,
+        &[0; 8],
+        2,
+        2,
+        PixelLayout::Gray16,
+        EncodeFormat::Ppm,
+        &EncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Unsupported);
+    assert!(!path.exists());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn empty_files_map_to_no_bytes() {
+    let path = temp_path("empty");
+    std::fs::write(&path, b"").unwrap();
+    // SAFETY: the test owns the file and does not modify it while mapped.
+    let file = unsafe { FileData::map(&path) }.unwrap();
+    assert!(file.is_empty());
+    assert_eq!(
+        decode(&file, Unstoppable).unwrap_err().error().kind(),
+        ErrorKind::Unsupported
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_files_stay_zero_copy() {
+    let path = temp_path("mapped.ppm");
+    let pixels = rgb(4, 2);
+    std::fs::write(
+        &path,
+        encode_ppm(&pixels, 4, 2, PixelLayout::Rgb8, Unstoppable).unwrap(),
+    )
+    .unwrap();
+    // SAFETY: the test owns the file and does not modify it while mapped.
+    let file = unsafe { FileData::map(&path) }.unwrap();
+    assert!(file.is_mapped());
+    let decoded = decode(&file, Unstoppable).unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &pixels[..]);
+    drop(decoded);
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,125 @@
+//! `Limits::max_file_size` is enforced before any header parsing.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn capped(max: usize) -> Limits {
+    Limits {
+        max_file_size: Some(max as u64),
+        ..Default::default()
+    }
+}
+
+fn assert_rejected<T>(result: Result<T>) {
+    let err = result.err().expect("oversized input was accepted");
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(msg) if msg.contains("file size")),
+        "{err}"
+    );
+}
+
+fn rgba() -> Vec<u8> {
+    (0..4 * 3 * 4).map(|i| (i * 9) as u8).collect()
+}
+
+#[test]
+fn exact_size_is_allowed() {
+    let ppm = encode_ppm(&[1, 2, 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert!(decode_with_limits(&ppm, &capped(ppm.len()), Unstoppable).is_ok());
+    assert_rejected(decode_with_limits(
+        &ppm,
+        &capped(ppm.len() - 1),
+        Unstoppable,
+    ));
+    assert!(decode_with_limits(&ppm, &Limits::default(), Unstoppable).is_ok());
+}
+
+#[test]
+fn checked_before_the_header() {
+    // Neither garbage nor a truncated header gets as far as format errors.
+    let limits = capped(4);
+    assert_rejected(decode_with_limits(b"not an image", &limits, Unstoppable));
+    assert_rejected(decode_farbfeld_with_limits(
+        b"farbfeld",
+        &limits,
+        Unstoppable,
+    ));
+    assert_rejected(decode_region_with_limits(
+        b"P6\n1 1\n",
+        0,
+        0,
+        1,
+        1,
+        &limits,
+        Unstoppable,
+    ));
+}
+
+#[test]
+fn every_format_entry_point() {
+    let rgba = rgba();
+    let ff = encode_farbfeld(&rgba, 4, 3, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let limits = capped(ff.len() - 1);
+    assert_rejected(decode_with_limits(&ff, &limits, Unstoppable));
+    assert_rejected(decode_farbfeld_with_limits(&ff, &limits, Unstoppable));
+    assert_rejected(decode_farbfeld_native_with_limits(
+        &ff,
+        &limits,
+        Unstoppable,
+    ));
+    assert_rejected(decode_farbfeld_rgba8_with_limits(&ff, &limits, Unstoppable));
+    assert_rejected(decode_farbfeld_rows_with_limits(&ff, &limits, Unstoppable));
+    let mut out = vec![0u8; 4 * 3 * 8];
+    assert_rejected(decode_farbfeld_into_with_limits(
+        &ff,
+        &mut out,
+        32,
+        PixelLayout::Rgba16,
+        &limits,
+        Unstoppable,
+    ));
+
+    let pam = encode_pam(&rgba, 4, 3, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let limits = capped(pam.len() - 1);
+    assert_rejected(decode_with_limits(&pam, &limits, Unstoppable));
+
+    #[cfg(feature = "bmp")]
+    {
+        let bmp = encode_bmp_rgba(&rgba, 4, 3, PixelLayout::Rgba8, Unstoppable).unwrap();
+        let limits = capped(bmp.len() - 1);
+        assert_rejected(decode_bmp_with_limits(&bmp, &limits, Unstoppable));
+        assert_rejected(decode_dib_with_limits(&bmp[14..], &capped(8), Unstoppable));
+        assert_rejected(decode_bmp_array_with_limits(&bmp, &limits, Unstoppable));
+    }
+    #[cfg(feature = "qoi")]
+    {
+        let qoi = encode_qoi(&rgba, 4, 3, PixelLayout::Rgba8, Unstoppable).unwrap();
+        assert_rejected(decode_qoi_with_limits(
+            &qoi,
+            &capped(qoi.len() - 1),
+            Unstoppable,
+        ));
+    }
+    #[cfg(feature = "tga")]
+    {
+        let tga = encode_tga(&rgba, 4, 3, PixelLayout::Rgba8, Unstoppable).unwrap();
+        assert_rejected(decode_tga_with_limits(
+            &tga,
+            &capped(tga.len() - 1),
+            Unstoppable,
+        ));
+    }
+    #[cfg(feature = "hdr")]
+    {
+        let rgb: Vec<u8> = [0.5f32; 4 * 3 * 3]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let hdr = encode_hdr(&rgb, 4, 3, PixelLayout::RgbF32, Unstoppable).unwrap();
+        assert_rejected(decode_hdr_with_limits(
+            &hdr,
+            &capped(hdr.len() - 1),
+            Unstoppable,
+        ));
+    }
+}
@@ -8,11 +8,67 @@ fn fuzz_regression() {
     RegressionSuite::new("fuzz/regression")
         .target("decode", |input| {
             let _ = zenbitmaps::decode(input, enough::Unstoppable);
+            let _ = zenbitmaps::decode_region(input, 1, 1, 2, 2, enough::Unstoppable);
             #[cfg(feature = "bmp")]
             {
                 let _ = zenbitmaps::decode_bmp(input, enough::Unstoppable);
+                let _ = zenbitmaps::decode_dib(input, enough::Unstoppable);
+                let permissive = zenbitmaps::BmpDecodeOptions::new()
+                    .with_permissiveness(zenbitmaps::BmpPermissiveness::Permissive);
+                let _ = zenbitmaps::decode_bmp_with_report(input, &permissive, enough::Unstoppable);
+                let preview = permissive.with_downscale(zenbitmaps::BmpDownscale::Quarter);
+                let _ = zenbitmaps::decode_bmp_with_report(input, &preview, enough::Unstoppable);
+                if let Ok(frames) = zenbitmaps::decode_bmp_array(input, enough::Unstoppable) {
+                    frames.for_each(drop);
+                }
             }
             let _ = zenbitmaps::decode_farbfeld(input, enough::Unstoppable);
+            #[cfg(feature = "ico")]
+            {
+                let _ = zenbitmaps::decode_ico(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "pcx")]
+            {
+                let _ = zenbitmaps::decode_pcx(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "xpm")]
+            {
+                let _ = zenbitmaps::decode_xpm(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "dds")]
+            {
+                let _ = zenbitmaps::decode_dds(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "xwd")]
+            {
+                let _ = zenbitmaps::decode_xwd(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "tiff")]
+            {
+                let _ = zenbitmaps::decode_tiff(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "dpx")]
+            {
+                let _ = zenbitmaps::decode_dpx_linear(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "pgx")]
+            {
+                let _ = zenbitmaps::decode_pgx(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "npy")]
+            {
+                let _ = zenbitmaps::decode_npy(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "exr")]
+            {
+                let _ = zenbitmaps::decode_exr(input, enough::Unstoppable);
+            }
+            #[cfg(feature = "y4m")]
+            if let Ok(frames) = zenbitmaps::decode_y4m_frames(input, enough::Unstoppable) {
+                for frame in frames.flatten() {
+                    let _ = frame.to_rgb(zenbitmaps::YuvMatrix::Bt709, enough::Unstoppable);
+                }
+            }
         })
         .target("roundtrip", |input| {
             // Mirror fuzz/fuzz_targets/fuzz_roundtrip.rs exactly, INCLUDING its
@@ -42,7 +98,10 @@ fn fuzz_regression() {
             }
             #[cfg(feature = "bmp")]
             {
-                use zenbitmaps::{PixelLayout, decode_bmp, encode_bmp, encode_bmp_rgba};
+                use zenbitmaps::{
+                    BmpAlphaPolicy, BmpDecodeOptions, PixelLayout, decode_bmp,
+                    decode_bmp_with_options, encode_bmp, encode_bmp_rgba,
+                };
                 if let Ok(decoded) = decode_bmp(input, enough::Unstoppable) {
                     let reencoded = if decoded.layout == PixelLayout::Rgba8 {
                         encode_bmp_rgba(
@@ -62,8 +121,12 @@ fn fuzz_regression() {
                         )
                     };
                     if let Ok(reencoded) = reencoded {
-                        let decoded2 = decode_bmp(&reencoded, enough::Unstoppable)
-                            .expect("re-encoded BMP must decode");
+                        // Our encoder writes real alpha into the reserved byte.
+                        let trust =
+                            BmpDecodeOptions::new().with_alpha_policy(BmpAlphaPolicy::Trust);
+                        let decoded2 =
+                            decode_bmp_with_options(&reencoded, &trust, enough::Unstoppable)
+                                .expect("re-encoded BMP must decode");
                         assert_eq!(
                             decoded.pixels(),
                             decoded2.pixels(),
@@ -73,6 +136,48 @@ fn fuzz_regression() {
                         assert_eq!(decoded.height, decoded2.height);
                     }
                 }
+                if let Some((&w, indices)) = input.split_first() {
+                    let width = u32::from(w % 32) + 1;
+                    let height = (indices.len() as u32 / width).min(64);
+                    let palette: Vec<[u8; 3]> =
+                        (0..=255u8).map(|i| [i, !i, i.rotate_left(3)]).collect();
+                    let n = (width * height) as usize;
+                    for depth in [8u16, 4] {
+                        if n == 0 {
+                            break;
+                        }
+                        let mask = ((1u16 << depth) - 1) as u8;
+                        let idx: Vec<u8> = indices[..n].iter().map(|&i| i & mask).collect();
+                        let palette = &palette[..1 << depth];
+                        let bmp = if depth == 8 {
+                            zenbitmaps::encode_bmp_rle8(
+                                &idx,
+                                palette,
+                                width,
+                                height,
+                                enough::Unstoppable,
+                            )
+                        } else {
+                            zenbitmaps::encode_bmp_rle4(
+                                &idx,
+                                palette,
+                                width,
+                                height,
+                                enough::Unstoppable,
+                            )
+                        }
+                        .expect("valid indices must encode");
+                        let decoded = decode_bmp(&bmp, enough::Unstoppable)
+                            .expect("self-encoded RLE BMP must decode");
+                        let expected: Vec<u8> =
+                            idx.iter().flat_map(|&i| palette[usize::from(i)]).collect();
+                        assert_eq!(
+                            decoded.pixels(),
+                            &expected[..],
+                            "RLE{depth} roundtrip pixel mismatch"
+                        );
+                    }
+                }
             }
         })
         .run();
@@ -276,6 +276,50 @@ fn flat_7x1() {
     assert_eq!(decoded.width, 7);
 }
 
+// ══════════════════════════════════════════════════════════════════════
+// Old-style RLE path — (1, 1, 1, n) repeat markers
+// ══════════════════════════════════════════════════════════════════════
+
+#[test]
+fn old_rle_repeat() {
+    // 5x1: one pixel then "repeat 4 times"
+    let hdr = build_hdr(5, 1, &[128, 64, 32, 129, 1, 1, 1, 4]);
+    let decoded = decode_hdr(&hdr, Unstoppable).unwrap();
+    let first = pixel_f32(decoded.pixels(), 0);
+    for i in 1..5 {
+        assert_eq!(pixel_f32(decoded.pixels(), i), first, "pixel {i}");
+    }
+    assert_f32_close(first.0, 1.0, 0.01, "R");
+}
+
+#[test]
+fn old_rle_consecutive_markers_shift() {
+    // 2 + (3 << 8) = 770 repeats after one literal pixel, width 771 (new-style
+    // RLE marker absent, so the old-style path applies)
+    let hdr = build_hdr(771, 1, &[10, 20, 30, 128, 1, 1, 1, 2, 1, 1, 1, 3]);
+    let decoded = decode_hdr(&hdr, Unstoppable).unwrap();
+    let first = pixel_f32(decoded.pixels(), 0);
+    assert_eq!(pixel_f32(decoded.pixels(), 770), first);
+}
+
+#[test]
+fn old_rle_repeat_across_scanlines() {
+    // A marker at the start of row 1 repeats the last pixel of row 0
+    let hdr = build_hdr(2, 2, &[0, 0, 0, 0, 128, 128, 128, 129, 1, 1, 1, 2]);
+    let decoded = decode_hdr(&hdr, Unstoppable).unwrap();
+    let last = pixel_f32(decoded.pixels(), 1);
+    assert_f32_close(last.0, 1.0, 0.01, "R");
+    assert_eq!(pixel_f32(decoded.pixels(), 2), last);
+    assert_eq!(pixel_f32(decoded.pixels(), 3), last);
+}
+
+#[test]
+fn old_rle_run_overflows_scanline() {
+    let hdr = build_hdr(3, 1, &[128, 64, 32, 129, 1, 1, 1, 3]);
+    let err = decode_hdr(&hdr, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+}
+
 // ══════════════════════════════════════════════════════════════════════
 // New-style RLE path — encode/decode roundtrip
 // ══════════════════════════════════════════════════════════════════════
@@ -0,0 +1,316 @@
+//! ICO / CUR container decoding, and ICO encoding.
+#![cfg(feature = "ico")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Turn a BMP file into an ICO DIB: drop the file header, double the
+/// stored height, and append a bottom-up AND mask (`mask[y][x]`, top-down).
+fn bmp_to_dib(bmp: &[u8], mask: &[&[bool]]) -> Vec<u8> {
+    let mut dib = bmp[14..].to_vec();
+    let h = i32::from_le_bytes(dib[8..12].try_into().unwrap());
+    dib[8..12].copy_from_slice(&(h * 2).to_le_bytes());
+    let w = mask[0].len();
+    let stride = w.div_ceil(32) * 4;
+    for row in mask.iter().rev() {
+        let mut bits = vec![0u8; stride];
+        for (x, &set) in row.iter().enumerate() {
+            if set {
+                bits[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        dib.extend_from_slice(&bits);
+    }
+    dib
+}
+
+/// Assemble an ICO (`kind` 1) or CUR (`kind` 2) from `(w, h, bpp_or_hotspot, image)`.
+fn container(kind: u16, entries: &[(u8, u8, [u16; 2], Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    let mut offset = 6 + 16 * entries.len();
+    for (w, h, fields, image) in entries {
+        out.extend_from_slice(&[*w, *h, 0, 0]);
+        out.extend_from_slice(&fields[0].to_le_bytes());
+        out.extend_from_slice(&fields[1].to_le_bytes());
+        out.extend_from_slice(&(image.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += image.len();
+    }
+    for (_, _, _, image) in entries {
+        out.extend_from_slice(image);
+    }
+    out
+}
+
+fn rgb_entry(w: u32, h: u32, color: [u8; 3], mask: &[&[bool]]) -> Vec<u8> {
+    let pixels: Vec<u8> = (0..w * h).flat_map(|_| color).collect();
+    let bmp = encode_bmp(&pixels, w, h, PixelLayout::Rgb8, Unstoppable).unwrap();
+    bmp_to_dib(&bmp, mask)
+}
+
+#[test]
+fn and_mask_becomes_alpha() {
+    let dib = rgb_entry(2, 2, [10, 20, 30], &[&[true, false], &[false, true]]);
+    let ico = container(1, &[(2, 2, [1, 24], dib)]);
+    let decoded = decode_ico(&ico, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!((decoded.width, decoded.height), (2, 2));
+    let alpha: Vec<u8> = decoded.pixels().chunks_exact(4).map(|p| p[3]).collect();
+    assert_eq!(alpha, [0, 255, 255, 0]);
+    assert_eq!(&decoded.pixels()[4..7], &[10, 20, 30]);
+}
+
+#[test]
+fn thirty_two_bit_alpha_wins_over_mask() {
+    let pixels = [1, 2, 3, 128, 4, 5, 6, 0];
+    let bmp = encode_bmp_rgba(&pixels, 2, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let dib = bmp_to_dib(&bmp, &[&[true, true]]);
+    let ico = container(1, &[(2, 1, [1, 32], dib)]);
+    let decoded = decode_ico(&ico, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &pixels);
+}
+
+#[test]
+fn thirty_two_bit_without_alpha_uses_mask() {
+    let pixels = [1, 2, 3, 0, 4, 5, 6, 0];
+    let bmp = encode_bmp_rgba(&pixels, 2, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let dib = bmp_to_dib(&bmp, &[&[false, true]]);
+    let ico = container(1, &[(2, 1, [1, 32], dib)]);
+    let decoded = decode_ico(&ico, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[1, 2, 3, 255, 4, 5, 6, 0]);
+}
+
+#[test]
+fn one_bit_paletted_entry() {
+    // 40-byte header + 2-entry palette + 1 XOR row + 1 AND row, 3x1.
+    let mut dib = Vec::new();
+    dib.extend_from_slice(&40u32.to_le_bytes());
+    dib.extend_from_slice(&3i32.to_le_bytes());
+    dib.extend_from_slice(&2i32.to_le_bytes()); // doubled height
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&[0u8; 24]);
+    dib.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]); // black, white
+    dib.extend_from_slice(&[0b0100_0000, 0, 0, 0]); // XOR: B W B
+    dib.extend_from_slice(&[0b0010_0000, 0, 0, 0]); // AND: last transparent
+    let ico = container(1, &[(3, 1, [1, 1], dib)]);
+    let decoded = decode_ico(&ico, Unstoppable).unwrap();
+    assert_eq!(
+        decoded.pixels(),
+        &[0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn small_eight_bit_paletted_entry() {
+    // 4x4 @ 8bpp: the 1 KiB palette dwarfs the pixel data.
+    let mut dib = Vec::new();
+    dib.extend_from_slice(&40u32.to_le_bytes());
+    dib.extend_from_slice(&4i32.to_le_bytes());
+    dib.extend_from_slice(&8i32.to_le_bytes()); // doubled height
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&8u16.to_le_bytes());
+    dib.extend_from_slice(&[0u8; 24]);
+    for i in 0..=255u8 {
+        dib.extend_from_slice(&[i, 0, 255 - i, 0]); // BGRx
+    }
+    dib.extend_from_slice(&[7u8; 16]); // XOR: index 7 everywhere
+    dib.extend_from_slice(&[0u8; 16]); // AND: all opaque
+    let ico = container(1, &[(4, 4, [1, 8], dib)]);
+    let decoded = decode_ico(&ico, Unstoppable).unwrap();
+    assert!(
+        decoded
+            .pixels()
+            .chunks_exact(4)
+            .all(|p| p == [248, 0, 7, 255])
+    );
+}
+
+#[test]
+fn probe_lists_entries_and_best_is_largest() {
+    let small = rgb_entry(1, 1, [255, 0, 0], &[&[false]]);
+    let large = rgb_entry(2, 2, [0, 255, 0], &[&[false, false], &[false, false]]);
+    let png = b"\x89PNG\r\n\x1a\n-not-really-a-png".to_vec();
+    let ico = container(
+        1,
+        &[
+            (1, 1, [1, 24], small),
+            (0, 0, [1, 32], png),
+            (2, 2, [1, 24], large),
+        ],
+    );
+
+    let dir = probe_ico(&ico).unwrap();
+    assert_eq!(dir.kind, IcoKind::Icon);
+    assert_eq!(dir.entries.len(), 3);
+    assert_eq!((dir.entries[1].width, dir.entries[1].height), (256, 256));
+    assert!(dir.entries[1].is_png);
+    assert!(!dir.entries[0].is_png);
+    assert_eq!(dir.entries[2].bit_count, 24);
+    assert_eq!(dir.entries[0].hotspot, None);
+
+    // The PNG entry is the largest but skipped.
+    let best = decode_ico(&ico, Unstoppable).unwrap();
+    assert_eq!((best.width, best.height), (2, 2));
+    assert_eq!(&best.pixels()[..4], &[0, 255, 0, 255]);
+
+    let first = decode_ico_entry(&ico, 0, Unstoppable).unwrap();
+    assert_eq!(first.pixels(), &[255, 0, 0, 255]);
+
+    let err = decode_ico_entry(&ico, 1, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    let err = decode_ico_entry(&ico, 3, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+}
+
+#[test]
+fn cursor_hotspot_and_dib_bit_count() {
+    let dib = rgb_entry(1, 1, [0, 0, 255], &[&[false]]);
+    let cur = container(2, &[(1, 1, [5, 7], dib)]);
+    let dir = probe_ico(&cur).unwrap();
+    assert_eq!(dir.kind, IcoKind::Cursor);
+    assert_eq!(dir.entries[0].hotspot, Some((5, 7)));
+    assert_eq!(dir.entries[0].bit_count, 24);
+    let decoded = decode_ico(&cur, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0, 0, 255, 255]);
+}
+
+#[test]
+fn rejects_bad_directories() {
+    assert!(matches!(
+        probe_ico(b"BM\0\0\0\0").unwrap_err().error(),
+        BitmapError::UnrecognizedFormat
+    ));
+    assert!(matches!(
+        probe_ico(&[0, 0, 1, 0, 0, 0]).unwrap_err().error(),
+        BitmapError::InvalidHeader(..)
+    ));
+    assert!(matches!(
+        probe_ico(&[0, 0, 1, 0, 2, 0, 0]).unwrap_err().error(),
+        BitmapError::UnexpectedEof(_)
+    ));
+}
+
+#[test]
+fn limits_apply_to_entries() {
+    let row: &[bool] = &[false; 4];
+    let dib = rgb_entry(4, 4, [1, 2, 3], &[row; 4]);
+    let ico = container(1, &[(4, 4, [1, 24], dib)]);
+    let limits = Limits {
+        max_pixels: Some(8),
+        ..Default::default()
+    };
+    assert!(decode_ico_with_limits(&ico, &limits, Unstoppable).is_err());
+    assert!(decode_ico_entry_with_limits(&ico, 0, &limits, Unstoppable).is_err());
+}
+
+#[test]
+fn memory_limit_counts_widened_copy() {
+    let row: &[bool] = &[false; 4];
+    let dib = rgb_entry(4, 4, [1, 2, 3], &[row; 4]);
+    let ico = container(1, &[(4, 4, [1, 24], dib)]);
+    // Enough for the 4x4 RGBA output alone, but not alongside the 24-bit
+    // decode it is widened from.
+    let tight = Limits {
+        max_memory_bytes: Some(4 * 4 * 4),
+        ..Default::default()
+    };
+    assert!(matches!(
+        decode_ico_with_limits(&ico, &tight, Unstoppable)
+            .unwrap_err()
+            .error(),
+        BitmapError::LimitExceeded(_)
+    ));
+    let roomy = Limits {
+        max_memory_bytes: Some(4096),
+        ..Default::default()
+    };
+    assert!(decode_ico_with_limits(&ico, &roomy, Unstoppable).is_ok());
+}
+
+#[test]
+fn encode_round_trips_sizes() {
+    let image = |size: u32| -> Vec<u8> {
+        (0..size * size)
+            .flat_map(|i| {
+                [
+                    i as u8,
+                    (i / 7) as u8,
+                    200,
+                    if i % 3 == 0 { 0 } else { 255 },
+                ]
+            })
+            .collect()
+    };
+    let sizes = [16, 33, 256];
+    let pixels: Vec<Vec<u8>> = sizes.iter().map(|&s| image(s)).collect();
+    let images: Vec<IcoImage<'_>> = sizes
+        .iter()
+        .zip(&pixels)
+        .map(|(&s, p)| IcoImage::new(p, s, s, PixelLayout::Rgba8))
+        .collect();
+    let ico = encode_ico(&images, Unstoppable).unwrap();
+
+    let dir = probe_ico(&ico).unwrap();
+    assert_eq!(dir.kind, IcoKind::Icon);
+    assert_eq!(ico[6 + 32], 0, "256 is stored as 0");
+    for (i, (entry, &size)) in dir.entries.iter().zip(&sizes).enumerate() {
+        assert_eq!((entry.width, entry.height), (size, size));
+        assert_eq!(entry.bit_count, 32);
+        assert!(!entry.is_png);
+        let decoded = decode_ico_entry(&ico, i, Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), &pixels[i][..], "{size}×{size}");
+    }
+    // The largest entry wins
+    assert_eq!(decode_ico(&ico, Unstoppable).unwrap().width, 256);
+}
+
+#[test]
+fn encode_and_mask_follows_alpha() {
+    let pixels = [1, 2, 3, 0, 4, 5, 6, 127, 7, 8, 9, 128, 10, 11, 12, 255];
+    let ico = encode_ico(
+        &[IcoImage::new(&pixels, 2, 2, PixelLayout::Rgba8)],
+        Unstoppable,
+    )
+    .unwrap();
+    let entry = &probe_ico(&ico).unwrap().entries[0];
+    let dib = &ico[entry.offset as usize..][..entry.size as usize];
+    assert_eq!(i32::from_le_bytes(dib[8..12].try_into().unwrap()), 4);
+    // Bottom-up mask rows, each padded to 4 bytes
+    assert_eq!(&dib[40 + 16..], &[0, 0, 0, 0, 0b1100_0000, 0, 0, 0]);
+
+    // Layouts without alpha are opaque, with an empty mask
+    let rgb = [9u8, 8, 7, 6, 5, 4];
+    let ico = encode_ico(&[IcoImage::new(&rgb, 2, 1, PixelLayout::Rgb8)], Unstoppable).unwrap();
+    assert_eq!(&ico[ico.len() - 4..], &[0; 4]);
+    let decoded = decode_ico(&ico, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[9, 8, 7, 255, 6, 5, 4, 255]);
+}
+
+#[test]
+fn encode_errors() {
+    let pixels = vec![0u8; 257 * 4];
+    type Check = fn(&BitmapError) -> bool;
+    let cases: [(Vec<IcoImage<'_>>, Check); 4] = [
+        (vec![], |e| matches!(e, BitmapError::InvalidData(..))),
+        (
+            vec![IcoImage::new(&pixels, 0, 1, PixelLayout::Rgba8)],
+            |e| matches!(e, BitmapError::InvalidData(..)),
+        ),
+        (
+            vec![IcoImage::new(&pixels, 257, 1, PixelLayout::Rgba8)],
+            |e| matches!(e, BitmapError::DimensionsTooLarge { .. }),
+        ),
+        (
+            vec![IcoImage::new(&pixels, 16, 17, PixelLayout::Rgba8)],
+            |e| matches!(e, BitmapError::BufferTooSmall { .. }),
+        ),
+    ];
+    for (i, (images, check)) in cases.into_iter().enumerate() {
+        let err = encode_ico(&images, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+}
@@ -0,0 +1,36 @@
+//! `Limits::max_duration` times decodes out at the `Stop` check cadence.
+#![cfg(feature = "std")]
+
+use std::time::Duration;
+
+use enough::{StopReason, Unstoppable};
+use zenbitmaps::*;
+
+fn ppm() -> Vec<u8> {
+    let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| (i % 251) as u8).collect();
+    encode_ppm(&pixels, 64, 64, PixelLayout::Rgb8, Unstoppable).unwrap()
+}
+
+#[test]
+fn expired_deadline_times_out() {
+    let file = ppm();
+    let limits = Limits::new().with_max_duration(Duration::ZERO);
+    for result in [
+        decode_with_limits(&file, &limits, Unstoppable).map(|_| ()),
+        decode_region_with_limits(&file, 8, 8, 16, 16, &limits, Unstoppable).map(|_| ()),
+    ] {
+        let err = result.unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::Cancelled(StopReason::TimedOut)),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn generous_deadline_decodes() {
+    let file = ppm();
+    let limits = Limits::web_safe().with_max_duration(Duration::from_secs(60));
+    let decoded = decode_with_limits(&file, &limits, Unstoppable).unwrap();
+    assert_eq!((decoded.width, decoded.height), (64, 64));
+}
@@ -0,0 +1,173 @@
+#![cfg(feature = "npy")]
+//! NumPy `.npy` array decoding and encoding.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// An `.npy` file with the given version, header dict and array bytes.
+fn npy(version: u8, dict: &str, array: &[u8]) -> Vec<u8> {
+    let mut out = b"\x93NUMPY".to_vec();
+    out.extend_from_slice(&[version, 0]);
+    let text = format!("{dict}\n");
+    if version == 1 {
+        out.extend_from_slice(&(text.len() as u16).to_le_bytes());
+    } else {
+        out.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    }
+    out.extend_from_slice(text.as_bytes());
+    out.extend_from_slice(array);
+    out
+}
+
+#[test]
+fn uint8_shapes() {
+    let pixels: Vec<u8> = (0..24).collect();
+    for (shape, layout) in [
+        ("(2, 12)", PixelLayout::Gray8),
+        ("(2, 12, 1)", PixelLayout::Gray8),
+        ("(2, 4, 3)", PixelLayout::Rgb8),
+        ("(2L, 3L, 4L)", PixelLayout::Rgba8),
+    ] {
+        let dict = format!("{{'descr': '|u1', 'fortran_order': False, 'shape': {shape}, }}");
+        let data = npy(1, &dict, &pixels);
+        let decoded = decode_npy(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, layout, "{shape}");
+        assert_eq!(decoded.height, 2);
+        assert_eq!(decoded.width as usize * layout.bytes_per_pixel(), 12);
+        assert!(decoded.is_borrowed());
+        assert_eq!(decoded.pixels(), &pixels[..]);
+    }
+}
+
+#[test]
+fn uint16_and_float32_either_byte_order() {
+    let values = [1u16, 0x0203, 0xFFFF, 0x8000];
+    for (order, big) in [('<', false), ('>', true)] {
+        let array: Vec<u8> = values
+            .iter()
+            .flat_map(|v| {
+                if big {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            })
+            .collect();
+        let dict =
+            format!("{{\"descr\": \"{order}u2\", \"fortran_order\": False, \"shape\": (1, 2, 2)}}");
+        let data = npy(2, &dict, &array);
+        let decoded = decode_npy(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::GrayA16);
+        let ne: Vec<u8> = values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        assert_eq!(decoded.pixels(), ne, "{order}");
+        assert_eq!(decoded.is_borrowed(), big == cfg!(target_endian = "big"));
+
+        let floats = [0.0f32, 0.5, 1.0];
+        let array: Vec<u8> = floats
+            .iter()
+            .flat_map(|v| {
+                if big {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            })
+            .collect();
+        let dict =
+            format!("{{'descr': '{order}f4', 'fortran_order': False, 'shape': (1, 1, 3), }}");
+        let data = npy(3, &dict, &array);
+        let decoded = decode_npy(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::RgbF32);
+        let ne: Vec<u8> = floats.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        assert_eq!(decoded.pixels(), ne);
+    }
+}
+
+#[test]
+fn encode_round_trips() {
+    let bytes: Vec<u8> = (0..2 * 3 * 16).map(|i| i as u8).collect();
+    for layout in [
+        PixelLayout::Gray8,
+        PixelLayout::Rgb8,
+        PixelLayout::Rgba8,
+        PixelLayout::Gray16,
+        PixelLayout::GrayA16,
+        PixelLayout::Rgb16,
+        PixelLayout::Rgba16,
+        PixelLayout::GrayF32,
+        PixelLayout::RgbF32,
+        PixelLayout::RgbaF32,
+    ] {
+        let pixels = &bytes[..2 * 3 * layout.bytes_per_pixel()];
+        let data = encode_npy(pixels, 3, 2, layout, Unstoppable).unwrap();
+        // Data starts on a 64-byte boundary after a newline-terminated header
+        let start = data.len() - pixels.len();
+        assert_eq!(start % 64, 0, "{layout:?}");
+        assert_eq!(data[start - 1], b'\n');
+        let decoded = decode_npy(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, layout);
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.pixels(), pixels, "{layout:?}");
+    }
+
+    let data = encode_npy(&[0; 12], 2, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let header = core::str::from_utf8(&data[10..data.len() - 12]).unwrap();
+    assert_eq!(
+        header.trim_end(),
+        "{'descr': '|u1', 'fortran_order': False, 'shape': (2, 2, 3), }"
+    );
+}
+
+#[test]
+fn errors() {
+    let dict = |descr: &str, fortran: &str, shape: &str| {
+        npy(
+            1,
+            &format!("{{'descr': '{descr}', 'fortran_order': {fortran}, 'shape': {shape}, }}"),
+            &[0; 64],
+        )
+    };
+    type Check = fn(&BitmapError) -> bool;
+    let unsupported: Check = |e| matches!(e, BitmapError::UnsupportedVariant(_));
+    let invalid: Check = |e| matches!(e, BitmapError::InvalidHeader(..));
+    let cases: [(Vec<u8>, Check); 11] = [
+        (dict("<i4", "False", "(2, 2)"), unsupported),
+        (dict("|u1", "True", "(2, 2)"), unsupported),
+        (dict("|u1", "False", "(16,)"), unsupported),
+        (dict("|u1", "False", "(2, 2, 2)"), unsupported),
+        (dict("|u1", "False", "(2, 2, 5)"), unsupported),
+        (dict("|u1", "False", "(0, 2)"), invalid),
+        (dict("|u1", "False", "(2, 2"), invalid),
+        (dict("|u1", "Maybe", "(2, 2)"), invalid),
+        (
+            npy(1, "{'descr': '|u1', 'shape': (2, 2)}", &[0; 4]),
+            invalid,
+        ),
+        (b"\x93NUMPZ\x01\x00".to_vec(), |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (dict("|u1", "False", "(9, 9)"), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+    ];
+    for (i, (data, check)) in cases.into_iter().enumerate() {
+        let err = decode_npy(&data, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+
+    let err = encode_npy(&[0; 6], 2, 1, PixelLayout::Bgr8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    let err = encode_npy(&[0; 5], 2, 1, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+}
+
+#[test]
+fn limits_apply() {
+    let data = encode_npy(&[0; 16], 4, 4, PixelLayout::Gray8, Unstoppable).unwrap();
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_npy_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
@@ -0,0 +1,228 @@
+#![cfg(feature = "pcx")]
+//! PCX decoding tests using synthetic files.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Build a PCX header; the window is (0,0)-(width-1,height-1).
+fn header(
+    version: u8,
+    rle: bool,
+    bits: u8,
+    planes: u8,
+    width: u16,
+    height: u16,
+    bpl: u16,
+) -> Vec<u8> {
+    let mut h = vec![0u8; 128];
+    h[0] = 0x0A;
+    h[1] = version;
+    h[2] = rle as u8;
+    h[3] = bits;
+    h[8..10].copy_from_slice(&(width - 1).to_le_bytes());
+    h[10..12].copy_from_slice(&(height - 1).to_le_bytes());
+    h[65] = planes;
+    h[66..68].copy_from_slice(&bpl.to_le_bytes());
+    h
+}
+
+/// PCX RLE: runs of up to 63, and literals >= 0xC0 escaped as runs of one.
+fn rle(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let v = data[i];
+        let mut n = 1;
+        while i + n < data.len() && data[i + n] == v && n < 63 {
+            n += 1;
+        }
+        if n > 1 || v >= 0xC0 {
+            out.extend_from_slice(&[0xC0 | n as u8, v]);
+        } else {
+            out.push(v);
+        }
+        i += n;
+    }
+    out
+}
+
+fn vga_palette(f: impl Fn(u8) -> [u8; 3]) -> Vec<u8> {
+    let mut p = vec![0x0C];
+    for i in 0..=255 {
+        p.extend_from_slice(&f(i));
+    }
+    p
+}
+
+#[test]
+fn rgb24_rle() {
+    // 3x2; planes per scanline are R, G, B with bytes_per_line 4 (padded)
+    let lines: [[u8; 12]; 2] = [
+        [1, 2, 3, 0, 10, 20, 30, 0, 200, 200, 200, 0],
+        [4, 5, 6, 0, 40, 50, 60, 0, 7, 7, 7, 0],
+    ];
+    let mut data = header(5, true, 8, 3, 3, 2, 4);
+    data.extend(rle(&lines.concat()));
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!((decoded.width, decoded.height), (3, 2));
+    assert_eq!(
+        decoded.pixels(),
+        &[
+            1, 10, 200, 2, 20, 200, 3, 30, 200, 4, 40, 7, 5, 50, 7, 6, 60, 7
+        ]
+    );
+}
+
+#[test]
+fn rgba32_uncompressed() {
+    let mut data = header(5, false, 8, 4, 2, 1, 2);
+    data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 255, 128]);
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[1, 3, 5, 255, 2, 4, 6, 128]);
+}
+
+#[test]
+fn indexed8_vga_palette() {
+    let indices = [0u8, 1, 2, 255, 255, 255, 255, 0];
+    let mut data = header(5, true, 8, 1, 4, 2, 4);
+    data.extend(rle(&indices));
+    data.extend(vga_palette(|i| [i, 255 - i, i / 2]));
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    let expected: Vec<u8> = indices.iter().flat_map(|&i| [i, 255 - i, i / 2]).collect();
+    assert_eq!(decoded.pixels(), &expected[..]);
+}
+
+#[test]
+fn indexed8_without_palette_is_gray() {
+    let mut data = header(5, true, 8, 1, 3, 1, 4);
+    data.extend(rle(&[9, 99, 199, 0]));
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Gray8);
+    assert_eq!(decoded.pixels(), &[9, 99, 199]);
+}
+
+#[test]
+fn ega_planar_4bit_header_palette() {
+    // 1 bit x 4 planes; pixel x has index x (0..8)
+    let mut data = header(5, true, 1, 4, 8, 1, 2);
+    for (i, rgb) in data[16..64].chunks_exact_mut(3).enumerate() {
+        rgb.copy_from_slice(&[i as u8 * 16, 0, 255 - i as u8]);
+    }
+    let plane = |p: u32| -> [u8; 2] {
+        let bits = (0..8u8).fold(0u8, |b, x| b | (((x >> p) & 1) << (7 - x)));
+        [bits, 0]
+    };
+    let line = [plane(0), plane(1), plane(2), plane(3)].concat();
+    data.extend(rle(&line));
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    let expected: Vec<u8> = (0..8u8).flat_map(|i| [i * 16, 0, 255 - i]).collect();
+    assert_eq!(decoded.pixels(), &expected[..]);
+}
+
+#[test]
+fn packed_4bit_and_2bit() {
+    let mut data = header(5, false, 4, 1, 3, 1, 2);
+    data[16 + 3 * 0xA..16 + 3 * 0xA + 3].copy_from_slice(&[1, 2, 3]);
+    data.extend_from_slice(&[0xA0, 0xA0]);
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(&decoded.pixels()[..3], &[1, 2, 3]);
+    assert_eq!(&decoded.pixels()[3..6], &[0, 0, 0]);
+    assert_eq!(&decoded.pixels()[6..9], &[1, 2, 3]);
+
+    // CGA-style 2 bits per pixel: indices 3, 2, 1, 0
+    let mut data = header(5, false, 2, 1, 4, 1, 2);
+    data[16..28].copy_from_slice(&[0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3]);
+    data.extend_from_slice(&[0b1110_0100, 0]);
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[3, 3, 3, 2, 2, 2, 1, 1, 1, 0, 0, 0]);
+}
+
+#[test]
+fn version3_uses_default_ega_palette() {
+    let mut data = header(3, false, 4, 1, 2, 1, 2);
+    data.extend_from_slice(&[0x1F, 0]);
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0, 0, 0xAA, 0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn mono() {
+    let mut data = header(5, true, 1, 1, 10, 2, 2);
+    data.extend(rle(&[0b1010_0000, 0b1100_0000, 0xFF, 0]));
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Gray8);
+    assert_eq!(
+        decoded.pixels(),
+        &[
+            255, 0, 255, 0, 0, 0, 0, 0, 255, 255, //
+            255, 255, 255, 255, 255, 255, 255, 255, 0, 0,
+        ]
+    );
+}
+
+#[test]
+fn runs_cross_scanlines() {
+    // One run covering both 4-byte scanlines
+    let mut data = header(5, true, 8, 1, 4, 2, 4);
+    data.extend_from_slice(&[0xC8, 42]);
+    let decoded = decode_pcx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[42; 8]);
+}
+
+#[test]
+fn truncated_data() {
+    let mut data = header(5, true, 8, 3, 4, 4, 4);
+    data.extend(rle(&[7; 20]));
+    let err = decode_pcx(&data, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+
+    let err = decode_pcx(&data[..100], Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+}
+
+#[test]
+fn invalid_headers() {
+    let good = header(5, true, 8, 1, 4, 1, 4);
+    let mut bad = good.clone();
+    bad[0] = 0x0B;
+    let err = decode_pcx(&bad, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnrecognizedFormat));
+
+    let mut bad = good.clone();
+    bad[1] = 1;
+    let err = decode_pcx(&bad, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+
+    let mut bad = good.clone();
+    bad[66] = 3; // bytes_per_line < width
+    let err = decode_pcx(&bad, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+
+    let mut bad = good;
+    bad[65] = 2; // two 8-bit planes
+    let err = decode_pcx(&bad, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+}
+
+#[test]
+fn limits_apply() {
+    let mut data = header(5, true, 8, 3, 4, 4, 4);
+    data.extend(rle(&[7; 48]));
+    let limits = Limits {
+        max_width: Some(3),
+        ..Default::default()
+    };
+    let err = decode_pcx_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    assert!(decode_pcx_with_limits(&data, &Limits::default(), Unstoppable).is_ok());
+}
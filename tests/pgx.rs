@@ -0,0 +1,180 @@
+#![cfg(feature = "pgx")]
+//! PGX (JPEG 2000 conformance format) decoding and encoding.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn ne16(values: &[u16]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+#[test]
+fn header_variants() {
+    for (text, depth, signed, big) in [
+        (&b"PG ML + 8 2 1\n"[..], 8, false, true),
+        (b"PG LM -12 2 1\n", 12, true, false),
+        (b"PG ML 8 2 1\n", 8, false, true),
+        (b"PG\tML  +  16  2  1 \r\n", 16, false, true),
+    ] {
+        let header = probe_pgx(text).unwrap();
+        assert_eq!(
+            (header.width, header.height),
+            (2, 1),
+            "{}",
+            text.escape_ascii()
+        );
+        assert_eq!(header.bit_depth, depth);
+        assert_eq!(header.signed, signed);
+        assert_eq!(header.big_endian, big);
+    }
+}
+
+#[test]
+fn unsigned_samples_as_stored() {
+    let mut data = b"PG ML + 8 3 1\n".to_vec();
+    data.extend_from_slice(&[0, 17, 255]);
+    let decoded = decode_pgx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Gray8);
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &[0, 17, 255]);
+
+    // 12-bit in two bytes, both byte orders; no rescaling
+    for (order, samples) in [
+        ("ML", [0x0F, 0xFF, 0x00, 0x01]),
+        ("LM", [0xFF, 0x0F, 0x01, 0x00]),
+    ] {
+        let mut data = format!("PG {order} + 12 2 1\n").into_bytes();
+        data.extend_from_slice(&samples);
+        let decoded = decode_pgx(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::Gray16);
+        assert_eq!(decoded.pixels(), ne16(&[4095, 1]), "{order}");
+    }
+}
+
+#[test]
+fn signed_samples_level_shifted() {
+    let mut data = b"PG LM - 12 3 1\n".to_vec();
+    for v in [-2048i16, 0, 2047] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    let decoded = decode_pgx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), ne16(&[0, 2048, 4095]));
+
+    // Out-of-range samples clamp
+    let mut data = b"PG ML - 4 2 1\n".to_vec();
+    data.extend_from_slice(&[0x80, 0x7F]);
+    let decoded = decode_pgx(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0, 15]);
+}
+
+#[test]
+fn decode_encode_reproduces_file() {
+    let files: [(&str, Vec<u8>); 4] = [
+        ("PG ML + 8 2 2\n", vec![0, 1, 2, 255]),
+        ("PG ML - 8 2 2\n", vec![0x80, 0xFF, 0, 0x7F]),
+        ("PG LM - 12 2 1\n", vec![0x00, 0xF8, 0xFF, 0x07]),
+        ("PG ML + 5 2 1\n", vec![31, 7]),
+    ];
+    for (header_line, samples) in files {
+        let mut file = header_line.as_bytes().to_vec();
+        file.extend_from_slice(&samples);
+        let header = probe_pgx(&file).unwrap();
+        let decoded = decode_pgx(&file, Unstoppable).unwrap();
+        let order = if header.big_endian {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        };
+        let options = PgxEncodeOptions::new()
+            .with_bit_depth(header.bit_depth)
+            .with_signed(header.signed)
+            .with_byte_order(order);
+        let encoded = encode_pgx_with(
+            decoded.pixels(),
+            decoded.width,
+            decoded.height,
+            decoded.layout,
+            &options,
+            Unstoppable,
+        )
+        .unwrap();
+        assert_eq!(encoded, file, "{header_line:?}");
+    }
+}
+
+#[test]
+fn encode_defaults() {
+    let data = encode_pgx(&[1, 2], 2, 1, PixelLayout::Gray8, Unstoppable).unwrap();
+    assert_eq!(data, b"PG ML + 8 2 1\n\x01\x02");
+    let data = encode_pgx(&ne16(&[0x1234]), 1, 1, PixelLayout::Gray16, Unstoppable).unwrap();
+    assert_eq!(data, b"PG ML + 16 1 1\n\x12\x34");
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let invalid: Check = |e| matches!(e, BitmapError::InvalidHeader(..));
+    let eof: Check = |e| matches!(e, BitmapError::UnexpectedEof(_));
+    let cases: [(&[u8], Check); 8] = [
+        (b"P5 2 1 255\n", |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (b"PG XX + 8 2 1\n\0\0", invalid),
+        (b"PG ML + 0 2 1\n\0\0", invalid),
+        (b"PG ML + 8 0 1\n", invalid),
+        (b"PG ML + 8 2 1 x\n\0\0", invalid),
+        (b"PG ML + 24 1 1\n\0\0\0\0", |e| {
+            matches!(e, BitmapError::UnsupportedVariant(_))
+        }),
+        (b"PG ML + 8 2", eof),
+        (b"PG ML - 8 2 1\n\0", eof),
+    ];
+    for (i, (data, check)) in cases.into_iter().enumerate() {
+        let err = decode_pgx(data, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+    assert!(matches!(
+        decode_pgx(b"PG ML + 8 2 1\n\0", Unstoppable)
+            .unwrap_err()
+            .error(),
+        BitmapError::UnexpectedEof(_)
+    ));
+
+    let depth = |d| PgxEncodeOptions::new().with_bit_depth(d);
+    let encode = |options: PgxEncodeOptions, layout| {
+        encode_pgx_with(&[200, 0], 2, 1, layout, &options, Unstoppable).unwrap_err()
+    };
+    assert!(matches!(
+        encode(depth(7), PixelLayout::Gray8).error(),
+        BitmapError::InvalidData(..)
+    ));
+    assert!(matches!(
+        encode(depth(17), PixelLayout::Gray8).error(),
+        BitmapError::UnsupportedVariant(_)
+    ));
+    assert!(matches!(
+        encode(PgxEncodeOptions::new(), PixelLayout::Rgb8).error(),
+        BitmapError::UnsupportedVariant(_)
+    ));
+    assert!(matches!(
+        encode(PgxEncodeOptions::new(), PixelLayout::Gray16).error(),
+        BitmapError::BufferTooSmall { .. }
+    ));
+}
+
+#[test]
+fn limits_apply() {
+    let data = b"PG ML - 8 4 4\n\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_pgx_with_limits(data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    let limits = Limits {
+        max_memory_bytes: Some(15),
+        ..Default::default()
+    };
+    let err = decode_pgx_with_limits(data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
@@ -0,0 +1,178 @@
+//! Planar layouts: split/interleave, per-channel planes, and the paths that
+//! accept planes.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn split_and_interleave_round_trip() {
+    let (w, h) = (5u32, 3u32);
+    let floats: Vec<u8> = (0..45)
+        .flat_map(|i| (i as f32 * 0.25 - 3.0).to_ne_bytes())
+        .collect();
+    let cases = [
+        (
+            PixelLayout::Rgb8,
+            (0..45).map(|i| i as u8).collect::<Vec<u8>>(),
+        ),
+        (PixelLayout::Rgba8, (0..60).map(|i| (i * 3) as u8).collect()),
+        (PixelLayout::RgbF32, floats),
+    ];
+    for (layout, pixels) in cases {
+        let planar_layout = layout.planar().unwrap();
+        assert!(planar_layout.is_planar() && !layout.is_planar());
+        assert_eq!(planar_layout.interleaved(), layout);
+        assert_eq!(planar_layout.bytes_per_pixel(), layout.bytes_per_pixel());
+        assert_eq!(planar_layout.channels(), layout.channels());
+
+        let planes = split_planes(&pixels, w, h, layout, Unstoppable).unwrap();
+        assert_eq!(planes.len(), pixels.len());
+        // Channel c of pixel i sits at plane c, index i.
+        let channels = layout.channels();
+        let sample = layout.bytes_per_pixel() / channels;
+        let plane = (w * h) as usize * sample;
+        for i in 0..(w * h) as usize {
+            for c in 0..channels {
+                let src = (i * channels + c) * sample;
+                let dst = c * plane + i * sample;
+                assert_eq!(
+                    planes[dst..dst + sample],
+                    pixels[src..src + sample],
+                    "{layout:?}"
+                );
+            }
+        }
+        let back = interleave_planes(&planes, w, h, planar_layout, Unstoppable).unwrap();
+        assert_eq!(back, pixels, "{layout:?}");
+    }
+}
+
+#[test]
+fn farbfeld_accepts_planes() {
+    let rgba: Vec<u8> = (0..24).map(|i| (i * 11) as u8).collect();
+    let planes = split_planes(&rgba, 3, 2, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let expected = encode_farbfeld(&rgba, 3, 2, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let encoded = encode_farbfeld(&planes, 3, 2, PixelLayout::RgbaPlanar8, Unstoppable).unwrap();
+    assert_eq!(encoded, expected);
+}
+
+#[test]
+fn raw_planar_dumps() {
+    // 2×1 RgbPlanarF32, little-endian
+    let values = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let layout = PixelLayout::RgbPlanarF32;
+    let decoded = decode_raw(&data, 2, 1, layout, 24, Endianness::Little, Unstoppable).unwrap();
+    let expected: Vec<u8> = values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    assert_eq!(decoded.pixels(), &expected[..]);
+    let rgb = interleave_planes(decoded.pixels(), 2, 1, layout, Unstoppable).unwrap();
+    let rgb: Vec<f32> = rgb
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert_eq!(rgb, [1.0, 3.0, 5.0, 2.0, 4.0, 6.0]);
+
+    let err = decode_raw(&data, 1, 2, layout, 16, Endianness::Little, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+}
+
+#[test]
+fn errors() {
+    let err = interleave_planes(&[0; 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    for layout in [
+        PixelLayout::Bgr8,
+        PixelLayout::Gray8,
+        PixelLayout::RgbPlanar8,
+    ] {
+        let err = split_planes(&[0; 16], 1, 1, layout, Unstoppable).unwrap_err();
+        assert!(
+            matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+            "{layout:?}"
+        );
+    }
+    let err = split_planes(&[0; 5], 2, 1, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+    let err = interleave_planes(&[], 0, 1, PixelLayout::RgbaPlanar8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+}
+
+#[test]
+fn channels_round_trip_every_layout() {
+    let layouts = [
+        PixelLayout::Gray8,
+        PixelLayout::Gray16,
+        PixelLayout::GrayA16,
+        PixelLayout::Rgb8,
+        PixelLayout::Bgra8,
+        PixelLayout::Rgbx8,
+        PixelLayout::Rgba16Be,
+        PixelLayout::RgbaF32,
+        PixelLayout::RgbPlanar8,
+        PixelLayout::RgbPlanarF32,
+    ];
+    let (w, h) = (3u32, 18u32);
+    for layout in layouts {
+        let len = (w * h) as usize * layout.bytes_per_pixel();
+        let pixels: Vec<u8> = (0..len).map(|i| (i * 7 % 253) as u8).collect();
+        let planes = split_channels(&pixels, w, h, layout, Unstoppable).unwrap();
+        assert_eq!(planes.len(), layout.channels(), "{layout:?}");
+        let sample = layout.bytes_per_pixel() / layout.channels();
+        assert!(planes.iter().all(|p| p.len() == (w * h) as usize * sample));
+        if !layout.is_planar() {
+            // Second pixel's last channel
+            let last = layout.channels() - 1;
+            let src = layout.bytes_per_pixel() + last * sample;
+            assert_eq!(planes[last][sample..2 * sample], pixels[src..src + sample]);
+        }
+        let refs: Vec<&[u8]> = planes.iter().map(Vec::as_slice).collect();
+        let back = merge_channels(&refs, w, h, layout, Unstoppable).unwrap();
+        assert_eq!(back, pixels, "{layout:?}");
+    }
+}
+
+#[test]
+fn decode_output_split_channels() {
+    let rgb: Vec<u8> = (0..18).collect();
+    let ppm = encode_ppm(&rgb, 3, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let planes = decode(&ppm, Unstoppable).unwrap().split_channels().unwrap();
+    assert_eq!(
+        planes,
+        [
+            vec![0, 3, 6, 9, 12, 15],
+            vec![1, 4, 7, 10, 13, 16],
+            vec![2, 5, 8, 11, 14, 17]
+        ]
+    );
+    // The planar layout is the same planes back to back.
+    let planar = split_planes(&rgb, 3, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    assert_eq!(planar, planes.concat());
+}
+
+#[test]
+fn merge_channels_errors() {
+    let plane: &[u8] = &[0; 4];
+    let err = merge_channels(&[plane; 3], 2, 2, PixelLayout::Rgba8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    let err = merge_channels(
+        &[plane, &plane[..3], plane],
+        2,
+        2,
+        PixelLayout::Rgb8,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.error(),
+        BitmapError::BufferTooSmall {
+            needed: 4,
+            actual: 3
+        }
+    ));
+    let err = merge_channels(&[plane], 2, 2, PixelLayout::Gray16, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+    let err = split_channels(&[0; 5], 2, 1, PixelLayout::Rgb8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+    let err = merge_channels(&[], 0, 1, PixelLayout::Gray8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+}
@@ -0,0 +1,48 @@
+//! `PnmEncodeOptions::with_maxval`: samples scaled to a custom range.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn wide_maxval_writes_two_byte_samples() {
+    let rgb = [0, 128, 255, 1, 2, 3];
+    let options = PnmEncodeOptions::new().with_maxval(1023);
+    let ppm = encode_ppm_with(&rgb, 2, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    let header = b"P6\n2 1\n1023\n";
+    assert_eq!(&ppm[..header.len()], header);
+    let samples: Vec<u16> = ppm[header.len()..]
+        .chunks_exact(2)
+        .map(|s| u16::from_be_bytes([s[0], s[1]]))
+        .collect();
+    assert_eq!(samples, [0, 514, 1023, 4, 8, 12]);
+    // The flat decoder scales back to 8-bit.
+    assert_eq!(decode(&ppm, Unstoppable).unwrap().pixels(), rgb);
+}
+
+#[test]
+fn narrow_maxval_rounds() {
+    let gray = [0, 17, 128, 255];
+    let options = PnmEncodeOptions::new().with_maxval(15);
+    let pgm = encode_pgm_with(&gray, 4, 1, PixelLayout::Gray8, &options, Unstoppable).unwrap();
+    assert_eq!(pgm, b"P5\n4 1\n15\n\x00\x01\x08\x0f");
+}
+
+#[test]
+fn maxval_combines_with_background() {
+    let rgba = [255, 0, 0, 0];
+    let options = PnmEncodeOptions::new()
+        .with_background([255, 255, 255])
+        .with_maxval(1);
+    let ppm = encode_ppm_with(&rgba, 1, 1, PixelLayout::Rgba8, &options, Unstoppable).unwrap();
+    assert_eq!(ppm, b"P6\n1 1\n1\n\x01\x01\x01");
+}
+
+#[test]
+fn zero_maxval_is_rejected() {
+    let options = PnmEncodeOptions::new().with_maxval(0);
+    let err = encode_pgm_with(&[0], 1, 1, PixelLayout::Gray8, &options, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnsupportedVariant(_)),
+        "{err}"
+    );
+}
@@ -0,0 +1,74 @@
+//! Row-at-a-time QOI decoding.
+#![cfg(feature = "qoi")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Runs that cross row boundaries, repeats that hit the index, and noise.
+fn image(width: usize, height: usize, channels: usize) -> Vec<u8> {
+    (0..width * height)
+        .flat_map(|i| {
+            let v = match i % 11 {
+                0..=4 => 200,
+                5 | 6 => (i / 3 % 4) as u8 * 60,
+                _ => (i * 37 % 251) as u8,
+            };
+            [v, v / 2, 255 - v, (i % 3) as u8 * 100][..channels].to_vec()
+        })
+        .collect()
+}
+
+#[test]
+fn rows_match_full_decode() {
+    for (layout, channels) in [(PixelLayout::Rgb8, 3), (PixelLayout::Rgba8, 4)] {
+        for (width, height) in [(1, 1), (3, 2), (7, 17), (40, 33)] {
+            let pixels = image(width, height, channels);
+            let qoi =
+                encode_qoi(&pixels, width as u32, height as u32, layout, Unstoppable).unwrap();
+            let mut rows = decode_qoi_rows(&qoi, Unstoppable).unwrap();
+            assert_eq!((rows.width(), rows.height()), (width as u32, height as u32));
+            assert_eq!(rows.layout(), layout);
+            let mut joined = Vec::new();
+            while let Some(row) = rows.next_row() {
+                joined.extend_from_slice(row.unwrap());
+            }
+            assert_eq!(rows.rows_remaining(), 0);
+            assert_eq!(joined, pixels, "{layout:?} {width}x{height}");
+            assert_eq!(joined, decode_qoi(&qoi, Unstoppable).unwrap().pixels());
+        }
+    }
+}
+
+#[test]
+fn truncated_input_ends_with_eof() {
+    let qoi = encode_qoi(&image(9, 6, 3), 9, 6, PixelLayout::Rgb8, Unstoppable).unwrap();
+    // Drop the end marker and the last few chunks.
+    let mut rows = decode_qoi_rows(&qoi[..qoi.len() / 2], Unstoppable).unwrap();
+    let mut results = Vec::new();
+    while let Some(row) = rows.next_row() {
+        results.push(row.map(<[u8]>::to_vec));
+    }
+    let err = results.pop().unwrap().unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::UnexpectedEof(_)),
+        "{err}"
+    );
+    assert!(results.iter().all(Result::is_ok));
+    assert!(rows.next_row().is_none());
+}
+
+#[test]
+fn limits_apply() {
+    let qoi = encode_qoi(&image(4, 2, 3), 4, 2, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let limits = Limits {
+        max_height: Some(1),
+        ..Default::default()
+    };
+    let err = decode_qoi_rows_with_limits(&qoi, &limits, Unstoppable)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(err.error(), BitmapError::LimitExceeded(_)),
+        "{err}"
+    );
+}
@@ -0,0 +1,241 @@
+//! Headerless raw pixel dump tests.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn tight_native_is_borrowed() {
+    let data: Vec<u8> = (0..24).collect();
+    let decoded = decode_raw(
+        &data,
+        2,
+        3,
+        PixelLayout::Rgba8,
+        8,
+        Endianness::Native,
+        Unstoppable,
+    )
+    .unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!((decoded.width, decoded.height), (2, 3));
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &data[..]);
+
+    // Trailing bytes past the last row are ignored
+    let decoded = decode_raw(
+        &data,
+        4,
+        1,
+        PixelLayout::Gray16,
+        8,
+        Endianness::Native,
+        Unstoppable,
+    )
+    .unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &data[..8]);
+}
+
+#[test]
+fn padded_rows_are_borrowed_with_their_stride() {
+    // 3x2 gray rows padded to 4 bytes; the last row is unpadded
+    let data = [1, 2, 3, 0xAA, 4, 5, 6];
+    let decoded = decode_raw(
+        &data,
+        3,
+        2,
+        PixelLayout::Gray8,
+        4,
+        Endianness::Big,
+        Unstoppable,
+    )
+    .unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!((decoded.stride(), decoded.is_contiguous()), (4, false));
+    assert_eq!(decoded.pixels(), &data[..]);
+    assert_eq!(decoded.rows().collect::<Vec<_>>(), [[1, 2, 3], [4, 5, 6]]);
+    assert_eq!(&*decoded.packed_pixels(), &[1, 2, 3, 4, 5, 6]);
+
+    // Swapped samples are copied into packed rows.
+    let data = [0x01, 0x02, 0xAA, 0xAA, 0x03, 0x04];
+    let swapped = decode_raw(
+        &data,
+        1,
+        2,
+        PixelLayout::Gray16,
+        4,
+        Endianness::Big,
+        Unstoppable,
+    )
+    .unwrap();
+    let big_endian_host = cfg!(target_endian = "big");
+    assert_eq!(swapped.is_borrowed(), big_endian_host);
+    assert_eq!(
+        &*swapped.packed_pixels(),
+        [0x0102u16, 0x0304]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn byte_order() {
+    let values = [0x0102u16, 0xA0B0, 0xFFFE];
+    let le: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let be: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    let native: Vec<u8> = values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    for (data, endianness) in [(le, Endianness::Little), (be, Endianness::Big)] {
+        let decoded =
+            decode_raw(&data, 3, 1, PixelLayout::Gray16, 6, endianness, Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), &native[..], "{endianness:?}");
+        assert_eq!(
+            decoded.is_borrowed(),
+            data == native,
+            "{endianness:?} should borrow only when no swap is needed"
+        );
+    }
+
+    let value = 0.25f32;
+    let be = value.to_be_bytes();
+    let decoded = decode_raw(
+        &be,
+        1,
+        1,
+        PixelLayout::GrayF32,
+        4,
+        Endianness::Big,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(decoded.pixels(), &value.to_ne_bytes());
+
+    // Rgba16Be stays as stored whatever the endianness argument says
+    let data = [1, 2, 3, 4, 5, 6, 7, 8];
+    let decoded = decode_raw(
+        &data,
+        1,
+        1,
+        PixelLayout::Rgba16Be,
+        8,
+        Endianness::Little,
+        Unstoppable,
+    )
+    .unwrap();
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &data);
+}
+
+#[test]
+fn errors() {
+    let data = [0u8; 16];
+    let err = decode_raw(
+        &data,
+        0,
+        1,
+        PixelLayout::Gray8,
+        1,
+        Endianness::Native,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+    let err = decode_raw(
+        &data,
+        2,
+        2,
+        PixelLayout::Rgb8,
+        5,
+        Endianness::Native,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+    // Needs 10 + 6 = 16 bytes with a 10-byte stride; 15 is short
+    let err = decode_raw(
+        &data[..15],
+        2,
+        2,
+        PixelLayout::Rgb8,
+        10,
+        Endianness::Native,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+    assert!(
+        decode_raw(
+            &data,
+            2,
+            2,
+            PixelLayout::Rgb8,
+            10,
+            Endianness::Native,
+            Unstoppable
+        )
+        .is_ok()
+    );
+}
+
+#[test]
+fn limits_apply() {
+    let data = [0u8; 32];
+    let limits = Limits {
+        max_pixels: Some(3),
+        ..Default::default()
+    };
+    let err = decode_raw_with_limits(
+        &data,
+        2,
+        2,
+        PixelLayout::Gray8,
+        2,
+        Endianness::Native,
+        &limits,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+
+    // Byte-swapped copies count against max_memory_bytes; borrowed padded
+    // rows allocate nothing.
+    let foreign = if cfg!(target_endian = "little") {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    };
+    let limits = Limits {
+        max_memory_bytes: Some(7),
+        ..Default::default()
+    };
+    let err = decode_raw_with_limits(
+        &data,
+        2,
+        2,
+        PixelLayout::Gray16,
+        6,
+        foreign,
+        &limits,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    let limits = Limits {
+        max_memory_bytes: Some(3),
+        ..Default::default()
+    };
+    assert!(
+        decode_raw_with_limits(
+            &data,
+            2,
+            2,
+            PixelLayout::Gray8,
+            4,
+            Endianness::Native,
+            &limits,
+            Unstoppable,
+        )
+        .unwrap()
+        .is_borrowed()
+    );
+}
@@ -0,0 +1,150 @@
+//! Crop-window decoding (`decode_region`).
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+mod common;
+use common::gradient;
+
+/// Crop a full decode the slow way.
+fn crop(full: &DecodeOutput<'_>, x: u32, y: u32, w: u32, h: u32) -> Vec<u8> {
+    let bpp = full.layout.bytes_per_pixel();
+    let row = full.width as usize * bpp;
+    (y as usize..(y + h) as usize)
+        .flat_map(|r| {
+            let start = r * row + x as usize * bpp;
+            full.pixels()[start..start + w as usize * bpp].to_vec()
+        })
+        .collect()
+}
+
+/// Every window on a coarse grid must match the cropped full decode.
+fn assert_regions_match(data: &[u8]) {
+    let full = decode(data, Unstoppable).unwrap();
+    let (fw, fh) = (full.width, full.height);
+    for (x, y, w, h) in [
+        (0, 0, fw, fh),
+        (0, 0, 1, 1),
+        (fw - 1, fh - 1, 1, 1),
+        (fw / 3, fh / 3, fw - fw / 3, fh / 2),
+        (1.min(fw - 1), 0, fw - 1.min(fw - 1), fh),
+    ] {
+        let region = decode_region(data, x, y, w, h, Unstoppable)
+            .unwrap_or_else(|e| panic!("{w}x{h} at ({x}, {y}): {e}"));
+        assert_eq!((region.width, region.height), (w, h));
+        assert_eq!(region.layout, full.layout);
+        assert_eq!(
+            region.pixels(),
+            &crop(&full, x, y, w, h)[..],
+            "{w}x{h} at ({x}, {y})"
+        );
+    }
+}
+
+#[test]
+fn binary_pnm_windows() {
+    let rgb = gradient(7, 5, 3);
+    assert_regions_match(&encode_ppm(&rgb, 7, 5, PixelLayout::Rgb8, Unstoppable).unwrap());
+    assert_regions_match(&encode_pam(&rgb, 7, 5, PixelLayout::Rgb8, Unstoppable).unwrap());
+    let gray16 = gradient(6, 4, 2);
+    assert_regions_match(&encode_pam(&gray16, 6, 4, PixelLayout::Gray16, Unstoppable).unwrap());
+    let floats: Vec<u8> = (0..5 * 3 * 3)
+        .flat_map(|i| (i as f32 / 8.0).to_ne_bytes())
+        .collect();
+    assert_regions_match(&encode_pfm(&floats, 5, 3, PixelLayout::RgbF32, Unstoppable).unwrap());
+}
+
+#[test]
+fn bitpacked_and_ascii_pnm_windows() {
+    // P4: 10 pixels per row → 2 stored bytes.
+    let mut p4 = b"P4\n10 3\n".to_vec();
+    p4.extend_from_slice(&[0b1010_1010, 0b1100_0000, 0xFF, 0x00, 0x0F, 0x40]);
+    assert_regions_match(&p4);
+    assert_regions_match(b"P3\n3 2\n255\n1 2 3 4 5 6 7 8 9\n10 11 12 13 14 15 16 17 18\n");
+    assert_regions_match(b"P2\n3 2\n100\n0 50 100\n25 75 100\n");
+}
+
+#[test]
+fn full_width_eight_bit_window_is_borrowed() {
+    let data = encode_pgm(&gradient(4, 6, 1), 4, 6, PixelLayout::Gray8, Unstoppable).unwrap();
+    assert!(
+        decode_region(&data, 0, 2, 4, 3, Unstoppable)
+            .unwrap()
+            .is_borrowed()
+    );
+    assert!(
+        !decode_region(&data, 1, 2, 3, 3, Unstoppable)
+            .unwrap()
+            .is_borrowed()
+    );
+}
+
+#[test]
+fn rejects_windows_outside_the_image() {
+    let data = encode_pgm(&[0; 4], 2, 2, PixelLayout::Gray8, Unstoppable).unwrap();
+    for (x, y, w, h) in [
+        (0, 0, 0, 1),
+        (0, 0, 3, 1),
+        (1, 1, 2, 1),
+        (0, u32::MAX, 1, 2),
+    ] {
+        let err = decode_region(&data, x, y, w, h, Unstoppable).unwrap_err();
+        assert!(matches!(err.error(), BitmapError::InvalidData(..)), "{err}");
+    }
+}
+
+#[test]
+fn other_formats_are_cropped() {
+    let rgba16 = gradient(3, 3, 8);
+    let ff = encode_farbfeld(&rgba16, 3, 3, PixelLayout::Rgba16, Unstoppable).unwrap();
+    assert_regions_match(&ff);
+}
+
+#[cfg(feature = "bmp")]
+mod bmp {
+    use super::*;
+    use crate::common::fixture;
+
+    #[test]
+    fn fixture_windows() {
+        for name in [
+            "pal1.bmp",
+            "pal4.bmp",
+            "pal4rle.bmp",
+            "pal8.bmp",
+            "pal8os2.bmp",
+            "pal8rle.bmp",
+            "pal8topdown.bmp",
+            "rgb16.bmp",
+            "rgb16-565.bmp",
+            "rgb24.bmp",
+            "rgb32.bmp",
+            "rgba32abf.bmp",
+        ] {
+            assert_regions_match(&fixture(name));
+        }
+    }
+
+    #[test]
+    fn encoded_windows() {
+        let rgb = gradient(9, 7, 3);
+        assert_regions_match(&encode_bmp(&rgb, 9, 7, PixelLayout::Rgb8, Unstoppable).unwrap());
+        let rgba = gradient(5, 4, 4);
+        assert_regions_match(
+            &encode_bmp_rgba(&rgba, 5, 4, PixelLayout::Rgba8, Unstoppable).unwrap(),
+        );
+    }
+
+    #[test]
+    fn limits_apply_to_the_window() {
+        let data = encode_bmp(&gradient(8, 8, 3), 8, 8, PixelLayout::Rgb8, Unstoppable).unwrap();
+        let limits = Limits {
+            max_pixels: Some(16),
+            ..Default::default()
+        };
+        assert!(decode_bmp_with_limits(&data, &limits, Unstoppable).is_err());
+        let region = decode_region_with_limits(&data, 2, 2, 4, 4, &limits, Unstoppable).unwrap();
+        assert_eq!(region.pixels().len(), 4 * 4 * 3);
+        assert!(decode_region_with_limits(&data, 0, 0, 5, 4, &limits, Unstoppable).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Typed 16-bit pixel decode (`RGBA16`, `RGB16`, `GRAY16`).
+#![cfg(feature = "imgref")]
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn rgba16() -> Vec<RGBA16> {
+    (0..6u16)
+        .map(|i| RGBA16::new(i * 1000, 65535 - i, i * 257, 40000 + i))
+        .collect()
+}
+
+fn farbfeld(pixels: &[RGBA16]) -> Vec<u8> {
+    let bytes: Vec<u8> = pixels
+        .iter()
+        .flat_map(|p| [p.r, p.g, p.b, p.a])
+        .flat_map(u16::to_ne_bytes)
+        .collect();
+    encode_farbfeld(&bytes, 3, 2, PixelLayout::Rgba16, Unstoppable).unwrap()
+}
+
+#[test]
+fn farbfeld_decodes_to_rgba16() {
+    let pixels = rgba16();
+    let data = farbfeld(&pixels);
+    let (decoded, w, h) = decode_pixels::<RGBA16>(&data, Unstoppable).unwrap();
+    assert_eq!((w, h), (3, 2));
+    assert_eq!(decoded, pixels);
+
+    let img = decode_img::<RGBA16>(&data, Unstoppable).unwrap();
+    assert_eq!(img.buf(), &pixels);
+
+    let output = decode_farbfeld(&data, Unstoppable).unwrap();
+    assert_eq!(output.as_pixels::<RGBA16>().unwrap(), &pixels[..]);
+    assert_eq!(*output.as_imgref::<RGBA16>().unwrap().buf(), &pixels[..]);
+    assert_eq!(output.to_imgvec::<RGBA16>().unwrap().buf(), &pixels);
+}
+
+#[test]
+fn pgm16_decodes_to_gray16() {
+    let mut data = b"P5\n3 1\n65535\n".to_vec();
+    for v in [0u16, 0x1234, 65535] {
+        data.extend_from_slice(&v.to_be_bytes());
+    }
+    let (decoded, w, h) = decode_pixels::<GRAY16>(&data, Unstoppable).unwrap();
+    assert_eq!((w, h), (3, 1));
+    let values: Vec<u16> = decoded.iter().map(|g| g.value()).collect();
+    assert_eq!(values, [0, 0x1234, 65535]);
+}
+
+#[test]
+fn decode_into_strided_rgba16() {
+    let pixels = rgba16();
+    let data = farbfeld(&pixels);
+    let mut buf = vec![RGBA16::new(1, 1, 1, 1); 5 * 2];
+    let img = imgref::ImgRefMut::new_stride(&mut buf, 3, 2, 5);
+    decode_into(&data, img, Unstoppable).unwrap();
+    assert_eq!(&buf[..3], &pixels[..3]);
+    assert_eq!(&buf[5..8], &pixels[3..]);
+    assert_eq!(buf[3], RGBA16::new(1, 1, 1, 1));
+}
+
+#[test]
+fn layout_must_match() {
+    let data = farbfeld(&rgba16());
+    for err in [
+        decode_pixels::<RGB16>(&data, Unstoppable).unwrap_err(),
+        decode_pixels::<RGBA8>(&data, Unstoppable).unwrap_err(),
+    ] {
+        assert!(
+            matches!(
+                err.error(),
+                BitmapError::LayoutMismatch {
+                    actual: PixelLayout::Rgba16,
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+    let ppm = encode_ppm(&[0u8; 3], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let err = decode_pixels::<RGB16>(&ppm, Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), BitmapError::LayoutMismatch { .. }),
+        "{err}"
+    );
+}
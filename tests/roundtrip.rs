@@ -506,7 +506,7 @@ fn gray16_binary_truncated_errors() {
     assert!(
         matches!(
             result.as_ref().map_err(|e| e.error()),
-            Err(BitmapError::UnexpectedEof)
+            Err(BitmapError::UnexpectedEof(_))
         ),
         "truncated binary Gray16 must return UnexpectedEof, got {result:?}"
     );
@@ -607,6 +607,14 @@ fn encode_ppm_from_non_rgb_layouts() {
         PixelLayout::Bgrx8,
         &[200, 150, 100],
     );
+    // Rgbx8 → ignore padding byte.
+    assert_ppm_rgb(
+        &[100, 150, 200, 0],
+        1,
+        1,
+        PixelLayout::Rgbx8,
+        &[100, 150, 200],
+    );
     // Gray8 → replicate to 3 channels.
     assert_ppm_rgb(&[128], 1, 1, PixelLayout::Gray8, &[128, 128, 128]);
 }
@@ -786,7 +794,7 @@ fn pfm_truncated_pixel_data_errors() {
     assert!(
         matches!(
             decode(&data, Unstoppable).as_ref().map_err(|e| e.error()),
-            Err(BitmapError::UnexpectedEof)
+            Err(BitmapError::UnexpectedEof(_))
         ),
         "truncated PFM must return UnexpectedEof"
     );
@@ -1195,6 +1203,40 @@ fn detect_format_unknown() {
     assert_eq!(detect_format(b"JPEG"), None);
 }
 
+#[test]
+fn identify_foreign_formats() {
+    let cases: [(&[u8], ForeignFormat); 11] = [
+        (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", ForeignFormat::Png),
+        (b"\xFF\xD8\xFF\xE0\0\x10JFIF\0", ForeignFormat::Jpeg),
+        (b"GIF89a\x01\0\x01\0", ForeignFormat::Gif),
+        (b"RIFF\x24\0\0\0WEBPVP8 ", ForeignFormat::WebP),
+        (b"II*\0\x08\0\0\0", ForeignFormat::Tiff),
+        (b"MM\0*\0\0\0\x08", ForeignFormat::Tiff),
+        (b"\xFF\x0A\xFA\x7F", ForeignFormat::JpegXl),
+        (b"\0\0\0\x0CJXL \r\n\x87\n", ForeignFormat::JpegXl),
+        (b"\0\0\0\x1CftypavifPLAIN", ForeignFormat::Avif),
+        (b"\0\0\0\x0CjP  \r\n\x87\n", ForeignFormat::Jpeg2000),
+        (b"\xFF\x4F\xFF\x51\0\x2F", ForeignFormat::Jpeg2000),
+    ];
+    for (data, format) in cases {
+        let identified = identify(data).unwrap();
+        assert_eq!(identified, Identified::Foreign(format), "{}", format.name());
+        assert!(!identified.is_decodable());
+        assert_eq!(detect_format(data), None, "{}", format.name());
+    }
+
+    let ppm = encode_ppm(&[255u8; 6], 2, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let identified = identify(&ppm).unwrap();
+    assert_eq!(identified, Identified::Supported(ImageFormat::Pnm));
+    assert!(identified.is_decodable());
+    assert_eq!(
+        identify(b"qoif\0\0\0\x01").map(Identified::is_decodable),
+        Some(cfg!(feature = "qoi"))
+    );
+    assert_eq!(identify(b"JPEG"), None);
+    assert_eq!(identify(b"RIFF\x24\0\0\0WAVEfmt "), None);
+}
+
 #[test]
 fn decode_unrecognized_format() {
     let result = decode(b"NOTAFORMAT", Unstoppable);
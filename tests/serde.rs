@@ -0,0 +1,58 @@
+//! Probe results, decode reports and limits round-trip through serde.
+#![cfg(feature = "serde")]
+
+use zenbitmaps::*;
+
+#[test]
+fn layouts_and_formats_serialize_by_name() {
+    assert_eq!(
+        serde_json::to_string(&PixelLayout::Rgba16).unwrap(),
+        "\"Rgba16\""
+    );
+    assert_eq!(
+        serde_json::from_str::<ImageFormat>("\"Farbfeld\"").unwrap(),
+        ImageFormat::Farbfeld
+    );
+}
+
+#[test]
+fn limits_round_trip_without_the_hook() {
+    let limits = Limits::new()
+        .with_max_dimensions(640, 480)
+        .with_max_file_size(1 << 20)
+        .with_on_alloc(|_| true);
+    let json = serde_json::to_string(&limits).unwrap();
+    assert!(!json.contains("on_alloc"), "{json}");
+    let back: Limits = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.max_width, Some(640));
+    assert_eq!(back.max_file_size, Some(1 << 20));
+    assert!(back.on_alloc.is_none());
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn bmp_probe_and_report_serialize() {
+    let pixels: Vec<u8> = (0..16 * 16 * 3).map(|i| (i % 251) as u8).collect();
+    let bmp = encode_bmp(&pixels, 16, 16, PixelLayout::Rgb8, Unstoppable).unwrap();
+
+    let meta = probe_bmp(&bmp).unwrap();
+    let json = serde_json::to_value(&meta).unwrap();
+    assert_eq!(json["width"], 16);
+    assert_eq!(json["layout"], "Rgb8");
+    assert_eq!(json["compression"], "Rgb");
+    let back: BmpMetadata = serde_json::from_value(json).unwrap();
+    assert_eq!(back.bit_count, meta.bit_count);
+    assert_eq!(back.header_variant, meta.header_variant);
+
+    let truncated = &bmp[..bmp.len() - 100];
+    let (_, report) = decode_bmp_with_report(
+        truncated,
+        &BmpDecodeOptions::new().with_permissiveness(BmpPermissiveness::Permissive),
+        Unstoppable,
+    )
+    .unwrap();
+    assert!(!report.is_clean());
+    let json = serde_json::to_string(&report).unwrap();
+    let back: DecodeReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, report);
+}
@@ -0,0 +1,157 @@
+#![cfg(feature = "sixel")]
+//! Sixel encoding, checked by parsing the output back.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// Parse a sixel stream into (width, height) and per-pixel register colors
+/// in percent, `None` for pixels never drawn.
+fn parse(data: &[u8]) -> ((usize, usize), Vec<Option<[u32; 3]>>) {
+    let body = data
+        .strip_prefix(b"\x1bP0;1;0q\"1;1;")
+        .and_then(|d| d.strip_suffix(b"\x1b\\"))
+        .expect("DCS framing");
+    let text = std::str::from_utf8(body).unwrap();
+    let mut chars = text.chars().peekable();
+    let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        let mut n = 0usize;
+        while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+            n = n * 10 + d as usize;
+            chars.next();
+        }
+        n
+    };
+    let width = number(&mut chars);
+    assert_eq!(chars.next(), Some(';'));
+    let height = number(&mut chars);
+    let mut pixels = vec![None; width * height];
+    let mut registers = std::collections::HashMap::new();
+    let (mut x, mut band, mut color) = (0, 0, 0);
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                color = number(&mut chars);
+                if chars.peek() == Some(&';') {
+                    chars.next();
+                    assert_eq!(number(&mut chars), 2);
+                    let mut rgb = [0; 3];
+                    for v in &mut rgb {
+                        assert_eq!(chars.next(), Some(';'));
+                        *v = number(&mut chars) as u32;
+                    }
+                    registers.insert(color, rgb);
+                }
+            }
+            '$' => x = 0,
+            '-' => (x, band) = (0, band + 6),
+            '!' | '?'..='~' => {
+                let count = if c == '!' { number(&mut chars) } else { 1 };
+                let c = if c == '!' { chars.next().unwrap() } else { c };
+                let bits = c as u8 - b'?';
+                for _ in 0..count {
+                    for dy in 0..6 {
+                        if bits & 1 << dy != 0 {
+                            pixels[(band + dy) * width + x] = Some(registers[&color]);
+                        }
+                    }
+                    x += 1;
+                }
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+    ((width, height), pixels)
+}
+
+fn percent(v: u8) -> u32 {
+    (u32::from(v) * 100 + 127) / 255
+}
+
+#[test]
+fn exact_colors_round_trip() {
+    // 13 rows spans three bands; long runs exercise `!count`
+    let (w, h) = (9, 13);
+    let mut pixels = Vec::new();
+    for y in 0..h {
+        for x in 0..w {
+            let rgb = match (x + y) % 4 {
+                0 => [255, 0, 0],
+                1 => [0, 128, 0],
+                2 => [10, 20, 250],
+                _ => [255, 255, 255],
+            };
+            pixels.extend_from_slice(&if y < 7 { [255, 255, 255] } else { rgb });
+        }
+    }
+    let options = SixelEncodeOptions::new();
+    let data = encode_sixel(&pixels, w, h, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+    assert!(data.windows(3).any(|s| s == b"!9~"));
+    let ((pw, ph), decoded) = parse(&data);
+    assert_eq!((pw, ph), (w as usize, h as usize));
+    for (px, got) in pixels.chunks_exact(3).zip(&decoded) {
+        assert_eq!(*got, Some([percent(px[0]), percent(px[1]), percent(px[2])]));
+    }
+}
+
+#[test]
+fn layouts_and_transparency() {
+    let rgba = [9, 8, 7, 255, 1, 2, 3, 0, 200, 100, 50, 128];
+    let bgra = [7, 8, 9, 255, 3, 2, 1, 0, 50, 100, 200, 128];
+    let expected = [Some([4, 3, 3]), None, Some([78, 39, 20])];
+    for (pixels, layout) in [(&rgba[..], PixelLayout::Rgba8), (&bgra, PixelLayout::Bgra8)] {
+        let data = encode_sixel(
+            pixels,
+            3,
+            1,
+            layout,
+            &SixelEncodeOptions::new(),
+            Unstoppable,
+        )
+        .unwrap();
+        assert_eq!(parse(&data).1, expected, "{layout:?}");
+    }
+
+    let gray16: Vec<u8> = [0x8000u16, 0xFFFF]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    let data = encode_sixel(
+        &gray16,
+        1,
+        2,
+        PixelLayout::Gray16,
+        &SixelEncodeOptions::new(),
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(parse(&data).1, [Some([50; 3]), Some([100; 3])]);
+}
+
+#[test]
+fn palette_is_limited() {
+    let pixels: Vec<u8> = (0..=255u8).flat_map(|v| [v, v, 255 - v]).collect();
+    for max in [1, 2, 16, 256] {
+        let options = SixelEncodeOptions::new().with_max_colors(max);
+        let data = encode_sixel(&pixels, 16, 16, PixelLayout::Rgb8, &options, Unstoppable).unwrap();
+        let colors: std::collections::HashSet<_> = parse(&data).1.into_iter().collect();
+        assert!(colors.len() <= usize::from(max), "{max}: {}", colors.len());
+        assert!(!colors.contains(&None));
+    }
+}
+
+#[test]
+fn errors() {
+    let options = SixelEncodeOptions::new();
+    let err = encode_sixel(&[0; 12], 1, 1, PixelLayout::RgbF32, &options, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    let err = encode_sixel(&[0; 5], 2, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+    let err = encode_sixel(&[], 0, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    for max in [0, 257] {
+        let options = options.with_max_colors(max);
+        let err =
+            encode_sixel(&[0; 3], 1, 1, PixelLayout::Rgb8, &options, Unstoppable).unwrap_err();
+        assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    }
+}
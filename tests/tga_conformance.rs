@@ -396,6 +396,101 @@ fn bottom_right_origin() {
     assert_eq!(&p[3..6], &[90, 80, 70]); // was leftmost pixel
 }
 
+// ══════════════════════════════════════════════════════════════════════
+// Attribute bits and the TGA 2.0 extension area
+// ══════════════════════════════════════════════════════════════════════
+
+/// Append a 495-byte extension area with `attributes_type` and the footer.
+fn with_extension_area(mut data: Vec<u8>, attributes_type: u8) -> Vec<u8> {
+    let ext_offset = data.len() as u32;
+    let mut area = vec![0u8; 495];
+    area[..2].copy_from_slice(&495u16.to_le_bytes());
+    area[494] = attributes_type;
+    data.extend_from_slice(&area);
+    data.extend_from_slice(&ext_offset.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // no developer directory
+    data.extend_from_slice(b"TRUEVISION-XFILE.\0");
+    data
+}
+
+#[test]
+fn type2_16bit_attribute_bit_alpha() {
+    // 1 attribute bit: bit 15 is alpha
+    let pixels: Vec<u8> = [0xFC00u16, 0x7C00]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let data = build_simple_tga(2, 2, 1, 16, 0x21, &pixels);
+    let decoded = decode_tga(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[255, 0, 0, 255, 255, 0, 0, 0]);
+}
+
+#[test]
+fn type2_24bit_with_attribute_bits_is_opaque() {
+    let data = build_simple_tga(2, 1, 1, 24, 0x28, &[30, 20, 10]);
+    let decoded = decode_tga(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[10, 20, 30, 255]);
+}
+
+#[test]
+fn extension_area_no_alpha() {
+    // Attributes type 0 (no alpha) and 1 (undefined, ignore): attribute bytes are dropped
+    for attributes_type in [0, 1] {
+        let data = with_extension_area(
+            build_simple_tga(2, 1, 1, 32, 0x28, &[30, 20, 10, 0]),
+            attributes_type,
+        );
+        let decoded = decode_tga(&data, Unstoppable).unwrap();
+        assert_eq!(
+            decoded.pixels(),
+            &[10, 20, 30, 255],
+            "type {attributes_type}"
+        );
+    }
+}
+
+#[test]
+fn extension_area_straight_alpha() {
+    for attributes_type in [2, 3] {
+        let data = with_extension_area(
+            build_simple_tga(2, 1, 1, 32, 0x28, &[30, 20, 10, 128]),
+            attributes_type,
+        );
+        let decoded = decode_tga(&data, Unstoppable).unwrap();
+        assert_eq!(
+            decoded.pixels(),
+            &[10, 20, 30, 128],
+            "type {attributes_type}"
+        );
+    }
+}
+
+#[test]
+fn extension_area_premultiplied_alpha() {
+    // Premultiplied BGRA (0, 64, 128, 128) and (9, 9, 9, 0)
+    let pixels = [0, 64, 128, 128, 9, 9, 9, 0];
+    let data = build_simple_tga(10, 2, 1, 32, 0x28, &[0x01, 0, 64, 128, 128, 9, 9, 9, 0]);
+    let raw = build_simple_tga(2, 2, 1, 32, 0x28, &pixels);
+    for data in [data, raw] {
+        let data = with_extension_area(data, 4);
+        let decoded = decode_tga(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.pixels(), &[255, 128, 0, 128, 9, 9, 9, 0]);
+    }
+}
+
+#[test]
+fn malformed_footer_is_ignored() {
+    let data = build_simple_tga(2, 1, 1, 32, 0x28, &[30, 20, 10, 0]);
+    let mut data = with_extension_area(data, 0);
+    // Extension area offset past the end of the file
+    let footer = data.len() - 26;
+    data[footer..footer + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let decoded = decode_tga(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[10, 20, 30, 0]);
+}
+
 // ══════════════════════════════════════════════════════════════════════
 // Image ID field
 // ══════════════════════════════════════════════════════════════════════
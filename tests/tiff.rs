@@ -0,0 +1,404 @@
+#![cfg(feature = "tiff")]
+//! Baseline uncompressed TIFF tests: synthetic files, and encoder round trips.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+
+/// Build a TIFF: header, the strips in order, then one IFD holding `tags`
+/// plus generated StripOffsets / StripByteCounts.
+fn tiff(big: bool, tags: &[(u16, u16, &[u32])], strips: &[&[u8]]) -> Vec<u8> {
+    let u16b = |v: u16| {
+        if big {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let u32b = |v: u32| {
+        if big {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let mut out = if big {
+        b"MM\0*".to_vec()
+    } else {
+        b"II*\0".to_vec()
+    };
+    out.extend_from_slice(&[0; 4]);
+    let mut offsets = Vec::new();
+    for strip in strips {
+        offsets.push(out.len() as u32);
+        out.extend_from_slice(strip);
+    }
+    let counts: Vec<u32> = strips.iter().map(|s| s.len() as u32).collect();
+    let mut tags = tags.to_vec();
+    tags.push((273, LONG, &offsets));
+    tags.push((279, LONG, &counts));
+    tags.sort_by_key(|t| t.0);
+
+    let ifd = out.len() as u32;
+    out[4..8].copy_from_slice(&u32b(ifd));
+    let mut extra_at = ifd as usize + 2 + tags.len() * 12 + 4;
+    let mut extra = Vec::new();
+    out.extend_from_slice(&u16b(tags.len() as u16));
+    for (tag, kind, values) in &tags {
+        out.extend_from_slice(&u16b(*tag));
+        out.extend_from_slice(&u16b(*kind));
+        out.extend_from_slice(&u32b(values.len() as u32));
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|&v| match *kind {
+                SHORT => u16b(v as u16).to_vec(),
+                _ => u32b(v).to_vec(),
+            })
+            .collect();
+        if bytes.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..bytes.len()].copy_from_slice(&bytes);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&u32b(extra_at as u32));
+            extra_at += bytes.len();
+            extra.extend_from_slice(&bytes);
+        }
+    }
+    out.extend_from_slice(&[0; 4]); // no next IFD
+    out.extend_from_slice(&extra);
+    out
+}
+
+fn ne16(values: &[u16]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
+#[test]
+fn gray8_is_borrowed() {
+    let pixels = [0u8, 64, 128, 255, 1, 2];
+    let data = tiff(
+        false,
+        &[
+            (256, SHORT, &[3]),
+            (257, SHORT, &[2]),
+            (258, SHORT, &[8]),
+            (262, SHORT, &[1]),
+        ],
+        &[&pixels],
+    );
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Gray8);
+    assert_eq!((decoded.width, decoded.height), (3, 2));
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &pixels);
+}
+
+#[test]
+fn rgb8_strips() {
+    let rows: [&[u8]; 3] = [
+        &[1, 2, 3, 4, 5, 6],
+        &[7, 8, 9, 10, 11, 12],
+        &[13, 14, 15, 16, 17, 18],
+    ];
+    let tags: [(u16, u16, &[u32]); 6] = [
+        (256, LONG, &[2]),
+        (257, LONG, &[3]),
+        (258, SHORT, &[8, 8, 8]),
+        (262, SHORT, &[2]),
+        (277, SHORT, &[3]),
+        (278, SHORT, &[2]),
+    ];
+    // Two strips of two rows and one row, contiguous: borrowed
+    let two = [rows[0], rows[1]].concat();
+    let data = tiff(true, &tags, &[&two, rows[2]]);
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), rows.concat());
+
+    // Strips stored out of order are gathered by offset
+    let mut data = tiff(true, &tags, &[rows[2], &two]);
+    let ifd = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+    for (tag, values) in [
+        (273u16, [0, 0, 0, 14, 0, 0, 0, 8]),
+        (279, [0, 0, 0, 12, 0, 0, 0, 6]),
+    ] {
+        let entry = (0..16)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&e| data[e..e + 2] == tag.to_be_bytes())
+            .unwrap();
+        let at = u32::from_be_bytes(data[entry + 8..entry + 12].try_into().unwrap()) as usize;
+        data[at..at + 8].copy_from_slice(&values);
+    }
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert!(!decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), rows.concat());
+}
+
+#[test]
+fn sixteen_bit_both_byte_orders() {
+    let values = [0x0102u16, 0xFFFF, 0x8000, 0, 0x1234, 0xABCD];
+    for big in [false, true] {
+        let raw: Vec<u8> = values
+            .iter()
+            .flat_map(|v| {
+                if big {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            })
+            .collect();
+        let data = tiff(
+            big,
+            &[
+                (256, SHORT, &[2]),
+                (257, SHORT, &[1]),
+                (258, SHORT, &[16, 16, 16]),
+                (262, SHORT, &[2]),
+                (277, SHORT, &[3]),
+            ],
+            &[&raw],
+        );
+        let decoded = decode_tiff(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::Rgb16);
+        assert_eq!(decoded.pixels(), ne16(&values), "big endian: {big}");
+    }
+}
+
+#[test]
+fn alpha() {
+    let header = |samples: u32, bits: u32, extra: u32| -> Vec<(u16, u16, Vec<u32>)> {
+        let photometric = if samples >= 3 { 2 } else { 1 };
+        vec![
+            (256, SHORT, vec![2]),
+            (257, SHORT, vec![1]),
+            (258, SHORT, vec![bits; samples as usize]),
+            (262, SHORT, vec![photometric]),
+            (277, SHORT, vec![samples]),
+            (338, SHORT, vec![extra]),
+        ]
+    };
+    let build = |tags: &[(u16, u16, Vec<u32>)], pixels: &[u8]| {
+        let tags: Vec<(u16, u16, &[u32])> = tags.iter().map(|(t, k, v)| (*t, *k, &v[..])).collect();
+        tiff(false, &tags, &[pixels])
+    };
+
+    // Straight RGBA: borrowed as is
+    let pixels = [10, 20, 30, 128, 1, 2, 3, 255];
+    let data = build(&header(4, 8, 2), &pixels);
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert!(decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &pixels);
+
+    // Premultiplied RGBA: converted to straight
+    let data = build(&header(4, 8, 1), &[64, 32, 0, 128, 0, 0, 0, 0]);
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[128, 64, 0, 128, 0, 0, 0, 0]);
+
+    // 8-bit gray + alpha expands to RGBA; 16-bit stays GrayA16
+    let data = build(&header(2, 8, 2), &[9, 200, 77, 0]);
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[9, 9, 9, 200, 77, 77, 77, 0]);
+    let raw: Vec<u8> = [1000u16, 2000, 3000, 4000]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let data = build(&header(2, 16, 2), &raw);
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::GrayA16);
+    assert_eq!(decoded.pixels(), ne16(&[1000, 2000, 3000, 4000]));
+}
+
+#[test]
+fn white_is_zero_inverts() {
+    let data = tiff(
+        false,
+        &[
+            (256, SHORT, &[3]),
+            (257, SHORT, &[1]),
+            (258, SHORT, &[8]),
+            (262, SHORT, &[0]),
+        ],
+        &[&[0, 100, 255]],
+    );
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert!(!decoded.is_borrowed());
+    assert_eq!(decoded.pixels(), &[255, 155, 0]);
+}
+
+#[test]
+fn errors() {
+    let gray = |extra: &[(u16, u16, &'static [u32])], pixels: &[u8]| {
+        let mut tags: Vec<(u16, u16, &[u32])> = vec![
+            (256, SHORT, &[2]),
+            (257, SHORT, &[2]),
+            (258, SHORT, &[8]),
+            (262, SHORT, &[1]),
+        ];
+        for e in extra {
+            tags.retain(|t| t.0 != e.0);
+            tags.push(*e);
+        }
+        tiff(false, &tags, &[pixels])
+    };
+    type Check = fn(&BitmapError) -> bool;
+    let unsupported: Check = |e| matches!(e, BitmapError::UnsupportedVariant(_));
+    let invalid: Check = |e| matches!(e, BitmapError::InvalidHeader(..));
+    let cases: [(Vec<u8>, Check); 11] = [
+        (gray(&[(259, SHORT, &[5])], &[0; 4]), unsupported),
+        (gray(&[(322, SHORT, &[16])], &[0; 4]), unsupported),
+        (gray(&[(258, SHORT, &[4])], &[0; 4]), unsupported),
+        (gray(&[(262, SHORT, &[3])], &[0; 4]), unsupported),
+        (gray(&[(339, SHORT, &[3])], &[0; 4]), unsupported),
+        (
+            gray(
+                &[(262, SHORT, &[2]), (277, SHORT, &[3]), (284, SHORT, &[2])],
+                &[0; 12],
+            ),
+            unsupported,
+        ),
+        (b"II+\0\x08\0\0\0".to_vec(), unsupported),
+        (gray(&[(257, SHORT, &[0])], &[]), invalid),
+        (b"IX*\0\x08\0\0\0".to_vec(), |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (gray(&[], &[0; 3]), |e| {
+            matches!(e, BitmapError::InvalidData(..))
+        }),
+        (gray(&[], &[0; 4])[..12].to_vec(), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+    ];
+    for (i, (data, check)) in cases.into_iter().enumerate() {
+        let err = decode_tiff(&data, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let data = tiff(
+        false,
+        &[
+            (256, SHORT, &[4]),
+            (257, SHORT, &[4]),
+            (258, SHORT, &[8]),
+            (262, SHORT, &[0]),
+        ],
+        &[&[0; 16]],
+    );
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_tiff_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+    let limits = Limits {
+        max_memory_bytes: Some(15),
+        ..Default::default()
+    };
+    let err = decode_tiff_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
+
+#[test]
+fn encode_round_trips() {
+    let rgba: Vec<u8> = (0..5 * 3 * 4).map(|i| (i * 7) as u8).collect();
+    let rgb: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    let gray: Vec<u8> = rgba.iter().step_by(4).copied().collect();
+    let wide: Vec<u16> = (0..5 * 3 * 4).map(|i| i * 1093).collect();
+    let cases: [(PixelLayout, Vec<u8>); 7] = [
+        (PixelLayout::Gray8, gray),
+        (PixelLayout::Rgb8, rgb),
+        (PixelLayout::Rgba8, rgba),
+        (PixelLayout::Gray16, ne16(&wide[..15])),
+        (PixelLayout::GrayA16, ne16(&wide[..30])),
+        (PixelLayout::Rgb16, ne16(&wide[..45])),
+        (PixelLayout::Rgba16, ne16(&wide)),
+    ];
+    for (layout, pixels) in &cases {
+        for rows in [0, 1, 2, 3, 100] {
+            for order in [Endianness::Little, Endianness::Big, Endianness::Native] {
+                let options = TiffEncodeOptions::new()
+                    .with_rows_per_strip(rows)
+                    .with_byte_order(order);
+                let data = encode_tiff_with(pixels, 5, 3, *layout, &options, Unstoppable).unwrap();
+                let decoded = decode_tiff(&data, Unstoppable).unwrap();
+                let what = format!("{layout:?}, {rows} rows per strip, {order:?}");
+                assert_eq!(decoded.layout, *layout, "{what}");
+                assert_eq!((decoded.width, decoded.height), (5, 3), "{what}");
+                assert_eq!(decoded.pixels(), &pixels[..], "{what}");
+            }
+        }
+    }
+}
+
+#[test]
+fn encode_file_structure() {
+    let data = encode_tiff(&[1, 2, 3, 4], 2, 2, PixelLayout::Gray8, Unstoppable).unwrap();
+    assert_eq!(&data[..8], b"II*\0\x08\0\0\0");
+    // Single contiguous strip at the end: zero-copy on the way back
+    assert_eq!(&data[data.len() - 4..], &[1, 2, 3, 4]);
+    assert!(decode_tiff(&data, Unstoppable).unwrap().is_borrowed());
+
+    let options = TiffEncodeOptions::new()
+        .with_rows_per_strip(1)
+        .with_byte_order(Endianness::Big);
+    let data = encode_tiff_with(
+        &[1, 2, 3, 4],
+        2,
+        2,
+        PixelLayout::Gray8,
+        &options,
+        Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(&data[..4], b"MM\0*");
+    assert!(decode_tiff(&data, Unstoppable).unwrap().is_borrowed());
+}
+
+#[test]
+fn encode_swizzles_bgr() {
+    let bgra = [1u8, 2, 3, 4, 5, 6, 7, 8];
+    let data = encode_tiff(&bgra, 2, 1, PixelLayout::Bgra8, Unstoppable).unwrap();
+    let decoded = decode_tiff(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[3, 2, 1, 4, 7, 6, 5, 8]);
+
+    for layout in [PixelLayout::Bgr8, PixelLayout::Bgrx8, PixelLayout::Rgbx8] {
+        let data = encode_tiff(&bgra, 2, 1, layout, Unstoppable).unwrap();
+        let decoded = decode_tiff(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::Rgb8);
+        let expected: &[u8] = match layout {
+            PixelLayout::Bgr8 => &[3, 2, 1, 6, 5, 4],
+            PixelLayout::Bgrx8 => &[3, 2, 1, 7, 6, 5],
+            _ => &[1, 2, 3, 5, 6, 7],
+        };
+        assert_eq!(decoded.pixels(), expected, "{layout:?}");
+    }
+}
+
+#[test]
+fn encode_errors() {
+    let err = encode_tiff(&[0; 3], 2, 2, PixelLayout::Gray8, Unstoppable).unwrap_err();
+    assert!(matches!(
+        err.error(),
+        BitmapError::BufferTooSmall {
+            needed: 4,
+            actual: 3
+        }
+    ));
+    let err = encode_tiff(&[], 0, 2, PixelLayout::Gray8, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    let err = encode_tiff(&[0; 12], 1, 1, PixelLayout::RgbF32, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+}
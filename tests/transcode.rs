@@ -0,0 +1,137 @@
+//! `transcode()` decodes, picks a layout the target takes, and re-encodes.
+
+use enough::{Stop, StopReason, Unstoppable};
+use zenbitmaps::*;
+
+struct AlreadyStopped;
+
+impl Stop for AlreadyStopped {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        Err(StopReason::Cancelled)
+    }
+}
+
+fn rgb(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 3)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect()
+}
+
+fn transcode_to(data: &[u8], format: EncodeFormat) -> Vec<u8> {
+    transcode(data, format, &EncodeOptions::new(), Unstoppable).unwrap()
+}
+
+#[test]
+fn ppm_to_farbfeld() {
+    let pixels = rgb(5, 3);
+    let ppm = encode_ppm(&pixels, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let ff = transcode_to(&ppm, EncodeFormat::Farbfeld);
+    assert_eq!(
+        ff,
+        encode_farbfeld(&pixels, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap()
+    );
+}
+
+#[test]
+fn farbfeld_becomes_16_bit_pam() {
+    let samples: Vec<u16> = (0..2 * 2 * 4).map(|i| i * 4099).collect();
+    let pixels: Vec<u8> = samples.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    let ff = encode_farbfeld(&pixels, 2, 2, PixelLayout::Rgba16, Unstoppable).unwrap();
+
+    let pam = transcode_to(&ff, EncodeFormat::Pam);
+    let header = b"P7\nWIDTH 2\nHEIGHT 2\nDEPTH 4\nMAXVAL 65535\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+    assert_eq!(&pam[..header.len()], header);
+    let body: Vec<u8> = samples.iter().flat_map(|v| v.to_be_bytes()).collect();
+    assert_eq!(&pam[header.len()..], &body[..]);
+}
+
+#[test]
+fn pam_writes_16_bit_rgb_big_endian() {
+    let pixels: Vec<u8> = [0x0102u16, 0x0304, 0x0506]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    let pam = encode_pam(&pixels, 1, 1, PixelLayout::Rgb16, Unstoppable).unwrap();
+    let header = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 65535\nTUPLTYPE RGB\nENDHDR\n";
+    assert_eq!(&pam[..header.len()], header);
+    assert_eq!(&pam[header.len()..], &[1, 2, 3, 4, 5, 6]);
+    assert_eq!(
+        pam.len(),
+        estimate_encoded_size(1, 1, PixelLayout::Rgb16, EncodeFormat::Pam).unwrap()
+    );
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn options_reach_the_encoder() {
+    let ff = encode_farbfeld(&[255, 0, 0, 0], 1, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let options =
+        EncodeOptions::new().with_pnm(PnmEncodeOptions::new().with_background([0, 0, 255]));
+    let ppm = transcode(&ff, EncodeFormat::Ppm, &options, Unstoppable).unwrap();
+    assert_eq!(decode(&ppm, Unstoppable).unwrap().pixels(), &[0, 0, 255]);
+}
+
+#[test]
+fn limits_and_stop_apply() {
+    let ppm = encode_ppm(&rgb(32, 32), 32, 32, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let options = EncodeOptions::new();
+    let limits = Limits::new().with_max_pixels(100);
+    let err =
+        transcode_with_limits(&ppm, EncodeFormat::Pam, &options, &limits, Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::LimitExceeded);
+
+    let err = transcode(&ppm, EncodeFormat::Pam, &options, AlreadyStopped).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Cancelled);
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn converts_when_the_target_needs_it() {
+    let pixels = rgb(5, 3);
+    let ff = encode_farbfeld(&pixels, 5, 3, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let ppm = transcode_to(&ff, EncodeFormat::Ppm);
+    assert_eq!(decode(&ppm, Unstoppable).unwrap().pixels(), &pixels[..]);
+
+    let ppm = encode_ppm(&[255, 0, 0], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let pfm = transcode_to(&ppm, EncodeFormat::Pfm);
+    let decoded = decode(&pfm, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::RgbF32);
+    assert_eq!(decoded.pixels()[..4], 1.0f32.to_ne_bytes());
+
+    let ff = encode_farbfeld(&[0, 0, 0, 255], 1, 1, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let pfm = transcode_to(&ff, EncodeFormat::Pfm);
+    assert_eq!(
+        decode(&pfm, Unstoppable).unwrap().layout,
+        PixelLayout::RgbF32
+    );
+}
+
+#[cfg(not(feature = "convert"))]
+#[test]
+fn conversion_needs_the_feature() {
+    let ppm = encode_ppm(&[255, 0, 0], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let err = transcode(&ppm, EncodeFormat::Pfm, &EncodeOptions::new(), Unstoppable).unwrap_err();
+    assert_eq!(err.error().kind(), ErrorKind::Unsupported);
+}
+
+#[cfg(all(feature = "bmp", feature = "convert"))]
+#[test]
+fn bmp_gets_8_bit_unless_64_bit_is_asked_for() {
+    let pixels = [9, 8, 7, 128].repeat(4);
+    let ff = encode_farbfeld(&pixels, 2, 2, PixelLayout::Rgba8, Unstoppable).unwrap();
+    assert_eq!(
+        probe_bmp(&transcode_to(&ff, EncodeFormat::Bmp))
+            .unwrap()
+            .bit_count,
+        24
+    );
+
+    let options = EncodeOptions::new().with_bmp(BmpEncodeOptions::new().with_alpha(true));
+    let bmp = transcode(&ff, EncodeFormat::Bmp, &options, Unstoppable).unwrap();
+    assert_eq!(probe_bmp(&bmp).unwrap().bit_count, 32);
+    assert_eq!(decode(&bmp, Unstoppable).unwrap().pixels(), &pixels[..]);
+
+    let options = EncodeOptions::new().with_bmp(BmpEncodeOptions::new().with_bit_depth(64));
+    let bmp = transcode(&ff, EncodeFormat::Bmp, &options, Unstoppable).unwrap();
+    assert_eq!(probe_bmp(&bmp).unwrap().bit_count, 64);
+}
@@ -0,0 +1,69 @@
+//! The wasm-bindgen wrappers, exercised natively. Failures build a
+//! JavaScript `Error`, which needs a wasm host, so only success paths and
+//! the errors' absence are checked here.
+#![cfg(feature = "wasm")]
+
+use zenbitmaps::wasm;
+use zenbitmaps::*;
+
+fn rgb(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 3)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect()
+}
+
+#[test]
+fn encode_and_decode_by_name() {
+    let pixels = rgb(4, 3);
+    let ppm = wasm::encode(&pixels, 4, 3, "Rgb8", "ppm").ok().unwrap();
+    assert_eq!(
+        ppm,
+        encode_ppm(&pixels, 4, 3, PixelLayout::Rgb8, Unstoppable).unwrap()
+    );
+
+    let image = wasm::decode(&ppm).ok().unwrap();
+    assert_eq!((image.width(), image.height()), (4, 3));
+    assert_eq!(image.layout(), "Rgb8");
+    assert_eq!(image.bytes(), pixels);
+}
+
+#[test]
+fn decode_rgba8_is_ready_for_image_data() {
+    let ppm = encode_ppm(&[255, 128, 0], 1, 1, PixelLayout::Rgb8, Unstoppable).unwrap();
+    let image = wasm::decode_rgba8(&ppm).ok().unwrap();
+    assert_eq!(image.layout(), "Rgba8");
+    assert_eq!(image.bytes(), [255, 128, 0, 255]);
+}
+
+#[test]
+fn typed_sample_views() {
+    let samples = [0u16, 1000, 65535, 42];
+    let pixels: Vec<u8> = samples.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    let ff = encode_farbfeld(&pixels, 1, 1, PixelLayout::Rgba16, Unstoppable).unwrap();
+    let image = wasm::decode(&ff).ok().unwrap();
+    assert_eq!(image.layout(), "Rgba16");
+    assert_eq!(image.samples16().ok().unwrap(), samples);
+
+    let float: Vec<u8> = [0.5f32, 1.0, 2.0]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    let pfm = wasm::encode(&float, 1, 1, "RgbF32", "pfm").ok().unwrap();
+    let image = wasm::decode(&pfm).ok().unwrap();
+    assert_eq!(image.samples_f32().ok().unwrap(), [0.5, 1.0, 2.0]);
+}
+
+#[test]
+fn probe_reads_headers() {
+    let ff = encode_farbfeld(&[0; 8 * 4], 4, 2, PixelLayout::Rgba8, Unstoppable).unwrap();
+    let info = wasm::probe(&ff).ok().unwrap();
+    assert_eq!(info.format(), "farbfeld");
+    assert_eq!((info.width(), info.height()), (Some(4), Some(2)));
+    assert!(info.decodable());
+
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    let info = wasm::probe(png).ok().unwrap();
+    assert_eq!(info.format(), "PNG");
+    assert_eq!(info.width(), None);
+    assert!(!info.decodable());
+}
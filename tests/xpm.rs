@@ -0,0 +1,131 @@
+#![cfg(feature = "xpm")]
+//! XPM3 decoding tests.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+#[test]
+fn basic_rgb() {
+    let xpm = br#"/* XPM */
+static char * icon_xpm[] = {
+/* width height ncolors cpp */
+"3 2 3 1",
+/* colors */
+". c #FF0000",
+"x c blue",
+"o c #0f0",
+/* pixels */
+".xo",
+"oo.",
+};
+"#;
+    let decoded = decode_xpm(xpm, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!((decoded.width, decoded.height), (3, 2));
+    assert_eq!(
+        decoded.pixels(),
+        &[
+            255, 0, 0, 0, 0, 255, 0, 255, 0, 0, 255, 0, 0, 255, 0, 255, 0, 0
+        ]
+    );
+}
+
+#[test]
+fn none_gives_rgba() {
+    let xpm =
+        b"/* XPM */\nstatic char *t[] = {\"2 1 2 1\", \"  c None\", \"# c #000000\", \" #\"};\n";
+    let decoded = decode_xpm(xpm, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[0, 0, 0, 0, 0, 0, 0, 255]);
+}
+
+#[test]
+fn two_chars_per_pixel_and_key_preference() {
+    // `m` listed first, but `c` wins; `s` names are not colors
+    let xpm = br#"/* XPM */
+static char *t[] = {
+"2 1 2 2",
+"aa m white s background c #123456",
+"bb s fg m black g gray50",
+"aabb"
+};"#;
+    let decoded = decode_xpm(xpm, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0x12, 0x34, 0x56, 128, 128, 128]);
+}
+
+#[test]
+fn color_forms() {
+    let xpm = br#"/* XPM */
+static char *t[] = {
+"4 1 4 1",
+"a c #FFFF00000000",
+"b c #800800800",
+"c c light blue",
+"d g4 Grey100",
+"abcd"
+};"#;
+    let decoded = decode_xpm(xpm, Unstoppable).unwrap();
+    assert_eq!(
+        decoded.pixels(),
+        &[255, 0, 0, 128, 128, 128, 173, 216, 230, 255, 255, 255]
+    );
+}
+
+#[test]
+fn hotspot_and_extensions_ignored() {
+    let xpm = br#"/* XPM */
+static char *t[] = {
+"1 1 1 1 0 0 XPMEXT",
+"a c white",
+"a",
+"XPMEXT name data",
+"XPMENDEXT"
+};"#;
+    let decoded = decode_xpm(xpm, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[255, 255, 255]);
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let cases: [(&[u8], Check); 6] = [
+        (b"static char *t[] = {\"1 1 1 1\"};", |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (b"/* XPM */ {\"1 x 1 1\"};", |e| {
+            matches!(e, BitmapError::InvalidHeader(..))
+        }),
+        (b"/* XPM */ {\"2 1 1 1\", \"a c red\", \"a\"};", |e| {
+            matches!(e, BitmapError::InvalidData(..))
+        }),
+        (b"/* XPM */ {\"1 1 1 1\", \"a c red\", \"b\"};", |e| {
+            matches!(e, BitmapError::InvalidData(..))
+        }),
+        (b"/* XPM */ {\"1 2 1 1\", \"a c red\", \"a\"};", |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+        (
+            b"/* XPM */ {\"1 1 1 1\", \"a c chartreuse\", \"a\"};",
+            |e| matches!(e, BitmapError::UnsupportedVariant(_)),
+        ),
+    ];
+    for (data, check) in cases {
+        let err = decode_xpm(data, Unstoppable).unwrap_err();
+        assert!(
+            check(err.error()),
+            "{}: {err}",
+            String::from_utf8_lossy(data)
+        );
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let xpm = b"/* XPM */ {\"2 2 1 1\", \"a c red\", \"aa\", \"aa\"};";
+    let limits = Limits {
+        max_pixels: Some(3),
+        ..Default::default()
+    };
+    let err = decode_xpm_with_limits(xpm, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "xwd")]
+//! XWD decoding tests using synthetic dumps.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+/// An XWD with a window name, two colormap entries, and `pixels` as rows of
+/// `bytes_per_line`. `big` picks the header byte order; `msb_first` the
+/// pixel byte order.
+#[allow(clippy::too_many_arguments)]
+fn xwd(
+    big: bool,
+    msb_first: bool,
+    depth: u32,
+    bpp: u32,
+    width: u32,
+    height: u32,
+    masks: [u32; 3],
+    bytes_per_line: u32,
+    pixels: &[u8],
+) -> Vec<u8> {
+    let name = b"xwdump\0\0";
+    let mut h = [0u32; 25];
+    h[0] = 100 + name.len() as u32;
+    h[1] = 7;
+    h[2] = 2; // ZPixmap
+    h[3] = depth;
+    h[4] = width;
+    h[5] = height;
+    h[7] = u32::from(msb_first);
+    h[8] = 32;
+    h[9] = u32::from(msb_first);
+    h[10] = 32;
+    h[11] = bpp;
+    h[12] = bytes_per_line;
+    h[13] = 4; // TrueColor
+    h[14..17].copy_from_slice(&masks);
+    h[17] = 8;
+    h[18] = 2;
+    h[19] = 2;
+    let mut out: Vec<u8> = h
+        .iter()
+        .flat_map(|v| {
+            if big {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            }
+        })
+        .collect();
+    out.extend_from_slice(name);
+    out.extend_from_slice(&[0x55; 24]);
+    out.extend_from_slice(pixels);
+    out
+}
+
+const RGB: [u32; 3] = [0xFF_0000, 0xFF00, 0xFF];
+
+#[test]
+fn depth24_in_32_bits_either_byte_order() {
+    for (big, msb_first) in [(true, true), (true, false), (false, false), (false, true)] {
+        let values = [0xAA11_2233u32, 0x00FF_8000, 0x1234_5678];
+        let mut pixels = Vec::new();
+        for (i, v) in values.iter().enumerate() {
+            pixels.extend_from_slice(&if msb_first {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            });
+            // 8 bytes of padding after each two-pixel row
+            if i == 1 {
+                pixels.extend_from_slice(&[0xEE; 8]);
+            }
+        }
+        pixels.extend_from_slice(&[0; 4]);
+        let data = xwd(big, msb_first, 24, 32, 2, 2, RGB, 16, &pixels);
+        let decoded = decode_xwd(&data, Unstoppable).unwrap();
+        assert_eq!(decoded.layout, PixelLayout::Rgb8);
+        assert_eq!((decoded.width, decoded.height), (2, 2));
+        assert_eq!(
+            decoded.pixels(),
+            &[
+                0x11, 0x22, 0x33, 0xFF, 0x80, 0x00, 0x34, 0x56, 0x78, 0, 0, 0
+            ],
+            "big {big}, msb_first {msb_first}"
+        );
+    }
+}
+
+#[test]
+fn packed_24_bit_and_bgr_masks() {
+    let pixels = [0x10, 0x20, 0x30, 0x40, 0x50, 0x60];
+    let data = xwd(
+        true,
+        true,
+        24,
+        24,
+        2,
+        1,
+        [0xFF, 0xFF00, 0xFF_0000],
+        6,
+        &pixels,
+    );
+    let decoded = decode_xwd(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0x30, 0x20, 0x10, 0x60, 0x50, 0x40]);
+    let data = xwd(true, false, 24, 24, 2, 1, RGB, 6, &pixels);
+    let decoded = decode_xwd(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.pixels(), &[0x30, 0x20, 0x10, 0x60, 0x50, 0x40]);
+}
+
+#[test]
+fn argb_visual_and_wide_masks() {
+    let pixels = 0x80FF_0000u32.to_be_bytes();
+    let data = xwd(true, true, 32, 32, 1, 1, RGB, 4, &pixels);
+    let decoded = decode_xwd(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgba8);
+    assert_eq!(decoded.pixels(), &[0xFF, 0, 0, 0x80]);
+
+    // 10-bit channels (depth 30)
+    let masks = [0x3FF0_0000, 0xF_FC00, 0x3FF];
+    let v: u32 = 1023 << 20 | 512 << 10;
+    let data = xwd(false, false, 30, 32, 1, 1, masks, 4, &v.to_le_bytes());
+    let decoded = decode_xwd(&data, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb16);
+    let samples: Vec<u16> = decoded
+        .pixels()
+        .chunks_exact(2)
+        .map(|s| u16::from_ne_bytes([s[0], s[1]]))
+        .collect();
+    assert_eq!(samples, [65535, 32800, 0]);
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let unsupported: Check = |e| matches!(e, BitmapError::UnsupportedVariant(_));
+    let invalid: Check = |e| matches!(e, BitmapError::InvalidHeader(..));
+    let good = || xwd(true, true, 24, 32, 2, 1, RGB, 8, &[0; 8]);
+    let patched = |field: usize, value: u32| {
+        let mut data = good();
+        data[field * 4..field * 4 + 4].copy_from_slice(&value.to_be_bytes());
+        data
+    };
+    let cases: [(Vec<u8>, Check); 9] = [
+        (patched(2, 1), unsupported),   // XYPixmap
+        (patched(11, 16), unsupported), // 16 bits per pixel
+        (patched(14, 0), unsupported),  // colormapped
+        (patched(0, 99), invalid),
+        (patched(4, 0), invalid),
+        (patched(12, 7), invalid),
+        (patched(1, 6), |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (good()[..99].to_vec(), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+        (good()[..good().len() - 1].to_vec(), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+    ];
+    for (i, (data, check)) in cases.into_iter().enumerate() {
+        let err = decode_xwd(&data, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "case {i}: {err}");
+    }
+}
+
+#[test]
+fn limits_apply() {
+    let data = xwd(true, true, 24, 32, 4, 4, RGB, 16, &[0; 64]);
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_xwd_with_limits(&data, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
@@ -0,0 +1,308 @@
+#![cfg(feature = "y4m")]
+//! YUV4MPEG2 decoding tests using synthetic streams.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn stream(header: &str, frames: &[&[u8]]) -> Vec<u8> {
+    let mut data = format!("YUV4MPEG2 {header}\n").into_bytes();
+    for frame in frames {
+        data.extend_from_slice(b"FRAME\n");
+        data.extend_from_slice(frame);
+    }
+    data
+}
+
+#[test]
+fn header_fields() {
+    let data = stream(
+        "W640 H360 F30000:1001 Ip A1:1 C420mpeg2 XYSCSS=420MPEG2",
+        &[],
+    );
+    let header = probe_y4m(&data).unwrap();
+    assert_eq!((header.width, header.height), (640, 360));
+    assert_eq!(header.frame_rate, Some((30000, 1001)));
+    assert_eq!(header.pixel_aspect, Some((1, 1)));
+    assert_eq!(header.interlace, b'p');
+    assert_eq!(header.layout, YuvLayout::I420);
+    assert!(!header.full_range);
+    assert_eq!(header.frame_size(), 640 * 360 * 3 / 2);
+
+    // Defaults: 420jpeg, unknown rate/aspect/interlacing
+    let header = probe_y4m(&stream("W3 H3 A0:0 XCOLORRANGE=FULL", &[])).unwrap();
+    assert_eq!(header.layout, YuvLayout::I420);
+    assert_eq!((header.frame_rate, header.pixel_aspect), (None, None));
+    assert_eq!(header.interlace, b'?');
+    assert!(header.full_range);
+    assert_eq!(header.frame_size(), 9 + 2 * 4);
+
+    for (tag, layout) in [
+        ("420jpeg", YuvLayout::I420),
+        ("420paldv", YuvLayout::I420),
+        ("422", YuvLayout::I422),
+        ("444", YuvLayout::I444),
+        ("mono", YuvLayout::Gray),
+    ] {
+        let header = probe_y4m(&stream(&format!("W2 H2 C{tag}"), &[])).unwrap();
+        assert_eq!(header.layout, layout, "{tag}");
+    }
+}
+
+#[test]
+fn frames_and_planes() {
+    let first: Vec<u8> = (0..6).collect();
+    let second: Vec<u8> = (10..16).collect();
+    // Frame headers may carry parameters
+    let mut data = stream("W2 H2 F25:1 C420jpeg", &[&first]);
+    data.extend_from_slice(b"FRAME Ip XFOO=1\n");
+    data.extend_from_slice(&second);
+
+    let mut frames = decode_y4m_frames(&data, Unstoppable).unwrap();
+    assert_eq!(frames.header().width, 2);
+    let frame = frames.next().unwrap().unwrap();
+    assert_eq!(frame.y(), &[0, 1, 2, 3]);
+    assert_eq!((frame.u(), frame.v()), (&[4][..], &[5][..]));
+    let frame = frames.next().unwrap().unwrap();
+    assert_eq!(frame.data(), &second[..]);
+    assert!(frames.next().is_none());
+    assert_eq!(frames.offset(), data.len());
+}
+
+#[test]
+fn rgb_matches_decode_yuv() {
+    let planes: Vec<u8> = (0..27).map(|i| 20 + i * 8).collect();
+    let data = stream("W3 H3 C444", &[&planes]);
+    let expected = decode_yuv(
+        &planes,
+        3,
+        3,
+        YuvLayout::I444,
+        YuvMatrix::Bt709,
+        Unstoppable,
+    )
+    .unwrap();
+    let decoded = decode_y4m(&data, YuvMatrix::Bt709, Unstoppable).unwrap();
+    assert_eq!(decoded.layout, PixelLayout::Rgb8);
+    assert_eq!(decoded.pixels(), expected.pixels());
+
+    let mono = stream("W2 H1 Cmono", &[&[16, 235]]);
+    let frame = decode_y4m_frames(&mono, Unstoppable)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!(frame.u().is_empty() && frame.v().is_empty());
+    let rgb = frame.to_rgb(YuvMatrix::Bt601, Unstoppable).unwrap();
+    assert_eq!(rgb.pixels(), &[0, 0, 0, 255, 255, 255]);
+}
+
+#[test]
+fn errors() {
+    type Check = fn(&BitmapError) -> bool;
+    let cases: [(Vec<u8>, Check); 8] = [
+        (b"YUV4MPEG W1 H1\n".to_vec(), |e| {
+            matches!(e, BitmapError::UnrecognizedFormat)
+        }),
+        (b"YUV4MPEG2 W1 H1".to_vec(), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+        (stream("W1", &[]), |e| {
+            matches!(e, BitmapError::InvalidHeader(..))
+        }),
+        (stream("W0 H1", &[]), |e| {
+            matches!(e, BitmapError::InvalidHeader(..))
+        }),
+        (stream("W1 H1 F30", &[]), |e| {
+            matches!(e, BitmapError::InvalidHeader(..))
+        }),
+        (stream("W2 H2 C420p10", &[]), |e| {
+            matches!(e, BitmapError::UnsupportedVariant(_))
+        }),
+        (stream("W2 H2", &[&[0; 5]]), |e| {
+            matches!(e, BitmapError::UnexpectedEof(_))
+        }),
+        (b"YUV4MPEG2 W1 H1 Cmono\nFRAMES\n\0".to_vec(), |e| {
+            matches!(e, BitmapError::InvalidData(..))
+        }),
+    ];
+    for (data, check) in cases {
+        let err = decode_y4m(&data, YuvMatrix::Bt601, Unstoppable).unwrap_err();
+        assert!(check(err.error()), "{}: {err}", data.escape_ascii());
+    }
+
+    // A bad frame ends the iteration after yielding its error
+    let mut data = stream("W1 H1 Cmono", &[&[1]]);
+    data.extend_from_slice(b"FRAM");
+    let results: Vec<_> = decode_y4m_frames(&data, Unstoppable).unwrap().collect();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(
+        results[1].as_ref().unwrap_err().error(),
+        BitmapError::UnexpectedEof(_)
+    ));
+}
+
+#[test]
+fn limits_apply() {
+    let data = stream("W4 H4 Cmono", &[&[0; 16]]);
+    let limits = Limits {
+        max_pixels: Some(15),
+        ..Default::default()
+    };
+    let err = decode_y4m_frames_with_limits(&data, &limits, Unstoppable)
+        .err()
+        .unwrap();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+
+    let limits = Limits {
+        max_memory_bytes: Some(47),
+        ..Default::default()
+    };
+    let frame = decode_y4m_frames_with_limits(&data, &limits, Unstoppable)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(frame.y().len(), 16);
+    let err = decode_y4m_with_limits(&data, YuvMatrix::Bt601, &limits, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
+
+// ── Writing ──────────────────────────────────────────────────────────
+
+#[test]
+fn writer_header_round_trips() {
+    let header = Y4mHeader::new(4, 2, YuvLayout::I422)
+        .with_frame_rate(24000, 1001)
+        .with_pixel_aspect(16, 15)
+        .with_interlace(b't')
+        .with_full_range(true);
+    let y4m = Y4mWriter::new(header).unwrap().finish();
+    assert_eq!(
+        y4m,
+        b"YUV4MPEG2 W4 H2 F24000:1001 It A16:15 C422 XCOLORRANGE=FULL\n"
+    );
+    assert_eq!(probe_y4m(&y4m).unwrap(), header);
+
+    let plain = Y4mWriter::new(Y4mHeader::new(1, 1, YuvLayout::Gray))
+        .unwrap()
+        .finish();
+    assert_eq!(plain, b"YUV4MPEG2 W1 H1 Cmono\n");
+}
+
+#[test]
+fn writer_rgb_round_trips() {
+    // 4x2 image whose 2x2 blocks are each one color, so 4:2:0 loses nothing
+    let (red, teal) = ([200u8, 30, 40], [20u8, 160, 170]);
+    let rgb: Vec<u8> = [red, red, teal, teal, red, red, teal, teal].concat();
+    for layout in [YuvLayout::I420, YuvLayout::I422, YuvLayout::I444] {
+        for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709, YuvMatrix::Bt601Full] {
+            let mut writer = Y4mWriter::new(Y4mHeader::new(4, 2, layout)).unwrap();
+            writer
+                .push_rgb(&rgb, PixelLayout::Rgb8, matrix, Unstoppable)
+                .unwrap();
+            let decoded = decode_y4m(&writer.finish(), matrix, Unstoppable).unwrap();
+            for (a, e) in decoded.pixels().iter().zip(&rgb) {
+                assert!(a.abs_diff(*e) <= 2, "{layout:?} {matrix:?}: {a} vs {e}");
+            }
+        }
+    }
+
+    // BGRA and gray inputs match the equivalent RGB
+    let encode = |pixels: &[u8], layout| {
+        let mut writer = Y4mWriter::new(Y4mHeader::new(2, 1, YuvLayout::I444)).unwrap();
+        writer
+            .push_rgb(pixels, layout, YuvMatrix::Bt709, Unstoppable)
+            .unwrap();
+        writer.finish()
+    };
+    assert_eq!(
+        encode(&[40, 30, 200, 0, 9, 9, 9, 255], PixelLayout::Bgra8),
+        encode(&[200, 30, 40, 9, 9, 9], PixelLayout::Rgb8)
+    );
+    assert_eq!(
+        encode(&[0, 255], PixelLayout::Gray8),
+        encode(&[0, 0, 0, 255, 255, 255], PixelLayout::Rgb8)
+    );
+}
+
+#[test]
+fn writer_averages_chroma() {
+    // Odd 3x1 I420: the last chroma sample covers one pixel only
+    let rgb = [255, 0, 0, 0, 0, 255, 0, 255, 0];
+    let mut full = Y4mWriter::new(Y4mHeader::new(3, 1, YuvLayout::I444)).unwrap();
+    full.push_rgb(&rgb, PixelLayout::Rgb8, YuvMatrix::Bt601, Unstoppable)
+        .unwrap();
+    let full = full.finish();
+    let full = decode_y4m_frames(&full, Unstoppable)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    let mut sub = Y4mWriter::new(Y4mHeader::new(3, 1, YuvLayout::I420)).unwrap();
+    sub.push_rgb(&rgb, PixelLayout::Rgb8, YuvMatrix::Bt601, Unstoppable)
+        .unwrap();
+    let sub = sub.finish();
+    let sub = decode_y4m_frames(&sub, Unstoppable)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(sub.y(), full.y());
+    for (sub, full) in [(sub.u(), full.u()), (sub.v(), full.v())] {
+        let avg = (u32::from(full[0]) + u32::from(full[1])) as f32 / 2.0;
+        assert!((f32::from(sub[0]) - avg).abs() <= 1.0, "{sub:?} {full:?}");
+        assert_eq!(sub[1], full[2]);
+    }
+}
+
+#[test]
+fn writer_appends() {
+    let mut writer = Y4mWriter::new(Y4mHeader::new(1, 1, YuvLayout::Gray)).unwrap();
+    writer.push_frame(&[1]).unwrap();
+    let y4m = writer.finish();
+
+    let mut writer = Y4mWriter::append(y4m).unwrap();
+    assert_eq!(writer.frames(), 1);
+    assert_eq!(writer.header().layout, YuvLayout::Gray);
+    writer.push_frame(&[2]).unwrap();
+    assert_eq!(writer.frames(), 2);
+    let y4m = writer.finish();
+    let lumas: Vec<u8> = decode_y4m_frames(&y4m, Unstoppable)
+        .unwrap()
+        .map(|f| f.unwrap().y()[0])
+        .collect();
+    assert_eq!(lumas, [1, 2]);
+
+    let mut truncated = y4m;
+    truncated.extend_from_slice(b"FRAME\n");
+    let err = Y4mWriter::append(truncated).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+}
+
+#[test]
+fn writer_errors() {
+    let err = Y4mWriter::new(Y4mHeader::new(2, 2, YuvLayout::Nv12)).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    let err = Y4mWriter::new(Y4mHeader::new(0, 2, YuvLayout::I420)).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    let err =
+        Y4mWriter::new(Y4mHeader::new(2, 2, YuvLayout::I420).with_interlace(b'x')).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+
+    let mut writer = Y4mWriter::new(Y4mHeader::new(2, 2, YuvLayout::I420)).unwrap();
+    let err = writer.push_frame(&[0; 5]).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidData(..)));
+    let err = writer
+        .push_rgb(&[0; 11], PixelLayout::Rgb8, YuvMatrix::Bt601, Unstoppable)
+        .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::BufferTooSmall { .. }));
+    let err = writer
+        .push_rgb(&[0; 32], PixelLayout::Rgba16, YuvMatrix::Bt601, Unstoppable)
+        .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnsupportedVariant(_)));
+    // Failed pushes leave the stream untouched
+    assert_eq!(writer.frames(), 0);
+    assert_eq!(writer.finish(), b"YUV4MPEG2 W2 H2 C420jpeg\n");
+}
@@ -0,0 +1,268 @@
+#![cfg(feature = "yuv")]
+//! Raw YUV → RGB decoding tests.
+
+use enough::Unstoppable;
+use zenbitmaps::*;
+
+fn rgb(frame: &[u8], w: u32, h: u32, layout: YuvLayout, matrix: YuvMatrix) -> Vec<u8> {
+    decode_yuv(frame, w, h, layout, matrix, Unstoppable)
+        .unwrap()
+        .pixels()
+        .to_vec()
+}
+
+fn close(actual: &[u8], expected: &[u8], tol: u8) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (&a, &e)) in actual.iter().zip(expected).enumerate() {
+        assert!(a.abs_diff(e) <= tol, "byte {i}: {a} vs {e}");
+    }
+}
+
+#[test]
+fn primaries() {
+    // Studio-range encodings of pure red, green, blue, white and black
+    let bt601 = [
+        (81, 90, 240),
+        (145, 54, 34),
+        (41, 240, 110),
+        (235, 128, 128),
+        (16, 128, 128),
+    ];
+    let bt709 = [
+        (63, 102, 240),
+        (173, 42, 26),
+        (32, 240, 118),
+        (235, 128, 128),
+        (16, 128, 128),
+    ];
+    let expected = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0];
+    for (matrix, samples) in [(YuvMatrix::Bt601, bt601), (YuvMatrix::Bt709, bt709)] {
+        let mut frame: Vec<u8> = samples.iter().map(|s| s.0).collect();
+        frame.extend(samples.iter().map(|s| s.1));
+        frame.extend(samples.iter().map(|s| s.2));
+        close(&rgb(&frame, 5, 1, YuvLayout::I444, matrix), &expected, 2);
+    }
+}
+
+#[test]
+fn full_range_passes_gray_through() {
+    let frame = [0, 77, 200, 255, 128, 128, 128, 128, 128, 128, 128, 128];
+    let out = rgb(&frame, 4, 1, YuvLayout::I444, YuvMatrix::Bt601Full);
+    assert_eq!(out, [0, 0, 0, 77, 77, 77, 200, 200, 200, 255, 255, 255]);
+    assert_eq!(
+        rgb(&frame[..4], 4, 1, YuvLayout::Gray, YuvMatrix::Bt601Full),
+        out
+    );
+    assert_eq!(YuvLayout::Gray.frame_size(4, 1), Some(4));
+}
+
+#[test]
+fn layouts_agree() {
+    // 5x3 (odd) image: 3x2 chroma for 4:2:0, 3x3 for 4:2:2
+    let (w, h) = (5u32, 3u32);
+    let luma: Vec<u8> = (0..15).map(|i| 30 + i * 13).collect();
+    let u420: Vec<u8> = (0..6).map(|i| 60 + i * 20).collect();
+    let v420: Vec<u8> = (0..6).map(|i| 200 - i * 17).collect();
+
+    // Reference: the same samples replicated to full resolution as I444
+    let up = |plane: &[u8], sy: u32| -> Vec<u8> {
+        (0..h)
+            .flat_map(|y| (0..w).map(move |x| (y >> sy) * 3 + x / 2))
+            .map(|i| plane[i as usize])
+            .collect()
+    };
+    let i444 = [luma.clone(), up(&u420, 1), up(&v420, 1)].concat();
+    let reference = rgb(&i444, w, h, YuvLayout::I444, YuvMatrix::Bt709);
+
+    let i420 = [luma.clone(), u420.clone(), v420.clone()].concat();
+    let yv12 = [luma.clone(), v420.clone(), u420.clone()].concat();
+    let nv12: Vec<u8> = luma
+        .iter()
+        .copied()
+        .chain(u420.iter().zip(&v420).flat_map(|(&u, &v)| [u, v]))
+        .collect();
+    for (layout, frame) in [
+        (YuvLayout::I420, i420),
+        (YuvLayout::Yv12, yv12),
+        (YuvLayout::Nv12, nv12),
+    ] {
+        assert_eq!(layout.frame_size(w, h), Some(frame.len()), "{layout:?}");
+        assert_eq!(
+            rgb(&frame, w, h, layout, YuvMatrix::Bt709),
+            reference,
+            "{layout:?}"
+        );
+    }
+
+    // 4:2:2: chroma rows are not shared
+    let u422: Vec<u8> = (0..9).map(|i| 50 + i * 15).collect();
+    let v422: Vec<u8> = (0..9).map(|i| 220 - i * 12).collect();
+    let i444 = [luma.clone(), up(&u422, 0), up(&v422, 0)].concat();
+    let reference = rgb(&i444, w, h, YuvLayout::I444, YuvMatrix::Bt601);
+    let i422 = [luma.clone(), u422.clone(), v422.clone()].concat();
+    assert_eq!(
+        rgb(&i422, w, h, YuvLayout::I422, YuvMatrix::Bt601),
+        reference
+    );
+    // YUY2 pads the odd last pixel of each row into a full quad
+    let yuy2: Vec<u8> = (0..h as usize)
+        .flat_map(|y| {
+            let (luma, u422, v422) = (&luma, &u422, &v422);
+            (0..3).flat_map(move |i| {
+                let y1 = luma.get(y * 5 + 2 * i + 1).filter(|_| 2 * i + 1 < 5);
+                [
+                    luma[y * 5 + 2 * i],
+                    u422[y * 3 + i],
+                    *y1.unwrap_or(&0),
+                    v422[y * 3 + i],
+                ]
+            })
+        })
+        .collect();
+    assert_eq!(YuvLayout::Yuy2.frame_size(w, h), Some(yuy2.len()));
+    assert_eq!(
+        rgb(&yuy2, w, h, YuvLayout::Yuy2, YuvMatrix::Bt601),
+        reference
+    );
+}
+
+#[test]
+fn extra_frames_ignored_and_truncation_rejected() {
+    let frame = [16u8, 16, 16, 16, 128, 128];
+    let two = [frame, [235, 235, 235, 235, 128, 128]].concat();
+    assert_eq!(rgb(&two, 2, 2, YuvLayout::I420, YuvMatrix::Bt601), [0; 12]);
+    assert_eq!(
+        rgb(&two[6..], 2, 2, YuvLayout::I420, YuvMatrix::Bt601),
+        [255; 12]
+    );
+
+    let err = decode_yuv(
+        &frame[..5],
+        2,
+        2,
+        YuvLayout::I420,
+        YuvMatrix::Bt601,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+    let err = decode_yuv(&frame, 0, 2, YuvLayout::I420, YuvMatrix::Bt601, Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+}
+
+#[test]
+fn limits_apply() {
+    let frame = [128u8; 6];
+    let limits = Limits {
+        max_memory_bytes: Some(11),
+        ..Default::default()
+    };
+    let err = decode_yuv_with_limits(
+        &frame,
+        2,
+        2,
+        YuvLayout::I420,
+        YuvMatrix::Bt601,
+        &limits,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}
+
+/// Pack 10-bit 4:2:2 samples (`y` per pixel, `cb`/`cr` per pair) as V210
+/// rows padded to 128 bytes.
+fn v210(width: usize, rows: &[(Vec<u16>, Vec<u16>, Vec<u16>)]) -> Vec<u8> {
+    let stride = width.div_ceil(48) * 128;
+    let mut out = Vec::new();
+    for (y, cb, cr) in rows {
+        let mut stream = Vec::new();
+        for i in 0..width.div_ceil(2) {
+            let y1 = y.get(2 * i + 1).copied().unwrap_or(0);
+            stream.extend_from_slice(&[cb[i], y[2 * i], cr[i], y1]);
+        }
+        stream.resize(stream.len().next_multiple_of(3), 0);
+        let start = out.len();
+        for s in stream.chunks_exact(3) {
+            let word = u32::from(s[0]) | u32::from(s[1]) << 10 | u32::from(s[2]) << 20;
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        out.resize(start + stride, 0);
+    }
+    out
+}
+
+fn p010(y: &[u16], uv: &[u16]) -> Vec<u8> {
+    y.iter()
+        .chain(uv)
+        .flat_map(|v| (v << 6).to_le_bytes())
+        .collect()
+}
+
+fn rgb16(decoded: &DecodeOutput<'_>) -> Vec<u16> {
+    decoded
+        .pixels()
+        .chunks_exact(2)
+        .map(|s| u16::from_ne_bytes([s[0], s[1]]))
+        .collect()
+}
+
+#[test]
+fn ten_bit_layouts_agree() {
+    // 7×2, odd width: black, white, BT.709 red, then arbitrary samples
+    let y = vec![64, 940, 250, 691, 127, 502, 502];
+    let cb = vec![512, 409, 960, 512];
+    let cr = vec![512, 960, 167, 512];
+    let rows = [
+        (y.clone(), cb.clone(), cr.clone()),
+        (y.clone(), cb.clone(), cr.clone()),
+    ];
+    let uv: Vec<u16> = cb.iter().zip(&cr).flat_map(|(&u, &v)| [u, v]).collect();
+
+    let matrix = YuvMatrix::Bt709;
+    let packed = v210(7, &rows);
+    assert_eq!(Yuv10Layout::V210.frame_size(7, 2), Some(256));
+    assert_eq!(packed.len(), 256);
+    let a = decode_yuv10(&packed, 7, 2, Yuv10Layout::V210, matrix, Unstoppable).unwrap();
+    let planar = p010(&[y.clone(), y].concat(), &uv);
+    assert_eq!(Yuv10Layout::P010.frame_size(7, 2), Some(planar.len()));
+    let b = decode_yuv10(&planar, 7, 2, Yuv10Layout::P010, matrix, Unstoppable).unwrap();
+    assert_eq!(a.layout, PixelLayout::Rgb16);
+    assert_eq!(rgb16(&a), rgb16(&b));
+
+    let px = rgb16(&a);
+    assert_eq!(&px[..6], &[0, 0, 0, 65535, 65535, 65535]);
+    for (got, expected) in px[6..9].iter().zip([65535u16, 0, 0]) {
+        assert!(got.abs_diff(expected) < 600, "{:?}", &px[6..9]);
+    }
+}
+
+#[test]
+fn ten_bit_errors_and_limits() {
+    let frame = p010(&[512; 4], &[512; 2]);
+    let decode = |data: &[u8], w, layout| {
+        decode_yuv10(data, w, 2, layout, YuvMatrix::Bt601, Unstoppable).unwrap_err()
+    };
+    let err = decode(&frame[..frame.len() - 1], 2, Yuv10Layout::P010);
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+    let err = decode(&[0; 255], 6, Yuv10Layout::V210);
+    assert!(matches!(err.error(), BitmapError::UnexpectedEof(_)));
+    let err = decode(&frame, 0, Yuv10Layout::P010);
+    assert!(matches!(err.error(), BitmapError::InvalidHeader(..)));
+
+    let limits = Limits {
+        max_memory_bytes: Some(23),
+        ..Default::default()
+    };
+    let err = decode_yuv10_with_limits(
+        &frame,
+        2,
+        2,
+        Yuv10Layout::P010,
+        YuvMatrix::Bt601,
+        &limits,
+        Unstoppable,
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), BitmapError::LimitExceeded(_)));
+}